@@ -0,0 +1,108 @@
+use crate::mft_dump::dump_mft_with_progress;
+use crate::mft_index::IndexEntry;
+use crate::mft_index::MftIndex;
+use crate::mft_index::build_index_for_with_progress;
+use crate::progress::ProgressEvent;
+use crate::progress::ProgressSubscriber;
+use crate::search_filter::SearchQuery;
+use futures_core::Stream;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::task::Context;
+use std::task::Poll;
+use tokio::sync::mpsc::Receiver;
+use tokio::sync::mpsc::channel;
+use tokio::task::JoinHandle;
+
+/// Events buffered for a reader that falls behind
+const EVENT_BUFFER: usize = 1024;
+
+/// Work running on tokio's blocking pool.
+///
+/// Poll it as a [`Stream`] of [`ProgressEvent`]s, ending with [`ProgressEvent::Finished`], then
+/// [`Operation::finish`] it for the result. Progress counts are cumulative, so a reader that
+/// falls behind skips some of them rather than slowing the work down; problems are never
+/// skipped.
+pub struct Operation<T> {
+    events: Receiver<ProgressEvent>,
+    task: JoinHandle<eyre::Result<T>>,
+}
+
+impl<T: Send + 'static> Operation<T> {
+    /// Run `work` on the blocking pool. Must be called from within a tokio runtime.
+    pub fn spawn(
+        work: impl FnOnce(&mut dyn ProgressSubscriber) -> eyre::Result<T> + Send + 'static,
+    ) -> Self {
+        let (tx, events) = channel(EVENT_BUFFER);
+        let task = tokio::task::spawn_blocking(move || {
+            let mut send = |event: ProgressEvent| match event {
+                ProgressEvent::MftRead { .. } | ProgressEvent::RecordsParsed { .. } => {
+                    let _ = tx.try_send(event);
+                }
+                // Fails only once the reader is gone, when nobody wants the event
+                event => {
+                    let _ = tx.blocking_send(event);
+                }
+            };
+            let result = work(&mut send);
+            send(ProgressEvent::Finished);
+            result
+        });
+        Self { events, task }
+    }
+}
+
+impl<T> Operation<T> {
+    /// The next event, or `None` once the work is done and every event was taken
+    pub async fn next_event(&mut self) -> Option<ProgressEvent> {
+        self.events.recv().await
+    }
+
+    /// Wait for the work to finish, dropping the events not taken yet
+    pub async fn finish(self) -> eyre::Result<T> {
+        let Self { events, task } = self;
+        drop(events);
+        task.await
+            .map_err(|e| eyre::eyre!("Background task failed: {e}"))?
+    }
+}
+
+impl<T> Stream for Operation<T> {
+    type Item = ProgressEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<ProgressEvent>> {
+        self.events.poll_recv(cx)
+    }
+}
+
+/// [`crate::mft_dump::dump_mft`] on the blocking pool. The caller must already be elevated.
+pub fn dump(drive_letter: char, output_path: PathBuf) -> Operation<u64> {
+    Operation::spawn(move |subscriber| {
+        dump_mft_with_progress(drive_letter, &output_path, subscriber)
+    })
+}
+
+/// Dump a volume's MFT to `dump` and write its index next to it, returning the index path.
+/// The caller must already be elevated.
+pub fn sync(drive_letter: char, dump: PathBuf) -> Operation<PathBuf> {
+    Operation::spawn(move |subscriber| {
+        dump_mft_with_progress(drive_letter, &dump, subscriber)?;
+        build_index_for_with_progress(&dump, subscriber)
+    })
+}
+
+/// [`MftIndex::query`] over a dump, loading its index with [`MftIndex::from_dump`]
+pub fn query(
+    mft_file: PathBuf,
+    drive_letter: char,
+    query: SearchQuery,
+) -> Operation<Vec<(String, IndexEntry)>> {
+    Operation::spawn(move |subscriber| {
+        let index = MftIndex::from_dump_with_progress(&mft_file, subscriber)?;
+        Ok(index
+            .query(drive_letter, &query)
+            .into_iter()
+            .map(|(path, entry)| (path, entry.clone()))
+            .collect())
+    })
+}
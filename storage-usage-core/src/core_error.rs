@@ -0,0 +1,12 @@
+/// Failures callers may want to tell apart from I/O and parse errors.
+///
+/// Returned inside `eyre::Report`s like every other error; find them with
+/// `report.downcast_ref::<CoreError>()` or by walking `report.chain()`.
+#[derive(Debug, thiserror::Error)]
+pub enum CoreError {
+    /// The volume isn't NTFS, so it has no MFT
+    #[error(
+        "Drive {drive_letter} does not appear to be using NTFS filesystem. FSCTL_GET_NTFS_VOLUME_DATA failed: {detail}. MFT dumping is only supported on NTFS volumes."
+    )]
+    NotNtfs { drive_letter: char, detail: String },
+}
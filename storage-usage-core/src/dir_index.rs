@@ -0,0 +1,346 @@
+use crate::mft_dump::apply_fixups;
+use eyre::Context;
+use mft::MftEntry;
+use mft::attribute::MftAttributeType;
+use mft::attribute::header::ResidentialHeader;
+use std::fs::File;
+use std::io::BufReader;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Extension of the `$I30` index blocks dumped next to an MFT dump
+pub const INDEX_ALLOCATION_EXTENSION: &str = "indx";
+/// Name of the index of file names every directory has
+pub const FILE_NAME_INDEX: &str = "$I30";
+/// `$INDEX_ROOT` starts with the indexed attribute type, collation rule and block size, then
+/// the header of its one node
+const ROOT_NODE_OFFSET: usize = 16;
+/// An `$INDEX_ALLOCATION` block's `INDX` header, with its update sequence array's place, is
+/// followed by the header of its node
+const BLOCK_NODE_OFFSET: usize = 24;
+/// Index blocks are a whole number of sectors, so one is looked for at every sector
+const SECTOR_SIZE: usize = 512;
+/// Index entry flag: the entry ends the node and carries no key
+const ENTRY_LAST: u32 = 0x2;
+/// Offset of the `$FILE_NAME` key in an index entry
+const KEY_OFFSET: usize = 16;
+/// `$FILE_NAME` flag NTFS sets on directories, as they have an index
+const NAME_IS_DIRECTORY: u32 = 0x1000_0000;
+/// `$FILE_NAME` namespace of 8.3 short names, which duplicate a long name
+const NAMESPACE_DOS: u8 = 2;
+
+/// A child a directory's `$I30` index lists, as the directory last saw it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IndexedChild {
+    pub record: u64,
+    /// The child's sequence number when it was added, which tells whether its record has been
+    /// reused since
+    pub sequence: u16,
+    pub name: String,
+    pub is_dir: bool,
+}
+
+/// Path of the `$I30` index blocks dumped next to the given MFT dump
+pub fn index_allocation_path_for(mft_file: &Path) -> PathBuf {
+    let mut os = mft_file.as_os_str().to_os_string();
+    os.push(".");
+    os.push(INDEX_ALLOCATION_EXTENSION);
+    PathBuf::from(os)
+}
+
+/// The children listed in the `$INDEX_ROOT` of a directory's record.
+///
+/// The index keeps a copy of each child's name, so these still name a child whose own record
+/// is corrupt or was reused. Larger directories keep most of their entries in
+/// `$INDEX_ALLOCATION` blocks instead, which live in clusters outside the MFT; see
+/// [`index_allocation_children`].
+pub fn index_root_children(entry: &MftEntry) -> Vec<IndexedChild> {
+    for attribute in entry.iter_attributes() {
+        let Ok(attribute) = attribute else { continue };
+        if attribute.header.type_code != MftAttributeType::IndexRoot
+            || attribute.header.name != FILE_NAME_INDEX
+        {
+            continue;
+        }
+        let ResidentialHeader::Resident(resident) = &attribute.header.residential_header else {
+            continue;
+        };
+        let start = attribute.header.start_offset as usize + resident.data_offset as usize;
+        let Some(root) = entry.data.get(start..start + resident.data_size as usize) else {
+            continue;
+        };
+        return root
+            .get(ROOT_NODE_OFFSET..)
+            .map(node_children)
+            .unwrap_or_default();
+    }
+    Vec::new()
+}
+
+/// The children listed in the `INDX` blocks of a directory's `$INDEX_ALLOCATION`, as read from
+/// the volume. Blocks that aren't in use or are torn are passed over.
+pub fn index_allocation_children(allocation: &[u8]) -> Vec<IndexedChild> {
+    let mut children = Vec::new();
+    let mut at = 0;
+    while at + BLOCK_NODE_OFFSET < allocation.len() {
+        let rest = &allocation[at..];
+        let Some(length) = block_length(rest).filter(|&length| length <= rest.len()) else {
+            at += SECTOR_SIZE;
+            continue;
+        };
+        let mut block = rest[..length].to_vec();
+        if apply_fixups(&mut block).is_ok() {
+            children.extend(node_children(&block[BLOCK_NODE_OFFSET..]));
+        }
+        at += length;
+    }
+    children
+}
+
+/// The length of the `INDX` block at the start of `data`: its node's allocated size after the
+/// block's own header
+fn block_length(data: &[u8]) -> Option<usize> {
+    if !data.starts_with(b"INDX") {
+        return None;
+    }
+    let at = BLOCK_NODE_OFFSET + 8;
+    let allocated = u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?) as usize;
+    let length = BLOCK_NODE_OFFSET + allocated;
+    (length % SECTOR_SIZE == 0).then_some(length)
+}
+
+/// Append a directory's `$INDEX_ALLOCATION` to a dump of them: its record number and length,
+/// then its index blocks as they are on the volume
+pub fn write_index_allocation(
+    out: &mut impl Write,
+    directory: u64,
+    allocation: &[u8],
+) -> std::io::Result<()> {
+    out.write_all(&directory.to_le_bytes())?;
+    out.write_all(&(allocation.len() as u64).to_le_bytes())?;
+    out.write_all(allocation)
+}
+
+/// Call `each` with every child the index blocks in a dump of them list, along with the record
+/// number of the directory listing it
+pub fn read_index_allocations(
+    path: &Path,
+    each: impl FnMut(u64, IndexedChild),
+) -> eyre::Result<()> {
+    let file = File::open(path).with_context(|| format!("opening {}", path.display()))?;
+    read_allocations(&mut BufReader::new(file), each)
+        .with_context(|| format!("reading {}", path.display()))
+}
+
+fn read_allocations(
+    input: &mut impl Read,
+    mut each: impl FnMut(u64, IndexedChild),
+) -> eyre::Result<()> {
+    let mut header = [0u8; 16];
+    let mut allocation = Vec::new();
+    loop {
+        match input.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e.into()),
+        }
+        let directory = u64::from_le_bytes(header[..8].try_into()?);
+        let length = u64::from_le_bytes(header[8..].try_into()?);
+        allocation.clear();
+        input.by_ref().take(length).read_to_end(&mut allocation)?;
+        if allocation.len() as u64 != length {
+            return Err(eyre::eyre!(
+                "Index blocks of directory {directory} end after {} of {length} bytes",
+                allocation.len()
+            ));
+        }
+        for child in index_allocation_children(&allocation) {
+            each(directory, child);
+        }
+    }
+}
+
+/// The children in an index node, starting at its node header
+fn node_children(node: &[u8]) -> Vec<IndexedChild> {
+    let u16_at = |data: &[u8], at: usize| {
+        Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?) as usize)
+    };
+    let u32_at =
+        |data: &[u8], at: usize| Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?));
+    let mut children = Vec::new();
+    let (Some(first), Some(end)) = (u32_at(node, 0), u32_at(node, 4)) else {
+        return children;
+    };
+    let mut at = first as usize;
+    let end = (end as usize).min(node.len());
+    while at + KEY_OFFSET <= end {
+        let Some(entry_length) = u16_at(node, at + 8) else {
+            break;
+        };
+        let flags = u32_at(node, at + 12).unwrap_or(ENTRY_LAST);
+        if flags & ENTRY_LAST != 0 || entry_length < KEY_OFFSET {
+            break;
+        }
+        if let Some(child) = node.get(at..end).and_then(entry_child) {
+            children.push(child);
+        }
+        at += entry_length;
+    }
+    children
+}
+
+/// The child an index entry points at, from its file reference and `$FILE_NAME` key
+fn entry_child(entry: &[u8]) -> Option<IndexedChild> {
+    let reference = u64::from_le_bytes(entry.get(0..8)?.try_into().ok()?);
+    let key = entry.get(KEY_OFFSET..)?;
+    let flags = u32::from_le_bytes(key.get(56..60)?.try_into().ok()?);
+    let name_length = *key.get(64)? as usize;
+    if *key.get(65)? == NAMESPACE_DOS {
+        return None;
+    }
+    let units: Vec<u16> = key
+        .get(66..66 + name_length * 2)?
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    Some(IndexedChild {
+        record: reference & 0xFFFF_FFFF_FFFF,
+        sequence: (reference >> 48) as u16,
+        name: String::from_utf16(&units).ok()?,
+        is_dir: flags & NAME_IS_DIRECTORY != 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ENTRY_LAST;
+    use super::IndexedChild;
+    use super::NAME_IS_DIRECTORY;
+    use super::node_children;
+    use super::read_allocations;
+    use super::write_index_allocation;
+    use std::io::Cursor;
+
+    /// An index entry for a child with a `$FILE_NAME` key in the given namespace
+    fn entry(record: u64, sequence: u16, name: &str, flags: u32, namespace: u8) -> Vec<u8> {
+        let name: Vec<u8> = name.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let mut key = vec![0; 56];
+        key.extend(flags.to_le_bytes());
+        key.extend([0; 4]);
+        key.push((name.len() / 2) as u8);
+        key.push(namespace);
+        key.extend(name);
+        while key.len() % 8 != 0 {
+            key.push(0);
+        }
+        let mut entry = (record | (sequence as u64) << 48).to_le_bytes().to_vec();
+        entry.extend(((16 + key.len()) as u16).to_le_bytes());
+        entry.extend((key.len() as u16).to_le_bytes());
+        entry.extend(0u32.to_le_bytes());
+        entry.extend(key);
+        entry
+    }
+
+    /// The entry that ends a node
+    fn last_entry() -> Vec<u8> {
+        let mut last = vec![0; 8];
+        last.extend(16u16.to_le_bytes());
+        last.extend(0u16.to_le_bytes());
+        last.extend(ENTRY_LAST.to_le_bytes());
+        last
+    }
+
+    #[test]
+    fn lists_long_names_until_the_last_entry() {
+        let mut entries = entry(40, 3, "docs", NAME_IS_DIRECTORY, 1);
+        entries.extend(entry(41, 1, "REPORT~1.PDF", 0, 2));
+        entries.extend(entry(41, 1, "report.pdf", 0, 1));
+        entries.extend(last_entry());
+        let mut node = 16u32.to_le_bytes().to_vec();
+        node.extend(((16 + entries.len()) as u32).to_le_bytes());
+        node.extend([0; 8]);
+        node.extend(entries);
+        assert_eq!(
+            node_children(&node),
+            vec![
+                IndexedChild {
+                    record: 40,
+                    sequence: 3,
+                    name: "docs".to_string(),
+                    is_dir: true,
+                },
+                IndexedChild {
+                    record: 41,
+                    sequence: 1,
+                    name: "report.pdf".to_string(),
+                    is_dir: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_children_from_index_blocks_after_fixups() {
+        // A 1024-byte INDX block of two sectors, its node starting after the block header and
+        // its entries after the update sequence array
+        let mut entries = entry(70, 2, "photos", NAME_IS_DIRECTORY, 1);
+        entries.extend(entry(71, 5, "notes.txt", 0, 3));
+        entries.extend(last_entry());
+        let mut block = b"INDX".to_vec();
+        block.extend(40u16.to_le_bytes());
+        block.extend(3u16.to_le_bytes());
+        block.extend([0; 16]);
+        block.extend(40u32.to_le_bytes());
+        block.extend(((40 + entries.len()) as u32).to_le_bytes());
+        block.extend((1024u32 - 24).to_le_bytes());
+        block.extend([0; 4]);
+        block.extend([0x07, 0x00, 0xAA, 0xAA, 0xBB, 0xBB]);
+        block.resize(64, 0);
+        block.extend(entries);
+        block.resize(1024, 0);
+        // Each sector's last two bytes are swapped for the update sequence number on disk
+        block[510..512].copy_from_slice(&[0x07, 0x00]);
+        block[1022..1024].copy_from_slice(&[0x07, 0x00]);
+
+        // A block that isn't in use comes first, then one whose fixups don't match
+        let mut torn = block.clone();
+        torn[1022] = 0x08;
+        let mut allocation = vec![0; 1024];
+        allocation.extend(torn);
+        allocation.extend(block);
+        let mut dump = Vec::new();
+        write_index_allocation(&mut dump, 5, &allocation).unwrap();
+
+        let mut children = Vec::new();
+        read_allocations(&mut Cursor::new(dump), |directory, child| {
+            children.push((directory, child))
+        })
+        .unwrap();
+        assert_eq!(
+            children,
+            vec![
+                (
+                    5,
+                    IndexedChild {
+                        record: 70,
+                        sequence: 2,
+                        name: "photos".to_string(),
+                        is_dir: true,
+                    }
+                ),
+                (
+                    5,
+                    IndexedChild {
+                        record: 71,
+                        sequence: 5,
+                        name: "notes.txt".to_string(),
+                        is_dir: false,
+                    }
+                ),
+            ]
+        );
+    }
+}
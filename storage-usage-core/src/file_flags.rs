@@ -0,0 +1,124 @@
+use serde::Serialize;
+use serde::Serializer;
+use std::fmt;
+
+// Windows FILE_ATTRIBUTE_* values as stored in $STANDARD_INFORMATION and $FILE_NAME
+const ATTRIBUTE_HIDDEN: u32 = 0x0002;
+const ATTRIBUTE_SYSTEM: u32 = 0x0004;
+const ATTRIBUTE_DIRECTORY: u32 = 0x0010;
+const ATTRIBUTE_SPARSE_FILE: u32 = 0x0200;
+const ATTRIBUTE_REPARSE_POINT: u32 = 0x0400;
+const ATTRIBUTE_COMPRESSED: u32 = 0x0800;
+/// Not a Windows attribute: marks names of records whose in-use flag is clear, in a bit no
+/// attribute uses
+const RECORD_DELETED: u32 = 0x8000_0000;
+
+/// A single attribute flag users can see and filter on
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum FileFlag {
+    Hidden,
+    System,
+    Compressed,
+    Sparse,
+    ReparsePoint,
+    Directory,
+    /// The record is no longer in use, so the file was deleted; its path is best-effort
+    Deleted,
+}
+
+impl FileFlag {
+    pub const ALL: [FileFlag; 7] = [
+        FileFlag::Hidden,
+        FileFlag::System,
+        FileFlag::Compressed,
+        FileFlag::Sparse,
+        FileFlag::ReparsePoint,
+        FileFlag::Directory,
+        FileFlag::Deleted,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileFlag::Hidden => "hidden",
+            FileFlag::System => "system",
+            FileFlag::Compressed => "compressed",
+            FileFlag::Sparse => "sparse",
+            FileFlag::ReparsePoint => "reparse-point",
+            FileFlag::Directory => "directory",
+            FileFlag::Deleted => "deleted",
+        }
+    }
+
+    /// Single-letter tag for compact display
+    pub fn tag(&self) -> char {
+        match self {
+            FileFlag::Hidden => 'H',
+            FileFlag::System => 'S',
+            FileFlag::Compressed => 'C',
+            FileFlag::Sparse => 'P',
+            FileFlag::ReparsePoint => 'L',
+            FileFlag::Directory => 'D',
+            FileFlag::Deleted => 'X',
+        }
+    }
+
+    fn bit(&self) -> u32 {
+        match self {
+            FileFlag::Hidden => ATTRIBUTE_HIDDEN,
+            FileFlag::System => ATTRIBUTE_SYSTEM,
+            FileFlag::Compressed => ATTRIBUTE_COMPRESSED,
+            FileFlag::Sparse => ATTRIBUTE_SPARSE_FILE,
+            FileFlag::ReparsePoint => ATTRIBUTE_REPARSE_POINT,
+            FileFlag::Directory => ATTRIBUTE_DIRECTORY,
+            FileFlag::Deleted => RECORD_DELETED,
+        }
+    }
+}
+
+/// Attribute bits of an entry, with the directory bit taken from the record header
+/// since NTFS doesn't set it in the standard information flags.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct FileFlags(pub u32);
+
+impl FileFlags {
+    pub fn new(attribute_bits: u32, is_dir: bool) -> Self {
+        let dir_bit = if is_dir { ATTRIBUTE_DIRECTORY } else { 0 };
+        Self(attribute_bits | dir_bit)
+    }
+
+    pub fn insert(&mut self, flag: FileFlag) {
+        self.0 |= flag.bit();
+    }
+
+    pub fn contains(&self, flag: FileFlag) -> bool {
+        self.0 & flag.bit() != 0
+    }
+
+    pub fn contains_all(&self, flags: &[FileFlag]) -> bool {
+        flags.iter().all(|f| self.contains(*f))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = FileFlag> + '_ {
+        FileFlag::ALL.into_iter().filter(|f| self.contains(*f))
+    }
+
+    /// Compact tags like `HS` for hidden + system
+    pub fn tags(&self) -> String {
+        self.iter().map(|f| f.tag()).collect()
+    }
+}
+
+impl fmt::Display for FileFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = self.iter().map(|flag| flag.as_str()).collect();
+        f.write_str(&names.join("|"))
+    }
+}
+
+impl Serialize for FileFlags {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter().map(|f| f.as_str()))
+    }
+}
@@ -0,0 +1,53 @@
+//! Read the Master File Table of an NTFS volume and get its file records back.
+//!
+//! This is the part of `storage-usage-v2` that other programs can embed: dumping the MFT of a
+//! live volume, indexing a dump into one entry per file name with sizes, timestamps and
+//! attribute flags, and resolving those entries into full paths. It has no terminal UI or
+//! argument parsing; progress and per-record problems are reported as typed
+//! [`progress::ProgressEvent`]s to a [`progress::ProgressSubscriber`].
+//!
+//! Reading a live volume needs an elevated process. Elevating is left to the caller.
+//!
+//! ```no_run
+//! use storage_usage_core::mft_dump::dump_mft;
+//! use storage_usage_core::mft_index::MftIndex;
+//! use std::path::Path;
+//!
+//! # fn main() -> eyre::Result<()> {
+//! let dump = Path::new("C.mft");
+//! dump_mft('C', dump)?;
+//! let index = MftIndex::from_dump(dump)?;
+//! for (path, entry) in index.resolve_paths('C') {
+//!     println!("{path} {}", entry.size);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! With the `tokio` feature, `async_ops` runs dumps, syncs and queries on tokio's blocking
+//! pool and streams their events.
+//!
+//! Errors are `eyre::Report`s; the ones worth telling apart are [`core_error::CoreError`]s in
+//! the report's chain.
+
+#[cfg(feature = "tokio")]
+pub mod async_ops;
+pub mod core_error;
+pub mod dir_index;
+pub mod file_flags;
+pub mod mapped_index;
+pub mod mft_dump;
+pub mod mft_index;
+pub mod mft_size;
+pub mod path_resolver;
+pub mod progress;
+pub mod reparse;
+pub mod search_filter;
+pub mod security;
+pub mod spill_sort;
+pub mod usn_journal;
+pub mod volume_bitmap;
+pub mod win_handles;
+pub mod win_paged_mft_reader;
+pub mod win_privileges;
+pub mod win_strings;
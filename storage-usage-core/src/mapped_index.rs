@@ -0,0 +1,613 @@
+//! The on-disk layout of an index, which can be searched where it lies in a memory map.
+//!
+//! Every integer is little-endian. Paths are stored whole, relative to the root and without a
+//! drive letter, so reading one entry never looks at another.
+//!
+//! ```text
+//! offset  size  header
+//!      0     8  magic: b"SUIDX\0\0" then the format version, 10
+//!      8     8  number of entries, N
+//!     16     8  records the parser couldn't read
+//!     24     8  USN journal id
+//!     32     8  next USN to apply, or i64::MIN when not tracking the journal
+//!     40     8  offset of the record table
+//!     48     8  offset of the path table
+//!     56     8  offset of the string heap
+//!
+//! record table: N records of 96 bytes, ordered by record number
+//!      0     8  record number
+//!      8     8  parent record number, or i64::MIN for none
+//!     16     8  size
+//!     24     8  allocated size
+//!     32    24  created, modified and accessed, in microseconds since 1970, or i64::MIN
+//!     56     8  heap offset of the path
+//!     64     4  length of the path in bytes
+//!     68     4  length of the name, which ends the path
+//!     72     4  attribute flags
+//!     76     4  reparse tag, or 0 for none
+//!     80     8  heap offset of the reparse target
+//!     88     4  length of the reparse target, or 0 when none was read
+//!     92     2  hard link count
+//!     94     2  zero
+//!
+//! path table: N u32 positions in the record table, ordered by path folded with
+//! `search_filter::fold_name`, so the paths below a directory sit next to each other
+//!
+//! string heap: UTF-8 paths and reparse targets
+//! ```
+//!
+//! The first 40 bytes are laid out as in the streamed format of version 9, whose entries follow
+//! the header one after another. [`crate::mft_index::MftIndex::read_from`] still reads those.
+
+use crate::file_flags::FileFlags;
+use crate::mft_index::INDEX_MAGIC;
+use crate::mft_index::IndexEntry;
+use crate::mft_index::IndexSummary;
+use crate::mft_index::JournalPosition;
+use crate::mft_index::MftIndex;
+use crate::mft_index::NONE_I64;
+use crate::path_resolver::PathResolver;
+use crate::reparse::ReparsePoint;
+use crate::search_filter::fold_name;
+use crate::search_filter::split_drive;
+use crate::spill_sort::SpillSort;
+use crate::spill_sort::TempFile;
+use chrono::DateTime;
+use eyre::Context;
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+use std::path::Path;
+use tracing::debug;
+
+const HEADER_LEN: usize = 64;
+const RECORD_LEN: usize = 96;
+const POSITION_LEN: usize = 4;
+
+/// Write an index in the layout above. Paths are worked out again for each table rather than
+/// kept, and sorting them holds about `sort_buffer` bytes at once, spilling to temporary files
+/// past that.
+pub(crate) fn write_mapped(
+    index: &MftIndex,
+    sort_buffer: Option<usize>,
+    mut out: impl Write,
+) -> eyre::Result<()> {
+    let mut resolver = index.path_resolver(PathResolver::relative());
+    let entries = &index.entries;
+    // Entries are in record order unless an update appended changed records to them
+    let mut order: Vec<usize> = (0..entries.len()).collect();
+    if !entries.is_sorted_by_key(|entry| entry.record_number) {
+        order.sort_by_key(|&i| entries[i].record_number);
+    }
+
+    write_header(
+        &mut out,
+        entries.len() as u64,
+        index.parse_errors,
+        index.journal.as_ref(),
+    )?;
+    let mut by_path = SpillSort::new(sort_buffer);
+    let mut heap_len = 0u64;
+    for (position, &i) in order.iter().enumerate() {
+        let entry = &entries[i];
+        let path = resolver.resolve_index_entry(entry).path;
+        out.write_all(&encode_record(entry, &path, heap_len))?;
+        heap_len += (path.len() + reparse_target(entry).len()) as u64;
+        by_path.push(fold_name(&path), position as u32)?;
+    }
+    if by_path.runs() > 0 {
+        debug!("Sorting index paths from {} spilled runs", by_path.runs());
+    }
+    by_path.finish(|position| Ok(out.write_all(&position.to_le_bytes())?))?;
+    // Strings go in the order the records placed them
+    for &i in &order {
+        let entry = &entries[i];
+        out.write_all(resolver.resolve_index_entry(entry).path.as_bytes())?;
+        out.write_all(reparse_target(entry).as_bytes())?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Writes an index in the layout above from entries handed over one at a time, so they're never
+/// all held. Records go straight to the output and strings to a temporary heap file, which is
+/// copied in after the path table once every entry is in. The header is written last, when the
+/// entries have been counted.
+pub(crate) struct MappedWriter<W: Write + Seek> {
+    out: W,
+    entries: u64,
+    last_record: u64,
+    heap_file: TempFile,
+    heap: BufWriter<File>,
+    heap_len: u64,
+    by_path: SpillSort,
+}
+
+impl<W: Write + Seek> MappedWriter<W> {
+    /// Start an index at the beginning of `out`, sorting paths in a buffer of about
+    /// `sort_buffer` bytes
+    pub(crate) fn new(mut out: W, sort_buffer: Option<usize>) -> eyre::Result<Self> {
+        // Held until the entries are counted
+        out.write_all(&[0; HEADER_LEN])?;
+        let (heap_file, heap) = TempFile::create("heap")?;
+        Ok(Self {
+            out,
+            entries: 0,
+            last_record: 0,
+            heap_file,
+            heap: BufWriter::new(heap),
+            heap_len: 0,
+            by_path: SpillSort::new(sort_buffer),
+        })
+    }
+
+    /// Add an entry and its path, without a drive. Entries must come in record order.
+    pub(crate) fn push(&mut self, entry: &IndexEntry, path: &str) -> eyre::Result<()> {
+        if entry.record_number < self.last_record {
+            return Err(eyre::eyre!(
+                "Record {} came after record {}",
+                entry.record_number,
+                self.last_record
+            ));
+        }
+        self.last_record = entry.record_number;
+        let target = reparse_target(entry);
+        self.out
+            .write_all(&encode_record(entry, path, self.heap_len))?;
+        self.heap.write_all(path.as_bytes())?;
+        self.heap.write_all(target.as_bytes())?;
+        self.heap_len += (path.len() + target.len()) as u64;
+        self.by_path.push(fold_name(path), self.entries as u32)?;
+        self.entries += 1;
+        Ok(())
+    }
+
+    /// Write the path table, the strings and the header, giving back how many entries there are
+    pub(crate) fn finish(
+        mut self,
+        parse_errors: u64,
+        journal: Option<&JournalPosition>,
+    ) -> eyre::Result<u64> {
+        if self.by_path.runs() > 0 {
+            debug!(
+                "Sorting index paths from {} spilled runs",
+                self.by_path.runs()
+            );
+        }
+        let out = &mut self.out;
+        self.by_path
+            .finish(|position| Ok(out.write_all(&position.to_le_bytes())?))?;
+        self.heap
+            .flush()
+            .with_context(|| format!("writing heap file {}", self.heap_file.path().display()))?;
+        std::io::copy(&mut self.heap_file.reader()?, &mut self.out)?;
+        self.out.seek(SeekFrom::Start(0))?;
+        write_header(&mut self.out, self.entries, parse_errors, journal)?;
+        self.out.flush()?;
+        Ok(self.entries)
+    }
+}
+
+/// The header of an index of `entries` entries, whose tables follow one another after it
+fn write_header(
+    out: &mut impl Write,
+    entries: u64,
+    parse_errors: u64,
+    journal: Option<&JournalPosition>,
+) -> eyre::Result<()> {
+    let records = HEADER_LEN as u64;
+    let path_table = records + entries * RECORD_LEN as u64;
+    let heap = path_table + entries * POSITION_LEN as u64;
+    out.write_all(INDEX_MAGIC)?;
+    out.write_all(&entries.to_le_bytes())?;
+    out.write_all(&parse_errors.to_le_bytes())?;
+    out.write_all(&journal.map(|j| j.journal_id).unwrap_or(0).to_le_bytes())?;
+    out.write_all(
+        &journal
+            .map(|j| j.next_usn)
+            .unwrap_or(NONE_I64)
+            .to_le_bytes(),
+    )?;
+    for offset in [records, path_table, heap] {
+        out.write_all(&offset.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// The record of an entry whose path is at `heap_offset` in the heap, followed by its reparse
+/// target
+fn encode_record(entry: &IndexEntry, path: &str, heap_offset: u64) -> [u8; RECORD_LEN] {
+    let target_len = reparse_target(entry).len();
+    let mut record = [0u8; RECORD_LEN];
+    let parent = entry.parent_ref.map(|p| p as i64).unwrap_or(NONE_I64);
+    put(&mut record, 0, &entry.record_number.to_le_bytes());
+    put(&mut record, 8, &parent.to_le_bytes());
+    put(&mut record, 16, &entry.size.to_le_bytes());
+    put(&mut record, 24, &entry.allocated_size.to_le_bytes());
+    for (slot, time) in [entry.created, entry.modified, entry.accessed]
+        .into_iter()
+        .enumerate()
+    {
+        let micros = time.map(|t| t.timestamp_micros()).unwrap_or(NONE_I64);
+        put(&mut record, 32 + slot * 8, &micros.to_le_bytes());
+    }
+    put(&mut record, 56, &heap_offset.to_le_bytes());
+    put(&mut record, 64, &(path.len() as u32).to_le_bytes());
+    put(&mut record, 68, &(entry.name.len() as u32).to_le_bytes());
+    put(&mut record, 72, &entry.flags.0.to_le_bytes());
+    let tag = entry.reparse.as_ref().map(|r| r.tag).unwrap_or(0);
+    put(&mut record, 76, &tag.to_le_bytes());
+    let target_offset = heap_offset + path.len() as u64;
+    put(&mut record, 80, &target_offset.to_le_bytes());
+    put(&mut record, 88, &(target_len as u32).to_le_bytes());
+    put(&mut record, 92, &entry.hard_link_count.to_le_bytes());
+    record
+}
+
+/// Where a link points, or empty when the entry isn't one or its target wasn't read
+fn reparse_target(entry: &IndexEntry) -> &str {
+    let reparse = entry.reparse.as_ref();
+    reparse.and_then(|r| r.target.as_deref()).unwrap_or("")
+}
+
+fn put(record: &mut [u8; RECORD_LEN], at: usize, bytes: &[u8]) {
+    record[at..at + bytes.len()].copy_from_slice(bytes);
+}
+
+/// An index file read in place, so opening one doesn't decode its entries and finding a path
+/// is a binary search. Entries are decoded one at a time as they're asked for.
+pub struct MappedIndex<B = Mmap> {
+    bytes: B,
+    summary: IndexSummary,
+    records: usize,
+    path_table: usize,
+    heap: usize,
+}
+
+impl MappedIndex {
+    /// Memory-map an index file. While it's mapped Windows refuses to truncate it, so an index
+    /// rebuilt meanwhile fails to write instead of shrinking under the map.
+    pub fn open(path: &Path) -> eyre::Result<Self> {
+        let file = File::open(path).with_context(|| format!("opening {}", path.display()))?;
+        let map =
+            unsafe { Mmap::map(&file) }.with_context(|| format!("mapping {}", path.display()))?;
+        Self::from_bytes(map).with_context(|| format!("reading {}", path.display()))
+    }
+}
+
+impl<B: AsRef<[u8]>> MappedIndex<B> {
+    /// Check the header of an index in memory, the bounds of its tables and the positions in
+    /// its path table
+    pub fn from_bytes(bytes: B) -> eyre::Result<Self> {
+        let data = bytes.as_ref();
+        if data.len() < HEADER_LEN {
+            return Err(eyre::eyre!("Index is shorter than its header"));
+        }
+        if &data[..8] != INDEX_MAGIC {
+            return Err(eyre::eyre!(
+                "Not a storage-usage index in the mappable format (bad magic)"
+            ));
+        }
+        let u64_at = |at: usize| u64::from_le_bytes(data[at..at + 8].try_into().unwrap());
+        let entries = u64_at(8);
+        let journal = match u64_at(32) as i64 {
+            NONE_I64 => None,
+            next_usn => Some(JournalPosition {
+                journal_id: u64_at(24),
+                next_usn,
+            }),
+        };
+        let summary = IndexSummary {
+            entries,
+            parse_errors: u64_at(16),
+            journal,
+        };
+        let [records, path_table, heap] = [u64_at(40), u64_at(48), u64_at(56)].map(|o| o as usize);
+        let count = entries as usize;
+        let fits = |offset: usize, len: usize| {
+            count
+                .checked_mul(len)
+                .and_then(|size| offset.checked_add(size))
+                .is_some_and(|end| end <= data.len())
+        };
+        if !fits(records, RECORD_LEN) || !fits(path_table, POSITION_LEN) || heap > data.len() {
+            return Err(eyre::eyre!("Index tables run past the end of the file"));
+        }
+        // Queries slice the record table at these, so a damaged one is refused here rather than
+        // panicking later
+        let positions = &data[path_table..path_table + count * POSITION_LEN];
+        if let Some(rank) = positions
+            .chunks_exact(POSITION_LEN)
+            .position(|position| field_u32(position, 0) as usize >= count)
+        {
+            return Err(eyre::eyre!(
+                "Path table entry {rank} points past the {count} records"
+            ));
+        }
+        Ok(Self {
+            bytes,
+            summary,
+            records,
+            path_table,
+            heap,
+        })
+    }
+
+    pub fn summary(&self) -> IndexSummary {
+        self.summary
+    }
+
+    /// Number of entries, one per name
+    pub fn len(&self) -> usize {
+        self.summary.entries as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Path of the entry at a position in the record table, below the root and without a
+    /// drive letter
+    pub fn relative_path(&self, position: usize) -> &str {
+        let record = self.record_at(position);
+        self.string(field_u64(record, 56), field_u32(record, 64))
+    }
+
+    /// Full path of the entry at a position in the record table, prefixed with the drive letter
+    pub fn path(&self, drive_letter: char, position: usize) -> String {
+        format!("{drive_letter}:\\{}", self.relative_path(position))
+    }
+
+    /// Decode the entry at a position in the record table
+    pub fn entry(&self, position: usize) -> IndexEntry {
+        let record = self.record_at(position);
+        let path = self.relative_path(position);
+        let name_len = (field_u32(record, 68) as usize).min(path.len());
+        let time = |at: usize| match field_u64(record, at) as i64 {
+            NONE_I64 => None,
+            micros => DateTime::from_timestamp_micros(micros),
+        };
+        let tag = field_u32(record, 76);
+        let target = self.string(field_u64(record, 80), field_u32(record, 88));
+        IndexEntry {
+            record_number: field_u64(record, 0),
+            hard_link_count: u16::from_le_bytes([record[92], record[93]]),
+            parent_ref: match field_u64(record, 8) as i64 {
+                NONE_I64 => None,
+                parent => Some(parent as u64),
+            },
+            name: path
+                .get(path.len() - name_len..)
+                .unwrap_or(path)
+                .to_string(),
+            size: field_u64(record, 16),
+            allocated_size: field_u64(record, 24),
+            created: time(32),
+            modified: time(40),
+            accessed: time(48),
+            flags: FileFlags(field_u32(record, 72)),
+            reparse: (tag != 0).then(|| ReparsePoint {
+                tag,
+                target: (!target.is_empty()).then(|| target.to_string()),
+            }),
+        }
+    }
+
+    /// Every entry with its path below the root, in record order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, IndexEntry)> + '_ {
+        (0..self.len()).map(|position| (self.relative_path(position), self.entry(position)))
+    }
+
+    /// Every name of a record, found by binary search
+    pub fn record(&self, record_number: u64) -> impl Iterator<Item = IndexEntry> + '_ {
+        let start = partition_point(self.len(), |position| {
+            field_u64(self.record_at(position), 0) < record_number
+        });
+        (start..self.len())
+            .take_while(move |&position| field_u64(self.record_at(position), 0) == record_number)
+            .map(|position| self.entry(position))
+    }
+
+    /// Entries at a full path, ignoring case and composition as
+    /// [`crate::mft_index::MftIndex::lookup_path`] does, found by binary search. A drive
+    /// letter is ignored, as an index holds a single volume.
+    pub fn lookup_path(&self, path: &str) -> Vec<IndexEntry> {
+        let key = fold_name(split_drive(path).1);
+        if key.is_empty() {
+            return Vec::new();
+        }
+        let start = self.first_path_from(&key);
+        (start..self.len())
+            .map(|rank| self.position_at(rank))
+            .take_while(|&position| fold_name(self.relative_path(position)) == key)
+            .map(|position| self.entry(position))
+            .collect()
+    }
+
+    /// Positions in the record table of every entry below a directory, in folded path order.
+    /// Only that part of the path table is read. The root, or an empty path, gives every entry.
+    pub fn under(&self, directory: &str) -> impl Iterator<Item = usize> + '_ {
+        let mut prefix = fold_name(split_drive(directory).1);
+        if !prefix.is_empty() {
+            prefix.push('\\');
+        }
+        let start = self.first_path_from(&prefix);
+        (start..self.len())
+            .map(|rank| self.position_at(rank))
+            .take_while(move |&position| {
+                fold_name(self.relative_path(position)).starts_with(&prefix)
+            })
+    }
+
+    /// Decode every entry into an [`MftIndex`]
+    pub fn to_index(&self) -> MftIndex {
+        MftIndex {
+            entries: (0..self.len())
+                .map(|position| self.entry(position))
+                .collect(),
+            parse_errors: self.summary.parse_errors,
+            journal: self.summary.journal,
+        }
+    }
+
+    /// Rank in the path table of the first path that folds to `key` or sorts after it
+    fn first_path_from(&self, key: &str) -> usize {
+        partition_point(self.len(), |rank| {
+            fold_name(self.relative_path(self.position_at(rank))).as_str() < key
+        })
+    }
+
+    fn record_at(&self, position: usize) -> &[u8] {
+        let start = self.records + position * RECORD_LEN;
+        &self.bytes.as_ref()[start..start + RECORD_LEN]
+    }
+
+    fn position_at(&self, rank: usize) -> usize {
+        let at = self.path_table + rank * POSITION_LEN;
+        field_u32(self.bytes.as_ref(), at) as usize
+    }
+
+    /// A string from the heap; empty when its bounds or bytes are damaged
+    fn string(&self, offset: u64, len: u32) -> &str {
+        let start = self.heap.saturating_add(offset as usize);
+        self.bytes
+            .as_ref()
+            .get(start..start.saturating_add(len as usize))
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+            .unwrap_or("")
+    }
+}
+
+fn field_u64(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+}
+
+fn field_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+/// The first index in `0..len` for which `before` is false, where it is true for a prefix
+fn partition_point(len: usize, before: impl Fn(usize) -> bool) -> usize {
+    let (mut low, mut high) = (0, len);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if before(middle) {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+    low
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MappedIndex;
+    use super::MappedWriter;
+    use crate::mft_index::IndexEntry;
+    use crate::mft_index::MftIndex;
+    use crate::mft_index::ROOT_RECORD;
+    use crate::mft_index::test_entry;
+    use crate::path_resolver::PathResolver;
+    use crate::reparse::ReparsePoint;
+    use std::io::Cursor;
+
+    fn mapped(index: &MftIndex) -> MappedIndex<Vec<u8>> {
+        let mut bytes = Vec::new();
+        index.write_to(&mut bytes).unwrap();
+        MappedIndex::from_bytes(bytes).unwrap()
+    }
+
+    #[test]
+    fn finds_paths_and_subtrees_by_binary_search() {
+        let index = MftIndex {
+            entries: vec![
+                test_entry(40, ROOT_RECORD, "Users"),
+                test_entry(41, 40, "Alice"),
+                test_entry(42, 41, "notes.txt"),
+                test_entry(43, ROOT_RECORD, "Users2"),
+                test_entry(44, 43, "\u{dc}ber.txt"),
+                IndexEntry {
+                    reparse: Some(ReparsePoint {
+                        tag: 0xA000_000C,
+                        target: Some(r"D:\Data".to_string()),
+                    }),
+                    ..entry(45, 41, "Data")
+                },
+            ],
+            parse_errors: 3,
+            journal: None,
+        };
+        let mapped = mapped(&index);
+        assert_eq!(mapped.to_index(), index);
+        assert_eq!(mapped.summary().parse_errors, 3);
+        assert_eq!(
+            mapped.lookup_path(r"C:\users\ALICE\Notes.txt"),
+            vec![index.entries[2].clone()]
+        );
+        assert_eq!(
+            mapped.lookup_path("\\Users2\\u\u{308}ber.TXT"),
+            vec![index.entries[4].clone()]
+        );
+        assert!(mapped.lookup_path(r"\Alice\notes.txt").is_empty());
+        let under: Vec<String> = mapped
+            .under(r"C:\USERS\")
+            .map(|position| mapped.path('C', position))
+            .collect();
+        assert_eq!(
+            under,
+            [
+                r"C:\Users\Alice",
+                r"C:\Users\Alice\Data",
+                r"C:\Users\Alice\notes.txt"
+            ]
+        );
+        assert_eq!(mapped.under("").count(), index.len());
+        assert_eq!(mapped.record(45).next(), Some(index.entries[5].clone()));
+
+        // Spilling the path sort to disk writes the same bytes
+        let (mut whole, mut spilled) = (Vec::new(), Vec::new());
+        index.write_to(&mut whole).unwrap();
+        index.write_to_with_limit(&mut spilled, Some(1)).unwrap();
+        assert_eq!(whole, spilled);
+
+        // So does writing the entries as they come, as a memory-limited build does
+        let mut resolver = index.path_resolver(PathResolver::relative());
+        let mut streamed = Cursor::new(Vec::new());
+        let mut writer = MappedWriter::new(&mut streamed, Some(1)).unwrap();
+        for entry in &index {
+            let path = resolver.resolve_index_entry(entry).path;
+            writer.push(entry, &path).unwrap();
+        }
+        assert_eq!(writer.finish(3, None).unwrap(), 6);
+        assert_eq!(streamed.into_inner(), whole);
+        let mut out_of_order = MappedWriter::new(Cursor::new(Vec::new()), None).unwrap();
+        out_of_order
+            .push(&index.entries[1], r"Users\Alice")
+            .unwrap();
+        assert!(out_of_order.push(&index.entries[0], "Users").is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_tables() {
+        let mut bytes = Vec::new();
+        MftIndex {
+            entries: vec![test_entry(40, ROOT_RECORD, "Users")],
+            parse_errors: 0,
+            journal: None,
+        }
+        .write_to(&mut bytes)
+        .unwrap();
+        let mut truncated = bytes.clone();
+        truncated.truncate(100);
+        assert!(MappedIndex::from_bytes(truncated).is_err());
+
+        // A path table position past the record table
+        let path_table = u64::from_le_bytes(bytes[48..56].try_into().unwrap()) as usize;
+        bytes[path_table..path_table + 4].copy_from_slice(&7u32.to_le_bytes());
+        assert!(MappedIndex::from_bytes(bytes).is_err());
+    }
+}
@@ -0,0 +1,1039 @@
+use crate::core_error::CoreError;
+use crate::dir_index::FILE_NAME_INDEX;
+use crate::dir_index::index_allocation_path_for;
+use crate::dir_index::write_index_allocation;
+use crate::progress::NoProgress;
+use crate::progress::ProgressEvent;
+use crate::progress::ProgressSubscriber;
+use crate::security::security_path_for;
+use crate::volume_bitmap::bitmap_path_for;
+use crate::win_handles::get_drive_handle;
+use crate::win_paged_mft_reader::MftExtent;
+use crate::win_paged_mft_reader::PagedMftReader;
+use crate::win_privileges::drop_privileges_if_requested;
+use eyre::Context;
+use eyre::eyre;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::BufWriter;
+use std::io::Write;
+use std::mem::size_of;
+use std::path::Path;
+use tracing::info;
+use tracing::info_span;
+use tracing::warn;
+use windows::Win32::Foundation::HANDLE;
+use windows::Win32::Foundation::LUID;
+use windows::Win32::Security::AdjustTokenPrivileges;
+use windows::Win32::Security::LookupPrivilegeValueW;
+use windows::Win32::Security::SE_BACKUP_NAME;
+use windows::Win32::Security::SE_PRIVILEGE_ENABLED;
+use windows::Win32::Security::SE_RESTORE_NAME;
+use windows::Win32::Security::SE_SECURITY_NAME;
+use windows::Win32::Security::TOKEN_ADJUST_PRIVILEGES;
+use windows::Win32::Security::TOKEN_PRIVILEGES;
+use windows::Win32::Security::TOKEN_QUERY;
+use windows::Win32::Storage::FileSystem::FILE_BEGIN;
+use windows::Win32::Storage::FileSystem::ReadFile;
+use windows::Win32::Storage::FileSystem::SetFilePointerEx;
+use windows::Win32::System::IO::DeviceIoControl;
+use windows::Win32::System::Ioctl::FSCTL_GET_NTFS_VOLUME_DATA;
+use windows::Win32::System::Ioctl::NTFS_VOLUME_DATA_BUFFER;
+use windows::Win32::System::Threading::GetCurrentProcess;
+use windows::Win32::System::Threading::OpenProcessToken;
+
+/// Dump the MFT of a live volume to `output_path`, replacing the file if it exists, and return
+/// its size in bytes. The volume's `$Bitmap` and security descriptors are dumped next to it,
+/// see [`dump_bitmap`] and [`dump_security`], as are the index blocks of directories too big
+/// for their record, see [`crate::dir_index::read_index_allocations`].
+/// The caller must already be elevated.
+pub fn dump_mft(drive_letter: char, output_path: &Path) -> eyre::Result<u64> {
+    dump_mft_with_progress(drive_letter, output_path, &mut NoProgress)
+}
+
+/// [`dump_mft`], sending [`ProgressEvent::MftRead`] to `subscriber` as the MFT is read
+pub fn dump_mft_with_progress(
+    drive_letter: char,
+    output_path: &Path,
+    subscriber: &mut dyn ProgressSubscriber,
+) -> eyre::Result<u64> {
+    // Enable backup privileges to access system files like $MFT
+    enable_backup_privileges().with_context(|| "Failed to enable backup privileges")?;
+
+    // Use the provided drive letter
+    let drive_letter = drive_letter.to_uppercase().next().unwrap_or('C');
+
+    // Validate that the drive is using NTFS filesystem
+    info!("Validating filesystem type for drive {}...", drive_letter);
+    validate_ntfs_filesystem(drive_letter)
+        .with_context(|| format!("NTFS validation failed for drive {drive_letter}"))?;
+
+    // Read before the MFT, whose reader drops privileges once the volume is open when asked
+    // to. The bitmap is only needed to cross-check usage, so a dump is still good without it
+    let bitmap_path = bitmap_path_for(output_path);
+    if let Err(e) = dump_bitmap(drive_letter, &bitmap_path) {
+        warn!(
+            "Failed to dump the volume bitmap to '{}': {e:#}",
+            bitmap_path.display()
+        );
+    }
+    // Only needed to report usage by owner
+    let security_path = security_path_for(output_path);
+    if let Err(e) = dump_security(drive_letter, &security_path) {
+        warn!(
+            "Failed to dump the security descriptors to '{}': {e:#}",
+            security_path.display()
+        );
+    }
+
+    // Index blocks are found through the MFT, so this handle is opened before reading it
+    // drops privileges
+    let index_handle = get_drive_handle(drive_letter);
+
+    info!("Reading MFT data from drive {}...", drive_letter);
+    let mft_data = read_mft_data(drive_letter, subscriber)?;
+
+    info!("Writing MFT data to '{}'...", output_path.display());
+    write_mft_to_file(&mft_data, output_path)?;
+
+    // Only needed to name files whose records are gone, so a dump is still good without it
+    let index_path = index_allocation_path_for(output_path);
+    match index_handle.and_then(|handle| dump_index_allocations(*handle, &mft_data, &index_path)) {
+        Ok(directories) => info!(
+            "Dumped the index blocks of {directories} directories to '{}'",
+            index_path.display()
+        ),
+        Err(e) => warn!(
+            "Failed to dump directory index blocks to '{}': {e:#}",
+            index_path.display()
+        ),
+    }
+
+    info!(
+        "Successfully dumped MFT ({}) to '{}'",
+        humansize::format_size(mft_data.len(), humansize::DECIMAL),
+        output_path.display()
+    );
+
+    Ok(mft_data.len() as u64)
+}
+
+/// Dump the `$Bitmap` of a live volume, one bit per cluster set while the cluster is in use,
+/// to `output_path` and return its size in bytes. The caller must already be elevated.
+pub fn dump_bitmap(drive_letter: char, output_path: &Path) -> eyre::Result<u64> {
+    // $Bitmap is always record 6
+    const BITMAP_RECORD: u64 = 6;
+
+    let size = dump_system_stream(drive_letter, BITMAP_RECORD, "", output_path)?;
+    info!(
+        "Dumped volume bitmap ({}) to '{}'",
+        humansize::format_size(size, humansize::DECIMAL),
+        output_path.display()
+    );
+    Ok(size)
+}
+
+/// Dump the `$SDS` stream of a live volume's `$Secure`, which holds the security descriptor of
+/// every security id files refer to, to `output_path` and return its size in bytes. The caller
+/// must already be elevated.
+pub fn dump_security(drive_letter: char, output_path: &Path) -> eyre::Result<u64> {
+    // $Secure is always record 9
+    const SECURE_RECORD: u64 = 9;
+
+    let size = dump_system_stream(drive_letter, SECURE_RECORD, "$SDS", output_path)?;
+    info!(
+        "Dumped security descriptors ({}) to '{}'",
+        humansize::format_size(size, humansize::DECIMAL),
+        output_path.display()
+    );
+    Ok(size)
+}
+
+/// Dump a non-resident `$DATA` stream, unnamed when `stream_name` is empty, of one of the
+/// metadata files in the first records of the MFT
+fn dump_system_stream(
+    drive_letter: char,
+    record_number: u64,
+    stream_name: &str,
+    output_path: &Path,
+) -> eyre::Result<u64> {
+    enable_backup_privileges().with_context(|| "Failed to enable backup privileges")?;
+    let drive_handle = get_drive_handle(drive_letter)
+        .with_context(|| format!("Failed to open handle to drive {drive_letter}"))?;
+    let boot_sector = read_boot_sector(*drive_handle)?;
+    let bytes_per_cluster =
+        boot_sector.bytes_per_sector as u64 * boot_sector.sectors_per_cluster as u64;
+    let mft_location = boot_sector.mft_cluster_number * bytes_per_cluster;
+    let record_size = boot_sector.bytes_per_record();
+
+    // The first records of the MFT are contiguous, so they're found without its runs
+    let record = read_mft_record(*drive_handle, mft_location, record_size, record_number)?;
+    let (data_runs, data_size) = parse_mft_record_for_stream(&record, stream_name)?;
+    let mut data = read_mft_using_data_runs(
+        *drive_handle,
+        &data_runs,
+        bytes_per_cluster,
+        &mut NoProgress,
+    )?;
+    // The last cluster is only partly used by the stream
+    data.truncate(data_size as usize);
+
+    write_mft_to_file(&data, output_path)?;
+    Ok(data.len() as u64)
+}
+
+/// Dump the `$INDEX_ALLOCATION` of every directory in `mft` that has one, read from the volume
+/// the MFT came from, to `output_path` and return how many directories were dumped. This reads
+/// the index blocks of every large directory, which on a volume with many files can add
+/// hundreds of megabytes of reads to each dump.
+fn dump_index_allocations(
+    drive_handle: HANDLE,
+    mft: &[u8],
+    output_path: &Path,
+) -> eyre::Result<u64> {
+    const RECORD_IN_USE: u8 = 0x1;
+
+    let boot_sector = read_boot_sector(drive_handle)?;
+    let bytes_per_cluster =
+        boot_sector.bytes_per_sector as u64 * boot_sector.sectors_per_cluster as u64;
+    let volume_size = boot_sector.volume_size();
+    let record_size = record_size_of_dump(mft)
+        .ok_or_else(|| eyre!("The MFT doesn't start with a record"))?
+        as usize;
+    let file = File::create(output_path)
+        .with_context(|| format!("Failed to create file: {}", output_path.display()))?;
+    let mut out = BufWriter::new(file);
+    let mut dumped = 0;
+    for (record_number, record) in mft.chunks_exact(record_size).enumerate() {
+        if !record.starts_with(b"FILE") || record[0x16] & RECORD_IN_USE == 0 {
+            continue;
+        }
+        let mut record = record.to_vec();
+        if apply_fixups(&mut record).is_err() {
+            continue;
+        }
+        let Ok(Some((runs, size))) = parse_mft_record_for_attribute(&record, 0xA0, FILE_NAME_INDEX)
+        else {
+            continue;
+        };
+        // An extension record's attributes belong to its base record
+        let base = u64::from_le_bytes(record[0x20..0x28].try_into()?) & 0xFFFF_FFFF_FFFF;
+        let directory = if base == 0 {
+            record_number as u64
+        } else {
+            base
+        };
+        // One damaged directory shouldn't cost the rest their blocks
+        let mut allocation =
+            match read_runs(drive_handle, &runs, bytes_per_cluster, volume_size, size) {
+                Ok(allocation) => allocation,
+                Err(e) => {
+                    warn!("Skipping the index blocks of record {directory}: {e:#}");
+                    continue;
+                }
+            };
+        allocation.truncate(size as usize);
+        write_index_allocation(&mut out, directory, &allocation)?;
+        dumped += 1;
+    }
+    out.flush()?;
+    Ok(dumped)
+}
+
+/// Validates that the specified drive is using NTFS filesystem
+pub fn validate_ntfs_filesystem(drive_letter: char) -> eyre::Result<()> {
+    // For now, we'll validate by attempting to get NTFS volume data
+    // If this succeeds, we know it's an NTFS volume
+    let drive_handle = get_drive_handle(drive_letter)
+        .with_context(|| format!("Failed to open handle to drive {drive_letter}"))?;
+
+    let mut volume_data = NTFS_VOLUME_DATA_BUFFER::default();
+    let mut bytes_returned = 0u32;
+
+    let result = unsafe {
+        DeviceIoControl(
+            *drive_handle,
+            FSCTL_GET_NTFS_VOLUME_DATA,
+            None,
+            0,
+            Some(&mut volume_data as *mut _ as *mut _),
+            size_of::<NTFS_VOLUME_DATA_BUFFER>() as u32,
+            Some(&mut bytes_returned),
+            None,
+        )
+    };
+
+    match result {
+        Ok(_) => {
+            info!(
+                "✓ Filesystem validation passed: Drive {} is using NTFS",
+                drive_letter
+            );
+            info!("NTFS Volume Info:");
+            // info!("  VolumeSerialNumber: 0x{:X}", volume_data.VolumeSerialNumber);
+            info!("  NumberSectors: {}", volume_data.NumberSectors);
+            info!("  TotalClusters: {}", volume_data.TotalClusters);
+            info!("  FreeClusters: {}", volume_data.FreeClusters);
+            info!("  BytesPerSector: {}", volume_data.BytesPerSector);
+            info!("  BytesPerCluster: {}", volume_data.BytesPerCluster);
+            info!(
+                "  BytesPerFileRecordSegment: {}",
+                volume_data.BytesPerFileRecordSegment
+            );
+            Ok(())
+        }
+        Err(e) => Err(CoreError::NotNtfs {
+            drive_letter,
+            detail: e.to_string(),
+        }
+        .into()),
+    }
+}
+
+/// Reads the raw MFT data by parsing the MFT's own record and following its data runs, sending
+/// [`ProgressEvent::MftRead`] to `subscriber` as it goes.
+/// The caller must already be elevated.
+pub fn read_mft_data(
+    drive_letter: char,
+    subscriber: &mut dyn ProgressSubscriber,
+) -> eyre::Result<Vec<u8>> {
+    info!("Reading MFT using proper data runs parsing approach");
+    read_mft_from_volume_with_dataruns(drive_letter, subscriber)
+}
+
+/// Reads the MFT by parsing the boot sector and following data runs properly
+fn read_mft_from_volume_with_dataruns(
+    drive_letter: char,
+    subscriber: &mut dyn ProgressSubscriber,
+) -> eyre::Result<Vec<u8>> {
+    // Get a handle to the volume
+    let drive_handle = get_drive_handle(drive_letter)
+        .with_context(|| format!("Failed to open handle to drive {drive_letter}"))?;
+    drop_privileges_if_requested()?;
+
+    // Step 1: Read the boot sector to get NTFS parameters
+    let boot_sector = read_boot_sector(*drive_handle)?;
+
+    info!("NTFS Boot Sector Info:");
+    info!("  Bytes per sector: {}", boot_sector.bytes_per_sector);
+    info!("  Sectors per cluster: {}", boot_sector.sectors_per_cluster);
+    info!("  MFT cluster number: {}", boot_sector.mft_cluster_number);
+    info!("  Bytes per MFT record: {}", boot_sector.bytes_per_record());
+
+    let bytes_per_cluster =
+        boot_sector.bytes_per_sector as u64 * boot_sector.sectors_per_cluster as u64;
+    let mft_location = boot_sector.mft_cluster_number * bytes_per_cluster;
+
+    info!("Calculated MFT location: {} bytes", mft_location);
+
+    // Step 2: Read the MFT's own record (record 0)
+    let mft_record = read_mft_record(
+        *drive_handle,
+        mft_location,
+        boot_sector.bytes_per_record(),
+        0,
+    )?;
+
+    // Step 3: Parse the MFT record to find the DATA attribute (0x80)
+    let (data_runs, _) = parse_mft_record_for_data_attribute(&mft_record)?;
+
+    // Step 4: Follow the data runs to read the complete MFT
+    read_mft_using_data_runs(*drive_handle, &data_runs, bytes_per_cluster, subscriber)
+}
+
+/// Opens the live volume and returns a reader over its MFT, without dumping it to disk.
+/// The caller must already be elevated.
+pub fn open_live_mft_reader(drive_letter: char) -> eyre::Result<PagedMftReader> {
+    enable_backup_privileges().with_context(|| "Failed to enable backup privileges")?;
+    let drive_letter = drive_letter.to_uppercase().next().unwrap_or('C');
+    validate_ntfs_filesystem(drive_letter)
+        .with_context(|| format!("NTFS validation failed for drive {drive_letter}"))?;
+
+    let drive_handle = get_drive_handle(drive_letter)
+        .with_context(|| format!("Failed to open handle to drive {drive_letter}"))?;
+    drop_privileges_if_requested()?;
+    let boot_sector = read_boot_sector(*drive_handle)?;
+    let bytes_per_cluster =
+        boot_sector.bytes_per_sector as u64 * boot_sector.sectors_per_cluster as u64;
+    let mft_location = boot_sector.mft_cluster_number * bytes_per_cluster;
+    let mft_record = read_mft_record(
+        *drive_handle,
+        mft_location,
+        boot_sector.bytes_per_record(),
+        0,
+    )?;
+    let (data_runs, _) = parse_mft_record_for_data_attribute(&mft_record)?;
+
+    let mut extents = Vec::with_capacity(data_runs.len());
+    let mut current_cluster = 0i64;
+    let mut virtual_offset = 0u64;
+    for run in &data_runs {
+        current_cluster += run.cluster;
+        let length = run.length * bytes_per_cluster;
+        extents.push(MftExtent {
+            virtual_offset,
+            physical_offset: current_cluster as u64 * bytes_per_cluster,
+            length,
+        });
+        virtual_offset += length;
+    }
+    info!(
+        "Opened live MFT on drive {} ({} in {} extents)",
+        drive_letter,
+        humansize::format_size(virtual_offset, humansize::DECIMAL),
+        extents.len()
+    );
+
+    // Read in 16MB pages
+    Ok(PagedMftReader::new(drive_handle, extents, 16 * 1024 * 1024))
+}
+
+/// NTFS boot sector information
+#[derive(Debug)]
+struct NtfsBootSector {
+    bytes_per_sector: u16,
+    sectors_per_cluster: u8,
+    mft_cluster_number: u64,
+    clusters_per_record: i8,
+    total_sectors: u64,
+}
+
+impl NtfsBootSector {
+    fn bytes_per_record(&self) -> u64 {
+        let bytes_per_cluster = self.bytes_per_sector as u64 * self.sectors_per_cluster as u64;
+        record_size_from_boot(self.clusters_per_record, bytes_per_cluster)
+    }
+
+    fn volume_size(&self) -> u64 {
+        self.total_sectors
+            .saturating_mul(self.bytes_per_sector as u64)
+    }
+}
+
+/// The size of an MFT record from the boot sector's "clusters per file record segment" byte:
+/// a count of clusters when positive, or the record size as a power of two, negated, when
+/// clusters are bigger than a record (`0xF6` is 2^10 = 1024 bytes)
+pub fn record_size_from_boot(clusters_per_record: i8, bytes_per_cluster: u64) -> u64 {
+    if clusters_per_record >= 0 {
+        clusters_per_record as u64 * bytes_per_cluster
+    } else {
+        1 << clusters_per_record.unsigned_abs()
+    }
+}
+
+/// The record size a dump was written with, from the allocated size in its first record's
+/// header; `None` when the dump doesn't start with a record
+pub fn record_size_of_dump(header: &[u8]) -> Option<u64> {
+    if header.get(0..4)? != b"FILE" {
+        return None;
+    }
+    let size = u32::from_le_bytes(header.get(0x1C..0x20)?.try_into().ok()?);
+    (size.is_power_of_two() && size >= 512).then_some(size as u64)
+}
+
+/// Reads and parses the NTFS boot sector
+fn read_boot_sector(drive_handle: HANDLE) -> eyre::Result<NtfsBootSector> {
+    // Seek to the beginning of the drive
+    unsafe {
+        SetFilePointerEx(drive_handle, 0, None, FILE_BEGIN)
+            .with_context(|| "Failed to seek to boot sector")?;
+    }
+
+    // Read the boot sector (512 bytes)
+    let mut boot_sector = vec![0u8; 512];
+    let mut bytes_read = 0u32;
+    unsafe {
+        ReadFile(
+            drive_handle,
+            Some(boot_sector.as_mut_slice()),
+            Some(&mut bytes_read),
+            None,
+        )
+        .with_context(|| "Failed to read boot sector")?;
+    }
+
+    if bytes_read != 512 {
+        return Err(eyre!(
+            "Failed to read complete boot sector: got {} bytes",
+            bytes_read
+        ));
+    }
+
+    // Parse relevant fields from the boot sector
+    let bytes_per_sector = u16::from_le_bytes([boot_sector[0x0b], boot_sector[0x0c]]);
+    let sectors_per_cluster = boot_sector[0x0d];
+    let clusters_per_record = boot_sector[0x40] as i8;
+    let mft_cluster_number = u64::from_le_bytes([
+        boot_sector[0x30],
+        boot_sector[0x31],
+        boot_sector[0x32],
+        boot_sector[0x33],
+        boot_sector[0x34],
+        boot_sector[0x35],
+        boot_sector[0x36],
+        boot_sector[0x37],
+    ]);
+
+    let total_sectors = u64::from_le_bytes(boot_sector[0x28..0x30].try_into()?);
+
+    Ok(NtfsBootSector {
+        bytes_per_sector,
+        sectors_per_cluster,
+        mft_cluster_number,
+        clusters_per_record,
+        total_sectors,
+    })
+}
+
+/// Reads a specific MFT record of `record_size` bytes, usually 1024 but 4096 on some volumes
+/// with 4K sectors
+fn read_mft_record(
+    drive_handle: HANDLE,
+    mft_location: u64,
+    record_size: u64,
+    record_number: u64,
+) -> eyre::Result<Vec<u8>> {
+    let record_offset = mft_location + (record_number * record_size);
+
+    // Seek to the record
+    unsafe {
+        SetFilePointerEx(drive_handle, record_offset as i64, None, FILE_BEGIN)
+            .with_context(|| format!("Failed to seek to MFT record {record_number}"))?;
+    }
+
+    // Read the record
+    let mut record = vec![0u8; record_size as usize];
+    let mut bytes_read = 0u32;
+    unsafe {
+        ReadFile(
+            drive_handle,
+            Some(record.as_mut_slice()),
+            Some(&mut bytes_read),
+            None,
+        )
+        .with_context(|| format!("Failed to read MFT record {record_number}"))?;
+    }
+
+    if bytes_read as u64 != record_size {
+        return Err(eyre!(
+            "Failed to read complete MFT record: got {} bytes",
+            bytes_read
+        ));
+    }
+
+    // Verify this is a valid MFT record by checking the signature
+    if &record[0..4] != b"FILE" {
+        return Err(eyre!(
+            "Invalid MFT record signature: expected 'FILE', got '{}'",
+            String::from_utf8_lossy(&record[0..4])
+        ));
+    }
+
+    // The last two bytes of every sector hold a placeholder until the fixups are applied,
+    // and the DATA attribute's runs can straddle one
+    apply_fixups(&mut record)
+        .with_context(|| format!("Failed to apply fixups to MFT record {record_number}"))?;
+
+    Ok(record)
+}
+
+/// Restore the last two bytes of each sector of a raw MFT record from its update sequence
+/// array, as NTFS does on read.
+///
+/// The header's update sequence number is written over the end of every sector so a torn
+/// write shows up as a mismatch; the real bytes are kept in the array after the number.
+pub fn apply_fixups(record: &mut [u8]) -> eyre::Result<()> {
+    if record.len() < 8 {
+        return Err(eyre!("Record of {} bytes has no header", record.len()));
+    }
+    let usa_offset = u16::from_le_bytes([record[4], record[5]]) as usize;
+    let usa_count = u16::from_le_bytes([record[6], record[7]]) as usize;
+    // One entry for the sequence number itself, then one per sector
+    let sectors = usa_count.saturating_sub(1);
+    if sectors == 0 || record.len() % sectors != 0 || usa_offset + usa_count * 2 > record.len() {
+        return Err(eyre!(
+            "Update sequence array of {usa_count} entries at offset {usa_offset} doesn't fit a {}-byte record",
+            record.len()
+        ));
+    }
+    let sector_size = record.len() / sectors;
+    let usn = [record[usa_offset], record[usa_offset + 1]];
+    for sector in 1..=sectors {
+        let end = sector * sector_size - 2;
+        if record[end..end + 2] != usn {
+            return Err(eyre!(
+                "Sector {sector} doesn't end with the update sequence number; the record is torn"
+            ));
+        }
+        let saved = usa_offset + sector * 2;
+        record.copy_within(saved..saved + 2, end);
+    }
+    Ok(())
+}
+
+/// Data run information
+#[derive(Debug)]
+struct DataRun {
+    length: u64,  // Length in clusters
+    cluster: i64, // Cluster offset (can be negative for relative positioning)
+}
+
+/// Parses an MFT record to extract data runs from the DATA attribute (0x80), along with the
+/// attribute's real size in bytes
+fn parse_mft_record_for_data_attribute(record: &[u8]) -> eyre::Result<(Vec<DataRun>, u64)> {
+    parse_mft_record_for_stream(record, "")
+}
+
+/// [`parse_mft_record_for_data_attribute`] for the DATA attribute with the given stream name
+fn parse_mft_record_for_stream(
+    record: &[u8],
+    stream_name: &str,
+) -> eyre::Result<(Vec<DataRun>, u64)> {
+    match parse_mft_record_for_attribute(record, 0x80, stream_name)? {
+        Some(found) => Ok(found),
+        None if stream_name.is_empty() => {
+            Err(eyre!("Could not find DATA attribute (0x80) in MFT record"))
+        }
+        None => Err(eyre!(
+            "Could not find DATA attribute (0x80) of stream {stream_name} in MFT record"
+        )),
+    }
+}
+
+/// The data runs and real size of the non-resident attribute of the given type and name in a
+/// record; `None` when the record has no such attribute or keeps it resident
+fn parse_mft_record_for_attribute(
+    record: &[u8],
+    attribute_type: u32,
+    name: &str,
+) -> eyre::Result<Option<(Vec<DataRun>, u64)>> {
+    // Get the offset to the first attribute (typically at offset 20)
+    let attr_offset = u16::from_le_bytes([record[20], record[21]]) as usize;
+    let mut read_ptr = attr_offset;
+
+    while read_ptr < record.len() {
+        // Read attribute header
+        if read_ptr + 8 > record.len() {
+            break;
+        }
+
+        let attr_type = u32::from_le_bytes([
+            record[read_ptr],
+            record[read_ptr + 1],
+            record[read_ptr + 2],
+            record[read_ptr + 3],
+        ]);
+
+        // Check for end marker
+        if attr_type == 0xffffffff {
+            break;
+        }
+
+        let attr_length = u32::from_le_bytes([
+            record[read_ptr + 4],
+            record[read_ptr + 5],
+            record[read_ptr + 6],
+            record[read_ptr + 7],
+        ]) as usize;
+
+        if attr_length == 0 {
+            break;
+        }
+
+        // Check if this is the attribute with the name asked for
+        if attr_type == attribute_type && attribute_name(record, read_ptr).as_deref() == Some(name)
+        {
+            // Check if it's non-resident (byte at offset 8 should be != 0)
+            if read_ptr + 8 < record.len() && record[read_ptr + 8] != 0 {
+                // Get the data runs offset (at offset 32 from attribute start) and the real
+                // size (at offset 48)
+                if read_ptr + 56 <= record.len() {
+                    let run_offset =
+                        u16::from_le_bytes([record[read_ptr + 32], record[read_ptr + 33]]) as usize;
+                    let mut data_size = [0u8; 8];
+                    data_size.copy_from_slice(&record[read_ptr + 48..read_ptr + 56]);
+                    let data_size = u64::from_le_bytes(data_size);
+
+                    let data_runs_start = read_ptr + run_offset;
+                    let data_runs_end = read_ptr + attr_length;
+
+                    if data_runs_start < data_runs_end && data_runs_end <= record.len() {
+                        let runs = decode_data_runs(&record[data_runs_start..data_runs_end])?;
+                        return Ok(Some((runs, data_size)));
+                    }
+                }
+            }
+        }
+
+        read_ptr += attr_length;
+    }
+
+    Ok(None)
+}
+
+/// The name of the attribute starting at `offset` in a record; empty when unnamed
+fn attribute_name(record: &[u8], offset: usize) -> Option<String> {
+    let length = *record.get(offset + 9)? as usize;
+    let name_offset = offset
+        + u16::from_le_bytes([*record.get(offset + 10)?, *record.get(offset + 11)?]) as usize;
+    let units: Vec<u16> = record
+        .get(name_offset..name_offset + length * 2)?
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    String::from_utf16(&units).ok()
+}
+
+/// Decodes NTFS data runs
+fn decode_data_runs(data_runs: &[u8]) -> eyre::Result<Vec<DataRun>> {
+    let mut runs = Vec::new();
+    let mut decode_pos = 0;
+
+    while decode_pos < data_runs.len() {
+        let header = data_runs[decode_pos];
+
+        // End of data runs
+        if header == 0 {
+            break;
+        }
+
+        let offset_bytes = (header & 0xf0) >> 4;
+        let length_bytes = header & 0x0f;
+
+        if offset_bytes == 0 || length_bytes == 0 {
+            break;
+        }
+
+        decode_pos += 1;
+
+        // Read length (little-endian)
+        if decode_pos + length_bytes as usize > data_runs.len() {
+            break;
+        }
+
+        let mut length = 0u64;
+        for i in 0..length_bytes {
+            length |= (data_runs[decode_pos + i as usize] as u64) << (i * 8);
+        }
+        decode_pos += length_bytes as usize;
+
+        // Read offset (little-endian, signed)
+        if decode_pos + offset_bytes as usize > data_runs.len() {
+            break;
+        }
+
+        let mut cluster = 0i64;
+        for i in 0..offset_bytes {
+            cluster |= (data_runs[decode_pos + i as usize] as i64) << (i * 8);
+        }
+
+        // Handle sign extension for the offset
+        if offset_bytes > 0 {
+            let sign_bit = 1i64 << (offset_bytes * 8 - 1);
+            if cluster & sign_bit != 0 {
+                cluster |= !((1i64 << (offset_bytes * 8)) - 1);
+            }
+        }
+
+        decode_pos += offset_bytes as usize;
+
+        runs.push(DataRun { length, cluster });
+    }
+
+    Ok(runs)
+}
+
+/// Reads the complete MFT using the parsed data runs
+fn read_mft_using_data_runs(
+    drive_handle: HANDLE,
+    data_runs: &[DataRun],
+    bytes_per_cluster: u64,
+    subscriber: &mut dyn ProgressSubscriber,
+) -> eyre::Result<Vec<u8>> {
+    let mut mft_data = Vec::new();
+    let mut current_cluster = 0i64;
+    let total_bytes: u64 = data_runs
+        .iter()
+        .map(|run| run.length * bytes_per_cluster)
+        .sum();
+
+    info!("Found {} data runs for MFT", data_runs.len());
+
+    for (i, run) in data_runs.iter().enumerate() {
+        // Calculate absolute cluster position
+        current_cluster += run.cluster;
+
+        let byte_offset = current_cluster as u64 * bytes_per_cluster;
+        let byte_length = run.length * bytes_per_cluster;
+
+        info!(
+            "Data run {}: cluster {} (offset {}), length {} clusters ({})",
+            i + 1,
+            current_cluster,
+            humansize::format_size(byte_offset, humansize::DECIMAL),
+            run.length,
+            humansize::format_size(byte_length, humansize::DECIMAL)
+        );
+
+        let _extent = info_span!(
+            "read_extent",
+            run = i + 1,
+            offset = byte_offset,
+            length = byte_length
+        )
+        .entered();
+        // Seek to the run location
+        unsafe {
+            SetFilePointerEx(drive_handle, byte_offset as i64, None, FILE_BEGIN).with_context(
+                || {
+                    format!(
+                        "Failed to seek to data run {} at offset {}",
+                        i + 1,
+                        byte_offset
+                    )
+                },
+            )?;
+        }
+
+        // Read the run data
+        let mut run_data = vec![0u8; byte_length as usize];
+        let mut total_read = 0;
+        let mut offset = 0;
+
+        while offset < byte_length {
+            let remaining = byte_length - offset;
+            let chunk_size = remaining.min(1024 * 1024) as usize; // Read in 1MB chunks
+
+            let mut bytes_read = 0u32;
+            unsafe {
+                ReadFile(
+                    drive_handle,
+                    Some(&mut run_data[offset as usize..offset as usize + chunk_size]),
+                    Some(&mut bytes_read),
+                    None,
+                )
+                .with_context(|| {
+                    format!("Failed to read data run {} at offset {}", i + 1, offset)
+                })?;
+            }
+
+            if bytes_read == 0 {
+                break;
+            }
+
+            offset += bytes_read as u64;
+            total_read += bytes_read as u64;
+            subscriber.on_event(ProgressEvent::MftRead {
+                read: mft_data.len() as u64 + total_read,
+                total: total_bytes,
+            });
+        }
+
+        run_data.truncate(total_read as usize);
+        mft_data.extend_from_slice(&run_data);
+
+        info!(
+            "Read {} from data run {}",
+            humansize::format_size(total_read, humansize::DECIMAL),
+            i + 1
+        );
+    }
+
+    info!(
+        "Successfully read complete MFT: {}",
+        humansize::format_size(mft_data.len(), humansize::DECIMAL)
+    );
+
+    Ok(mft_data)
+}
+
+/// Read the clusters of `data_runs` in order, stopping once `limit` bytes are in, in chunks
+/// of 1 MiB so a long run is never read in one go. A run reaching past the end of the volume,
+/// as a corrupt record's can, is an error rather than a huge allocation.
+fn read_runs(
+    drive_handle: HANDLE,
+    data_runs: &[DataRun],
+    bytes_per_cluster: u64,
+    volume_size: u64,
+    limit: u64,
+) -> eyre::Result<Vec<u8>> {
+    const CHUNK_SIZE: u64 = 1024 * 1024;
+
+    let mut data = Vec::new();
+    let mut current_cluster = 0i64;
+    for run in data_runs {
+        let wanted = limit.saturating_sub(data.len() as u64);
+        if wanted == 0 {
+            break;
+        }
+        current_cluster += run.cluster;
+        let byte_offset = u64::try_from(current_cluster)
+            .ok()
+            .and_then(|cluster| cluster.checked_mul(bytes_per_cluster));
+        let byte_length = run.length.checked_mul(bytes_per_cluster);
+        let (Some(byte_offset), Some(byte_length)) = (byte_offset, byte_length) else {
+            return Err(eyre!(
+                "Data run at cluster {current_cluster} is out of range"
+            ));
+        };
+        if byte_offset
+            .checked_add(byte_length)
+            .is_none_or(|end| end > volume_size)
+        {
+            return Err(eyre!(
+                "Data run of {} clusters at cluster {current_cluster} runs past the end of the volume",
+                run.length
+            ));
+        }
+        // Volume reads must stay whole clusters, so the tail is trimmed by the caller
+        let byte_length = byte_length.min(wanted.div_ceil(bytes_per_cluster) * bytes_per_cluster);
+        unsafe {
+            SetFilePointerEx(drive_handle, byte_offset as i64, None, FILE_BEGIN)
+                .with_context(|| format!("Failed to seek to offset {byte_offset}"))?;
+        }
+        let mut offset = 0;
+        while offset < byte_length {
+            let start = data.len();
+            data.resize(start + (byte_length - offset).min(CHUNK_SIZE) as usize, 0);
+            let mut bytes_read = 0u32;
+            unsafe {
+                ReadFile(
+                    drive_handle,
+                    Some(&mut data[start..]),
+                    Some(&mut bytes_read),
+                    None,
+                )
+                .with_context(|| format!("Failed to read at offset {}", byte_offset + offset))?;
+            }
+            data.truncate(start + bytes_read as usize);
+            if bytes_read == 0 {
+                break;
+            }
+            offset += bytes_read as u64;
+        }
+    }
+    Ok(data)
+}
+
+/// Writes the MFT data to the specified file
+pub fn write_mft_to_file(mft_data: &[u8], output_path: &Path) -> eyre::Result<()> {
+    let mut file = if output_path.exists() {
+        // Callers decide whether replacing an existing dump is fine
+        OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .open(output_path)
+            .with_context(|| {
+                format!("Failed to open file for writing: {}", output_path.display())
+            })?
+    } else {
+        // Create new file
+        File::create(output_path)
+            .with_context(|| format!("Failed to create file: {}", output_path.display()))?
+    };
+
+    file.write_all(mft_data).with_context(|| {
+        format!(
+            "Failed to write MFT data to file: {}",
+            output_path.display()
+        )
+    })?;
+
+    file.flush()
+        .with_context(|| format!("Failed to flush file: {}", output_path.display()))?;
+
+    Ok(())
+}
+
+/// Enables backup and security privileges for the current process
+pub fn enable_backup_privileges() -> eyre::Result<()> {
+    use std::mem::size_of;
+
+    unsafe {
+        // Get current process token
+        let mut token = windows::Win32::Foundation::HANDLE::default();
+        OpenProcessToken(
+            GetCurrentProcess(),
+            TOKEN_ADJUST_PRIVILEGES | TOKEN_QUERY,
+            &mut token,
+        )
+        .with_context(|| "Failed to open process token")?;
+
+        // Enable multiple privileges that might be needed
+        let privileges_to_enable = [SE_BACKUP_NAME, SE_RESTORE_NAME, SE_SECURITY_NAME];
+
+        for privilege_name in &privileges_to_enable {
+            // Look up the privilege LUID
+            let mut luid = LUID::default();
+            if LookupPrivilegeValueW(None, *privilege_name, &mut luid).is_ok() {
+                // Set up the privilege structure
+                let privileges = TOKEN_PRIVILEGES {
+                    PrivilegeCount: 1,
+                    Privileges: [windows::Win32::Security::LUID_AND_ATTRIBUTES {
+                        Luid: luid,
+                        Attributes: SE_PRIVILEGE_ENABLED,
+                    }],
+                };
+
+                // Adjust token privileges
+                let _ = AdjustTokenPrivileges(
+                    token,
+                    false,
+                    Some(&privileges),
+                    size_of::<TOKEN_PRIVILEGES>() as u32,
+                    None,
+                    None,
+                );
+            }
+        }
+
+        // Close token handle
+        windows::Win32::Foundation::CloseHandle(token)
+            .with_context(|| "Failed to close token handle")?;
+
+        info!("Successfully enabled backup privileges");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::apply_fixups;
+    use super::record_size_from_boot;
+
+    /// A 1 KiB record over two 512-byte sectors, with the array at offset 48
+    fn record_with_fixups(usn: [u8; 2], saved: [[u8; 2]; 2]) -> Vec<u8> {
+        let mut record = vec![0u8; 1024];
+        record[0..4].copy_from_slice(b"FILE");
+        record[4..6].copy_from_slice(&48u16.to_le_bytes());
+        record[6..8].copy_from_slice(&3u16.to_le_bytes());
+        record[48..50].copy_from_slice(&usn);
+        record[50..52].copy_from_slice(&saved[0]);
+        record[52..54].copy_from_slice(&saved[1]);
+        record[510..512].copy_from_slice(&usn);
+        record[1022..1024].copy_from_slice(&usn);
+        record
+    }
+
+    #[test]
+    fn restores_sector_ends() {
+        let mut record = record_with_fixups([7, 0], [[0xAA, 0xBB], [0xCC, 0xDD]]);
+        apply_fixups(&mut record).unwrap();
+        assert_eq!(record[510..512], [0xAA, 0xBB]);
+        assert_eq!(record[1022..1024], [0xCC, 0xDD]);
+    }
+
+    #[test]
+    fn rejects_torn_records() {
+        let mut record = record_with_fixups([7, 0], [[0xAA, 0xBB], [0xCC, 0xDD]]);
+        record[1022] = 8;
+        assert!(apply_fixups(&mut record).is_err());
+    }
+
+    #[test]
+    fn decodes_record_sizes() {
+        assert_eq!(record_size_from_boot(0xF6u8 as i8, 4096), 1024);
+        assert_eq!(record_size_from_boot(0xF4u8 as i8, 65536), 4096);
+        assert_eq!(record_size_from_boot(1, 4096), 4096);
+    }
+}
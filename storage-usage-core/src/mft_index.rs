@@ -0,0 +1,1378 @@
+use crate::dir_index::index_allocation_path_for;
+use crate::file_flags::FileFlag;
+use crate::file_flags::FileFlags;
+use crate::mapped_index::MappedIndex;
+use crate::mapped_index::MappedWriter;
+use crate::mapped_index::write_mapped;
+use crate::mft_size::DataSize;
+use crate::mft_size::data_size;
+use crate::mft_size::record_data_size;
+use crate::path_resolver::ParentRef;
+use crate::path_resolver::PathResolver;
+use crate::progress::ErrorSeverity;
+use crate::progress::MftError;
+use crate::progress::NoProgress;
+use crate::progress::ProgressEvent;
+use crate::progress::ProgressSubscriber;
+use crate::reparse::ReparsePoint;
+use crate::reparse::TAG_SYMLINK;
+use crate::reparse::TAG_UNKNOWN;
+use crate::reparse::reparse_point;
+use crate::search_filter::SearchQuery;
+use crate::search_filter::fold_name;
+use crate::search_filter::same_name;
+use crate::search_filter::split_drive;
+use crate::usn_journal::UsnJournal;
+use crate::usn_journal::UsnRecord;
+use chrono::DateTime;
+use chrono::Utc;
+use eyre::Context;
+use mft::MftEntry;
+use mft::MftParser;
+use mft::attribute::MftAttributeContent;
+use mft::attribute::MftAttributeType;
+use mft::attribute::x30::FileNamespace;
+use mft::entry::EntryFlags;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::Read;
+use std::io::Seek;
+use std::io::Write;
+use std::os::windows::fs::MetadataExt;
+use std::path::Path;
+use std::path::PathBuf;
+use tracing::debug;
+use tracing::info;
+use tracing::info_span;
+use tracing::warn;
+
+/// Magic bytes at the start of every index file, ending in the format version. The layout is
+/// documented in [`crate::mapped_index`].
+pub(crate) const INDEX_MAGIC: &[u8; 8] = b"SUIDX\0\0\x0a";
+
+/// Magic bytes of the streamed format before it, which is still read
+const STREAMED_INDEX_MAGIC: &[u8; 8] = b"SUIDX\0\0\x09";
+
+/// Bytes of the smallest entry in the streamed format, one with an empty name and no target
+const MIN_STREAMED_ENTRY_LEN: usize = 74;
+
+/// Extension appended to the MFT dump path to get its index path (e.g. `C.mft` -> `C.mft.idx`)
+pub const INDEX_EXTENSION: &str = "idx";
+
+/// Sentinel used to encode a missing timestamp or parent reference
+pub(crate) const NONE_I64: i64 = i64::MIN;
+
+/// Record number treated as the volume root when resolving paths
+pub(crate) const ROOT_RECORD: u64 = 5;
+
+/// Records per `parse_batch` span in `--trace-out` traces
+const PARSE_BATCH_RECORDS: usize = 65536;
+
+/// Smallest buffer a memory-limited build sorts paths in, whatever its directories leave
+const MIN_SORT_BUFFER: usize = 16 * 1024 * 1024;
+
+/// Records below this are the volume's own metadata files (`$MFT`, `$Bitmap`, `$Extend`, ...)
+const FIRST_NON_METADATA_RECORD: u64 = 16;
+
+/// Metadata files kept in records past the reserved ones, mostly below `$Extend`
+const METADATA_NAMES: &[&str] = &[
+    "$Quota",
+    "$ObjId",
+    "$Reparse",
+    "$UsnJrnl",
+    "$RmMetadata",
+    "$Repair",
+    "$Deleted",
+    "$Txf",
+    "$TxfLog",
+    "$Tops",
+];
+
+/// `FILE_ATTRIBUTE_REPARSE_POINT`; junctions and symlinks are listed but not followed
+const ATTRIBUTE_REPARSE_POINT: u32 = 0x0400;
+
+/// One name (hard link) of an MFT record as stored in the index
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct IndexEntry {
+    pub record_number: u64,
+    pub hard_link_count: u16,
+    pub parent_ref: Option<u64>,
+    pub name: String,
+    pub size: u64,
+    pub allocated_size: u64,
+    pub created: Option<DateTime<Utc>>,
+    pub modified: Option<DateTime<Utc>>,
+    pub accessed: Option<DateTime<Utc>>,
+    pub flags: FileFlags,
+    /// Set for symlinks, junctions, cloud placeholders and other reparse points
+    pub reparse: Option<ReparsePoint>,
+}
+
+/// The files below one directory, as [`MftIndex::directory_usage`] adds them up
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct DirectoryUsage {
+    pub path: String,
+    /// Levels below the root, 1 for `C:\Users`
+    pub depth: usize,
+    pub files: u64,
+    pub bytes: u64,
+    pub allocated_bytes: u64,
+}
+
+/// Queryable summary of an MFT dump, small enough to load in milliseconds
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MftIndex {
+    pub entries: Vec<IndexEntry>,
+    /// Records the parser couldn't read, so they are missing from `entries`
+    pub parse_errors: u64,
+    /// How far into the volume's USN journal the entries are up to date; `None` for indexes
+    /// built from a dump, which [`MftIndex::update_from_journal`] has to rebuild once
+    pub journal: Option<JournalPosition>,
+}
+
+/// A point in a volume's USN change journal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct JournalPosition {
+    pub journal_id: u64,
+    /// The first change not yet applied
+    pub next_usn: i64,
+}
+
+/// What [`MftIndex::update_from_journal`] did
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IndexUpdate {
+    /// Records re-read because the journal listed a change to them
+    pub changed_records: usize,
+    /// Set when the whole live MFT was read instead, as no changes could be applied
+    pub rebuilt: bool,
+}
+
+/// The counts at the start of an index file, readable without loading its entries
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct IndexSummary {
+    pub entries: u64,
+    pub parse_errors: u64,
+    pub journal: Option<JournalPosition>,
+}
+
+/// Path of the index that belongs to the given MFT dump
+pub fn index_path_for(mft_file: &Path) -> PathBuf {
+    let mut os = mft_file.as_os_str().to_os_string();
+    os.push(".");
+    os.push(INDEX_EXTENSION);
+    PathBuf::from(os)
+}
+
+/// An index is fresh when it exists and was written after the dump was last modified
+pub fn is_index_fresh(mft_file: &Path) -> bool {
+    let index_file = index_path_for(mft_file);
+    let (Ok(dump_meta), Ok(index_meta)) =
+        (std::fs::metadata(mft_file), std::fs::metadata(&index_file))
+    else {
+        return false;
+    };
+    match (dump_meta.modified(), index_meta.modified()) {
+        (Ok(dump_time), Ok(index_time)) => index_time >= dump_time,
+        _ => false,
+    }
+}
+
+/// Map the index for a dump if one exists, is fresh and is in the mappable format, to look
+/// paths up without loading every entry
+pub fn open_fresh_index(mft_file: &Path) -> Option<MappedIndex> {
+    if !is_index_fresh(mft_file) {
+        return None;
+    }
+    let index_file = index_path_for(mft_file);
+    match MappedIndex::open(&index_file) {
+        Ok(index) => {
+            debug!("Mapped index {}", index_file.display());
+            Some(index)
+        }
+        Err(e) => {
+            debug!("Not mapping index {}: {e:#}", index_file.display());
+            None
+        }
+    }
+}
+
+/// Load the index for a dump if one exists and is fresh
+pub fn load_fresh_index(mft_file: &Path) -> Option<MftIndex> {
+    if !is_index_fresh(mft_file) {
+        return None;
+    }
+    let index_file = index_path_for(mft_file);
+    match MftIndex::read_from_file(&index_file) {
+        Ok(index) => {
+            debug!("Using index {}", index_file.display());
+            Some(index)
+        }
+        Err(e) => {
+            tracing::warn!("Ignoring unreadable index {}: {e:#}", index_file.display());
+            None
+        }
+    }
+}
+
+impl MftIndex {
+    /// Index an MFT dump, loading its cached index instead when that is fresh
+    pub fn from_dump(mft_file: &Path) -> eyre::Result<Self> {
+        Self::from_dump_with_progress(mft_file, &mut NoProgress)
+    }
+
+    /// [`MftIndex::from_dump`], sending parse events to `subscriber` when the dump has to be
+    /// parsed
+    pub fn from_dump_with_progress(
+        mft_file: &Path,
+        subscriber: &mut dyn ProgressSubscriber,
+    ) -> eyre::Result<Self> {
+        match load_fresh_index(mft_file) {
+            Some(index) => Ok(index),
+            None => Self::build_from_mft_with_progress(mft_file, subscriber),
+        }
+    }
+
+    /// Read the MFT of a live volume and index it in memory. The caller must already be
+    /// elevated.
+    pub fn from_volume(drive_letter: char) -> eyre::Result<Self> {
+        Self::build_from_live(drive_letter)
+    }
+
+    /// Parse an MFT dump and collect every name of every record (8.3 short names excluded)
+    pub fn build_from_mft(mft_file: &Path) -> eyre::Result<Self> {
+        Self::build_from_mft_with_progress(mft_file, &mut NoProgress)
+    }
+
+    /// [`MftIndex::build_from_mft`], sending parse events to `subscriber`
+    pub fn build_from_mft_with_progress(
+        mft_file: &Path,
+        subscriber: &mut dyn ProgressSubscriber,
+    ) -> eyre::Result<Self> {
+        let mut parser = MftParser::from_path(mft_file)
+            .map_err(|e| eyre::eyre!("Failed to open MFT {}: {}", mft_file.display(), e))?;
+        Ok(Self::build_from_parser_with_progress(
+            &mut parser,
+            subscriber,
+        ))
+    }
+
+    /// Read the MFT of a live volume (requires elevation) and index it in memory
+    pub fn build_from_live(drive_letter: char) -> eyre::Result<Self> {
+        let reader = crate::mft_dump::open_live_mft_reader(drive_letter)?;
+        let size = reader.total_size();
+        let mut parser = MftParser::from_read_seek(reader, Some(size))
+            .map_err(|e| eyre::eyre!("Failed to parse live MFT of {drive_letter}: {e}"))?;
+        Ok(Self::build_from_parser(&mut parser))
+    }
+
+    /// Walk the volume's directory tree without raw volume access, for `--no-elevate`.
+    ///
+    /// Record numbers are made up (so only paths line up with a real index), hard links show
+    /// up once per name, allocated sizes are the logical sizes, junctions are labelled as
+    /// symlinks, and directories that can't be listed are skipped with a warning.
+    pub fn build_from_walk(drive_letter: char) -> Self {
+        let mut entries = Vec::new();
+        let mut unreadable = 0usize;
+        let mut next_record = ROOT_RECORD + 1;
+        let mut pending = vec![(PathBuf::from(format!("{drive_letter}:\\")), ROOT_RECORD)];
+        while let Some((dir, dir_record)) = pending.pop() {
+            let Ok(read_dir) = std::fs::read_dir(&dir) else {
+                unreadable += 1;
+                continue;
+            };
+            for child in read_dir.flatten() {
+                let Ok(metadata) = child.metadata() else {
+                    continue;
+                };
+                let record_number = next_record;
+                next_record += 1;
+                let attributes = metadata.file_attributes();
+                let is_reparse_point = attributes & ATTRIBUTE_REPARSE_POINT != 0;
+                if metadata.is_dir() && !is_reparse_point {
+                    pending.push((child.path(), record_number));
+                }
+                // std only tells name surrogates like symlinks and junctions from other tags
+                let reparse = is_reparse_point.then(|| ReparsePoint {
+                    tag: if metadata.is_symlink() {
+                        TAG_SYMLINK
+                    } else {
+                        TAG_UNKNOWN
+                    },
+                    target: std::fs::read_link(child.path())
+                        .ok()
+                        .map(|target| target.to_string_lossy().into_owned()),
+                });
+                entries.push(IndexEntry {
+                    record_number,
+                    hard_link_count: 1,
+                    parent_ref: Some(dir_record),
+                    name: child.file_name().to_string_lossy().into_owned(),
+                    size: metadata.len(),
+                    allocated_size: metadata.len(),
+                    created: metadata.created().ok().map(DateTime::<Utc>::from),
+                    modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+                    accessed: metadata.accessed().ok().map(DateTime::<Utc>::from),
+                    flags: FileFlags::new(attributes, metadata.is_dir()),
+                    reparse,
+                });
+            }
+        }
+        if unreadable > 0 {
+            warn!("Skipped {unreadable} directories on {drive_letter}: that couldn't be listed");
+        }
+        Self {
+            entries,
+            parse_errors: 0,
+            journal: None,
+        }
+    }
+
+    pub fn build_from_parser<T: Read + Seek>(parser: &mut MftParser<T>) -> Self {
+        Self::build_from_parser_with_progress(parser, &mut NoProgress)
+    }
+
+    /// [`MftIndex::build_from_parser`], sending [`ProgressEvent::RecordSize`] first, then
+    /// [`ProgressEvent::RecordsParsed`] per record and [`ProgressEvent::Problem`] per record
+    /// that fails to parse
+    pub fn build_from_parser_with_progress<T: Read + Seek>(
+        parser: &mut MftParser<T>,
+        subscriber: &mut dyn ProgressSubscriber,
+    ) -> Self {
+        let total = parser.get_entry_count();
+        subscriber.on_event(ProgressEvent::RecordSize {
+            bytes: parser.entry_size as u64,
+        });
+        let mut entries = Vec::new();
+        let mut parse_errors = 0;
+        // Base records with an $ATTRIBUTE_LIST, and where their entries start
+        let mut listed: HashMap<u64, (BaseRecord, usize)> = HashMap::new();
+        let mut continuations: HashMap<u64, Continuation> = HashMap::new();
+        let mut batch = info_span!("parse_batch", first_record = 0).entered();
+        for (i, entry) in parser.iter_entries().enumerate() {
+            subscriber.on_event(ProgressEvent::RecordsParsed {
+                parsed: i as u64 + 1,
+                total,
+            });
+            if i > 0 && i % PARSE_BATCH_RECORDS == 0 {
+                drop(batch);
+                batch = info_span!("parse_batch", first_record = i).entered();
+            }
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    parse_errors += 1;
+                    subscriber.on_event(ProgressEvent::Problem(MftError {
+                        severity: ErrorSeverity::Error,
+                        record_number: Some(i as u64),
+                        message: format!("Error processing entry: {e}"),
+                    }));
+                    continue;
+                }
+            };
+            let base_record = entry.header.base_reference.entry;
+            if base_record != 0 {
+                // An extension record only holds attributes its base record's
+                // $ATTRIBUTE_LIST moved out; they're folded into the base once both are seen
+                let continuation = continuations.entry(base_record).or_default();
+                continuation.names.extend(indexed_names(&entry));
+                if continuation.data.is_none() {
+                    continuation.data = data_size(&entry);
+                }
+                continue;
+            }
+            let record_number = entry.header.record_number;
+            let (base, has_attribute_list) = BaseRecord::of(&entry);
+            if has_attribute_list {
+                listed.insert(record_number, (base.clone(), entries.len()));
+            }
+            for name in indexed_names(&entry) {
+                entries.push(base.entry(record_number, name));
+            }
+        }
+        drop(batch);
+
+        // Records are parsed in number order but extensions can come before their base, so
+        // the two are only joined here
+        for (base_record, continuation) in continuations {
+            let Some((base, first)) = listed.get(&base_record) else {
+                continue;
+            };
+            if base.data.is_none()
+                && let Some(data) = continuation.data
+            {
+                for entry in entries[*first..]
+                    .iter_mut()
+                    .take_while(|entry| entry.record_number == base_record)
+                {
+                    entry.size = data.logical;
+                    entry.allocated_size = data.allocated;
+                }
+            }
+            let base = BaseRecord {
+                data: base.data.or(continuation.data),
+                ..base.clone()
+            };
+            for name in continuation.names {
+                entries.push(base.entry(base_record, name));
+            }
+        }
+        Self {
+            entries,
+            parse_errors,
+            journal: None,
+        }
+    }
+
+    /// Bring the index up to date with a live volume by re-reading only the records its USN
+    /// journal lists changes to since [`MftIndex::journal`]. The whole live MFT is read instead
+    /// when the index has no position yet, or the journal was recreated or has overwritten
+    /// changes since. The caller must already be elevated.
+    pub fn update_from_journal(&mut self, drive_letter: char) -> eyre::Result<IndexUpdate> {
+        let resumed = match self.journal {
+            None => Err("it has no journal position yet"),
+            Some(position) => {
+                let journal = UsnJournal::open_at(drive_letter, Some(position.next_usn))?;
+                if journal.journal_id() != position.journal_id {
+                    Err("the journal was recreated since the last update")
+                } else if journal.first_usn()? > position.next_usn {
+                    Err("the journal has overwritten changes since the last update")
+                } else {
+                    Ok(journal)
+                }
+            }
+        };
+        let mut journal = match resumed {
+            Ok(journal) => journal,
+            Err(reason) => return self.rebuild_from_live(drive_letter, reason),
+        };
+        let mut changed = BTreeSet::new();
+        loop {
+            let records = journal.read(false)?;
+            if records.is_empty() {
+                break;
+            }
+            changed.extend(records.iter().map(UsnRecord::record_number));
+        }
+        if !changed.is_empty() {
+            let reader = crate::mft_dump::open_live_mft_reader(drive_letter)?;
+            let size = reader.total_size();
+            let mut parser = MftParser::from_read_seek(reader, Some(size))
+                .map_err(|e| eyre::eyre!("Failed to parse live MFT of {drive_letter}: {e}"))?;
+            self.entries
+                .retain(|entry| !changed.contains(&entry.record_number));
+            for &record_number in &changed {
+                let entry = match parser.get_entry(record_number) {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        warn!("Skipping changed record {record_number} of {drive_letter}: {e}");
+                        continue;
+                    }
+                };
+                // A record reused as an extension of another file has no entries of its own
+                self.entries.extend(
+                    record_entries(&mut parser, &entry)
+                        .into_iter()
+                        .map(|(entry, _)| entry),
+                );
+            }
+            self.entries.sort_by_key(|entry| entry.record_number);
+        }
+        self.journal = Some(JournalPosition {
+            journal_id: journal.journal_id(),
+            next_usn: journal.next_usn(),
+        });
+        Ok(IndexUpdate {
+            changed_records: changed.len(),
+            rebuilt: false,
+        })
+    }
+
+    /// Replace the index with one of the whole live MFT, positioned where the journal was
+    /// before reading it
+    fn rebuild_from_live(&mut self, drive_letter: char, reason: &str) -> eyre::Result<IndexUpdate> {
+        info!("Reading the whole MFT of {drive_letter}:, as {reason}");
+        // Changes made while the MFT is read get applied again by the next update, which
+        // re-reading records makes harmless
+        let journal = UsnJournal::open(drive_letter)?;
+        *self = Self::build_from_live(drive_letter)?;
+        self.journal = Some(JournalPosition {
+            journal_id: journal.journal_id(),
+            next_usn: journal.next_usn(),
+        });
+        Ok(IndexUpdate {
+            changed_records: 0,
+            rebuilt: true,
+        })
+    }
+
+    /// Number of entries, one per name
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Every entry, one per name, in record order
+    pub fn iter(&self) -> std::slice::Iter<'_, IndexEntry> {
+        self.entries.iter()
+    }
+
+    /// Every name of a record; more than one when the file is hard linked
+    pub fn record(&self, record_number: u64) -> impl Iterator<Item = &IndexEntry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.record_number == record_number)
+    }
+
+    /// Full path of one entry, prefixed with the drive letter. Reads every directory, so use
+    /// [`MftIndex::resolve_paths`] for more than a few entries.
+    pub fn path_of(&self, drive_letter: char, entry: &IndexEntry) -> String {
+        self.path_resolver(PathResolver::new(drive_letter))
+            .resolve_index_entry(entry)
+            .path
+    }
+
+    /// Full path of every name of a record, one per hard link
+    pub fn paths_of_record(&self, drive_letter: char, record_number: u64) -> Vec<String> {
+        let mut resolver = self.path_resolver(PathResolver::new(drive_letter));
+        self.record(record_number)
+            .map(|entry| resolver.resolve_index_entry(entry).path)
+            .collect()
+    }
+
+    /// Entries at a full path, ignoring case as NTFS does and how accents are composed. The
+    /// drive letter is optional, so `C:\Windows\notepad.exe` and `\Windows\notepad.exe` find the
+    /// same entries.
+    pub fn lookup_path(&self, path: &str) -> Vec<&IndexEntry> {
+        let relative = split_drive(path).1;
+        let components: Vec<&str> = relative.split('\\').filter(|c| !c.is_empty()).collect();
+        let Some((name, parents)) = components.split_last() else {
+            return Vec::new();
+        };
+        let by_record = self.by_record();
+        self.entries
+            .iter()
+            .filter(|entry| {
+                if !same_name(&entry.name, name) {
+                    return false;
+                }
+                let mut current = entry.parent_ref;
+                let parents_match = parents.iter().rev().all(|wanted| {
+                    let parent = current
+                        .filter(|&record| record != ROOT_RECORD)
+                        .and_then(|record| by_record.get(&record));
+                    match parent {
+                        Some(parent) if same_name(&parent.name, wanted) => {
+                            current = parent.parent_ref;
+                            true
+                        }
+                        _ => false,
+                    }
+                });
+                parents_match && current.is_none_or(|record| record == ROOT_RECORD)
+            })
+            .collect()
+    }
+
+    /// Entries passing every filter of `query` whose path contains each word of its fuzzy term,
+    /// ignoring case and how accents are composed, with their full paths. Ranking fuzzy matches
+    /// is left to the caller.
+    pub fn query(&self, drive_letter: char, query: &SearchQuery) -> Vec<(String, &IndexEntry)> {
+        let words: Vec<String> = query.fuzzy.split_whitespace().map(fold_name).collect();
+        self.resolve_paths(drive_letter)
+            .into_iter()
+            .filter(|(path, entry)| {
+                if !query.matches(path, entry.size, entry.modified) {
+                    return false;
+                }
+                let path = fold_name(path);
+                words.iter().all(|word| path.contains(word.as_str()))
+            })
+            .collect()
+    }
+
+    /// Resolve the full path of every entry, prefixed with the drive letter. A name whose
+    /// parents run out or loop before the root goes under [`crate::path_resolver::ORPHANED`].
+    pub fn resolve_paths(&self, drive_letter: char) -> Vec<(String, &IndexEntry)> {
+        let _span =
+            info_span!("resolve_paths", drive = %drive_letter, entries = self.entries.len())
+                .entered();
+        let mut resolver = self.path_resolver(PathResolver::new(drive_letter));
+        self.entries
+            .iter()
+            .map(|entry| (resolver.resolve_index_entry(entry).path, entry))
+            .collect()
+    }
+
+    /// A resolver that's had its first pass over the directories of the index
+    pub(crate) fn path_resolver(&self, mut resolver: PathResolver) -> PathResolver {
+        resolver.add_index_directories(&self.entries);
+        resolver
+    }
+
+    /// Add up the files below every directory up to `depth` levels under the root, like `du`.
+    /// A hard-linked file counts once per directory however many of its names are below it;
+    /// directories themselves and deleted files add nothing. Sorted by allocated bytes, largest
+    /// first.
+    pub fn directory_usage(&self, drive_letter: char, depth: usize) -> Vec<DirectoryUsage> {
+        let by_record = self.by_record();
+        let mut resolver = self.path_resolver(PathResolver::new(drive_letter));
+        let mut by_directory: HashMap<u64, DirectoryUsage> = HashMap::new();
+        let mut linked: HashSet<(u64, u64)> = HashSet::new();
+        let mut ancestors: Vec<&IndexEntry> = Vec::new();
+        for entry in &self.entries {
+            if entry.flags.contains(FileFlag::Directory) || entry.flags.contains(FileFlag::Deleted)
+            {
+                continue;
+            }
+            // From the file's own directory up to the one just below the root
+            ancestors.clear();
+            let mut current = entry.parent_ref;
+            while let Some(parent) = current.filter(|parent| *parent != ROOT_RECORD)
+                && ancestors.len() <= 4096
+                && let Some(directory) = by_record.get(&parent)
+            {
+                ancestors.push(directory);
+                current = directory.parent_ref;
+            }
+            let top = ancestors.len().saturating_sub(depth);
+            for (level, directory) in ancestors[top..].iter().rev().enumerate() {
+                if entry.hard_link_count > 1
+                    && !linked.insert((entry.record_number, directory.record_number))
+                {
+                    continue;
+                }
+                let usage = by_directory
+                    .entry(directory.record_number)
+                    .or_insert_with(|| DirectoryUsage {
+                        path: resolver.resolve_index_entry(directory).path,
+                        depth: level + 1,
+                        ..DirectoryUsage::default()
+                    });
+                usage.files += 1;
+                usage.bytes += entry.size;
+                usage.allocated_bytes += entry.allocated_size;
+            }
+        }
+        let mut usage: Vec<DirectoryUsage> = by_directory.into_values().collect();
+        usage.sort_by(|a, b| {
+            b.allocated_bytes
+                .cmp(&a.allocated_bytes)
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        usage
+    }
+
+    /// The entry of each record, for walking up to parents
+    pub(crate) fn by_record(&self) -> HashMap<u64, &IndexEntry> {
+        // Directories can't be hard linked, so the first name of a record is its only name
+        let mut by_record: HashMap<u64, &IndexEntry> = HashMap::with_capacity(self.entries.len());
+        for entry in &self.entries {
+            by_record.entry(entry.record_number).or_insert(entry);
+        }
+        by_record
+    }
+
+    pub fn write_to_file(&self, path: &Path) -> eyre::Result<()> {
+        let file = File::create(path).with_context(|| format!("creating {}", path.display()))?;
+        self.write_to(BufWriter::new(file))
+            .with_context(|| format!("writing {}", path.display()))
+    }
+
+    /// Write the index in the format [`MftIndex::read_from`] reads, which
+    /// [`MappedIndex`] can also search in place
+    pub fn write_to(&self, out: impl Write) -> eyre::Result<()> {
+        self.write_to_with_limit(out, None)
+    }
+
+    /// [`MftIndex::write_to`], sorting paths in a buffer of about `sort_buffer` bytes. Past that,
+    /// sorted runs of paths are spilled to the temp directory and merged back as the index is
+    /// written. Only the sort is bounded; the index itself is already in memory.
+    pub fn write_to_with_limit(
+        &self,
+        out: impl Write,
+        sort_buffer: Option<usize>,
+    ) -> eyre::Result<()> {
+        write_mapped(self, sort_buffer, out)
+    }
+
+    pub fn read_from_file(path: &Path) -> eyre::Result<Self> {
+        let file = File::open(path).with_context(|| format!("opening {}", path.display()))?;
+        Self::read_from(BufReader::new(file)).with_context(|| format!("reading {}", path.display()))
+    }
+
+    /// Read an index written by [`MftIndex::write_to`], or by a version that streamed its
+    /// entries
+    pub fn read_from(mut input: impl Read) -> eyre::Result<Self> {
+        let mut bytes = Vec::new();
+        input.read_to_end(&mut bytes)?;
+        if bytes.starts_with(STREAMED_INDEX_MAGIC) {
+            return Self::read_streamed(bytes.as_slice());
+        }
+        Ok(MappedIndex::from_bytes(bytes)?.to_index())
+    }
+
+    /// Read the streamed format of version 9, each entry after the one before
+    fn read_streamed(mut input: &[u8]) -> eyre::Result<Self> {
+        let summary = read_summary(&mut input)?;
+        // The count is only trusted as far as the bytes left could hold that many entries
+        let count = (summary.entries as usize).min(input.len() / MIN_STREAMED_ENTRY_LEN);
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..summary.entries {
+            let record_number = read_u64(&mut input)?;
+            let mut hard_link_count = [0u8; 2];
+            input.read_exact(&mut hard_link_count)?;
+            let hard_link_count = u16::from_le_bytes(hard_link_count);
+            let parent_ref = match read_u64(&mut input)? as i64 {
+                NONE_I64 => None,
+                p => Some(p as u64),
+            };
+            let size = read_u64(&mut input)?;
+            let allocated_size = read_u64(&mut input)?;
+            let mut times = [None; 3];
+            for time in &mut times {
+                *time = match read_u64(&mut input)? as i64 {
+                    NONE_I64 => None,
+                    micros => DateTime::from_timestamp_micros(micros),
+                };
+            }
+            let name = read_str(&mut input)?;
+            let mut flags = [0u8; 4];
+            input.read_exact(&mut flags)?;
+            let flags = FileFlags(u32::from_le_bytes(flags));
+            let mut tag = [0u8; 4];
+            input.read_exact(&mut tag)?;
+            let tag = u32::from_le_bytes(tag);
+            let target = read_str(&mut input)?;
+            let reparse = (tag != 0).then(|| ReparsePoint {
+                tag,
+                target: (!target.is_empty()).then_some(target),
+            });
+            entries.push(IndexEntry {
+                record_number,
+                hard_link_count,
+                parent_ref,
+                name,
+                size,
+                allocated_size,
+                created: times[0],
+                modified: times[1],
+                accessed: times[2],
+                flags,
+                reparse,
+            });
+        }
+        Ok(Self {
+            entries,
+            parse_errors: summary.parse_errors,
+            journal: summary.journal,
+        })
+    }
+}
+
+/// A `$FILE_NAME` worth indexing
+struct IndexedName {
+    name: String,
+    parent: Option<ParentRef>,
+    times: (DateTime<Utc>, DateTime<Utc>, DateTime<Utc>),
+    flags: u32,
+}
+
+/// What every name of a base record shares
+#[derive(Clone)]
+struct BaseRecord {
+    hard_link_count: u16,
+    is_dir: bool,
+    /// Cleared once the file is deleted, while its attributes stay until the record is reused
+    in_use: bool,
+    std_times: Option<(DateTime<Utc>, DateTime<Utc>, DateTime<Utc>)>,
+    std_flags: Option<u32>,
+    data: Option<DataSize>,
+    reparse: Option<ReparsePoint>,
+}
+
+impl BaseRecord {
+    /// What the names of a base record share, and whether it has an `$ATTRIBUTE_LIST` that
+    /// moved some attributes to extension records
+    fn of(entry: &MftEntry) -> (Self, bool) {
+        let mut std_times = None;
+        let mut std_flags = None;
+        let mut has_attribute_list = false;
+        for attribute in entry.iter_attributes() {
+            let Ok(attribute) = attribute else { continue };
+            if attribute.header.type_code == MftAttributeType::AttributeList {
+                has_attribute_list = true;
+            }
+            if let MftAttributeContent::AttrX10(info) = &attribute.data {
+                std_times = Some((info.created, info.modified, info.accessed));
+                std_flags = Some(info.file_flags.bits());
+            }
+        }
+        let base = BaseRecord {
+            hard_link_count: entry.header.hard_link_count,
+            is_dir: entry.is_dir(),
+            in_use: entry.header.flags.contains(EntryFlags::ALLOCATED),
+            std_times,
+            std_flags,
+            data: data_size(entry),
+            reparse: reparse_point(entry),
+        };
+        (base, has_attribute_list)
+    }
+
+    /// The entry for one name, preferring `$STANDARD_INFORMATION` times and flags as Explorer
+    /// does
+    fn entry(&self, record_number: u64, name: IndexedName) -> IndexEntry {
+        let (created, modified, accessed) = self.std_times.unwrap_or(name.times);
+        let data = self.data.unwrap_or_default();
+        let mut flags = FileFlags::new(self.std_flags.unwrap_or(name.flags), self.is_dir);
+        if !self.in_use {
+            flags.insert(FileFlag::Deleted);
+        }
+        IndexEntry {
+            record_number,
+            hard_link_count: self.hard_link_count,
+            parent_ref: name.parent.map(|parent| parent.record),
+            name: name.name,
+            size: data.logical,
+            allocated_size: data.allocated,
+            created: Some(created),
+            modified: Some(modified),
+            accessed: Some(accessed),
+            flags,
+            reparse: self.reparse.clone(),
+        }
+    }
+}
+
+/// Attributes an extension record holds for its base record
+#[derive(Default)]
+struct Continuation {
+    names: Vec<IndexedName>,
+    /// Set when the first segment of the unnamed `$DATA` moved here
+    data: Option<DataSize>,
+}
+
+/// The entries of one base record read on its own, following its `$ATTRIBUTE_LIST` to the
+/// extension records holding more of its names or its `$DATA`. Each comes with its name's
+/// reference to its parent, whose sequence number an entry doesn't keep. An extension record
+/// has none, as its names are its base record's.
+pub fn record_entries<T: Read + Seek>(
+    parser: &mut MftParser<T>,
+    entry: &MftEntry,
+) -> Vec<(IndexEntry, Option<ParentRef>)> {
+    if entry.header.base_reference.entry != 0 {
+        return Vec::new();
+    }
+    let record_number = entry.header.record_number;
+    let (mut base, has_attribute_list) = BaseRecord::of(entry);
+    let mut names = indexed_names(entry);
+    if has_attribute_list {
+        if base.data.is_none() {
+            base.data = record_data_size(entry, |record| parser.get_entry(record).ok());
+        }
+        for segment in name_segments(entry) {
+            if let Ok(extension) = parser.get_entry(segment) {
+                names.extend(indexed_names(&extension));
+            }
+        }
+    }
+    names
+        .into_iter()
+        .map(|name| {
+            let parent = name.parent;
+            (base.entry(record_number, name), parent)
+        })
+        .collect()
+}
+
+/// The extension records an entry's `$ATTRIBUTE_LIST` puts some of its `$FILE_NAME`s in
+fn name_segments(entry: &MftEntry) -> BTreeSet<u64> {
+    let record_number = entry.header.record_number;
+    let mut segments = BTreeSet::new();
+    for attribute in entry.iter_attributes() {
+        let Ok(attribute) = attribute else { continue };
+        let MftAttributeContent::AttrX20(list) = &attribute.data else {
+            continue;
+        };
+        segments.extend(
+            list.entries
+                .iter()
+                .filter(|listed| listed.attribute_type == MftAttributeType::FileName as u32)
+                .map(|listed| listed.base_reference.entry)
+                .filter(|&segment| segment != record_number),
+        );
+    }
+    segments
+}
+
+/// The names of a record that get entries: 8.3 short names, `.`/`..` and `$` metadata files
+/// are left out
+fn indexed_names(entry: &MftEntry) -> Vec<IndexedName> {
+    let record_number = match entry.header.base_reference.entry {
+        0 => entry.header.record_number,
+        base_record => base_record,
+    };
+    let mut names = Vec::new();
+    for attribute in entry.iter_attributes() {
+        let Ok(attribute) = attribute else { continue };
+        let MftAttributeContent::AttrX30(filename_attr) = attribute.data else {
+            continue;
+        };
+        let name = filename_attr.name;
+        if name.is_empty() || is_metadata_name(record_number, &name) || name == "." || name == ".."
+        {
+            continue;
+        }
+        if filename_attr.namespace == FileNamespace::DOS {
+            continue;
+        }
+        let parent = (filename_attr.parent.entry != 0).then_some(ParentRef {
+            record: filename_attr.parent.entry,
+            sequence: filename_attr.parent.sequence,
+        });
+        names.push(IndexedName {
+            name,
+            parent,
+            times: (
+                filename_attr.created,
+                filename_attr.modified,
+                filename_attr.accessed,
+            ),
+            flags: filename_attr.flags.bits(),
+        });
+    }
+    names
+}
+
+/// Whether a name belongs to an NTFS metadata file rather than something like `$Recycle.Bin`
+/// or `$Windows.~BT` that only shares the `$` prefix
+pub(crate) fn is_metadata_name(record_number: u64, name: &str) -> bool {
+    name.starts_with('$')
+        && (record_number < FIRST_NON_METADATA_RECORD
+            || METADATA_NAMES
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(name)))
+}
+
+impl<'a> IntoIterator for &'a MftIndex {
+    type Item = &'a IndexEntry;
+    type IntoIter = std::slice::Iter<'a, IndexEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Read just the entry and parse error counts of an index file
+pub fn read_index_summary(path: &Path) -> eyre::Result<IndexSummary> {
+    let file = File::open(path).with_context(|| format!("opening {}", path.display()))?;
+    read_summary(&mut BufReader::new(file)).with_context(|| format!("reading {}", path.display()))
+}
+
+fn read_summary(input: &mut impl Read) -> eyre::Result<IndexSummary> {
+    let mut magic = [0u8; 8];
+    input.read_exact(&mut magic)?;
+    if &magic != INDEX_MAGIC && &magic != STREAMED_INDEX_MAGIC {
+        return Err(eyre::eyre!("Not a storage-usage index (bad magic)"));
+    }
+    let entries = read_u64(input)?;
+    let parse_errors = read_u64(input)?;
+    let journal_id = read_u64(input)?;
+    let journal = match read_u64(input)? as i64 {
+        NONE_I64 => None,
+        next_usn => Some(JournalPosition {
+            journal_id,
+            next_usn,
+        }),
+    };
+    Ok(IndexSummary {
+        entries,
+        parse_errors,
+        journal,
+    })
+}
+
+/// Build the index for one dump and write it next to the dump
+pub fn build_index_for(mft_file: &Path) -> eyre::Result<PathBuf> {
+    build_index_for_with_progress(mft_file, &mut NoProgress)
+}
+
+/// [`build_index_for`], sending parse events to `subscriber`
+pub fn build_index_for_with_progress(
+    mft_file: &Path,
+    subscriber: &mut dyn ProgressSubscriber,
+) -> eyre::Result<PathBuf> {
+    build_index_for_with_limit(mft_file, None, subscriber)
+}
+
+/// [`build_index_for_with_progress`] in about `memory_limit` bytes. With a limit, entries are
+/// written as they're parsed rather than collected: the dump is read once for the directories
+/// that paths are built from and again for the entries, and paths are sorted in what the
+/// directories leave of the limit, spilling to temp files past it. The directories are held
+/// either way, so a limit below what they take is exceeded.
+pub fn build_index_for_with_limit(
+    mft_file: &Path,
+    memory_limit: Option<usize>,
+    subscriber: &mut dyn ProgressSubscriber,
+) -> eyre::Result<PathBuf> {
+    let index_file = index_path_for(mft_file);
+    let entries = match memory_limit {
+        None => {
+            let index = MftIndex::build_from_mft_with_progress(mft_file, subscriber)?;
+            index.write_to_file(&index_file)?;
+            index.entries.len() as u64
+        }
+        Some(memory_limit) => stream_index(mft_file, &index_file, memory_limit, subscriber)?,
+    };
+    info!(
+        "Indexed {} entries from {} into {}",
+        entries,
+        mft_file.display(),
+        index_file.display()
+    );
+    Ok(index_file)
+}
+
+/// Write the index of a dump to `index_file` as its records are parsed, giving back how many
+/// entries it has. [`ProgressEvent::RecordsParsed`] counts both passes over the dump.
+fn stream_index(
+    mft_file: &Path,
+    index_file: &Path,
+    memory_limit: usize,
+    subscriber: &mut dyn ProgressSubscriber,
+) -> eyre::Result<u64> {
+    let mut parser = MftParser::from_path(mft_file)
+        .map_err(|e| eyre::eyre!("Failed to open MFT {}: {}", mft_file.display(), e))?;
+    let total = parser.get_entry_count();
+    subscriber.on_event(ProgressEvent::RecordSize {
+        bytes: parser.entry_size as u64,
+    });
+    let mut resolver = PathResolver::relative();
+    for record in 0..total {
+        subscriber.on_event(ProgressEvent::RecordsParsed {
+            parsed: record + 1,
+            total: total * 2,
+        });
+        if let Ok(entry) = parser.get_entry(record) {
+            resolver.add_entry(&entry);
+        }
+    }
+    if let Err(e) = resolver.add_index_allocations(&index_allocation_path_for(mft_file)) {
+        debug!("Not using directory index blocks: {e:#}");
+    }
+    let directories = resolver.approximate_bytes();
+    if directories + MIN_SORT_BUFFER > memory_limit {
+        warn!(
+            "The directories of {} take about {} of the {} memory limit",
+            mft_file.display(),
+            humansize::format_size(directories, humansize::DECIMAL),
+            humansize::format_size(memory_limit, humansize::DECIMAL)
+        );
+    }
+    let sort_buffer = memory_limit
+        .saturating_sub(directories)
+        .max(MIN_SORT_BUFFER);
+
+    let file =
+        File::create(index_file).with_context(|| format!("creating {}", index_file.display()))?;
+    let mut writer = MappedWriter::new(BufWriter::new(file), Some(sort_buffer))?;
+    let mut parse_errors = 0;
+    // Read by number rather than through `iter_entries` so the parser stays free to read the
+    // extension records that hold some files' names and $DATA
+    for record in 0..total {
+        subscriber.on_event(ProgressEvent::RecordsParsed {
+            parsed: total + record + 1,
+            total: total * 2,
+        });
+        let entry = match parser.get_entry(record) {
+            Ok(entry) => entry,
+            Err(e) => {
+                parse_errors += 1;
+                subscriber.on_event(ProgressEvent::Problem(MftError {
+                    severity: ErrorSeverity::Error,
+                    record_number: Some(record),
+                    message: format!("Error processing entry: {e}"),
+                }));
+                continue;
+            }
+        };
+        for (entry, parent) in record_entries(&mut parser, &entry) {
+            let path = resolver
+                .resolve(entry.record_number, &entry.name, parent)
+                .path;
+            writer
+                .push(&entry, &path)
+                .with_context(|| format!("writing {}", index_file.display()))?;
+        }
+    }
+    writer
+        .finish(parse_errors, None)
+        .with_context(|| format!("writing {}", index_file.display()))
+}
+
+fn read_u64(input: &mut impl Read) -> eyre::Result<u64> {
+    let mut buf = [0u8; 8];
+    input.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// A length-prefixed string, read only as far as the input goes rather than allocated up front
+fn read_str(input: &mut impl Read) -> eyre::Result<String> {
+    let mut len = [0u8; 4];
+    input.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len) as u64;
+    let mut buf = Vec::new();
+    input.by_ref().take(len).read_to_end(&mut buf)?;
+    if (buf.len() as u64) < len {
+        return Err(eyre::eyre!("Index ends inside a string of {len} bytes"));
+    }
+    Ok(String::from_utf8(buf)?)
+}
+
+/// An entry for tests: a file named `name` below `parent_ref`, sized by its record number
+#[cfg(test)]
+pub(crate) fn test_entry(record_number: u64, parent_ref: u64, name: &str) -> IndexEntry {
+    IndexEntry {
+        record_number,
+        hard_link_count: 1,
+        parent_ref: Some(parent_ref),
+        name: name.to_string(),
+        size: record_number * 100,
+        allocated_size: 4096,
+        created: None,
+        modified: "2024-03-01T12:00:00Z".parse().ok(),
+        accessed: None,
+        flags: FileFlags::default(),
+        reparse: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IndexEntry;
+    use super::JournalPosition;
+    use super::MftIndex;
+    use super::NONE_I64;
+    use super::ROOT_RECORD;
+    use super::STREAMED_INDEX_MAGIC;
+    use super::is_metadata_name;
+    use super::read_summary;
+    use super::test_entry;
+    use crate::file_flags::FileFlags;
+    use crate::reparse::ReparsePoint;
+    use std::io::Write;
+
+    /// Write an index the way version 9 did, entry after entry, to check it's still read
+    fn write_streamed(index: &MftIndex, mut out: impl Write) -> std::io::Result<()> {
+        out.write_all(STREAMED_INDEX_MAGIC)?;
+        out.write_all(&(index.entries.len() as u64).to_le_bytes())?;
+        out.write_all(&index.parse_errors.to_le_bytes())?;
+        let journal = index.journal.as_ref();
+        out.write_all(&journal.map(|j| j.journal_id).unwrap_or(0).to_le_bytes())?;
+        out.write_all(
+            &journal
+                .map(|j| j.next_usn)
+                .unwrap_or(NONE_I64)
+                .to_le_bytes(),
+        )?;
+        for entry in &index.entries {
+            out.write_all(&entry.record_number.to_le_bytes())?;
+            out.write_all(&entry.hard_link_count.to_le_bytes())?;
+            out.write_all(
+                &entry
+                    .parent_ref
+                    .map(|p| p as i64)
+                    .unwrap_or(NONE_I64)
+                    .to_le_bytes(),
+            )?;
+            out.write_all(&entry.size.to_le_bytes())?;
+            out.write_all(&entry.allocated_size.to_le_bytes())?;
+            for time in [entry.created, entry.modified, entry.accessed] {
+                let micros = time.map(|t| t.timestamp_micros()).unwrap_or(NONE_I64);
+                out.write_all(&micros.to_le_bytes())?;
+            }
+            write_str(&mut out, &entry.name)?;
+            out.write_all(&entry.flags.0.to_le_bytes())?;
+            // A zero tag means no reparse point; an empty target means none was read
+            let reparse = entry.reparse.as_ref();
+            out.write_all(&reparse.map(|r| r.tag).unwrap_or(0).to_le_bytes())?;
+            write_str(
+                &mut out,
+                reparse.and_then(|r| r.target.as_deref()).unwrap_or(""),
+            )?;
+        }
+        out.flush()
+    }
+
+    fn write_str(out: &mut impl Write, s: &str) -> std::io::Result<()> {
+        out.write_all(&(s.len() as u32).to_le_bytes())?;
+        out.write_all(s.as_bytes())
+    }
+
+    fn sample() -> MftIndex {
+        MftIndex {
+            entries: vec![
+                test_entry(40, ROOT_RECORD, "Users"),
+                test_entry(41, 40, "Alice"),
+                test_entry(42, 41, "notes.txt"),
+                test_entry(43, ROOT_RECORD, "notes.txt"),
+            ],
+            parse_errors: 0,
+            journal: None,
+        }
+    }
+
+    #[test]
+    fn keeps_dollar_names_that_are_not_metadata() {
+        assert!(is_metadata_name(0, "$MFT"));
+        assert!(is_metadata_name(11, "$Extend"));
+        assert!(is_metadata_name(27, "$UsnJrnl"));
+        assert!(!is_metadata_name(40, "$Recycle.Bin"));
+        assert!(!is_metadata_name(41, "$Windows.~BT"));
+        assert!(!is_metadata_name(42, "$RABC.txt"));
+        assert!(!is_metadata_name(43, "notes.txt"));
+    }
+
+    #[test]
+    fn orphans_paths_whose_parents_are_missing_or_loop() {
+        let mut index = sample();
+        index.entries.push(test_entry(50, 99, "lost.txt"));
+        index.entries.push(test_entry(60, 61, "a"));
+        index.entries.push(test_entry(61, 60, "b"));
+        let path_of = |record| index.paths_of_record('C', record);
+        assert_eq!(path_of(50), [r"C:\<orphaned>\lost.txt"]);
+        assert_eq!(path_of(60), [r"C:\<orphaned>\b\a"]);
+    }
+
+    #[test]
+    fn looks_up_paths_ignoring_case_and_drive() {
+        let index = sample();
+        let found = index.lookup_path(r"C:\users\ALICE\Notes.txt");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].record_number, 42);
+        assert_eq!(index.lookup_path(r"\notes.txt")[0].record_number, 43);
+        assert!(index.lookup_path(r"C:\Alice\notes.txt").is_empty());
+        assert_eq!(
+            index.path_of('C', found[0]),
+            r"C:\Users\Alice\notes.txt".to_string()
+        );
+    }
+
+    #[test]
+    fn rolls_up_directory_usage_to_a_depth() {
+        let mut index = sample();
+        for entry in &mut index.entries[..2] {
+            entry.flags = FileFlags::new(0, true);
+        }
+        for (name, parent) in [("a.bin", 41), ("b.bin", 41)] {
+            index.entries.push(IndexEntry {
+                hard_link_count: 2,
+                allocated_size: 4096,
+                ..entry(44, parent, name)
+            });
+        }
+        index.entries[2].allocated_size = 8192;
+
+        let usage = index.directory_usage('C', 2);
+        let rows: Vec<(&str, usize, u64, u64)> = usage
+            .iter()
+            .map(|u| (u.path.as_str(), u.depth, u.files, u.allocated_bytes))
+            .collect();
+        // The two links of record 44 in one directory count once
+        assert_eq!(
+            rows,
+            vec![
+                (r"C:\Users", 1, 2, 12_288),
+                (r"C:\Users\Alice", 2, 2, 12_288),
+            ]
+        );
+        assert_eq!(index.directory_usage('C', 1).len(), 1);
+    }
+
+    #[test]
+    fn lists_every_link_of_a_record() {
+        let mut index = sample();
+        for name in ["report.pdf", "report-link.pdf"] {
+            let parent = if name == "report.pdf" {
+                41
+            } else {
+                ROOT_RECORD
+            };
+            index.entries.push(IndexEntry {
+                hard_link_count: 2,
+                ..entry(44, parent, name)
+            });
+        }
+        assert_eq!(
+            index.paths_of_record('C', 44),
+            vec![
+                r"C:\Users\Alice\report.pdf".to_string(),
+                r"C:\report-link.pdf".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn round_trips_through_the_index_format() {
+        let index = MftIndex {
+            journal: Some(JournalPosition {
+                journal_id: 0x01d9_4c2e_7f00_1234,
+                next_usn: 81_920,
+            }),
+            ..sample()
+        };
+        let mut bytes = Vec::new();
+        index.write_to(&mut bytes).unwrap();
+        assert_eq!(MftIndex::read_from(bytes.as_slice()).unwrap(), index);
+    }
+
+    #[test]
+    fn reads_indexes_streamed_by_version_9() {
+        let mut index = sample();
+        index.parse_errors = 2;
+        index.entries[2].reparse = Some(ReparsePoint {
+            tag: 0xA000_0003,
+            target: Some(r"D:\Notes".to_string()),
+        });
+        index.entries[3].modified = "2023-05-06T07:08:09Z".parse().ok();
+        let mut streamed = Vec::new();
+        write_streamed(&index, &mut streamed).unwrap();
+        assert_eq!(MftIndex::read_from(streamed.as_slice()).unwrap(), index);
+        let intact = streamed.clone();
+
+        // Both formats keep the counts where a summary reads them
+        let mut mapped = Vec::new();
+        index.write_to(&mut mapped).unwrap();
+        for bytes in [streamed, mapped] {
+            let summary = read_summary(&mut bytes.as_slice()).unwrap();
+            assert_eq!((summary.entries, summary.parse_errors), (4, 2));
+            assert_eq!(summary.journal, None);
+        }
+
+        // A damaged path table is an error, not a panic once queried
+        let mut damaged = Vec::new();
+        index.write_to(&mut damaged).unwrap();
+        let path_table = u64::from_le_bytes(damaged[48..56].try_into().unwrap()) as usize;
+        damaged[path_table..path_table + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(MftIndex::read_from(damaged.as_slice()).is_err());
+
+        // So are counts and lengths past what the file holds, rather than an allocation of them
+        let mut overcounted = intact.clone();
+        overcounted[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(MftIndex::read_from(overcounted.as_slice()).is_err());
+        let mut overlong = intact;
+        // The first entry's name length follows the header, its record number, link count and
+        // six more u64 fields
+        let name_len_at = 40 + 8 + 2 + 8 * 6;
+        overlong[name_len_at..name_len_at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(MftIndex::read_from(overlong.as_slice()).is_err());
+    }
+}
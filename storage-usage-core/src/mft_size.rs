@@ -0,0 +1,125 @@
+use mft::MftEntry;
+use mft::attribute::MftAttributeContent;
+use mft::attribute::MftAttributeType;
+use mft::attribute::data_run::DataRun;
+use mft::attribute::data_run::RunType;
+use mft::attribute::header::NonResidentHeader;
+use mft::attribute::header::ResidentialHeader;
+
+/// Size of a file's unnamed `$DATA` stream
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DataSize {
+    /// Bytes of file content
+    pub logical: u64,
+    /// Bytes of clusters in use on disk: zero for data resident in the MFT record, and less
+    /// than the logical size for sparse and compressed files, whose sparse runs take no space
+    pub allocated: u64,
+}
+
+/// Compute the size of a record's unnamed `$DATA`, wherever its first segment is stored.
+///
+/// When the record's `$ATTRIBUTE_LIST` moved `$DATA` into an extension record, `lookup` is
+/// asked for that record by number. Every frontend sizes records through this so they report
+/// the same sizes; the index, which can't read records out of order, joins the extension
+/// records it comes across with [`data_size`] instead.
+pub fn record_data_size(
+    entry: &MftEntry,
+    lookup: impl FnOnce(u64) -> Option<MftEntry>,
+) -> Option<DataSize> {
+    if let Some(size) = data_size(entry) {
+        return Some(size);
+    }
+    let segment = data_segment_record(entry)?;
+    if segment == entry.header.record_number {
+        return None;
+    }
+    data_size(&lookup(segment)?)
+}
+
+/// The record holding the first segment of an entry's unnamed `$DATA`, as its
+/// `$ATTRIBUTE_LIST` records it
+pub fn data_segment_record(entry: &MftEntry) -> Option<u64> {
+    for attribute in entry.iter_attributes() {
+        let Ok(attribute) = attribute else { continue };
+        let MftAttributeContent::AttrX20(list) = &attribute.data else {
+            continue;
+        };
+        return list
+            .entries
+            .iter()
+            .find(|listed| {
+                listed.attribute_type == MftAttributeType::DATA as u32
+                    && listed.first_vcn == 0
+                    && listed.name.is_empty()
+            })
+            .map(|listed| listed.base_reference.entry);
+    }
+    None
+}
+
+/// Compute the size of an entry's unnamed `$DATA` attribute.
+///
+/// Returns `None` for entries without a `$DATA` attribute (e.g. directories), or when the
+/// entry only holds a later segment of it. For a file whose `$ATTRIBUTE_LIST` moved `$DATA`
+/// out of the base record, call this on the extension record holding the first segment, or
+/// use [`record_data_size`].
+pub fn data_size(entry: &MftEntry) -> Option<DataSize> {
+    for attribute in entry.iter_attributes() {
+        let Ok(attribute) = attribute else { continue };
+        if attribute.header.type_code != MftAttributeType::DATA || attribute.header.name_size != 0 {
+            continue;
+        }
+        return match &attribute.header.residential_header {
+            ResidentialHeader::Resident(resident) => Some(DataSize {
+                logical: resident.data_size as u64,
+                allocated: 0,
+            }),
+            // Only the first segment of a non-resident attribute carries the sizes
+            ResidentialHeader::NonResident(non_resident) if non_resident.vnc_first == 0 => {
+                let runs = match &attribute.data {
+                    MftAttributeContent::DataRun(runs) => runs.data_runs.as_slice(),
+                    _ => &[],
+                };
+                Some(DataSize {
+                    logical: non_resident.file_size,
+                    allocated: allocated_bytes(non_resident, runs),
+                })
+            }
+            ResidentialHeader::NonResident(_) => None,
+        };
+    }
+    None
+}
+
+/// Bytes of the attribute's clusters that aren't in sparse runs.
+///
+/// A dump doesn't record the cluster size, so it's worked out from the allocated length and
+/// the clusters the first segment's runs cover. When that isn't a whole power of two, the
+/// attribute is continued in extension records and the compressed size NTFS stores for sparse
+/// and compressed attributes is used instead, or else the allocated length.
+fn allocated_bytes(header: &NonResidentHeader, runs: &[DataRun]) -> u64 {
+    let Some(cluster_size) = cluster_size(header, runs) else {
+        return header.total_allocated.unwrap_or(header.allocated_length);
+    };
+    let clusters: u64 = runs.iter().map(|run| run.lcn_length).sum();
+    (clusters - sparse_clusters(runs)) * cluster_size
+}
+
+/// The volume's cluster size, when the first segment's runs cover the whole allocated length
+pub(crate) fn cluster_size(header: &NonResidentHeader, runs: &[DataRun]) -> Option<u64> {
+    let clusters: u64 = runs.iter().map(|run| run.lcn_length).sum();
+    if header.vnc_first != 0 || clusters == 0 {
+        return None;
+    }
+    let cluster_size = header.allocated_length / clusters;
+    (cluster_size.is_power_of_two() && cluster_size * clusters == header.allocated_length)
+        .then_some(cluster_size)
+}
+
+/// Clusters of the runs that take no space on disk
+pub(crate) fn sparse_clusters(runs: &[DataRun]) -> u64 {
+    runs.iter()
+        .filter(|run| run.run_type == RunType::Sparse)
+        .map(|run| run.lcn_length)
+        .sum()
+}
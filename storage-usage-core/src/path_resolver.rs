@@ -0,0 +1,659 @@
+use crate::dir_index::index_root_children;
+use crate::dir_index::read_index_allocations;
+use crate::mft_index::IndexEntry;
+use crate::mft_index::ROOT_RECORD;
+use crate::mft_index::is_metadata_name;
+use mft::MftEntry;
+use mft::attribute::MftAttributeContent;
+use mft::attribute::x30::FileNamespace;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+/// How many lost directories in a row a path is rebuilt through from directory indexes
+const MAX_RECOVERY_DEPTH: usize = 64;
+/// Stands in for the ancestors of a path whose parent is gone, as no Windows name can
+pub const ORPHANED: &str = "<orphaned>";
+
+/// A `$FILE_NAME`'s reference to its parent directory
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParentRef {
+    pub record: u64,
+    /// The sequence number the parent's record had when the name was written; 0 skips the check
+    pub sequence: u16,
+}
+
+/// Why a path was put under [`ORPHANED`] instead of its real ancestors
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Orphan {
+    /// The parent record never showed up as a directory
+    Missing(u64),
+    /// The parent's record slot was freed and reused by another file since the name was written
+    Reused(u64),
+    /// Following parents from this record leads back to it
+    Cycle(u64),
+}
+
+impl fmt::Display for Orphan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Orphan::Missing(record) => {
+                write!(f, "Parent record {record} not found; path is orphaned")
+            }
+            Orphan::Reused(record) => write!(
+                f,
+                "Parent record {record} was reused by another file; path is orphaned"
+            ),
+            Orphan::Cycle(record) => write!(
+                f,
+                "Parents loop back through record {record}; path is orphaned"
+            ),
+        }
+    }
+}
+
+/// A name placed in the tree
+#[derive(Clone, Debug, PartialEq)]
+pub struct Resolved {
+    pub path: String,
+    /// Set on the name whose own parent was unusable; names below it keep their real names
+    /// under its orphaned path and have no reason of their own
+    pub orphan: Option<Orphan>,
+}
+
+/// Position of a name in [`Names`]
+type NameId = u32;
+
+/// Every distinct name the first pass keeps, stored once. A volume has far fewer distinct
+/// directory names than directories, with a `src` and a `bin` in every project.
+#[derive(Default)]
+struct Names {
+    names: Vec<Arc<str>>,
+    ids: HashMap<Arc<str>, NameId>,
+}
+
+impl Names {
+    fn intern(&mut self, name: &str) -> NameId {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+        let id = self.names.len() as NameId;
+        let name: Arc<str> = Arc::from(name);
+        self.names.push(name.clone());
+        self.ids.insert(name, id);
+        id
+    }
+
+    fn get(&self, id: NameId) -> &str {
+        &self.names[id as usize]
+    }
+}
+
+/// A directory's own name, kept by the first pass
+struct Directory {
+    sequence: u16,
+    name: NameId,
+    parent: Option<ParentRef>,
+}
+
+/// Where the outermost of a chain of directories being placed goes
+enum Below {
+    Root,
+    /// Under a placed directory
+    Directory(u64),
+    /// Under the path a directory index gives a lost parent
+    Recovered(String),
+    Orphaned(Orphan),
+}
+
+/// What a placed directory's name is joined to. Its path is built by following these up to
+/// the root whenever it's needed, so no ancestor's name is copied into each path below it.
+enum Anchor {
+    Root,
+    Orphaned,
+    Directory(u64),
+    Recovered(Box<str>),
+}
+
+/// A directory whose place in the tree is known
+struct Placed {
+    anchor: Anchor,
+    name: NameId,
+    orphan: Option<Orphan>,
+}
+
+/// Builds full paths from `$FILE_NAME`s in two passes over an MFT, which lists children before
+/// their parents whenever a directory's record number is higher than theirs.
+///
+/// The first pass hands every record to [`PathResolver::add_entry`], which keeps only the
+/// sequence numbers of records and the names of directories. The second places any name with
+/// [`PathResolver::resolve`] right away; each directory is placed once, the first time a name
+/// needs it, as an interned name under its parent, and full paths are only built for the names
+/// resolved. A parent that is missing or was reused is rebuilt from the names directory indexes
+/// hold, or else the name is placed under [`ORPHANED`].
+pub struct PathResolver {
+    root: String,
+    /// Sequence number of every record seen, by record number; 0 for records not seen
+    sequences: Vec<u16>,
+    /// Names of directories and of the children directory indexes list
+    names: Names,
+    /// Name and parent of every directory seen
+    directories: HashMap<u64, Directory>,
+    /// Sequence number, directory and name of every child a directory index lists, by record
+    indexed: HashMap<u64, Vec<(u16, u64, NameId)>>,
+    /// The directories placed so far
+    placed: HashMap<u64, Placed>,
+}
+
+impl PathResolver {
+    /// Paths start at `C:\` for drive `C`, or at `\` for the `?` of an unknown drive
+    pub fn new(drive_letter: char) -> Self {
+        if drive_letter == '?' {
+            Self::with_root("\\".to_string())
+        } else {
+            Self::with_root(format!("{drive_letter}:\\"))
+        }
+    }
+
+    /// Paths without a drive, such as `Users\docs`, for indexes that add it when they're read
+    pub fn relative() -> Self {
+        Self::with_root(String::new())
+    }
+
+    fn with_root(root: String) -> Self {
+        Self {
+            root,
+            sequences: Vec::new(),
+            names: Names::default(),
+            directories: HashMap::new(),
+            indexed: HashMap::new(),
+            placed: HashMap::new(),
+        }
+    }
+
+    /// First pass: note a record's sequence number and, for a directory, its name and the
+    /// children its index lists
+    pub fn add_entry(&mut self, entry: &MftEntry) {
+        let record_number = entry.header.record_number;
+        let sequence = entry.header.sequence;
+        self.add_record(record_number, sequence);
+        if !entry.is_dir() {
+            return;
+        }
+        // A directory's index still names children whose own records are corrupt or reused
+        for child in index_root_children(entry) {
+            let child_ref = ParentRef {
+                record: child.record,
+                sequence: child.sequence,
+            };
+            self.add_indexed(record_number, child_ref, child.name);
+        }
+        let name = entry
+            .iter_attributes()
+            .filter_map(Result::ok)
+            .find_map(|attribute| match attribute.data {
+                MftAttributeContent::AttrX30(name)
+                    if name.namespace != FileNamespace::DOS
+                        && is_listed(record_number, &name.name) =>
+                {
+                    Some(name)
+                }
+                _ => None,
+            });
+        if let Some(name) = name {
+            let parent = (name.parent.entry != 0).then_some(ParentRef {
+                record: name.parent.entry,
+                sequence: name.parent.sequence,
+            });
+            self.add_directory(record_number, sequence, name.name, parent);
+        }
+    }
+
+    /// Add a directory's name, as [`PathResolver::add_entry`] does for directory records
+    pub fn add_directory(
+        &mut self,
+        record_number: u64,
+        sequence: u16,
+        name: String,
+        parent: Option<ParentRef>,
+    ) {
+        self.add_record(record_number, sequence);
+        if self.directories.contains_key(&record_number) {
+            return;
+        }
+        let name = self.names.intern(&name);
+        self.directories.insert(
+            record_number,
+            Directory {
+                sequence,
+                name,
+                parent,
+            },
+        );
+    }
+
+    /// Add a child that a directory's `$I30` index lists. Its name is used for the paths of
+    /// names below a record that's corrupt or was reused, which its own `$FILE_NAME` can't give.
+    pub fn add_indexed(&mut self, directory: u64, child: ParentRef, name: String) {
+        let name = self.names.intern(&name);
+        self.indexed
+            .entry(child.record)
+            .or_default()
+            .push((child.sequence, directory, name));
+    }
+
+    /// Add the children listed in the `$INDEX_ALLOCATION` blocks dumped next to an MFT, which
+    /// hold most of the index of a directory too big for its record
+    pub fn add_index_allocations(&mut self, path: &Path) -> eyre::Result<()> {
+        read_index_allocations(path, |directory, child| {
+            let child_ref = ParentRef {
+                record: child.record,
+                sequence: child.sequence,
+            };
+            self.add_indexed(directory, child_ref, child.name);
+        })
+    }
+
+    /// First pass over an index: add every entry that's another's parent. Index entries don't
+    /// keep their parents' sequence numbers, so a parent record that was reused isn't caught.
+    pub fn add_index_directories(&mut self, entries: &[IndexEntry]) {
+        let parents: HashSet<u64> = entries
+            .iter()
+            .filter_map(|entry| entry.parent_ref)
+            .collect();
+        for entry in entries {
+            if parents.contains(&entry.record_number) {
+                self.add_directory(
+                    entry.record_number,
+                    0,
+                    entry.name.clone(),
+                    index_parent(entry),
+                );
+            }
+        }
+    }
+
+    /// Second pass over an index: the path of one of its entries
+    pub fn resolve_index_entry(&mut self, entry: &IndexEntry) -> Resolved {
+        self.resolve(entry.record_number, &entry.name, index_parent(entry))
+    }
+
+    /// Roughly how many bytes the first pass holds, counting the directories the second pass
+    /// will place as already placed. Hash tables are counted at their capacity.
+    pub fn approximate_bytes(&self) -> usize {
+        // An interned name is shared by the list and the map, with its id and the `Arc`'s counts
+        let name_overhead =
+            2 * size_of::<Arc<str>>() + size_of::<NameId>() + 2 * size_of::<usize>();
+        let names: usize = self
+            .names
+            .names
+            .iter()
+            .map(|name| name.len() + name_overhead)
+            .sum();
+        let indexed: usize = self
+            .indexed
+            .values()
+            .map(|children| children.capacity() * size_of::<(u16, u64, NameId)>())
+            .sum();
+        self.sequences.capacity() * size_of::<u16>()
+            + names
+            + self.directories.capacity() * (size_of::<(u64, Directory)>() + 1)
+            + self.indexed.capacity() * (size_of::<(u64, Vec<(u16, u64, NameId)>)>() + 1)
+            + indexed
+            + self.directories.len() * (size_of::<(u64, Placed)>() + 1)
+    }
+
+    /// Fold in a first pass made over a later range of records, such as by another thread
+    pub fn merge(&mut self, other: PathResolver) {
+        if self.sequences.len() < other.sequences.len() {
+            self.sequences.resize(other.sequences.len(), 0);
+        }
+        for (mine, theirs) in self.sequences.iter_mut().zip(other.sequences) {
+            if *mine == 0 {
+                *mine = theirs;
+            }
+        }
+        // Name ids only mean something to the resolver that gave them out
+        for (record, directory) in other.directories {
+            let name = other.names.get(directory.name).to_string();
+            self.add_directory(record, directory.sequence, name, directory.parent);
+        }
+        for (record, children) in other.indexed {
+            for (sequence, directory, name) in children {
+                let name = self.names.intern(other.names.get(name));
+                self.indexed
+                    .entry(record)
+                    .or_default()
+                    .push((sequence, directory, name));
+            }
+        }
+    }
+
+    /// Second pass: the path of a name of a record. A directory gets the path names below it
+    /// are placed under.
+    pub fn resolve(
+        &mut self,
+        record_number: u64,
+        name: &str,
+        parent: Option<ParentRef>,
+    ) -> Resolved {
+        if self
+            .directories
+            .get(&record_number)
+            .is_some_and(|directory| self.names.get(directory.name) == name)
+        {
+            self.place_directory(record_number, 0);
+            return Resolved {
+                path: self.path_of(record_number),
+                orphan: self.placed[&record_number].orphan,
+            };
+        }
+        let below = match parent.filter(|parent| parent.record != ROOT_RECORD) {
+            None => Below::Root,
+            Some(parent) => self.below(parent, 0),
+        };
+        self.join(below, name)
+    }
+
+    fn add_record(&mut self, record_number: u64, sequence: u16) {
+        let index = record_number as usize;
+        if index >= self.sequences.len() {
+            self.sequences.resize(index + 1, 0);
+        }
+        // With hard links the first name a record shows up with wins
+        if self.sequences[index] == 0 {
+            self.sequences[index] = sequence;
+        }
+    }
+
+    /// Where names under `parent` go: its path when it's a directory whose sequence number
+    /// matches, else the path a directory index gives it, else why they're orphaned
+    fn below(&mut self, parent: ParentRef, depth: usize) -> Below {
+        if self.is_usable(parent) {
+            self.place_directory(parent.record, depth);
+            return Below::Directory(parent.record);
+        }
+        // A directory index may still name a parent whose own record is gone or reused
+        if let Some(path) = self.indexed_path(parent, depth) {
+            return Below::Recovered(path);
+        }
+        let sequence = self.sequences.get(parent.record as usize).copied();
+        match sequence {
+            Some(sequence)
+                if sequence != 0 && parent.sequence != 0 && sequence != parent.sequence =>
+            {
+                Below::Orphaned(Orphan::Reused(parent.record))
+            }
+            _ => Below::Orphaned(Orphan::Missing(parent.record)),
+        }
+    }
+
+    fn is_usable(&self, parent: ParentRef) -> bool {
+        self.directories
+            .get(&parent.record)
+            .is_some_and(|directory| parent.sequence == 0 || directory.sequence == parent.sequence)
+    }
+
+    /// Place a directory and the directories above it that aren't placed yet. Following parents
+    /// back to a directory already on the way is a cycle, which orphans the directory it closes
+    /// on.
+    fn place_directory(&mut self, record: u64, depth: usize) {
+        let mut chain = Vec::new();
+        let mut current = record;
+        let below = loop {
+            if self.placed.contains_key(&current) {
+                break Below::Directory(current);
+            }
+            chain.push(current);
+            let parent = self.directories[&current].parent;
+            let Some(parent) = parent.filter(|parent| parent.record != ROOT_RECORD) else {
+                break Below::Root;
+            };
+            if chain.contains(&parent.record) {
+                break Below::Orphaned(Orphan::Cycle(parent.record));
+            }
+            if !self.is_usable(parent) {
+                break self.below(parent, depth);
+            }
+            current = parent.record;
+        };
+        let mut below = below;
+        for record in chain.into_iter().rev() {
+            let (anchor, orphan) = match below {
+                Below::Root => (Anchor::Root, None),
+                Below::Directory(directory) => (Anchor::Directory(directory), None),
+                Below::Recovered(path) => (Anchor::Recovered(path.into_boxed_str()), None),
+                Below::Orphaned(orphan) => (Anchor::Orphaned, Some(orphan)),
+            };
+            let name = self.directories[&record].name;
+            self.placed.entry(record).or_insert(Placed {
+                anchor,
+                name,
+                orphan,
+            });
+            below = Below::Directory(record);
+        }
+    }
+
+    /// The full path of a placed directory, joined from the names of it and its ancestors
+    fn path_of(&self, record: u64) -> String {
+        let mut names = Vec::new();
+        let mut current = &self.placed[&record];
+        let prefix = loop {
+            names.push(self.names.get(current.name));
+            match &current.anchor {
+                Anchor::Root => break self.root.clone(),
+                Anchor::Orphaned => break format!("{}{ORPHANED}\\", self.root),
+                Anchor::Recovered(path) => break format!("{path}\\"),
+                Anchor::Directory(directory) => current = &self.placed[directory],
+            }
+        };
+        let mut path = prefix;
+        for (index, name) in names.into_iter().rev().enumerate() {
+            if index > 0 {
+                path.push('\\');
+            }
+            path.push_str(name);
+        }
+        path
+    }
+
+    /// The path of a record as the index of the directory it was in names it
+    fn indexed_path(&mut self, record: ParentRef, depth: usize) -> Option<String> {
+        if depth >= MAX_RECOVERY_DEPTH {
+            return None;
+        }
+        let (directory, name) = self
+            .indexed
+            .get(&record.record)?
+            .iter()
+            .find(|(sequence, ..)| record.sequence == 0 || *sequence == record.sequence)
+            .map(|(_, directory, name)| (*directory, *name))?;
+        let name = self.names.get(name).to_string();
+        if directory == ROOT_RECORD {
+            return Some(format!("{}{name}", self.root));
+        }
+        let directory_path = if self.directories.contains_key(&directory) {
+            self.place_directory(directory, depth + 1);
+            self.path_of(directory)
+        } else {
+            // The directory may be lost too, yet listed in its own parent's index
+            let directory = ParentRef {
+                record: directory,
+                sequence: 0,
+            };
+            self.indexed_path(directory, depth + 1)?
+        };
+        Some(format!("{directory_path}\\{name}"))
+    }
+
+    fn join(&self, below: Below, name: &str) -> Resolved {
+        match below {
+            Below::Root => Resolved {
+                path: format!("{}{name}", self.root),
+                orphan: None,
+            },
+            Below::Directory(directory) => Resolved {
+                path: format!("{}\\{name}", self.path_of(directory)),
+                orphan: None,
+            },
+            Below::Recovered(directory) => Resolved {
+                path: format!("{directory}\\{name}"),
+                orphan: None,
+            },
+            Below::Orphaned(orphan) => Resolved {
+                path: format!("{}{ORPHANED}\\{name}", self.root),
+                orphan: Some(orphan),
+            },
+        }
+    }
+}
+
+/// Whether a name is one queries list: `.`, `..` and `$` metadata files are left out
+fn is_listed(record_number: u64, name: &str) -> bool {
+    !name.is_empty() && !is_metadata_name(record_number, name) && name != "." && name != ".."
+}
+
+/// An index entry's parent, with its sequence number unknown
+fn index_parent(entry: &IndexEntry) -> Option<ParentRef> {
+    entry.parent_ref.map(|record| ParentRef {
+        record,
+        sequence: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Orphan;
+    use super::ParentRef;
+    use super::PathResolver;
+
+    fn parent(record: u64, sequence: u16) -> Option<ParentRef> {
+        Some(ParentRef { record, sequence })
+    }
+
+    /// Paths and orphan reasons of names, resolved in the order given
+    fn paths(
+        resolver: &mut PathResolver,
+        names: &[(u64, &str, Option<ParentRef>)],
+    ) -> Vec<(u64, String, Option<Orphan>)> {
+        names
+            .iter()
+            .map(|(record, name, parent)| {
+                let resolved = resolver.resolve(*record, name, *parent);
+                (*record, resolved.path, resolved.orphan)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn resolves_children_listed_before_their_parents() {
+        let mut resolver = PathResolver::new('C');
+        resolver.add_directory(30, 2, "docs".into(), parent(20, 1));
+        resolver.add_directory(20, 1, "Users".into(), parent(5, 5));
+        assert_eq!(
+            paths(
+                &mut resolver,
+                &[
+                    (40, "notes.txt", parent(30, 2)),
+                    (30, "docs", parent(20, 1)),
+                    (20, "Users", parent(5, 5)),
+                    (50, "a.txt", parent(20, 1)),
+                ]
+            ),
+            vec![
+                (40, r"C:\Users\docs\notes.txt".to_string(), None),
+                (30, r"C:\Users\docs".to_string(), None),
+                (20, r"C:\Users".to_string(), None),
+                (50, r"C:\Users\a.txt".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn orphans_names_whose_parent_slot_was_reused() {
+        let mut resolver = PathResolver::new('C');
+        resolver.add_directory(20, 7, "new".into(), parent(5, 5));
+        // Written while record 20 was still at sequence 6
+        assert_eq!(
+            paths(&mut resolver, &[(30, "stale.txt", parent(20, 6))]),
+            vec![(
+                30,
+                r"C:\<orphaned>\stale.txt".to_string(),
+                Some(Orphan::Reused(20))
+            )]
+        );
+    }
+
+    #[test]
+    fn rebuilds_paths_through_reused_records_from_directory_indexes() {
+        let mut resolver = PathResolver::new('C');
+        resolver.add_directory(10, 1, "docs".into(), parent(5, 5));
+        resolver.add_directory(20, 7, "new".into(), parent(5, 5));
+        // Record 20 held "old" in docs before it was reused
+        resolver.add_indexed(
+            10,
+            ParentRef {
+                record: 20,
+                sequence: 6,
+            },
+            "old".into(),
+        );
+        assert_eq!(
+            paths(&mut resolver, &[(30, "notes.txt", parent(20, 6))]),
+            vec![(30, r"C:\docs\old\notes.txt".to_string(), None)]
+        );
+    }
+
+    #[test]
+    fn keeps_the_tree_below_a_missing_parent() {
+        let mut resolver = PathResolver::new('?');
+        resolver.add_directory(30, 1, "lost".into(), parent(99, 1));
+        assert_eq!(
+            paths(
+                &mut resolver,
+                &[(40, "file", parent(30, 1)), (30, "lost", parent(99, 1))]
+            ),
+            vec![
+                (40, r"\<orphaned>\lost\file".to_string(), None),
+                (
+                    30,
+                    r"\<orphaned>\lost".to_string(),
+                    Some(Orphan::Missing(99))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn breaks_cycles() {
+        let mut resolver = PathResolver::new('C');
+        resolver.add_directory(30, 1, "a".into(), parent(40, 1));
+        resolver.add_directory(40, 1, "b".into(), parent(30, 1));
+        assert_eq!(
+            paths(
+                &mut resolver,
+                &[(30, "a", parent(40, 1)), (40, "b", parent(30, 1))]
+            ),
+            vec![
+                (30, r"C:\<orphaned>\b\a".to_string(), None),
+                (40, r"C:\<orphaned>\b".to_string(), Some(Orphan::Cycle(30))),
+            ]
+        );
+    }
+
+    #[test]
+    fn merges_first_passes_over_record_ranges() {
+        let mut first = PathResolver::new('C');
+        first.add_directory(20, 1, "Users".into(), parent(5, 5));
+        let mut second = PathResolver::new('C');
+        second.add_directory(90, 3, "docs".into(), parent(20, 1));
+        first.merge(second);
+        assert_eq!(
+            paths(&mut first, &[(12, "notes.txt", parent(90, 3))]),
+            vec![(12, r"C:\Users\docs\notes.txt".to_string(), None)]
+        );
+    }
+}
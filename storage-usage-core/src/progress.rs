@@ -0,0 +1,68 @@
+use serde::Serialize;
+
+/// How serious a problem found while processing an MFT is
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorSeverity {
+    /// The entry was processed but something about it is incomplete, like an unresolved parent
+    Warning,
+    /// The entry couldn't be processed
+    Error,
+}
+
+impl ErrorSeverity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorSeverity::Warning => "warning",
+            ErrorSeverity::Error => "error",
+        }
+    }
+}
+
+/// A problem found while processing an MFT
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MftError {
+    pub severity: ErrorSeverity,
+    /// The record that produced the problem, when known
+    pub record_number: Option<u64>,
+    pub message: String,
+}
+
+/// Something that happened while reading or indexing an MFT, for frontends to show however
+/// they like
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    /// Bytes of a volume's MFT read so far, while it is dumped
+    MftRead { read: u64, total: u64 },
+    /// Size of one MFT record in bytes, known once parsing starts
+    RecordSize { bytes: u64 },
+    /// Records parsed so far, out of the record count
+    RecordsParsed { parsed: u64, total: u64 },
+    /// A record failed to parse, or its entry is incomplete
+    Problem(MftError),
+    /// The work is done. The library's functions are done when they return, so only work run
+    /// in the background, like `async_ops` and the TUI's workers, sends this.
+    Finished,
+}
+
+/// Receives the [`ProgressEvent`]s of the `*_with_progress` functions.
+///
+/// Closures taking a [`ProgressEvent`] are subscribers, so a one-off handler needs no type.
+pub trait ProgressSubscriber {
+    fn on_event(&mut self, event: ProgressEvent);
+}
+
+impl<F: FnMut(ProgressEvent)> ProgressSubscriber for F {
+    fn on_event(&mut self, event: ProgressEvent) {
+        self(event)
+    }
+}
+
+/// A subscriber ignoring every event
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoProgress;
+
+impl ProgressSubscriber for NoProgress {
+    fn on_event(&mut self, _event: ProgressEvent) {}
+}
@@ -0,0 +1,224 @@
+use mft::MftEntry;
+use mft::attribute::MftAttributeContent;
+use mft::attribute::MftAttributeType;
+use serde::Serialize;
+use std::fmt;
+
+// IO_REPARSE_TAG_* values from winnt.h
+const TAG_MOUNT_POINT: u32 = 0xA000_0003;
+pub(crate) const TAG_SYMLINK: u32 = 0xA000_000C;
+/// `IO_REPARSE_TAG_CLOUD` with the 4 bits that tell `CLOUD_1` to `CLOUD_F` apart masked out
+const TAG_CLOUD: u32 = 0x9000_001A;
+const CLOUD_MASK: u32 = 0xFFFF_0FFF;
+/// Stands in for a tag that couldn't be read, as when walking the tree without raw access
+pub const TAG_UNKNOWN: u32 = 0xFFFF_FFFF;
+/// `SYMLINK_FLAG_RELATIVE`
+const SYMLINK_RELATIVE: u32 = 0x1;
+
+/// What a reparse point redirects to, as far as sizing cares
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReparseKind {
+    Symlink,
+    /// A mount point, which is what `mklink /J` creates
+    Junction,
+    /// A cloud files placeholder, such as a OneDrive file that isn't kept on the device
+    Cloud,
+    /// Any other tag, such as deduplicated or WOF-compressed files
+    Other,
+}
+
+impl ReparseKind {
+    pub fn from_tag(tag: u32) -> Self {
+        match tag {
+            TAG_SYMLINK => ReparseKind::Symlink,
+            TAG_MOUNT_POINT => ReparseKind::Junction,
+            tag if tag & CLOUD_MASK == TAG_CLOUD => ReparseKind::Cloud,
+            _ => ReparseKind::Other,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReparseKind::Symlink => "symlink",
+            ReparseKind::Junction => "junction",
+            ReparseKind::Cloud => "cloud placeholder",
+            ReparseKind::Other => "reparse point",
+        }
+    }
+
+    /// Whether the point stands in for another path whose bytes are counted where they live
+    pub fn is_link(&self) -> bool {
+        matches!(self, ReparseKind::Symlink | ReparseKind::Junction)
+    }
+}
+
+impl fmt::Display for ReparseKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The `$REPARSE_POINT` attribute of an entry
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct ReparsePoint {
+    pub tag: u32,
+    /// Where a symlink or junction points, as `mklink` shows it. Relative symlink targets
+    /// are left relative to the link's directory.
+    pub target: Option<String>,
+}
+
+impl ReparsePoint {
+    pub fn kind(&self) -> ReparseKind {
+        ReparseKind::from_tag(self.tag)
+    }
+
+    /// Parse a `REPARSE_DATA_BUFFER`
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let tag = u32::from_le_bytes(data.get(0..4)?.try_into().ok()?);
+        let target = match tag {
+            TAG_SYMLINK => link_target(data, 12),
+            TAG_MOUNT_POINT => link_target(data, 8),
+            _ => None,
+        };
+        Some(Self { tag, target })
+    }
+
+    /// Whether a symlink's target is relative to the link's directory
+    pub fn is_relative(&self) -> bool {
+        self.tag == TAG_SYMLINK
+            && self.target.as_deref().is_some_and(|target| {
+                !target.starts_with('\\') && target.as_bytes().get(1) != Some(&b':')
+            })
+    }
+}
+
+impl fmt::Display for ReparsePoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.target {
+            Some(target) => write!(f, "{} -> {target}", self.kind()),
+            None => write!(f, "{}", self.kind()),
+        }
+    }
+}
+
+/// The reparse point of an entry, when it has one stored in its record
+pub fn reparse_point(entry: &MftEntry) -> Option<ReparsePoint> {
+    for attribute in entry.iter_attributes() {
+        let Ok(attribute) = attribute else { continue };
+        if attribute.header.type_code != MftAttributeType::ReparsePoint {
+            continue;
+        }
+        let MftAttributeContent::Raw(raw) = &attribute.data else {
+            return None;
+        };
+        return ReparsePoint::parse(&raw.data);
+    }
+    None
+}
+
+/// The print name of a symlink or mount point buffer, whose path buffer starts `path_start`
+/// bytes into the reparse data; falls back to the substitute name without its `\??\` prefix
+fn link_target(data: &[u8], path_start: usize) -> Option<String> {
+    let field =
+        |at: usize| Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?) as usize);
+    let substitute = (field(8)?, field(10)?);
+    let print = (field(12)?, field(14)?);
+    if path_start == 12 {
+        let flags = u32::from_le_bytes(data.get(16..20)?.try_into().ok()?);
+        // Relative targets have no `\??\` form to fall back on
+        if flags & SYMLINK_RELATIVE != 0 {
+            return utf16_at(data, 8 + path_start, print);
+        }
+    }
+    let buffer = 8 + path_start;
+    match utf16_at(data, buffer, print) {
+        Some(name) if !name.is_empty() => Some(name),
+        _ => utf16_at(data, buffer, substitute).map(|name| {
+            name.strip_prefix(r"\??\")
+                .map(str::to_string)
+                .unwrap_or(name)
+        }),
+    }
+}
+
+fn utf16_at(data: &[u8], buffer: usize, (offset, len): (usize, usize)) -> Option<String> {
+    let start = buffer + offset;
+    let units: Vec<u16> = data
+        .get(start..start + len)?
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    String::from_utf16(&units).ok()
+}
+
+/// How directory rollups treat symlinks and junctions, whose target's bytes are already
+/// counted wherever the target lives
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum ReparsePolicy {
+    /// Add the target's size below the link too, like `du -L`; the same bytes are counted twice
+    Follow,
+    /// Leave links out of the rollups altogether
+    Skip,
+    /// List links but count no bytes for them
+    #[default]
+    CountOnly,
+}
+
+impl ReparsePolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReparsePolicy::Follow => "follow",
+            ReparsePolicy::Skip => "skip",
+            ReparsePolicy::CountOnly => "count-only",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReparseKind;
+    use super::ReparsePoint;
+
+    /// A `REPARSE_DATA_BUFFER` for a link with the given substitute and print names
+    fn buffer(tag: u32, flags: Option<u32>, substitute: &str, print: &str) -> Vec<u8> {
+        let substitute: Vec<u8> = substitute
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let print: Vec<u8> = print.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let mut data = tag.to_le_bytes().to_vec();
+        data.extend([0; 4]);
+        for field in [0, substitute.len(), substitute.len(), print.len()] {
+            data.extend((field as u16).to_le_bytes());
+        }
+        if let Some(flags) = flags {
+            data.extend(flags.to_le_bytes());
+        }
+        data.extend(substitute);
+        data.extend(print);
+        data
+    }
+
+    #[test]
+    fn reads_link_targets() {
+        let junction =
+            ReparsePoint::parse(&buffer(0xA000_0003, None, r"\??\D:\Games", "")).unwrap();
+        assert_eq!(junction.kind(), ReparseKind::Junction);
+        assert_eq!(junction.target.as_deref(), Some(r"D:\Games"));
+
+        let symlink = ReparsePoint::parse(&buffer(0xA000_000C, Some(1), r"..\x", r"..\x")).unwrap();
+        assert_eq!(symlink.kind(), ReparseKind::Symlink);
+        assert_eq!(symlink.target.as_deref(), Some(r"..\x"));
+        assert!(symlink.is_relative());
+    }
+
+    #[test]
+    fn recognises_every_cloud_tag() {
+        assert_eq!(ReparseKind::from_tag(0x9000_001A), ReparseKind::Cloud);
+        assert_eq!(ReparseKind::from_tag(0x9000_601A), ReparseKind::Cloud);
+        assert_eq!(ReparseKind::from_tag(0x8000_0017), ReparseKind::Other);
+    }
+}
@@ -0,0 +1,358 @@
+use chrono::DateTime;
+use chrono::NaiveDate;
+use chrono::Utc;
+use std::borrow::Cow;
+use unicode_normalization::IsNormalized;
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::is_nfc_quick;
+
+/// Comparison used by `size` and `modified` filters
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    fn holds<T: Ord>(&self, left: T, right: T) -> bool {
+        match self {
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Equal => left == right,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Greater => left > right,
+        }
+    }
+}
+
+/// One inline filter token from a search query
+#[derive(Clone, Debug, PartialEq)]
+pub enum SearchFilter {
+    /// `ext:iso` or `ext:iso,img`
+    Extension(Vec<String>),
+    /// `size>1gb`
+    Size(Comparison, u64),
+    /// `modified<2023-01-01`
+    Modified(Comparison, NaiveDate),
+}
+
+/// A search query split into the fuzzy term and inline filters.
+///
+/// Tokens that don't parse as filters stay part of the fuzzy term, so typing `size>` on the way
+/// to `size>1gb` doesn't hide everything.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchQuery {
+    pub fuzzy: String,
+    pub filters: Vec<SearchFilter>,
+}
+
+impl SearchQuery {
+    pub fn parse(query: &str) -> Self {
+        let mut fuzzy = Vec::new();
+        let mut filters = Vec::new();
+        for token in query.split_whitespace() {
+            match parse_filter(token) {
+                Some(filter) => filters.push(filter),
+                None => fuzzy.push(token),
+            }
+        }
+        Self {
+            fuzzy: fuzzy.join(" "),
+            filters,
+        }
+    }
+
+    pub fn has_filters(&self) -> bool {
+        !self.filters.is_empty()
+    }
+
+    /// Whether a file passes every filter. Entries without a modified time fail date filters.
+    pub fn matches(&self, path: &str, size: u64, modified: Option<DateTime<Utc>>) -> bool {
+        self.filters.iter().all(|filter| match filter {
+            SearchFilter::Extension(extensions) => {
+                let name = path.rsplit(['\\', '/']).next().unwrap_or(path);
+                name.rsplit_once('.')
+                    .is_some_and(|(_, ext)| extensions.iter().any(|wanted| same_name(wanted, ext)))
+            }
+            SearchFilter::Size(comparison, bytes) => comparison.holds(size, *bytes),
+            SearchFilter::Modified(comparison, date) => {
+                modified.is_some_and(|m| comparison.holds(m.date_naive(), *date))
+            }
+        })
+    }
+}
+
+/// Text in NFC, so a name typed with a precomposed `Ü` and one stored as `U` plus a combining
+/// diaeresis compare equal. Borrows when the text already is, as nearly every NTFS name is.
+pub fn nfc(text: &str) -> Cow<'_, str> {
+    match is_nfc_quick(text.chars()) {
+        IsNormalized::Yes => Cow::Borrowed(text),
+        _ => Cow::Owned(text.nfc().collect()),
+    }
+}
+
+/// Text folded for matching names the way Windows does: in NFC and lowercase, so `Über`,
+/// `über` and a decomposed `u\u{308}ber` all fold to the same string
+pub fn fold_name(text: &str) -> String {
+    text.nfc().flat_map(char::to_lowercase).collect()
+}
+
+/// Whether two names are equal once folded with [`fold_name`]
+pub fn same_name(a: &str, b: &str) -> bool {
+    a.nfc()
+        .flat_map(char::to_lowercase)
+        .eq(b.nfc().flat_map(char::to_lowercase))
+}
+
+/// A path's drive letter, if it starts with one, and the rest of it below the root with the
+/// outer backslashes trimmed, so `C:\Users\` gives `(Some('C'), "Users")`
+pub fn split_drive(path: &str) -> (Option<char>, &str) {
+    let (drive_letter, relative) = match path.as_bytes() {
+        [letter, b':', ..] if letter.is_ascii_alphabetic() => {
+            (Some(letter.to_ascii_uppercase() as char), &path[2..])
+        }
+        _ => (None, path),
+    };
+    (drive_letter, relative.trim_matches('\\'))
+}
+
+/// Whether a path is somewhere below a directory, ignoring case and composition as
+/// [`same_name`] does. A directory without a drive letter is looked for on every drive.
+pub fn is_under(path: &str, directory: &str) -> bool {
+    let (path_drive, path) = split_drive(path);
+    let (directory_drive, directory) = split_drive(directory);
+    if directory_drive.is_some() && path_drive != directory_drive {
+        return false;
+    }
+    if directory.is_empty() {
+        return true;
+    }
+    // A backslash starts a new character for NFC, so folding a path folds each component
+    fold_name(path)
+        .strip_prefix(&fold_name(directory))
+        .is_some_and(|rest| rest.starts_with('\\'))
+}
+
+/// How a fuzzy query narrows its candidates before the matcher scores them
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum PrefilterMode {
+    /// Every candidate is scored
+    #[default]
+    Off,
+    /// Skip paths missing a letter or digit of the query; never skips a path that would match
+    Chars,
+    /// Skip paths that don't contain each word of the query as written, ignoring case; fewer
+    /// matches than fuzzy matching finds
+    Words,
+}
+
+impl PrefilterMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PrefilterMode::Off => "off",
+            PrefilterMode::Chars => "chars",
+            PrefilterMode::Words => "words",
+        }
+    }
+}
+
+/// A cheap test a path has to pass before it's worth fuzzy matching against a query.
+///
+/// The query is split into words the way the matcher splits it. Negated words (`!tmp`) are
+/// ignored, and the `'`, `^` and `$` that anchor a word are dropped.
+#[derive(Clone, Debug)]
+pub struct Prefilter {
+    mode: PrefilterMode,
+    /// One bit per ASCII letter and digit of the query, as [`char_mask`] sets them
+    mask: u64,
+    /// Folded with [`fold_name`]
+    words: Vec<String>,
+}
+
+impl Prefilter {
+    pub fn new(mode: PrefilterMode, query: &str) -> Self {
+        let words: Vec<String> = query
+            .split_whitespace()
+            .filter(|word| !word.starts_with('!'))
+            .map(|word| {
+                let word = word.trim_start_matches(['\'', '^']);
+                fold_name(word.strip_suffix('$').unwrap_or(word))
+            })
+            .filter(|word| !word.is_empty())
+            .collect();
+        let mask = words.iter().fold(0, |mask, word| mask | char_mask(word));
+        Self { mode, mask, words }
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        match self.mode {
+            PrefilterMode::Off => true,
+            PrefilterMode::Chars => char_mask(path) & self.mask == self.mask,
+            PrefilterMode::Words => {
+                let path = fold_name(path);
+                self.words.iter().all(|word| path.contains(word.as_str()))
+            }
+        }
+    }
+}
+
+/// Bits for the ASCII letters and digits in `text`, ignoring case. Any other character sets
+/// every bit, as the matcher may read it as a letter, such as `é` for `e`.
+fn char_mask(text: &str) -> u64 {
+    let mut mask = 0;
+    for byte in text.bytes() {
+        mask |= match byte {
+            b'a'..=b'z' => 1 << (byte - b'a'),
+            b'A'..=b'Z' => 1 << (byte - b'A'),
+            b'0'..=b'9' => 1 << (26 + byte - b'0'),
+            0x80.. => u64::MAX,
+            _ => 0,
+        };
+    }
+    mask
+}
+
+fn parse_filter(token: &str) -> Option<SearchFilter> {
+    if let Some(extensions) = token.strip_prefix("ext:") {
+        let extensions: Vec<String> = extensions
+            .split(',')
+            .map(|e| e.trim_start_matches('.').to_string())
+            .filter(|e| !e.is_empty())
+            .collect();
+        return (!extensions.is_empty()).then_some(SearchFilter::Extension(extensions));
+    }
+    let (key, comparison, value) = split_comparison(token)?;
+    match key.to_ascii_lowercase().as_str() {
+        "size" => parse_size(value).map(|bytes| SearchFilter::Size(comparison, bytes)),
+        "modified" => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .ok()
+            .map(|date| SearchFilter::Modified(comparison, date)),
+        _ => None,
+    }
+}
+
+fn split_comparison(token: &str) -> Option<(&str, Comparison, &str)> {
+    let index = token.find(['<', '>', '='])?;
+    let (key, rest) = token.split_at(index);
+    let (comparison, value) = if let Some(value) = rest.strip_prefix(">=") {
+        (Comparison::GreaterOrEqual, value)
+    } else if let Some(value) = rest.strip_prefix("<=") {
+        (Comparison::LessOrEqual, value)
+    } else if let Some(value) = rest.strip_prefix('>') {
+        (Comparison::Greater, value)
+    } else if let Some(value) = rest.strip_prefix('<') {
+        (Comparison::Less, value)
+    } else {
+        (Comparison::Equal, rest.strip_prefix('=')?)
+    };
+    Some((key, comparison, value))
+}
+
+/// Parse sizes like `1gb`, `500MB`, `1.5GiB` or plain bytes
+pub fn parse_size(value: &str) -> Option<u64> {
+    let value = value.trim().to_ascii_lowercase();
+    // Exact, where going through f64 would round byte counts past 2^53
+    if let Ok(bytes) = value.parse() {
+        return Some(bytes);
+    }
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let multiplier: f64 = match unit {
+        "" | "b" => 1.0,
+        "k" | "kb" => 1e3,
+        "m" | "mb" => 1e6,
+        "g" | "gb" => 1e9,
+        "t" | "tb" => 1e12,
+        "kib" => 1024.0,
+        "mib" => 1024.0 * 1024.0,
+        "gib" => 1024.0 * 1024.0 * 1024.0,
+        "tib" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some((number * multiplier) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Comparison;
+    use super::Prefilter;
+    use super::PrefilterMode;
+    use super::SearchFilter;
+    use super::SearchQuery;
+    use super::fold_name;
+    use super::is_under;
+    use super::nfc;
+    use super::same_name;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn parses_filters_and_keeps_fuzzy_term() {
+        let query = SearchQuery::parse("backup ext:iso,img size>1gb modified<2023-01-01 size>");
+        assert_eq!(query.fuzzy, "backup size>");
+        assert_eq!(
+            query.filters,
+            vec![
+                SearchFilter::Extension(vec!["iso".to_string(), "img".to_string()]),
+                SearchFilter::Size(Comparison::Greater, 1_000_000_000),
+                SearchFilter::Modified(
+                    Comparison::Less,
+                    NaiveDate::from_ymd_opt(2023, 1, 1).unwrap()
+                ),
+            ]
+        );
+        assert!(query.matches(
+            "C:\\backup\\disk.ISO",
+            2_000_000_000,
+            "2022-06-01T00:00:00Z".parse().ok()
+        ));
+        assert!(!query.matches("C:\\backup\\disk.iso", 2_000_000_000, None));
+        assert!(!query.matches(
+            "C:\\backup\\disk.zip",
+            2_000_000_000,
+            "2022-06-01T00:00:00Z".parse().ok()
+        ));
+    }
+
+    #[test]
+    fn folds_case_and_composition_of_names() {
+        let decomposed = "U\u{308}ber.txt";
+        assert_eq!(nfc(decomposed), "\u{dc}ber.txt");
+        assert!(matches!(nfc("plain.txt"), std::borrow::Cow::Borrowed(_)));
+        assert_eq!(fold_name(decomposed), fold_name("über.TXT"));
+        assert!(same_name("ÜBER", "u\u{308}ber"));
+        assert!(!same_name("über", "uber"));
+        assert!(SearchQuery::parse("ext:MÖV").matches("C:\\clips\\a.mo\u{308}v", 0, None));
+    }
+
+    #[test]
+    fn tells_paths_under_a_directory() {
+        assert!(is_under("C:\\Users\\Alice\\notes.txt", "c:\\users\\"));
+        assert!(is_under("D:\\Users\\Alice", "\\USERS"));
+        assert!(is_under("C:\\Users", "C:\\"));
+        assert!(!is_under("D:\\Users\\Alice", "C:\\Users"));
+        assert!(!is_under("C:\\Users2\\Alice", "C:\\Users"));
+        assert!(!is_under("C:\\Users", "C:\\Users"));
+    }
+
+    #[test]
+    fn prefilters_paths_before_fuzzy_matching() {
+        let chars = Prefilter::new(PrefilterMode::Chars, "rprt 2024 !tmp");
+        assert!(chars.matches("C:\\Work\\Report-2024.docx"));
+        assert!(!chars.matches("C:\\Work\\Report-2023.docx"));
+        // The matcher may read é as e, so non-ASCII paths always pass
+        assert!(Prefilter::new(PrefilterMode::Chars, "cafe").matches("C:\\caf\u{e9}"));
+
+        let words = Prefilter::new(PrefilterMode::Words, "'REPORT ^c: docx$");
+        assert!(words.matches("C:\\Work\\report-2024.docx"));
+        assert!(!words.matches("C:\\Work\\rprt-2024.docx"));
+        assert!(Prefilter::new(PrefilterMode::Off, "anything").matches("C:\\"));
+    }
+}
@@ -0,0 +1,228 @@
+use crate::mft_size::record_data_size;
+use eyre::Context;
+use mft::MftParser;
+use mft::attribute::MftAttributeContent;
+use mft::entry::EntryFlags;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::path::Path;
+use std::path::PathBuf;
+use windows::Win32::Security::LookupAccountSidW;
+use windows::Win32::Security::PSID;
+use windows::Win32::Security::SID_NAME_USE;
+use windows::core::PCWSTR;
+use windows::core::PWSTR;
+
+/// Extension of the `$Secure:$SDS` dump kept next to an MFT dump
+pub const SECURITY_EXTENSION: &str = "sds";
+/// `$SDS` is written in 256 KiB blocks, each followed by a mirror copy of itself
+const SDS_BLOCK: usize = 0x40000;
+/// Hash, security id, offset and length in front of every descriptor in `$SDS`
+const SDS_ENTRY_HEADER: usize = 20;
+/// Stands in for the owner of files whose security id has no descriptor
+pub const UNKNOWN_OWNER: &str = "(unknown)";
+
+/// Path of the security descriptor dump that belongs to the given MFT dump
+pub fn security_path_for(mft_file: &Path) -> PathBuf {
+    let mut os = mft_file.as_os_str().to_os_string();
+    os.push(".");
+    os.push(SECURITY_EXTENSION);
+    PathBuf::from(os)
+}
+
+/// The files one account owns on a volume
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct OwnerUsage {
+    /// The owner's SID, like `S-1-5-32-544`, or [`UNKNOWN_OWNER`]
+    pub sid: String,
+    /// `DOMAIN\name` of the SID, when this machine can resolve it
+    pub account: Option<String>,
+    pub files: u64,
+    pub bytes: u64,
+    pub allocated_bytes: u64,
+}
+
+/// The owner SID of every security descriptor in an `$SDS` stream, by security id
+pub fn owner_sids(sds: &[u8]) -> HashMap<u32, Vec<u8>> {
+    let u32_at = |at: usize| Some(u32::from_le_bytes(sds.get(at..at + 4)?.try_into().ok()?));
+    let mut owners = HashMap::new();
+    // Only the first of each pair of blocks is read; the second mirrors it
+    for block in (0..sds.len()).step_by(SDS_BLOCK * 2) {
+        let block_end = (block + SDS_BLOCK).min(sds.len());
+        let mut at = block;
+        while at + SDS_ENTRY_HEADER <= block_end {
+            let (Some(security_id), Some(length)) = (u32_at(at + 4), u32_at(at + 16)) else {
+                break;
+            };
+            let length = length as usize;
+            // The rest of a block after its last descriptor is zeroed
+            if length < SDS_ENTRY_HEADER || at + length > block_end {
+                break;
+            }
+            let descriptor = &sds[at + SDS_ENTRY_HEADER..at + length];
+            if let Some(owner) = descriptor_owner(descriptor) {
+                owners.insert(security_id, owner.to_vec());
+            }
+            // Descriptors start on 16-byte boundaries
+            at += length.next_multiple_of(16);
+        }
+    }
+    owners
+}
+
+/// The owner SID of a self-relative security descriptor
+fn descriptor_owner(descriptor: &[u8]) -> Option<&[u8]> {
+    let offset = u32::from_le_bytes(descriptor.get(4..8)?.try_into().ok()?) as usize;
+    if offset == 0 {
+        return None;
+    }
+    let sub_authorities = *descriptor.get(offset + 1)? as usize;
+    descriptor.get(offset..offset + 8 + sub_authorities * 4)
+}
+
+/// A SID in its `S-1-5-21-...` form
+pub fn sid_string(sid: &[u8]) -> Option<String> {
+    let revision = *sid.first()?;
+    let sub_authorities = *sid.get(1)? as usize;
+    let authority = sid
+        .get(2..8)?
+        .iter()
+        .fold(0u64, |authority, byte| authority << 8 | *byte as u64);
+    let mut string = format!("S-{revision}-{authority}");
+    for sub_authority in sid.get(8..8 + sub_authorities * 4)?.chunks_exact(4) {
+        let sub_authority = u32::from_le_bytes(sub_authority.try_into().ok()?);
+        string.push_str(&format!("-{sub_authority}"));
+    }
+    Some(string)
+}
+
+/// The `DOMAIN\name` of a SID as this machine knows it; `None` for accounts of other machines
+/// or deleted ones
+pub fn account_name(sid: &[u8]) -> Option<String> {
+    let sid = PSID(sid.as_ptr() as *mut c_void);
+    let mut name = [0u16; 256];
+    let mut domain = [0u16; 256];
+    let mut name_len = name.len() as u32;
+    let mut domain_len = domain.len() as u32;
+    let mut kind = SID_NAME_USE::default();
+    unsafe {
+        LookupAccountSidW(
+            PCWSTR::null(),
+            sid,
+            Some(PWSTR(name.as_mut_ptr())),
+            &mut name_len,
+            Some(PWSTR(domain.as_mut_ptr())),
+            &mut domain_len,
+            &mut kind,
+        )
+        .ok()?;
+    }
+    let name = String::from_utf16_lossy(&name[..name_len as usize]);
+    let domain = String::from_utf16_lossy(&domain[..domain_len as usize]);
+    Some(if domain.is_empty() {
+        name
+    } else {
+        format!("{domain}\\{name}")
+    })
+}
+
+/// Add up the files of a dump by the owner in their security descriptor, using the `$SDS`
+/// dumped next to `mft_file`. Each record counts once, however many names it has; rows are
+/// sorted by allocated bytes, largest first.
+pub fn usage_by_owner(mft_file: &Path) -> eyre::Result<Vec<OwnerUsage>> {
+    let security_file = security_path_for(mft_file);
+    let sds = std::fs::read(&security_file).with_context(|| {
+        format!(
+            "Failed to read the security descriptors {}; dump the MFT again to write them",
+            security_file.display()
+        )
+    })?;
+    let owners = owner_sids(&sds);
+    let mut parser = MftParser::from_path(mft_file)
+        .map_err(|e| eyre::eyre!("Failed to open MFT {}: {}", mft_file.display(), e))?;
+
+    let mut by_security_id: HashMap<Option<u32>, OwnerUsage> = HashMap::new();
+    for record in 0..parser.get_entry_count() {
+        let Ok(entry) = parser.get_entry(record) else {
+            continue;
+        };
+        if !entry.header.flags.contains(EntryFlags::ALLOCATED)
+            || entry.header.base_reference.entry != 0
+            || entry.is_dir()
+        {
+            continue;
+        }
+        // Volumes from before NTFS 3.0 keep descriptors in each record and have no ids
+        let security_id =
+            entry
+                .iter_attributes()
+                .find_map(|attribute| match attribute.ok()?.data {
+                    MftAttributeContent::AttrX10(info) => Some(info.security_id),
+                    _ => None,
+                });
+        let data =
+            record_data_size(&entry, |record| parser.get_entry(record).ok()).unwrap_or_default();
+        let usage = by_security_id.entry(security_id).or_default();
+        usage.files += 1;
+        usage.bytes += data.logical;
+        usage.allocated_bytes += data.allocated;
+    }
+
+    // Different ids can have the same owner and different groups or permissions
+    let mut by_owner: HashMap<String, OwnerUsage> = HashMap::new();
+    for (security_id, usage) in by_security_id {
+        let sid = security_id.and_then(|id| owners.get(&id));
+        let sid_text = sid
+            .and_then(|sid| sid_string(sid))
+            .unwrap_or_else(|| UNKNOWN_OWNER.to_string());
+        let row = by_owner
+            .entry(sid_text.clone())
+            .or_insert_with(|| OwnerUsage {
+                sid: sid_text,
+                account: sid.and_then(|sid| account_name(sid)),
+                ..OwnerUsage::default()
+            });
+        row.files += usage.files;
+        row.bytes += usage.bytes;
+        row.allocated_bytes += usage.allocated_bytes;
+    }
+    let mut rows: Vec<OwnerUsage> = by_owner.into_values().collect();
+    rows.sort_by(|a, b| {
+        b.allocated_bytes
+            .cmp(&a.allocated_bytes)
+            .then_with(|| a.sid.cmp(&b.sid))
+    });
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::owner_sids;
+    use super::sid_string;
+
+    /// `S-1-5-32-544`, the local Administrators group
+    const ADMINISTRATORS: [u8; 16] = [1, 2, 0, 0, 0, 0, 0, 5, 32, 0, 0, 0, 32, 2, 0, 0];
+
+    #[test]
+    fn reads_owners_from_descriptors() {
+        let mut descriptor = vec![1, 0, 0x04, 0x80];
+        descriptor.extend(20u32.to_le_bytes());
+        descriptor.extend([0; 12]);
+        descriptor.extend(ADMINISTRATORS);
+        let length = 20 + descriptor.len() as u32;
+        let mut sds = 0u32.to_le_bytes().to_vec();
+        sds.extend(0x100u32.to_le_bytes());
+        sds.extend(0u64.to_le_bytes());
+        sds.extend(length.to_le_bytes());
+        sds.extend(descriptor);
+        sds.resize(0x40, 0);
+
+        let owners = owner_sids(&sds);
+        assert_eq!(owners.len(), 1);
+        assert_eq!(
+            sid_string(&owners[&0x100]),
+            Some("S-1-5-32-544".to_string())
+        );
+    }
+}
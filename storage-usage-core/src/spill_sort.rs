@@ -0,0 +1,193 @@
+//! Sorting more keys than a memory budget allows, for writing the path table of an index.
+//!
+//! Keys are held until they pass the budget, then sorted and written to a temporary file as a
+//! run. Finishing merges the runs and whatever is still held, a key from each at a time.
+//! [`TempFile`] names and cleans up those files, and anything else an index write keeps on disk.
+
+use eyre::Context;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+/// Heap bytes a held key costs besides its text: the `String` and the value beside it
+const HELD_OVERHEAD: usize = size_of::<(String, u32)>();
+
+/// Tells apart the temp files of writes going on at once, such as one per drive being indexed
+static NEXT_TEMP_FILE: AtomicUsize = AtomicUsize::new(0);
+
+/// Sorts `(key, value)` pairs by key, then value, holding about `budget` bytes of them at once
+pub struct SpillSort {
+    budget: Option<usize>,
+    held: Vec<(String, u32)>,
+    held_bytes: usize,
+    runs: Vec<TempFile>,
+}
+
+impl SpillSort {
+    /// A sort that spills once its pairs pass `budget` bytes, or never without one
+    pub fn new(budget: Option<usize>) -> Self {
+        Self {
+            budget,
+            held: Vec::new(),
+            held_bytes: 0,
+            runs: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, key: String, value: u32) -> eyre::Result<()> {
+        self.held_bytes += key.capacity() + HELD_OVERHEAD;
+        self.held.push((key, value));
+        if self.budget.is_some_and(|budget| self.held_bytes > budget) {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    /// Runs written to disk so far
+    pub fn runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// Hand every value to `each` in key order
+    pub fn finish(mut self, mut each: impl FnMut(u32) -> eyre::Result<()>) -> eyre::Result<()> {
+        self.held.sort_unstable();
+        if self.runs.is_empty() {
+            for (_, value) in &self.held {
+                each(*value)?;
+            }
+            return Ok(());
+        }
+        let mut readers = self
+            .runs
+            .iter()
+            .map(TempFile::reader)
+            .collect::<eyre::Result<Vec<_>>>()?;
+        let mut held = std::mem::take(&mut self.held).into_iter();
+        // The held pairs merge in as one more source, after the runs
+        let mut next = |source: usize| -> eyre::Result<Option<(String, u32)>> {
+            match readers.get_mut(source) {
+                Some(reader) => read_pair(reader),
+                None => Ok(held.next()),
+            }
+        };
+        let mut heads = BinaryHeap::new();
+        for source in 0..=self.runs.len() {
+            if let Some((key, value)) = next(source)? {
+                heads.push(Reverse((key, value, source)));
+            }
+        }
+        while let Some(Reverse((_, value, source))) = heads.pop() {
+            each(value)?;
+            if let Some((key, value)) = next(source)? {
+                heads.push(Reverse((key, value, source)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Sort the held pairs and write them out as a run
+    fn spill(&mut self) -> eyre::Result<()> {
+        self.held.sort_unstable();
+        // Owned before writing, so a failed write still removes the file
+        let (run, file) = TempFile::create("run")?;
+        let mut out = BufWriter::new(file);
+        for (key, value) in self.held.drain(..) {
+            out.write_all(&value.to_le_bytes())?;
+            out.write_all(&(key.len() as u32).to_le_bytes())?;
+            out.write_all(key.as_bytes())?;
+        }
+        out.flush()
+            .with_context(|| format!("writing spill file {}", run.path.display()))?;
+        self.runs.push(run);
+        self.held_bytes = 0;
+        Ok(())
+    }
+}
+
+/// A file in the temp directory, removed once dropped
+pub(crate) struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    /// Create a file named for this process and a number no other of its temp files has
+    pub(crate) fn create(extension: &str) -> eyre::Result<(Self, File)> {
+        let path = std::env::temp_dir().join(format!(
+            "storage-usage-{}-{}.{extension}",
+            std::process::id(),
+            NEXT_TEMP_FILE.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = File::create(&path)
+            .with_context(|| format!("creating spill file {}", path.display()))?;
+        Ok((Self { path }, file))
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn reader(&self) -> eyre::Result<BufReader<File>> {
+        let file = File::open(&self.path)
+            .with_context(|| format!("opening spill file {}", self.path.display()))?;
+        Ok(BufReader::new(file))
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn read_pair(reader: &mut impl Read) -> eyre::Result<Option<(String, u32)>> {
+    let mut value = [0u8; 4];
+    match reader.read_exact(&mut value) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len)?;
+    let mut key = vec![0u8; u32::from_le_bytes(len) as usize];
+    reader.read_exact(&mut key)?;
+    Ok(Some((String::from_utf8(key)?, u32::from_le_bytes(value))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpillSort;
+
+    #[test]
+    fn merges_spilled_runs_in_key_order() {
+        let keys: Vec<String> = (0..500u32)
+            .map(|i| format!("dir{}\\file{}", (i * 7919) % 23, i % 11))
+            .collect();
+        let mut expected: Vec<(String, u32)> = keys.iter().cloned().zip(0..).collect();
+        expected.sort();
+        let expected: Vec<u32> = expected.into_iter().map(|(_, value)| value).collect();
+
+        for budget in [None, Some(1), Some(2_000)] {
+            let mut sort = SpillSort::new(budget);
+            for (value, key) in keys.iter().enumerate() {
+                sort.push(key.clone(), value as u32).unwrap();
+            }
+            assert_eq!(sort.runs() > 0, budget.is_some());
+            let mut values = Vec::new();
+            sort.finish(|value| {
+                values.push(value);
+                Ok(())
+            })
+            .unwrap();
+            assert_eq!(values, expected, "budget {budget:?}");
+        }
+    }
+}
@@ -0,0 +1,444 @@
+use crate::win_handles::AutoClosingHandle;
+use crate::win_handles::get_drive_handle;
+use chrono::DateTime;
+use chrono::Utc;
+use eyre::Context;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::mem::size_of;
+use windows::Win32::Storage::FileSystem::FILE_FLAG_BACKUP_SEMANTICS;
+use windows::Win32::Storage::FileSystem::FILE_ID_DESCRIPTOR;
+use windows::Win32::Storage::FileSystem::FILE_ID_DESCRIPTOR_0;
+use windows::Win32::Storage::FileSystem::FILE_NAME_NORMALIZED;
+use windows::Win32::Storage::FileSystem::FILE_READ_ATTRIBUTES;
+use windows::Win32::Storage::FileSystem::FILE_SHARE_DELETE;
+use windows::Win32::Storage::FileSystem::FILE_SHARE_READ;
+use windows::Win32::Storage::FileSystem::FILE_SHARE_WRITE;
+use windows::Win32::Storage::FileSystem::FileIdType;
+use windows::Win32::Storage::FileSystem::GetFileSizeEx;
+use windows::Win32::Storage::FileSystem::GetFinalPathNameByHandleW;
+use windows::Win32::Storage::FileSystem::OpenFileById;
+use windows::Win32::System::IO::DeviceIoControl;
+use windows::Win32::System::Ioctl::FSCTL_QUERY_USN_JOURNAL;
+use windows::Win32::System::Ioctl::FSCTL_READ_USN_JOURNAL;
+use windows::Win32::System::Ioctl::READ_USN_JOURNAL_DATA_V0;
+use windows::Win32::System::Ioctl::USN_JOURNAL_DATA_V0;
+
+// USN_REASON_* values from winioctl.h
+const REASON_DATA_OVERWRITE: u32 = 0x0000_0001;
+const REASON_DATA_EXTEND: u32 = 0x0000_0002;
+const REASON_DATA_TRUNCATION: u32 = 0x0000_0004;
+const REASON_FILE_CREATE: u32 = 0x0000_0100;
+const REASON_FILE_DELETE: u32 = 0x0000_0200;
+const REASON_RENAME_OLD_NAME: u32 = 0x0000_1000;
+const REASON_RENAME_NEW_NAME: u32 = 0x0000_2000;
+const REASON_CLOSE: u32 = 0x8000_0000;
+/// `FILE_ATTRIBUTE_DIRECTORY`
+const ATTRIBUTE_DIRECTORY: u32 = 0x0010;
+/// 100ns intervals between 1601-01-01, where `FILETIME`s count from, and 1970-01-01
+const FILETIME_UNIX_EPOCH: i64 = 116_444_736_000_000_000;
+/// Bytes asked for per journal read
+const READ_BUFFER: usize = 64 * 1024;
+/// Stands in for the directory of a change whose parent can no longer be opened
+pub const UNKNOWN_PARENT: &str = "<unknown>";
+
+/// One `USN_RECORD_V2` from the change journal
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UsnRecord {
+    pub usn: i64,
+    /// Record number in the low 48 bits and sequence number in the high 16, like an MFT
+    /// file reference
+    pub file_reference: u64,
+    pub parent_reference: u64,
+    pub timestamp: Option<DateTime<Utc>>,
+    /// `USN_REASON_*` bits of every change made since the file was opened
+    pub reason: u32,
+    pub attributes: u32,
+    pub name: String,
+}
+
+impl UsnRecord {
+    pub fn record_number(&self) -> u64 {
+        self.file_reference & 0xFFFF_FFFF_FFFF
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.attributes & ATTRIBUTE_DIRECTORY != 0
+    }
+
+    /// What happened to the file, once it's closed. A file that's created and written is only
+    /// reported as created, and one that's renamed and deleted only as deleted.
+    pub fn change(&self) -> Option<UsnChange> {
+        if self.reason & REASON_CLOSE == 0 {
+            return None;
+        }
+        let reason = self.reason;
+        if reason & REASON_FILE_DELETE != 0 {
+            Some(UsnChange::Deleted)
+        } else if reason & REASON_FILE_CREATE != 0 {
+            Some(UsnChange::Created)
+        } else if reason & REASON_RENAME_NEW_NAME != 0 {
+            Some(UsnChange::Renamed)
+        } else if reason & (REASON_DATA_EXTEND | REASON_DATA_TRUNCATION | REASON_DATA_OVERWRITE)
+            != 0
+        {
+            Some(UsnChange::SizeChanged)
+        } else {
+            None
+        }
+    }
+}
+
+/// The kinds of change `mft watch` reports
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UsnChange {
+    Created,
+    Deleted,
+    Renamed,
+    SizeChanged,
+}
+
+impl UsnChange {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UsnChange::Created => "created",
+            UsnChange::Deleted => "deleted",
+            UsnChange::Renamed => "renamed",
+            UsnChange::SizeChanged => "size-changed",
+        }
+    }
+}
+
+/// The records in the output of `FSCTL_READ_USN_JOURNAL`, after the USN to read from next
+pub fn parse_usn_records(buffer: &[u8]) -> (Option<i64>, Vec<UsnRecord>) {
+    let next_usn = buffer
+        .get(0..8)
+        .map(|bytes| i64::from_le_bytes(bytes.try_into().unwrap_or_default()));
+    let mut records = Vec::new();
+    let mut at = 8;
+    while let Some(length) = buffer
+        .get(at..at + 4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap_or_default()) as usize)
+    {
+        if length == 0 || at + length > buffer.len() {
+            break;
+        }
+        if let Some(record) = parse_usn_record(&buffer[at..at + length]) {
+            records.push(record);
+        }
+        at += length;
+    }
+    (next_usn, records)
+}
+
+/// A `USN_RECORD_V2`; other versions are skipped
+fn parse_usn_record(record: &[u8]) -> Option<UsnRecord> {
+    let u16_at = |at: usize| Some(u16::from_le_bytes(record.get(at..at + 2)?.try_into().ok()?));
+    let u32_at = |at: usize| Some(u32::from_le_bytes(record.get(at..at + 4)?.try_into().ok()?));
+    let u64_at = |at: usize| Some(u64::from_le_bytes(record.get(at..at + 8)?.try_into().ok()?));
+    if u16_at(4)? != 2 {
+        return None;
+    }
+    let name_length = u16_at(56)? as usize;
+    let name_offset = u16_at(58)? as usize;
+    let units: Vec<u16> = record
+        .get(name_offset..name_offset + name_length)?
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    let filetime = u64_at(32)? as i64;
+    Some(UsnRecord {
+        usn: u64_at(24)? as i64,
+        file_reference: u64_at(8)?,
+        parent_reference: u64_at(16)?,
+        timestamp: DateTime::from_timestamp_micros((filetime - FILETIME_UNIX_EPOCH) / 10),
+        reason: u32_at(40)?,
+        attributes: u32_at(52)?,
+        name: String::from_utf16_lossy(&units),
+    })
+}
+
+/// A change to a file, with its full path
+#[derive(Clone, Debug, Serialize)]
+pub struct UsnEvent {
+    pub timestamp: Option<DateTime<Utc>>,
+    pub change: UsnChange,
+    pub path: String,
+    /// The path before a rename, when the rename was seen
+    pub old_path: Option<String>,
+    pub record_number: u64,
+    pub is_dir: bool,
+    /// The size after a change to a file's data, when it can still be opened
+    pub size: Option<u64>,
+}
+
+/// Reads a volume's USN change journal from where it last left off. Reading needs an elevated
+/// process.
+pub struct UsnJournal {
+    drive_letter: char,
+    volume: AutoClosingHandle,
+    journal_id: u64,
+    next_usn: i64,
+    /// Paths of directories by file reference, as opening one by id is comparatively slow
+    directories: HashMap<u64, String>,
+    /// Paths before a rename by file reference, until the rename's closing record
+    renamed_from: HashMap<u64, String>,
+}
+
+impl UsnJournal {
+    /// Open the journal of a volume, positioned after its latest change
+    pub fn open(drive_letter: char) -> eyre::Result<Self> {
+        let mut journal = Self::open_at(drive_letter, None)?;
+        journal.next_usn = journal.query()?.NextUsn;
+        Ok(journal)
+    }
+
+    /// Open the journal of a volume to read the changes from `start_usn` on, or from the
+    /// oldest one it still holds
+    pub fn open_at(drive_letter: char, start_usn: Option<i64>) -> eyre::Result<Self> {
+        let volume = get_drive_handle(drive_letter)
+            .with_context(|| format!("Failed to open handle to drive {drive_letter}"))?;
+        let mut journal = Self {
+            drive_letter,
+            volume,
+            journal_id: 0,
+            next_usn: 0,
+            directories: HashMap::new(),
+            renamed_from: HashMap::new(),
+        };
+        let data = journal.query()?;
+        journal.journal_id = data.UsnJournalID;
+        journal.next_usn = start_usn.unwrap_or(data.FirstUsn);
+        Ok(journal)
+    }
+
+    /// Identifies the journal; a journal that was deleted and recreated gets a new id, and
+    /// USNs of the old one mean nothing to it
+    pub fn journal_id(&self) -> u64 {
+        self.journal_id
+    }
+
+    /// The USN the next read starts from
+    pub fn next_usn(&self) -> i64 {
+        self.next_usn
+    }
+
+    /// The oldest USN the journal still holds; changes before it were overwritten
+    pub fn first_usn(&self) -> eyre::Result<i64> {
+        Ok(self.query()?.FirstUsn)
+    }
+
+    fn query(&self) -> eyre::Result<USN_JOURNAL_DATA_V0> {
+        let mut data = USN_JOURNAL_DATA_V0::default();
+        let mut bytes_returned = 0u32;
+        unsafe {
+            DeviceIoControl(
+                *self.volume,
+                FSCTL_QUERY_USN_JOURNAL,
+                None,
+                0,
+                Some(&mut data as *mut _ as *mut c_void),
+                size_of::<USN_JOURNAL_DATA_V0>() as u32,
+                Some(&mut bytes_returned),
+                None,
+            )
+            .with_context(|| {
+                format!(
+                    "Failed to query the USN journal of drive {}; is it enabled?",
+                    self.drive_letter
+                )
+            })?;
+        }
+        Ok(data)
+    }
+
+    /// Read the records written since the last read. With `wait`, blocks until there's at
+    /// least one; otherwise returns an empty list once the journal is caught up.
+    pub fn read(&mut self, wait: bool) -> eyre::Result<Vec<UsnRecord>> {
+        let request = READ_USN_JOURNAL_DATA_V0 {
+            StartUsn: self.next_usn,
+            ReasonMask: u32::MAX,
+            ReturnOnlyOnClose: 0,
+            Timeout: 0,
+            BytesToWaitFor: wait as u64,
+            UsnJournalID: self.journal_id,
+        };
+        let mut buffer = vec![0u8; READ_BUFFER];
+        let mut bytes_returned = 0u32;
+        unsafe {
+            DeviceIoControl(
+                *self.volume,
+                FSCTL_READ_USN_JOURNAL,
+                Some(&request as *const _ as *const c_void),
+                size_of::<READ_USN_JOURNAL_DATA_V0>() as u32,
+                Some(buffer.as_mut_ptr() as *mut c_void),
+                buffer.len() as u32,
+                Some(&mut bytes_returned),
+                None,
+            )
+            .with_context(|| {
+                format!(
+                    "Failed to read the USN journal of drive {} from USN {}",
+                    self.drive_letter, self.next_usn
+                )
+            })?;
+        }
+        let (next_usn, records) = parse_usn_records(&buffer[..bytes_returned as usize]);
+        if let Some(next_usn) = next_usn {
+            self.next_usn = next_usn;
+        }
+        Ok(records)
+    }
+
+    /// Turn a record into an event with full paths, or `None` for records that only add to a
+    /// change still in progress
+    pub fn event(&mut self, record: &UsnRecord) -> Option<UsnEvent> {
+        let directory = self.directory_path(record.parent_reference);
+        let path = format!("{directory}\\{}", record.name);
+        if record.reason & REASON_RENAME_OLD_NAME != 0 {
+            self.renamed_from.insert(record.file_reference, path);
+            return None;
+        }
+        let change = record.change()?;
+        let old_path = self.renamed_from.remove(&record.file_reference);
+        if record.is_dir() {
+            match change {
+                UsnChange::Deleted => self.directories.remove(&record.file_reference),
+                _ => self.directories.insert(record.file_reference, path.clone()),
+            };
+            // Paths cached below a renamed directory are stale
+            if change == UsnChange::Renamed
+                && let Some(old_path) = &old_path
+            {
+                let old_prefix = format!("{old_path}\\");
+                self.directories
+                    .retain(|_, cached| !cached.starts_with(&old_prefix));
+            }
+        }
+        let size = match change {
+            UsnChange::SizeChanged | UsnChange::Created if !record.is_dir() => {
+                self.file_size(record.file_reference)
+            }
+            _ => None,
+        };
+        Some(UsnEvent {
+            timestamp: record.timestamp,
+            change,
+            path,
+            old_path,
+            record_number: record.record_number(),
+            is_dir: record.is_dir(),
+            size,
+        })
+    }
+
+    fn directory_path(&mut self, reference: u64) -> String {
+        if let Some(path) = self.directories.get(&reference) {
+            return path.clone();
+        }
+        let path = self.path_by_id(reference).unwrap_or_else(|| {
+            format!(
+                "{}:\\{UNKNOWN_PARENT}",
+                self.drive_letter.to_ascii_uppercase()
+            )
+        });
+        self.directories.insert(reference, path.clone());
+        path
+    }
+
+    fn open_by_id(&self, reference: u64) -> Option<AutoClosingHandle> {
+        let id = FILE_ID_DESCRIPTOR {
+            dwSize: size_of::<FILE_ID_DESCRIPTOR>() as u32,
+            Type: FileIdType,
+            Anonymous: FILE_ID_DESCRIPTOR_0 {
+                FileId: reference as i64,
+            },
+        };
+        let handle = unsafe {
+            OpenFileById(
+                *self.volume,
+                &id,
+                FILE_READ_ATTRIBUTES.0,
+                FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+                None,
+                FILE_FLAG_BACKUP_SEMANTICS,
+            )
+            .ok()?
+        };
+        Some(AutoClosingHandle::new(handle))
+    }
+
+    /// The current path of a file, which has to still exist
+    fn path_by_id(&self, reference: u64) -> Option<String> {
+        let handle = self.open_by_id(reference)?;
+        let mut buffer = vec![0u16; 32 * 1024];
+        let length =
+            unsafe { GetFinalPathNameByHandleW(*handle, &mut buffer, FILE_NAME_NORMALIZED) };
+        if length == 0 || length as usize > buffer.len() {
+            return None;
+        }
+        let path = String::from_utf16_lossy(&buffer[..length as usize]);
+        Some(path.strip_prefix(r"\\?\").unwrap_or(&path).to_string())
+    }
+
+    fn file_size(&self, reference: u64) -> Option<u64> {
+        let handle = self.open_by_id(reference)?;
+        let mut size = 0i64;
+        unsafe { GetFileSizeEx(*handle, &mut size).ok()? };
+        Some(size as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::REASON_CLOSE;
+    use super::REASON_DATA_EXTEND;
+    use super::REASON_FILE_CREATE;
+    use super::UsnChange;
+    use super::parse_usn_records;
+
+    fn record(reference: u64, reason: u32, name: &str) -> Vec<u8> {
+        let name: Vec<u8> = name.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let length = (60 + name.len()).next_multiple_of(8);
+        let mut record = (length as u32).to_le_bytes().to_vec();
+        record.extend(2u16.to_le_bytes());
+        record.extend(0u16.to_le_bytes());
+        record.extend(reference.to_le_bytes());
+        record.extend((5u64 | 5 << 48).to_le_bytes());
+        record.extend(4096i64.to_le_bytes());
+        // 2024-01-01T00:00:00Z
+        record.extend(133_485_408_000_000_000i64.to_le_bytes());
+        record.extend(reason.to_le_bytes());
+        record.extend([0; 8]);
+        record.extend(0x20u32.to_le_bytes());
+        record.extend((name.len() as u16).to_le_bytes());
+        record.extend(60u16.to_le_bytes());
+        record.extend(name);
+        record.resize(length, 0);
+        record
+    }
+
+    #[test]
+    fn reads_records_after_the_next_usn() {
+        let mut buffer = 8192i64.to_le_bytes().to_vec();
+        buffer.extend(record(40 | 3 << 48, REASON_FILE_CREATE, "new.txt"));
+        buffer.extend(record(
+            40 | 3 << 48,
+            REASON_FILE_CREATE | REASON_DATA_EXTEND | REASON_CLOSE,
+            "new.txt",
+        ));
+        let (next_usn, records) = parse_usn_records(&buffer);
+        assert_eq!(next_usn, Some(8192));
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].record_number(), 40);
+        assert_eq!(records[1].name, "new.txt");
+        assert_eq!(
+            records[1].timestamp.map(|t| t.to_rfc3339()),
+            Some("2024-01-01T00:00:00+00:00".to_string())
+        );
+        assert_eq!(records[0].change(), None);
+        assert_eq!(records[1].change(), Some(UsnChange::Created));
+    }
+}
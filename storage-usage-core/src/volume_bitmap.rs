@@ -0,0 +1,163 @@
+use crate::mft_size::cluster_size;
+use crate::mft_size::sparse_clusters;
+use eyre::Context;
+use mft::MftParser;
+use mft::attribute::MftAttributeContent;
+use mft::attribute::MftAttributeType;
+use mft::attribute::header::ResidentialHeader;
+use mft::entry::EntryFlags;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Extension of the `$Bitmap` dump kept next to an MFT dump
+pub const BITMAP_EXTENSION: &str = "bitmap";
+/// Records below this number are reserved for the filesystem's own metadata files
+const FIRST_USER_RECORD: u64 = 24;
+/// `$Extend`, the directory holding the metadata files added after the first 24 records
+const EXTEND_RECORD: u64 = 11;
+
+/// Path of the `$Bitmap` dump that belongs to the given MFT dump
+pub fn bitmap_path_for(mft_file: &Path) -> PathBuf {
+    let mut os = mft_file.as_os_str().to_os_string();
+    os.push(".");
+    os.push(BITMAP_EXTENSION);
+    PathBuf::from(os)
+}
+
+/// The clusters a volume's `$Bitmap` marks in use, next to the clusters its records account for
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct VolumeUsage {
+    pub bytes_per_cluster: u64,
+    /// Clusters set in `$Bitmap`
+    pub bitmap_clusters: u64,
+    /// Clusters of the unnamed `$DATA` of files, which their allocated sizes add up to
+    pub file_data_clusters: u64,
+    /// Clusters of every attribute of the metadata files, such as `$MFT`, `$LogFile` and the
+    /// files in `$Extend`
+    pub metadata_clusters: u64,
+    /// Clusters of the other attributes of files and directories: directory indexes,
+    /// alternate data streams and whatever else outgrew its record
+    pub other_attribute_clusters: u64,
+}
+
+impl VolumeUsage {
+    /// Clusters that some record's attributes take
+    pub fn accounted_clusters(&self) -> u64 {
+        self.file_data_clusters + self.metadata_clusters + self.other_attribute_clusters
+    }
+
+    /// Clusters in use that no record accounts for, such as those of records that failed to
+    /// parse. Negative when records claim more than the bitmap, which happens when files
+    /// changed between reading the bitmap and reading the MFT.
+    pub fn unaccounted_clusters(&self) -> i64 {
+        self.bitmap_clusters as i64 - self.accounted_clusters() as i64
+    }
+
+    pub fn bytes(&self, clusters: u64) -> u64 {
+        clusters * self.bytes_per_cluster
+    }
+}
+
+/// Count the clusters a `$Bitmap` marks in use
+pub fn used_clusters(bitmap: &[u8]) -> u64 {
+    bitmap.iter().map(|byte| byte.count_ones() as u64).sum()
+}
+
+/// Compare the `$Bitmap` dumped next to `mft_file` with the clusters taken by the non-resident
+/// attributes of the dump's records in use
+pub fn check_usage(mft_file: &Path) -> eyre::Result<VolumeUsage> {
+    let bitmap_file = bitmap_path_for(mft_file);
+    let bitmap = std::fs::read(&bitmap_file).with_context(|| {
+        format!(
+            "Failed to read the volume bitmap {}; dump the MFT again to write it",
+            bitmap_file.display()
+        )
+    })?;
+    let mut parser = MftParser::from_path(mft_file)
+        .map_err(|e| eyre::eyre!("Failed to open MFT {}: {}", mft_file.display(), e))?;
+
+    let mut bytes_per_cluster = None;
+    // Clusters of unnamed $DATA and of everything else, per base record
+    let mut clusters: HashMap<u64, (u64, u64)> = HashMap::new();
+    let mut metadata: HashSet<u64> = HashSet::new();
+    for entry in parser.iter_entries() {
+        let Ok(entry) = entry else { continue };
+        if !entry.header.flags.contains(EntryFlags::ALLOCATED) {
+            continue;
+        }
+        let record_number = entry.header.record_number;
+        let base_record = match entry.header.base_reference.entry {
+            0 => record_number,
+            base_record => base_record,
+        };
+        let totals = clusters.entry(base_record).or_default();
+        for attribute in entry.iter_attributes() {
+            let Ok(attribute) = attribute else { continue };
+            if let MftAttributeContent::AttrX30(filename_attr) = &attribute.data
+                && filename_attr.parent.entry == EXTEND_RECORD
+                && filename_attr.name.starts_with('$')
+            {
+                metadata.insert(base_record);
+            }
+            let ResidentialHeader::NonResident(header) = &attribute.header.residential_header
+            else {
+                continue;
+            };
+            let MftAttributeContent::DataRun(runs) = &attribute.data else {
+                continue;
+            };
+            let runs = runs.data_runs.as_slice();
+            let is_data = attribute.header.type_code == MftAttributeType::DATA
+                && attribute.header.name_size == 0;
+            if is_data && record_number == 0 && bytes_per_cluster.is_none() {
+                bytes_per_cluster = cluster_size(header, runs);
+            }
+            let used = runs.iter().map(|run| run.lcn_length).sum::<u64>() - sparse_clusters(runs);
+            if is_data {
+                totals.0 += used;
+            } else {
+                totals.1 += used;
+            }
+        }
+    }
+
+    let bytes_per_cluster = bytes_per_cluster
+        .ok_or_else(|| eyre::eyre!("Couldn't work out the cluster size from the runs of $MFT"))?;
+    let mut usage = VolumeUsage {
+        bytes_per_cluster,
+        bitmap_clusters: used_clusters(&bitmap),
+        ..VolumeUsage::default()
+    };
+    for (base_record, (data, other)) in clusters {
+        if base_record < FIRST_USER_RECORD || metadata.contains(&base_record) {
+            usage.metadata_clusters += data + other;
+        } else {
+            usage.file_data_clusters += data;
+            usage.other_attribute_clusters += other;
+        }
+    }
+    Ok(usage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VolumeUsage;
+    use super::used_clusters;
+
+    #[test]
+    fn counts_set_bits_against_accounted_clusters() {
+        let usage = VolumeUsage {
+            bytes_per_cluster: 4096,
+            bitmap_clusters: used_clusters(&[0xFF, 0x0F, 0x01]),
+            file_data_clusters: 6,
+            metadata_clusters: 2,
+            other_attribute_clusters: 1,
+        };
+        assert_eq!(usage.bitmap_clusters, 13);
+        assert_eq!(usage.unaccounted_clusters(), 4);
+        assert_eq!(usage.bytes(usage.accounted_clusters()), 9 * 4096);
+    }
+}
@@ -14,6 +14,11 @@ use windows::Win32::Storage::FileSystem::OPEN_EXISTING;
 
 /// Auto-closing handle wrapper
 pub struct AutoClosingHandle(HANDLE);
+impl AutoClosingHandle {
+    pub fn new(handle: HANDLE) -> Self {
+        Self(handle)
+    }
+}
 impl Deref for AutoClosingHandle {
     type Target = HANDLE;
     fn deref(&self) -> &Self::Target {
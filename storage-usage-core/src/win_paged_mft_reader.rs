@@ -0,0 +1,139 @@
+use crate::win_handles::AutoClosingHandle;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use tracing::debug;
+use windows::Win32::Storage::FileSystem::FILE_BEGIN;
+use windows::Win32::Storage::FileSystem::ReadFile;
+use windows::Win32::Storage::FileSystem::SetFilePointerEx;
+
+/// A contiguous piece of the MFT on disk
+#[derive(Debug, Clone, Copy)]
+pub struct MftExtent {
+    /// Virtual byte offset of this extent within the MFT
+    pub virtual_offset: u64,
+    /// Physical byte offset of this extent on the volume
+    pub physical_offset: u64,
+    /// Length of the extent in bytes
+    pub length: u64,
+}
+
+/// A reader that paginates access to the MFT on a live volume by reading in chunks.
+/// It maps virtual positions (0..total_size) onto the physical extents described by the MFT's data runs,
+/// so fragmented MFTs read the same as a dump would.
+pub struct PagedMftReader {
+    handle: AutoClosingHandle,
+    extents: Vec<MftExtent>,
+    buffer: Vec<u8>,
+    buffer_start: u64, // Virtual byte offset where the buffer starts in the MFT
+    buffer_end: u64,   // Virtual byte offset where the buffer ends in the MFT
+    current_pos: u64,  // Current virtual read position in the MFT
+    buffer_capacity: usize, // Size of each buffer chunk
+    total_size: u64,   // Total MFT size
+}
+
+impl PagedMftReader {
+    /// Creates a new `PagedMftReader`.
+    pub fn new(handle: AutoClosingHandle, extents: Vec<MftExtent>, buffer_capacity: usize) -> Self {
+        let total_size = extents
+            .iter()
+            .map(|e| e.virtual_offset + e.length)
+            .max()
+            .unwrap_or(0);
+        Self {
+            handle,
+            extents,
+            buffer: Vec::with_capacity(buffer_capacity),
+            buffer_start: 0,
+            buffer_end: 0,
+            current_pos: 0,
+            buffer_capacity,
+            total_size,
+        }
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.total_size
+    }
+
+    /// Fills the buffer starting from `current_pos`, never crossing an extent boundary.
+    fn fill_buffer(&mut self) -> std::io::Result<()> {
+        self.buffer.clear();
+        self.buffer_start = self.current_pos;
+        self.buffer_end = self.current_pos;
+
+        let Some(extent) = self.extents.iter().find(|e| {
+            self.current_pos >= e.virtual_offset && self.current_pos < e.virtual_offset + e.length
+        }) else {
+            return Ok(()); // past the end
+        };
+
+        let within = self.current_pos - extent.virtual_offset;
+        let physical_offset = extent.physical_offset + within;
+        let to_read = (extent.length - within).min(self.buffer_capacity as u64) as usize;
+
+        unsafe {
+            SetFilePointerEx(*self.handle, physical_offset as i64, None, FILE_BEGIN)
+                .map_err(std::io::Error::other)?;
+        }
+
+        self.buffer.resize(to_read, 0);
+        let mut bytes_read = 0u32;
+        unsafe {
+            ReadFile(
+                *self.handle,
+                Some(self.buffer.as_mut_slice()),
+                Some(&mut bytes_read),
+                None,
+            )
+            .map_err(std::io::Error::other)?;
+        }
+
+        self.buffer.truncate(bytes_read as usize);
+        self.buffer_end = self.current_pos + bytes_read as u64;
+
+        debug!(
+            "Filled buffer: virtual_start={} virtual_end={} physical_offset={} bytes_read={}",
+            self.buffer_start, self.buffer_end, physical_offset, bytes_read
+        );
+
+        Ok(())
+    }
+}
+
+impl Read for PagedMftReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        // If current_pos is outside the buffer, refill it
+        if self.current_pos < self.buffer_start || self.current_pos >= self.buffer_end {
+            self.fill_buffer()?;
+            if self.buffer.is_empty() {
+                return Ok(0); // EOF
+            }
+        }
+
+        let buffer_offset = (self.current_pos - self.buffer_start) as usize;
+        let available = self.buffer.len().saturating_sub(buffer_offset);
+        let to_read = buf.len().min(available);
+        buf[..to_read].copy_from_slice(&self.buffer[buffer_offset..buffer_offset + to_read]);
+        self.current_pos += to_read as u64;
+        Ok(to_read)
+    }
+}
+
+impl Seek for PagedMftReader {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let new_pos = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.total_size.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.current_pos.checked_add_signed(offset),
+        };
+        let new_pos = new_pos.filter(|p| *p <= self.total_size).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Seek position out of bounds",
+            )
+        })?;
+        self.current_pos = new_pos;
+        Ok(self.current_pos)
+    }
+}
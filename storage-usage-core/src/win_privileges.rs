@@ -0,0 +1,203 @@
+use crate::win_handles::AutoClosingHandle;
+use eyre::Context;
+use serde::Serialize;
+use std::ffi::c_void;
+use std::mem::size_of;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use tracing::info;
+use windows::Win32::Foundation::HANDLE;
+use windows::Win32::Foundation::LUID;
+use windows::Win32::Security::AdjustTokenPrivileges;
+use windows::Win32::Security::CreateWellKnownSid;
+use windows::Win32::Security::GetLengthSid;
+use windows::Win32::Security::GetTokenInformation;
+use windows::Win32::Security::LUID_AND_ATTRIBUTES;
+use windows::Win32::Security::LookupPrivilegeValueW;
+use windows::Win32::Security::PSID;
+use windows::Win32::Security::SE_BACKUP_NAME;
+use windows::Win32::Security::SE_CHANGE_NOTIFY_NAME;
+use windows::Win32::Security::SE_PRIVILEGE_ENABLED;
+use windows::Win32::Security::SE_PRIVILEGE_REMOVED;
+use windows::Win32::Security::SE_RESTORE_NAME;
+use windows::Win32::Security::SE_SECURITY_NAME;
+use windows::Win32::Security::SID_AND_ATTRIBUTES;
+use windows::Win32::Security::SetTokenInformation;
+use windows::Win32::Security::TOKEN_ACCESS_MASK;
+use windows::Win32::Security::TOKEN_ADJUST_DEFAULT;
+use windows::Win32::Security::TOKEN_ADJUST_PRIVILEGES;
+use windows::Win32::Security::TOKEN_MANDATORY_LABEL;
+use windows::Win32::Security::TOKEN_PRIVILEGES;
+use windows::Win32::Security::TOKEN_QUERY;
+use windows::Win32::Security::TokenIntegrityLevel;
+use windows::Win32::Security::TokenPrivileges;
+use windows::Win32::Security::WinMediumLabelSid;
+use windows::Win32::System::SystemServices::SE_GROUP_INTEGRITY;
+use windows::Win32::System::Threading::GetCurrentProcess;
+use windows::Win32::System::Threading::OpenProcessToken;
+use windows::core::PCWSTR;
+
+/// Largest SID in bytes
+const SECURITY_MAX_SID_SIZE: usize = 68;
+
+/// Whether privileges are dropped once the volume is open; `--drop-privileges` turns this on
+static DROP_PRIVILEGES: AtomicBool = AtomicBool::new(false);
+
+/// Whether the current token holds a privilege, and whether it is switched on
+#[derive(Debug, Clone, Serialize)]
+pub struct PrivilegeStatus {
+    pub name: &'static str,
+    /// Held by the token, so the process can enable it
+    pub available: bool,
+    pub enabled: bool,
+}
+
+/// The privileges raw MFT access leans on: backup and restore for reading system files
+/// regardless of their security, and security for reading their SACLs
+pub fn mft_privilege_statuses() -> eyre::Result<Vec<PrivilegeStatus>> {
+    let token = open_process_token(TOKEN_QUERY)?;
+    let held = token_privileges(*token)?;
+    [
+        ("SeBackupPrivilege", SE_BACKUP_NAME),
+        ("SeRestorePrivilege", SE_RESTORE_NAME),
+        ("SeSecurityPrivilege", SE_SECURITY_NAME),
+    ]
+    .into_iter()
+    .map(|(name, privilege)| {
+        let luid = lookup_privilege(privilege)?;
+        let found = held.iter().find(|p| same_luid(&p.Luid, &luid));
+        Ok(PrivilegeStatus {
+            name,
+            available: found.is_some(),
+            enabled: found.is_some_and(|p| p.Attributes.contains(SE_PRIVILEGE_ENABLED)),
+        })
+    })
+    .collect()
+}
+
+/// Drop privileges once the next volume is open; the CLI turns this on for `--drop-privileges`
+pub fn configure_privilege_dropping(enabled: bool) {
+    DROP_PRIVILEGES.store(enabled, Ordering::Relaxed);
+}
+
+/// Privileges are dropped by the first volume opened, so later volumes in the same process
+/// could no longer be opened; refuse up front instead
+pub fn ensure_single_volume(volume_count: usize) -> eyre::Result<()> {
+    if DROP_PRIVILEGES.load(Ordering::Relaxed) && volume_count > 1 {
+        return Err(eyre::eyre!(
+            "--drop-privileges reads one volume per run, but {volume_count} were requested"
+        ));
+    }
+    Ok(())
+}
+
+/// Call once the volume handle is open and before MFT bytes are parsed. Handles opened earlier
+/// keep working; nothing opened later gets backup semantics or high integrity access.
+pub fn drop_privileges_if_requested() -> eyre::Result<()> {
+    if !DROP_PRIVILEGES.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    let token = open_process_token(TOKEN_ADJUST_PRIVILEGES | TOKEN_ADJUST_DEFAULT | TOKEN_QUERY)?;
+    let removed = remove_privileges(*token)?;
+    lower_integrity_to_medium(*token)?;
+    info!("Removed {removed} privileges and lowered the integrity level to medium");
+    Ok(())
+}
+
+fn open_process_token(access: TOKEN_ACCESS_MASK) -> eyre::Result<AutoClosingHandle> {
+    let mut token = HANDLE::default();
+    unsafe { OpenProcessToken(GetCurrentProcess(), access, &mut token) }
+        .wrap_err("Failed to open process token")?;
+    Ok(AutoClosingHandle::new(token))
+}
+
+fn lookup_privilege(name: PCWSTR) -> eyre::Result<LUID> {
+    let mut luid = LUID::default();
+    unsafe { LookupPrivilegeValueW(None, name, &mut luid) }
+        .wrap_err("Failed to look up a privilege")?;
+    Ok(luid)
+}
+
+fn same_luid(a: &LUID, b: &LUID) -> bool {
+    a.LowPart == b.LowPart && a.HighPart == b.HighPart
+}
+
+/// Every privilege the token holds, enabled or not
+fn token_privileges(token: HANDLE) -> eyre::Result<Vec<LUID_AND_ATTRIBUTES>> {
+    let mut needed = 0u32;
+    let _ = unsafe { GetTokenInformation(token, TokenPrivileges, None, 0, &mut needed) };
+    // u64 keeps the buffer aligned for TOKEN_PRIVILEGES
+    let mut buffer = vec![0u64; (needed as usize).div_ceil(size_of::<u64>())];
+    unsafe {
+        GetTokenInformation(
+            token,
+            TokenPrivileges,
+            Some(buffer.as_mut_ptr() as *mut c_void),
+            needed,
+            &mut needed,
+        )
+    }
+    .wrap_err("Failed to list token privileges")?;
+    let privileges = unsafe {
+        let header = &*(buffer.as_ptr() as *const TOKEN_PRIVILEGES);
+        std::slice::from_raw_parts(header.Privileges.as_ptr(), header.PrivilegeCount as usize)
+    };
+    Ok(privileges.to_vec())
+}
+
+/// Permanently remove every privilege but SeChangeNotify, which directory traversal relies on
+fn remove_privileges(token: HANDLE) -> eyre::Result<usize> {
+    let keep = lookup_privilege(SE_CHANGE_NOTIFY_NAME)?;
+    let mut removed = 0;
+    for privilege in token_privileges(token)? {
+        if same_luid(&privilege.Luid, &keep) {
+            continue;
+        }
+        let removal = TOKEN_PRIVILEGES {
+            PrivilegeCount: 1,
+            Privileges: [LUID_AND_ATTRIBUTES {
+                Luid: privilege.Luid,
+                Attributes: SE_PRIVILEGE_REMOVED,
+            }],
+        };
+        unsafe {
+            AdjustTokenPrivileges(
+                token,
+                false,
+                Some(&removal),
+                size_of::<TOKEN_PRIVILEGES>() as u32,
+                None,
+                None,
+            )
+        }
+        .wrap_err("Failed to remove a token privilege")?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+/// Lower the token's mandatory label from high to medium
+fn lower_integrity_to_medium(token: HANDLE) -> eyre::Result<()> {
+    // u64 keeps the buffer aligned for the SID
+    let mut sid = [0u64; SECURITY_MAX_SID_SIZE.div_ceil(size_of::<u64>())];
+    let mut sid_size = size_of_val(&sid) as u32;
+    let psid = PSID(sid.as_mut_ptr() as *mut c_void);
+    unsafe { CreateWellKnownSid(WinMediumLabelSid, None, Some(psid), &mut sid_size) }
+        .wrap_err("Failed to create the medium integrity SID")?;
+    let label = TOKEN_MANDATORY_LABEL {
+        Label: SID_AND_ATTRIBUTES {
+            Sid: psid,
+            Attributes: SE_GROUP_INTEGRITY as u32,
+        },
+    };
+    unsafe {
+        SetTokenInformation(
+            token,
+            TokenIntegrityLevel,
+            &label as *const _ as *const c_void,
+            size_of::<TOKEN_MANDATORY_LABEL>() as u32 + GetLengthSid(psid),
+        )
+    }
+    .wrap_err("Failed to lower the integrity level")?;
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use crate::config_migration::ConfigDiagnostic;
+use std::path::PathBuf;
+use storage_usage_core::core_error::CoreError;
+
+/// Exit code for any failure without a more specific one below
+pub const EXIT_FAILURE: i32 = 1;
+
+/// Failures wrappers may want to tell apart, each with its own process exit code.
+///
+/// Returned inside `eyre::Report`s like every other error; [`exit_code_for`] finds them in the
+/// report's chain.
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    /// Exit code 2: raw volume access is needed, but the process isn't elevated and may not
+    /// relaunch itself elevated
+    #[error("{operation} needs raw volume access, which --no-elevate rules out. {hint}")]
+    NotElevated {
+        operation: String,
+        hint: &'static str,
+    },
+    /// Exit code 2: administrator rights are needed, but the process isn't elevated and may not
+    /// relaunch itself elevated
+    #[error("{operation} needs administrator rights, which --no-elevate rules out")]
+    NotAdmin { operation: String },
+    /// Exit code 2: relaunching elevated was refused or failed
+    #[error("Failed to relaunch as administrator: {0}")]
+    ElevationFailed(String),
+    /// Exit code 4: no cached dump matches the drives asked for
+    #[error("No cached MFT files found for pattern '{pattern}'. {hint}")]
+    NoCachedDumps {
+        pattern: String,
+        hint: &'static str,
+    },
+    /// Exit code 4: a dump given by path doesn't exist
+    #[error("File not found: {}", .0.display())]
+    DumpNotFound(PathBuf),
+    /// Exit code 5: no cache dir is configured
+    #[error("cache-dir is not configured. Use: storage-usage-v2.exe config set cache-dir .")]
+    CacheDirUnset,
+}
+
+impl AppError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AppError::NotElevated { .. }
+            | AppError::NotAdmin { .. }
+            | AppError::ElevationFailed(_) => 2,
+            AppError::NoCachedDumps { .. } | AppError::DumpNotFound(_) => 4,
+            AppError::CacheDirUnset => 5,
+        }
+    }
+}
+
+/// The exit code for a failed run: the first typed error in the report's chain decides, and
+/// anything else is [`EXIT_FAILURE`]. A volume that isn't NTFS (exit code 3, raised by the core
+/// library) and an unreadable config file (exit code 6) count as typed.
+pub fn exit_code_for(report: &eyre::Report) -> i32 {
+    for cause in report.chain() {
+        if let Some(error) = cause.downcast_ref::<AppError>() {
+            return error.exit_code();
+        }
+        if let Some(CoreError::NotNtfs { .. }) = cause.downcast_ref::<CoreError>() {
+            return 3;
+        }
+        if cause.downcast_ref::<ConfigDiagnostic>().is_some() {
+            return 6;
+        }
+    }
+    EXIT_FAILURE
+}
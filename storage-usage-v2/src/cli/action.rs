@@ -1,6 +1,11 @@
+use crate::cli::completions_action::CompletionsArgs;
 use crate::cli::config_action::ConfigArgs;
 use crate::cli::elevation_action::ElevationArgs;
+use crate::cli::metrics_action::MetricsArgs;
 use crate::cli::mft_action::MftArgs;
+use crate::cli::schedule_action::ScheduleArgs;
+use crate::cli::serve_action::ServeArgs;
+use crate::cli::service_action::ServiceArgs;
 use crate::to_args::ToArgs;
 use arbitrary::Arbitrary;
 use clap::Subcommand;
@@ -15,6 +20,16 @@ pub enum Action {
     Elevation(ElevationArgs),
     /// Application configuration
     Config(ConfigArgs),
+    /// Print a shell completion script
+    Completions(CompletionsArgs),
+    /// Windows service syncing on a schedule
+    Service(ServiceArgs),
+    /// Daily sync through Task Scheduler
+    Schedule(ScheduleArgs),
+    /// Answer lookups against cached indexes for other local tools
+    Serve(ServeArgs),
+    /// Export drive space and sync freshness as Prometheus metrics
+    Metrics(MetricsArgs),
 }
 
 impl Action {
@@ -23,6 +38,11 @@ impl Action {
             Action::Mft(args) => args.run(),
             Action::Elevation(args) => args.run(),
             Action::Config(args) => args.run(),
+            Action::Completions(args) => args.run(),
+            Action::Service(args) => args.run(),
+            Action::Schedule(args) => args.run(),
+            Action::Serve(args) => args.run(),
+            Action::Metrics(args) => args.run(),
         }
     }
 }
@@ -43,6 +63,26 @@ impl ToArgs for Action {
                 args.push("config".into());
                 args.extend(config_args.to_args());
             }
+            Action::Completions(completions_args) => {
+                args.push("completions".into());
+                args.extend(completions_args.to_args());
+            }
+            Action::Service(service_args) => {
+                args.push("service".into());
+                args.extend(service_args.to_args());
+            }
+            Action::Schedule(schedule_args) => {
+                args.push("schedule".into());
+                args.extend(schedule_args.to_args());
+            }
+            Action::Serve(serve_args) => {
+                args.push("serve".into());
+                args.extend(serve_args.to_args());
+            }
+            Action::Metrics(metrics_args) => {
+                args.push("metrics".into());
+                args.extend(metrics_args.to_args());
+            }
         }
         args
     }
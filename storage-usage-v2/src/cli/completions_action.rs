@@ -0,0 +1,147 @@
+use crate::cli::Cli;
+use crate::to_args::ToArgs;
+use arbitrary::Arbitrary;
+use clap::Args;
+use clap::Command;
+use clap::CommandFactory;
+use clap::ValueEnum;
+use clap_complete::Shell;
+use std::ffi::OsString;
+use std::io::Write;
+
+/// Shells completion scripts can be generated for
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum, Arbitrary)]
+pub enum CompletionShell {
+    /// PowerShell; evaluate the output from your $PROFILE
+    #[clap(name = "powershell")]
+    PowerShell,
+    /// cmd.exe through clink; save the output as a .lua file in a clink scripts directory
+    #[clap(name = "clink", alias = "cmd")]
+    Clink,
+    Bash,
+    Zsh,
+}
+
+impl CompletionShell {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CompletionShell::PowerShell => "powershell",
+            CompletionShell::Clink => "clink",
+            CompletionShell::Bash => "bash",
+            CompletionShell::Zsh => "zsh",
+        }
+    }
+}
+
+/// Arguments for printing a shell completion script
+#[derive(Args, Clone, Arbitrary, PartialEq, Debug)]
+pub struct CompletionsArgs {
+    /// Shell to print the completion script for
+    pub shell: CompletionShell,
+}
+
+impl CompletionsArgs {
+    pub fn run(self) -> eyre::Result<()> {
+        let mut command = Cli::command();
+        let name = command.get_name().to_string();
+        let mut out = std::io::stdout().lock();
+        match self.shell {
+            CompletionShell::PowerShell => {
+                clap_complete::generate(Shell::PowerShell, &mut command, name, &mut out)
+            }
+            CompletionShell::Bash => {
+                clap_complete::generate(Shell::Bash, &mut command, name, &mut out)
+            }
+            CompletionShell::Zsh => {
+                clap_complete::generate(Shell::Zsh, &mut command, name, &mut out)
+            }
+            CompletionShell::Clink => {
+                // Copies global flags down to every subcommand
+                command.build();
+                writeln!(out, "-- {name} completions for clink")?;
+                writeln!(
+                    out,
+                    "clink.argmatcher({}){}",
+                    lua_string(&name),
+                    clink_matcher_body(&command, 0)
+                )?;
+            }
+        }
+        out.flush()?;
+        Ok(())
+    }
+}
+
+impl ToArgs for CompletionsArgs {
+    fn to_args(&self) -> Vec<OsString> {
+        vec![self.shell.as_str().into()]
+    }
+}
+
+/// The `:addarg(..):addflags(..)` chain completing one command, nested for its subcommands
+fn clink_matcher_body(command: &Command, depth: usize) -> String {
+    let indent = "    ".repeat(depth + 1);
+    let mut body = String::new();
+
+    let subcommands: Vec<&Command> = command
+        .get_subcommands()
+        .filter(|sub| !sub.is_hide_set())
+        .collect();
+    if !subcommands.is_empty() {
+        body.push_str(&format!("\n{indent}:addarg({{"));
+        for sub in subcommands {
+            body.push_str(&format!(
+                "\n{indent}    {} .. clink.argmatcher(){},",
+                lua_string(sub.get_name()),
+                clink_matcher_body(sub, depth + 2)
+            ));
+        }
+        body.push_str(&format!("\n{indent}}})"));
+    } else if let Some(positional) = command
+        .get_positionals()
+        .find(|arg| !arg.get_possible_values().is_empty())
+    {
+        body.push_str(&format!(
+            "\n{indent}:addarg({})",
+            lua_list(
+                positional
+                    .get_possible_values()
+                    .iter()
+                    .map(|v| v.get_name())
+            )
+        ));
+    }
+
+    let flags: Vec<String> = command
+        .get_arguments()
+        .filter(|arg| !arg.is_hide_set() && !arg.is_positional())
+        .filter_map(|arg| {
+            let flag = lua_string(&format!("--{}", arg.get_long()?));
+            let takes_value = arg.get_num_args().is_some_and(|n| n.takes_values());
+            Some(if !takes_value {
+                flag
+            } else if arg.get_possible_values().is_empty() {
+                format!("{flag} .. clink.argmatcher():addarg(clink.filematches)")
+            } else {
+                let values = arg.get_possible_values();
+                format!(
+                    "{flag} .. clink.argmatcher():addarg({})",
+                    lua_list(values.iter().map(|v| v.get_name()))
+                )
+            })
+        })
+        .collect();
+    if !flags.is_empty() {
+        body.push_str(&format!("\n{indent}:addflags({{ {} }})", flags.join(", ")));
+    }
+    body
+}
+
+fn lua_list<'a>(items: impl Iterator<Item = &'a str>) -> String {
+    let items: Vec<String> = items.map(lua_string).collect();
+    format!("{{ {} }}", items.join(", "))
+}
+
+fn lua_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
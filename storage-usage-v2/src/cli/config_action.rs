@@ -1,3 +1,6 @@
+use crate::config::Config;
+use crate::config::config_file_path;
+use crate::config::ensure_config_file;
 use crate::config::get_cache_dir;
 use crate::config::set_cache_dir;
 use crate::to_args::ToArgs;
@@ -7,18 +10,49 @@ use clap::Subcommand;
 use clap::ValueEnum;
 use color_eyre::eyre;
 use std::ffi::OsString;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum, Arbitrary)]
 pub enum ConfigKey {
+    /// Where dumps are written and read
     #[clap(name = "cache-dir")]
     CacheDir,
+    /// Drive pattern used when a command is given none
+    #[clap(name = "default-drives")]
+    DefaultDrives,
+    /// Output format of `mft query`
+    #[clap(name = "output-format")]
+    OutputFormat,
+    /// Results listed by `mft query`
+    #[clap(name = "query-limit")]
+    QueryLimit,
+    /// Results written per export from the search tab of `mft show`
+    #[clap(name = "export-limit")]
+    ExportLimit,
+    /// Differences listed per category by `mft diff`
+    #[clap(name = "max-diffs")]
+    MaxDiffs,
+    /// File every command logs to as JSON lines
+    #[clap(name = "log-file")]
+    LogFile,
+    /// Time between syncs of the `service`
+    #[clap(name = "sync-interval")]
+    SyncInterval,
 }
 
 impl ConfigKey {
-    fn as_str(&self) -> &'static str {
+    pub fn as_str(&self) -> &'static str {
         match self {
             ConfigKey::CacheDir => "cache-dir",
+            ConfigKey::DefaultDrives => "default-drives",
+            ConfigKey::OutputFormat => "output-format",
+            ConfigKey::QueryLimit => "query-limit",
+            ConfigKey::ExportLimit => "export-limit",
+            ConfigKey::MaxDiffs => "max-diffs",
+            ConfigKey::LogFile => "log-file",
+            ConfigKey::SyncInterval => "sync-interval",
         }
     }
 }
@@ -43,8 +77,9 @@ impl ToArgs for ConfigArgs {
 
 #[derive(Subcommand, Arbitrary, PartialEq, Debug, Clone)]
 pub enum ConfigAction {
-    /// Show all config values (human-readable)
-    Show,
+    /// List all config values (human-readable)
+    #[clap(alias = "show")]
+    List,
     /// Get a config value by name
     Get {
         /// Name of the config value to get
@@ -54,18 +89,32 @@ pub enum ConfigAction {
     Set {
         /// Name of the config value to set
         key: ConfigKey,
-        /// Value to set (defaults to current directory)
-        #[clap(default_value = ".")]
-        value: PathBuf,
+        /// Value to set (cache-dir defaults to the current directory)
+        value: Option<String>,
     },
+    /// Remove a config value, restoring its built-in default
+    Unset {
+        /// Name of the config value to remove
+        key: ConfigKey,
+    },
+    /// Print the path of the config file
+    Path,
+    /// Open the config file in %EDITOR% (or notepad), creating it from a template if missing
+    Edit,
 }
 
 impl ConfigAction {
     pub fn run(self) -> eyre::Result<()> {
         match self {
-            ConfigAction::Show => show_all(),
+            ConfigAction::List => show_all(),
             ConfigAction::Get { key } => get_one(key),
             ConfigAction::Set { key, value } => set_one(key, value),
+            ConfigAction::Unset { key } => unset_one(key),
+            ConfigAction::Path => {
+                println!("{}", config_file_path()?.display());
+                Ok(())
+            }
+            ConfigAction::Edit => edit(),
         }
     }
 }
@@ -74,8 +123,8 @@ impl ToArgs for ConfigAction {
     fn to_args(&self) -> Vec<OsString> {
         let mut args = Vec::new();
         match self {
-            ConfigAction::Show => {
-                args.push("show".into());
+            ConfigAction::List => {
+                args.push("list".into());
             }
             ConfigAction::Get { key } => {
                 args.push("get".into());
@@ -84,7 +133,19 @@ impl ToArgs for ConfigAction {
             ConfigAction::Set { key, value } => {
                 args.push("set".into());
                 args.push(key.as_str().into());
-                args.push(value.as_os_str().to_os_string());
+                if let Some(value) = value {
+                    args.push(value.into());
+                }
+            }
+            ConfigAction::Unset { key } => {
+                args.push("unset".into());
+                args.push(key.as_str().into());
+            }
+            ConfigAction::Path => {
+                args.push("path".into());
+            }
+            ConfigAction::Edit => {
+                args.push("edit".into());
             }
         }
         args
@@ -94,23 +155,30 @@ impl ToArgs for ConfigAction {
 fn show_all() -> eyre::Result<()> {
     use owo_colors::OwoColorize;
 
-    // cache-dir
-    match get_cache_dir() {
-        Ok(p) => {
-            println!(
-                "{} {} {}",
-                "cache-dir".bright_blue().bold(),
-                "=".dimmed(),
-                p.display().to_string().bright_green()
-            );
-        }
-        Err(_) => {
-            println!(
-                "{} {} {}",
-                "cache-dir".bright_blue().bold(),
-                "=".dimmed(),
-                "<unset>".yellow()
-            );
+    let config = Config::load()?;
+    for key in ConfigKey::value_variants() {
+        // cache-dir may also come from MFT_CACHE_DIR
+        let value = match key {
+            ConfigKey::CacheDir => get_cache_dir().ok().map(|p| p.display().to_string()),
+            _ => config.get(*key),
+        };
+        match value {
+            Some(value) => {
+                println!(
+                    "{} {} {}",
+                    key.as_str().bright_blue().bold(),
+                    "=".dimmed(),
+                    value.bright_green()
+                );
+            }
+            None => {
+                println!(
+                    "{} {} {}",
+                    key.as_str().bright_blue().bold(),
+                    "=".dimmed(),
+                    "<unset>".yellow()
+                );
+            }
         }
     }
 
@@ -124,11 +192,71 @@ fn get_one(key: ConfigKey) -> eyre::Result<()> {
             println!("{}", p.display());
             Ok(())
         }
+        _ => match Config::load()?.get(key) {
+            Some(value) => {
+                println!("{value}");
+                Ok(())
+            }
+            None => Err(eyre::eyre!("{} is not configured", key.as_str())),
+        },
     }
 }
 
-fn set_one(key: ConfigKey, value: PathBuf) -> eyre::Result<()> {
-    match key {
-        ConfigKey::CacheDir => set_cache_dir(&value),
+fn set_one(key: ConfigKey, value: Option<String>) -> eyre::Result<()> {
+    match (key, value) {
+        (ConfigKey::CacheDir, value) => set_cache_dir(Path::new(value.as_deref().unwrap_or("."))),
+        (key, Some(value)) => {
+            let mut config = Config::load()?;
+            config.set(key, &value)?;
+            config.save()
+        }
+        (key, None) => Err(eyre::eyre!("config set {} needs a value", key.as_str())),
+    }
+}
+
+fn unset_one(key: ConfigKey) -> eyre::Result<()> {
+    let mut config = Config::load()?;
+    config.unset(key);
+    config.save()
+}
+
+fn edit() -> eyre::Result<()> {
+    let path = ensure_config_file()?;
+    let editor = std::env::var("EDITOR")
+        .ok()
+        .filter(|e| !e.trim().is_empty())
+        .unwrap_or_else(|| "notepad".to_string());
+    loop {
+        // EDITOR may carry arguments, e.g. `code --wait`
+        let mut words = editor.split_whitespace();
+        let program = words.next().unwrap_or("notepad");
+        let status = Command::new(program)
+            .args(words)
+            .arg(&path)
+            .status()
+            .map_err(|e| eyre::eyre!("Failed to launch editor '{editor}': {e}"))?;
+        if !status.success() {
+            return Err(eyre::eyre!("Editor '{editor}' exited with {status}"));
+        }
+
+        let problem = match Config::load() {
+            Ok(config) => config.validate().err(),
+            Err(e) => Some(e),
+        };
+        let Some(problem) = problem else {
+            println!("{} is valid", path.display());
+            return Ok(());
+        };
+        println!("{problem}");
+        print!("Edit again? [Y/n] ");
+        std::io::stdout().flush()?;
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        if input.trim().eq_ignore_ascii_case("n") {
+            return Err(eyre::eyre!(
+                "{} was left invalid; other commands will fail until it is fixed",
+                path.display()
+            ));
+        }
     }
 }
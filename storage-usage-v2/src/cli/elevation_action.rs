@@ -1,4 +1,6 @@
 use crate::cli::elevation_check_action::ElevationCheckArgs;
+use crate::cli::elevation_run_action::ElevationRunArgs;
+use crate::cli::elevation_strategy::ElevationStrategy;
 use crate::cli::elevation_test_action::ElevationTestArgs;
 use crate::to_args::ToArgs;
 use arbitrary::Arbitrary;
@@ -9,19 +11,29 @@ use std::ffi::OsString;
 /// Elevation command arguments container
 #[derive(Args, Arbitrary, PartialEq, Debug)]
 pub struct ElevationArgs {
+    /// How to launch the elevated instance
+    #[clap(long, value_enum, global = true, default_value_t = ElevationStrategy::Uac)]
+    pub strategy: ElevationStrategy,
+
     #[clap(subcommand)]
     pub action: ElevationAction,
 }
 
 impl ElevationArgs {
     pub fn run(self) -> eyre::Result<()> {
-        self.action.run()
+        self.action.run(self.strategy)
     }
 }
 
 impl ToArgs for ElevationArgs {
     fn to_args(&self) -> Vec<OsString> {
-        self.action.to_args()
+        let mut args = Vec::new();
+        if self.strategy != ElevationStrategy::Uac {
+            args.push("--strategy".into());
+            args.push(self.strategy.as_str().into());
+        }
+        args.extend(self.action.to_args());
+        args
     }
 }
 
@@ -32,13 +44,16 @@ pub enum ElevationAction {
     Check(ElevationCheckArgs),
     /// Test elevation functionality by relaunching with administrator privileges
     Test(ElevationTestArgs),
+    /// Run this tool elevated with the arguments after `--`, relaying its output and exit code
+    Run(ElevationRunArgs),
 }
 
 impl ElevationAction {
-    pub fn run(self) -> eyre::Result<()> {
+    pub fn run(self, strategy: ElevationStrategy) -> eyre::Result<()> {
         match self {
             ElevationAction::Check(args) => args.run(),
-            ElevationAction::Test(args) => args.run(),
+            ElevationAction::Test(args) => args.run(strategy),
+            ElevationAction::Run(args) => args.run(strategy),
         }
     }
 }
@@ -55,6 +70,10 @@ impl ToArgs for ElevationAction {
                 args.push("test".into());
                 args.extend(test_args.to_args());
             }
+            ElevationAction::Run(run_args) => {
+                args.push("run".into());
+                args.extend(run_args.to_args());
+            }
         }
         args
     }
@@ -1,16 +1,59 @@
+use crate::cli::output_format::OutputFormat;
+use crate::cli::output_format::csv_escape;
+use crate::cli::output_format::output_format;
+use crate::cli::output_format::print_json;
 use crate::to_args::ToArgs;
 use crate::win_elevation::is_elevated;
+use crate::win_handles::get_drive_handle;
+use crate::win_privileges::PrivilegeStatus;
+use crate::win_privileges::mft_privilege_statuses;
 use arbitrary::Arbitrary;
 use clap::Args;
+use serde::Serialize;
 use std::ffi::OsString;
 use std::io::Write;
 
 /// Arguments for checking elevation status
 #[derive(Args, Clone, Arbitrary, PartialEq, Debug)]
-pub struct ElevationCheckArgs {}
+pub struct ElevationCheckArgs {
+    /// Report which backup-related privileges the token holds and whether the system volume
+    /// can be opened for raw $MFT reads, instead of only elevated or not
+    #[clap(long)]
+    pub capabilities: bool,
+}
+
+/// What `elevation check --capabilities` found, for the structured formats
+#[derive(Debug, Serialize)]
+struct Capabilities {
+    elevated: bool,
+    privileges: Vec<PrivilegeStatus>,
+    raw_mft_access: RawMftAccess,
+}
+
+#[derive(Debug, Serialize)]
+struct RawMftAccess {
+    drive: char,
+    available: bool,
+    error: Option<String>,
+}
 
 impl ElevationCheckArgs {
     pub fn run(self) -> eyre::Result<()> {
+        if self.capabilities {
+            return report_capabilities();
+        }
+        let format = output_format();
+        match format {
+            OutputFormat::Text => {}
+            OutputFormat::Json | OutputFormat::Jsonl => {
+                return print_json(&serde_json::json!({ "elevated": is_elevated() }), format);
+            }
+            OutputFormat::Csv => {
+                println!("elevated");
+                println!("{}", is_elevated());
+                return Ok(());
+            }
+        }
         if is_elevated() {
             println!("Elevated");
             println!("Press Enter to continue...");
@@ -24,9 +67,74 @@ impl ElevationCheckArgs {
     }
 }
 
+fn report_capabilities() -> eyre::Result<()> {
+    // Opening the volume is the step that fails without enough rights; the privileges are what
+    // then let the MFT's own records be read
+    let drive = std::env::var("SystemDrive")
+        .ok()
+        .and_then(|drive| drive.chars().next())
+        .filter(|c| c.is_ascii_alphabetic())
+        .unwrap_or('C');
+    let error = get_drive_handle(drive).err().map(|e| format!("{e:#}"));
+    let capabilities = Capabilities {
+        elevated: is_elevated(),
+        privileges: mft_privilege_statuses()?,
+        raw_mft_access: RawMftAccess {
+            drive,
+            available: error.is_none(),
+            error,
+        },
+    };
+
+    let format = output_format();
+    match format {
+        OutputFormat::Text => {
+            let yes_no = |value: bool| if value { "yes" } else { "no" };
+            println!("Elevated: {}", yes_no(capabilities.elevated));
+            println!("Privileges:");
+            for privilege in &capabilities.privileges {
+                let state = match (privilege.available, privilege.enabled) {
+                    (true, true) => "enabled",
+                    (true, false) => "available (disabled until needed)",
+                    (false, _) => "missing",
+                };
+                println!("  {:<20} {state}", privilege.name);
+            }
+            let access = &capabilities.raw_mft_access;
+            match &access.error {
+                None => println!("Raw $MFT access on {drive}: yes"),
+                Some(e) => println!("Raw $MFT access on {drive}: no ({e})"),
+            }
+        }
+        OutputFormat::Json | OutputFormat::Jsonl => print_json(&capabilities, format)?,
+        OutputFormat::Csv => {
+            // One row per capability, so privileges and volume access share the columns
+            println!("capability,available,enabled,detail");
+            println!("elevated,{},{},", capabilities.elevated, capabilities.elevated);
+            for privilege in &capabilities.privileges {
+                println!(
+                    "{},{},{},",
+                    privilege.name, privilege.available, privilege.enabled
+                );
+            }
+            let access = &capabilities.raw_mft_access;
+            println!(
+                "raw-mft-access-{drive},{},{},{}",
+                access.available,
+                access.available,
+                csv_escape(access.error.as_deref().unwrap_or_default())
+            );
+        }
+    }
+    Ok(())
+}
+
 impl ToArgs for ElevationCheckArgs {
     fn to_args(&self) -> Vec<OsString> {
-        // No additional args for check command
-        Vec::new()
+        let mut args = Vec::new();
+        if self.capabilities {
+            args.push("--capabilities".into());
+        }
+        args
     }
 }
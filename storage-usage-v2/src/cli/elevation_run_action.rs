@@ -0,0 +1,88 @@
+use crate::cli::elevation_strategy::ElevationStrategy;
+use crate::elevated_report::OutputRelay;
+use crate::to_args::Invocable;
+use crate::to_args::ToArgs;
+use crate::win_elevation::run_elevated;
+use arbitrary::Arbitrary;
+use clap::Args;
+use std::ffi::OsString;
+use std::path::PathBuf;
+use tracing::debug;
+
+/// Arguments for running this tool elevated and relaying its output
+#[derive(Args, Clone, PartialEq, Debug)]
+pub struct ElevationRunArgs {
+    /// Arguments for the elevated instance, after `--` (e.g. `elevation run -- mft sync C`)
+    #[clap(last = true, required = true)]
+    pub args: Vec<String>,
+}
+
+impl<'a> Arbitrary<'a> for ElevationRunArgs {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<Self> {
+        // At least one argument, since clap requires one
+        let count = u8::arbitrary(u)? % 3 + 1;
+        let args = (0..count)
+            .map(|_| {
+                let word: String = (0..6)
+                    .map(|_| {
+                        let c = char::arbitrary(u).unwrap_or('a');
+                        if c.is_ascii_alphanumeric() { c } else { 'a' }
+                    })
+                    .collect();
+                word
+            })
+            .collect();
+        Ok(Self { args })
+    }
+}
+
+/// This executable with the given arguments, its output relayed through the named pipes
+struct RelayedInvocation<'a> {
+    relay_pipe: &'a str,
+    args: &'a [String],
+}
+
+impl Invocable for RelayedInvocation<'_> {
+    fn executable(&self) -> PathBuf {
+        std::env::current_exe().expect("Failed to get current executable path")
+    }
+
+    fn args(&self) -> Vec<OsString> {
+        // Global flags go first so they can't end up after a nested `--`
+        let mut args: Vec<OsString> = vec!["--relay-pipe".into(), self.relay_pipe.into()];
+        args.extend(self.args.iter().map(OsString::from));
+        args
+    }
+}
+
+impl ElevationRunArgs {
+    /// Relaunch elevated, print the child's stdout and stderr here as it runs, and exit with
+    /// its exit code
+    pub fn run(self, strategy: ElevationStrategy) -> eyre::Result<()> {
+        let relay = OutputRelay::create()?;
+        let invocation = RelayedInvocation {
+            relay_pipe: relay.name(),
+            args: &self.args,
+        };
+        // Logged at debug so the relayed stdout stays clean for scripts
+        debug!(
+            "Relaunching as administrator ({strategy}): {}",
+            self.args.join(" ")
+        );
+        let child = run_elevated(&invocation, strategy)?;
+        let readers = relay.spawn();
+        let exit_code = child.wait();
+        for reader in readers {
+            reader.finish();
+        }
+        std::process::exit(exit_code? as i32);
+    }
+}
+
+impl ToArgs for ElevationRunArgs {
+    fn to_args(&self) -> Vec<OsString> {
+        let mut args: Vec<OsString> = vec!["--".into()];
+        args.extend(self.args.iter().map(OsString::from));
+        args
+    }
+}
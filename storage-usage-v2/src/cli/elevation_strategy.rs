@@ -0,0 +1,29 @@
+use arbitrary::Arbitrary;
+use clap::ValueEnum;
+use std::fmt;
+
+/// How an elevated instance is launched
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum, Arbitrary)]
+pub enum ElevationStrategy {
+    /// Ask through a UAC prompt
+    #[default]
+    Uac,
+    /// Register a one-shot scheduled task that runs with the highest privileges, for machines
+    /// where policy denies UAC prompts; the current user must be an administrator
+    TaskScheduler,
+}
+
+impl ElevationStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ElevationStrategy::Uac => "uac",
+            ElevationStrategy::TaskScheduler => "task-scheduler",
+        }
+    }
+}
+
+impl fmt::Display for ElevationStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
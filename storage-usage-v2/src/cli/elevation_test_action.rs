@@ -3,10 +3,11 @@ use crate::cli::action::Action;
 use crate::cli::elevation_action::ElevationAction;
 use crate::cli::elevation_action::ElevationArgs;
 use crate::cli::elevation_check_action::ElevationCheckArgs;
+use crate::cli::elevation_strategy::ElevationStrategy;
 use crate::cli::global_args::GlobalArgs;
 use crate::to_args::ToArgs;
 use crate::win_elevation::is_elevated;
-use crate::win_elevation::run_as_admin;
+use crate::win_elevation::run_elevated;
 use arbitrary::Arbitrary;
 use clap::Args;
 use eyre::eyre;
@@ -19,7 +20,7 @@ use tracing::warn;
 pub struct ElevationTestArgs {}
 
 impl ElevationTestArgs {
-    pub fn run(self) -> eyre::Result<()> {
+    pub fn run(self, strategy: ElevationStrategy) -> eyre::Result<()> {
         if is_elevated() {
             info!("Already running as elevated, elevation test successful!");
             return Ok(());
@@ -34,12 +35,15 @@ impl ElevationTestArgs {
                 ..Default::default()
             },
             action: Action::Elevation(ElevationArgs {
-                action: ElevationAction::Check(ElevationCheckArgs {}),
+                strategy: ElevationStrategy::Uac,
+                action: ElevationAction::Check(ElevationCheckArgs {
+                    capabilities: false,
+                }),
             }),
         };
 
-        info!("Relaunching as administrator to run elevation check...");
-        match run_as_admin(&check_cli) {
+        info!("Relaunching as administrator ({strategy}) to run elevation check...");
+        match run_elevated(&check_cli, strategy) {
             Ok(child) => {
                 info!("Spawned elevated process – waiting for it to finish…");
                 let exit_code = child.wait()?;
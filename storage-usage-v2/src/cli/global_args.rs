@@ -1,7 +1,9 @@
+use crate::cli::output_format::OutputFormat;
 use crate::to_args::ToArgs;
 use arbitrary::Arbitrary;
 use clap::Args;
 use std::ffi::OsString;
+use std::path::PathBuf;
 
 #[derive(Args, Default, Arbitrary, PartialEq, Debug)]
 pub struct GlobalArgs {
@@ -12,6 +14,59 @@ pub struct GlobalArgs {
     /// Console PID for console reuse (hidden)
     #[clap(long, hide = true, global = true)]
     pub console_pid: Option<u32>,
+
+    /// Skip the TUI's startup and quit animations
+    #[clap(long, global = true)]
+    pub no_effects: bool,
+
+    /// Draw the TUI at most this many times per second [default: 30]
+    #[clap(long, global = true)]
+    pub max_fps: Option<u32>,
+
+    /// Never relaunch elevated; commands needing raw volume access walk the directory tree or
+    /// use cached dumps instead, and say what is missing
+    #[clap(long, global = true)]
+    pub no_elevate: bool,
+
+    /// Once the volume is open, remove every privilege and drop to medium integrity before
+    /// parsing MFT bytes, so a parser bug can do less damage
+    #[clap(long, global = true)]
+    pub drop_privileges: bool,
+
+    /// Hide the progress bars of `mft dump`, `mft sync` and `mft index build`
+    #[clap(long, global = true)]
+    pub quiet: bool,
+
+    /// Print results as text (alias: human), json, jsonl or csv, so the CLI can be scripted
+    #[clap(long, value_enum, global = true)]
+    pub format: Option<OutputFormat>,
+
+    /// Also write logs to this file as JSON lines, rotated at 10 MiB; an elevated child uses
+    /// `<stem>.elevated.log` beside it [default: log-file from the config]
+    #[clap(long, global = true)]
+    #[arbitrary(default)]
+    pub log_file: Option<PathBuf>,
+
+    /// Read and write settings in this file instead of config.toml in the config dir
+    #[clap(long, global = true)]
+    #[arbitrary(default)]
+    pub config_file: Option<PathBuf>,
+
+    /// Record spans (MFT extents read, parse batches, path resolution, TUI frames) to this
+    /// file for chrome://tracing or Perfetto
+    #[clap(long, global = true)]
+    #[arbitrary(default)]
+    pub trace_out: Option<PathBuf>,
+
+    /// Pipe to stream logs and progress to the process that launched this one (hidden)
+    #[clap(long, hide = true, global = true)]
+    #[arbitrary(default)]
+    pub report_pipe: Option<String>,
+
+    /// Pipes to send stdout and stderr to the process that launched this one (hidden)
+    #[clap(long, hide = true, global = true)]
+    #[arbitrary(default)]
+    pub relay_pipe: Option<String>,
 }
 
 impl GlobalArgs {
@@ -34,6 +89,46 @@ impl ToArgs for GlobalArgs {
             args.push("--console-pid".into());
             args.push(pid.to_string().into());
         }
+        if self.no_effects {
+            args.push("--no-effects".into());
+        }
+        if let Some(max_fps) = self.max_fps {
+            args.push("--max-fps".into());
+            args.push(max_fps.to_string().into());
+        }
+        if self.no_elevate {
+            args.push("--no-elevate".into());
+        }
+        if self.drop_privileges {
+            args.push("--drop-privileges".into());
+        }
+        if self.quiet {
+            args.push("--quiet".into());
+        }
+        if let Some(format) = self.format {
+            args.push("--format".into());
+            args.push(format.as_str().into());
+        }
+        if let Some(log_file) = &self.log_file {
+            args.push("--log-file".into());
+            args.push(log_file.into());
+        }
+        if let Some(config_file) = &self.config_file {
+            args.push("--config-file".into());
+            args.push(config_file.into());
+        }
+        if let Some(trace_out) = &self.trace_out {
+            args.push("--trace-out".into());
+            args.push(trace_out.into());
+        }
+        if let Some(report_pipe) = &self.report_pipe {
+            args.push("--report-pipe".into());
+            args.push(report_pipe.into());
+        }
+        if let Some(relay_pipe) = &self.relay_pipe {
+            args.push("--relay-pipe".into());
+            args.push(relay_pipe.into());
+        }
         args
     }
 }
@@ -0,0 +1,79 @@
+use super::drive_letter_pattern::DriveLetterPattern;
+use crate::config::Config;
+use crate::metrics::render_metrics;
+use crate::metrics::serve_metrics;
+use crate::metrics::write_metrics_file;
+use crate::to_args::ToArgs;
+use arbitrary::Arbitrary;
+use clap::Args;
+use humantime::parse_duration;
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::warn;
+
+/// Arguments for exporting drive space and sync freshness as Prometheus metrics
+#[derive(Args, Arbitrary, PartialEq, Debug, Clone)]
+pub struct MetricsArgs {
+    /// Drive letter pattern to report on (e.g. '*', 'C', 'CD', 'C,D')
+    #[clap(default_value_t = DriveLetterPattern::default())]
+    pub drive_pattern: DriveLetterPattern,
+
+    /// Answer Prometheus scrapes of /metrics on this address, e.g. 127.0.0.1:9184
+    #[clap(long, value_name = "ADDR", conflicts_with = "textfile")]
+    #[arbitrary(default)]
+    pub listen: Option<String>,
+
+    /// Write the metrics to this .prom file for node_exporter's textfile collector
+    #[clap(long, value_name = "PATH")]
+    #[arbitrary(default)]
+    pub textfile: Option<PathBuf>,
+
+    /// With --textfile, rewrite the file this often instead of once (e.g. '1m', '15m')
+    #[clap(long, value_parser = parse_duration, requires = "textfile")]
+    #[arbitrary(default)]
+    pub interval: Option<Duration>,
+}
+
+impl MetricsArgs {
+    pub fn run(self) -> eyre::Result<()> {
+        let drive_pattern = Config::load()?.drives_or(self.drive_pattern)?;
+        if let Some(addr) = &self.listen {
+            return serve_metrics(addr, &drive_pattern);
+        }
+        let Some(path) = &self.textfile else {
+            print!("{}", render_metrics(&drive_pattern)?);
+            return Ok(());
+        };
+        let Some(interval) = self.interval else {
+            return write_metrics_file(path, &drive_pattern);
+        };
+        loop {
+            // A failed write is retried next time rather than ending the loop
+            if let Err(e) = write_metrics_file(path, &drive_pattern) {
+                warn!("Failed to write {}: {e:#}", path.display());
+            }
+            std::thread::sleep(interval);
+        }
+    }
+}
+
+impl ToArgs for MetricsArgs {
+    fn to_args(&self) -> Vec<OsString> {
+        let mut args = Vec::new();
+        args.push(self.drive_pattern.to_string().into());
+        if let Some(listen) = &self.listen {
+            args.push("--listen".into());
+            args.push(listen.into());
+        }
+        if let Some(textfile) = &self.textfile {
+            args.push("--textfile".into());
+            args.push(textfile.into());
+        }
+        if let Some(interval) = self.interval {
+            args.push("--interval".into());
+            args.push(humantime::format_duration(interval).to_string().into());
+        }
+        args
+    }
+}
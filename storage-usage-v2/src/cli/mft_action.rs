@@ -1,8 +1,14 @@
+use crate::cli::mft_bench_action::MftBenchArgs;
+use crate::cli::mft_check_action::MftCheckArgs;
+use crate::cli::mft_dedupe_action::MftDedupeArgs;
 use crate::cli::mft_diff_action::MftDiffArgs;
 use crate::cli::mft_dump_action::MftDumpArgs;
+use crate::cli::mft_index_action::MftIndexArgs;
 use crate::cli::mft_query_action::MftQueryArgs;
+use crate::cli::mft_report_action::MftReportArgs;
 use crate::cli::mft_show_action::MftShowArgs;
 use crate::cli::mft_sync_action::MftSyncArgs;
+use crate::cli::mft_watch_action::MftWatchArgs;
 use crate::to_args::ToArgs;
 use arbitrary::Arbitrary;
 use clap::Args;
@@ -41,6 +47,18 @@ pub enum MftAction {
     Query(MftQueryArgs),
     /// Sync MFTs for drives matching a pattern into the cache dir
     Sync(MftSyncArgs),
+    /// Build persistent query indexes next to cached MFTs
+    Index(MftIndexArgs),
+    /// Cross-check cached MFTs against the clusters their volume bitmaps mark in use
+    Check(MftCheckArgs),
+    /// Add up the files of cached MFTs, such as per owner, per directory or what can be cleaned up
+    Report(MftReportArgs),
+    /// Stream changes to a volume's files from its USN change journal
+    Watch(MftWatchArgs),
+    /// Time parsing, path resolution and index building over a dump, cold and warm
+    Bench(MftBenchArgs),
+    /// Find duplicate files by size in cached MFTs, confirmed by hashing their contents
+    Dedupe(MftDedupeArgs),
 }
 
 impl MftAction {
@@ -51,6 +69,12 @@ impl MftAction {
             MftAction::Show(args) => args.run(),
             MftAction::Query(args) => args.run(),
             MftAction::Sync(args) => args.run(),
+            MftAction::Index(args) => args.run(),
+            MftAction::Check(args) => args.run(),
+            MftAction::Report(args) => args.run(),
+            MftAction::Watch(args) => args.run(),
+            MftAction::Bench(args) => args.run(),
+            MftAction::Dedupe(args) => args.run(),
         }
     }
 }
@@ -79,6 +103,30 @@ impl ToArgs for MftAction {
                 args.push("sync".into());
                 args.extend(sync_args.to_args());
             }
+            MftAction::Index(index_args) => {
+                args.push("index".into());
+                args.extend(index_args.to_args());
+            }
+            MftAction::Check(check_args) => {
+                args.push("check".into());
+                args.extend(check_args.to_args());
+            }
+            MftAction::Report(report_args) => {
+                args.push("report".into());
+                args.extend(report_args.to_args());
+            }
+            MftAction::Watch(watch_args) => {
+                args.push("watch".into());
+                args.extend(watch_args.to_args());
+            }
+            MftAction::Bench(bench_args) => {
+                args.push("bench".into());
+                args.extend(bench_args.to_args());
+            }
+            MftAction::Dedupe(dedupe_args) => {
+                args.push("dedupe".into());
+                args.extend(dedupe_args.to_args());
+            }
         }
         args
     }
@@ -0,0 +1,115 @@
+use crate::cli::output_format::OutputFormat;
+use crate::cli::output_format::output_format;
+use crate::cli::output_format::print_json;
+use crate::mft_bench::BenchPhase;
+use crate::mft_bench::BenchReport;
+use crate::mft_bench::bench_mft;
+use crate::to_args::ToArgs;
+use arbitrary::Arbitrary;
+use clap::Args;
+use humansize::DECIMAL;
+use humansize::format_size;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+/// Warm runs timed after the cold one unless `--runs` says otherwise
+const DEFAULT_WARM_RUNS: usize = 3;
+
+/// Arguments for timing the stages of processing an MFT dump
+#[derive(Args, Arbitrary, PartialEq, Debug, Clone)]
+pub struct MftBenchArgs {
+    /// MFT dump to time, such as a cached `C.mft`
+    pub dump: PathBuf,
+
+    /// Runs to time after the cold one, with the dump in the file cache; the median is reported
+    #[clap(long, default_value_t = DEFAULT_WARM_RUNS)]
+    pub runs: usize,
+}
+
+impl MftBenchArgs {
+    pub fn run(self) -> eyre::Result<()> {
+        let report = bench_mft(&self.dump, self.runs)?;
+        let format = output_format();
+        match format {
+            OutputFormat::Text => print_report(&report),
+            OutputFormat::Json | OutputFormat::Jsonl => print_json(&report, format)?,
+            OutputFormat::Csv => {
+                println!(
+                    "phase,unit,items,cold_seconds,warm_seconds,cold_per_second,warm_per_second"
+                );
+                for timing in &report.phases {
+                    println!(
+                        "{},{},{},{},{},{},{}",
+                        timing.phase.as_str(),
+                        timing.unit,
+                        timing.items,
+                        timing.cold_seconds,
+                        timing
+                            .warm_seconds
+                            .map(|s| s.to_string())
+                            .unwrap_or_default(),
+                        timing.cold_per_second(),
+                        timing
+                            .warm_per_second()
+                            .map(|rate| rate.to_string())
+                            .unwrap_or_default()
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn print_report(report: &BenchReport) {
+    println!(
+        "{} ({}, {} records, {} parse errors, {} entries)",
+        report.dump.display(),
+        format_size(report.bytes, DECIMAL),
+        report.records,
+        report.parse_errors,
+        report.entries
+    );
+    let warm_header = format!("Warm (median of {})", report.warm_runs);
+    // Each heading spans a time and a rate column
+    println!("  {:<8} {:>31}  {:>31}", "Phase", "Cold", warm_header);
+    for timing in &report.phases {
+        let warm = match (timing.warm_seconds, timing.warm_per_second()) {
+            (Some(seconds), Some(rate)) => {
+                (format!("{seconds:.3}s"), format_rate(timing.phase, rate))
+            }
+            _ => ("-".to_string(), String::new()),
+        };
+        println!(
+            "  {:<8} {:>10} {:>20}  {:>10} {:>20}",
+            timing.phase.as_str(),
+            format!("{:.3}s", timing.cold_seconds),
+            format_rate(timing.phase, timing.cold_per_second()),
+            warm.0,
+            warm.1
+        );
+    }
+    if !report.evicted {
+        println!(
+            "  The dump couldn't be dropped from the file cache, so cold reads were warm too."
+        );
+    }
+}
+
+fn format_rate(phase: BenchPhase, per_second: f64) -> String {
+    match phase {
+        BenchPhase::Read => format!("{}/s", format_size(per_second as u64, DECIMAL)),
+        _ => format!("{:.0} {}/s", per_second, phase.unit()),
+    }
+}
+
+impl ToArgs for MftBenchArgs {
+    fn to_args(&self) -> Vec<OsString> {
+        let mut args = vec![self.dump.clone().into()];
+        if self.runs != DEFAULT_WARM_RUNS {
+            args.push("--runs".into());
+            args.push(self.runs.to_string().into());
+        }
+        args
+    }
+}
@@ -0,0 +1,131 @@
+use super::drive_letter_pattern::DriveLetterPattern;
+use crate::app_error::AppError;
+use crate::cli::output_format::OutputFormat;
+use crate::cli::output_format::output_format;
+use crate::cli::output_format::print_json;
+use crate::config::Config;
+use crate::config::get_cache_dir;
+use crate::to_args::ToArgs;
+use crate::volume_bitmap::VolumeUsage;
+use crate::volume_bitmap::check_usage;
+use arbitrary::Arbitrary;
+use clap::Args;
+use humansize::DECIMAL;
+use humansize::format_size;
+use serde::Serialize;
+use std::ffi::OsString;
+
+/// Arguments for cross-checking cached MFTs against their volume bitmaps
+#[derive(Args, Arbitrary, PartialEq, Debug, Clone)]
+pub struct MftCheckArgs {
+    /// Drive letter pattern to select cached MFTs (e.g. '*', 'C', 'CD', 'C,D')
+    #[clap(default_value_t = DriveLetterPattern::default())]
+    pub drive_pattern: DriveLetterPattern,
+}
+
+/// One drive's usage, as printed for structured formats
+#[derive(Serialize)]
+struct DriveUsage {
+    drive: char,
+    #[serde(flatten)]
+    usage: VolumeUsage,
+    unaccounted_clusters: i64,
+}
+
+impl MftCheckArgs {
+    pub fn run(self) -> eyre::Result<()> {
+        let drive_pattern = Config::load()?.drives_or(self.drive_pattern)?;
+        let cache = get_cache_dir()?;
+        let mft_files: Vec<_> = drive_pattern
+            .resolve()?
+            .into_iter()
+            .map(|drive| (drive, cache.join(format!("{drive}.mft"))))
+            .filter(|(_, path)| path.exists())
+            .collect();
+        if mft_files.is_empty() {
+            return Err(AppError::NoCachedDumps {
+                pattern: drive_pattern.to_string(),
+                hint: "Run mft sync first.",
+            }
+            .into());
+        }
+        let mut drives = Vec::new();
+        for (drive, mft_file) in mft_files {
+            let usage = check_usage(&mft_file)?;
+            drives.push(DriveUsage {
+                drive,
+                usage,
+                unaccounted_clusters: usage.unaccounted_clusters(),
+            });
+        }
+
+        let format = output_format();
+        match format {
+            OutputFormat::Text => {
+                for drive in &drives {
+                    print_usage(drive);
+                }
+            }
+            OutputFormat::Json | OutputFormat::Jsonl => print_json(&drives, format)?,
+            OutputFormat::Csv => {
+                println!(
+                    "drive,bytes_per_cluster,bitmap_clusters,file_data_clusters,metadata_clusters,other_attribute_clusters,unaccounted_clusters"
+                );
+                for drive in &drives {
+                    let usage = &drive.usage;
+                    println!(
+                        "{},{},{},{},{},{},{}",
+                        drive.drive,
+                        usage.bytes_per_cluster,
+                        usage.bitmap_clusters,
+                        usage.file_data_clusters,
+                        usage.metadata_clusters,
+                        usage.other_attribute_clusters,
+                        drive.unaccounted_clusters
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn print_usage(drive: &DriveUsage) {
+    let usage = &drive.usage;
+    let row = |label: &str, clusters: u64| {
+        println!(
+            "  {label:<20} {:>12}  ({clusters} clusters)",
+            format_size(usage.bytes(clusters), DECIMAL)
+        );
+    };
+    println!(
+        "Drive {} ({} clusters):",
+        drive.drive,
+        format_size(usage.bytes_per_cluster, DECIMAL)
+    );
+    row("In use ($Bitmap)", usage.bitmap_clusters);
+    row("File data", usage.file_data_clusters);
+    row("Metadata files", usage.metadata_clusters);
+    row("Other attributes", usage.other_attribute_clusters);
+    let unaccounted = drive.unaccounted_clusters;
+    let sign = if unaccounted < 0 { "-" } else { "" };
+    println!(
+        "  {:<20} {:>12}  ({unaccounted} clusters)",
+        "Unaccounted",
+        format!(
+            "{sign}{}",
+            format_size(usage.bytes(unaccounted.unsigned_abs()), DECIMAL)
+        )
+    );
+    if unaccounted < 0 {
+        println!(
+            "  Records claim more than the bitmap; the volume changed between reading the two."
+        );
+    }
+}
+
+impl ToArgs for MftCheckArgs {
+    fn to_args(&self) -> Vec<OsString> {
+        vec![self.drive_pattern.to_string().into()]
+    }
+}
@@ -0,0 +1,190 @@
+use super::drive_letter_pattern::DriveLetterPattern;
+use crate::app_error::AppError;
+use crate::cli::output_format::OutputFormat;
+use crate::cli::output_format::csv_escape;
+use crate::cli::output_format::output_format;
+use crate::cli::output_format::print_json;
+use crate::config::Config;
+use crate::config::get_cache_dir;
+use crate::mft_dedupe::DedupeCheck;
+use crate::mft_dedupe::DedupeOptions;
+use crate::mft_dedupe::DedupeReport;
+use crate::mft_dedupe::find_duplicates;
+use crate::progress_bars::index_with_bar;
+use crate::search_filter::parse_size;
+use crate::to_args::ToArgs;
+use arbitrary::Arbitrary;
+use clap::Args;
+use humansize::DECIMAL;
+use humansize::format_size;
+use std::ffi::OsString;
+
+/// Files smaller than this aren't considered unless `--min-size` says otherwise
+const DEFAULT_MIN_SIZE: u64 = 1_000_000;
+
+/// Files hashed at once unless `--threads` says otherwise; few, so a disk isn't swamped
+const DEFAULT_HASH_THREADS: usize = 4;
+
+/// Duplicate sets listed unless `--top` says otherwise
+const DEFAULT_TOP_SETS: usize = 50;
+
+/// Arguments for finding duplicate files on the drives of cached MFTs
+#[derive(Args, Arbitrary, PartialEq, Debug, Clone)]
+pub struct MftDedupeArgs {
+    /// Drive letter pattern to select cached MFTs (e.g. '*', 'C', 'CD', 'C,D')
+    #[clap(default_value_t = DriveLetterPattern::default())]
+    pub drive_pattern: DriveLetterPattern,
+
+    /// Smallest file to consider (e.g. '100KB', '1GB')
+    #[clap(long, value_name = "SIZE", default_value_t = DEFAULT_MIN_SIZE, value_parser = parse_byte_size)]
+    pub min_size: u64,
+
+    /// How far to confirm that files of the same size are copies: by size alone, by hashing their
+    /// first and last 64 KiB, or by hashing them whole
+    #[clap(long, value_enum, default_value_t = DedupeCheck::Full)]
+    pub check: DedupeCheck,
+
+    /// Files hashed at once
+    #[clap(long, short = 'j', default_value_t = DEFAULT_HASH_THREADS)]
+    pub threads: usize,
+
+    /// Read at most this many bytes per second across all threads (e.g. '50MB')
+    #[clap(long, value_name = "SIZE", value_parser = parse_byte_size)]
+    pub rate: Option<u64>,
+
+    /// Duplicate sets to list, those freeing the most bytes first
+    #[clap(long, default_value_t = DEFAULT_TOP_SETS)]
+    pub top: usize,
+}
+
+impl MftDedupeArgs {
+    pub fn run(self) -> eyre::Result<()> {
+        let drive_pattern = Config::load()?.drives_or(self.drive_pattern)?;
+        let cache = get_cache_dir()?;
+        let mft_files: Vec<_> = drive_pattern
+            .resolve()?
+            .into_iter()
+            .map(|drive| (drive, cache.join(format!("{drive}.mft"))))
+            .filter(|(_, path)| path.exists())
+            .collect();
+        if mft_files.is_empty() {
+            return Err(AppError::NoCachedDumps {
+                pattern: drive_pattern.to_string(),
+                hint: "Run mft sync first.",
+            }
+            .into());
+        }
+        let indexes = mft_files
+            .iter()
+            .map(|(drive, mft_file)| Ok((*drive, index_with_bar(mft_file)?)))
+            .collect::<eyre::Result<Vec<_>>>()?;
+        let options = DedupeOptions {
+            min_size: self.min_size,
+            check: self.check,
+            threads: self.threads,
+            rate: self.rate,
+        };
+        let report = find_duplicates(&indexes, &options, self.top)?;
+
+        let format = output_format();
+        match format {
+            OutputFormat::Text => print_report(&report),
+            OutputFormat::Json | OutputFormat::Jsonl => print_json(&report, format)?,
+            OutputFormat::Csv => {
+                println!("set,size,hash,path,suggestion,keep");
+                for (i, set) in report.sets.iter().enumerate() {
+                    for path in &set.paths {
+                        let suggestion = report
+                            .suggestions
+                            .iter()
+                            .find(|suggestion| &suggestion.path == path);
+                        println!(
+                            "{},{},{},{},{},{}",
+                            i + 1,
+                            set.size,
+                            set.hash.as_deref().unwrap_or_default(),
+                            csv_escape(&path.to_string_lossy()),
+                            suggestion.map(|s| s.kind.as_str()).unwrap_or_default(),
+                            csv_escape(
+                                &suggestion
+                                    .map(|s| s.keep.to_string_lossy())
+                                    .unwrap_or_default()
+                            )
+                        );
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn print_report(report: &DedupeReport) {
+    println!(
+        "{} duplicate sets, {} reclaimable ({} candidates, {} read, {} unreadable)",
+        report.sets.len(),
+        format_size(report.reclaimable_bytes, DECIMAL),
+        report.candidates,
+        format_size(report.bytes_read, DECIMAL),
+        report.unreadable
+    );
+    for set in &report.sets {
+        println!(
+            "\n{} copies of {}, {} reclaimable",
+            set.paths.len(),
+            format_size(set.size, DECIMAL),
+            format_size(set.reclaimable_bytes, DECIMAL)
+        );
+        for path in &set.paths {
+            println!("  {}", path.display());
+        }
+    }
+    if report.check != DedupeCheck::Full {
+        println!(
+            "\nCompared by {} only; run with --check full for suggestions.",
+            report.check.as_str()
+        );
+    } else if !report.suggestions.is_empty() {
+        println!("\nSuggestions:");
+        for suggestion in &report.suggestions {
+            println!(
+                "  {:<9} {} (copy of {})",
+                suggestion.kind.as_str(),
+                suggestion.path.display(),
+                suggestion.keep.display()
+            );
+        }
+    }
+}
+
+/// A size like '1MB' as a byte count
+fn parse_byte_size(value: &str) -> Result<u64, String> {
+    parse_size(value).ok_or_else(|| format!("invalid size '{value}', expected e.g. '1MB' or '2GB'"))
+}
+
+impl ToArgs for MftDedupeArgs {
+    fn to_args(&self) -> Vec<OsString> {
+        let mut args = vec![self.drive_pattern.to_string().into()];
+        if self.min_size != DEFAULT_MIN_SIZE {
+            args.push("--min-size".into());
+            args.push(self.min_size.to_string().into());
+        }
+        if self.check != DedupeCheck::Full {
+            args.push("--check".into());
+            args.push(self.check.as_str().into());
+        }
+        if self.threads != DEFAULT_HASH_THREADS {
+            args.push("--threads".into());
+            args.push(self.threads.to_string().into());
+        }
+        if let Some(rate) = self.rate {
+            args.push("--rate".into());
+            args.push(rate.to_string().into());
+        }
+        if self.top != DEFAULT_TOP_SETS {
+            args.push("--top".into());
+            args.push(self.top.to_string().into());
+        }
+        args
+    }
+}
@@ -1,28 +1,106 @@
+use crate::config::Config;
 use crate::to_args::ToArgs;
 use arbitrary::Arbitrary;
 use clap::Args;
+use clap::ValueEnum;
 use std::ffi::OsString;
 use std::path::PathBuf;
 
+/// Kinds of change the structural diff can be told to overlook
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum, Arbitrary)]
+pub enum DiffIgnore {
+    /// Created, modified and access times
+    Timestamps,
+    /// Only the last access time
+    AccessTime,
+}
+
+impl DiffIgnore {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiffIgnore::Timestamps => "timestamps",
+            DiffIgnore::AccessTime => "access-time",
+        }
+    }
+}
+
 /// Arguments for comparing two MFT files
 #[derive(Args, Clone, PartialEq, Debug, Arbitrary)]
 pub struct MftDiffArgs {
-    #[clap(help = "First MFT file to compare")]
+    #[clap(help = "First MFT file to compare, or a drive like 'C:' to read the live volume")]
     pub file1: PathBuf,
 
-    #[clap(help = "Second MFT file to compare")]
+    #[clap(help = "Second MFT file to compare, or a drive like 'C:' to read the live volume")]
     pub file2: PathBuf,
 
-    #[clap(long, help = "Show detailed byte-by-byte differences")]
+    #[clap(long, help = "Show every difference instead of the first few")]
     pub verbose: bool,
 
-    #[clap(long, help = "Maximum number of differences to show (default: 10)")]
+    #[clap(
+        long,
+        help = "Maximum number of differences to show per category (default: 10)"
+    )]
     pub max_diffs: Option<usize>,
+
+    #[clap(long, help = "Compare raw bytes instead of parsed file entries")]
+    pub bytes: bool,
+
+    #[clap(
+        long,
+        value_name = "PATH",
+        conflicts_with = "bytes",
+        help = "Also write every difference to a report file; HTML when the path ends in .html/.htm, otherwise JSON"
+    )]
+    pub report: Option<PathBuf>,
+
+    #[clap(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        conflicts_with = "bytes",
+        help = "Don't count these changes as modifications (e.g. 'timestamps' or 'access-time')"
+    )]
+    pub ignore: Vec<DiffIgnore>,
+
+    #[clap(
+        long,
+        value_name = "DIR",
+        conflicts_with = "bytes",
+        help = "Only compare entries at or below this directory (e.g. 'C:\\Program Files'); without a drive letter it applies to any drive"
+    )]
+    pub under: Option<String>,
+
+    #[clap(
+        long,
+        default_value = "10",
+        conflicts_with = "bytes",
+        help = "Number of directories to list as top growers and shrinkers (0 to hide)"
+    )]
+    pub top_dirs: usize,
+
+    #[clap(
+        long,
+        default_value = "2",
+        conflicts_with = "bytes",
+        help = "Directory depth below the drive root to group size changes by"
+    )]
+    pub dir_depth: usize,
 }
 
 impl MftDiffArgs {
-    pub fn run(self) -> eyre::Result<()> {
-        crate::mft_diff::diff_mft_files(self.file1, self.file2, self.verbose, self.max_diffs)
+    pub fn run(mut self) -> eyre::Result<()> {
+        if self.max_diffs.is_none() {
+            self.max_diffs = Config::load()?.max_diffs;
+        }
+        if self.bytes {
+            return crate::mft_diff::diff_mft_bytes(
+                self.file1,
+                self.file2,
+                self.verbose,
+                self.max_diffs,
+            );
+        }
+        crate::mft_diff::diff_mft_entries(self)
     }
 }
 
@@ -41,6 +119,36 @@ impl ToArgs for MftDiffArgs {
             args.push(max_diffs.to_string().into());
         }
 
+        if self.bytes {
+            args.push("--bytes".into());
+        }
+
+        if let Some(report) = &self.report {
+            args.push("--report".into());
+            args.push(report.as_os_str().into());
+        }
+
+        if !self.ignore.is_empty() {
+            args.push("--ignore".into());
+            let ignore: Vec<&str> = self.ignore.iter().map(|i| i.as_str()).collect();
+            args.push(ignore.join(",").into());
+        }
+
+        if let Some(under) = &self.under {
+            args.push("--under".into());
+            args.push(under.clone().into());
+        }
+
+        if self.top_dirs != 10 {
+            args.push("--top-dirs".into());
+            args.push(self.top_dirs.to_string().into());
+        }
+
+        if self.dir_depth != 2 {
+            args.push("--dir-depth".into());
+            args.push(self.dir_depth.to_string().into());
+        }
+
         args
     }
 }
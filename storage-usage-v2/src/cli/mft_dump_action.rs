@@ -1,5 +1,7 @@
 use super::drive_letter_pattern::DriveLetterPattern;
+use crate::config::Config;
 use crate::to_args::ToArgs;
+use crate::win_privileges::ensure_single_volume;
 use arbitrary::Arbitrary;
 use clap::Args;
 use eyre;
@@ -56,7 +58,9 @@ impl<'a> Arbitrary<'a> for MftDumpArgs {
 
 impl MftDumpArgs {
     pub fn run(self) -> eyre::Result<()> {
-        let drives = self.drive_letters.resolve()?;
+        let drive_letters = Config::load()?.drives_or(self.drive_letters)?;
+        let drives = drive_letters.resolve()?;
+        ensure_single_volume(drives.len())?;
 
         if drives.len() > 1 {
             let output_str = self.output_path.to_string_lossy().into_owned();
@@ -75,7 +79,7 @@ impl MftDumpArgs {
         } else if drives.len() == 1 {
             crate::mft_dump::dump_mft_to_file(&self.output_path, self.overwrite_existing, drives[0])?;
         } else {
-            return Err(eyre::eyre!("No valid drives found for: {}", self.drive_letters));
+            return Err(eyre::eyre!("No valid drives found for: {}", drive_letters));
         }
         Ok(())
     }
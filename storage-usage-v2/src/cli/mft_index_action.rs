@@ -0,0 +1,184 @@
+use super::drive_letter_pattern::DriveLetterPattern;
+use crate::app_error::AppError;
+use crate::config::Config;
+use crate::config::get_cache_dir;
+use crate::mft_index::MftIndex;
+use crate::mft_index::index_path_for;
+use crate::progress_bars::build_index_with_bar;
+use crate::search_filter::parse_size;
+use crate::to_args::ToArgs;
+use crate::win_elevation::elevation_allowed;
+use crate::win_elevation::is_elevated;
+use crate::win_elevation::relaunch_as_admin;
+use arbitrary::Arbitrary;
+use clap::Args;
+use clap::Subcommand;
+use rayon::prelude::*;
+use std::ffi::OsString;
+use std::path::PathBuf;
+use tracing::info;
+use tracing::warn;
+
+/// Index command arguments container
+#[derive(Args, Arbitrary, PartialEq, Debug, Clone)]
+pub struct MftIndexArgs {
+    #[clap(subcommand)]
+    pub action: MftIndexAction,
+}
+
+impl MftIndexArgs {
+    pub fn run(self) -> eyre::Result<()> {
+        self.action.run()
+    }
+}
+
+impl ToArgs for MftIndexArgs {
+    fn to_args(&self) -> Vec<OsString> {
+        self.action.to_args()
+    }
+}
+
+/// Operations on the persistent query index stored next to cached MFTs
+#[derive(Subcommand, Arbitrary, PartialEq, Debug, Clone)]
+pub enum MftIndexAction {
+    /// Build (or rebuild) the index for cached MFTs matching a drive pattern
+    Build {
+        /// Drive letter pattern to select cached MFTs (e.g. '*', 'C', 'CD', 'C,D')
+        #[clap(default_value_t = DriveLetterPattern::default())]
+        drive_pattern: DriveLetterPattern,
+        /// Index within about this much memory (e.g. '2GB'), writing entries as they're parsed,
+        /// spilling the path sort to temp files and indexing drives one at a time
+        #[clap(long, value_name = "SIZE", value_parser = parse_memory_limit)]
+        memory_limit: Option<usize>,
+    },
+    /// Apply the changes the USN journal recorded since the last update to the live volume
+    Update {
+        /// Drive letter pattern to select cached MFTs (e.g. '*', 'C', 'CD', 'C,D')
+        #[clap(default_value_t = DriveLetterPattern::default())]
+        drive_pattern: DriveLetterPattern,
+    },
+}
+
+impl MftIndexAction {
+    pub fn run(self) -> eyre::Result<()> {
+        match self {
+            MftIndexAction::Build {
+                drive_pattern,
+                memory_limit,
+            } => {
+                let mft_files: Vec<_> = cached_mft_files(drive_pattern)?
+                    .into_iter()
+                    .map(|(_, p)| p)
+                    .collect();
+                // Each drive indexed at once would take its own share of the limit
+                match memory_limit {
+                    Some(_) => mft_files
+                        .iter()
+                        .try_for_each(|p| build_index_with_bar(p, memory_limit).map(|_| ())),
+                    None => mft_files
+                        .par_iter()
+                        .try_for_each(|p| build_index_with_bar(p, None).map(|_| ())),
+                }
+            }
+            MftIndexAction::Update { drive_pattern } => {
+                let mft_files = cached_mft_files(drive_pattern)?;
+                if !is_elevated() {
+                    if !elevation_allowed() {
+                        return Err(AppError::NotElevated {
+                            operation: "Reading the USN journal".to_string(),
+                            hint: "Run elevated, or rebuild from a fresh dump with mft sync and mft index build.",
+                        }
+                        .into());
+                    }
+                    warn!("Updating indexes from the USN journal needs elevated privileges.");
+                    info!("Relaunching as administrator...");
+                    let child = relaunch_as_admin()?;
+                    let exit_code = child.wait()?;
+                    std::process::exit(exit_code as i32);
+                }
+                for (drive, mft_file) in mft_files {
+                    let index_file = index_path_for(&mft_file);
+                    // A missing or unreadable index has no journal position, so it's rebuilt
+                    let mut index = if index_file.exists() {
+                        MftIndex::read_from_file(&index_file).unwrap_or_else(|e| {
+                            warn!(
+                                "Rebuilding unreadable index {}: {e:#}",
+                                index_file.display()
+                            );
+                            MftIndex::default()
+                        })
+                    } else {
+                        MftIndex::default()
+                    };
+                    let update = index.update_from_journal(drive)?;
+                    index.write_to_file(&index_file)?;
+                    if update.rebuilt {
+                        info!(
+                            "Rebuilt {} with {} entries from the live MFT of {drive}:",
+                            index_file.display(),
+                            index.len()
+                        );
+                    } else {
+                        info!(
+                            "Applied changes to {} records of {drive}: to {}",
+                            update.changed_records,
+                            index_file.display()
+                        );
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A size like '512MB' in bytes
+fn parse_memory_limit(value: &str) -> Result<usize, String> {
+    parse_size(value)
+        .map(|bytes| bytes as usize)
+        .ok_or_else(|| format!("invalid size '{value}', expected e.g. '512MB' or '2GB'"))
+}
+
+/// The cached dumps of the drives matching a pattern, erroring when there are none
+fn cached_mft_files(drive_pattern: DriveLetterPattern) -> eyre::Result<Vec<(char, PathBuf)>> {
+    let drive_pattern = Config::load()?.drives_or(drive_pattern)?;
+    let cache = get_cache_dir()?;
+    let mft_files: Vec<_> = drive_pattern
+        .resolve()?
+        .into_iter()
+        .map(|d| (d, cache.join(format!("{d}.mft"))))
+        .filter(|(_, p)| p.exists())
+        .collect();
+    if mft_files.is_empty() {
+        return Err(AppError::NoCachedDumps {
+            pattern: drive_pattern.to_string(),
+            hint: "Run mft sync first.",
+        }
+        .into());
+    }
+    Ok(mft_files)
+}
+
+impl ToArgs for MftIndexAction {
+    fn to_args(&self) -> Vec<OsString> {
+        let mut args = Vec::new();
+        match self {
+            MftIndexAction::Build {
+                drive_pattern,
+                memory_limit,
+            } => {
+                args.push("build".into());
+                args.push(drive_pattern.to_string().into());
+                if let Some(memory_limit) = memory_limit {
+                    args.push("--memory-limit".into());
+                    args.push(memory_limit.to_string().into());
+                }
+            }
+            MftIndexAction::Update { drive_pattern } => {
+                args.push("update".into());
+                args.push(drive_pattern.to_string().into());
+            }
+        }
+        args
+    }
+}
@@ -1,11 +1,39 @@
 use crate::to_args::ToArgs;
 use arbitrary::Arbitrary;
 use clap::Args;
+use clap::ValueEnum;
 use std::ffi::OsString;
 use super::drive_letter_pattern::DriveLetterPattern;
+use super::output_format::OutputFormat;
+use super::output_format::requested_output_format;
+use crate::exclude::ExcludeSet;
+use crate::file_flags::FileFlag;
+use crate::search_filter::PrefilterMode;
 use std::time::Duration;
 use humantime::parse_duration;
 
+/// How to summarize query matches instead of listing them
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum, Arbitrary)]
+pub enum QueryAggregate {
+    /// List individual matches
+    #[default]
+    None,
+    /// Totals per file extension
+    Ext,
+    /// Totals per top-level directory
+    Dir,
+}
+
+impl QueryAggregate {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QueryAggregate::None => "none",
+            QueryAggregate::Ext => "ext",
+            QueryAggregate::Dir => "dir",
+        }
+    }
+}
+
 /// Arguments for fuzzy searching files within cached MFTs matching a drive pattern
 #[derive(Args, Clone, PartialEq, Debug, Arbitrary)]
 pub struct MftQueryArgs {
@@ -16,7 +44,10 @@ pub struct MftQueryArgs {
     )]
     pub drive_pattern: DriveLetterPattern,
 
-    #[clap(help = "Search query for fuzzy matching filenames")]
+    #[clap(
+        default_value = "",
+        help = "Search query for fuzzy matching filenames (initial picker query with --pick)"
+    )]
     pub query: String,
 
     #[clap(
@@ -47,18 +78,91 @@ pub struct MftQueryArgs {
         help = "Maximum total run time before aborting (e.g. '5s', '2m'). If omitted, runs until completion"
     )]
     pub timeout: Option<Duration>,
+
+    /// Output format for matches, from the global `--format`. Non-text formats suppress the
+    /// interval previews
+    #[clap(skip)]
+    #[arbitrary(default)]
+    pub format: OutputFormat,
+
+    #[clap(
+        long,
+        help = "Open an interactive fuzzy picker and print the selected path(s) to stdout"
+    )]
+    pub pick: bool,
+
+    #[clap(
+        long,
+        value_name = "GLOB",
+        help = "Exclude paths matching this glob (repeatable). Matched against the full path and each path component; patterns in the config dir's ignore.txt are always applied"
+    )]
+    pub exclude: Vec<String>,
+
+    #[clap(
+        long,
+        help = "Read the MFT directly from the live volume instead of the cached dump (requires elevation)"
+    )]
+    pub live: bool,
+
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = QueryAggregate::None,
+        help = "Report match counts and bytes grouped by extension or top-level directory instead of listing files"
+    )]
+    pub aggregate: QueryAggregate,
+
+    #[clap(
+        long,
+        value_enum,
+        value_name = "FLAG",
+        help = "Only include entries that have this attribute flag (repeatable; all must match)"
+    )]
+    pub only: Vec<FileFlag>,
+
+    #[clap(
+        long,
+        help = "Also list files whose records are no longer in use, such as recently deleted ones. Their paths are best-effort, as their directories may be gone too. Implied by --only deleted"
+    )]
+    pub include_deleted: bool,
+
+    #[clap(
+        long,
+        value_name = "NAME",
+        help = "Run a named query from saved-queries.json in the config dir; explicit arguments override its values"
+    )]
+    pub saved: Option<String>,
+
+    #[clap(
+        long,
+        value_name = "DIR",
+        help = "Only include files below this directory (e.g. 'C:\\Users\\me'); without a drive letter it applies on every drive. A fresh index is searched for the directory instead of read whole"
+    )]
+    pub under: Option<String>,
+
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = PrefilterMode::Off,
+        help = "Skip paths cheaply before fuzzy matching them: 'chars' skips paths missing a letter or digit of the query and never drops a match; 'words' keeps only paths containing each query word as written, ignoring case"
+    )]
+    pub prefilter: PrefilterMode,
 }
 
 impl MftQueryArgs {
-    pub fn run(self) -> eyre::Result<()> {
-        crate::mft_query::query_mft_files_fuzzy(
-            self.drive_pattern,
-            self.query,
-            self.limit,
-            self.display_interval,
-            self.top_n,
-            self.timeout,
-        )
+    pub fn run(mut self) -> eyre::Result<()> {
+        if let Some(format) = requested_output_format() {
+            self.format = format;
+        }
+        if let Some(name) = &self.saved {
+            crate::saved_query::find_saved_query(name)?.apply_to(&mut self)?;
+        }
+        crate::config::Config::load()?.apply_to(&mut self)?;
+        let excludes = ExcludeSet::with_ignore_file(&self.exclude)?;
+        if self.pick {
+            return crate::mft_query::pick_mft_files(self.drive_pattern, self.query, excludes, self.only, self.include_deleted, self.under);
+        }
+        crate::mft_query::query_mft_files_fuzzy(self, excludes)
     }
 }
 
@@ -80,6 +184,33 @@ impl ToArgs for MftQueryArgs {
             args.push(self.top_n.to_string().into());
         }
         if let Some(timeout) = self.timeout { args.push("--timeout".into()); args.push(humantime::format_duration(timeout).to_string().into()); }
+        if self.pick { args.push("--pick".into()); }
+        if self.live { args.push("--live".into()); }
+        if self.aggregate != QueryAggregate::None {
+            args.push("--aggregate".into());
+            args.push(self.aggregate.as_str().into());
+        }
+        for flag in &self.only {
+            args.push("--only".into());
+            args.push(flag.as_str().into());
+        }
+        if self.include_deleted { args.push("--include-deleted".into()); }
+        for pattern in &self.exclude {
+            args.push("--exclude".into());
+            args.push(pattern.clone().into());
+        }
+        if let Some(name) = &self.saved {
+            args.push("--saved".into());
+            args.push(name.clone().into());
+        }
+        if let Some(directory) = &self.under {
+            args.push("--under".into());
+            args.push(directory.clone().into());
+        }
+        if self.prefilter != PrefilterMode::Off {
+            args.push("--prefilter".into());
+            args.push(self.prefilter.as_str().into());
+        }
         args
     }
 }
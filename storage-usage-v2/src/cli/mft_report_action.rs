@@ -0,0 +1,604 @@
+use super::drive_letter_pattern::DriveLetterPattern;
+use crate::app_error::AppError;
+use crate::cli::output_format::OutputFormat;
+use crate::cli::output_format::csv_escape;
+use crate::cli::output_format::output_format;
+use crate::cli::output_format::print_json;
+use crate::config::Config;
+use crate::config::get_cache_dir;
+use crate::mft_cleanup::CleanupFinding;
+use crate::mft_cleanup::CleanupKind;
+use crate::mft_cleanup::clean_up_interactively;
+use crate::mft_cleanup::find_cleanup;
+use crate::mft_cleanup::stale_before;
+use crate::mft_diff::ChangeKind;
+use crate::mft_diff::DiffOptions;
+use crate::mft_diff::DiffSource;
+use crate::mft_diff::DirectoryDelta;
+use crate::mft_diff::ExtensionDelta;
+use crate::mft_diff::MftDiff;
+use crate::mft_diff::drive_letter_for;
+use crate::mft_diff::format_signed_size;
+use crate::mft_index::DirectoryUsage;
+use crate::progress_bars::index_with_bar;
+use crate::security::OwnerUsage;
+use crate::security::usage_by_owner;
+use crate::to_args::ToArgs;
+use arbitrary::Arbitrary;
+use chrono::Utc;
+use clap::Args;
+use clap::Subcommand;
+use humansize::DECIMAL;
+use humansize::format_size;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::PathBuf;
+use tracing::warn;
+
+/// Directory levels listed by `mft report dirs` unless `--depth` says otherwise
+const DEFAULT_DIR_DEPTH: usize = 2;
+
+/// Directories listed by `mft report dirs` unless `--top` says otherwise
+const DEFAULT_TOP_DIRS: usize = 50;
+
+/// Directories and extensions listed by `mft report growth` unless `--top` says otherwise
+const DEFAULT_TOP_GROWTH: usize = 10;
+
+/// Days before crash dumps and virtual disks count as stale unless `--older-than` says otherwise
+const DEFAULT_STALE_DAYS: u64 = 30;
+
+/// Arguments for reports that add up the files of cached MFTs
+#[derive(Args, Arbitrary, PartialEq, Debug, Clone)]
+pub struct MftReportArgs {
+    /// Drive letter pattern to select cached MFTs (e.g. '*', 'C', 'CD', 'C,D')
+    #[clap(default_value_t = DriveLetterPattern::default())]
+    pub drive_pattern: DriveLetterPattern,
+
+    /// Add up files and bytes per owner, from the security descriptors dumped with each MFT
+    #[clap(long)]
+    pub by_owner: bool,
+
+    #[clap(subcommand)]
+    pub report: Option<MftReport>,
+}
+
+/// Reports with options of their own
+#[derive(Subcommand, Arbitrary, PartialEq, Debug, Clone)]
+pub enum MftReport {
+    /// The largest directories by the allocated bytes of the files below them, like du
+    Dirs {
+        /// Drive letter pattern to select cached MFTs (e.g. '*', 'C', 'CD', 'C,D')
+        #[clap(default_value_t = DriveLetterPattern::default())]
+        drive_pattern: DriveLetterPattern,
+        /// Directory levels below the root to list, 1 for `C:\Users` alone
+        #[clap(long, default_value_t = DEFAULT_DIR_DEPTH)]
+        depth: usize,
+        /// Directories listed per drive, or in all with --merge
+        #[clap(long, default_value_t = DEFAULT_TOP_DIRS)]
+        top: usize,
+        /// Rank the directories of every drive together instead of per drive
+        #[clap(long)]
+        merge: bool,
+    },
+    /// The directories and extensions that grew the most between two dumps of a drive
+    Growth {
+        /// The older dump, such as a copy of `C.mft` kept from an earlier sync
+        #[clap(long, value_name = "DUMP")]
+        from: PathBuf,
+        /// The newer dump
+        #[clap(long, value_name = "DUMP")]
+        to: PathBuf,
+        /// Directory levels below the root to group growth by, 1 for `C:\Users`
+        #[clap(long, default_value_t = DEFAULT_DIR_DEPTH)]
+        depth: usize,
+        /// Directories and extensions to list
+        #[clap(long, default_value_t = DEFAULT_TOP_GROWTH)]
+        top: usize,
+    },
+    /// Space that's usually safe to reclaim: temp files, the Recycle Bin, Windows.old, installer
+    /// caches, old crash dumps, browser caches and stale Docker and WSL disks
+    Cleanup {
+        /// Drive letter pattern to select cached MFTs (e.g. '*', 'C', 'CD', 'C,D')
+        #[clap(default_value_t = DriveLetterPattern::default())]
+        drive_pattern: DriveLetterPattern,
+        /// Days since a crash dump or virtual disk was last modified before it counts
+        #[clap(long, value_name = "DAYS", default_value_t = DEFAULT_STALE_DAYS)]
+        older_than: u64,
+        /// Go through the findings one at a time, asking whether to send the files of each to
+        /// the Recycle Bin
+        #[clap(long)]
+        interactive: bool,
+        /// With --interactive, delete the files outright instead of recycling them
+        #[clap(long)]
+        permanent: bool,
+    },
+}
+
+/// One owner's usage on one drive, as printed for structured formats
+#[derive(Serialize)]
+struct DriveOwnerUsage {
+    drive: char,
+    #[serde(flatten)]
+    usage: OwnerUsage,
+}
+
+/// What `mft report growth` prints for structured formats
+#[derive(Serialize)]
+struct GrowthReport {
+    from: PathBuf,
+    to: PathBuf,
+    added: usize,
+    removed: usize,
+    modified: usize,
+    net_size_change: i64,
+    /// Largest growth first, only those that grew
+    directories: Vec<DirectoryDelta>,
+    extensions: Vec<ExtensionDelta>,
+}
+
+/// One directory's usage on one drive, as printed for structured formats
+#[derive(Serialize)]
+struct DriveDirectoryUsage {
+    drive: char,
+    #[serde(flatten)]
+    usage: DirectoryUsage,
+}
+
+impl MftReportArgs {
+    pub fn run(self) -> eyre::Result<()> {
+        match self.report {
+            Some(_) if self.by_owner => Err(eyre::eyre!(
+                "--by-owner is a report of its own; run it separately"
+            )),
+            Some(report) => report.run(),
+            None if self.by_owner => report_by_owner(self.drive_pattern),
+            None => Err(eyre::eyre!(
+                "Pick a report to run, such as --by-owner, dirs or cleanup"
+            )),
+        }
+    }
+}
+
+impl MftReport {
+    pub fn run(self) -> eyre::Result<()> {
+        match self {
+            MftReport::Dirs {
+                drive_pattern,
+                depth,
+                top,
+                merge,
+            } => report_dirs(drive_pattern, depth, top, merge),
+            MftReport::Growth {
+                from,
+                to,
+                depth,
+                top,
+            } => report_growth(from, to, depth, top),
+            MftReport::Cleanup {
+                drive_pattern,
+                older_than,
+                interactive,
+                permanent,
+            } => report_cleanup(drive_pattern, older_than, interactive, permanent),
+        }
+    }
+}
+
+/// Cached dumps of the drives a pattern (or the configured drives) selects
+fn cached_mft_files(drive_pattern: DriveLetterPattern) -> eyre::Result<Vec<(char, PathBuf)>> {
+    let drive_pattern = Config::load()?.drives_or(drive_pattern)?;
+    let cache = get_cache_dir()?;
+    let mft_files: Vec<_> = drive_pattern
+        .resolve()?
+        .into_iter()
+        .map(|drive| (drive, cache.join(format!("{drive}.mft"))))
+        .filter(|(_, path)| path.exists())
+        .collect();
+    if mft_files.is_empty() {
+        return Err(AppError::NoCachedDumps {
+            pattern: drive_pattern.to_string(),
+            hint: "Run mft sync first.",
+        }
+        .into());
+    }
+    Ok(mft_files)
+}
+
+fn report_dirs(
+    drive_pattern: DriveLetterPattern,
+    depth: usize,
+    top: usize,
+    merge: bool,
+) -> eyre::Result<()> {
+    let mut rows = Vec::new();
+    for (drive, mft_file) in cached_mft_files(drive_pattern)? {
+        let usage = index_with_bar(&mft_file)?.directory_usage(drive, depth.max(1));
+        // Merged rankings are cut after sorting every drive's directories together
+        let keep = if merge { usage.len() } else { top };
+        rows.extend(
+            usage
+                .into_iter()
+                .take(keep)
+                .map(|usage| DriveDirectoryUsage { drive, usage }),
+        );
+    }
+    if merge {
+        rows.sort_by(|a, b| {
+            b.usage
+                .allocated_bytes
+                .cmp(&a.usage.allocated_bytes)
+                .then_with(|| a.usage.path.cmp(&b.usage.path))
+        });
+        rows.truncate(top);
+    }
+
+    let format = output_format();
+    match format {
+        OutputFormat::Text => print_directories(&rows, merge),
+        OutputFormat::Json | OutputFormat::Jsonl => print_json(&rows, format)?,
+        OutputFormat::Csv => {
+            println!("drive,path,depth,files,bytes,allocated_bytes");
+            for row in &rows {
+                let usage = &row.usage;
+                println!(
+                    "{},{},{},{},{},{}",
+                    row.drive,
+                    csv_escape(&usage.path),
+                    usage.depth,
+                    usage.files,
+                    usage.bytes,
+                    usage.allocated_bytes
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_directories(rows: &[DriveDirectoryUsage], merge: bool) {
+    let mut drive = None;
+    for (i, row) in rows.iter().enumerate() {
+        let heading = if merge {
+            (i == 0).then(|| "All drives:".to_string())
+        } else {
+            (drive != Some(row.drive)).then(|| format!("Drive {}:", row.drive))
+        };
+        if let Some(heading) = heading {
+            drive = Some(row.drive);
+            println!("{heading}");
+            println!(
+                "  {:<60} {:>10} {:>12} {:>12}",
+                "Directory", "Files", "Size", "Allocated"
+            );
+        }
+        let usage = &row.usage;
+        println!(
+            "  {:<60} {:>10} {:>12} {:>12}",
+            usage.path,
+            usage.files,
+            format_size(usage.bytes, DECIMAL),
+            format_size(usage.allocated_bytes, DECIMAL)
+        );
+    }
+}
+
+fn report_growth(from: PathBuf, to: PathBuf, depth: usize, top: usize) -> eyre::Result<()> {
+    let (old_drive, new_drive) = (drive_letter_for(&from), drive_letter_for(&to));
+    if old_drive != new_drive && old_drive != '?' && new_drive != '?' {
+        warn!(
+            "Comparing dumps of {old_drive}: and {new_drive}:; paths are matched as if on {new_drive}:"
+        );
+    }
+    let (old, new) = rayon::join(
+        || DiffSource::Dump(from.clone()).load_index(),
+        || DiffSource::Dump(to.clone()).load_index(),
+    );
+    let diff = MftDiff::between(&old?, old_drive, &new?, new_drive, &DiffOptions::default());
+    let grown = |size_delta: i64| size_delta > 0;
+    let report = GrowthReport {
+        added: diff.count(ChangeKind::Added),
+        removed: diff.count(ChangeKind::Removed),
+        modified: diff.count(ChangeKind::Modified),
+        net_size_change: diff.net_size_change(),
+        directories: diff
+            .directory_deltas(depth)
+            .into_iter()
+            .filter(|delta| grown(delta.size_delta))
+            .take(top)
+            .collect(),
+        extensions: diff
+            .extension_deltas()
+            .into_iter()
+            .filter(|delta| grown(delta.size_delta))
+            .take(top)
+            .collect(),
+        from,
+        to,
+    };
+
+    let format = output_format();
+    match format {
+        OutputFormat::Text => print_growth(&report),
+        OutputFormat::Json | OutputFormat::Jsonl => print_json(&report, format)?,
+        OutputFormat::Csv => {
+            println!("kind,name,size_delta,changes");
+            for delta in &report.directories {
+                println!(
+                    "directory,{},{},{}",
+                    csv_escape(&delta.path),
+                    delta.size_delta,
+                    delta.changes
+                );
+            }
+            for delta in &report.extensions {
+                println!(
+                    "extension,{},{},{}",
+                    csv_escape(&delta.extension),
+                    delta.size_delta,
+                    delta.changes
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_growth(report: &GrowthReport) {
+    println!(
+        "{} -> {}: {} net ({} added, {} removed, {} modified)",
+        report.from.display(),
+        report.to.display(),
+        format_signed_size(report.net_size_change),
+        report.added,
+        report.removed,
+        report.modified
+    );
+    println!("\n  {:<60} {:>12} {:>10}", "Directory", "Growth", "Changes");
+    for delta in &report.directories {
+        println!(
+            "  {:<60} {:>12} {:>10}",
+            delta.path,
+            format_signed_size(delta.size_delta),
+            delta.changes
+        );
+    }
+    println!("\n  {:<60} {:>12} {:>10}", "Extension", "Growth", "Changes");
+    for delta in &report.extensions {
+        println!(
+            "  {:<60} {:>12} {:>10}",
+            delta.extension,
+            format_signed_size(delta.size_delta),
+            delta.changes
+        );
+    }
+}
+
+fn report_cleanup(
+    drive_pattern: DriveLetterPattern,
+    older_than: u64,
+    interactive: bool,
+    permanent: bool,
+) -> eyre::Result<()> {
+    let format = output_format();
+    if interactive && format != OutputFormat::Text {
+        return Err(eyre::eyre!(
+            "--interactive asks on the terminal, so it needs text output"
+        ));
+    }
+    if permanent && !interactive {
+        return Err(eyre::eyre!("--permanent only applies with --interactive"));
+    }
+    let stale_before = stale_before(Utc::now(), older_than);
+    let mut findings = Vec::new();
+    for (drive, mft_file) in cached_mft_files(drive_pattern)? {
+        findings.extend(find_cleanup(
+            &index_with_bar(&mft_file)?,
+            drive,
+            stale_before,
+        ));
+    }
+
+    match format {
+        OutputFormat::Text => print_cleanup(&findings),
+        OutputFormat::Json | OutputFormat::Jsonl => print_json(&findings, format)?,
+        OutputFormat::Csv => {
+            println!("drive,kind,path,files,bytes,allocated_bytes,newest");
+            for finding in &findings {
+                println!(
+                    "{},{},{},{},{},{},{}",
+                    finding.drive,
+                    finding.kind.as_str(),
+                    csv_escape(&finding.path),
+                    finding.files,
+                    finding.bytes,
+                    finding.allocated_bytes,
+                    finding
+                        .newest
+                        .map(|newest| newest.to_rfc3339())
+                        .unwrap_or_default()
+                );
+            }
+        }
+    }
+    if interactive && !findings.is_empty() {
+        println!();
+        clean_up_interactively(&findings, permanent)?;
+    }
+    Ok(())
+}
+
+fn print_cleanup(findings: &[CleanupFinding]) {
+    let mut drive = None;
+    for finding in findings {
+        if drive != Some(finding.drive) {
+            drive = Some(finding.drive);
+            println!("Drive {}:", finding.drive);
+            println!(
+                "  {:<16} {:<60} {:>10} {:>12}",
+                "Kind", "Location", "Files", "Allocated"
+            );
+        }
+        println!(
+            "  {:<16} {:<60} {:>10} {:>12}",
+            finding.kind.as_str(),
+            finding.path,
+            finding.files,
+            format_size(finding.allocated_bytes, DECIMAL)
+        );
+    }
+    let mut by_kind: BTreeMap<CleanupKind, u64> = BTreeMap::new();
+    for finding in findings {
+        *by_kind.entry(finding.kind).or_default() += finding.allocated_bytes;
+    }
+    println!("\nTotal by kind:");
+    for (kind, bytes) in &by_kind {
+        println!(
+            "  {:<16} {:>12}",
+            kind.as_str(),
+            format_size(*bytes, DECIMAL)
+        );
+    }
+    println!(
+        "  {:<16} {:>12}",
+        "all",
+        format_size(by_kind.values().sum::<u64>(), DECIMAL)
+    );
+}
+
+fn report_by_owner(drive_pattern: DriveLetterPattern) -> eyre::Result<()> {
+    let mut rows = Vec::new();
+    for (drive, mft_file) in cached_mft_files(drive_pattern)? {
+        rows.extend(
+            usage_by_owner(&mft_file)?
+                .into_iter()
+                .map(|usage| DriveOwnerUsage { drive, usage }),
+        );
+    }
+
+    let format = output_format();
+    match format {
+        OutputFormat::Text => print_owners(&rows),
+        OutputFormat::Json | OutputFormat::Jsonl => print_json(&rows, format)?,
+        OutputFormat::Csv => {
+            println!("drive,sid,account,files,bytes,allocated_bytes");
+            for row in &rows {
+                let usage = &row.usage;
+                println!(
+                    "{},{},{},{},{},{}",
+                    row.drive,
+                    usage.sid,
+                    csv_escape(usage.account.as_deref().unwrap_or_default()),
+                    usage.files,
+                    usage.bytes,
+                    usage.allocated_bytes
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_owners(rows: &[DriveOwnerUsage]) {
+    let mut drive = None;
+    for row in rows {
+        if drive != Some(row.drive) {
+            drive = Some(row.drive);
+            println!("Drive {}:", row.drive);
+            println!(
+                "  {:<40} {:>10} {:>12} {:>12}",
+                "Owner", "Files", "Size", "Allocated"
+            );
+        }
+        let usage = &row.usage;
+        println!(
+            "  {:<40} {:>10} {:>12} {:>12}",
+            usage.account.as_deref().unwrap_or(&usage.sid),
+            usage.files,
+            format_size(usage.bytes, DECIMAL),
+            format_size(usage.allocated_bytes, DECIMAL)
+        );
+    }
+}
+
+impl ToArgs for MftReportArgs {
+    fn to_args(&self) -> Vec<OsString> {
+        let mut args = vec![self.drive_pattern.to_string().into()];
+        if self.by_owner {
+            args.push("--by-owner".into());
+        }
+        if let Some(report) = &self.report {
+            args.extend(report.to_args());
+        }
+        args
+    }
+}
+
+impl ToArgs for MftReport {
+    fn to_args(&self) -> Vec<OsString> {
+        match self {
+            MftReport::Dirs {
+                drive_pattern,
+                depth,
+                top,
+                merge,
+            } => {
+                let mut args = vec!["dirs".into(), drive_pattern.to_string().into()];
+                if *depth != DEFAULT_DIR_DEPTH {
+                    args.push("--depth".into());
+                    args.push(depth.to_string().into());
+                }
+                if *top != DEFAULT_TOP_DIRS {
+                    args.push("--top".into());
+                    args.push(top.to_string().into());
+                }
+                if *merge {
+                    args.push("--merge".into());
+                }
+                args
+            }
+            MftReport::Growth {
+                from,
+                to,
+                depth,
+                top,
+            } => {
+                let mut args = vec![
+                    "growth".into(),
+                    "--from".into(),
+                    from.as_os_str().into(),
+                    "--to".into(),
+                    to.as_os_str().into(),
+                ];
+                if *depth != DEFAULT_DIR_DEPTH {
+                    args.push("--depth".into());
+                    args.push(depth.to_string().into());
+                }
+                if *top != DEFAULT_TOP_GROWTH {
+                    args.push("--top".into());
+                    args.push(top.to_string().into());
+                }
+                args
+            }
+            MftReport::Cleanup {
+                drive_pattern,
+                older_than,
+                interactive,
+                permanent,
+            } => {
+                let mut args = vec!["cleanup".into(), drive_pattern.to_string().into()];
+                if *older_than != DEFAULT_STALE_DAYS {
+                    args.push("--older-than".into());
+                    args.push(older_than.to_string().into());
+                }
+                if *interactive {
+                    args.push("--interactive".into());
+                }
+                if *permanent {
+                    args.push("--permanent".into());
+                }
+                args
+            }
+        }
+    }
+}
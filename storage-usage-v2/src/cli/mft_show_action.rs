@@ -1,7 +1,14 @@
+use crate::search_export::DEFAULT_EXPORT_LIMIT;
+use crate::config::Config;
+use crate::reparse::ReparsePolicy;
 use crate::to_args::ToArgs;
+use crate::tui::theme::Theme;
+use crate::tui::theme::ThemeName;
+use crate::tui::theme::set_theme;
 use arbitrary::Arbitrary;
 use clap::Args;
 use std::ffi::OsString;
+use std::path::PathBuf;
 use crate::config::get_cache_dir; // keep
 
 /// Arguments for generating MFT statistics and summary
@@ -16,7 +23,10 @@ pub struct MftShowArgs {
     #[clap(long, help = "Show detailed statistics about MFT entries")]
     pub verbose: bool,
 
-    #[clap(long, help = "Show sample file paths from the MFT")]
+    #[clap(
+        long,
+        help = "Show a sample of each MFT's file paths, with counts by kind, below the overview"
+    )]
     pub show_paths: bool,
 
     #[clap(
@@ -31,10 +41,57 @@ pub struct MftShowArgs {
         help = "Number of threads to use for parallel processing (default: auto-detect)"
     )]
     pub threads: Option<usize>,
+
+    #[clap(
+        long,
+        default_value_t = DEFAULT_EXPORT_LIMIT,
+        help = "Maximum number of search results written by the search tab export (Ctrl+O)"
+    )]
+    pub export_limit: usize,
+
+    #[clap(
+        long,
+        value_enum,
+        help = "Color theme; defaults to the user theme in theme.json when it exists, otherwise dark"
+    )]
+    pub theme: Option<ThemeName>,
+
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = ReparsePolicy::CountOnly,
+        help = "How the treemap and largest directories tabs roll up symlinks and junctions: add their target's size (follow), leave them out (skip), or list them without bytes (count-only)"
+    )]
+    pub reparse: ReparsePolicy,
+
+    #[clap(
+        long,
+        num_args = 2,
+        value_names = ["OLD", "NEW"],
+        conflicts_with = "mft_pattern",
+        help = "Load two MFT dumps and open a tab listing the files added, removed and resized between them"
+    )]
+    pub compare: Option<Vec<PathBuf>>,
 }
 
 impl MftShowArgs {
-    pub fn run(self) -> eyre::Result<()> {
+    pub fn run(mut self) -> eyre::Result<()> {
+        if self.export_limit == DEFAULT_EXPORT_LIMIT
+            && let Some(limit) = Config::load()?.export_limit
+        {
+            self.export_limit = limit;
+        }
+        if let Some(theme) = self.theme {
+            set_theme(Theme::by_name(theme)?);
+        }
+        if let Some([old, new]) = self.compare.as_deref() {
+            return crate::mft_show::compare_mft_files(
+                old.clone(),
+                new.clone(),
+                self.export_limit,
+                self.reparse,
+            );
+        }
         let resolved_pattern = match &self.mft_pattern {
             Some(p) => p.clone(),
             None => {
@@ -48,6 +105,8 @@ impl MftShowArgs {
             self.show_paths,
             self.max_entries,
             self.threads,
+            self.export_limit,
+            self.reparse,
         )
     }
 }
@@ -60,6 +119,10 @@ impl ToArgs for MftShowArgs {
         if self.show_paths { args.push("--show-paths".into()); }
         if let Some(max_entries) = self.max_entries { args.push("--max-entries".into()); args.push(max_entries.to_string().into()); }
         if let Some(threads) = self.threads { args.push("--threads".into()); args.push(threads.to_string().into()); }
+        if self.export_limit != DEFAULT_EXPORT_LIMIT { args.push("--export-limit".into()); args.push(self.export_limit.to_string().into()); }
+        if let Some(theme) = self.theme { args.push("--theme".into()); args.push(theme.as_str().into()); }
+        if self.reparse != ReparsePolicy::CountOnly { args.push("--reparse".into()); args.push(self.reparse.as_str().into()); }
+        if let Some(compare) = &self.compare { args.push("--compare".into()); args.extend(compare.iter().map(|p| p.clone().into())); }
         args
     }
 }
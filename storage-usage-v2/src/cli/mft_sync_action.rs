@@ -1,6 +1,8 @@
 use super::drive_letter_pattern::DriveLetterPattern;
+use crate::config::Config;
 use crate::config::get_cache_dir;
 use crate::to_args::ToArgs;
+use crate::win_privileges::ensure_single_volume;
 use arbitrary::Arbitrary;
 use clap::Args;
 use color_eyre::eyre;
@@ -37,7 +39,8 @@ impl<'a> Arbitrary<'a> for MftSyncArgs {
 
 impl MftSyncArgs {
     pub fn run(self) -> eyre::Result<()> {
-        let drives = self.drive_pattern.resolve()?;
+        let drives = Config::load()?.drives_or(self.drive_pattern)?.resolve()?;
+        ensure_single_volume(drives.len())?;
         let cache = get_cache_dir()?;
         fs::create_dir_all(&cache)?;
         let overwrite_existing = self.overwrite_existing; // capture for closure
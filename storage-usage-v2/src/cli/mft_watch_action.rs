@@ -0,0 +1,129 @@
+use super::drive_letter_pattern::DriveLetterPattern;
+use crate::app_error::AppError;
+use crate::cli::output_format::OutputFormat;
+use crate::cli::output_format::csv_escape;
+use crate::cli::output_format::output_format;
+use crate::cli::output_format::print_json;
+use crate::exclude::ExcludeSet;
+use crate::to_args::ToArgs;
+use crate::usn_journal::UsnEvent;
+use crate::usn_journal::UsnJournal;
+use crate::win_elevation::elevation_allowed;
+use crate::win_elevation::is_elevated;
+use crate::win_elevation::relaunch_as_admin;
+use arbitrary::Arbitrary;
+use clap::Args;
+use humansize::DECIMAL;
+use humansize::format_size;
+use std::ffi::OsString;
+use tracing::info;
+use tracing::warn;
+
+/// Arguments for streaming changes to a volume from its USN change journal
+#[derive(Args, Arbitrary, PartialEq, Debug, Clone)]
+pub struct MftWatchArgs {
+    /// Drive to watch (e.g. 'C')
+    pub drive_pattern: DriveLetterPattern,
+
+    /// Only report changes to paths matching this glob (repeatable). Matched like --exclude,
+    /// against the full path and each path component
+    #[clap(long, value_name = "GLOB")]
+    pub pattern: Vec<String>,
+}
+
+impl MftWatchArgs {
+    pub fn run(self) -> eyre::Result<()> {
+        let drives = self.drive_pattern.resolve()?;
+        let [drive_letter] = drives.as_slice() else {
+            return Err(eyre::eyre!(
+                "mft watch reads one volume, but '{}' matches {} drives",
+                self.drive_pattern,
+                drives.len()
+            ));
+        };
+        if !is_elevated() {
+            if !elevation_allowed() {
+                return Err(AppError::NotElevated {
+                    operation: format!("Reading the USN journal of drive {drive_letter}"),
+                    hint: "Run elevated.",
+                }
+                .into());
+            }
+            warn!("Reading the USN journal needs elevated privileges.");
+            info!("Relaunching as administrator...");
+            let child = relaunch_as_admin()?;
+            let exit_code = child.wait()?;
+            std::process::exit(exit_code as i32);
+        }
+
+        let filter = ExcludeSet::new(&self.pattern)?;
+        let format = output_format();
+        let mut journal = UsnJournal::open(*drive_letter)?;
+        info!(
+            "Watching drive {drive_letter} from USN {}",
+            journal.next_usn()
+        );
+        if format == OutputFormat::Csv {
+            println!("timestamp,change,path,old_path,record_number,is_dir,size");
+        }
+        loop {
+            for record in journal.read(true)? {
+                let Some(event) = journal.event(&record) else {
+                    continue;
+                };
+                // An empty set matches nothing, so no patterns means every path
+                if !filter.is_empty() && !filter.is_excluded(&event.path) {
+                    continue;
+                }
+                print_event(&event, format)?;
+            }
+        }
+    }
+}
+
+fn print_event(event: &UsnEvent, format: OutputFormat) -> eyre::Result<()> {
+    let timestamp = event.timestamp.map(|t| t.to_rfc3339()).unwrap_or_default();
+    match format {
+        OutputFormat::Text => {
+            let time = event
+                .timestamp
+                .map(|t| t.format("%H:%M:%S").to_string())
+                .unwrap_or_default();
+            let size = event
+                .size
+                .map(|size| format!(" ({})", format_size(size, DECIMAL)))
+                .unwrap_or_default();
+            match &event.old_path {
+                Some(old_path) => println!(
+                    "{time} {:<12} {old_path} -> {}",
+                    event.change.as_str(),
+                    event.path
+                ),
+                None => println!("{time} {:<12} {}{size}", event.change.as_str(), event.path),
+            }
+        }
+        // Events stream as they come, so JSON is printed a line per event too
+        OutputFormat::Json | OutputFormat::Jsonl => print_json(event, OutputFormat::Jsonl)?,
+        OutputFormat::Csv => println!(
+            "{timestamp},{},{},{},{},{},{}",
+            event.change.as_str(),
+            csv_escape(&event.path),
+            csv_escape(event.old_path.as_deref().unwrap_or_default()),
+            event.record_number,
+            event.is_dir,
+            event.size.map(|size| size.to_string()).unwrap_or_default()
+        ),
+    }
+    Ok(())
+}
+
+impl ToArgs for MftWatchArgs {
+    fn to_args(&self) -> Vec<OsString> {
+        let mut args = vec![self.drive_pattern.to_string().into()];
+        for pattern in &self.pattern {
+            args.push("--pattern".into());
+            args.push(pattern.into());
+        }
+        args
+    }
+}
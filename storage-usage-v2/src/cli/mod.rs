@@ -1,24 +1,44 @@
 use crate::cli::action::Action;
 use crate::cli::global_args::GlobalArgs;
+use crate::cli::output_format::configure_output_format;
+use crate::elevated_report::connect_reporter;
+use crate::progress_bars::configure_progress_bars;
 use crate::to_args::Invocable;
 use crate::to_args::ToArgs;
+use crate::tui::app::configure_rendering;
+use crate::win_elevation::configure_elevation;
+use crate::win_privileges::configure_privilege_dropping;
 use arbitrary::Arbitrary;
 use clap::Parser;
 use std::ffi::OsString;
 
 pub mod action;
+pub mod completions_action;
 pub mod config_action;
 pub mod drive_letter_pattern;
 pub mod elevation_action;
 pub mod elevation_check_action;
+pub mod elevation_run_action;
+pub mod elevation_strategy;
 pub mod elevation_test_action;
 pub mod global_args;
+pub mod metrics_action;
 pub mod mft_action;
+pub mod mft_bench_action;
+pub mod mft_check_action;
+pub mod mft_dedupe_action;
 pub mod mft_diff_action;
 pub mod mft_dump_action;
+pub mod mft_index_action;
 pub mod mft_query_action;
+pub mod mft_report_action;
 pub mod mft_show_action;
 pub mod mft_sync_action;
+pub mod mft_watch_action;
+pub mod output_format;
+pub mod schedule_action;
+pub mod serve_action;
+pub mod service_action;
 
 #[derive(Parser, Arbitrary, PartialEq, Debug)]
 #[clap(version)]
@@ -31,6 +51,16 @@ pub struct Cli {
 
 impl Cli {
     pub fn run(self) -> eyre::Result<()> {
+        configure_rendering(!self.global_args.no_effects, self.global_args.max_fps);
+        configure_elevation(!self.global_args.no_elevate);
+        configure_privilege_dropping(self.global_args.drop_privileges);
+        configure_output_format(self.global_args.format);
+        configure_progress_bars(self.global_args.quiet);
+        if let Some(report_pipe) = &self.global_args.report_pipe
+            && let Err(e) = connect_reporter(report_pipe)
+        {
+            tracing::warn!("{e:#}");
+        }
         self.action.run()
     }
 }
@@ -140,6 +170,7 @@ mod tests {
         use crate::cli::elevation_action::ElevationAction;
         use crate::cli::elevation_action::ElevationArgs;
         use crate::cli::elevation_check_action::ElevationCheckArgs;
+        use crate::cli::elevation_strategy::ElevationStrategy;
         use crate::cli::elevation_test_action::ElevationTestArgs;
         use crate::cli::global_args::GlobalArgs;
         use crate::cli::mft_action::MftAction;
@@ -151,6 +182,17 @@ mod tests {
                 global_args: GlobalArgs {
                     debug: false,
                     console_pid: None,
+                    no_effects: false,
+                    max_fps: None,
+                    no_elevate: false,
+                    drop_privileges: false,
+                    quiet: false,
+                    format: None,
+                    log_file: None,
+                    config_file: None,
+                    trace_out: None,
+                    report_pipe: None,
+                    relay_pipe: None,
                 },
                 action: Action::Mft(MftArgs {
                     action: MftAction::Dump(MftDumpArgs {
@@ -164,6 +206,17 @@ mod tests {
                 global_args: GlobalArgs {
                     debug: true,
                     console_pid: Some(1234),
+                    no_effects: true,
+                    max_fps: Some(60),
+                    no_elevate: true,
+                    drop_privileges: true,
+                    quiet: false,
+                    format: None,
+                    log_file: None,
+                    config_file: None,
+                    trace_out: None,
+                    report_pipe: None,
+                    relay_pipe: None,
                 },
                 action: Action::Mft(MftArgs {
                     action: MftAction::Dump(MftDumpArgs {
@@ -177,17 +230,43 @@ mod tests {
                 global_args: GlobalArgs {
                     debug: false,
                     console_pid: None,
+                    no_effects: false,
+                    max_fps: None,
+                    no_elevate: false,
+                    drop_privileges: false,
+                    quiet: false,
+                    format: None,
+                    log_file: None,
+                    config_file: None,
+                    trace_out: None,
+                    report_pipe: None,
+                    relay_pipe: None,
                 },
                 action: Action::Elevation(ElevationArgs {
-                    action: ElevationAction::Check(ElevationCheckArgs {}),
+                    strategy: ElevationStrategy::Uac,
+                    action: ElevationAction::Check(ElevationCheckArgs {
+                        capabilities: false,
+                    }),
                 }),
             },
             Cli {
                 global_args: GlobalArgs {
                     debug: true,
                     console_pid: Some(5678),
+                    no_effects: false,
+                    max_fps: None,
+                    no_elevate: false,
+                    drop_privileges: false,
+                    quiet: false,
+                    format: None,
+                    log_file: None,
+                    config_file: None,
+                    trace_out: None,
+                    report_pipe: None,
+                    relay_pipe: None,
                 },
                 action: Action::Elevation(ElevationArgs {
+                    strategy: ElevationStrategy::TaskScheduler,
                     action: ElevationAction::Test(ElevationTestArgs {}),
                 }),
             },
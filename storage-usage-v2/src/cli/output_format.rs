@@ -0,0 +1,89 @@
+use arbitrary::Arbitrary;
+use clap::ValueEnum;
+use serde::Deserialize;
+use serde::Serialize;
+use std::fmt;
+use std::io::Write;
+use std::sync::OnceLock;
+
+/// Format picked with the global `--format` flag, if any
+static REQUESTED_FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// Output format for commands that print results
+#[derive(
+    Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum, Arbitrary, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Human-readable text
+    #[default]
+    #[clap(alias = "human")]
+    #[serde(alias = "human")]
+    Text,
+    /// A single JSON array
+    Json,
+    /// One JSON object per line
+    Jsonl,
+    /// Comma-separated values with a header row
+    Csv,
+}
+
+impl OutputFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Json => "json",
+            OutputFormat::Jsonl => "jsonl",
+            OutputFormat::Csv => "csv",
+        }
+    }
+
+    /// True for formats intended to be consumed by other programs
+    pub fn is_machine_readable(&self) -> bool {
+        !matches!(self, OutputFormat::Text)
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Apply the global `--format` flag
+pub fn configure_output_format(format: Option<OutputFormat>) {
+    if let Some(format) = format {
+        let _ = REQUESTED_FORMAT.set(format);
+    }
+}
+
+/// The format given with `--format`, for commands that fall back to other defaults without it
+pub fn requested_output_format() -> Option<OutputFormat> {
+    REQUESTED_FORMAT.get().copied()
+}
+
+/// The format leaf commands print their results in
+pub fn output_format() -> OutputFormat {
+    requested_output_format().unwrap_or_default()
+}
+
+/// Print a result as pretty JSON, or as a single line for `jsonl`
+pub fn print_json<T: Serialize>(value: &T, format: OutputFormat) -> eyre::Result<()> {
+    let mut out = std::io::stdout().lock();
+    if format == OutputFormat::Jsonl {
+        serde_json::to_writer(&mut out, value)?;
+    } else {
+        serde_json::to_writer_pretty(&mut out, value)?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Quote a CSV field if it contains a separator, quote, or newline
+pub fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,242 @@
+use crate::cli::Cli;
+use crate::cli::action::Action;
+use crate::cli::drive_letter_pattern::DriveLetterPattern;
+use crate::cli::global_args::GlobalArgs;
+use crate::cli::mft_action::MftAction;
+use crate::cli::mft_action::MftArgs;
+use crate::cli::mft_sync_action::MftSyncArgs;
+use crate::cli::output_format::OutputFormat;
+use crate::cli::output_format::csv_escape;
+use crate::cli::output_format::output_format;
+use crate::cli::output_format::print_json;
+use crate::to_args::ToArgs;
+use crate::win_elevation::schtasks;
+use crate::win_elevation::task_command;
+use arbitrary::Arbitrary;
+use clap::Args;
+use clap::Subcommand;
+use serde::Serialize;
+use std::ffi::OsString;
+use std::fmt;
+use std::str::FromStr;
+use tracing::debug;
+use tracing::info;
+
+/// Name of the scheduled task registered by `schedule install`
+pub const TASK_NAME: &str = "storage-usage-v2 sync";
+
+/// A time of day in 24-hour `HH:MM` form, as schtasks takes it
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct DailyTime {
+    pub hour: u8,
+    pub minute: u8,
+}
+
+impl Default for DailyTime {
+    fn default() -> Self {
+        DailyTime { hour: 3, minute: 0 }
+    }
+}
+
+impl fmt::Display for DailyTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)
+    }
+}
+
+impl FromStr for DailyTime {
+    type Err = eyre::Report;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parsed = s.trim().split_once(':').and_then(|(hour, minute)| {
+            Some(DailyTime {
+                hour: hour.parse().ok().filter(|hour| *hour < 24)?,
+                minute: minute.parse().ok().filter(|minute| *minute < 60)?,
+            })
+        });
+        parsed.ok_or_else(|| eyre::eyre!("'{s}' is not a time of day like 03:00"))
+    }
+}
+
+impl<'a> Arbitrary<'a> for DailyTime {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<Self> {
+        Ok(DailyTime {
+            hour: u8::arbitrary(u)? % 24,
+            minute: u8::arbitrary(u)? % 60,
+        })
+    }
+}
+
+/// Schedule command arguments container
+#[derive(Args, Arbitrary, PartialEq, Debug, Clone)]
+pub struct ScheduleArgs {
+    #[clap(subcommand)]
+    pub action: ScheduleAction,
+}
+
+impl ScheduleArgs {
+    pub fn run(self) -> eyre::Result<()> {
+        self.action.run()
+    }
+}
+
+impl ToArgs for ScheduleArgs {
+    fn to_args(&self) -> Vec<OsString> {
+        self.action.to_args()
+    }
+}
+
+/// A daily `mft sync` through Task Scheduler, for those who'd rather not run a service
+#[derive(Subcommand, Arbitrary, PartialEq, Debug, Clone)]
+pub enum ScheduleAction {
+    /// Register a scheduled task running `mft sync` every day
+    Install {
+        /// Time of day to sync at, in 24-hour HH:MM
+        #[clap(long, default_value_t = DailyTime::default())]
+        daily: DailyTime,
+        /// Drive letter pattern to sync (e.g., "*", "C", "CD", "C,D")
+        #[clap(long, default_value_t = DriveLetterPattern::default())]
+        drives: DriveLetterPattern,
+    },
+    /// Show whether the task is registered, its next and last run, and the last exit code
+    Status,
+    /// Remove the scheduled task
+    Remove,
+}
+
+impl ScheduleAction {
+    pub fn run(self) -> eyre::Result<()> {
+        match self {
+            ScheduleAction::Install { daily, drives } => install(daily, drives),
+            ScheduleAction::Status => status(),
+            ScheduleAction::Remove => {
+                schtasks(&["/Delete", "/TN", TASK_NAME, "/F"])?;
+                info!("Removed the scheduled task '{TASK_NAME}'");
+                Ok(())
+            }
+        }
+    }
+}
+
+impl ToArgs for ScheduleAction {
+    fn to_args(&self) -> Vec<OsString> {
+        let mut args = Vec::new();
+        match self {
+            ScheduleAction::Install { daily, drives } => {
+                args.push("install".into());
+                if *daily != DailyTime::default() {
+                    args.push("--daily".into());
+                    args.push(daily.to_string().into());
+                }
+                if *drives != DriveLetterPattern::default() {
+                    args.push("--drives".into());
+                    args.push(drives.to_string().into());
+                }
+            }
+            ScheduleAction::Status => {
+                args.push("status".into());
+            }
+            ScheduleAction::Remove => {
+                args.push("remove".into());
+            }
+        }
+        args
+    }
+}
+
+fn install(daily: DailyTime, drives: DriveLetterPattern) -> eyre::Result<()> {
+    // Run by Task Scheduler with nobody to answer a UAC prompt, so it must not relaunch
+    let sync = Cli {
+        global_args: GlobalArgs {
+            quiet: true,
+            no_elevate: true,
+            ..Default::default()
+        },
+        action: Action::Mft(MftArgs {
+            action: MftAction::Sync(MftSyncArgs {
+                drive_pattern: drives,
+                overwrite_existing: true,
+            }),
+        }),
+    };
+    let command = task_command(&sync)?;
+    let time = daily.to_string();
+    schtasks(&[
+        "/Create", "/TN", TASK_NAME, "/TR", &command, "/SC", "DAILY", "/ST", &time, "/RL",
+        "HIGHEST", "/F",
+    ])?;
+    info!("Scheduled '{command}' daily at {time} as '{TASK_NAME}'");
+    Ok(())
+}
+
+/// What `schedule status` reports
+#[derive(Serialize, Debug, Default)]
+struct ScheduleStatus {
+    registered: bool,
+    next_run: Option<String>,
+    status: Option<String>,
+    last_run: Option<String>,
+    last_result: Option<String>,
+    command: Option<String>,
+}
+
+fn query_status() -> ScheduleStatus {
+    let output = match schtasks(&["/Query", "/TN", TASK_NAME, "/V", "/FO", "CSV", "/NH"]) {
+        Ok(output) => output,
+        Err(e) => {
+            debug!("Treating the task as not registered: {e:#}");
+            return ScheduleStatus::default();
+        }
+    };
+    // HostName, TaskName, Next Run Time, Status, Logon Mode, Last Run Time, Last Result,
+    // Author, Task To Run, ...
+    let Some(line) = output.lines().find(|line| !line.trim().is_empty()) else {
+        return ScheduleStatus::default();
+    };
+    let fields: Vec<&str> = line
+        .split("\",\"")
+        .map(|field| field.trim_matches('"'))
+        .collect();
+    let field = |index: usize| fields.get(index).map(|field| field.to_string());
+    ScheduleStatus {
+        registered: true,
+        next_run: field(2),
+        status: field(3),
+        last_run: field(5),
+        last_result: field(6),
+        command: field(8),
+    }
+}
+
+fn status() -> eyre::Result<()> {
+    let status = query_status();
+    let format = output_format();
+    match format {
+        OutputFormat::Json | OutputFormat::Jsonl => print_json(&status, format)?,
+        OutputFormat::Csv => {
+            println!("registered,next_run,status,last_run,last_result,command");
+            let fields = [
+                &status.next_run,
+                &status.status,
+                &status.last_run,
+                &status.last_result,
+                &status.command,
+            ]
+            .map(|field| csv_escape(field.as_deref().unwrap_or_default()));
+            println!("{},{}", status.registered, fields.join(","));
+        }
+        OutputFormat::Text => {
+            if !status.registered {
+                println!("No scheduled sync; use `schedule install --daily 03:00`");
+                return Ok(());
+            }
+            let show = |field: &Option<String>| field.clone().unwrap_or_default();
+            println!("Task:        {TASK_NAME}");
+            println!("Command:     {}", show(&status.command));
+            println!("Status:      {}", show(&status.status));
+            println!("Next run:    {}", show(&status.next_run));
+            println!("Last run:    {}", show(&status.last_run));
+            println!("Last result: {}", show(&status.last_result));
+        }
+    }
+    Ok(())
+}
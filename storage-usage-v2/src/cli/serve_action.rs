@@ -0,0 +1,71 @@
+use super::drive_letter_pattern::DriveLetterPattern;
+use crate::config::Config;
+use crate::http_api::serve_http;
+use crate::json_rpc::DEFAULT_PIPE_NAME;
+use crate::json_rpc::serve_pipe;
+use crate::query_server::QueryServer;
+use crate::to_args::ToArgs;
+use arbitrary::Arbitrary;
+use clap::Args;
+use std::ffi::OsString;
+use std::sync::Arc;
+
+/// Arguments for serving lookups against cached indexes to other local tools
+#[derive(Args, Arbitrary, PartialEq, Debug, Clone)]
+pub struct ServeArgs {
+    /// Drive letter pattern of the cached MFTs to load (e.g. '*', 'C', 'CD', 'C,D')
+    #[clap(default_value_t = DriveLetterPattern::default())]
+    pub drive_pattern: DriveLetterPattern,
+
+    /// Serve JSON-RPC 2.0 on \\.\pipe\<NAME>, one request per line [default name:
+    /// storage-usage-v2]
+    #[clap(long, value_name = "NAME", num_args = 0..=1, default_missing_value = DEFAULT_PIPE_NAME)]
+    #[arbitrary(default)]
+    pub pipe: Option<String>,
+
+    /// Serve a JSON REST API on this address, e.g. 127.0.0.1:8080
+    #[clap(long, value_name = "ADDR")]
+    #[arbitrary(default)]
+    pub http: Option<String>,
+}
+
+impl ServeArgs {
+    pub fn run(self) -> eyre::Result<()> {
+        if self.pipe.is_none() && self.http.is_none() {
+            return Err(eyre::eyre!(
+                "Nothing to serve on; pass --pipe, --http or both"
+            ));
+        }
+        let drive_pattern = Config::load()?.drives_or(self.drive_pattern)?;
+        let server = Arc::new(QueryServer::load(drive_pattern)?);
+        match (self.pipe, self.http) {
+            (Some(pipe), Some(http)) => {
+                let pipe_server = server.clone();
+                let pipe_thread = std::thread::spawn(move || serve_pipe(&pipe, pipe_server));
+                serve_http(&http, server)?;
+                pipe_thread
+                    .join()
+                    .map_err(|_| eyre::eyre!("The pipe server panicked"))?
+            }
+            (Some(pipe), None) => serve_pipe(&pipe, server),
+            (None, Some(http)) => serve_http(&http, server),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+impl ToArgs for ServeArgs {
+    fn to_args(&self) -> Vec<OsString> {
+        let mut args = Vec::new();
+        args.push(self.drive_pattern.to_string().into());
+        if let Some(pipe) = &self.pipe {
+            args.push("--pipe".into());
+            args.push(pipe.into());
+        }
+        if let Some(http) = &self.http {
+            args.push("--http".into());
+            args.push(http.into());
+        }
+        args
+    }
+}
@@ -0,0 +1,101 @@
+use crate::app_error::AppError;
+use crate::to_args::ToArgs;
+use crate::win_elevation::elevation_allowed;
+use crate::win_elevation::is_elevated;
+use crate::win_elevation::relaunch_as_admin;
+use crate::win_service::install_service;
+use crate::win_service::run_service_dispatcher;
+use crate::win_service::uninstall_service;
+use arbitrary::Arbitrary;
+use clap::Args;
+use clap::Subcommand;
+use std::ffi::OsString;
+use tracing::info;
+
+/// Service command arguments container
+#[derive(Args, Arbitrary, PartialEq, Debug, Clone)]
+pub struct ServiceArgs {
+    #[clap(subcommand)]
+    pub action: ServiceAction,
+}
+
+impl ServiceArgs {
+    pub fn run(self) -> eyre::Result<()> {
+        self.action.run()
+    }
+}
+
+impl ToArgs for ServiceArgs {
+    fn to_args(&self) -> Vec<OsString> {
+        self.action.to_args()
+    }
+}
+
+/// Windows service syncing MFT dumps and recording free space on a schedule
+#[derive(Subcommand, Arbitrary, PartialEq, Debug, Clone)]
+pub enum ServiceAction {
+    /// Register the service with the current user's config file
+    Install {
+        /// Start the service yourself instead of with Windows
+        #[clap(long)]
+        manual: bool,
+    },
+    /// Stop and remove the service
+    Uninstall,
+    /// Run as the service; started by Windows, not from a terminal
+    Run,
+}
+
+impl ServiceAction {
+    pub fn run(self) -> eyre::Result<()> {
+        match self {
+            ServiceAction::Install { manual } => {
+                ensure_admin("Installing the service")?;
+                install_service(manual)
+            }
+            ServiceAction::Uninstall => {
+                ensure_admin("Uninstalling the service")?;
+                uninstall_service()
+            }
+            ServiceAction::Run => run_service_dispatcher(),
+        }
+    }
+}
+
+impl ToArgs for ServiceAction {
+    fn to_args(&self) -> Vec<OsString> {
+        let mut args = Vec::new();
+        match self {
+            ServiceAction::Install { manual } => {
+                args.push("install".into());
+                if *manual {
+                    args.push("--manual".into());
+                }
+            }
+            ServiceAction::Uninstall => {
+                args.push("uninstall".into());
+            }
+            ServiceAction::Run => {
+                args.push("run".into());
+            }
+        }
+        args
+    }
+}
+
+/// Relaunch elevated unless already elevated, exiting with the elevated instance's exit code
+fn ensure_admin(operation: &str) -> eyre::Result<()> {
+    if is_elevated() {
+        return Ok(());
+    }
+    if !elevation_allowed() {
+        return Err(AppError::NotAdmin {
+            operation: operation.to_string(),
+        }
+        .into());
+    }
+    info!("{operation} needs administrator rights, relaunching as administrator...");
+    let child = relaunch_as_admin().map_err(|e| AppError::ElevationFailed(format!("{e:#}")))?;
+    let exit_code = child.wait()?;
+    std::process::exit(exit_code as i32);
+}
@@ -1,27 +1,344 @@
+use crate::app_error::AppError;
+use crate::cli::config_action::ConfigKey;
+use crate::cli::drive_letter_pattern::DriveLetterPattern;
+use crate::cli::mft_query_action::MftQueryArgs;
+use crate::cli::output_format::OutputFormat;
+use crate::config_migration::CONFIG_VERSION;
+use crate::config_migration::ConfigDiagnostic;
+use crate::config_migration::VERSION_KEY;
+use crate::config_migration::check_keys;
+use crate::config_migration::migrate;
+use clap::ValueEnum;
 use color_eyre::eyre::Context;
 use color_eyre::eyre::{self};
 use directories_next::ProjectDirs;
+use serde::Deserialize;
+use serde::Serialize;
 use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::LazyLock;
+use std::sync::OnceLock;
 use std::sync::RwLock;
+use std::time::Duration;
+use toml::Table;
+use toml::Value;
+use tracing::info;
+
+/// Config file used instead of the one in the config dir; set by `service run`
+static CONFIG_FILE_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
 
 static CACHE_DIR_CACHE: LazyLock<RwLock<Option<PathBuf>>> = LazyLock::new(|| {
     let initial = read_initial_cache_dir().ok().flatten();
     RwLock::new(initial)
 });
 
+/// Settings from `config.toml` in the config dir, used where a command's own arguments are left
+/// at their defaults.
+///
+/// ```toml
+/// cache-dir = 'D:\mft-cache'
+/// default-drives = "CD"
+/// output-format = "json"
+/// query-limit = 500
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    /// Where `mft sync` writes dumps and other commands look for them
+    pub cache_dir: Option<PathBuf>,
+    /// Drive pattern for commands given none
+    pub default_drives: Option<String>,
+    /// Output format for `mft query`
+    pub output_format: Option<OutputFormat>,
+    /// Results listed by `mft query`
+    pub query_limit: Option<usize>,
+    /// Results written by the search tab's export in `mft show`
+    pub export_limit: Option<usize>,
+    /// Differences listed per category by `mft diff`
+    pub max_diffs: Option<usize>,
+    /// File every command logs to as JSON lines, unless `--log-file` says otherwise
+    pub log_file: Option<PathBuf>,
+    /// Time between syncs of the `service`, e.g. "6h"
+    pub sync_interval: Option<String>,
+}
+
+/// Written by `config edit` when there is no config file yet
+const CONFIG_TEMPLATE: &str = r#"# storage-usage-v2 settings, used where a command's own arguments are left at their defaults.
+# Uncomment a line to set it. `config list` shows the values in effect.
+
+# Schema version, so settings from older versions can be upgraded
+version = 1
+
+# Where `mft sync` writes dumps and other commands look for them
+# cache-dir = 'D:\mft-cache'
+
+# Drive pattern for commands given none: "*", "C", "CD" or "C,D"
+# default-drives = "C"
+
+# Output format of `mft query`: "text", "json" or "csv"
+# output-format = "text"
+
+# Results listed by `mft query`
+# query-limit = 100
+
+# Results written per export from the search tab of `mft show`
+# export-limit = 100000
+
+# Differences listed per category by `mft diff` (unlimited when unset)
+# max-diffs = 50
+
+# File every command logs to as JSON lines, rotated at 10 MiB
+# log-file = 'D:\logs\storage-usage.jsonl'
+
+# Time between syncs and free-space snapshots of the `service`
+# sync-interval = "24h"
+"#;
+
+impl Config {
+    /// Load the config file, or the defaults when it doesn't exist. A file written by an older
+    /// version is upgraded and saved back. A `cache-dir.txt` left from before the config file
+    /// existed still provides the cache dir.
+    pub fn load() -> eyre::Result<Self> {
+        let path = config_file_path()?;
+        let (mut config, changes) = if path.exists() {
+            let contents =
+                fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+            let mut table: Table = contents
+                .parse()
+                .map_err(|e| eyre::eyre!("parsing {}: {e}", path.display()))?;
+            let changes = migrate(&mut table, &path)?;
+            check_keys(&table, &path)?;
+            table.remove(VERSION_KEY);
+            // One key at a time, so a bad value is reported against its key
+            for (key, value) in &table {
+                let single = Table::from_iter([(key.clone(), value.clone())]);
+                if let Err(e) = single.try_into::<Config>() {
+                    return Err(ConfigDiagnostic::new(&path, key, e.message().to_string()).into());
+                }
+            }
+            let config: Config = table
+                .try_into()
+                .map_err(|e| eyre::eyre!("parsing {}: {e}", path.display()))?;
+            (config, changes)
+        } else {
+            (Config::default(), Vec::new())
+        };
+        if config.cache_dir.is_none() {
+            config.cache_dir = read_cache_dir_file()?;
+        }
+        if !changes.is_empty() {
+            info!("Migrated {}: {}", path.display(), changes.join(", "));
+            config.save()?;
+        }
+        Ok(config)
+    }
+
+    pub fn save(&self) -> eyre::Result<()> {
+        let path = config_file_path()?;
+        let cfg_dir = path.parent().unwrap_or(Path::new("."));
+        fs::create_dir_all(cfg_dir).with_context(|| format!("creating {}", cfg_dir.display()))?;
+        let mut table = Table::try_from(self).wrap_err("serializing config")?;
+        table.insert(VERSION_KEY.to_string(), Value::Integer(CONFIG_VERSION));
+        let contents = toml::to_string_pretty(&table).wrap_err("serializing config")?;
+        fs::write(&path, contents).with_context(|| format!("writing {}", path.display()))?;
+
+        // Its value was loaded into this config, so it would only shadow an unset cache-dir
+        let legacy = cache_dir_file_path()?;
+        if legacy.exists() {
+            fs::remove_file(&legacy).with_context(|| format!("removing {}", legacy.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Check the values a parse alone doesn't, naming the key at fault
+    pub fn validate(&self) -> eyre::Result<()> {
+        if let Some(cache_dir) = &self.cache_dir
+            && !cache_dir.is_dir()
+        {
+            return Err(eyre::eyre!(
+                "cache-dir '{}' is not an existing directory",
+                cache_dir.display()
+            ));
+        }
+        if let Some(drives) = &self.default_drives {
+            DriveLetterPattern::from_str(drives)
+                .and_then(|pattern| pattern.resolve())
+                .map_err(|e| eyre::eyre!("default-drives '{drives}' is not a drive pattern: {e}"))?;
+        }
+        if let Some(interval) = &self.sync_interval {
+            humantime::parse_duration(interval)
+                .map_err(|e| eyre::eyre!("sync-interval '{interval}' is not a duration: {e}"))?;
+        }
+        Ok(())
+    }
+
+    /// The value of a key as it would be typed into `config set`, if set
+    pub fn get(&self, key: ConfigKey) -> Option<String> {
+        match key {
+            ConfigKey::CacheDir => self.cache_dir.as_ref().map(|p| p.display().to_string()),
+            ConfigKey::DefaultDrives => self.default_drives.clone(),
+            ConfigKey::OutputFormat => self.output_format.map(|f| f.as_str().to_string()),
+            ConfigKey::QueryLimit => self.query_limit.map(|n| n.to_string()),
+            ConfigKey::ExportLimit => self.export_limit.map(|n| n.to_string()),
+            ConfigKey::MaxDiffs => self.max_diffs.map(|n| n.to_string()),
+            ConfigKey::LogFile => self.log_file.as_ref().map(|p| p.display().to_string()),
+            ConfigKey::SyncInterval => self.sync_interval.clone(),
+        }
+    }
+
+    /// Parse and store a value for a key
+    pub fn set(&mut self, key: ConfigKey, value: &str) -> eyre::Result<()> {
+        let parse_count = |value: &str| {
+            value
+                .parse::<usize>()
+                .map_err(|e| eyre::eyre!("invalid {} '{value}': {e}", key.as_str()))
+        };
+        match key {
+            ConfigKey::CacheDir => {
+                let path = Path::new(value);
+                self.cache_dir = Some(
+                    fs::canonicalize(path)
+                        .with_context(|| format!("canonicalizing {}", path.display()))?,
+                );
+            }
+            ConfigKey::DefaultDrives => {
+                let pattern = DriveLetterPattern::from_str(value)?;
+                pattern.resolve()?;
+                self.default_drives = Some(pattern.0);
+            }
+            ConfigKey::OutputFormat => {
+                self.output_format = Some(
+                    OutputFormat::from_str(value, true)
+                        .map_err(|e| eyre::eyre!("invalid output-format '{value}': {e}"))?,
+                );
+            }
+            ConfigKey::QueryLimit => self.query_limit = Some(parse_count(value)?),
+            ConfigKey::ExportLimit => self.export_limit = Some(parse_count(value)?),
+            ConfigKey::MaxDiffs => self.max_diffs = Some(parse_count(value)?),
+            ConfigKey::LogFile => {
+                // The file needn't exist yet, so it can't be canonicalized
+                self.log_file = Some(
+                    std::path::absolute(value)
+                        .with_context(|| format!("resolving log-file '{value}'"))?,
+                );
+            }
+            ConfigKey::SyncInterval => {
+                humantime::parse_duration(value)
+                    .map_err(|e| eyre::eyre!("invalid sync-interval '{value}': {e}"))?;
+                self.sync_interval = Some(value.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn unset(&mut self, key: ConfigKey) {
+        match key {
+            ConfigKey::CacheDir => self.cache_dir = None,
+            ConfigKey::DefaultDrives => self.default_drives = None,
+            ConfigKey::OutputFormat => self.output_format = None,
+            ConfigKey::QueryLimit => self.query_limit = None,
+            ConfigKey::ExportLimit => self.export_limit = None,
+            ConfigKey::MaxDiffs => self.max_diffs = None,
+            ConfigKey::LogFile => self.log_file = None,
+            ConfigKey::SyncInterval => self.sync_interval = None,
+        }
+    }
+
+    /// Fill the query arguments still at their built-in defaults, after any saved query
+    pub fn apply_to(&self, args: &mut MftQueryArgs) -> eyre::Result<()> {
+        args.drive_pattern = self.drives_or(args.drive_pattern.clone())?;
+        if args.format == OutputFormat::Text
+            && let Some(format) = self.output_format
+        {
+            args.format = format;
+        }
+        if args.limit == 100
+            && let Some(limit) = self.query_limit
+        {
+            args.limit = limit;
+        }
+        Ok(())
+    }
+
+    /// Time between syncs of the `service`, a day unless configured
+    pub fn sync_interval(&self) -> eyre::Result<Duration> {
+        match &self.sync_interval {
+            Some(interval) => humantime::parse_duration(interval)
+                .map_err(|e| eyre::eyre!("invalid sync-interval '{interval}': {e}")),
+            None => Ok(Duration::from_secs(24 * 60 * 60)),
+        }
+    }
+
+    /// The configured default drives when `pattern` was left at its default
+    pub fn drives_or(&self, pattern: DriveLetterPattern) -> eyre::Result<DriveLetterPattern> {
+        match &self.default_drives {
+            Some(drives) if pattern == DriveLetterPattern::default() => {
+                DriveLetterPattern::from_str(drives)
+            }
+            _ => Ok(pattern),
+        }
+    }
+}
+
 fn project_config_dir() -> eyre::Result<PathBuf> {
     ProjectDirs::from("com", "TeamDman", "storage-usage-v2")
         .ok_or_else(|| eyre::eyre!("No valid config directory for this platform"))
         .map(|p| p.config_dir().to_path_buf())
 }
 
+/// Path of the TOML file holding the settings shown by `config list`
+pub fn config_file_path() -> eyre::Result<PathBuf> {
+    if let Some(path) = CONFIG_FILE_OVERRIDE.get() {
+        return Ok(path.clone());
+    }
+    Ok(project_config_dir()?.join("config.toml"))
+}
+
+/// Read and write `path` instead of the config file in the config dir.
+///
+/// A service runs as another account with its own config dir, so it is pointed at the config
+/// file of the user who installed it.
+pub fn use_config_file(path: PathBuf) {
+    let _ = CONFIG_FILE_OVERRIDE.set(path);
+}
+
+/// Create the config file from a commented template if it doesn't exist yet
+pub fn ensure_config_file() -> eyre::Result<PathBuf> {
+    let path = config_file_path()?;
+    if !path.exists() {
+        let cfg_dir = path.parent().unwrap_or(Path::new("."));
+        fs::create_dir_all(cfg_dir).with_context(|| format!("creating {}", cfg_dir.display()))?;
+        fs::write(&path, CONFIG_TEMPLATE).with_context(|| format!("writing {}", path.display()))?;
+    }
+    Ok(path)
+}
+
 fn cache_dir_file_path() -> eyre::Result<PathBuf> {
     Ok(project_config_dir()?.join("cache-dir.txt"))
 }
 
+/// Path of the ignore file holding one exclude glob per line
+pub fn ignore_file_path() -> eyre::Result<PathBuf> {
+    Ok(project_config_dir()?.join("ignore.txt"))
+}
+
+/// Path of the JSON file holding named queries for `mft query --saved`
+pub fn saved_queries_file_path() -> eyre::Result<PathBuf> {
+    Ok(project_config_dir()?.join("saved-queries.json"))
+}
+
+/// Path of the JSON file where `mft show` remembers its tab, search and scroll positions
+pub fn session_file_path() -> eyre::Result<PathBuf> {
+    Ok(project_config_dir()?.join("tui-session.json"))
+}
+
+/// Path of the JSON file holding the user TUI theme
+pub fn theme_file_path() -> eyre::Result<PathBuf> {
+    Ok(project_config_dir()?.join("theme.json"))
+}
+
 fn read_env_cache_dir() -> eyre::Result<Option<PathBuf>> {
     match std::env::var("MFT_CACHE_DIR") {
         Ok(val) => {
@@ -58,7 +375,7 @@ fn read_initial_cache_dir() -> eyre::Result<Option<PathBuf>> {
     if let Some(p) = read_env_cache_dir()? {
         return Ok(Some(p));
     }
-    read_cache_dir_file()
+    Ok(Config::load()?.cache_dir)
 }
 
 pub fn get_cache_dir() -> eyre::Result<PathBuf> {
@@ -71,25 +388,17 @@ pub fn get_cache_dir() -> eyre::Result<PathBuf> {
             *CACHE_DIR_CACHE.write().unwrap() = Some(p.clone());
             Ok(p)
         }
-        None => Err(eyre::eyre!(
-            "cache-dir is not configured. Use: storage-usage-v2.exe config set cache-dir ."
-        )),
+        None => Err(AppError::CacheDirUnset.into()),
     }
 }
 
 pub fn set_cache_dir(cache_dir: &Path) -> eyre::Result<()> {
-    let canon = fs::canonicalize(cache_dir)
-        .with_context(|| format!("canonicalizing {}", cache_dir.display()))?;
-
-    let cfg_dir = project_config_dir()?;
-    fs::create_dir_all(&cfg_dir).with_context(|| format!("creating {}", cfg_dir.display()))?;
-
-    let file = cfg_dir.join("cache-dir.txt");
-    fs::write(&file, canon.to_string_lossy().as_bytes())
-        .with_context(|| format!("writing {}", file.display()))?;
+    let mut config = Config::load()?;
+    config.set(ConfigKey::CacheDir, &cache_dir.to_string_lossy())?;
+    config.save()?;
 
     // Update cache
-    *CACHE_DIR_CACHE.write().unwrap() = Some(canon);
+    *CACHE_DIR_CACHE.write().unwrap() = config.cache_dir;
 
     Ok(())
 }
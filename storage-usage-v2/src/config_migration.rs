@@ -0,0 +1,179 @@
+use crate::cli::config_action::ConfigKey;
+use clap::ValueEnum;
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+use toml::Table;
+use toml::Value;
+
+/// Schema version written to `config.toml`; files without one predate versioning
+pub const CONFIG_VERSION: i64 = 1;
+
+/// Key holding the schema version
+pub const VERSION_KEY: &str = "version";
+
+/// Upgrades a table from the version at its index to the next, returning what it changed
+type Migration = fn(&mut Table) -> Vec<String>;
+
+const MIGRATIONS: [Migration; CONFIG_VERSION as usize] = [migrate_unversioned];
+
+/// Version 0 accepted the names saved queries use for the same settings
+fn migrate_unversioned(table: &mut Table) -> Vec<String> {
+    let mut changes = Vec::new();
+    for (old, new) in [
+        ("drives", "default-drives"),
+        ("format", "output-format"),
+        ("limit", "query-limit"),
+    ] {
+        if let Some(value) = table.remove(old) {
+            if table.contains_key(new) {
+                changes.push(format!("dropped '{old}', which '{new}' already sets"));
+            } else {
+                table.insert(new.to_string(), value);
+                changes.push(format!("renamed '{old}' to '{new}'"));
+            }
+        }
+    }
+    changes
+}
+
+/// Bring a parsed config table up to [`CONFIG_VERSION`], stamping the version on it.
+/// Returns what changed, empty when the table was already current.
+pub fn migrate(table: &mut Table, path: &Path) -> Result<Vec<String>, ConfigDiagnostic> {
+    let version = match table.get(VERSION_KEY) {
+        None => 0,
+        Some(Value::Integer(version)) => *version,
+        Some(other) => {
+            return Err(ConfigDiagnostic::new(
+                path,
+                VERSION_KEY,
+                format!("expected an integer, found {}", other.type_str()),
+            ));
+        }
+    };
+    if !(0..=CONFIG_VERSION).contains(&version) {
+        return Err(ConfigDiagnostic::new(
+            path,
+            VERSION_KEY,
+            format!(
+                "version {version} is not supported; this build reads versions 0 to {CONFIG_VERSION}"
+            ),
+        ));
+    }
+    let mut changes = Vec::new();
+    for migration in &MIGRATIONS[version as usize..] {
+        changes.extend(migration(table));
+    }
+    if version != CONFIG_VERSION {
+        table.insert(VERSION_KEY.to_string(), Value::Integer(CONFIG_VERSION));
+        changes.push(format!(
+            "upgraded from version {version} to {CONFIG_VERSION}"
+        ));
+    }
+    Ok(changes)
+}
+
+/// The keys a current config file may hold
+pub fn allowed_keys() -> Vec<&'static str> {
+    std::iter::once(VERSION_KEY)
+        .chain(ConfigKey::value_variants().iter().map(|key| key.as_str()))
+        .collect()
+}
+
+/// Reject keys the schema doesn't know, suggesting the closest known one
+pub fn check_keys(table: &Table, path: &Path) -> Result<(), ConfigDiagnostic> {
+    let allowed = allowed_keys();
+    let Some(unknown) = table.keys().find(|key| !allowed.contains(&key.as_str())) else {
+        return Ok(());
+    };
+    let mut diagnostic = ConfigDiagnostic::new(path, unknown, "unknown key".to_string());
+    diagnostic.suggestion = allowed
+        .iter()
+        .map(|candidate| (edit_distance(unknown, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= 3)
+        .min()
+        .map(|(_, candidate)| candidate);
+    Err(diagnostic)
+}
+
+/// A problem with one key of the config file, explained along with every key that is allowed
+#[derive(Debug)]
+pub struct ConfigDiagnostic {
+    pub path: PathBuf,
+    pub key: String,
+    pub problem: String,
+    pub suggestion: Option<&'static str>,
+}
+
+impl ConfigDiagnostic {
+    pub fn new(path: &Path, key: &str, problem: String) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            key: key.to_string(),
+            problem,
+            suggestion: None,
+        }
+    }
+}
+
+impl fmt::Display for ConfigDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: '{}': {}",
+            self.path.display(),
+            self.key,
+            self.problem
+        )?;
+        if let Some(suggestion) = self.suggestion {
+            writeln!(f, "  did you mean '{suggestion}'?")?;
+        }
+        write!(f, "  allowed keys: {}", allowed_keys().join(", "))
+    }
+}
+
+impl std::error::Error for ConfigDiagnostic {}
+
+/// Single-character insertions, deletions and substitutions turning `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unversioned_keys_are_renamed() {
+        let mut table: Table = toml::from_str("drives = \"C\"\nlimit = 5").unwrap();
+        let changes = migrate(&mut table, Path::new("config.toml")).unwrap();
+        assert_eq!(changes.len(), 3);
+        assert_eq!(table["default-drives"].as_str(), Some("C"));
+        assert_eq!(table["query-limit"].as_integer(), Some(5));
+        assert_eq!(table[VERSION_KEY].as_integer(), Some(CONFIG_VERSION));
+        assert!(check_keys(&table, Path::new("config.toml")).is_ok());
+    }
+
+    #[test]
+    fn unknown_key_suggests_the_closest() {
+        let table: Table = toml::from_str("query-limt = 5").unwrap();
+        let diagnostic = check_keys(&table, Path::new("config.toml")).unwrap_err();
+        assert_eq!(diagnostic.key, "query-limt");
+        assert_eq!(diagnostic.suggestion, Some("query-limit"));
+        assert!(
+            diagnostic
+                .to_string()
+                .contains("allowed keys: version, cache-dir")
+        );
+    }
+}
@@ -0,0 +1,269 @@
+use crate::tui::log_buffer::MessageVisitor;
+use crate::win_strings::EasyPCWSTR;
+use serde::Deserialize;
+use serde::Serialize;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::os::windows::io::AsRawHandle;
+use std::os::windows::io::FromRawHandle;
+use std::os::windows::io::IntoRawHandle;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::thread::JoinHandle;
+use tracing::Event;
+use tracing::Level;
+use tracing::Subscriber;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::layer::Layer;
+use windows::Win32::Foundation::ERROR_PIPE_CONNECTED;
+use windows::Win32::Foundation::GetLastError;
+use windows::Win32::Foundation::HANDLE;
+use windows::Win32::Storage::FileSystem::FILE_FLAG_FIRST_PIPE_INSTANCE;
+use windows::Win32::Storage::FileSystem::PIPE_ACCESS_INBOUND;
+use windows::Win32::System::Console::STD_ERROR_HANDLE;
+use windows::Win32::System::Console::STD_OUTPUT_HANDLE;
+use windows::Win32::System::Console::SetStdHandle;
+use windows::Win32::System::Pipes::ConnectNamedPipe;
+use windows::Win32::System::Pipes::CreateNamedPipeW;
+use windows::Win32::System::Pipes::PIPE_READMODE_BYTE;
+use windows::Win32::System::Pipes::PIPE_REJECT_REMOTE_CLIENTS;
+use windows::Win32::System::Pipes::PIPE_TYPE_BYTE;
+use windows::Win32::System::Pipes::PIPE_WAIT;
+
+/// Pipes created by this process, so each gets its own name
+static PIPE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// The parent's pipe, once an elevated child has connected to it
+static REPORTER: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// Something an elevated child tells the process that launched it, one JSON object per line
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ElevatedReport {
+    /// A tracing event from the child
+    Log { level: String, message: String },
+    /// Bytes of the MFT read so far, out of the total
+    Progress { read: u64, total: u64 },
+}
+
+fn pipe_path(name: &str) -> String {
+    format!(r"\\.\pipe\{name}")
+}
+
+/// A fresh name for a pipe owned by this process
+fn new_pipe_name() -> String {
+    format!(
+        "storage-usage-{}-{}",
+        std::process::id(),
+        PIPE_COUNT.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Create the server end of an inbound pipe only a local client can connect to
+fn create_pipe(name: &str) -> eyre::Result<File> {
+    let path = pipe_path(name).easy_pcwstr()?;
+    let handle = unsafe {
+        CreateNamedPipeW(
+            path.as_ptr(),
+            PIPE_ACCESS_INBOUND | FILE_FLAG_FIRST_PIPE_INSTANCE,
+            PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+            1,
+            0,
+            64 * 1024,
+            0,
+            None,
+        )
+    };
+    if handle.is_invalid() {
+        return Err(eyre::eyre!("Failed to create pipe {name}: {:?}", unsafe {
+            GetLastError()
+        }));
+    }
+    // The file owns the handle from here, closing it when dropped
+    Ok(unsafe { File::from_raw_handle(handle.0 as _) })
+}
+
+/// Wait for the client on a background thread, then hand the connected pipe to `read`
+fn spawn_pipe_reader(
+    name: String,
+    pipe: File,
+    read: impl FnOnce(&File) + Send + 'static,
+) -> PipeReader {
+    let handle = std::thread::spawn({
+        let name = name.clone();
+        move || {
+            let raw = HANDLE(pipe.as_raw_handle() as _);
+            if let Err(e) = unsafe { ConnectNamedPipe(raw, None) }
+                && e.code() != ERROR_PIPE_CONNECTED.to_hresult()
+            {
+                tracing::warn!("Elevated child never connected to {name}: {e}");
+                return;
+            }
+            read(&pipe);
+        }
+    });
+    PipeReader { name, handle }
+}
+
+/// A thread reading a pipe an elevated child writes to
+pub struct PipeReader {
+    name: String,
+    handle: JoinHandle<()>,
+}
+
+impl PipeReader {
+    /// Wait for the rest of the child's output once it has exited
+    pub fn finish(self) {
+        // A child that exited without connecting leaves the reader waiting for a connection;
+        // connecting here releases it. This fails harmlessly when the child did connect.
+        let _ = OpenOptions::new().write(true).open(pipe_path(&self.name));
+        let _ = self.handle.join();
+    }
+}
+
+/// The parent's end of a pipe an elevated child streams reports through.
+///
+/// Pass [`ReportPipe::name`] to the child as `--report-pipe`, launch it, then read with
+/// [`ReportPipe::spawn_reader`].
+pub struct ReportPipe {
+    name: String,
+    file: File,
+}
+
+impl ReportPipe {
+    pub fn create() -> eyre::Result<Self> {
+        let name = new_pipe_name();
+        let file = create_pipe(&name)?;
+        Ok(Self { name, file })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Wait for the child on a background thread and hand every report it sends to
+    /// `on_report`, until it disconnects
+    pub fn spawn_reader(
+        self,
+        mut on_report: impl FnMut(ElevatedReport) + Send + 'static,
+    ) -> PipeReader {
+        spawn_pipe_reader(self.name, self.file, move |pipe| {
+            for line in BufReader::new(pipe).lines() {
+                let Ok(line) = line else { break };
+                match serde_json::from_str(&line) {
+                    Ok(report) => on_report(report),
+                    Err(e) => tracing::warn!("Unreadable report from elevated child: {e}"),
+                }
+            }
+        })
+    }
+}
+
+/// The parent's ends of the pipes an elevated child's stdout and stderr are relayed through.
+///
+/// Pass [`OutputRelay::name`] to the child as `--relay-pipe`, launch it, then copy its output
+/// with [`OutputRelay::spawn`].
+pub struct OutputRelay {
+    name: String,
+    stdout: File,
+    stderr: File,
+}
+
+impl OutputRelay {
+    pub fn create() -> eyre::Result<Self> {
+        let name = new_pipe_name();
+        let stdout = create_pipe(&format!("{name}-stdout"))?;
+        let stderr = create_pipe(&format!("{name}-stderr"))?;
+        Ok(Self {
+            name,
+            stdout,
+            stderr,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Copy the child's stdout and stderr to this process's as they arrive
+    pub fn spawn(self) -> [PipeReader; 2] {
+        [
+            spawn_pipe_reader(format!("{}-stdout", self.name), self.stdout, |mut pipe| {
+                let _ = std::io::copy(&mut pipe, &mut std::io::stdout());
+            }),
+            spawn_pipe_reader(format!("{}-stderr", self.name), self.stderr, |mut pipe| {
+                let _ = std::io::copy(&mut pipe, &mut std::io::stderr());
+            }),
+        ]
+    }
+}
+
+/// Send this process's stdout and stderr to the pipes of the process that launched it.
+/// Must be called before anything is printed.
+pub fn relay_output(name: &str) -> eyre::Result<()> {
+    for (suffix, std_handle) in [("stdout", STD_OUTPUT_HANDLE), ("stderr", STD_ERROR_HANDLE)] {
+        let pipe = OpenOptions::new()
+            .write(true)
+            .open(pipe_path(&format!("{name}-{suffix}")))
+            .map_err(|e| eyre::eyre!("Failed to connect to relay pipe {name}-{suffix}: {e}"))?;
+        // Standard handles live until the process exits
+        let raw = HANDLE(pipe.into_raw_handle() as _);
+        unsafe { SetStdHandle(std_handle, raw) }
+            .map_err(|e| eyre::eyre!("Failed to redirect {suffix}: {e}"))?;
+    }
+    Ok(())
+}
+
+/// Connect to the pipe of the process that launched this one, so later reports reach it
+pub fn connect_reporter(name: &str) -> eyre::Result<()> {
+    let file = OpenOptions::new()
+        .write(true)
+        .open(pipe_path(name))
+        .map_err(|e| eyre::eyre!("Failed to connect to report pipe {name}: {e}"))?;
+    let _ = REPORTER.set(Mutex::new(file));
+    Ok(())
+}
+
+/// Send a report to the launching process; does nothing unless [`connect_reporter`] succeeded
+pub fn report(report: &ElevatedReport) {
+    let Some(reporter) = REPORTER.get() else {
+        return;
+    };
+    let Ok(line) = serde_json::to_string(report) else {
+        return;
+    };
+    let mut file = reporter.lock().unwrap();
+    let _ = writeln!(file, "{line}");
+}
+
+/// Tracing layer sending every event to the launching process while connected
+pub struct ReportLayer;
+
+impl<S: Subscriber> Layer<S> for ReportLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if REPORTER.get().is_none() {
+            return;
+        }
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        report(&ElevatedReport::Log {
+            level: event.metadata().level().to_string(),
+            message: visitor.0,
+        });
+    }
+}
+
+/// Re-emit a child's log line as an event of this process
+pub fn log_child_event(level: &str, message: &str) {
+    match level.parse::<Level>() {
+        Ok(Level::ERROR) => tracing::error!(target: "elevated", "{message}"),
+        Ok(Level::WARN) => tracing::warn!(target: "elevated", "{message}"),
+        Ok(Level::DEBUG) => tracing::debug!(target: "elevated", "{message}"),
+        Ok(Level::TRACE) => tracing::trace!(target: "elevated", "{message}"),
+        _ => tracing::info!(target: "elevated", "{message}"),
+    }
+}
@@ -0,0 +1,52 @@
+use crate::cli::output_format::OutputFormat;
+use crate::search_export::export_format_for;
+use eyre::Context;
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+
+/// One error collected while processing an MFT, as written to an export file
+#[derive(Debug, Serialize)]
+pub struct ExportedError<'a> {
+    /// The MFT file the error came from
+    pub file: &'a str,
+    pub record_number: Option<u64>,
+    /// `error` or `warning`
+    pub category: &'static str,
+    pub message: &'a str,
+}
+
+/// Write errors to a file: `.json` and `.jsonl` as JSON, anything else as one line per error
+pub fn write_errors(path: &Path, errors: &[ExportedError]) -> eyre::Result<()> {
+    let file =
+        std::fs::File::create(path).with_context(|| format!("creating {}", path.display()))?;
+    let mut out = std::io::BufWriter::new(file);
+    match export_format_for(path) {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, errors)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Jsonl => {
+            for error in errors {
+                serde_json::to_writer(&mut out, error)?;
+                writeln!(out)?;
+            }
+        }
+        OutputFormat::Csv | OutputFormat::Text => {
+            for error in errors {
+                let record = error
+                    .record_number
+                    .map(|record| format!(" record {record}"))
+                    .unwrap_or_default();
+                writeln!(
+                    out,
+                    "{}{record} [{}] {}",
+                    error.file, error.category, error.message
+                )?;
+            }
+        }
+    }
+    out.flush()
+        .with_context(|| format!("writing {}", path.display()))?;
+    Ok(())
+}
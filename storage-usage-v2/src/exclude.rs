@@ -0,0 +1,94 @@
+use crate::config::ignore_file_path;
+use crate::search_filter::fold_name;
+use eyre::Context;
+use glob::MatchOptions;
+use glob::Pattern;
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: false,
+    require_literal_leading_dot: false,
+};
+
+/// Glob patterns for paths to leave out of query and report output.
+///
+/// A path is excluded when a pattern matches the whole path or any single component of it,
+/// so `node_modules` hides everything below any `node_modules` directory. Patterns and paths are
+/// compared folded with [`fold_name`], as glob only ignores the case of ASCII letters.
+#[derive(Clone, Debug, Default)]
+pub struct ExcludeSet {
+    patterns: Vec<Pattern>,
+}
+
+impl ExcludeSet {
+    pub fn new(patterns: &[String]) -> eyre::Result<Self> {
+        let patterns = patterns
+            .iter()
+            .map(|p| {
+                Pattern::new(&fold_name(p))
+                    .with_context(|| format!("invalid exclude pattern '{p}'"))
+            })
+            .collect::<eyre::Result<Vec<_>>>()?;
+        Ok(Self { patterns })
+    }
+
+    /// Combine the given patterns with those from the ignore file in the config dir
+    pub fn with_ignore_file(patterns: &[String]) -> eyre::Result<Self> {
+        let mut all = read_ignore_file()?;
+        all.extend(patterns.iter().cloned());
+        Self::new(&all)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    pub fn is_excluded(&self, path: &str) -> bool {
+        if self.patterns.is_empty() {
+            return false;
+        }
+        let path = fold_name(path);
+        let path = path.as_str();
+        self.patterns.iter().any(|pattern| {
+            pattern.matches_with(path, MATCH_OPTIONS)
+                || path
+                    .split(['\\', '/'])
+                    .any(|component| pattern.matches_with(component, MATCH_OPTIONS))
+        })
+    }
+}
+
+/// Read patterns from the ignore file, one per line; blank lines and `#` comments are skipped
+fn read_ignore_file() -> eyre::Result<Vec<String>> {
+    let path = ignore_file_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let contents =
+        std::fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExcludeSet;
+
+    #[test]
+    fn matches_components_and_full_paths() -> eyre::Result<()> {
+        let set = ExcludeSet::new(&[
+            "node_modules".to_string(),
+            "$Recycle.Bin".to_string(),
+            "C:\\Windows\\WinSxS*".to_string(),
+        ])?;
+        assert!(set.is_excluded("D:\\repo\\node_modules\\left-pad\\index.js"));
+        assert!(set.is_excluded("C:\\$RECYCLE.BIN\\S-1-5-21\\file.txt"));
+        assert!(set.is_excluded("C:\\Windows\\WinSxS\\amd64_foo\\bar.dll"));
+        assert!(!set.is_excluded("D:\\repo\\src\\main.rs"));
+        Ok(())
+    }
+}
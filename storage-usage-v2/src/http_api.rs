@@ -0,0 +1,104 @@
+use crate::query_server::QueryParams;
+use crate::query_server::QueryServer;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tiny_http::Header;
+use tiny_http::Method;
+use tiny_http::Request;
+use tiny_http::Response;
+use tiny_http::Server;
+use tracing::debug;
+use tracing::info;
+use tracing::warn;
+
+/// Serve the REST API on `addr` (e.g. `127.0.0.1:8080`) until the process is stopped.
+///
+/// Every endpoint answers JSON:
+/// - `GET /drives`: loaded drives with total, free and used bytes and their file counts
+/// - `GET /query?q=<query>&limit=<n>&drives=<pattern>`: best fuzzy matches first
+/// - `GET /stat?path=<path>`: every name with exactly that path
+/// - `GET /sync`: age of each cached dump and whether a reload would pick up a newer one
+/// - `POST /reload`: load the cached indexes again
+pub fn serve_http(addr: &str, server: Arc<QueryServer>) -> eyre::Result<()> {
+    let http = Server::http(addr).map_err(|e| eyre::eyre!("Failed to listen on {addr}: {e}"))?;
+    info!("Serving HTTP on http://{addr}");
+    for request in http.incoming_requests() {
+        let server = server.clone();
+        std::thread::spawn(move || {
+            debug!("{} {}", request.method(), request.url());
+            let (status, body) = route(&server, &request);
+            let header = Header::from_bytes("Content-Type", "application/json")
+                .expect("static header is valid");
+            let response = Response::from_string(body)
+                .with_status_code(status)
+                .with_header(header);
+            if let Err(e) = request.respond(response) {
+                warn!("Failed to answer an HTTP request: {e}");
+            }
+        });
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+/// Status code and JSON body for a request
+fn route(server: &QueryServer, request: &Request) -> (u16, String) {
+    let (path, query) = request.url().split_once('?').unwrap_or((request.url(), ""));
+    let params: HashMap<String, String> = form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect();
+    let result = match (request.method(), path.trim_end_matches('/')) {
+        (Method::Get, "/drives") => server.drive_stats().and_then(to_json),
+        (Method::Get, "/query") => {
+            let Some(q) = params.get("q") else {
+                return error(400, "Missing the q parameter");
+            };
+            let limit = match params.get("limit").map(|limit| limit.parse::<usize>()) {
+                Some(Ok(limit)) => Some(limit),
+                Some(Err(e)) => return error(400, format!("Invalid limit: {e}")),
+                None => None,
+            };
+            let query = QueryParams {
+                query: q.clone(),
+                limit,
+                drives: params.get("drives").cloned(),
+            };
+            server.query(&query).and_then(to_json)
+        }
+        (Method::Get, "/stat") => {
+            let Some(path) = params.get("path") else {
+                return error(400, "Missing the path parameter");
+            };
+            to_json(server.stat(path))
+        }
+        (Method::Get, "/sync") => server.sync_status().and_then(to_json),
+        (Method::Post, "/reload") => server.reload().and_then(to_json),
+        (_, "/drives" | "/query" | "/stat" | "/sync" | "/reload") => {
+            return error(405, "Method not allowed");
+        }
+        _ => return error(404, format!("No endpoint at {path}")),
+    };
+    match result {
+        Ok(body) => (200, body),
+        Err(e) => error(500, format!("{e:#}")),
+    }
+}
+
+fn to_json(value: impl Serialize) -> eyre::Result<String> {
+    Ok(serde_json::to_string(&value)?)
+}
+
+fn error(status: u16, message: impl Into<String>) -> (u16, String) {
+    let body = ErrorBody {
+        error: message.into(),
+    };
+    (
+        status,
+        serde_json::to_string(&body).unwrap_or_else(|_| "{}".to_string()),
+    )
+}
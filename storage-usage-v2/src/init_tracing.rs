@@ -1,19 +1,73 @@
+use crate::elevated_report::ReportLayer;
+use crate::log_file::FileLogLayer;
+use crate::progress_bars::SuspendingStdout;
+use crate::tui::log_buffer::LogBufferLayer;
+use crate::tui::log_buffer::capturing;
+use crate::win_event_log::EventLogLayer;
+use std::fs::File;
+use std::path::Path;
 use tracing::Level;
 use tracing::debug;
+use tracing::warn;
+use tracing_chrome::ChromeLayerBuilder;
+use tracing_chrome::FlushGuard;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::filter::filter_fn;
+use tracing_subscriber::layer::Layer;
+use tracing_subscriber::layer::SubscriberExt;
 
 /// Initialize tracing subscriber with the given log level.
 /// In debug builds, include file and line number without timestamp.
 /// In release builds, include timestamp and log level.
-pub fn init_tracing(level: Level) {
-    let builder = tracing_subscriber::fmt().with_max_level(level);
+/// Events are also kept for the TUI's log tab, and only kept there while the TUI is open.
+/// An elevated child also sends them to its parent once connected with `--report-pipe`.
+/// With a log file, events are appended to it as JSON lines too.
+/// While running as a service, events are also reported to the Windows Event Log.
+/// Log lines are written around any progress bars rather than into them.
+/// With a trace file, spans are recorded to it in the chrome://tracing format; it is complete
+/// once the returned guard is dropped.
+pub fn init_tracing(
+    level: Level,
+    log_file: Option<&Path>,
+    trace_out: Option<&Path>,
+) -> Option<FlushGuard> {
+    let fmt_layer = tracing_subscriber::fmt::layer().with_writer(|| SuspendingStdout);
     #[cfg(debug_assertions)]
-    let subscriber = builder
+    let fmt_layer = fmt_layer
         .with_target(false)
         .with_file(true)
-        .with_line_number(true)
-        .finish();
-    #[cfg(not(debug_assertions))]
-    let subscriber = builder.finish();
+        .with_line_number(true);
+    let subscriber = tracing_subscriber::registry()
+        .with(LevelFilter::from_level(level))
+        .with(fmt_layer.with_filter(filter_fn(|_| !capturing())))
+        .with(LogBufferLayer)
+        .with(ReportLayer)
+        .with(EventLogLayer);
+    let (file_layer, file_error) = match log_file.map(FileLogLayer::open) {
+        Some(Ok(layer)) => (Some(layer), None),
+        Some(Err(e)) => (None, Some(e)),
+        None => (None, None),
+    };
+    let subscriber = subscriber.with(file_layer);
+    let (chrome_layer, guard, trace_error) = match trace_out.map(File::create) {
+        Some(Ok(file)) => {
+            let (layer, guard) = ChromeLayerBuilder::new()
+                .writer(file)
+                .include_args(true)
+                .build();
+            (Some(layer), Some(guard), None)
+        }
+        Some(Err(e)) => (None, None, Some(e)),
+        None => (None, None, None),
+    };
+    let subscriber = subscriber.with(chrome_layer);
     tracing::subscriber::set_global_default(subscriber).expect("Failed to set tracing subscriber");
     debug!("Tracing initialized with level: {:?}", level);
+    if let Some(e) = file_error {
+        warn!("Not logging to a file: {e:#}");
+    }
+    if let Some(e) = trace_error {
+        warn!("Not recording a trace: {e}");
+    }
+    guard
 }
@@ -0,0 +1,207 @@
+use crate::query_server::QueryParams;
+use crate::query_server::QueryServer;
+use crate::win_strings::EasyPCWSTR;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::os::windows::io::AsRawHandle;
+use std::os::windows::io::FromRawHandle;
+use std::sync::Arc;
+use tracing::debug;
+use tracing::info;
+use tracing::warn;
+use windows::Win32::Foundation::ERROR_PIPE_CONNECTED;
+use windows::Win32::Foundation::GetLastError;
+use windows::Win32::Foundation::HANDLE;
+use windows::Win32::Storage::FileSystem::PIPE_ACCESS_DUPLEX;
+use windows::Win32::System::Pipes::ConnectNamedPipe;
+use windows::Win32::System::Pipes::CreateNamedPipeW;
+use windows::Win32::System::Pipes::PIPE_READMODE_BYTE;
+use windows::Win32::System::Pipes::PIPE_REJECT_REMOTE_CLIENTS;
+use windows::Win32::System::Pipes::PIPE_TYPE_BYTE;
+use windows::Win32::System::Pipes::PIPE_UNLIMITED_INSTANCES;
+use windows::Win32::System::Pipes::PIPE_WAIT;
+
+/// Pipe `serve --pipe` listens on when given no name
+pub const DEFAULT_PIPE_NAME: &str = "storage-usage-v2";
+
+/// Invalid JSON was received
+pub const PARSE_ERROR: i64 = -32700;
+/// The JSON sent is not a valid request object
+pub const INVALID_REQUEST: i64 = -32600;
+/// The method does not exist
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// The params don't fit the method
+pub const INVALID_PARAMS: i64 = -32602;
+/// The method failed
+pub const INTERNAL_ERROR: i64 = -32603;
+
+/// A JSON-RPC 2.0 request, one per line
+#[derive(Deserialize, Debug)]
+pub struct Request {
+    pub jsonrpc: String,
+    /// Absent for notifications, which get no response
+    #[serde(default)]
+    pub id: Option<Value>,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+/// A JSON-RPC 2.0 response, one per line
+#[derive(Serialize, Debug)]
+pub struct Response {
+    pub jsonrpc: &'static str,
+    pub id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl Response {
+    fn result(id: Value, result: Value) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            id,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    fn error(id: Value, code: i64, message: impl Into<String>) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            id,
+            result: None,
+            error: Some(RpcError {
+                code,
+                message: message.into(),
+            }),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct StatParams {
+    path: String,
+}
+
+/// Answer one line of input, or `None` for a notification
+pub fn handle_line(server: &QueryServer, line: &str) -> Option<Response> {
+    let value: Value = match serde_json::from_str(line) {
+        Ok(value) => value,
+        Err(e) => return Some(Response::error(Value::Null, PARSE_ERROR, e.to_string())),
+    };
+    let request: Request = match serde_json::from_value(value) {
+        Ok(request) => request,
+        Err(e) => return Some(Response::error(Value::Null, INVALID_REQUEST, e.to_string())),
+    };
+    let id = request.id.clone()?;
+    if request.jsonrpc != "2.0" {
+        return Some(Response::error(
+            id,
+            INVALID_REQUEST,
+            "jsonrpc must be \"2.0\"",
+        ));
+    }
+    Some(match dispatch(server, &request.method, request.params) {
+        Ok(result) => Response::result(id, result),
+        Err((code, message)) => Response::error(id, code, message),
+    })
+}
+
+/// Run a method: `query`, `stat`, `drives`, `stats`, `sync_status` or `reload`
+fn dispatch(server: &QueryServer, method: &str, params: Value) -> Result<Value, (i64, String)> {
+    let invalid_params = |e: serde_json::Error| (INVALID_PARAMS, e.to_string());
+    let internal = |e: eyre::Report| (INTERNAL_ERROR, format!("{e:#}"));
+    let result = match method {
+        "query" => {
+            let params: QueryParams = serde_json::from_value(params).map_err(invalid_params)?;
+            serde_json::to_value(server.query(&params).map_err(internal)?)
+        }
+        "stat" => {
+            let params: StatParams = serde_json::from_value(params).map_err(invalid_params)?;
+            serde_json::to_value(server.stat(&params.path))
+        }
+        "drives" => serde_json::to_value(server.drives()),
+        "stats" => serde_json::to_value(server.drive_stats().map_err(internal)?),
+        "sync_status" => serde_json::to_value(server.sync_status().map_err(internal)?),
+        "reload" => serde_json::to_value(server.reload().map_err(internal)?),
+        _ => return Err((METHOD_NOT_FOUND, format!("Unknown method '{method}'"))),
+    };
+    result.map_err(|e| (INTERNAL_ERROR, e.to_string()))
+}
+
+/// Create one instance of a duplex pipe only local clients can connect to
+fn create_pipe_instance(name: &str) -> eyre::Result<File> {
+    let path = format!(r"\\.\pipe\{name}").easy_pcwstr()?;
+    let handle = unsafe {
+        CreateNamedPipeW(
+            path.as_ptr(),
+            PIPE_ACCESS_DUPLEX,
+            PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+            PIPE_UNLIMITED_INSTANCES,
+            64 * 1024,
+            64 * 1024,
+            0,
+            None,
+        )
+    };
+    if handle.is_invalid() {
+        return Err(eyre::eyre!("Failed to create pipe {name}: {:?}", unsafe {
+            GetLastError()
+        }));
+    }
+    // The file owns the handle from here, closing it when dropped
+    Ok(unsafe { File::from_raw_handle(handle.0 as _) })
+}
+
+/// Serve JSON-RPC on `\\.\pipe\<name>` until the process is stopped, one thread per client
+pub fn serve_pipe(name: &str, server: Arc<QueryServer>) -> eyre::Result<()> {
+    info!(r"Serving JSON-RPC on \\.\pipe\{name}");
+    loop {
+        let pipe = create_pipe_instance(name)?;
+        let raw = HANDLE(pipe.as_raw_handle() as _);
+        if let Err(e) = unsafe { ConnectNamedPipe(raw, None) }
+            && e.code() != ERROR_PIPE_CONNECTED.to_hresult()
+        {
+            warn!("A client failed to connect to {name}: {e}");
+            continue;
+        }
+        let server = server.clone();
+        std::thread::spawn(move || {
+            debug!("Client connected");
+            if let Err(e) = serve_client(&server, pipe) {
+                debug!("Client went away: {e}");
+            }
+        });
+    }
+}
+
+fn serve_client(server: &QueryServer, pipe: File) -> std::io::Result<()> {
+    let mut writer = pipe.try_clone()?;
+    for line in BufReader::new(pipe).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = handle_line(server, &line) {
+            let mut json = serde_json::to_string(&response)?;
+            json.push('\n');
+            writer.write_all(json.as_bytes())?;
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
@@ -1,13 +1,50 @@
+pub mod app_error;
 pub mod cli;
 pub mod config;
+pub mod config_migration;
 pub mod console_reuse;
+pub mod elevated_report;
+pub mod error_export;
+pub mod exclude;
+pub mod http_api;
 pub mod init_tracing;
+pub mod json_rpc;
+pub mod log_file;
+pub mod metrics;
+pub mod mft_bench;
+pub mod mft_cleanup;
+pub mod mft_dedupe;
 pub mod mft_diff;
+pub mod mft_diff_report;
 pub mod mft_dump;
 pub mod mft_query;
+pub mod mft_record_details;
 pub mod mft_show;
+pub mod progress_bars;
+pub mod query_server;
+pub mod saved_query;
+pub mod search_export;
 pub mod to_args;
 pub mod tui;
 pub mod win_elevation;
-pub mod win_handles;
-pub mod win_strings;
+pub mod win_event_log;
+pub mod win_memory;
+pub mod win_service;
+pub mod win_shell;
+
+pub use storage_usage_core::dir_index;
+pub use storage_usage_core::file_flags;
+pub use storage_usage_core::mapped_index;
+pub use storage_usage_core::mft_index;
+pub use storage_usage_core::mft_size;
+pub use storage_usage_core::path_resolver;
+pub use storage_usage_core::progress;
+pub use storage_usage_core::reparse;
+pub use storage_usage_core::search_filter;
+pub use storage_usage_core::security;
+pub use storage_usage_core::usn_journal;
+pub use storage_usage_core::volume_bitmap;
+pub use storage_usage_core::win_handles;
+pub use storage_usage_core::win_paged_mft_reader;
+pub use storage_usage_core::win_privileges;
+pub use storage_usage_core::win_strings;
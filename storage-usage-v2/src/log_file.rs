@@ -0,0 +1,124 @@
+use crate::tui::log_buffer::MessageVisitor;
+use chrono::Local;
+use eyre::Context as _;
+use serde::Serialize;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::Event;
+use tracing::Subscriber;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::layer::Layer;
+
+/// Size at which the log file is rotated
+const MAX_LOG_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Rotated files kept next to the log file, as `<name>.1` (newest) to `<name>.5`
+const KEEP_ROTATED: usize = 5;
+
+/// One line of the log file
+#[derive(Serialize)]
+struct LogLine<'a> {
+    time: String,
+    level: &'a str,
+    target: &'a str,
+    message: String,
+}
+
+struct LogFile {
+    path: PathBuf,
+    /// Closed only while rotating, since Windows can't rename a file that is still open
+    file: Option<File>,
+    size: u64,
+}
+
+impl LogFile {
+    fn open(path: &Path) -> eyre::Result<Self> {
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("creating {}", parent.display()))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("opening log file {}", path.display()))?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: Some(file),
+            size,
+        })
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if self.size + line.len() as u64 + 1 > MAX_LOG_FILE_SIZE
+            && self.size > 0
+            && let Err(e) = self.rotate()
+        {
+            eprintln!("{e:#}");
+            // Try again after another full file rather than on every line
+            self.size = 0;
+        }
+        if let Some(file) = &mut self.file
+            && writeln!(file, "{line}").is_ok()
+        {
+            self.size += line.len() as u64 + 1;
+        }
+    }
+
+    /// Shift `<name>.N` to `<name>.N+1`, dropping the oldest, and start a fresh file
+    fn rotate(&mut self) -> eyre::Result<()> {
+        let rotated = |n: usize| {
+            let mut name = self.path.clone().into_os_string();
+            name.push(format!(".{n}"));
+            PathBuf::from(name)
+        };
+        let _ = std::fs::remove_file(rotated(KEEP_ROTATED));
+        for n in (1..KEEP_ROTATED).rev() {
+            let _ = std::fs::rename(rotated(n), rotated(n + 1));
+        }
+        self.file = None;
+        let renamed = std::fs::rename(&self.path, rotated(1))
+            .with_context(|| format!("rotating {}", self.path.display()));
+        // Reopened even when the rename failed, so logging carries on in the old file
+        *self = Self::open(&self.path)?;
+        renamed
+    }
+}
+
+/// Tracing layer appending every event to a file as JSON lines, rotated by size, so a TUI
+/// session or long sync leaves a trail once the alternate screen is gone
+pub struct FileLogLayer {
+    file: Mutex<LogFile>,
+}
+
+impl FileLogLayer {
+    pub fn open(path: &Path) -> eyre::Result<Self> {
+        Ok(Self {
+            file: Mutex::new(LogFile::open(path)?),
+        })
+    }
+}
+
+impl<S: Subscriber> Layer<S> for FileLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let line = LogLine {
+            time: Local::now().to_rfc3339(),
+            level: event.metadata().level().as_str(),
+            target: event.metadata().target(),
+            message: visitor.0,
+        };
+        let Ok(line) = serde_json::to_string(&line) else {
+            return;
+        };
+        self.file.lock().unwrap().write_line(&line);
+    }
+}
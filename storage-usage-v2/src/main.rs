@@ -1,7 +1,11 @@
 use clap::CommandFactory;
 use clap::FromArgMatches;
+use storage_usage_v2::app_error::exit_code_for;
 use storage_usage_v2::cli::Cli;
+use storage_usage_v2::config::Config;
+use storage_usage_v2::config::use_config_file;
 use storage_usage_v2::console_reuse::reuse_console_if_requested;
+use storage_usage_v2::elevated_report::relay_output;
 use storage_usage_v2::init_tracing::init_tracing;
 
 fn main() -> eyre::Result<()> {
@@ -10,8 +14,54 @@ fn main() -> eyre::Result<()> {
     let cli = Cli::from_arg_matches(&cli.get_matches())?;
 
     reuse_console_if_requested(&cli.global_args);
-    init_tracing(cli.global_args.log_level());
+    if let Some(relay_pipe) = &cli.global_args.relay_pipe
+        && let Err(e) = relay_output(relay_pipe)
+    {
+        eprintln!("{e:#}");
+    }
+    if let Some(config_file) = &cli.global_args.config_file {
+        use_config_file(config_file.clone());
+    }
+    // A broken config file is reported by the command that reads it. An elevated child's
+    // events already reach its parent's log file through the report pipe; one relaunched
+    // into its parent's console logs next to the parent's file so the two never rotate it
+    // out from under each other.
+    let log_file = if cli.global_args.report_pipe.is_some() {
+        None
+    } else {
+        cli.global_args
+            .log_file
+            .clone()
+            .or_else(|| Config::load().ok().and_then(|config| config.log_file))
+            .map(|path| {
+                if cli.global_args.console_pid.is_some() {
+                    path.with_extension("elevated.log")
+                } else {
+                    path
+                }
+            })
+    };
+    // An elevated child records its own trace next to its parent's rather than over it
+    let trace_out = cli.global_args.trace_out.clone().map(|path| {
+        if cli.global_args.report_pipe.is_some() {
+            path.with_extension("elevated.json")
+        } else {
+            path
+        }
+    });
+    let trace_guard = init_tracing(
+        cli.global_args.log_level(),
+        log_file.as_deref(),
+        trace_out.as_deref(),
+    );
 
-    cli.run()?;
+    if let Err(report) = cli.run() {
+        // Wrappers branch on the exit code; see "Exit Codes" in the README
+        let exit_code = exit_code_for(&report);
+        eprintln!("Error: {report:?}");
+        // Exiting skips destructors, so finish the trace first
+        drop(trace_guard);
+        std::process::exit(exit_code);
+    }
     Ok(())
 }
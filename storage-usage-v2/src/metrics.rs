@@ -0,0 +1,136 @@
+use crate::cli::drive_letter_pattern::DriveLetterPattern;
+use crate::config::get_cache_dir;
+use crate::mft_index::index_path_for;
+use crate::mft_index::is_index_fresh;
+use crate::mft_index::read_index_summary;
+use crate::win_service::free_space_snapshot;
+use eyre::Context;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::time::SystemTime;
+use tiny_http::Header;
+use tiny_http::Response;
+use tiny_http::Server;
+use tracing::info;
+use tracing::warn;
+
+/// One metric family: its name, help text and one sample per drive
+struct Family {
+    name: &'static str,
+    help: &'static str,
+    samples: Vec<(char, f64)>,
+}
+
+/// Render the metrics of the drives matching `drive_pattern` in the Prometheus text format.
+///
+/// Volume space is read live; sync age, dump size and parse errors come from the cache dir, so
+/// a drive never synced only has volume metrics.
+pub fn render_metrics(drive_pattern: &DriveLetterPattern) -> eyre::Result<String> {
+    let cache = get_cache_dir()?;
+    let mut families = [
+        Family {
+            name: "storage_usage_volume_total_bytes",
+            help: "Size of the volume",
+            samples: Vec::new(),
+        },
+        Family {
+            name: "storage_usage_volume_free_bytes",
+            help: "Free space on the volume",
+            samples: Vec::new(),
+        },
+        Family {
+            name: "storage_usage_last_sync_age_seconds",
+            help: "Seconds since the cached MFT dump was written",
+            samples: Vec::new(),
+        },
+        Family {
+            name: "storage_usage_dump_size_bytes",
+            help: "Size of the cached MFT dump",
+            samples: Vec::new(),
+        },
+        Family {
+            name: "storage_usage_index_entries",
+            help: "Names in the index of the cached MFT dump",
+            samples: Vec::new(),
+        },
+        Family {
+            name: "storage_usage_parse_errors",
+            help: "MFT records that failed to parse when the dump was indexed",
+            samples: Vec::new(),
+        },
+    ];
+    for drive in drive_pattern.resolve()? {
+        match free_space_snapshot(drive) {
+            Ok(space) => {
+                families[0].samples.push((drive, space.total_bytes as f64));
+                families[1].samples.push((drive, space.free_bytes as f64));
+            }
+            Err(e) => warn!("No volume metrics for {drive}: {e:#}"),
+        }
+        let dump = cache.join(format!("{drive}.mft"));
+        let Ok(metadata) = std::fs::metadata(&dump) else {
+            continue;
+        };
+        if let Some(age) = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        {
+            families[2].samples.push((drive, age.as_secs_f64()));
+        }
+        families[3].samples.push((drive, metadata.len() as f64));
+        // A stale index counts an older dump, so it is left out rather than misreported
+        if is_index_fresh(&dump)
+            && let Ok(summary) = read_index_summary(&index_path_for(&dump))
+        {
+            families[4].samples.push((drive, summary.entries as f64));
+            families[5]
+                .samples
+                .push((drive, summary.parse_errors as f64));
+        }
+    }
+
+    let mut out = String::new();
+    for family in families.iter().filter(|family| !family.samples.is_empty()) {
+        writeln!(out, "# HELP {} {}", family.name, family.help)?;
+        writeln!(out, "# TYPE {} gauge", family.name)?;
+        for (drive, value) in &family.samples {
+            writeln!(out, "{}{{drive=\"{drive}\"}} {value}", family.name)?;
+        }
+    }
+    Ok(out)
+}
+
+/// Write the metrics for a node_exporter textfile collector, replacing the file in one step so
+/// a scrape never sees it half written
+pub fn write_metrics_file(path: &Path, drive_pattern: &DriveLetterPattern) -> eyre::Result<()> {
+    let metrics = render_metrics(drive_pattern)?;
+    let mut temp = path.as_os_str().to_os_string();
+    temp.push(".tmp");
+    std::fs::write(&temp, metrics).with_context(|| format!("writing {}", path.display()))?;
+    std::fs::rename(&temp, path).with_context(|| format!("replacing {}", path.display()))?;
+    Ok(())
+}
+
+/// Answer scrapes of `/metrics` on `addr` until the process is stopped, rendering each fresh
+pub fn serve_metrics(addr: &str, drive_pattern: &DriveLetterPattern) -> eyre::Result<()> {
+    let http = Server::http(addr).map_err(|e| eyre::eyre!("Failed to listen on {addr}: {e}"))?;
+    info!("Serving metrics on http://{addr}/metrics");
+    for request in http.incoming_requests() {
+        let response = if request.url().trim_end_matches('/') != "/metrics" {
+            Response::from_string("Not found; metrics are at /metrics").with_status_code(404)
+        } else {
+            match render_metrics(drive_pattern) {
+                Ok(metrics) => Response::from_string(metrics).with_header(
+                    Header::from_bytes("Content-Type", "text/plain; version=0.0.4")
+                        .expect("static header is valid"),
+                ),
+                Err(e) => Response::from_string(format!("{e:#}")).with_status_code(500),
+            }
+        };
+        if let Err(e) = request.respond(response) {
+            warn!("Failed to answer a scrape: {e}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,232 @@
+use crate::app_error::AppError;
+use crate::mft_diff::drive_letter_for;
+use crate::mft_index::MftIndex;
+use crate::path_resolver::PathResolver;
+use eyre::Context;
+use mft::MftParser;
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::hint::black_box;
+use std::io::Cursor;
+use std::os::windows::fs::OpenOptionsExt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::Instant;
+use tracing::info;
+use tracing::warn;
+use windows::Win32::Storage::FileSystem::FILE_FLAG_NO_BUFFERING;
+
+/// A stage of turning a dump into paths, timed on its own
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BenchPhase {
+    /// Reading the dump's bytes from disk
+    Read,
+    /// Parsing every record of the read bytes on one thread
+    Parse,
+    /// Building the index from the dump and serializing it, as `mft index build` does
+    Index,
+    /// Placing every indexed name with the path resolver the TUI uses
+    Resolve,
+}
+
+impl BenchPhase {
+    pub const ALL: [BenchPhase; 4] = [
+        BenchPhase::Read,
+        BenchPhase::Parse,
+        BenchPhase::Index,
+        BenchPhase::Resolve,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BenchPhase::Read => "read",
+            BenchPhase::Parse => "parse",
+            BenchPhase::Index => "index",
+            BenchPhase::Resolve => "resolve",
+        }
+    }
+
+    /// What the phase's throughput counts
+    pub fn unit(&self) -> &'static str {
+        match self {
+            BenchPhase::Read => "bytes",
+            BenchPhase::Parse => "records",
+            BenchPhase::Index => "entries",
+            BenchPhase::Resolve => "paths",
+        }
+    }
+
+    /// Whether the phase reads the dump, so a cold run drops it from the file cache first
+    fn reads_dump(&self) -> bool {
+        matches!(self, BenchPhase::Read | BenchPhase::Index)
+    }
+}
+
+/// How long one phase took on the cold run and, as the median, on the warm runs
+#[derive(Clone, Debug, Serialize)]
+pub struct PhaseTiming {
+    pub phase: BenchPhase,
+    pub unit: &'static str,
+    /// Bytes, records, paths or entries the phase went through each run
+    pub items: u64,
+    pub cold_seconds: f64,
+    pub warm_seconds: Option<f64>,
+}
+
+impl PhaseTiming {
+    pub fn cold_per_second(&self) -> f64 {
+        self.items as f64 / self.cold_seconds.max(f64::EPSILON)
+    }
+
+    pub fn warm_per_second(&self) -> Option<f64> {
+        self.warm_seconds
+            .map(|seconds| self.items as f64 / seconds.max(f64::EPSILON))
+    }
+}
+
+/// Timings of every phase over one dump
+#[derive(Clone, Debug, Serialize)]
+pub struct BenchReport {
+    pub dump: PathBuf,
+    pub bytes: u64,
+    pub records: u64,
+    pub parse_errors: u64,
+    pub entries: usize,
+    pub warm_runs: usize,
+    /// Whether the dump could be dropped from the file cache before the cold run's reads
+    pub evicted: bool,
+    pub phases: Vec<PhaseTiming>,
+}
+
+/// Time each phase once cold, then `warm_runs` more times with the dump in the file cache
+pub fn bench_mft(dump: &Path, warm_runs: usize) -> eyre::Result<BenchReport> {
+    if !dump.is_file() {
+        return Err(AppError::DumpNotFound(dump.to_path_buf()).into());
+    }
+    let drive_letter = drive_letter_for(dump);
+    let mut evicted = true;
+    let mut counts = Counts::default();
+    let mut runs: Vec<[Duration; BenchPhase::ALL.len()]> = Vec::with_capacity(warm_runs + 1);
+    for run in 0..=warm_runs {
+        let cold = run == 0;
+        let mut durations = [Duration::ZERO; BenchPhase::ALL.len()];
+        let mut bytes = Vec::new();
+        let mut index = MftIndex::default();
+        for phase in BenchPhase::ALL {
+            if cold
+                && phase.reads_dump()
+                && let Err(e) = evict_from_file_cache(dump)
+            {
+                warn!(
+                    "The cold run reads {} from the file cache: {e:#}",
+                    dump.display()
+                );
+                evicted = false;
+            }
+            let start = Instant::now();
+            match phase {
+                BenchPhase::Read => {
+                    bytes = std::fs::read(dump)
+                        .with_context(|| format!("reading {}", dump.display()))?;
+                    counts.bytes = bytes.len() as u64;
+                }
+                BenchPhase::Parse => {
+                    (counts.records, counts.parse_errors) = parse_records(&bytes)?;
+                }
+                BenchPhase::Index => {
+                    index = MftIndex::build_from_mft(dump)?;
+                    index.write_to(std::io::sink())?;
+                    counts.entries = index.len();
+                }
+                BenchPhase::Resolve => resolve_paths(&index, drive_letter),
+            }
+            durations[phase as usize] = start.elapsed();
+        }
+        info!(
+            "{} run: {}",
+            if cold { "Cold" } else { "Warm" },
+            BenchPhase::ALL
+                .iter()
+                .map(|phase| format!("{} {:.2?}", phase.as_str(), durations[*phase as usize]))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        runs.push(durations);
+    }
+
+    let phases = BenchPhase::ALL
+        .iter()
+        .map(|phase| {
+            let mut warm: Vec<Duration> =
+                runs[1..].iter().map(|run| run[*phase as usize]).collect();
+            warm.sort();
+            PhaseTiming {
+                phase: *phase,
+                unit: phase.unit(),
+                items: match phase {
+                    BenchPhase::Read => counts.bytes,
+                    BenchPhase::Parse => counts.records,
+                    BenchPhase::Index | BenchPhase::Resolve => counts.entries as u64,
+                },
+                cold_seconds: runs[0][*phase as usize].as_secs_f64(),
+                warm_seconds: warm.get(warm.len() / 2).map(Duration::as_secs_f64),
+            }
+        })
+        .collect();
+    Ok(BenchReport {
+        dump: dump.to_path_buf(),
+        bytes: counts.bytes,
+        records: counts.records,
+        parse_errors: counts.parse_errors,
+        entries: counts.entries,
+        warm_runs,
+        evicted,
+        phases,
+    })
+}
+
+/// What the phases went through, the same every run
+#[derive(Default)]
+struct Counts {
+    bytes: u64,
+    records: u64,
+    parse_errors: u64,
+    entries: usize,
+}
+
+/// Parse every record by number, as the TUI's workers do; the records, and how many failed
+fn parse_records(bytes: &[u8]) -> eyre::Result<(u64, u64)> {
+    let mut parser = MftParser::from_read_seek(Cursor::new(bytes), Some(bytes.len() as u64))
+        .map_err(|e| eyre::eyre!("Failed to parse MFT bytes: {e}"))?;
+    let total = parser.get_entry_count();
+    let mut errors = 0;
+    for position in 0..total {
+        if parser.get_entry(position).is_err() {
+            errors += 1;
+        }
+    }
+    Ok((total, errors))
+}
+
+/// Place every name of the index with a [`PathResolver`], directories first as its first pass
+/// does
+fn resolve_paths(index: &MftIndex, drive_letter: char) {
+    let mut resolver = PathResolver::new(drive_letter);
+    resolver.add_index_directories(&index.entries);
+    for entry in index.iter() {
+        black_box(resolver.resolve_index_entry(entry));
+    }
+}
+
+/// Drop a file's pages from the Windows file cache. Opening it unbuffered makes the cache manager
+/// flush and purge what it holds of the file, provided nothing else has it open.
+fn evict_from_file_cache(path: &Path) -> eyre::Result<()> {
+    OpenOptions::new()
+        .read(true)
+        .custom_flags(FILE_FLAG_NO_BUFFERING.0)
+        .open(path)
+        .with_context(|| format!("opening {} unbuffered", path.display()))?;
+    Ok(())
+}
@@ -0,0 +1,473 @@
+use crate::file_flags::FileFlag;
+use crate::mft_index::IndexEntry;
+use crate::mft_index::MftIndex;
+use crate::win_shell::RecycleOutcome;
+use crate::win_shell::move_to_recycle_bin;
+use chrono::DateTime;
+use chrono::TimeDelta;
+use chrono::Utc;
+use humansize::DECIMAL;
+use humansize::format_size;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::PathBuf;
+use tracing::debug;
+
+/// A kind of space that's usually safe to reclaim
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CleanupKind {
+    Temp,
+    RecycleBin,
+    /// What a Windows upgrade keeps of the previous install
+    WindowsOld,
+    InstallerCache,
+    /// Only those last modified before the cutoff
+    CrashDumps,
+    BrowserCache,
+    /// Docker and WSL virtual disks last modified before the cutoff
+    StaleVhd,
+}
+
+impl CleanupKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CleanupKind::Temp => "temp",
+            CleanupKind::RecycleBin => "recycle-bin",
+            CleanupKind::WindowsOld => "windows-old",
+            CleanupKind::InstallerCache => "installer-cache",
+            CleanupKind::CrashDumps => "crash-dumps",
+            CleanupKind::BrowserCache => "browser-cache",
+            CleanupKind::StaleVhd => "stale-vhd",
+        }
+    }
+
+    /// Whether only files untouched since the cutoff count, as a recent one may still be wanted
+    fn needs_age(&self) -> bool {
+        matches!(self, CleanupKind::CrashDumps | CleanupKind::StaleVhd)
+    }
+}
+
+/// Where a kind of space hog lives, relative to a drive's root; `*` stands for any one name
+struct Location {
+    kind: CleanupKind,
+    pattern: &'static str,
+}
+
+const LOCATIONS: &[Location] = &[
+    Location {
+        kind: CleanupKind::Temp,
+        pattern: r"Windows\Temp",
+    },
+    Location {
+        kind: CleanupKind::Temp,
+        pattern: r"Users\*\AppData\Local\Temp",
+    },
+    Location {
+        kind: CleanupKind::RecycleBin,
+        pattern: r"$Recycle.Bin",
+    },
+    Location {
+        kind: CleanupKind::WindowsOld,
+        pattern: r"Windows.old",
+    },
+    Location {
+        kind: CleanupKind::WindowsOld,
+        pattern: r"$Windows.~BT",
+    },
+    Location {
+        kind: CleanupKind::InstallerCache,
+        pattern: r"Windows\SoftwareDistribution\Download",
+    },
+    Location {
+        kind: CleanupKind::InstallerCache,
+        pattern: r"Windows\Installer\$PatchCache$",
+    },
+    Location {
+        kind: CleanupKind::InstallerCache,
+        pattern: r"ProgramData\Package Cache",
+    },
+    Location {
+        kind: CleanupKind::CrashDumps,
+        pattern: r"Windows\MEMORY.DMP",
+    },
+    Location {
+        kind: CleanupKind::CrashDumps,
+        pattern: r"Windows\Minidump",
+    },
+    Location {
+        kind: CleanupKind::CrashDumps,
+        pattern: r"Windows\LiveKernelReports",
+    },
+    Location {
+        kind: CleanupKind::CrashDumps,
+        pattern: r"ProgramData\Microsoft\Windows\WER",
+    },
+    Location {
+        kind: CleanupKind::CrashDumps,
+        pattern: r"Users\*\AppData\Local\CrashDumps",
+    },
+    Location {
+        kind: CleanupKind::BrowserCache,
+        pattern: r"Users\*\AppData\Local\Google\Chrome\User Data\*\Cache",
+    },
+    Location {
+        kind: CleanupKind::BrowserCache,
+        pattern: r"Users\*\AppData\Local\Google\Chrome\User Data\*\Code Cache",
+    },
+    Location {
+        kind: CleanupKind::BrowserCache,
+        pattern: r"Users\*\AppData\Local\Microsoft\Edge\User Data\*\Cache",
+    },
+    Location {
+        kind: CleanupKind::BrowserCache,
+        pattern: r"Users\*\AppData\Local\Microsoft\Edge\User Data\*\Code Cache",
+    },
+    Location {
+        kind: CleanupKind::BrowserCache,
+        pattern: r"Users\*\AppData\Local\BraveSoftware\Brave-Browser\User Data\*\Cache",
+    },
+    Location {
+        kind: CleanupKind::BrowserCache,
+        pattern: r"Users\*\AppData\Local\Mozilla\Firefox\Profiles\*\cache2",
+    },
+    Location {
+        kind: CleanupKind::StaleVhd,
+        pattern: r"Users\*\AppData\Local\Docker\wsl",
+    },
+    Location {
+        kind: CleanupKind::StaleVhd,
+        pattern: r"ProgramData\DockerDesktop\vm-data",
+    },
+    Location {
+        kind: CleanupKind::StaleVhd,
+        pattern: r"Users\*\AppData\Local\Packages\*\LocalState\ext4.vhdx",
+    },
+];
+
+/// The files of one location a detector matched, such as one user's temp directory
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CleanupFinding {
+    pub drive: char,
+    pub kind: CleanupKind,
+    /// The matched directory, or the file itself for one like `MEMORY.DMP`
+    pub path: String,
+    pub files: u64,
+    pub bytes: u64,
+    pub allocated_bytes: u64,
+    pub newest: Option<DateTime<Utc>>,
+    /// Every counted file, for deleting exactly what was reported
+    #[serde(skip)]
+    pub counted: Vec<CleanupFile>,
+}
+
+/// A counted file as the index saw it, checked against the disk before it's deleted
+#[derive(Clone, Debug, PartialEq)]
+pub struct CleanupFile {
+    pub path: String,
+    pub size: u64,
+    pub allocated_size: u64,
+    pub modified: Option<DateTime<Utc>>,
+}
+
+impl CleanupFile {
+    /// Whether the file on disk still has the size and modified time in the index. The index
+    /// keeps microseconds, so the disk's time is compared at that precision.
+    fn unchanged(&self) -> std::io::Result<bool> {
+        let metadata = std::fs::symlink_metadata(&self.path)?;
+        let modified = metadata.modified().ok().map(DateTime::<Utc>::from);
+        Ok(metadata.is_file()
+            && metadata.len() == self.size
+            && modified.map(|m| m.timestamp_micros())
+                == self.modified.map(|m| m.timestamp_micros()))
+    }
+}
+
+/// Match every file of an index against the built-in locations. Crash dumps and virtual disks
+/// count only when last modified before `stale_before`. Sorted by allocated bytes, largest first
+pub fn find_cleanup(
+    index: &MftIndex,
+    drive: char,
+    stale_before: DateTime<Utc>,
+) -> Vec<CleanupFinding> {
+    findings_from(drive, index.resolve_paths(drive), stale_before)
+}
+
+fn findings_from<'a>(
+    drive: char,
+    paths: impl IntoIterator<Item = (String, &'a IndexEntry)>,
+    stale_before: DateTime<Utc>,
+) -> Vec<CleanupFinding> {
+    let mut by_path: HashMap<String, CleanupFinding> = HashMap::new();
+    let mut linked: HashSet<u64> = HashSet::new();
+    for (path, entry) in paths {
+        if entry.flags.contains(FileFlag::Directory) || entry.flags.contains(FileFlag::Deleted) {
+            continue;
+        }
+        let Some((kind, root)) = locate(&path) else {
+            continue;
+        };
+        if kind.needs_age() && entry.modified.is_none_or(|m| m >= stale_before) {
+            continue;
+        }
+        if entry.hard_link_count > 1 && !linked.insert(entry.record_number) {
+            continue;
+        }
+        let finding = by_path
+            .entry(root.to_string())
+            .or_insert_with(|| CleanupFinding {
+                drive,
+                kind,
+                path: root.to_string(),
+                files: 0,
+                bytes: 0,
+                allocated_bytes: 0,
+                newest: None,
+                counted: Vec::new(),
+            });
+        finding.files += 1;
+        finding.bytes += entry.size;
+        finding.allocated_bytes += entry.allocated_size;
+        finding.newest = finding.newest.max(entry.modified);
+        finding.counted.push(CleanupFile {
+            path,
+            size: entry.size,
+            allocated_size: entry.allocated_size,
+            modified: entry.modified,
+        });
+    }
+    let mut findings: Vec<CleanupFinding> = by_path.into_values().collect();
+    findings.sort_by(|a, b| {
+        b.allocated_bytes
+            .cmp(&a.allocated_bytes)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    findings
+}
+
+/// The kind and matched root of the location a full path (`C:\Windows\Temp\x.log`) is in
+fn locate(path: &str) -> Option<(CleanupKind, &str)> {
+    let names: Vec<&str> = path.split('\\').collect();
+    LOCATIONS.iter().find_map(|location| {
+        let pattern: Vec<&str> = location.pattern.split('\\').collect();
+        let matched = names.len() > pattern.len()
+            && pattern
+                .iter()
+                .zip(&names[1..])
+                .all(|(wanted, name)| *wanted == "*" || wanted.eq_ignore_ascii_case(name));
+        matched.then(|| {
+            // The drive plus the matched names, sliced from the path to keep its own case
+            let root_len: usize = names[..=pattern.len()]
+                .iter()
+                .map(|name| name.len() + 1)
+                .sum();
+            (location.kind, &path[..root_len - 1])
+        })
+    })
+}
+
+/// The cutoff `--older-than` days before now, or the earliest time when it's too far back
+pub fn stale_before(now: DateTime<Utc>, days: u64) -> DateTime<Utc> {
+    TimeDelta::try_days(days.min(i64::MAX as u64) as i64)
+        .and_then(|age| now.checked_sub_signed(age))
+        .unwrap_or(DateTime::<Utc>::MIN_UTC)
+}
+
+/// Ask about each finding in turn and send the files of those confirmed to the Recycle Bin,
+/// or delete them outright with `permanent`. The index can be older than the disk, so a file
+/// is only touched while its size and modified time still match; changed files are left, as
+/// are directories and files in use.
+pub fn clean_up_interactively(findings: &[CleanupFinding], permanent: bool) -> eyre::Result<()> {
+    let mut freed = 0;
+    for finding in findings {
+        if finding.kind == CleanupKind::RecycleBin && !permanent {
+            println!(
+                "Skipping {} [{}]; emptying the Recycle Bin needs --permanent",
+                finding.path,
+                finding.kind.as_str()
+            );
+            continue;
+        }
+        let action = if permanent {
+            "Permanently delete"
+        } else {
+            "Recycle"
+        };
+        print!(
+            "{action} {} files ({}) in {} [{}]? [y/N/q] ",
+            finding.files,
+            format_size(finding.allocated_bytes, DECIMAL),
+            finding.path,
+            finding.kind.as_str()
+        );
+        std::io::stdout().flush()?;
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        match input.trim().to_ascii_lowercase().as_str() {
+            "y" | "yes" => {}
+            "q" | "quit" => break,
+            _ => continue,
+        }
+        let (mut changed, mut gone) = (0, 0);
+        let mut ready = Vec::new();
+        for file in &finding.counted {
+            match file.unchanged() {
+                Ok(true) => ready.push(file),
+                Ok(false) => changed += 1,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => gone += 1,
+                Err(e) => debug!("Couldn't check {}: {e}", file.path),
+            }
+        }
+        // Files the shell deleted outright, being too big for the Recycle Bin or on a volume
+        // without one, after the user agreed to its warning
+        let mut nuked = Vec::new();
+        let removed: Vec<&CleanupFile> = if permanent {
+            ready
+                .into_iter()
+                .filter(|file| match std::fs::remove_file(&file.path) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        debug!("Couldn't delete {}: {e}", file.path);
+                        false
+                    }
+                })
+                .collect()
+        } else {
+            let paths: Vec<PathBuf> = ready.iter().map(|file| PathBuf::from(&file.path)).collect();
+            let outcomes = match move_to_recycle_bin(&paths) {
+                Ok(outcomes) => outcomes,
+                Err(e) => {
+                    debug!("{e:#}");
+                    vec![RecycleOutcome::Failed; ready.len()]
+                }
+            };
+            let mut recycled = Vec::new();
+            for (file, outcome) in ready.into_iter().zip(outcomes) {
+                match outcome {
+                    RecycleOutcome::Recycled => recycled.push(file),
+                    RecycleOutcome::DeletedPermanently => nuked.push(file),
+                    RecycleOutcome::Failed => {}
+                }
+            }
+            recycled
+        };
+        let failed = finding.counted.len() - changed - gone - removed.len() - nuked.len();
+        let removed_bytes: u64 = removed.iter().map(|file| file.allocated_size).sum();
+        let nuked_bytes: u64 = nuked.iter().map(|file| file.allocated_size).sum();
+        freed += removed_bytes + nuked_bytes;
+
+        let verb = if permanent { "Deleted" } else { "Recycled" };
+        let mut outcome = format!(
+            "  {verb} {} files ({})",
+            removed.len(),
+            format_size(removed_bytes, DECIMAL)
+        );
+        if !nuked.is_empty() {
+            outcome += &format!(
+                "; {} files ({}) too big for the Recycle Bin were deleted permanently",
+                nuked.len(),
+                format_size(nuked_bytes, DECIMAL)
+            );
+        }
+        if changed > 0 {
+            outcome += &format!("; {changed} changed since the dump and were left");
+        }
+        if gone > 0 {
+            outcome += &format!("; {gone} were already gone");
+        }
+        if failed > 0 {
+            outcome += &format!(
+                "; {failed} couldn't be, likely in use, needing elevation or declined at the warning"
+            );
+        }
+        println!("{outcome}");
+    }
+    if freed > 0 {
+        println!("Freed about {}", format_size(freed, DECIMAL));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CleanupKind;
+    use super::findings_from;
+    use super::locate;
+    use crate::file_flags::FileFlags;
+    use crate::mft_index::IndexEntry;
+    use chrono::DateTime;
+    use chrono::TimeZone;
+    use chrono::Utc;
+
+    #[test]
+    fn locates_and_ages_findings() {
+        assert_eq!(
+            locate(r"C:\Users\me\AppData\Local\Temp\a\b.tmp"),
+            Some((CleanupKind::Temp, r"C:\Users\me\AppData\Local\Temp"))
+        );
+        assert_eq!(
+            locate(r"C:\windows\MEMORY.DMP"),
+            Some((CleanupKind::CrashDumps, r"C:\windows\MEMORY.DMP"))
+        );
+        assert_eq!(locate(r"C:\Users\me\Temp\b.tmp"), None);
+        assert_eq!(
+            locate(r"C:\$Recycle.Bin\S-1-5-21-1004\$RABC.txt"),
+            Some((CleanupKind::RecycleBin, r"C:\$Recycle.Bin"))
+        );
+
+        let at = |year| Some(Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0).unwrap());
+        let entry = |size, modified: Option<DateTime<Utc>>| IndexEntry {
+            record_number: size,
+            hard_link_count: 1,
+            parent_ref: None,
+            name: String::new(),
+            size,
+            allocated_size: size,
+            created: None,
+            modified,
+            accessed: None,
+            flags: FileFlags::default(),
+            reparse: None,
+        };
+        let (old_dump, new_dump, temp, recycled) = (
+            entry(100, at(2020)),
+            entry(200, at(2026)),
+            entry(5, None),
+            entry(50, at(2026)),
+        );
+        let findings = findings_from(
+            'C',
+            [
+                (r"C:\Windows\Minidump\old.dmp".to_string(), &old_dump),
+                (r"C:\Windows\Minidump\new.dmp".to_string(), &new_dump),
+                (r"C:\Windows\Temp\x.log".to_string(), &temp),
+                (
+                    r"C:\$Recycle.Bin\S-1-5-21-1004\$RABC.txt".to_string(),
+                    &recycled,
+                ),
+            ],
+            at(2025).unwrap(),
+        );
+        let summary: Vec<_> = findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding.kind,
+                    finding.path.as_str(),
+                    finding.files,
+                    finding.bytes,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (CleanupKind::CrashDumps, r"C:\Windows\Minidump", 1, 100),
+                (CleanupKind::RecycleBin, r"C:\$Recycle.Bin", 1, 50),
+                (CleanupKind::Temp, r"C:\Windows\Temp", 1, 5),
+            ]
+        );
+    }
+}
@@ -0,0 +1,459 @@
+use crate::file_flags::FileFlag;
+use crate::mft_index::MftIndex;
+use crate::progress_bars::bytes_bar;
+use arbitrary::Arbitrary;
+use clap::ValueEnum;
+use indicatif::ProgressBar;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+use tracing::debug;
+use tracing::info;
+
+/// Bytes hashed from each end of a file by [`DedupeCheck::Edges`]
+const EDGE_BYTES: u64 = 64 * 1024;
+
+/// Bytes read at a time while hashing a whole file
+const CHUNK_BYTES: usize = 1024 * 1024;
+
+/// How far `mft dedupe` goes to confirm that files of the same size are copies
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, ValueEnum, Arbitrary)]
+#[serde(rename_all = "lowercase")]
+pub enum DedupeCheck {
+    /// Equal sizes in the index, without reading any file
+    Size,
+    /// Also the first and last 64 KiB of each file
+    Edges,
+    /// Also every byte, after ruling out what the edges tell apart
+    #[default]
+    Full,
+}
+
+impl DedupeCheck {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DedupeCheck::Size => "size",
+            DedupeCheck::Edges => "edges",
+            DedupeCheck::Full => "full",
+        }
+    }
+}
+
+/// What `mft dedupe` looks at and how hard it reads
+#[derive(Clone, Debug)]
+pub struct DedupeOptions {
+    /// Smaller files aren't considered
+    pub min_size: u64,
+    pub check: DedupeCheck,
+    /// Files hashed at once
+    pub threads: usize,
+    /// Bytes read per second across all threads, unlimited when `None`
+    pub rate: Option<u64>,
+}
+
+/// A file of a cached index that might have copies
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate {
+    pub drive: char,
+    pub record: u64,
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+/// Files with the same contents, as far as the check went
+#[derive(Clone, Debug, Serialize)]
+pub struct DuplicateSet {
+    pub size: u64,
+    /// BLAKE3 of the last thing hashed, the edges or the whole file; none for a size check
+    pub hash: Option<String>,
+    /// Sorted, so the first is the copy the suggestions keep
+    pub paths: Vec<PathBuf>,
+    /// Bytes freed by keeping one copy
+    pub reclaimable_bytes: u64,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SuggestionKind {
+    /// Replace the copy with a hard link to the kept file, on the same volume
+    Hardlink,
+    /// Delete the copy, which is on another volume than the kept file
+    Delete,
+}
+
+impl SuggestionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SuggestionKind::Hardlink => "hardlink",
+            SuggestionKind::Delete => "delete",
+        }
+    }
+}
+
+/// What to do with one copy of a duplicate set
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Suggestion {
+    pub kind: SuggestionKind,
+    pub path: PathBuf,
+    pub keep: PathBuf,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DedupeReport {
+    pub check: DedupeCheck,
+    /// Files sharing their size with another, before any was read
+    pub candidates: usize,
+    pub bytes_read: u64,
+    /// Files that couldn't be read or changed size since the dump, left out of the sets
+    pub unreadable: usize,
+    /// Over every set found, including those past `top`
+    pub reclaimable_bytes: u64,
+    /// Largest reclaimable first
+    pub sets: Vec<DuplicateSet>,
+    /// Only for a full check; sizes and edges alone don't make files safe to remove
+    pub suggestions: Vec<Suggestion>,
+}
+
+/// Find duplicate files among the indexes of some drives, keeping the `top` sets that free the
+/// most bytes
+pub fn find_duplicates(
+    indexes: &[(char, MftIndex)],
+    options: &DedupeOptions,
+    top: usize,
+) -> eyre::Result<DedupeReport> {
+    let mut candidates = Vec::new();
+    for (drive, index) in indexes {
+        candidates.extend(candidates_of(index, *drive, options.min_size));
+    }
+    let mut groups = size_groups(candidates);
+    let candidates = groups.iter().map(Vec::len).sum();
+    info!(
+        "{candidates} files in {} groups share a size of at least {} bytes",
+        groups.len(),
+        options.min_size
+    );
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(options.threads.max(1))
+        .build()?;
+    let reader = Reader {
+        rate: options.rate.filter(|rate| *rate > 0),
+        start: Instant::now(),
+        read: AtomicU64::new(0),
+        unreadable: AtomicUsize::new(0),
+    };
+    let mut hashes: Vec<Option<String>> = vec![None; groups.len()];
+    if options.check != DedupeCheck::Size {
+        (groups, hashes) = pool.install(|| reader.refine(groups, HashRange::Edges));
+    }
+    if options.check == DedupeCheck::Full {
+        // Files no larger than both edges were hashed whole already
+        let (whole, partial): (Vec<_>, Vec<_>) = groups
+            .into_iter()
+            .zip(hashes)
+            .partition(|(group, _)| group[0].size <= 2 * EDGE_BYTES);
+        let (partial, _): (Vec<_>, Vec<_>) = partial.into_iter().unzip();
+        let (refined, refined_hashes) = pool.install(|| reader.refine(partial, HashRange::Full));
+        (groups, hashes) = whole.into_iter().unzip();
+        groups.extend(refined);
+        hashes.extend(refined_hashes);
+    }
+
+    let mut sets: Vec<DuplicateSet> = groups
+        .into_iter()
+        .zip(hashes)
+        .map(|(group, hash)| {
+            let size = group[0].size;
+            let mut paths: Vec<PathBuf> = group.into_iter().map(|file| file.path).collect();
+            paths.sort();
+            DuplicateSet {
+                size,
+                hash,
+                reclaimable_bytes: size * (paths.len() as u64 - 1),
+                paths,
+            }
+        })
+        .collect();
+    sets.sort_by(|a, b| {
+        b.reclaimable_bytes
+            .cmp(&a.reclaimable_bytes)
+            .then_with(|| a.paths.cmp(&b.paths))
+    });
+    let reclaimable_bytes = sets.iter().map(|set| set.reclaimable_bytes).sum();
+    sets.truncate(top);
+    let suggestions = if options.check == DedupeCheck::Full {
+        sets.iter().flat_map(suggest).collect()
+    } else {
+        Vec::new()
+    };
+    Ok(DedupeReport {
+        check: options.check,
+        candidates,
+        bytes_read: reader.read.into_inner(),
+        unreadable: reader.unreadable.into_inner(),
+        reclaimable_bytes,
+        sets,
+        suggestions,
+    })
+}
+
+/// The files of an index worth comparing: at least `min_size` bytes, not deleted, not links or
+/// cloud placeholders, and outside NTFS metadata and `$Recycle.Bin`
+fn candidates_of(index: &MftIndex, drive: char, min_size: u64) -> Vec<Candidate> {
+    let skipped = [
+        FileFlag::Directory,
+        FileFlag::Deleted,
+        FileFlag::ReparsePoint,
+    ];
+    index
+        .resolve_paths(drive)
+        .into_iter()
+        .filter(|(path, entry)| {
+            entry.size >= min_size.max(1)
+                && !skipped.iter().any(|flag| entry.flags.contains(*flag))
+                && !path.split('\\').any(|name| name.starts_with('$'))
+        })
+        .map(|(path, entry)| Candidate {
+            drive,
+            record: entry.record_number,
+            path: PathBuf::from(path),
+            size: entry.size,
+        })
+        .collect()
+}
+
+/// Group candidates by size, leaving out sizes only one file has. The names of a hard-linked
+/// file are one file, so only the first name of each record is kept
+pub fn size_groups(candidates: Vec<Candidate>) -> Vec<Vec<Candidate>> {
+    let mut seen = HashSet::new();
+    let mut by_size: HashMap<u64, Vec<Candidate>> = HashMap::new();
+    for candidate in candidates {
+        if seen.insert((candidate.drive, candidate.record)) {
+            by_size.entry(candidate.size).or_default().push(candidate);
+        }
+    }
+    let mut groups: Vec<Vec<Candidate>> = by_size
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect();
+    groups.sort_by_key(|group| std::cmp::Reverse(group[0].size));
+    groups
+}
+
+/// Keep the first path of a set and suggest a hard link in place of each copy on its volume,
+/// or deleting copies on other volumes
+pub fn suggest(set: &DuplicateSet) -> Vec<Suggestion> {
+    let Some((keep, copies)) = set.paths.split_first() else {
+        return Vec::new();
+    };
+    copies
+        .iter()
+        .map(|path| Suggestion {
+            kind: if same_volume(path, keep) {
+                SuggestionKind::Hardlink
+            } else {
+                SuggestionKind::Delete
+            },
+            path: path.clone(),
+            keep: keep.clone(),
+        })
+        .collect()
+}
+
+fn same_volume(a: &Path, b: &Path) -> bool {
+    let drive = |path: &Path| {
+        path.to_string_lossy()
+            .chars()
+            .next()
+            .map(|c| c.to_ascii_uppercase())
+    };
+    drive(a) == drive(b)
+}
+
+/// The part of a file that's hashed
+#[derive(Copy, Clone)]
+enum HashRange {
+    /// The first and last [`EDGE_BYTES`], or the whole file when it's no larger than both
+    Edges,
+    Full,
+}
+
+impl HashRange {
+    fn bytes(&self, size: u64) -> u64 {
+        match self {
+            HashRange::Edges => size.min(2 * EDGE_BYTES),
+            HashRange::Full => size,
+        }
+    }
+}
+
+/// Reads files for hashing within a shared byte rate, counting what it read and couldn't
+struct Reader {
+    rate: Option<u64>,
+    start: Instant,
+    read: AtomicU64,
+    unreadable: AtomicUsize,
+}
+
+impl Reader {
+    /// Split each group by the hash of `range` of its files, in parallel, keeping the parts
+    /// with more than one file and their hashes
+    fn refine(
+        &self,
+        groups: Vec<Vec<Candidate>>,
+        range: HashRange,
+    ) -> (Vec<Vec<Candidate>>, Vec<Option<String>>) {
+        let total = groups
+            .iter()
+            .flatten()
+            .map(|file| range.bytes(file.size))
+            .sum();
+        let bar = bytes_bar(
+            total,
+            match range {
+                HashRange::Edges => "Hashing edges",
+                HashRange::Full => "Hashing files",
+            },
+        );
+        let hashed: Vec<(usize, blake3::Hash, Candidate)> = groups
+            .into_iter()
+            .enumerate()
+            .flat_map(|(group, files)| files.into_iter().map(move |file| (group, file)))
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .filter_map(|(group, file)| match self.hash(&file, range, &bar) {
+                Ok(hash) => Some((group, hash, file)),
+                Err(e) => {
+                    debug!("Leaving out {}: {e}", file.path.display());
+                    self.unreadable.fetch_add(1, Ordering::Relaxed);
+                    None
+                }
+            })
+            .collect();
+        bar.finish_and_clear();
+
+        let mut by_hash: HashMap<(usize, blake3::Hash), Vec<Candidate>> = HashMap::new();
+        for (group, hash, file) in hashed {
+            by_hash.entry((group, hash)).or_default().push(file);
+        }
+        by_hash
+            .into_iter()
+            .filter(|(_, files)| files.len() > 1)
+            .map(|((_, hash), files)| (files, Some(hash.to_hex().to_string())))
+            .unzip()
+    }
+
+    fn hash(
+        &self,
+        file: &Candidate,
+        range: HashRange,
+        bar: &ProgressBar,
+    ) -> std::io::Result<blake3::Hash> {
+        let mut handle = File::open(&file.path)?;
+        if handle.metadata()?.len() != file.size {
+            return Err(std::io::Error::other("changed size since the dump"));
+        }
+        let mut hasher = blake3::Hasher::new();
+        let mut buffer = vec![0; CHUNK_BYTES.min(file.size as usize)];
+        match range {
+            HashRange::Edges if file.size > 2 * EDGE_BYTES => {
+                let edge = &mut buffer[..EDGE_BYTES as usize];
+                handle.read_exact(edge)?;
+                hasher.update(edge);
+                handle.seek(SeekFrom::End(-(EDGE_BYTES as i64)))?;
+                handle.read_exact(edge)?;
+                hasher.update(edge);
+                self.take(2 * EDGE_BYTES, bar);
+            }
+            _ => loop {
+                let read = handle.read(&mut buffer)?;
+                if read == 0 {
+                    break;
+                }
+                hasher.update(&buffer[..read]);
+                self.take(read as u64, bar);
+            },
+        }
+        Ok(hasher.finalize())
+    }
+
+    /// Count bytes read, sleeping while the reads are ahead of the rate
+    fn take(&self, bytes: u64, bar: &ProgressBar) {
+        bar.inc(bytes);
+        let read = self.read.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        if let Some(rate) = self.rate {
+            let due = Duration::from_secs_f64(read as f64 / rate as f64);
+            if let Some(wait) = due.checked_sub(self.start.elapsed()) {
+                std::thread::sleep(wait);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Candidate;
+    use super::DuplicateSet;
+    use super::SuggestionKind;
+    use super::size_groups;
+    use super::suggest;
+    use std::path::PathBuf;
+
+    fn candidate(drive: char, record: u64, path: &str, size: u64) -> Candidate {
+        Candidate {
+            drive,
+            record,
+            path: PathBuf::from(path),
+            size,
+        }
+    }
+
+    #[test]
+    fn groups_by_size_and_suggests_per_volume() {
+        let groups = size_groups(vec![
+            candidate('C', 40, "C:\\a.iso", 100),
+            // A second name of the same file isn't a copy
+            candidate('C', 40, "C:\\links\\a.iso", 100),
+            candidate('C', 41, "C:\\b.iso", 100),
+            candidate('D', 40, "D:\\a.iso", 100),
+            candidate('C', 42, "C:\\alone.bin", 50),
+        ]);
+        assert_eq!(groups.len(), 1);
+        let mut paths: Vec<PathBuf> = groups[0].iter().map(|c| c.path.clone()).collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            ["C:\\a.iso", "C:\\b.iso", "D:\\a.iso"].map(PathBuf::from)
+        );
+
+        let set = DuplicateSet {
+            size: 100,
+            hash: None,
+            reclaimable_bytes: 200,
+            paths,
+        };
+        let kinds: Vec<_> = suggest(&set)
+            .into_iter()
+            .map(|suggestion| (suggestion.kind, suggestion.path))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                (SuggestionKind::Hardlink, PathBuf::from("C:\\b.iso")),
+                (SuggestionKind::Delete, PathBuf::from("D:\\a.iso")),
+            ]
+        );
+    }
+}
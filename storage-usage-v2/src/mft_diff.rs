@@ -1,18 +1,635 @@
+use crate::app_error::AppError;
+use crate::cli::mft_diff_action::DiffIgnore;
+use crate::cli::mft_diff_action::MftDiffArgs;
+use crate::cli::output_format::OutputFormat;
+use crate::cli::output_format::csv_escape;
+use crate::cli::output_format::output_format;
+use crate::cli::output_format::print_json;
+use crate::mft_index::IndexEntry;
+use crate::mft_index::MftIndex;
+use crate::mft_index::load_fresh_index;
+use crate::progress_bars::index_with_bar;
+use crate::win_elevation::elevation_allowed;
+use crate::win_elevation::is_elevated;
+use crate::win_privileges::ensure_single_volume;
+use crate::win_elevation::relaunch_as_admin;
+use chrono::DateTime;
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::BufReader;
 use std::io::Read;
+use std::path::Path;
 use std::path::PathBuf;
+use storage_usage_core::mft_dump::record_size_of_dump;
+use tracing::info;
+use tracing::warn;
 
-pub fn diff_mft_files(
+/// What happened to a path between the old and new dump
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl ChangeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Modified => "modified",
+        }
+    }
+
+    pub fn marker(&self) -> char {
+        match self {
+            ChangeKind::Added => '+',
+            ChangeKind::Removed => '-',
+            ChangeKind::Modified => '~',
+        }
+    }
+}
+
+/// The parts of an entry that are compared between dumps
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct EntryState {
+    pub record_number: u64,
+    pub size: u64,
+    pub allocated_size: u64,
+    pub created: Option<DateTime<Utc>>,
+    pub modified: Option<DateTime<Utc>>,
+    pub accessed: Option<DateTime<Utc>>,
+}
+
+impl EntryState {
+    /// Whether the two states differ in anything not covered by `ignore`
+    pub fn differs_from(&self, other: &EntryState, ignore: &[DiffIgnore]) -> bool {
+        let ignore_timestamps = ignore.contains(&DiffIgnore::Timestamps);
+        let ignore_access_time = ignore_timestamps || ignore.contains(&DiffIgnore::AccessTime);
+        self.record_number != other.record_number
+            || self.size != other.size
+            || self.allocated_size != other.allocated_size
+            || (!ignore_timestamps
+                && (self.created != other.created || self.modified != other.modified))
+            || (!ignore_access_time && self.accessed != other.accessed)
+    }
+}
+
+impl From<&IndexEntry> for EntryState {
+    fn from(entry: &IndexEntry) -> Self {
+        Self {
+            record_number: entry.record_number,
+            size: entry.size,
+            allocated_size: entry.allocated_size,
+            created: entry.created,
+            modified: entry.modified,
+            accessed: entry.accessed,
+        }
+    }
+}
+
+/// One changed path
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct EntryChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    pub old: Option<EntryState>,
+    pub new: Option<EntryState>,
+}
+
+impl EntryChange {
+    /// Change in logical size from old to new
+    pub fn size_delta(&self) -> i64 {
+        let old = self.old.as_ref().map(|s| s.size).unwrap_or(0) as i64;
+        let new = self.new.as_ref().map(|s| s.size).unwrap_or(0) as i64;
+        new - old
+    }
+}
+
+/// What to leave out of a structural diff
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DiffOptions {
+    /// Differences that don't count as modifications
+    pub ignore: Vec<DiffIgnore>,
+    /// Only compare paths at or below this directory
+    pub under: Option<String>,
+}
+
+impl DiffOptions {
+    /// Whether `path` (e.g. `C:\Program Files\App\app.exe`) falls under the `--under` directory.
+    ///
+    /// Matching is case-insensitive and accepts either slash; a scope without a drive letter
+    /// matches on every drive.
+    pub fn in_scope(&self, path: &str) -> bool {
+        let Some(under) = &self.under else {
+            return true;
+        };
+        let under = under.replace('/', "\\");
+        let under = under.trim_end_matches('\\');
+        let has_drive = under.as_bytes().get(1) == Some(&b':');
+        let path = if has_drive {
+            path
+        } else {
+            path.get(2..).unwrap_or(path)
+        };
+        if path.len() < under.len() || !path.is_char_boundary(under.len()) {
+            return false;
+        }
+        let (head, rest) = path.split_at(under.len());
+        head.eq_ignore_ascii_case(under) && (rest.is_empty() || rest.starts_with('\\'))
+    }
+}
+
+/// File-level differences between two MFT dumps, sorted by path
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MftDiff {
+    pub changes: Vec<EntryChange>,
+}
+
+impl MftDiff {
+    /// Compare two indexes by resolved path.
+    ///
+    /// Record numbers get reused and reordered between snapshots, so the path is the identity;
+    /// a path whose record number changed is reported as modified.
+    pub fn between(
+        old: &MftIndex,
+        old_drive: char,
+        new: &MftIndex,
+        new_drive: char,
+        options: &DiffOptions,
+    ) -> Self {
+        let old_by_path = by_path(old, old_drive, new_drive, options);
+        let mut new_by_path = by_path(new, new_drive, new_drive, options);
+        let ignore = &options.ignore;
+        let mut changes = Vec::new();
+        for (path, old_state) in old_by_path {
+            match new_by_path.remove(&path) {
+                None => changes.push(EntryChange {
+                    path,
+                    kind: ChangeKind::Removed,
+                    old: Some(old_state),
+                    new: None,
+                }),
+                Some(new_state) if old_state.differs_from(&new_state, ignore) => {
+                    changes.push(EntryChange {
+                        path,
+                        kind: ChangeKind::Modified,
+                        old: Some(old_state),
+                        new: Some(new_state),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        changes.extend(
+            new_by_path
+                .into_iter()
+                .map(|(path, new_state)| EntryChange {
+                    path,
+                    kind: ChangeKind::Added,
+                    old: None,
+                    new: Some(new_state),
+                }),
+        );
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        Self { changes }
+    }
+
+    pub fn count(&self, kind: ChangeKind) -> usize {
+        self.changes.iter().filter(|c| c.kind == kind).count()
+    }
+
+    /// Net change in logical bytes across all changes
+    pub fn net_size_change(&self) -> i64 {
+        self.changes.iter().map(EntryChange::size_delta).sum()
+    }
+
+    /// Sum size deltas per directory, cut off `depth` levels below the drive root
+    /// (depth 1 groups by `C:\Users`, depth 2 by `C:\Users\me`).
+    ///
+    /// Sorted from the largest growth to the largest shrink.
+    pub fn directory_deltas(&self, depth: usize) -> Vec<DirectoryDelta> {
+        let mut by_dir: HashMap<String, DirectoryDelta> = HashMap::new();
+        for change in &self.changes {
+            let components: Vec<&str> = change.path.split('\\').collect();
+            // Drop the file name, keep the drive plus up to `depth` directories
+            let keep = (components.len() - 1).min(depth + 1).max(1);
+            let mut dir = components[..keep].join("\\");
+            if keep == 1 {
+                dir.push('\\');
+            }
+            let entry = by_dir.entry(dir.clone()).or_insert_with(|| DirectoryDelta {
+                path: dir,
+                size_delta: 0,
+                changes: 0,
+            });
+            entry.size_delta += change.size_delta();
+            entry.changes += 1;
+        }
+        let mut deltas: Vec<DirectoryDelta> = by_dir.into_values().collect();
+        deltas.sort_by(|a, b| b.size_delta.cmp(&a.size_delta).then(a.path.cmp(&b.path)));
+        deltas
+    }
+
+    /// Sum size deltas per lowercased extension (`.iso`), as `mft query --aggregate ext` groups
+    /// files. Sorted from the largest growth to the largest shrink.
+    pub fn extension_deltas(&self) -> Vec<ExtensionDelta> {
+        let mut by_extension: HashMap<String, ExtensionDelta> = HashMap::new();
+        for change in &self.changes {
+            let name = change.path.rsplit('\\').next().unwrap_or(&change.path);
+            let extension = match name.rsplit_once('.') {
+                Some((stem, ext)) if !stem.is_empty() => format!(".{}", ext.to_lowercase()),
+                _ => "(no extension)".to_string(),
+            };
+            let entry = by_extension
+                .entry(extension.clone())
+                .or_insert_with(|| ExtensionDelta {
+                    extension,
+                    size_delta: 0,
+                    changes: 0,
+                });
+            entry.size_delta += change.size_delta();
+            entry.changes += 1;
+        }
+        let mut deltas: Vec<ExtensionDelta> = by_extension.into_values().collect();
+        deltas.sort_by(|a, b| {
+            b.size_delta
+                .cmp(&a.size_delta)
+                .then(a.extension.cmp(&b.extension))
+        });
+        deltas
+    }
+}
+
+/// What `mft diff` prints instead of its listing when a structured `--format` is given
+#[derive(Debug, Serialize)]
+struct DiffSummary<'a> {
+    old: String,
+    new: String,
+    added: usize,
+    removed: usize,
+    modified: usize,
+    net_size_change: i64,
+    top_growers: Vec<&'a DirectoryDelta>,
+    top_shrinkers: Vec<&'a DirectoryDelta>,
+}
+
+/// What `mft diff --bytes` prints when a structured `--format` is given. Without `--verbose`
+/// the comparison stops in the first differing chunk, so `differences_found` is a lower bound.
+#[derive(Debug, Serialize)]
+struct ByteDiffSummary {
+    file1: String,
+    file2: String,
+    size1: u64,
+    size2: u64,
+    differences_found: usize,
+    first_difference: Option<u64>,
+    length_differs_at: Option<u64>,
+    /// Bytes per MFT record of the first file, to turn byte offsets into record numbers
+    record_size: u64,
+    first_difference_record: Option<u64>,
+}
+
+/// The record size of most volumes, assumed when a dump doesn't start with a readable record
+const DEFAULT_RECORD_SIZE: u64 = 1024;
+
+/// Bytes per record of a dump, from its first record's header
+pub fn dump_record_size(path: &Path) -> Option<u64> {
+    let mut header = [0u8; 0x20];
+    File::open(path).ok()?.read_exact(&mut header).ok()?;
+    record_size_of_dump(&header)
+}
+
+/// Net size change of everything that changed below one directory
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DirectoryDelta {
+    pub path: String,
+    pub size_delta: i64,
+    pub changes: usize,
+}
+
+/// Net size change of the changed files with one extension
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ExtensionDelta {
+    pub extension: String,
+    pub size_delta: i64,
+    pub changes: usize,
+}
+
+/// Resolve every entry to its path. Paths are rewritten onto `target_drive` so dumps of the
+/// same volume taken under different letters still line up.
+fn by_path(
+    index: &MftIndex,
+    drive: char,
+    target_drive: char,
+    options: &DiffOptions,
+) -> HashMap<String, EntryState> {
+    index
+        .resolve_paths(drive)
+        .into_iter()
+        .map(|(path, entry)| {
+            let path = format!("{target_drive}{}", &path[drive.len_utf8()..]);
+            (path, EntryState::from(entry))
+        })
+        .filter(|(path, _)| options.in_scope(path))
+        .collect()
+}
+
+/// One side of a diff: a dump on disk, or a volume read live when given as `C:`
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiffSource {
+    Dump(PathBuf),
+    Live(char),
+}
+
+impl DiffSource {
+    /// Treat `C` or `C:` as a live volume unless a file by that name exists
+    pub fn from_arg(arg: &Path) -> Self {
+        let text = arg.to_string_lossy();
+        let letter = text.strip_suffix(':').unwrap_or(&text);
+        let mut chars = letter.chars();
+        if let (Some(c), None) = (chars.next(), chars.next())
+            && c.is_ascii_alphabetic()
+            && !arg.is_file()
+        {
+            return DiffSource::Live(c.to_ascii_uppercase());
+        }
+        DiffSource::Dump(arg.to_path_buf())
+    }
+
+    pub fn is_live(&self) -> bool {
+        matches!(self, DiffSource::Live(_))
+    }
+
+    pub fn drive_letter(&self) -> char {
+        match self {
+            DiffSource::Dump(path) => drive_letter_for(path),
+            DiffSource::Live(drive_letter) => *drive_letter,
+        }
+    }
+
+    /// Load the index, building it in memory when there is no fresh index on disk
+    pub fn load_index(&self) -> eyre::Result<MftIndex> {
+        match self {
+            DiffSource::Dump(mft_file) => {
+                if !mft_file.is_file() {
+                    return Err(AppError::DumpNotFound(mft_file.clone()).into());
+                }
+                if let Some(index) = load_fresh_index(mft_file) {
+                    return Ok(index);
+                }
+                info!(
+                    "Parsing {} (run `mft index build` to speed this up)",
+                    mft_file.display()
+                );
+                index_with_bar(mft_file)
+            }
+            DiffSource::Live(drive_letter) if !is_elevated() && !elevation_allowed() => {
+                warn!(
+                    "Not elevated and --no-elevate given: walking {drive_letter}:\\ instead of reading its MFT. \
+                     Only paths, sizes and timestamps are compared; files in directories that can't be listed look removed."
+                );
+                Ok(MftIndex::build_from_walk(*drive_letter))
+            }
+            DiffSource::Live(drive_letter) => {
+                info!("Reading the live MFT of {drive_letter}:");
+                MftIndex::from_volume(*drive_letter)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for DiffSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiffSource::Dump(path) => write!(f, "{}", path.display()),
+            DiffSource::Live(drive_letter) => write!(f, "{drive_letter}: (live)"),
+        }
+    }
+}
+
+/// Guess the drive letter from a cached dump name like `C.mft`
+pub fn drive_letter_for(mft_file: &Path) -> char {
+    let stem = mft_file
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut chars = stem.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphabetic() => c.to_ascii_uppercase(),
+        _ => '?',
+    }
+}
+
+pub fn format_signed_size(delta: i64) -> String {
+    let sign = if delta < 0 { "-" } else { "+" };
+    format!(
+        "{sign}{}",
+        humansize::format_size(delta.unsigned_abs(), humansize::DECIMAL)
+    )
+}
+
+/// Parse both dumps and print added, removed and modified files with the net size change
+pub fn diff_mft_entries(args: MftDiffArgs) -> eyre::Result<()> {
+    let MftDiffArgs {
+        file1,
+        file2,
+        verbose,
+        max_diffs,
+        report,
+        ignore,
+        under,
+        top_dirs,
+        dir_depth,
+        ..
+    } = args;
+    let mut old_source = DiffSource::from_arg(&file1);
+    let mut new_source = DiffSource::from_arg(&file2);
+    // `mft diff C: C.mft` asks what changed since the dump, so the dump is always the old side
+    if old_source.is_live() && !new_source.is_live() {
+        std::mem::swap(&mut old_source, &mut new_source);
+    }
+    ensure_single_volume(
+        [&old_source, &new_source]
+            .iter()
+            .filter(|source| source.is_live())
+            .count(),
+    )?;
+    if (old_source.is_live() || new_source.is_live()) && !is_elevated() && elevation_allowed() {
+        warn!("Diffing a live volume needs elevated privileges to open the volume.");
+        info!("Relaunching as administrator...");
+        let child = relaunch_as_admin()?;
+        let exit_code = child.wait()?;
+        std::process::exit(exit_code as i32);
+    }
+
+    let format = output_format();
+    if !format.is_machine_readable() {
+        println!("Comparing MFT entries:");
+        println!("  Old: {old_source}");
+        println!("  New: {new_source}");
+        println!();
+    }
+
+    let (old, new) = rayon::join(|| old_source.load_index(), || new_source.load_index());
+    let diff = MftDiff::between(
+        &old?,
+        old_source.drive_letter(),
+        &new?,
+        new_source.drive_letter(),
+        &DiffOptions { ignore, under },
+    );
+    let directories = diff.directory_deltas(dir_depth);
+    let growers: Vec<&DirectoryDelta> = directories
+        .iter()
+        .filter(|d| d.size_delta > 0)
+        .take(top_dirs)
+        .collect();
+    let shrinkers: Vec<&DirectoryDelta> = directories
+        .iter()
+        .rev()
+        .filter(|d| d.size_delta < 0)
+        .take(top_dirs)
+        .collect();
+
+    if format.is_machine_readable() {
+        let summary = DiffSummary {
+            old: old_source.to_string(),
+            new: new_source.to_string(),
+            added: diff.count(ChangeKind::Added),
+            removed: diff.count(ChangeKind::Removed),
+            modified: diff.count(ChangeKind::Modified),
+            net_size_change: diff.net_size_change(),
+            top_growers: growers,
+            top_shrinkers: shrinkers,
+        };
+        if format == OutputFormat::Csv {
+            // The per-directory rows don't fit one header; `--report` has them
+            println!("old,new,added,removed,modified,net_size_change");
+            println!(
+                "{},{},{},{},{},{}",
+                csv_escape(&summary.old),
+                csv_escape(&summary.new),
+                summary.added,
+                summary.removed,
+                summary.modified,
+                summary.net_size_change
+            );
+        } else {
+            print_json(&summary, format)?;
+        }
+        if let Some(report) = report {
+            crate::mft_diff_report::write_report(
+                &diff,
+                &directories,
+                &old_source,
+                &new_source,
+                &report,
+            )?;
+            info!("Report written to {}", report.display());
+        }
+        return Ok(());
+    }
+
+    let limit = if verbose {
+        usize::MAX
+    } else {
+        max_diffs.unwrap_or(10)
+    };
+    for kind in [ChangeKind::Added, ChangeKind::Removed, ChangeKind::Modified] {
+        let total = diff.count(kind);
+        if total == 0 {
+            continue;
+        }
+        println!("{} ({total}):", kind.as_str());
+        for change in diff.changes.iter().filter(|c| c.kind == kind).take(limit) {
+            let detail = match (&change.old, &change.new) {
+                (Some(old), Some(new)) => format!(
+                    "{} -> {} ({})",
+                    humansize::format_size(old.size, humansize::DECIMAL),
+                    humansize::format_size(new.size, humansize::DECIMAL),
+                    format_signed_size(change.size_delta())
+                ),
+                _ => format_signed_size(change.size_delta()),
+            };
+            println!("  {} {}  {detail}", kind.marker(), change.path);
+        }
+        if total > limit {
+            println!(
+                "  ... and {} more (use --max-diffs or --verbose to see more)",
+                total - limit
+            );
+        }
+        println!();
+    }
+
+    println!("Summary:");
+    if diff.changes.is_empty() {
+        println!("  No file-level differences");
+    } else {
+        println!(
+            "  {} added, {} removed, {} modified",
+            diff.count(ChangeKind::Added),
+            diff.count(ChangeKind::Removed),
+            diff.count(ChangeKind::Modified)
+        );
+        println!(
+            "  Net size change: {}",
+            format_signed_size(diff.net_size_change())
+        );
+    }
+
+    if top_dirs > 0 && !directories.is_empty() {
+        for (title, rows) in [("Top growers", growers), ("Top shrinkers", shrinkers)] {
+            if rows.is_empty() {
+                continue;
+            }
+            println!();
+            println!("{title} (depth {dir_depth}):");
+            for row in rows {
+                println!(
+                    "  {:>12}  {}  ({} changes)",
+                    format_signed_size(row.size_delta),
+                    row.path,
+                    row.changes
+                );
+            }
+        }
+    }
+
+    if let Some(report) = report {
+        crate::mft_diff_report::write_report(
+            &diff,
+            &directories,
+            &old_source,
+            &new_source,
+            &report,
+        )?;
+        println!();
+        println!("Report written to {}", report.display());
+    }
+    Ok(())
+}
+
+/// Compare the raw bytes of two dumps
+pub fn diff_mft_bytes(
     file1: PathBuf,
     file2: PathBuf,
     verbose: bool,
     max_diffs: Option<usize>,
 ) -> eyre::Result<()> {
-    println!("Comparing MFT files:");
-    println!("  File 1: {}", file1.display());
-    println!("  File 2: {}", file2.display());
-    println!();
+    let format = output_format();
+    // Structured formats print only the summary at the end
+    let human = !format.is_machine_readable();
+    if human {
+        println!("Comparing MFT files:");
+        println!("  File 1: {}", file1.display());
+        println!("  File 2: {}", file2.display());
+        println!();
+    }
 
     // Open both files
     let file1_handle = File::open(&file1)?;
@@ -27,15 +644,19 @@ pub fn diff_mft_files(
 
     let size1 = metadata1.len();
     let size2 = metadata2.len();
+    // Volumes with 4K sectors can use 4096-byte records
+    let record_size = dump_record_size(&file1).unwrap_or(DEFAULT_RECORD_SIZE);
 
-    println!("File sizes:");
-    println!("  File 1: {size1} bytes");
-    println!("  File 2: {size2} bytes");
-    println!(
-        "  Difference: {} bytes",
-        (size1 as i64 - size2 as i64).abs()
-    );
-    println!();
+    if human {
+        println!("File sizes:");
+        println!("  File 1: {size1} bytes");
+        println!("  File 2: {size2} bytes");
+        println!(
+            "  Difference: {} bytes",
+            (size1 as i64 - size2 as i64).abs()
+        );
+        println!();
+    }
 
     // Read files in chunks and compare
     let mut buffer1 = [0u8; 4096];
@@ -44,6 +665,7 @@ pub fn diff_mft_files(
     let mut differences_found = 0usize;
     let max_diffs_to_show = max_diffs.unwrap_or(10);
     let mut first_difference: Option<u64> = None;
+    let mut length_differs_at: Option<u64> = None;
 
     loop {
         let bytes_read1 = reader1.read(&mut buffer1)?;
@@ -54,13 +676,13 @@ pub fn diff_mft_files(
             let shorter_file = if bytes_read1 < bytes_read2 { 1 } else { 2 };
             let longer_file = if bytes_read1 < bytes_read2 { 2 } else { 1 };
 
-            println!("Files differ in length:");
-            println!(
-                "  File {} ends at position {}",
-                shorter_file,
-                position + bytes_read1.min(bytes_read2) as u64
-            );
-            println!("  File {longer_file} continues beyond this point");
+            let end = position + bytes_read1.min(bytes_read2) as u64;
+            length_differs_at = Some(end);
+            if human {
+                println!("Files differ in length:");
+                println!("  File {shorter_file} ends at position {end}");
+                println!("  File {longer_file} continues beyond this point");
+            }
             break;
         }
 
@@ -78,24 +700,31 @@ pub fn diff_mft_files(
                     first_difference = Some(byte_position);
                 }
 
-                if verbose && differences_found < max_diffs_to_show {
+                if human && verbose && differences_found < max_diffs_to_show {
                     println!(
-                        "Difference at byte {}: 0x{:02X} vs 0x{:02X} (decimal: {} vs {})",
-                        byte_position, buffer1[i], buffer2[i], buffer1[i], buffer2[i]
+                        "Difference at byte {} (record {}): 0x{:02X} vs 0x{:02X} (decimal: {} vs {})",
+                        byte_position,
+                        byte_position / record_size,
+                        buffer1[i],
+                        buffer2[i],
+                        buffer1[i],
+                        buffer2[i]
                     );
                 }
 
                 differences_found += 1;
 
                 if differences_found >= max_diffs_to_show && verbose {
-                    println!(
-                        "... and {} more differences (use --max-diffs to see more)",
-                        count_remaining_differences(
-                            &mut reader1,
-                            &mut reader2,
-                            position + bytes_read1 as u64
-                        )?
-                    );
+                    let remaining = count_remaining_differences(
+                        &mut reader1,
+                        &mut reader2,
+                        position + bytes_read1 as u64,
+                    )?;
+                    if human {
+                        println!(
+                            "... and {remaining} more differences (use --max-diffs to see more)"
+                        );
+                    }
                     break;
                 }
             }
@@ -109,12 +738,50 @@ pub fn diff_mft_files(
         }
     }
 
+    if !human {
+        let summary = ByteDiffSummary {
+            file1: file1.display().to_string(),
+            file2: file2.display().to_string(),
+            size1,
+            size2,
+            differences_found,
+            first_difference,
+            length_differs_at,
+            record_size,
+            first_difference_record: first_difference.map(|position| position / record_size),
+        };
+        if format == OutputFormat::Csv {
+            println!(
+                "file1,file2,size1,size2,differences_found,first_difference,length_differs_at,record_size,first_difference_record"
+            );
+            let optional = |value: Option<u64>| value.map(|v| v.to_string()).unwrap_or_default();
+            println!(
+                "{},{},{},{},{},{},{},{},{}",
+                csv_escape(&summary.file1),
+                csv_escape(&summary.file2),
+                summary.size1,
+                summary.size2,
+                summary.differences_found,
+                optional(summary.first_difference),
+                optional(summary.length_differs_at),
+                summary.record_size,
+                optional(summary.first_difference_record)
+            );
+        } else {
+            print_json(&summary, format)?;
+        }
+        return Ok(());
+    }
+
     println!("Summary:");
     if differences_found == 0 {
         println!("  Files are identical!");
     } else {
         if let Some(first_diff_pos) = first_difference {
-            println!("  First difference at byte: {first_diff_pos}");
+            println!(
+                "  First difference at byte: {first_diff_pos} (record {} of {record_size} bytes)",
+                first_diff_pos / record_size
+            );
             println!(
                 "  As percentage of file: {:.2}%",
                 (first_diff_pos as f64 / size1.min(size2) as f64) * 100.0
@@ -124,8 +791,8 @@ pub fn diff_mft_files(
 
         if differences_found == 1 {
             println!("  Files are very similar (only 1 byte differs)");
-        } else if first_difference.unwrap_or(0) < 1024 {
-            println!("  Files diverge very early (likely different headers/metadata)");
+        } else if first_difference.unwrap_or(0) < record_size {
+            println!("  Files diverge in the first record (likely different headers/metadata)");
         } else {
             println!("  Files are mostly similar initially, then diverge");
         }
@@ -166,3 +833,70 @@ fn count_remaining_differences(
 
     Ok(remaining_diffs)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ChangeKind;
+    use super::DiffOptions;
+    use super::EntryChange;
+    use super::EntryState;
+    use super::MftDiff;
+
+    #[test]
+    fn in_scope_matches_directory_boundaries() {
+        let options = DiffOptions {
+            under: Some("c:/Program Files/".to_string()),
+            ..Default::default()
+        };
+        assert!(options.in_scope("C:\\Program Files"));
+        assert!(options.in_scope("C:\\Program Files\\App\\app.exe"));
+        assert!(!options.in_scope("C:\\Program Files (x86)\\App\\app.exe"));
+        assert!(!options.in_scope("D:\\Program Files\\App\\app.exe"));
+
+        let any_drive = DiffOptions {
+            under: Some("\\Users".to_string()),
+            ..Default::default()
+        };
+        assert!(any_drive.in_scope("D:\\Users\\me\\notes.txt"));
+        assert!(!any_drive.in_scope("D:\\Windows\\notepad.exe"));
+    }
+
+    #[test]
+    fn extension_deltas_sum_by_lowercased_extension() {
+        let state = |size| EntryState {
+            record_number: 0,
+            size,
+            allocated_size: size,
+            created: None,
+            modified: None,
+            accessed: None,
+        };
+        let change = |path: &str, old: Option<u64>, new: Option<u64>| EntryChange {
+            path: path.to_string(),
+            kind: ChangeKind::Modified,
+            old: old.map(state),
+            new: new.map(state),
+        };
+        let diff = MftDiff {
+            changes: vec![
+                change("C:\\VMs\\a.ISO", None, Some(700)),
+                change("C:\\VMs\\b.iso", Some(100), Some(400)),
+                change("C:\\logs\\.gitignore", Some(50), None),
+                change("C:\\logs\\app.log", Some(90), Some(10)),
+            ],
+        };
+        let deltas: Vec<_> = diff
+            .extension_deltas()
+            .into_iter()
+            .map(|delta| (delta.extension, delta.size_delta, delta.changes))
+            .collect();
+        assert_eq!(
+            deltas,
+            [
+                (".iso".to_string(), 1000, 2),
+                ("(no extension)".to_string(), -50, 1),
+                (".log".to_string(), -80, 1),
+            ]
+        );
+    }
+}
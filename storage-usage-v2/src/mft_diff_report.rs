@@ -0,0 +1,167 @@
+use crate::mft_diff::ChangeKind;
+use crate::mft_diff::DiffSource;
+use crate::mft_diff::DirectoryDelta;
+use crate::mft_diff::EntryChange;
+use crate::mft_diff::EntryState;
+use crate::mft_diff::MftDiff;
+use crate::mft_diff::format_signed_size;
+use chrono::Utc;
+use eyre::Context;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+
+#[derive(Serialize)]
+struct ReportSummary {
+    added: usize,
+    removed: usize,
+    modified: usize,
+    net_size_change: i64,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    generated: String,
+    old: String,
+    new: String,
+    summary: ReportSummary,
+    directories: &'a [DirectoryDelta],
+    changes: &'a [EntryChange],
+}
+
+/// Write the diff as a self-contained HTML page when `report` ends in `.html`/`.htm`, JSON otherwise
+pub fn write_report(
+    diff: &MftDiff,
+    directories: &[DirectoryDelta],
+    old: &DiffSource,
+    new: &DiffSource,
+    report: &Path,
+) -> eyre::Result<()> {
+    let is_html = report
+        .extension()
+        .map(|e| e.eq_ignore_ascii_case("html") || e.eq_ignore_ascii_case("htm"))
+        .unwrap_or(false);
+    let data = Report {
+        generated: Utc::now().to_rfc3339(),
+        old: old.to_string(),
+        new: new.to_string(),
+        summary: ReportSummary {
+            added: diff.count(ChangeKind::Added),
+            removed: diff.count(ChangeKind::Removed),
+            modified: diff.count(ChangeKind::Modified),
+            net_size_change: diff.net_size_change(),
+        },
+        directories,
+        changes: &diff.changes,
+    };
+    let file = File::create(report).with_context(|| format!("creating {}", report.display()))?;
+    let mut out = BufWriter::new(file);
+    if is_html {
+        out.write_all(render_html(&data).as_bytes())?;
+    } else {
+        serde_json::to_writer_pretty(&mut out, &data)?;
+        writeln!(out)?;
+    }
+    out.flush()
+        .with_context(|| format!("writing {}", report.display()))?;
+    Ok(())
+}
+
+fn render_html(report: &Report) -> String {
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>MFT diff: {old} vs {new}</title>
+<style>
+body {{ font-family: system-ui, sans-serif; margin: 2em; }}
+table {{ border-collapse: collapse; width: 100%; font-size: 0.9em; }}
+th, td {{ border-bottom: 1px solid #ddd; padding: 4px 8px; text-align: left; }}
+td.num {{ text-align: right; font-variant-numeric: tabular-nums; }}
+tr.added td:first-child {{ color: #1a7f37; }}
+tr.removed td:first-child {{ color: #cf222e; }}
+tr.modified td:first-child {{ color: #9a6700; }}
+</style>
+</head>
+<body>
+<h1>MFT diff</h1>
+<p>Old: <code>{old}</code><br>New: <code>{new}</code><br>Generated: {generated}</p>
+<p>{added} added, {removed} removed, {modified} modified. Net size change: {net}</p>
+"#,
+        old = escape_html(&report.old),
+        new = escape_html(&report.new),
+        generated = escape_html(&report.generated),
+        added = report.summary.added,
+        removed = report.summary.removed,
+        modified = report.summary.modified,
+        net = escape_html(&format_signed_size(report.summary.net_size_change)),
+    );
+    html.push_str("<h2>By directory</h2>\n<table>\n<thead><tr><th>Directory</th><th>Delta</th><th>Changes</th></tr></thead>\n<tbody>\n");
+    for dir in report.directories {
+        let _ = writeln!(
+            html,
+            r#"<tr><td>{path}</td><td class="num">{delta}</td><td class="num">{changes}</td></tr>"#,
+            path = escape_html(&dir.path),
+            delta = escape_html(&format_signed_size(dir.size_delta)),
+            changes = dir.changes,
+        );
+    }
+    html.push_str(
+        r#"</tbody>
+</table>
+<h2>Changes</h2>
+<table>
+<thead><tr><th>Change</th><th>Path</th><th>Old size</th><th>New size</th><th>Delta</th><th>Old modified</th><th>New modified</th></tr></thead>
+<tbody>
+"#,
+    );
+    for change in report.changes {
+        let size = |state: &Option<EntryState>| {
+            state
+                .as_ref()
+                .map(|s| humansize::format_size(s.size, humansize::DECIMAL))
+                .unwrap_or_default()
+        };
+        let modified = |state: &Option<EntryState>| {
+            state
+                .as_ref()
+                .and_then(|s| s.modified)
+                .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_default()
+        };
+        let _ = writeln!(
+            html,
+            r#"<tr class="{kind}"><td>{kind}</td><td>{path}</td><td class="num">{old_size}</td><td class="num">{new_size}</td><td class="num">{delta}</td><td>{old_modified}</td><td>{new_modified}</td></tr>"#,
+            kind = change.kind.as_str(),
+            path = escape_html(&change.path),
+            old_size = size(&change.old),
+            new_size = size(&change.new),
+            delta = escape_html(&format_signed_size(change.size_delta())),
+            old_modified = modified(&change.old),
+            new_modified = modified(&change.new),
+        );
+    }
+    html.push_str("</tbody>\n</table>\n</body>\n</html>\n");
+    html
+}
+
+fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -1,34 +1,16 @@
+use crate::app_error::AppError;
+use crate::elevated_report::ElevatedReport;
+use crate::elevated_report::report;
+use crate::progress::ProgressEvent;
+use crate::progress_bars::bytes_bar;
+use crate::win_elevation::elevation_allowed;
 use crate::win_elevation::is_elevated;
 use crate::win_elevation::relaunch_as_admin;
-use crate::win_handles::get_drive_handle;
-use eyre::Context;
 use eyre::eyre;
-use std::fs::File;
-use std::fs::OpenOptions;
-use std::io::Write;
-use std::mem::size_of;
 use std::path::Path;
+use storage_usage_core::mft_dump::dump_mft_with_progress;
 use tracing::info;
 use tracing::warn;
-use windows::Win32::Foundation::HANDLE;
-use windows::Win32::Foundation::LUID;
-use windows::Win32::Security::AdjustTokenPrivileges;
-use windows::Win32::Security::LookupPrivilegeValueW;
-use windows::Win32::Security::SE_BACKUP_NAME;
-use windows::Win32::Security::SE_PRIVILEGE_ENABLED;
-use windows::Win32::Security::SE_RESTORE_NAME;
-use windows::Win32::Security::SE_SECURITY_NAME;
-use windows::Win32::Security::TOKEN_ADJUST_PRIVILEGES;
-use windows::Win32::Security::TOKEN_PRIVILEGES;
-use windows::Win32::Security::TOKEN_QUERY;
-use windows::Win32::Storage::FileSystem::FILE_BEGIN;
-use windows::Win32::Storage::FileSystem::ReadFile;
-use windows::Win32::Storage::FileSystem::SetFilePointerEx;
-use windows::Win32::System::IO::DeviceIoControl;
-use windows::Win32::System::Ioctl::FSCTL_GET_NTFS_VOLUME_DATA;
-use windows::Win32::System::Ioctl::NTFS_VOLUME_DATA_BUFFER;
-use windows::Win32::System::Threading::GetCurrentProcess;
-use windows::Win32::System::Threading::OpenProcessToken;
 
 /// Dumps the MFT to the specified file path
 pub fn dump_mft_to_file<P: AsRef<Path>>(
@@ -47,6 +29,13 @@ pub fn dump_mft_to_file<P: AsRef<Path>>(
     }
 
     // Check if we're elevated, and relaunch if not
+    if !is_elevated() && !elevation_allowed() {
+        return Err(AppError::NotElevated {
+            operation: format!("Dumping the MFT of drive {drive_letter}"),
+            hint: "Run elevated, or use `mft query --live` or `mft diff <drive>:`, which walk the directory tree instead.",
+        }
+        .into());
+    }
     if !is_elevated() {
         warn!("Program needs to be run with elevated privileges.");
         info!("Relaunching as administrator...");
@@ -59,516 +48,21 @@ pub fn dump_mft_to_file<P: AsRef<Path>>(
                 std::process::exit(exit_code as i32);
             }
             Err(e) => {
-                return Err(eyre!("Failed to relaunch as administrator: {}", e));
+                return Err(AppError::ElevationFailed(format!("{e:#}")).into());
             }
         }
     }
 
     info!("Program is running with elevated privileges.");
 
-    // Enable backup privileges to access system files like $MFT
-    enable_backup_privileges().with_context(|| "Failed to enable backup privileges")?;
-
-    // Use the provided drive letter
-    let drive_letter = drive_letter.to_uppercase().next().unwrap_or('C');
-
-    // Validate that the drive is using NTFS filesystem
-    info!("Validating filesystem type for drive {}...", drive_letter);
-    validate_ntfs_filesystem(drive_letter)
-        .with_context(|| format!("NTFS validation failed for drive {drive_letter}"))?;
-
-    info!("Reading MFT data from drive {}...", drive_letter);
-    let mft_data = read_mft_data(drive_letter)?;
-
-    info!("Writing MFT data to '{}'...", output_path.display());
-    write_mft_to_file(&mft_data, output_path)?;
-
-    info!(
-        "Successfully dumped MFT ({}) to '{}'",
-        humansize::format_size(mft_data.len(), humansize::DECIMAL),
-        output_path.display()
-    );
-
-    Ok(())
-}
-
-/// Validates that the specified drive is using NTFS filesystem
-fn validate_ntfs_filesystem(drive_letter: char) -> eyre::Result<()> {
-    // For now, we'll validate by attempting to get NTFS volume data
-    // If this succeeds, we know it's an NTFS volume
-    let drive_handle = get_drive_handle(drive_letter)
-        .with_context(|| format!("Failed to open handle to drive {drive_letter}"))?;
-
-    let mut volume_data = NTFS_VOLUME_DATA_BUFFER::default();
-    let mut bytes_returned = 0u32;
-
-    let result = unsafe {
-        DeviceIoControl(
-            *drive_handle,
-            FSCTL_GET_NTFS_VOLUME_DATA,
-            None,
-            0,
-            Some(&mut volume_data as *mut _ as *mut _),
-            size_of::<NTFS_VOLUME_DATA_BUFFER>() as u32,
-            Some(&mut bytes_returned),
-            None,
-        )
-    };
-
-    match result {
-        Ok(_) => {
-            info!(
-                "✓ Filesystem validation passed: Drive {} is using NTFS",
-                drive_letter
-            );
-            info!("NTFS Volume Info:");
-            // info!("  VolumeSerialNumber: 0x{:X}", volume_data.VolumeSerialNumber);
-            info!("  NumberSectors: {}", volume_data.NumberSectors);
-            info!("  TotalClusters: {}", volume_data.TotalClusters);
-            info!("  FreeClusters: {}", volume_data.FreeClusters);
-            info!("  BytesPerSector: {}", volume_data.BytesPerSector);
-            info!("  BytesPerCluster: {}", volume_data.BytesPerCluster);
-            Ok(())
-        }
-        Err(e) => Err(eyre!(
-            "Drive {} does not appear to be using NTFS filesystem. FSCTL_GET_NTFS_VOLUME_DATA failed: {}. MFT dumping is only supported on NTFS volumes.",
-            drive_letter,
-            e
-        )),
-    }
-}
-
-/// Reads the raw MFT data by parsing the MFT's own record and following its data runs
-fn read_mft_data(drive_letter: char) -> eyre::Result<Vec<u8>> {
-    info!("Reading MFT using proper data runs parsing approach");
-    read_mft_from_volume_with_dataruns(drive_letter)
-}
-
-/// Reads the MFT by parsing the boot sector and following data runs properly
-fn read_mft_from_volume_with_dataruns(drive_letter: char) -> eyre::Result<Vec<u8>> {
-    // Get a handle to the volume
-    let drive_handle = get_drive_handle(drive_letter)
-        .with_context(|| format!("Failed to open handle to drive {drive_letter}"))?;
-
-    // Step 1: Read the boot sector to get NTFS parameters
-    let boot_sector = read_boot_sector(*drive_handle)?;
-
-    info!("NTFS Boot Sector Info:");
-    info!("  Bytes per sector: {}", boot_sector.bytes_per_sector);
-    info!("  Sectors per cluster: {}", boot_sector.sectors_per_cluster);
-    info!("  MFT cluster number: {}", boot_sector.mft_cluster_number);
-
-    let bytes_per_cluster =
-        boot_sector.bytes_per_sector as u64 * boot_sector.sectors_per_cluster as u64;
-    let mft_location = boot_sector.mft_cluster_number * bytes_per_cluster;
-
-    info!("Calculated MFT location: {} bytes", mft_location);
-
-    // Step 2: Read the MFT's own record (record 0)
-    let mft_record = read_mft_record(*drive_handle, mft_location, 0)?;
-
-    // Step 3: Parse the MFT record to find the DATA attribute (0x80)
-    let data_runs = parse_mft_record_for_data_attribute(&mft_record)?;
-
-    // Step 4: Follow the data runs to read the complete MFT
-    read_mft_using_data_runs(*drive_handle, &data_runs, bytes_per_cluster)
-}
-
-/// NTFS boot sector information
-#[derive(Debug)]
-struct NtfsBootSector {
-    bytes_per_sector: u16,
-    sectors_per_cluster: u8,
-    mft_cluster_number: u64,
-}
-
-/// Reads and parses the NTFS boot sector
-fn read_boot_sector(drive_handle: HANDLE) -> eyre::Result<NtfsBootSector> {
-    // Seek to the beginning of the drive
-    unsafe {
-        SetFilePointerEx(drive_handle, 0, None, FILE_BEGIN)
-            .with_context(|| "Failed to seek to boot sector")?;
-    }
-
-    // Read the boot sector (512 bytes)
-    let mut boot_sector = vec![0u8; 512];
-    let mut bytes_read = 0u32;
-    unsafe {
-        ReadFile(
-            drive_handle,
-            Some(boot_sector.as_mut_slice()),
-            Some(&mut bytes_read),
-            None,
-        )
-        .with_context(|| "Failed to read boot sector")?;
-    }
-
-    if bytes_read != 512 {
-        return Err(eyre!(
-            "Failed to read complete boot sector: got {} bytes",
-            bytes_read
-        ));
-    }
-
-    // Parse relevant fields from the boot sector
-    let bytes_per_sector = u16::from_le_bytes([boot_sector[0x0b], boot_sector[0x0c]]);
-    let sectors_per_cluster = boot_sector[0x0d];
-    let mft_cluster_number = u64::from_le_bytes([
-        boot_sector[0x30],
-        boot_sector[0x31],
-        boot_sector[0x32],
-        boot_sector[0x33],
-        boot_sector[0x34],
-        boot_sector[0x35],
-        boot_sector[0x36],
-        boot_sector[0x37],
-    ]);
-
-    Ok(NtfsBootSector {
-        bytes_per_sector,
-        sectors_per_cluster,
-        mft_cluster_number,
-    })
-}
-
-/// Reads a specific MFT record
-fn read_mft_record(
-    drive_handle: HANDLE,
-    mft_location: u64,
-    record_number: u64,
-) -> eyre::Result<Vec<u8>> {
-    // MFT records are typically 1024 bytes each
-    const MFT_RECORD_SIZE: u64 = 1024;
-    let record_offset = mft_location + (record_number * MFT_RECORD_SIZE);
-
-    // Seek to the record
-    unsafe {
-        SetFilePointerEx(drive_handle, record_offset as i64, None, FILE_BEGIN)
-            .with_context(|| format!("Failed to seek to MFT record {record_number}"))?;
-    }
-
-    // Read the record
-    let mut record = vec![0u8; MFT_RECORD_SIZE as usize];
-    let mut bytes_read = 0u32;
-    unsafe {
-        ReadFile(
-            drive_handle,
-            Some(record.as_mut_slice()),
-            Some(&mut bytes_read),
-            None,
-        )
-        .with_context(|| format!("Failed to read MFT record {record_number}"))?;
-    }
-
-    if bytes_read != MFT_RECORD_SIZE as u32 {
-        return Err(eyre!(
-            "Failed to read complete MFT record: got {} bytes",
-            bytes_read
-        ));
-    }
-
-    // Verify this is a valid MFT record by checking the signature
-    if &record[0..4] != b"FILE" {
-        return Err(eyre!(
-            "Invalid MFT record signature: expected 'FILE', got '{}'",
-            String::from_utf8_lossy(&record[0..4])
-        ));
-    }
-
-    Ok(record)
-}
-
-/// Data run information
-#[derive(Debug)]
-struct DataRun {
-    length: u64,  // Length in clusters
-    cluster: i64, // Cluster offset (can be negative for relative positioning)
-}
-
-/// Parses an MFT record to extract data runs from the DATA attribute (0x80)
-fn parse_mft_record_for_data_attribute(record: &[u8]) -> eyre::Result<Vec<DataRun>> {
-    // Get the offset to the first attribute (typically at offset 20)
-    let attr_offset = u16::from_le_bytes([record[20], record[21]]) as usize;
-    let mut read_ptr = attr_offset;
-
-    while read_ptr < record.len() {
-        // Read attribute header
-        if read_ptr + 8 > record.len() {
-            break;
+    let bar = bytes_bar(0, format!("{drive_letter}: $MFT"));
+    let dumped = dump_mft_with_progress(drive_letter, output_path, &mut |event: ProgressEvent| {
+        if let ProgressEvent::MftRead { read, total } = event {
+            report(&ElevatedReport::Progress { read, total });
+            bar.set_length(total);
+            bar.set_position(read);
         }
-
-        let attr_type = u32::from_le_bytes([
-            record[read_ptr],
-            record[read_ptr + 1],
-            record[read_ptr + 2],
-            record[read_ptr + 3],
-        ]);
-
-        // Check for end marker
-        if attr_type == 0xffffffff {
-            break;
-        }
-
-        let attr_length = u32::from_le_bytes([
-            record[read_ptr + 4],
-            record[read_ptr + 5],
-            record[read_ptr + 6],
-            record[read_ptr + 7],
-        ]) as usize;
-
-        if attr_length == 0 {
-            break;
-        }
-
-        // Check if this is the DATA attribute (0x80)
-        if attr_type == 0x80 {
-            // Check if it's non-resident (byte at offset 8 should be != 0)
-            if read_ptr + 8 < record.len() && record[read_ptr + 8] != 0 {
-                // Get the data runs offset (at offset 32 from attribute start)
-                if read_ptr + 34 <= record.len() {
-                    let run_offset =
-                        u16::from_le_bytes([record[read_ptr + 32], record[read_ptr + 33]]) as usize;
-
-                    let data_runs_start = read_ptr + run_offset;
-                    let data_runs_end = read_ptr + attr_length;
-
-                    if data_runs_start < data_runs_end && data_runs_end <= record.len() {
-                        return decode_data_runs(&record[data_runs_start..data_runs_end]);
-                    }
-                }
-            }
-        }
-
-        read_ptr += attr_length;
-    }
-
-    Err(eyre!("Could not find DATA attribute (0x80) in MFT record"))
-}
-
-/// Decodes NTFS data runs
-fn decode_data_runs(data_runs: &[u8]) -> eyre::Result<Vec<DataRun>> {
-    let mut runs = Vec::new();
-    let mut decode_pos = 0;
-
-    while decode_pos < data_runs.len() {
-        let header = data_runs[decode_pos];
-
-        // End of data runs
-        if header == 0 {
-            break;
-        }
-
-        let offset_bytes = (header & 0xf0) >> 4;
-        let length_bytes = header & 0x0f;
-
-        if offset_bytes == 0 || length_bytes == 0 {
-            break;
-        }
-
-        decode_pos += 1;
-
-        // Read length (little-endian)
-        if decode_pos + length_bytes as usize > data_runs.len() {
-            break;
-        }
-
-        let mut length = 0u64;
-        for i in 0..length_bytes {
-            length |= (data_runs[decode_pos + i as usize] as u64) << (i * 8);
-        }
-        decode_pos += length_bytes as usize;
-
-        // Read offset (little-endian, signed)
-        if decode_pos + offset_bytes as usize > data_runs.len() {
-            break;
-        }
-
-        let mut cluster = 0i64;
-        for i in 0..offset_bytes {
-            cluster |= (data_runs[decode_pos + i as usize] as i64) << (i * 8);
-        }
-
-        // Handle sign extension for the offset
-        if offset_bytes > 0 {
-            let sign_bit = 1i64 << (offset_bytes * 8 - 1);
-            if cluster & sign_bit != 0 {
-                cluster |= !((1i64 << (offset_bytes * 8)) - 1);
-            }
-        }
-
-        decode_pos += offset_bytes as usize;
-
-        runs.push(DataRun { length, cluster });
-    }
-
-    Ok(runs)
-}
-
-/// Reads the complete MFT using the parsed data runs
-fn read_mft_using_data_runs(
-    drive_handle: HANDLE,
-    data_runs: &[DataRun],
-    bytes_per_cluster: u64,
-) -> eyre::Result<Vec<u8>> {
-    let mut mft_data = Vec::new();
-    let mut current_cluster = 0i64;
-
-    info!("Found {} data runs for MFT", data_runs.len());
-
-    for (i, run) in data_runs.iter().enumerate() {
-        // Calculate absolute cluster position
-        current_cluster += run.cluster;
-
-        let byte_offset = current_cluster as u64 * bytes_per_cluster;
-        let byte_length = run.length * bytes_per_cluster;
-
-        info!(
-            "Data run {}: cluster {} (offset {}), length {} clusters ({})",
-            i + 1,
-            current_cluster,
-            humansize::format_size(byte_offset, humansize::DECIMAL),
-            run.length,
-            humansize::format_size(byte_length, humansize::DECIMAL)
-        );
-
-        // Seek to the run location
-        unsafe {
-            SetFilePointerEx(drive_handle, byte_offset as i64, None, FILE_BEGIN).with_context(
-                || {
-                    format!(
-                        "Failed to seek to data run {} at offset {}",
-                        i + 1,
-                        byte_offset
-                    )
-                },
-            )?;
-        }
-
-        // Read the run data
-        let mut run_data = vec![0u8; byte_length as usize];
-        let mut total_read = 0;
-        let mut offset = 0;
-
-        while offset < byte_length {
-            let remaining = byte_length - offset;
-            let chunk_size = remaining.min(1024 * 1024) as usize; // Read in 1MB chunks
-
-            let mut bytes_read = 0u32;
-            unsafe {
-                ReadFile(
-                    drive_handle,
-                    Some(&mut run_data[offset as usize..offset as usize + chunk_size]),
-                    Some(&mut bytes_read),
-                    None,
-                )
-                .with_context(|| {
-                    format!("Failed to read data run {} at offset {}", i + 1, offset)
-                })?;
-            }
-
-            if bytes_read == 0 {
-                break;
-            }
-
-            offset += bytes_read as u64;
-            total_read += bytes_read as u64;
-        }
-
-        run_data.truncate(total_read as usize);
-        mft_data.extend_from_slice(&run_data);
-
-        info!(
-            "Read {} from data run {}",
-            humansize::format_size(total_read, humansize::DECIMAL),
-            i + 1
-        );
-    }
-
-    info!(
-        "Successfully read complete MFT: {}",
-        humansize::format_size(mft_data.len(), humansize::DECIMAL)
-    );
-
-    Ok(mft_data)
-}
-
-/// Writes the MFT data to the specified file
-fn write_mft_to_file(mft_data: &[u8], output_path: &Path) -> eyre::Result<()> {
-    let mut file = if output_path.exists() {
-        // If file exists and we got here, overwrite_existing must be true
-        OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open(output_path)
-            .with_context(|| {
-                format!("Failed to open file for writing: {}", output_path.display())
-            })?
-    } else {
-        // Create new file
-        File::create(output_path)
-            .with_context(|| format!("Failed to create file: {}", output_path.display()))?
-    };
-
-    file.write_all(mft_data).with_context(|| {
-        format!(
-            "Failed to write MFT data to file: {}",
-            output_path.display()
-        )
-    })?;
-
-    file.flush()
-        .with_context(|| format!("Failed to flush file: {}", output_path.display()))?;
-
-    Ok(())
-}
-
-/// Enables backup and security privileges for the current process
-fn enable_backup_privileges() -> eyre::Result<()> {
-    use std::mem::size_of;
-
-    unsafe {
-        // Get current process token
-        let mut token = windows::Win32::Foundation::HANDLE::default();
-        OpenProcessToken(
-            GetCurrentProcess(),
-            TOKEN_ADJUST_PRIVILEGES | TOKEN_QUERY,
-            &mut token,
-        )
-        .with_context(|| "Failed to open process token")?;
-
-        // Enable multiple privileges that might be needed
-        let privileges_to_enable = [SE_BACKUP_NAME, SE_RESTORE_NAME, SE_SECURITY_NAME];
-
-        for privilege_name in &privileges_to_enable {
-            // Look up the privilege LUID
-            let mut luid = LUID::default();
-            if LookupPrivilegeValueW(None, *privilege_name, &mut luid).is_ok() {
-                // Set up the privilege structure
-                let privileges = TOKEN_PRIVILEGES {
-                    PrivilegeCount: 1,
-                    Privileges: [windows::Win32::Security::LUID_AND_ATTRIBUTES {
-                        Luid: luid,
-                        Attributes: SE_PRIVILEGE_ENABLED,
-                    }],
-                };
-
-                // Adjust token privileges
-                let _ = AdjustTokenPrivileges(
-                    token,
-                    false,
-                    Some(&privileges),
-                    size_of::<TOKEN_PRIVILEGES>() as u32,
-                    None,
-                    None,
-                );
-            }
-        }
-
-        // Close token handle
-        windows::Win32::Foundation::CloseHandle(token)
-            .with_context(|| "Failed to close token handle")?;
-
-        info!("Successfully enabled backup privileges");
-        Ok(())
-    }
+    });
+    bar.finish_and_clear();
+    dumped.map(|_| ())
 }
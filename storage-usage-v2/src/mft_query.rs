@@ -1,34 +1,154 @@
 // Import chrono types from mft crate's exports
 use chrono::{DateTime, Utc};
 use mft::MftParser;
-use mft::attribute::MftAttributeContent;
 use nucleo::Nucleo;
 use std::collections::HashMap;
+use std::io::Read;
+use std::io::Seek;
+use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::Mutex;
+use crate::app_error::AppError;
 use crate::cli::drive_letter_pattern::DriveLetterPattern; // new
+use crate::cli::mft_query_action::MftQueryArgs;
+use crate::cli::mft_query_action::QueryAggregate;
+use crate::cli::output_format::OutputFormat;
+use crate::cli::output_format::csv_escape;
 use crate::config::get_cache_dir; // new
+use crate::dir_index::index_allocation_path_for;
+use crate::exclude::ExcludeSet;
+use crate::file_flags::FileFlag;
+use crate::file_flags::FileFlags;
+use crate::mapped_index::MappedIndex;
+use crate::mft_index::IndexEntry;
+use crate::mft_index::MftIndex;
+use crate::mft_index::load_fresh_index;
+use crate::mft_index::open_fresh_index;
+use crate::mft_index::record_entries;
+use crate::path_resolver::PathResolver;
+use crate::reparse::ReparseKind;
+use crate::reparse::ReparsePoint;
+use crate::search_filter::is_under;
+use crate::search_filter::Prefilter;
+use crate::search_filter::nfc;
+use crate::search_filter::split_drive;
+use crate::progress_bars::index_with_bar;
+use crate::tui::picker::Picker;
+use crate::win_elevation::elevation_allowed;
+use crate::win_elevation::is_elevated;
+use crate::win_elevation::relaunch_as_admin;
+use crate::win_privileges::ensure_single_volume;
+use storage_usage_core::mft_dump::open_live_mft_reader;
+use tracing::info;
+use tracing::warn;
 use rayon::prelude::*; // new
-use std::time::{Duration, Instant}; // added
+use std::time::Duration;
+use std::time::Instant;
 use std::sync::atomic::{AtomicU64, AtomicBool, Ordering}; // new
 
 #[derive(Clone)]
 struct FileEntry {
+    drive_letter: char,
+    record_number: u64,
+    hard_link_count: u16,
     filename: String,
     parent_ref: Option<u64>,
     display_path: String,
     created: Option<DateTime<Utc>>,
     modified: Option<DateTime<Utc>>,
     accessed: Option<DateTime<Utc>>,
+    size: u64,
+    allocated_size: u64,
+    flags: FileFlags,
+    reparse: Option<ReparsePoint>,
 }
 
-#[derive(Clone)]
-struct DirectoryEntry {
-    name: String,
-    parent_reference: Option<u64>,
+impl FileEntry {
+    fn from_index(drive_letter: char, display_path: String, entry: IndexEntry) -> Self {
+        Self {
+            drive_letter,
+            record_number: entry.record_number,
+            hard_link_count: entry.hard_link_count,
+            filename: entry.name,
+            parent_ref: entry.parent_ref,
+            display_path,
+            created: entry.created,
+            modified: entry.modified,
+            accessed: entry.accessed,
+            size: entry.size,
+            allocated_size: entry.allocated_size,
+            flags: entry.flags,
+            reparse: entry.reparse,
+        }
+    }
+}
+
+/// A single query match as emitted by the machine-readable formats
+#[derive(serde::Serialize)]
+struct QueryResultRecord<'a> {
+    path: &'a str,
+    drive: char,
+    record_number: u64,
+    hard_link_count: u16,
+    size: u64,
+    allocated_size: u64,
+    created: Option<DateTime<Utc>>,
+    modified: Option<DateTime<Utc>>,
+    accessed: Option<DateTime<Utc>>,
+    flags: FileFlags,
+    /// What kind of reparse point the entry is, if any
+    reparse: Option<ReparseKind>,
+    reparse_target: Option<&'a str>,
+}
+
+impl<'a> From<&'a FileEntry> for QueryResultRecord<'a> {
+    fn from(entry: &'a FileEntry) -> Self {
+        Self {
+            path: &entry.display_path,
+            drive: entry.drive_letter,
+            record_number: entry.record_number,
+            hard_link_count: entry.hard_link_count,
+            size: entry.size,
+            allocated_size: entry.allocated_size,
+            created: entry.created,
+            modified: entry.modified,
+            accessed: entry.accessed,
+            flags: entry.flags,
+            reparse: entry.reparse.as_ref().map(ReparsePoint::kind),
+            reparse_target: entry.reparse.as_ref().and_then(|r| r.target.as_deref()),
+        }
+    }
 }
 
-pub fn query_mft_files_fuzzy(drive_pattern: DriveLetterPattern, query: String, limit: usize, display_interval: Duration, top_n: usize, timeout: Option<Duration>) -> eyre::Result<()> {
+/// Match totals where hard links to the same record are only counted once
+#[derive(Default)]
+struct DedupTotals {
+    paths: usize,
+    unique_files: usize,
+    bytes: u64,
+    allocated_bytes: u64,
+}
+
+impl DedupTotals {
+    fn from_entries<'a>(entries: impl Iterator<Item = &'a FileEntry>) -> Self {
+        let mut seen = std::collections::HashSet::new();
+        let mut totals = Self::default();
+        for entry in entries {
+            totals.paths += 1;
+            if seen.insert((entry.drive_letter, entry.record_number)) {
+                totals.unique_files += 1;
+                totals.bytes += entry.size;
+                totals.allocated_bytes += entry.allocated_size;
+            }
+        }
+        totals
+    }
+}
+
+pub fn query_mft_files_fuzzy(args: MftQueryArgs, excludes: ExcludeSet) -> eyre::Result<()> {
+    let MftQueryArgs { drive_pattern, query, limit, display_interval, top_n, timeout, format, live, aggregate, only, include_deleted, under, prefilter, .. } = args;
+    let include_deleted = include_deleted || only.contains(&FileFlag::Deleted);
     if query.trim().is_empty() {
         return Err(eyre::eyre!(
             "No search query specified. Please provide a search term for fuzzy matching."
@@ -36,18 +156,59 @@ pub fn query_mft_files_fuzzy(drive_pattern: DriveLetterPattern, query: String, l
     }
 
     let drives = drive_pattern.resolve()?;
-    let cache = get_cache_dir()?;
-    let mut mft_files: Vec<PathBuf> = drives.iter().map(|d| cache.join(format!("{d}.mft"))).collect();
-    mft_files.retain(|p| p.exists());
+    // Without elevation under --no-elevate, live volumes are walked instead of read
+    let walk = live && !is_elevated() && !elevation_allowed();
+    // (drive, cached dump) pairs; a missing dump means the volume is read live
+    let mft_files: Vec<(char, Option<PathBuf>)> = if walk {
+        let cache = get_cache_dir()?;
+        drives
+            .iter()
+            .map(|d| {
+                let dump = cache.join(format!("{d}.mft"));
+                if dump.exists() {
+                    warn!("Not elevated and --no-elevate given: using the cached dump of {d}: instead of its live MFT; it may be out of date");
+                    (*d, Some(dump))
+                } else {
+                    warn!("Not elevated and --no-elevate given: walking {d}:\\ instead of reading its MFT; record numbers and hard links are made up, allocated sizes are logical sizes, and unlistable directories are skipped");
+                    (*d, None)
+                }
+            })
+            .collect()
+    } else if live {
+        if !is_elevated() {
+            warn!("Live queries need elevated privileges to open the volume.");
+            info!("Relaunching as administrator...");
+            let child = relaunch_as_admin()?;
+            let exit_code = child.wait()?;
+            std::process::exit(exit_code as i32);
+        }
+        drives.iter().map(|d| (*d, None)).collect()
+    } else {
+        let cache = get_cache_dir()?;
+        let mut mft_files: Vec<(char, Option<PathBuf>)> = drives.iter().map(|d| (*d, Some(cache.join(format!("{d}.mft"))))).collect();
+        mft_files.retain(|(_, p)| p.as_ref().is_some_and(|p| p.exists()));
+        mft_files
+    };
 
+    if live && !walk {
+        ensure_single_volume(mft_files.len())?;
+    }
     if mft_files.is_empty() {
-        return Err(eyre::eyre!("No cached MFT files found for pattern '{}'. Run mft sync first.", drive_pattern));
+        return Err(AppError::NoCachedDumps {
+            pattern: drive_pattern.to_string(),
+            hint: "Run mft sync first, or pass --live.",
+        }
+        .into());
     }
 
-    println!("Fuzzy searching for: '{query}'");
-    println!("Drives: {}", drives.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(","));
-    println!("Using full paths for all results");
-    println!();
+    let verbose_output = !format.is_machine_readable();
+    if verbose_output {
+        println!("Fuzzy searching for: '{query}'");
+        println!("Drives: {}", drives.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(","));
+        println!("Using full paths for all results");
+        if include_deleted { println!("Including deleted files; their paths are best-effort"); }
+        println!();
+    }
 
     // Set up nucleo matcher
     let config = nucleo::Config::DEFAULT;
@@ -58,7 +219,9 @@ pub fn query_mft_files_fuzzy(drive_pattern: DriveLetterPattern, query: String, l
         1,               // single column for matching
     );
 
-    println!("Collecting files from cached MFTs in parallel...");
+    if verbose_output {
+        if live { println!("Reading MFTs from live volumes in parallel..."); } else { println!("Collecting files from cached MFTs in parallel..."); }
+    }
 
     // Shared progress counters
     let total_entries = Arc::new(AtomicU64::new(0));
@@ -66,139 +229,84 @@ pub fn query_mft_files_fuzzy(drive_pattern: DriveLetterPattern, query: String, l
     let done = Arc::new(AtomicBool::new(false));
 
     let injector = matcher.injector();
+    // Every path of each hard-linked record, matched or not, keyed by (drive, record)
+    let links: Arc<Mutex<HashMap<(char, u64), Vec<String>>>> = Arc::default();
 
     // Spawn worker thread performing parallel parsing & streaming injection
     let worker_total = total_entries.clone();
     let worker_files = files_collected.clone();
     let worker_done = done.clone();
+    let worker_links = links.clone();
     let mft_files_cloned = mft_files.clone();
-    let drives_cloned = drives.clone();
+    let prefilter = Prefilter::new(prefilter, &query);
     std::thread::spawn(move || {
-        // Structure holding a not-yet-resolved entry
-        #[derive(Clone)]
-        struct PendingEntry {
-            record_number: u64,
-            filename: String,
-            parent_ref: Option<u64>,
-            created: Option<DateTime<Utc>>,
-            modified: Option<DateTime<Utc>>,
-            accessed: Option<DateTime<Utc>>,
-        }
+        // Inject a match candidate unless an exclude pattern filters it out
+        let push_entry = |entry_record: FileEntry| {
+            if !include_deleted && entry_record.flags.contains(FileFlag::Deleted) { return; }
+            if entry_record.hard_link_count > 1 {
+                worker_links
+                    .lock()
+                    .unwrap()
+                    .entry((entry_record.drive_letter, entry_record.record_number))
+                    .or_default()
+                    .push(entry_record.display_path.clone());
+            }
+            if under.as_deref().is_some_and(|directory| !is_under(&entry_record.display_path, directory)) { return; }
+            if excludes.is_excluded(&entry_record.display_path) { return; }
+            if !entry_record.flags.contains_all(&only) { return; }
+            if !prefilter.matches(&entry_record.display_path) { return; }
+            injector.push(entry_record, |e, cols| { cols[0] = nfc(&e.display_path).as_ref().into(); });
+            worker_files.fetch_add(1, Ordering::Relaxed);
+        };
+
+        let inject_index = |index: &MftIndex, drive_letter: char| {
+            for (display_path, entry) in index.resolve_paths(drive_letter) {
+                worker_total.fetch_add(1, Ordering::Relaxed);
+                push_entry(FileEntry::from_index(drive_letter, display_path, entry.clone()));
+            }
+        };
 
-        mft_files_cloned.par_iter().enumerate().for_each(|(drive_index, mft_file)| {
-            if let Ok(mut parser) = MftParser::from_path(mft_file) {
-                let drive_letter = drives_cloned[drive_index];
-                let mut directories: HashMap<u64, DirectoryEntry> = HashMap::new();
-                // parent_id -> list of children waiting for that ancestor to appear
-                let mut pending: HashMap<u64, Vec<PendingEntry>> = HashMap::new();
-
-                // Attempt to resolve a vector of pending entries (called when a new directory becomes available)
-                let mut resolve_queue = Vec::new();
-
-                for entry_result in parser.iter_entries() {
-                    worker_total.fetch_add(1, Ordering::Relaxed);
-                    if let Ok(entry) = entry_result {
-                        let record_number = entry.header.record_number;
-                        let mut std_created = None;
-                        let mut std_modified = None;
-                        let mut std_accessed = None;
-                        for attribute_result in entry.iter_attributes() {
-                            if let Ok(attribute) = attribute_result
-                                && let MftAttributeContent::AttrX10(info) = &attribute.data
-                            {
-                                std_created = Some(info.created);
-                                std_modified = Some(info.modified);
-                                std_accessed = Some(info.accessed);
-                                break;
-                            }
-                        }
-                        for attribute_result in entry.iter_attributes() {
-                            if let Ok(attribute) = attribute_result
-                                && let MftAttributeContent::AttrX30(filename_attr) = &attribute.data
-                            {
-                                let filename = &filename_attr.name;
-                                if filename.starts_with('$') || filename == "." || filename == ".." { continue; }
-                                let parent_ref = if filename_attr.parent.entry == 0 { None } else { Some(filename_attr.parent.entry) };
-
-                                // Insert directory entry for this record (even if it's a file; harmless, enables parent traversal)
-                                directories.insert(record_number, DirectoryEntry { name: filename.clone(), parent_reference: parent_ref });
-
-                                // Try to build full path now
-                                match try_build_full_path(filename, parent_ref, &directories, drive_letter) {
-                                    Ok(full_path) => {
-                                        let entry_record = FileEntry {
-                                            filename: filename.clone(),
-                                            parent_ref,
-                                            display_path: full_path,
-                                            created: Some(filename_attr.created).or(std_created),
-                                            modified: Some(filename_attr.modified).or(std_modified),
-                                            accessed: Some(filename_attr.accessed).or(std_accessed),
-                                        };
-                                        injector.push(entry_record, |e, cols| { cols[0] = e.display_path.clone().into(); });
-                                        worker_files.fetch_add(1, Ordering::Relaxed);
-
-                                        // Newly inserted directory might unblock children waiting on this record_number
-                                        if let Some(children) = pending.remove(&record_number) {
-                                            resolve_queue.extend(children);
-                                        }
-                                    }
-                                    Err(missing_parent) => {
-                                        // Queue for later when that parent id appears
-                                        let p = PendingEntry {
-                                            record_number,
-                                            filename: filename.clone(),
-                                            parent_ref,
-                                            created: Some(filename_attr.created).or(std_created),
-                                            modified: Some(filename_attr.modified).or(std_modified),
-                                            accessed: Some(filename_attr.accessed).or(std_accessed),
-                                        };
-                                        pending.entry(missing_parent).or_default().push(p);
-                                    }
-                                }
-
-                                // Resolve queue breadth-first
-                                while let Some(pend) = resolve_queue.pop() {
-                                    match try_build_full_path(&pend.filename, pend.parent_ref, &directories, drive_letter) {
-                                        Ok(path) => {
-                                            let entry_record = FileEntry {
-                                                filename: pend.filename.clone(),
-                                                parent_ref: pend.parent_ref,
-                                                display_path: path,
-                                                created: pend.created,
-                                                modified: pend.modified,
-                                                accessed: pend.accessed,
-                                            };
-                                            injector.push(entry_record, |e, cols| { cols[0] = e.display_path.clone().into(); });
-                                            worker_files.fetch_add(1, Ordering::Relaxed);
-                                            if let Some(children) = pending.remove(&pend.record_number) {
-                                                resolve_queue.extend(children);
-                                            }
-                                        }
-                                        Err(missing_parent) => {
-                                            pending.entry(missing_parent).or_default().push(pend);
-                                        }
-                                    }
-                                }
-                                break; // first X30 only
-                            }
-                        }
+        // Paths are stored in a mapped index, so only the entries below --under are decoded
+        let inject_mapped = |index: &MappedIndex, drive_letter: char| {
+            let positions: Box<dyn Iterator<Item = usize>> = match &under {
+                Some(directory) if split_drive(directory).0.is_some_and(|d| d != drive_letter) => return,
+                Some(directory) => Box::new(index.under(directory)),
+                None => Box::new(0..index.len()),
+            };
+            for position in positions {
+                worker_total.fetch_add(1, Ordering::Relaxed);
+                push_entry(FileEntry::from_index(drive_letter, index.path(drive_letter, position), index.entry(position)));
+            }
+        };
+
+        mft_files_cloned.par_iter().for_each(|(drive_letter, mft_file)| {
+            let drive_letter = *drive_letter;
+            match mft_file {
+                Some(mft_file) => {
+                    // Prefer a fresh on-disk index over re-parsing the whole dump; indexes from
+                    // before the mappable format are loaded whole
+                    if let Some(index) = open_fresh_index(mft_file) {
+                        inject_mapped(&index, drive_letter);
+                        return;
+                    }
+                    if let Some(index) = load_fresh_index(mft_file) {
+                        inject_index(&index, drive_letter);
+                        return;
+                    }
+                    if let Ok(mut parser) = MftParser::from_path(mft_file) {
+                        inject_from_parser(&mut parser, drive_letter, Some(&index_allocation_path_for(mft_file)), &worker_total, &push_entry);
                     }
                 }
-
-                // Any remaining pending entries couldn't resolve (cycles or missing ancestors); inject best-effort partials
-                for (_missing, entries) in pending.into_iter() {
-                    for pend in entries {
-                        let partial_path = format!("{drive_letter}:\\{}", pend.filename); // minimal fallback
-                        let entry_record = FileEntry {
-                            filename: pend.filename,
-                            parent_ref: pend.parent_ref,
-                            display_path: partial_path,
-                            created: pend.created,
-                            modified: pend.modified,
-                            accessed: pend.accessed,
-                        };
-                        injector.push(entry_record, |e, cols| { cols[0] = e.display_path.clone().into(); });
-                        worker_files.fetch_add(1, Ordering::Relaxed);
+                None if walk => inject_index(&MftIndex::build_from_walk(drive_letter), drive_letter),
+                None => {
+                    let parser = open_live_mft_reader(drive_letter).and_then(|reader| {
+                        let size = reader.total_size();
+                        MftParser::from_read_seek(reader, Some(size))
+                            .map_err(|e| eyre::eyre!("Failed to parse live MFT: {}", e))
+                    });
+                    match parser {
+                        Ok(mut parser) => inject_from_parser(&mut parser, drive_letter, None, &worker_total, &push_entry),
+                        Err(e) => tracing::error!("Skipping live drive {drive_letter}: {e:#}"),
                     }
                 }
             }
@@ -206,11 +314,13 @@ pub fn query_mft_files_fuzzy(drive_pattern: DriveLetterPattern, query: String, l
         worker_done.store(true, Ordering::Release);
     });
 
-    println!("Performing fuzzy search & streaming results...");
+    if verbose_output { println!("Performing fuzzy search & streaming results..."); }
+    // Windows ignores case in names, so an uppercase letter doesn't turn on case-sensitive
+    // matching the way smart case would
     matcher.pattern.reparse(
         0,
-        &query,
-        nucleo::pattern::CaseMatching::Smart,
+        &nfc(&query),
+        nucleo::pattern::CaseMatching::Ignore,
         nucleo::pattern::Normalization::Smart,
         false,
     );
@@ -222,6 +332,10 @@ pub fn query_mft_files_fuzzy(drive_pattern: DriveLetterPattern, query: String, l
     loop {
         if let Some(t) = timeout { if start.elapsed() >= t { break; } }
         matcher.tick(10); // small wait for matcher updates
+        if !verbose_output {
+            if done.load(Ordering::Acquire) { break; }
+            continue;
+        }
         if last_display.elapsed() >= display_interval {
             let snapshot = matcher.snapshot();
             let matched_count = snapshot.matched_item_count() as usize;
@@ -255,13 +369,31 @@ pub fn query_mft_files_fuzzy(drive_pattern: DriveLetterPattern, query: String, l
         }
     }
 
-    // Final snapshot & full display up to limit
-    matcher.tick(0);
+    // Final snapshot & full display up to limit. Once the worker is done, matching catches up
+    // with everything it injected; past the timeout it's still injecting, so matching gets only
+    // what's left of the deadline and the results are partial
+    if done.load(Ordering::Acquire) {
+        finish_matching(&mut matcher);
+    } else {
+        let left = timeout.map_or(Duration::ZERO, |t| t.saturating_sub(start.elapsed()));
+        matcher.tick(left.as_millis() as u64);
+    }
     let snapshot = matcher.snapshot();
     let matched_count = snapshot.matched_item_count() as usize;
     let total_entries_val = total_entries.load(Ordering::Relaxed);
     let files_collected_val = files_collected.load(Ordering::Relaxed);
 
+    if aggregate != QueryAggregate::None {
+        let rows = aggregate_matches(snapshot.matched_items(0..matched_count as u32).map(|item| item.data), aggregate);
+        return write_aggregate(&rows, aggregate, format);
+    }
+
+    if format.is_machine_readable() {
+        let results_to_show = matched_count.min(limit);
+        let entries: Vec<&FileEntry> = snapshot.matched_items(0..results_to_show as u32).map(|item| item.data).collect();
+        return write_machine_readable(&entries, format);
+    }
+
     if matched_count == 0 {
         println!("No files found matching the search query '{query}'");
         println!("Searched {files_collected_val} files ({} entries) total.", total_entries_val);
@@ -270,48 +402,316 @@ pub fn query_mft_files_fuzzy(drive_pattern: DriveLetterPattern, query: String, l
 
     println!("Found {matched_count} matching files (processed {files_collected_val} files / {total_entries_val} entries across {} drives):\n", mft_files.len());
 
+    // List each file once under its best-matching link, followed by all its other links
     let results_to_show = matched_count.min(limit);
-    let matched_items = snapshot.matched_items(0..results_to_show as u32);
-    for (i, item) in matched_items.enumerate() {
-        let entry = &item.data;
+    let links = links.lock().unwrap();
+    let mut listed = std::collections::HashSet::new();
+    for item in snapshot.matched_items(0..results_to_show as u32) {
+        let entry = item.data;
+        if !listed.insert((entry.drive_letter, entry.record_number)) { continue; }
+        let other_links = links
+            .get(&(entry.drive_letter, entry.record_number))
+            .into_iter()
+            .flatten()
+            .filter(|link| **link != entry.display_path);
         let created_str = entry.created.map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string()).unwrap_or_else(|| "N/A".to_string());
         let modified_str = entry.modified.map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string()).unwrap_or_else(|| "N/A".to_string());
         let accessed_str = entry.accessed.map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string()).unwrap_or_else(|| "N/A".to_string());
         println!("{}", entry.display_path);
+        for link in other_links { println!("  Also linked as: {link}"); }
+        if entry.hard_link_count > 1 { println!("  Hard links: {}", entry.hard_link_count); }
+        if entry.flags != FileFlags::default() { println!("  Flags:    {}", entry.flags); }
+        if let Some(reparse) = &entry.reparse { println!("  Reparse:  {reparse}"); }
+        // Sparse runs and compression are why a file can take less space than its size
+        let physical = if entry.flags.contains(FileFlag::Sparse) {
+            " on disk, sparse"
+        } else if entry.flags.contains(FileFlag::Compressed) {
+            " on disk, compressed"
+        } else {
+            " allocated"
+        };
+        println!(
+            "  Size:     {} ({}{physical})",
+            humansize::format_size(entry.size, humansize::DECIMAL),
+            humansize::format_size(entry.allocated_size, humansize::DECIMAL)
+        );
         println!("  Created:  {created_str} UTC");
         println!("  Modified: {modified_str} UTC");
         println!("  Accessed: {accessed_str} UTC\n");
-        if i + 1 >= limit { break; }
     }
+    let totals = DedupTotals::from_entries(snapshot.matched_items(0..matched_count as u32).map(|item| item.data));
+    println!(
+        "Totals across {} drives: {} paths, {} unique files after hard link dedup, {} ({} allocated)",
+        mft_files.len(),
+        totals.paths,
+        totals.unique_files,
+        humansize::format_size(totals.bytes, humansize::DECIMAL),
+        humansize::format_size(totals.allocated_bytes, humansize::DECIMAL),
+    );
     if matched_count > limit { println!("\n... and {} more results (showing first {} due to limit)", matched_count - limit, limit); }
     println!("\nFound {matched_count} files matching '{query}' (limit: {limit})");
     if let Some(t) = timeout { if start.elapsed() >= t { println!("Timeout reached after {} ms", start.elapsed().as_millis()); } }
     Ok(())
 }
 
-fn try_build_full_path(
-    filename: &str,
-    parent_ref: Option<u64>,
-    directories: &HashMap<u64, DirectoryEntry>,
+/// Tick until nucleo has matched everything injected so far; the worker finishing injecting
+/// doesn't mean matching has caught up, and a snapshot taken before then misses matches
+fn finish_matching<T: Sync + Send + 'static>(matcher: &mut Nucleo<T>) {
+    while matcher.tick(10).running {}
+}
+
+/// Parse every entry from an MFT parser, resolve full paths, and hand each named record to `push_entry`.
+/// Index blocks dumped next to the MFT at `index_allocations` name children whose records are gone.
+fn inject_from_parser<T: Read + Seek>(
+    parser: &mut MftParser<T>,
     drive_letter: char,
-) -> Result<String, u64> {
-    let mut components = vec![filename.to_string()];
-    let mut current = parent_ref;
-    let mut guard = 0usize;
-    while let Some(pid) = current {
-        if guard > 4096 { break; }
-        if pid == 5 { // root sentinel
-            break;
+    index_allocations: Option<&Path>,
+    worker_total: &AtomicU64,
+    push_entry: &impl Fn(FileEntry),
+) {
+    // A first pass over the directories lets every name be placed as soon as it's read
+    let mut resolver = PathResolver::new(drive_letter);
+    for record in 0..parser.get_entry_count() {
+        if let Ok(entry) = parser.get_entry(record) {
+            resolver.add_entry(&entry);
         }
-        if let Some(dir) = directories.get(&pid) {
-            if dir.name == "." { break; }
-            components.push(dir.name.clone());
-            current = dir.parent_reference;
-        } else {
-            return Err(pid); // missing ancestor
+    }
+    if let Some(path) = index_allocations && let Err(e) = resolver.add_index_allocations(path) {
+        tracing::debug!("Not using directory index blocks: {e:#}");
+    }
+
+    // Read by number rather than through `iter_entries` so the parser stays free to read the
+    // extension records that hold some files' names and $DATA
+    for record in 0..parser.get_entry_count() {
+        let entry_result = parser.get_entry(record);
+        worker_total.fetch_add(1, Ordering::Relaxed);
+        if let Ok(entry) = entry_result {
+            for (entry, parent) in record_entries(parser, &entry) {
+                let display_path = resolver.resolve(entry.record_number, &entry.name, parent).path;
+                push_entry(FileEntry::from_index(drive_letter, display_path, entry));
+            }
         }
-        guard += 1;
     }
-    components.reverse();
-    Ok(format!("{drive_letter}:\\{}", components.join("\\")))
+}
+
+/// Open the interactive picker over every indexed path and print the chosen ones, one per line
+pub fn pick_mft_files(drive_pattern: DriveLetterPattern, initial_query: String, excludes: ExcludeSet, only: Vec<FileFlag>, include_deleted: bool, under: Option<String>) -> eyre::Result<()> {
+    let include_deleted = include_deleted || only.contains(&FileFlag::Deleted);
+    let drives = drive_pattern.resolve()?;
+    let cache = get_cache_dir()?;
+    let sources: Vec<(char, PathBuf)> = drives
+        .iter()
+        .map(|d| (*d, cache.join(format!("{d}.mft"))))
+        .filter(|(_, p)| p.exists())
+        .collect();
+    if sources.is_empty() {
+        return Err(AppError::NoCachedDumps {
+            pattern: drive_pattern.to_string(),
+            hint: "Run mft sync first.",
+        }
+        .into());
+    }
+
+    let paths: Vec<String> = sources
+        .par_iter()
+        .map(|(drive_letter, mft_file)| -> eyre::Result<Vec<String>> {
+            let index = index_with_bar(mft_file)?;
+            Ok(index
+                .resolve_paths(*drive_letter)
+                .into_iter()
+                .filter(|(path, entry)| !excludes.is_excluded(path) && entry.flags.contains_all(&only))
+                .filter(|(_, entry)| include_deleted || !entry.flags.contains(FileFlag::Deleted))
+                .filter(|(path, _)| under.as_deref().is_none_or(|directory| is_under(path, directory)))
+                .map(|(path, _)| path)
+                .collect())
+        })
+        .collect::<eyre::Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect();
+
+    let chosen = Picker::new(paths, &initial_query).run()?;
+    for path in chosen {
+        println!("{path}");
+    }
+    Ok(())
+}
+
+/// Count and byte totals for one aggregation group
+#[derive(serde::Serialize)]
+struct AggregateRow {
+    key: String,
+    count: usize,
+    bytes: u64,
+    allocated_bytes: u64,
+}
+
+/// Group matches by extension or top-level directory, counting each hard-linked record once.
+/// Rows are sorted by bytes, largest first.
+fn aggregate_matches<'a>(entries: impl Iterator<Item = &'a FileEntry>, mode: QueryAggregate) -> Vec<AggregateRow> {
+    let mut seen = std::collections::HashSet::new();
+    let mut groups: HashMap<String, AggregateRow> = HashMap::new();
+    for entry in entries {
+        if !seen.insert((entry.drive_letter, entry.record_number)) { continue; }
+        let key = match mode {
+            QueryAggregate::Ext => match entry.filename.rsplit_once('.') {
+                Some((stem, ext)) if !stem.is_empty() => format!(".{}", ext.to_lowercase()),
+                _ => "(no extension)".to_string(),
+            },
+            QueryAggregate::Dir => {
+                let mut parts = entry.display_path.splitn(3, '\\');
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some(root), Some(top), Some(_)) => format!("{root}\\{top}"),
+                    (Some(root), _, _) => format!("{root}\\"),
+                    _ => entry.display_path.clone(),
+                }
+            }
+            QueryAggregate::None => unreachable!("aggregation requested without a mode"),
+        };
+        let row = groups.entry(key.clone()).or_insert_with(|| AggregateRow { key, count: 0, bytes: 0, allocated_bytes: 0 });
+        row.count += 1;
+        row.bytes += entry.size;
+        row.allocated_bytes += entry.allocated_size;
+    }
+    let mut rows: Vec<AggregateRow> = groups.into_values().collect();
+    rows.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.key.cmp(&b.key)));
+    rows
+}
+
+fn write_aggregate(rows: &[AggregateRow], mode: QueryAggregate, format: OutputFormat) -> eyre::Result<()> {
+    use std::io::Write;
+
+    let stdout = std::io::stdout();
+    let mut out = std::io::BufWriter::new(stdout.lock());
+    match format {
+        OutputFormat::Text => {
+            let header = if mode == QueryAggregate::Ext { "Extension" } else { "Directory" };
+            writeln!(out, "{header:<40} {:>12} {:>12} {:>12}", "Files", "Size", "Allocated")?;
+            for row in rows {
+                writeln!(
+                    out,
+                    "{:<40} {:>12} {:>12} {:>12}",
+                    row.key,
+                    row.count,
+                    humansize::format_size(row.bytes, humansize::DECIMAL),
+                    humansize::format_size(row.allocated_bytes, humansize::DECIMAL),
+                )?;
+            }
+        }
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, rows)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Jsonl => {
+            for row in rows {
+                serde_json::to_writer(&mut out, row)?;
+                writeln!(out)?;
+            }
+        }
+        OutputFormat::Csv => {
+            writeln!(out, "key,count,bytes,allocated_bytes")?;
+            for row in rows {
+                writeln!(out, "{},{},{},{}", csv_escape(&row.key), row.count, row.bytes, row.allocated_bytes)?;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Write matches to stdout in one of the machine-readable formats
+fn write_machine_readable(entries: &[&FileEntry], format: OutputFormat) -> eyre::Result<()> {
+    use std::io::Write;
+
+    let stdout = std::io::stdout();
+    let mut out = std::io::BufWriter::new(stdout.lock());
+    match format {
+        OutputFormat::Json => {
+            let records: Vec<QueryResultRecord> = entries.iter().map(|e| QueryResultRecord::from(*e)).collect();
+            serde_json::to_writer_pretty(&mut out, &records)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Jsonl => {
+            for entry in entries {
+                serde_json::to_writer(&mut out, &QueryResultRecord::from(*entry))?;
+                writeln!(out)?;
+            }
+        }
+        OutputFormat::Csv => {
+            writeln!(out, "path,size,allocated_size,created,modified,accessed,flags")?;
+            let fmt_time = |t: Option<DateTime<Utc>>| t.map(|t| t.to_rfc3339()).unwrap_or_default();
+            for entry in entries {
+                writeln!(
+                    out,
+                    "{},{},{},{},{},{},{}",
+                    csv_escape(&entry.display_path),
+                    entry.size,
+                    entry.allocated_size,
+                    fmt_time(entry.created),
+                    fmt_time(entry.modified),
+                    fmt_time(entry.accessed),
+                    csv_escape(&entry.flags.to_string()),
+                )?;
+            }
+        }
+        OutputFormat::Text => unreachable!("text output is handled by the caller"),
+    }
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FileEntry;
+    use super::aggregate_matches;
+    use super::finish_matching;
+    use crate::cli::mft_query_action::QueryAggregate;
+    use crate::file_flags::FileFlags;
+    use nucleo::Nucleo;
+    use std::sync::Arc;
+
+    #[test]
+    fn aggregates_every_match_once_matching_finishes() {
+        let mut matcher = Nucleo::new(nucleo::Config::DEFAULT, Arc::new(|| {}), None, 1);
+        matcher.pattern.reparse(
+            0,
+            "log",
+            nucleo::pattern::CaseMatching::Ignore,
+            nucleo::pattern::Normalization::Smart,
+            false,
+        );
+        let injector = matcher.injector();
+        let files = 50_000;
+        for record_number in 0..files {
+            let entry = FileEntry {
+                drive_letter: 'C',
+                record_number,
+                hard_link_count: 1,
+                filename: format!("{record_number}.log"),
+                parent_ref: None,
+                display_path: format!(r"C:\logs\{record_number}.log"),
+                created: None,
+                modified: None,
+                accessed: None,
+                size: 10,
+                allocated_size: 4096,
+                flags: FileFlags::default(),
+                reparse: None,
+            };
+            injector.push(entry, |e, cols| cols[0] = e.display_path.as_str().into());
+        }
+        finish_matching(&mut matcher);
+
+        let snapshot = matcher.snapshot();
+        let matched = snapshot.matched_item_count();
+        assert_eq!(matched as u64, files);
+        let rows = aggregate_matches(
+            snapshot.matched_items(0..matched).map(|item| item.data),
+            QueryAggregate::Ext,
+        );
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].key, ".log");
+        assert_eq!(rows[0].count as u64, files);
+        assert_eq!(rows[0].bytes, files * 10);
+    }
 }
@@ -0,0 +1,184 @@
+use crate::file_flags::FileFlag;
+use crate::file_flags::FileFlags;
+use crate::mft_size::record_data_size;
+use crate::reparse::reparse_point;
+use chrono::DateTime;
+use chrono::Utc;
+use mft::MftEntry;
+use mft::MftParser;
+use mft::attribute::MftAttributeContent;
+use mft::attribute::MftAttributeType;
+use mft::attribute::data_run::RunType;
+use mft::attribute::header::ResidentialHeader;
+use mft::attribute::x30::FileNamespace;
+use std::io::Read;
+use std::io::Seek;
+
+/// Every decoded attribute of one MFT record as label/value rows, for display
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RecordDetails {
+    pub record_number: u64,
+    pub rows: Vec<(String, String)>,
+}
+
+impl RecordDetails {
+    fn push(&mut self, label: impl Into<String>, value: impl Into<String>) {
+        self.rows.push((label.into(), value.into()));
+    }
+}
+
+fn format_time(time: &DateTime<Utc>) -> String {
+    time.format("%Y-%m-%d %H:%M:%S%.3f UTC").to_string()
+}
+
+/// Read one record and decode its paths, sizes, MACB timestamps, flags, data runs and streams
+pub fn describe_record<T: Read + Seek>(
+    parser: &mut MftParser<T>,
+    record_number: u64,
+    drive_letter: char,
+) -> eyre::Result<RecordDetails> {
+    let entry = parser
+        .get_entry(record_number)
+        .map_err(|e| eyre::eyre!("Failed to read record {record_number}: {e}"))?;
+    let mut details = RecordDetails {
+        record_number,
+        rows: Vec::new(),
+    };
+    details.push("Record", record_number.to_string());
+    details.push("Sequence", entry.header.sequence.to_string());
+    details.push("Hard links", entry.header.hard_link_count.to_string());
+    details.push("Kind", if entry.is_dir() { "directory" } else { "file" });
+
+    let mut std_flags = None;
+    for attribute in entry.iter_attributes() {
+        let Ok(attribute) = attribute else { continue };
+        match &attribute.data {
+            MftAttributeContent::AttrX10(info) => {
+                std_flags = Some(info.file_flags.bits());
+                details.push("Modified", format_time(&info.modified));
+                details.push("Accessed", format_time(&info.accessed));
+                details.push("Changed (MFT)", format_time(&info.mft_modified));
+                details.push("Born", format_time(&info.created));
+            }
+            MftAttributeContent::AttrX30(filename_attr) => {
+                let namespace = format!("{:?}", filename_attr.namespace);
+                if filename_attr.namespace == FileNamespace::DOS {
+                    details.push("Short name", filename_attr.name.clone());
+                    continue;
+                }
+                let path = resolve_path(
+                    parser,
+                    &filename_attr.name,
+                    filename_attr.parent.entry,
+                    drive_letter,
+                );
+                details.push(format!("Path ({namespace})"), path);
+                details.push("  $FN modified", format_time(&filename_attr.modified));
+                details.push("  $FN born", format_time(&filename_attr.created));
+            }
+            _ => {}
+        }
+    }
+    if let Some(bits) = std_flags {
+        let flags = FileFlags::new(bits, entry.is_dir());
+        details.push("Flags", format!("{flags} (0x{bits:08X})"));
+    }
+    if let Some(reparse) = reparse_point(&entry) {
+        details.push("Reparse point", format!("{reparse} (tag 0x{:08X})", reparse.tag));
+    }
+
+    let data = record_data_size(&entry, |record| parser.get_entry(record).ok());
+    if let Some(data) = data {
+        details.push("Size", format!("{} bytes", data.logical));
+    }
+    // Resident data has no clusters at all, so only sparse and compressed files are shown
+    let flags = FileFlags::new(std_flags.unwrap_or(0), entry.is_dir());
+    if (flags.contains(FileFlag::Sparse) || flags.contains(FileFlag::Compressed))
+        && let Some(data) = data
+    {
+        details.push(
+            "On disk",
+            format!("{} of {} bytes", data.allocated, data.logical),
+        );
+    }
+    describe_streams(&entry, &mut details);
+    Ok(details)
+}
+
+/// Sizes and data runs of every `$DATA` stream, the unnamed one first
+fn describe_streams(entry: &MftEntry, details: &mut RecordDetails) {
+    for attribute in entry.iter_attributes() {
+        let Ok(attribute) = attribute else { continue };
+        if attribute.header.type_code != MftAttributeType::DATA {
+            continue;
+        }
+        let stream = if attribute.header.name_size == 0 {
+            "$DATA".to_string()
+        } else {
+            format!("$DATA:{}", attribute.header.name)
+        };
+        match &attribute.header.residential_header {
+            ResidentialHeader::Resident(resident) => {
+                details.push(stream, format!("{} bytes, resident", resident.data_size));
+            }
+            ResidentialHeader::NonResident(non_resident) => {
+                if non_resident.vnc_first == 0 {
+                    details.push(
+                        stream,
+                        format!(
+                            "{} bytes, {} allocated",
+                            non_resident.file_size, non_resident.allocated_length
+                        ),
+                    );
+                }
+                if let MftAttributeContent::DataRun(runs) = &attribute.data {
+                    for run in &runs.data_runs {
+                        let value = match run.run_type {
+                            RunType::Sparse => format!("{} clusters sparse", run.lcn_length),
+                            RunType::Standard => {
+                                format!("{} clusters at LCN {}", run.lcn_length, run.lcn_offset)
+                            }
+                        };
+                        details.push("  run", value);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Walk parent references through the parser to build a full path for one name
+fn resolve_path<T: Read + Seek>(
+    parser: &mut MftParser<T>,
+    name: &str,
+    parent: u64,
+    drive_letter: char,
+) -> String {
+    let mut components = vec![name.to_string()];
+    let mut current = parent;
+    let mut guard = 0usize;
+    while current != 5 && current != 0 && guard < 4096 {
+        let Ok(parent_entry) = parser.get_entry(current) else {
+            break;
+        };
+        let parent_name = parent_entry.iter_attributes().find_map(|attribute| {
+            let attribute = attribute.ok()?;
+            match attribute.data {
+                MftAttributeContent::AttrX30(filename_attr)
+                    if filename_attr.namespace != FileNamespace::DOS =>
+                {
+                    Some((filename_attr.name, filename_attr.parent.entry))
+                }
+                _ => None,
+            }
+        });
+        let Some((parent_name, next)) = parent_name else {
+            break;
+        };
+        components.push(parent_name);
+        current = next;
+        guard += 1;
+    }
+    components.reverse();
+    format!("{drive_letter}:\\{}", components.join("\\"))
+}
@@ -1,3 +1,5 @@
+use crate::app_error::AppError;
+use crate::reparse::ReparsePolicy;
 use std::path::PathBuf;
 use tracing::info;
 
@@ -6,10 +8,10 @@ use tracing::info;
 pub fn show_mft_file(
     mft_file: PathBuf,
     _verbose: bool,
-    _show_paths: bool,
+    show_paths: bool,
     _max_entries: Option<usize>,
 ) -> eyre::Result<()> {
-    let app = crate::tui::app::MftShowApp::new(vec![mft_file]);
+    let app = crate::tui::app::MftShowApp::new(vec![mft_file]).with_show_paths(show_paths);
     app.run()
 }
 
@@ -17,9 +19,11 @@ pub fn show_mft_file(
 pub fn show_mft_files(
     pattern: &str,
     _verbose: bool,
-    _show_paths: bool,
+    show_paths: bool,
     _max_entries: Option<usize>,
     _threads: Option<usize>,
+    export_limit: usize,
+    reparse_policy: ReparsePolicy,
 ) -> eyre::Result<()> {
     let mft_files = expand_glob_pattern(pattern)?;
     info!(
@@ -30,7 +34,29 @@ pub fn show_mft_files(
     if mft_files.is_empty() {
         return Err(eyre::eyre!("At least one MFT file is required to proceed"));
     }
-    let app = crate::tui::app::MftShowApp::new(mft_files);
+    let app = crate::tui::app::MftShowApp::new(mft_files)
+        .with_export_limit(export_limit)
+        .with_reparse_policy(reparse_policy)
+        .with_show_paths(show_paths);
+    app.run()
+}
+
+/// Load two dumps into the TUI and open a tab with what changed from `old` to `new`
+pub fn compare_mft_files(
+    old: PathBuf,
+    new: PathBuf,
+    export_limit: usize,
+    reparse_policy: ReparsePolicy,
+) -> eyre::Result<()> {
+    for path in [&old, &new] {
+        if !path.is_file() {
+            return Err(AppError::DumpNotFound(path.clone()).into());
+        }
+    }
+    let app = crate::tui::app::MftShowApp::new(vec![old.clone(), new.clone()])
+        .with_export_limit(export_limit)
+        .with_reparse_policy(reparse_policy)
+        .with_compare(old, new);
     app.run()
 }
 
@@ -58,7 +84,7 @@ fn expand_glob_pattern(pattern: &str) -> eyre::Result<Vec<PathBuf>> {
         if path.is_file() {
             files.push(path);
         } else if !path.exists() {
-            return Err(eyre::eyre!("File not found: {}", pattern));
+            return Err(AppError::DumpNotFound(path).into());
         } else {
             return Err(eyre::eyre!("Path is not a file: {}", pattern));
         }
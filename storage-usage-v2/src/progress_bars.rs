@@ -0,0 +1,104 @@
+use crate::mft_index::MftIndex;
+use crate::mft_index::build_index_for_with_limit;
+use crate::progress::ProgressEvent;
+use indicatif::MultiProgress;
+use indicatif::ProgressBar;
+use indicatif::ProgressStyle;
+use std::io::IsTerminal;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+/// Whether progress bars are hidden; `--quiet` turns this on
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Every bar is drawn through this, so bars of drives synced in parallel stack
+static BARS: LazyLock<MultiProgress> = LazyLock::new(MultiProgress::new);
+
+/// Apply the global `--quiet` flag
+pub fn configure_progress_bars(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+fn bars_visible() -> bool {
+    !QUIET.load(Ordering::Relaxed) && std::io::stderr().is_terminal()
+}
+
+fn add_bar(total: u64, template: &str, prefix: String) -> ProgressBar {
+    if !bars_visible() {
+        return ProgressBar::hidden();
+    }
+    let style = ProgressStyle::with_template(template)
+        .unwrap_or_else(|_| ProgressStyle::default_bar())
+        .progress_chars("=> ");
+    BARS.add(ProgressBar::new(total).with_style(style).with_prefix(prefix))
+}
+
+/// A bar counting bytes with their rate and ETA, drawn on stderr when it is a terminal
+pub fn bytes_bar(total: u64, prefix: impl Into<String>) -> ProgressBar {
+    add_bar(
+        total,
+        "{prefix} [{bar:30}] {bytes}/{total_bytes} {bytes_per_sec} ETA {eta}",
+        prefix.into(),
+    )
+}
+
+/// A bar counting MFT records with their rate and ETA, drawn on stderr when it is a terminal
+pub fn records_bar(total: u64, prefix: impl Into<String>) -> ProgressBar {
+    add_bar(
+        total,
+        "{prefix} [{bar:30}] {human_pos}/{human_len} records {per_sec} ETA {eta}",
+        prefix.into(),
+    )
+}
+
+/// Prefix of the bar shown while a dump is indexed
+fn indexing_prefix(mft_file: &Path) -> String {
+    let name = mft_file.file_name().unwrap_or_default().to_string_lossy();
+    format!("Indexing {name}")
+}
+
+/// Advance a records bar on each record parsed
+fn records_progress(bar: &ProgressBar, event: ProgressEvent) {
+    if let ProgressEvent::RecordsParsed { parsed, total } = event {
+        bar.set_length(total);
+        bar.set_position(parsed);
+    }
+}
+
+/// [`MftIndex::from_dump`] behind a records bar
+pub fn index_with_bar(mft_file: &Path) -> eyre::Result<MftIndex> {
+    let bar = records_bar(0, indexing_prefix(mft_file));
+    let index = MftIndex::from_dump_with_progress(mft_file, &mut |event: ProgressEvent| {
+        records_progress(&bar, event)
+    });
+    bar.finish_and_clear();
+    index
+}
+
+/// [`crate::mft_index::build_index_for`] behind a records bar
+pub fn build_index_with_bar(mft_file: &Path, memory_limit: Option<usize>) -> eyre::Result<PathBuf> {
+    let bar = records_bar(0, indexing_prefix(mft_file));
+    let index_file =
+        build_index_for_with_limit(mft_file, memory_limit, &mut |event: ProgressEvent| {
+            records_progress(&bar, event)
+        });
+    bar.finish_and_clear();
+    index_file
+}
+
+/// Stdout for log lines, clearing the bars while a line is written so it doesn't land in them
+pub struct SuspendingStdout;
+
+impl Write for SuspendingStdout {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        BARS.suspend(|| std::io::stdout().write(buf))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::stdout().flush()
+    }
+}
@@ -0,0 +1,311 @@
+use crate::app_error::AppError;
+use crate::cli::drive_letter_pattern::DriveLetterPattern;
+use crate::config::get_cache_dir;
+use crate::file_flags::FileFlags;
+use crate::mft_index::MftIndex;
+use crate::mft_index::is_index_fresh;
+use crate::mft_index::load_fresh_index;
+use crate::progress_bars::build_index_with_bar;
+use crate::search_filter::SearchQuery;
+use crate::search_filter::fold_name;
+use crate::search_filter::nfc;
+use crate::win_service::free_space_snapshot;
+use chrono::DateTime;
+use chrono::Local;
+use chrono::Utc;
+use nucleo::Matcher;
+use nucleo::Utf32Str;
+use nucleo::pattern::CaseMatching;
+use nucleo::pattern::Normalization;
+use nucleo::pattern::Pattern;
+use rayon::prelude::*;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use tracing::info;
+use tracing::warn;
+
+/// Results returned by a query that doesn't ask for a limit
+pub const DEFAULT_QUERY_LIMIT: usize = 100;
+
+/// One name of a file in a cached index, as servers return it
+#[derive(Clone, Debug, Serialize)]
+pub struct IndexedFile {
+    pub path: String,
+    pub drive: char,
+    pub record_number: u64,
+    pub hard_link_count: u16,
+    pub size: u64,
+    pub allocated_size: u64,
+    pub created: Option<DateTime<Utc>>,
+    pub modified: Option<DateTime<Utc>>,
+    pub accessed: Option<DateTime<Utc>>,
+    pub flags: FileFlags,
+}
+
+/// A fuzzy search, with the same inline filters as the search tab (`ext:`, `size>`,
+/// `modified<`)
+#[derive(Clone, Debug, Deserialize)]
+pub struct QueryParams {
+    pub query: String,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// Drive letters to search, all loaded drives when absent
+    #[serde(default)]
+    pub drives: Option<String>,
+}
+
+/// A loaded drive, as listed by the `drives` method
+#[derive(Clone, Debug, Serialize)]
+pub struct DriveSummary {
+    pub drive: char,
+    pub dump: PathBuf,
+    pub files: usize,
+    pub dump_modified: Option<DateTime<Local>>,
+    pub loaded_at: DateTime<Local>,
+}
+
+/// Space on a loaded drive right now, as listed by the `stats` method
+#[derive(Clone, Debug, Serialize)]
+pub struct DriveStats {
+    pub drive: char,
+    pub files: usize,
+    pub total_bytes: u64,
+    pub free_bytes: u64,
+    pub used_bytes: u64,
+}
+
+/// Freshness of a drive's cached dump, as listed by the `sync_status` method
+#[derive(Clone, Debug, Serialize)]
+pub struct SyncStatus {
+    pub drive: char,
+    pub dump_modified: Option<DateTime<Local>>,
+    pub age_seconds: Option<i64>,
+    pub index_fresh: bool,
+    /// The dump was synced after it was loaded; `reload` picks it up
+    pub reload_needed: bool,
+}
+
+struct LoadedDrive {
+    summary: DriveSummary,
+    files: Vec<IndexedFile>,
+    /// Path folded with `fold_name` to positions in `files`, for exact lookups
+    by_path: HashMap<String, Vec<usize>>,
+}
+
+/// The cached indexes of some drives held in memory, so lookups don't reload them.
+///
+/// Shared by the `serve` transports; each method takes `&self` and may run on any thread.
+pub struct QueryServer {
+    drive_pattern: DriveLetterPattern,
+    drives: RwLock<Vec<LoadedDrive>>,
+}
+
+impl QueryServer {
+    /// Load the indexes of the cached dumps matching `drive_pattern`, building stale ones
+    pub fn load(drive_pattern: DriveLetterPattern) -> eyre::Result<Self> {
+        let drives = load_drives(&drive_pattern)?;
+        if drives.is_empty() {
+            return Err(AppError::NoCachedDumps {
+                pattern: drive_pattern.to_string(),
+                hint: "Run mft sync first.",
+            }
+            .into());
+        }
+        Ok(Self {
+            drive_pattern,
+            drives: RwLock::new(drives),
+        })
+    }
+
+    /// Load the indexes again, picking up dumps synced since
+    pub fn reload(&self) -> eyre::Result<Vec<DriveSummary>> {
+        let drives = load_drives(&self.drive_pattern)?;
+        *self.drives.write().unwrap() = drives;
+        Ok(self.drives())
+    }
+
+    pub fn drives(&self) -> Vec<DriveSummary> {
+        self.drives
+            .read()
+            .unwrap()
+            .iter()
+            .map(|drive| drive.summary.clone())
+            .collect()
+    }
+
+    /// Total, free and used bytes of every loaded drive
+    pub fn drive_stats(&self) -> eyre::Result<Vec<DriveStats>> {
+        self.drives()
+            .into_iter()
+            .map(|summary| {
+                let space = free_space_snapshot(summary.drive)?;
+                Ok(DriveStats {
+                    drive: summary.drive,
+                    files: summary.files,
+                    total_bytes: space.total_bytes,
+                    free_bytes: space.free_bytes,
+                    used_bytes: space.total_bytes.saturating_sub(space.free_bytes),
+                })
+            })
+            .collect()
+    }
+
+    /// How old the cached dump of each served drive is, read from disk rather than memory
+    pub fn sync_status(&self) -> eyre::Result<Vec<SyncStatus>> {
+        let cache = get_cache_dir()?;
+        let loaded = self.drives();
+        Ok(self
+            .drive_pattern
+            .resolve()?
+            .into_iter()
+            .map(|drive| {
+                let dump = cache.join(format!("{drive}.mft"));
+                let dump_modified = modified_time(&dump);
+                let loaded_modified = loaded
+                    .iter()
+                    .find(|summary| summary.drive == drive)
+                    .and_then(|summary| summary.dump_modified);
+                SyncStatus {
+                    drive,
+                    dump_modified,
+                    age_seconds: dump_modified.map(|time| (Local::now() - time).num_seconds()),
+                    index_fresh: is_index_fresh(&dump),
+                    reload_needed: dump_modified.is_some() && dump_modified != loaded_modified,
+                }
+            })
+            .collect())
+    }
+
+    /// Best fuzzy matches first, among the files passing every inline filter
+    pub fn query(&self, params: &QueryParams) -> eyre::Result<Vec<IndexedFile>> {
+        let wanted = match &params.drives {
+            Some(drives) => Some(drives.parse::<DriveLetterPattern>()?.resolve()?),
+            None => None,
+        };
+        let search = SearchQuery::parse(&params.query);
+        let pattern = Pattern::parse(
+            &nfc(&search.fuzzy),
+            CaseMatching::Ignore,
+            Normalization::Smart,
+        );
+        let drives = self.drives.read().unwrap();
+        let mut matches: Vec<(u32, &IndexedFile)> = drives
+            .par_iter()
+            .filter(|drive| {
+                wanted
+                    .as_ref()
+                    .is_none_or(|wanted| wanted.contains(&drive.summary.drive))
+            })
+            .flat_map(|drive| drive.files.par_iter())
+            .map_init(
+                || {
+                    (
+                        Matcher::new(nucleo::Config::DEFAULT.match_paths()),
+                        Vec::new(),
+                    )
+                },
+                |(matcher, buf), file| {
+                    if !search.matches(&file.path, file.size, file.modified) {
+                        return None;
+                    }
+                    let score = pattern.score(Utf32Str::new(&nfc(&file.path), buf), matcher)?;
+                    Some((score, file))
+                },
+            )
+            .flatten()
+            .collect();
+        // Ties keep path order, so the same query gives the same results
+        matches.par_sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.path.cmp(&b.1.path)));
+        Ok(matches
+            .into_iter()
+            .take(params.limit.unwrap_or(DEFAULT_QUERY_LIMIT))
+            .map(|(_, file)| file.clone())
+            .collect())
+    }
+
+    /// Every name matching a full path exactly, ignoring case as NTFS does
+    pub fn stat(&self, path: &str) -> Vec<IndexedFile> {
+        let key = fold_name(path.trim_end_matches('\\'));
+        let drives = self.drives.read().unwrap();
+        drives
+            .iter()
+            .filter_map(|drive| {
+                let positions = drive.by_path.get(&key)?;
+                Some(positions.iter().map(|&i| drive.files[i].clone()))
+            })
+            .flatten()
+            .collect()
+    }
+}
+
+fn load_drives(drive_pattern: &DriveLetterPattern) -> eyre::Result<Vec<LoadedDrive>> {
+    let cache = get_cache_dir()?;
+    let mut drives = Vec::new();
+    for drive in drive_pattern.resolve()? {
+        let dump = cache.join(format!("{drive}.mft"));
+        if !dump.exists() {
+            warn!(
+                "No cached dump of {drive}: at {}; skipping it",
+                dump.display()
+            );
+            continue;
+        }
+        let index = match load_fresh_index(&dump) {
+            Some(index) => index,
+            None => MftIndex::read_from_file(&build_index_with_bar(&dump, None)?)?,
+        };
+        drives.push(load_drive(drive, dump, &index));
+    }
+    info!(
+        "Loaded {} files from {} drives",
+        drives.iter().map(|d| d.files.len()).sum::<usize>(),
+        drives.len()
+    );
+    Ok(drives)
+}
+
+fn load_drive(drive: char, dump: PathBuf, index: &MftIndex) -> LoadedDrive {
+    let files: Vec<IndexedFile> = index
+        .resolve_paths(drive)
+        .into_iter()
+        .map(|(path, entry)| IndexedFile {
+            path,
+            drive,
+            record_number: entry.record_number,
+            hard_link_count: entry.hard_link_count,
+            size: entry.size,
+            allocated_size: entry.allocated_size,
+            created: entry.created,
+            modified: entry.modified,
+            accessed: entry.accessed,
+            flags: entry.flags,
+        })
+        .collect();
+    let mut by_path: HashMap<String, Vec<usize>> = HashMap::with_capacity(files.len());
+    for (i, file) in files.iter().enumerate() {
+        by_path.entry(fold_name(&file.path)).or_default().push(i);
+    }
+    let dump_modified = modified_time(&dump);
+    LoadedDrive {
+        summary: DriveSummary {
+            drive,
+            dump,
+            files: files.len(),
+            dump_modified,
+            loaded_at: Local::now(),
+        },
+        files,
+        by_path,
+    }
+}
+
+fn modified_time(path: &Path) -> Option<DateTime<Local>> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .map(DateTime::<Local>::from)
+}
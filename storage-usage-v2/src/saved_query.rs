@@ -0,0 +1,105 @@
+use crate::cli::drive_letter_pattern::DriveLetterPattern;
+use crate::cli::mft_query_action::MftQueryArgs;
+use crate::cli::mft_query_action::QueryAggregate;
+use crate::cli::output_format::OutputFormat;
+use crate::config::saved_queries_file_path;
+use crate::file_flags::FileFlag;
+use clap::ValueEnum;
+use eyre::Context;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// A named `mft query` stored in `saved-queries.json` in the config dir.
+///
+/// ```json
+/// {
+///   "big-videos": { "query": ".mp4", "drives": "CD", "only": [], "exclude": ["node_modules"], "format": "csv" }
+/// }
+/// ```
+///
+/// Every field is optional; values given on the command line win over saved ones.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SavedQuery {
+    pub query: Option<String>,
+    pub drives: Option<String>,
+    pub exclude: Vec<String>,
+    pub only: Vec<String>,
+    pub format: Option<String>,
+    pub aggregate: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// Load all saved queries, or an empty set when the file doesn't exist
+pub fn load_saved_queries() -> eyre::Result<BTreeMap<String, SavedQuery>> {
+    let path = saved_queries_file_path()?;
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let contents =
+        std::fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+    serde_json::from_str(&contents).with_context(|| format!("parsing {}", path.display()))
+}
+
+/// Look up a saved query by name
+pub fn find_saved_query(name: &str) -> eyre::Result<SavedQuery> {
+    let mut saved = load_saved_queries()?;
+    saved.remove(name).ok_or_else(|| {
+        let known: Vec<&str> = saved.keys().map(String::as_str).collect();
+        eyre::eyre!(
+            "No saved query named '{name}' in {}. Known: {}",
+            saved_queries_file_path()
+                .map(|p| p.display().to_string())
+                .unwrap_or_default(),
+            if known.is_empty() {
+                "<none>".to_string()
+            } else {
+                known.join(", ")
+            }
+        )
+    })
+}
+
+impl SavedQuery {
+    /// Fill in the parts of `args` that were left at their defaults
+    pub fn apply_to(&self, args: &mut MftQueryArgs) -> eyre::Result<()> {
+        if args.query.is_empty()
+            && let Some(query) = &self.query
+        {
+            args.query = query.clone();
+        }
+        if args.drive_pattern == DriveLetterPattern::default()
+            && let Some(drives) = &self.drives
+        {
+            args.drive_pattern = DriveLetterPattern::from_str(drives)?;
+        }
+        if args.format == OutputFormat::Text
+            && let Some(format) = &self.format
+        {
+            args.format = OutputFormat::from_str(format, true)
+                .map_err(|e| eyre::eyre!("invalid saved format '{format}': {e}"))?;
+        }
+        if args.aggregate == QueryAggregate::None
+            && let Some(aggregate) = &self.aggregate
+        {
+            args.aggregate = QueryAggregate::from_str(aggregate, true)
+                .map_err(|e| eyre::eyre!("invalid saved aggregate '{aggregate}': {e}"))?;
+        }
+        if args.limit == 100
+            && let Some(limit) = self.limit
+        {
+            args.limit = limit;
+        }
+        for flag in &self.only {
+            let flag = FileFlag::from_str(flag, true)
+                .map_err(|e| eyre::eyre!("invalid saved flag '{flag}': {e}"))?;
+            if !args.only.contains(&flag) {
+                args.only.push(flag);
+            }
+        }
+        args.exclude.extend(self.exclude.iter().cloned());
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+use crate::cli::output_format::OutputFormat;
+use crate::cli::output_format::csv_escape;
+use crate::file_flags::FileFlags;
+use chrono::DateTime;
+use chrono::Utc;
+use eyre::Context;
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+
+/// Default number of search results written by one export
+pub const DEFAULT_EXPORT_LIMIT: usize = 100_000;
+
+/// One search result as written to an export file
+#[derive(Debug, Serialize)]
+pub struct ExportedResult<'a> {
+    pub path: &'a str,
+    pub record_number: u64,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+    pub flags: FileFlags,
+}
+
+/// Export format picked from the file extension: `.json`, `.jsonl`, anything else is CSV
+pub fn export_format_for(path: &Path) -> OutputFormat {
+    match path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("json") => OutputFormat::Json,
+        Some("jsonl") => OutputFormat::Jsonl,
+        _ => OutputFormat::Csv,
+    }
+}
+
+/// Write search results to a file in the format implied by its extension
+pub fn write_search_results(path: &Path, results: &[ExportedResult]) -> eyre::Result<()> {
+    let file =
+        std::fs::File::create(path).with_context(|| format!("creating {}", path.display()))?;
+    let mut out = std::io::BufWriter::new(file);
+    match export_format_for(path) {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, results)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Jsonl => {
+            for result in results {
+                serde_json::to_writer(&mut out, result)?;
+                writeln!(out)?;
+            }
+        }
+        OutputFormat::Csv | OutputFormat::Text => {
+            writeln!(out, "path,record_number,size,modified,flags")?;
+            for result in results {
+                writeln!(
+                    out,
+                    "{},{},{},{},{}",
+                    csv_escape(result.path),
+                    result.record_number,
+                    result.size,
+                    result.modified.map(|t| t.to_rfc3339()).unwrap_or_default(),
+                    csv_escape(&result.flags.to_string()),
+                )?;
+            }
+        }
+    }
+    out.flush()
+        .with_context(|| format!("writing {}", path.display()))?;
+    Ok(())
+}
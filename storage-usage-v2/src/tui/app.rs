@@ -1,13 +1,32 @@
+use crate::progress::ProgressEvent;
+use crate::reparse::ReparsePolicy;
+use crate::search_export::DEFAULT_EXPORT_LIMIT;
+use crate::tui::entry_statuses::EntryStatuses;
+use crate::tui::log_buffer::set_capturing;
+use crate::tui::mainbound_message::MainboundMessage;
+use crate::tui::path_sample::PathSample;
 use crate::tui::progress::MftFileProgress;
+use crate::tui::resync::start_resync;
+use crate::tui::session::Session;
+use crate::tui::theme::theme;
+use crate::tui::widgets::tabs::app_tab::AppTab;
 use crate::tui::widgets::tabs::app_tabs::AppTabs;
+use crate::tui::widgets::tabs::compare_tab::CompareTab;
 use crate::tui::widgets::tabs::keyboard_response::KeyboardResponse;
+use crate::tui::worker::PauseFlag;
 use crate::tui::worker::start_workers;
 use ratatui::crossterm::event;
+use ratatui::crossterm::event::DisableMouseCapture;
+use ratatui::crossterm::event::EnableMouseCapture;
 use ratatui::crossterm::event::Event;
 use ratatui::crossterm::event::KeyCode;
 use ratatui::crossterm::event::KeyEventKind;
-use ratatui::style::Color;
+use ratatui::crossterm::execute;
+use std::collections::HashSet;
 use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::Ordering;
 use std::time::Duration;
 use std::time::Instant;
 use tachyonfx::Effect;
@@ -16,9 +35,32 @@ use tachyonfx::Interpolation;
 use tachyonfx::Motion;
 use tachyonfx::Shader;
 use tachyonfx::fx;
+use tracing::info_span;
 use uom::ConstZero;
 use uom::si::f64::Information;
 
+/// Frames drawn per second at most, unless `--max-fps` says otherwise
+pub const DEFAULT_MAX_FPS: u32 = 30;
+
+/// Whether the startup and quit animations play; `--no-effects` turns them off
+static EFFECTS_ENABLED: AtomicBool = AtomicBool::new(true);
+static MAX_FPS: AtomicU32 = AtomicU32::new(DEFAULT_MAX_FPS);
+
+/// Apply the global `--no-effects` and `--max-fps` flags to the TUIs
+pub fn configure_rendering(effects: bool, max_fps: Option<u32>) {
+    EFFECTS_ENABLED.store(effects, Ordering::Relaxed);
+    MAX_FPS.store(max_fps.unwrap_or(DEFAULT_MAX_FPS).max(1), Ordering::Relaxed);
+}
+
+/// Shortest time between two drawn frames
+pub fn frame_interval() -> Duration {
+    Duration::from_secs(1) / MAX_FPS.load(Ordering::Relaxed)
+}
+
+pub fn effects_enabled() -> bool {
+    EFFECTS_ENABLED.load(Ordering::Relaxed)
+}
+
 pub struct MftShowApp {
     pub mft_files: Vec<MftFileProgress>,
     pub processing_begin: Instant,
@@ -27,6 +69,14 @@ pub struct MftShowApp {
     pub quit_effect: Option<Effect>,
     pub last_frame_time: Instant,
     pub is_quitting: bool,
+    pub export_limit: usize,
+    /// How the treemap and largest directories tabs roll up symlinks and junctions
+    pub reparse_policy: ReparsePolicy,
+    /// MFT files being dumped and reloaded, until their processing completes
+    pub resyncing: HashSet<usize>,
+    /// Where the last session left off; its rows are selected once every file is processed
+    pub session: Session,
+    pub rows_restored: bool,
 }
 
 impl MftShowApp {
@@ -40,126 +90,239 @@ impl MftShowApp {
                 processed_size: Information::ZERO,
                 processing_end: None,
                 files_within: Vec::new(),
+                path_sample: PathSample::default(),
                 errors: Vec::new(),
-                entry_health_statuses: Vec::new(),
+                entry_health_statuses: EntryStatuses::default(),
+                dumped: None,
+                pause: PauseFlag::default(),
+                from_index: None,
             })
             .collect();
 
         // Create startup effect - sweep in with fade and gentle settle
-        let startup_effect = Some(fx::sweep_in(
-            Motion::LeftToRight,
-            15,
-            0,
-            Color::Black,
-            (1200, Interpolation::QuadOut),
-        ));
+        let startup_effect = effects_enabled().then(|| {
+            fx::sweep_in(
+                Motion::LeftToRight,
+                15,
+                0,
+                theme().background,
+                (1200, Interpolation::QuadOut),
+            )
+        });
 
         // Create quit effect - fade out with slide
-        let quit_effect = Some(fx::sequence(&[fx::parallel(&[
-            fx::fade_to_fg(Color::DarkGray, (800, Interpolation::SineIn)),
-            fx::slide_out(
-                Motion::RightToLeft,
-                20,
-                0,
-                Color::Black,
-                (1000, Interpolation::QuadIn),
-            ),
-        ])]));
+        let quit_effect = effects_enabled().then(|| {
+            fx::sequence(&[fx::parallel(&[
+                fx::fade_to_fg(theme().muted, (800, Interpolation::SineIn)),
+                fx::slide_out(
+                    Motion::RightToLeft,
+                    20,
+                    0,
+                    theme().background,
+                    (1000, Interpolation::QuadIn),
+                ),
+            ])])
+        });
+
+        let session = Session::load();
+        let mut tabs = AppTabs::new();
+        tabs.restore_session(&session);
 
         Self {
             mft_files,
             processing_begin: Instant::now(),
-            tabs: AppTabs::new(),
+            tabs,
             startup_effect,
             quit_effect,
             last_frame_time: Instant::now(),
             is_quitting: false,
+            export_limit: DEFAULT_EXPORT_LIMIT,
+            reparse_policy: ReparsePolicy::default(),
+            resyncing: HashSet::new(),
+            session,
+            rows_restored: false,
+        }
+    }
+
+    /// The current session, keeping the saved rows if they were never restored
+    fn current_session(&self) -> Session {
+        let mut session = self.tabs.session();
+        if !self.rows_restored {
+            session.selected_rows = self.session.selected_rows.clone();
         }
+        session
     }
+    /// Cap the number of results the search tab writes per export
+    pub fn with_export_limit(mut self, limit: usize) -> Self {
+        self.export_limit = limit;
+        self.apply_export_limit();
+        self
+    }
+
+    /// Roll up symlinks and junctions in the directory tabs as `policy` says
+    pub fn with_reparse_policy(mut self, policy: ReparsePolicy) -> Self {
+        self.reparse_policy = policy;
+        self.apply_reparse_policy();
+        self
+    }
+
+    /// Show a sample of each file's paths below the overview
+    pub fn with_show_paths(mut self, show_paths: bool) -> Self {
+        for tab in &mut self.tabs.tabs {
+            if let AppTab::Overview(overview) = tab {
+                overview.set_show_paths(show_paths);
+            }
+        }
+        self
+    }
+
+    /// Add a tab comparing two of the loaded dumps, opened at launch
+    pub fn with_compare(mut self, old: PathBuf, new: PathBuf) -> Self {
+        self.tabs.add_compare(CompareTab::new(old, new));
+        self
+    }
+
+    fn apply_export_limit(&mut self) {
+        for tab in &mut self.tabs.tabs {
+            if let AppTab::Search(search) = tab {
+                search.set_export_limit(self.export_limit);
+            }
+        }
+    }
+
+    fn apply_reparse_policy(&mut self) {
+        for tab in &mut self.tabs.tabs {
+            match tab {
+                AppTab::Treemap(treemap) => treemap.set_reparse_policy(self.reparse_policy),
+                AppTab::LargestDirectories(directories) => {
+                    directories.set_reparse_policy(self.reparse_policy)
+                }
+                _ => {}
+            }
+        }
+    }
+
     pub fn run(mut self) -> eyre::Result<()> {
-        let (rx, handle) = start_workers(
+        let (tx, rx, handle) = start_workers(
             self.mft_files
                 .iter()
-                .map(|progress| progress.path.clone())
+                .map(|progress| (progress.path.clone(), progress.pause.clone()))
                 .collect(),
         )?;
 
         let mut terminal = ratatui::init();
+        set_capturing(true);
+        execute!(std::io::stdout(), EnableMouseCapture)?;
         terminal.clear()?;
         let mut handle = Some(handle);
+        let frame_interval = frame_interval();
+        let mut drawn = false;
 
         loop {
-            // Calculate delta time for effects
-            let now = Instant::now();
-            let delta_time = now.duration_since(self.last_frame_time);
-            self.last_frame_time = now;
-
-            // Check if any effects are running
-            let any_effect_running = self.startup_effect.as_ref().is_some_and(|e| e.running())
-                || (self.is_quitting && self.quit_effect.as_ref().is_some_and(|e| e.running()));
-
-            // Use shorter timeout when effects are running for smoother animation
-            let poll_timeout = if any_effect_running {
-                Duration::from_millis(1)
-            } else {
-                Duration::from_millis(10)
-            };
-
             // process messages
             while let Ok(message) = rx.try_recv() {
+                match &message {
+                    // The tabs' per-file counters would be past the end of the cleared entries
+                    MainboundMessage::Reset { .. } => {
+                        self.session = self.current_session();
+                        self.rows_restored = false;
+                        self.tabs.reset();
+                        self.tabs.restore_session(&self.session);
+                        self.apply_export_limit();
+                        self.apply_reparse_policy();
+                    }
+                    MainboundMessage::Event {
+                        file_index,
+                        event: ProgressEvent::Finished,
+                    } => {
+                        self.resyncing.remove(file_index);
+                    }
+                    MainboundMessage::FileJobDone { tab, result } => {
+                        self.tabs.finish_file_job(*tab, result.clone());
+                    }
+                    _ => {}
+                }
                 message.handle(&mut self.mft_files)?;
             }
+            if !self.rows_restored
+                && self.mft_files.iter().all(|mft| mft.processing_end.is_some())
+            {
+                self.tabs.restore_rows(&self.session);
+                self.rows_restored = true;
+            }
 
-            terminal.draw(|frame| {
-                self.tabs.render(
-                    frame.area(),
-                    frame.buffer_mut(),
-                    &self.mft_files,
-                    self.processing_begin,
-                );
-
-                // Apply startup effect if it's running
-                if let Some(ref mut effect) = self.startup_effect {
-                    if effect.running() {
-                        frame.render_effect(effect, frame.area(), delta_time.into());
-                    } else {
-                        // Effect is done, remove it to save resources
-                        self.startup_effect = None;
+            // Frames are drawn at most once per interval; input is still read as it arrives
+            if !drawn || self.last_frame_time.elapsed() >= frame_interval {
+                drawn = true;
+
+                // Calculate delta time for effects
+                let now = Instant::now();
+                let delta_time = now.duration_since(self.last_frame_time);
+                self.last_frame_time = now;
+
+                let _frame = info_span!("frame").entered();
+                terminal.draw(|frame| {
+                    self.tabs.render(
+                        frame.area(),
+                        frame.buffer_mut(),
+                        &self.mft_files,
+                        self.processing_begin,
+                    );
+
+                    // Apply startup effect if it's running
+                    if let Some(ref mut effect) = self.startup_effect {
+                        if effect.running() {
+                            frame.render_effect(effect, frame.area(), delta_time.into());
+                        } else {
+                            // Effect is done, remove it to save resources
+                            self.startup_effect = None;
+                        }
                     }
-                }
 
-                // Apply quit effect if quitting
-                if self.is_quitting
-                    && let Some(ref mut effect) = self.quit_effect
-                {
-                    frame.render_effect(effect, frame.area(), delta_time.into());
+                    // Apply quit effect if quitting
+                    if self.is_quitting
+                        && let Some(ref mut effect) = self.quit_effect
+                    {
+                        frame.render_effect(effect, frame.area(), delta_time.into());
 
-                    // If quit effect is done, break the loop
-                    if !effect.running() {}
-                }
-            })?;
+                        // If quit effect is done, break the loop
+                        if !effect.running() {}
+                    }
+                })?;
 
-            // Break immediately if quit effect is done
-            if self.is_quitting && self.quit_effect.as_ref().is_none_or(|e| !e.running()) {
-                break;
+                // Break immediately if quit effect is done
+                if self.is_quitting && self.quit_effect.as_ref().is_none_or(|e| !e.running()) {
+                    break;
+                }
             }
 
-            if event::poll(poll_timeout)?
-                && let Event::Key(key) = event::read()?
-                && key.kind == KeyEventKind::Press
-            {
-                if key.code == KeyCode::Char('q') || key.code == KeyCode::Esc {
+            let poll_timeout = frame_interval.saturating_sub(self.last_frame_time.elapsed());
+            if event::poll(poll_timeout)? {
+                let key = match event::read()? {
+                    Event::Key(key) if key.kind == KeyEventKind::Press => key,
+                    Event::Mouse(mouse) => {
+                        if !self.is_quitting {
+                            self.tabs.on_mouse(mouse);
+                        }
+                        continue;
+                    }
+                    _ => continue,
+                };
+                if !self.tabs.show_help
+                    && !self.tabs.captures_input()
+                    && (key.code == KeyCode::Char('q') || key.code == KeyCode::Esc)
+                {
                     if !self.is_quitting {
                         self.is_quitting = true;
                         // Restart the quit effect
                         if let Some(ref mut effect) = self.quit_effect {
                             *effect = fx::sequence(&[fx::parallel(&[
-                                fx::fade_to_fg(Color::DarkGray, (800, Interpolation::SineIn)),
+                                fx::fade_to_fg(theme().muted, (800, Interpolation::SineIn)),
                                 fx::slide_out(
                                     Motion::RightToLeft,
                                     20,
                                     0,
-                                    Color::Black,
+                                    theme().background,
                                     (1000, Interpolation::QuadIn),
                                 ),
                             ])]);
@@ -175,10 +338,40 @@ impl MftShowApp {
                 {
                     // Key was handled by tabs
                 }
+                if let Some(file_index) = self.tabs.take_resync_request()
+                    && self.mft_files[file_index].processing_end.is_some()
+                    && self.resyncing.insert(file_index)
+                {
+                    start_resync(
+                        file_index,
+                        self.mft_files[file_index].path.clone(),
+                        self.mft_files[file_index].pause.clone(),
+                        tx.clone(),
+                    );
+                }
+                if let Some((tab, job)) = self.tabs.take_file_job() {
+                    let tx = tx.clone();
+                    std::thread::spawn(move || {
+                        let _ = tx.send(MainboundMessage::FileJobDone {
+                            tab,
+                            result: job.run(),
+                        });
+                    });
+                }
+                if let Some(file_index) = self.tabs.take_pause_request()
+                    && let Some(mft) = self.mft_files.get(file_index)
+                {
+                    mft.pause.toggle();
+                }
             }
         }
 
+        execute!(std::io::stdout(), DisableMouseCapture)?;
         ratatui::restore();
+        set_capturing(false);
+        if let Err(e) = self.current_session().save() {
+            tracing::warn!("Failed to save the TUI session: {e:#}");
+        }
         if let Some(handle) = handle.take() {
             handle
                 .join()
@@ -0,0 +1,117 @@
+use std::ops::Range;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Whether each record of an MFT parsed, one bit per record in record order, with the healthy
+/// count kept as bits are added so the visualizer's totals don't rescan millions of records
+#[derive(Clone, Debug, Default)]
+pub struct EntryStatuses {
+    words: Vec<u64>,
+    len: usize,
+    healthy: usize,
+}
+
+impl EntryStatuses {
+    pub fn push(&mut self, is_healthy: bool) {
+        let bit = self.len % WORD_BITS;
+        if bit == 0 {
+            self.words.push(0);
+        }
+        if is_healthy {
+            *self.words.last_mut().unwrap() |= 1 << bit;
+            self.healthy += 1;
+        }
+        self.len += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Whether the record at `index` parsed
+    pub fn get(&self, index: usize) -> bool {
+        index < self.len && self.words[index / WORD_BITS] & (1 << (index % WORD_BITS)) != 0
+    }
+
+    /// Records that parsed
+    pub fn healthy(&self) -> usize {
+        self.healthy
+    }
+
+    /// Records that didn't parse
+    pub fn unhealthy(&self) -> usize {
+        self.len - self.healthy
+    }
+
+    /// Records in `range` that parsed, counted a word at a time
+    pub fn count_healthy(&self, range: Range<usize>) -> usize {
+        let Range { start, end } = range.start..range.end.min(self.len);
+        if start >= end {
+            return 0;
+        }
+        let (first, last) = (start / WORD_BITS, (end - 1) / WORD_BITS);
+        // Bits at and above the start in the first word, and up to the end in the last
+        let low_mask = u64::MAX << (start % WORD_BITS);
+        let high_mask = u64::MAX >> (WORD_BITS - 1 - (end - 1) % WORD_BITS);
+        if first == last {
+            return (self.words[first] & low_mask & high_mask).count_ones() as usize;
+        }
+        let middle: u32 = self.words[first + 1..last]
+            .iter()
+            .map(|word| word.count_ones())
+            .sum();
+        ((self.words[first] & low_mask).count_ones()
+            + middle
+            + (self.words[last] & high_mask).count_ones()) as usize
+    }
+}
+
+impl Extend<bool> for EntryStatuses {
+    fn extend<I: IntoIterator<Item = bool>>(&mut self, statuses: I) {
+        for is_healthy in statuses {
+            self.push(is_healthy);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EntryStatuses;
+
+    #[test]
+    fn counts_healthy_records_across_words() {
+        let pattern = |index: usize| index % 3 != 0;
+        let mut statuses = EntryStatuses::default();
+        statuses.extend((0..200).map(pattern));
+        assert_eq!(statuses.len(), 200);
+        assert_eq!(statuses.healthy(), (0..200).filter(|&i| pattern(i)).count());
+        assert_eq!(statuses.unhealthy(), 200 - statuses.healthy());
+        for (start, end) in [
+            (0, 200),
+            (5, 9),
+            (60, 70),
+            (63, 64),
+            (64, 128),
+            (1, 199),
+            (150, 400),
+        ] {
+            let expected = (start..end.min(200)).filter(|&i| pattern(i)).count();
+            assert_eq!(
+                statuses.count_healthy(start..end),
+                expected,
+                "{start}..{end}"
+            );
+        }
+        assert_eq!(statuses.count_healthy(10..10), 0);
+        assert!((0..200).all(|i| statuses.get(i) == pattern(i)));
+        assert!(!statuses.get(200));
+    }
+}
@@ -0,0 +1,109 @@
+use chrono::DateTime;
+use chrono::Local;
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::fmt::Write;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use tracing::Event;
+use tracing::Level;
+use tracing::Subscriber;
+use tracing::field::Field;
+use tracing::field::Visit;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::layer::Layer;
+
+/// How many records are kept; the oldest are dropped first
+const CAPACITY: usize = 10_000;
+
+static RECORDS: LazyLock<Mutex<VecDeque<LogRecord>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::with_capacity(CAPACITY)));
+
+/// Warnings and errors kept in the buffer, for the log tab's badge
+static WARNING_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Set while the TUI owns the terminal, so log lines aren't printed over it
+static CAPTURING: AtomicBool = AtomicBool::new(false);
+
+/// A tracing event kept for the TUI's log tab
+#[derive(Clone, Debug)]
+pub struct LogRecord {
+    pub time: DateTime<Local>,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+/// Tracing layer feeding every event it sees into the log buffer
+pub struct LogBufferLayer;
+
+impl<S: Subscriber> Layer<S> for LogBufferLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        push(LogRecord {
+            time: Local::now(),
+            level: *event.metadata().level(),
+            target: event.metadata().target().to_string(),
+            message: visitor.0,
+        });
+    }
+}
+
+/// Formats the `message` field followed by every other field as `name=value`
+#[derive(Default)]
+pub(crate) struct MessageVisitor(pub(crate) String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, "{value:?}");
+        } else {
+            let _ = write!(self.0, " {}={value:?}", field.name());
+        }
+    }
+}
+
+fn is_warning(level: &Level) -> bool {
+    *level <= Level::WARN
+}
+
+fn push(record: LogRecord) {
+    let mut records = RECORDS.lock().unwrap();
+    if records.len() == CAPACITY
+        && let Some(dropped) = records.pop_front()
+        && is_warning(&dropped.level)
+    {
+        WARNING_COUNT.fetch_sub(1, Ordering::Relaxed);
+    }
+    if is_warning(&record.level) {
+        WARNING_COUNT.fetch_add(1, Ordering::Relaxed);
+    }
+    records.push_back(record);
+}
+
+/// Run `f` over the buffered records, oldest first
+pub fn with_records<T>(f: impl FnOnce(&VecDeque<LogRecord>) -> T) -> T {
+    f(&RECORDS.lock().unwrap())
+}
+
+pub fn warning_count() -> usize {
+    WARNING_COUNT.load(Ordering::Relaxed)
+}
+
+pub fn clear() {
+    RECORDS.lock().unwrap().clear();
+    WARNING_COUNT.store(0, Ordering::Relaxed);
+}
+
+/// Whether log lines go to the buffer only, instead of also being printed
+pub fn capturing() -> bool {
+    CAPTURING.load(Ordering::Relaxed)
+}
+
+pub fn set_capturing(capturing: bool) {
+    CAPTURING.store(capturing, Ordering::Relaxed);
+}
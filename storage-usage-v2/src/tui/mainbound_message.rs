@@ -1,8 +1,14 @@
+use crate::progress::ProgressEvent;
+use crate::tui::progress::DiscoveredFile;
+use crate::tui::progress::MftError;
 use crate::tui::progress::MftFileProgress;
-use ratatui::text::Line;
-use std::path::PathBuf;
+use crate::tui::widgets::tabs::file_actions::FileJobResult;
+use std::sync::mpsc::Sender;
+use std::time::Duration;
 use std::time::Instant;
+use uom::ConstZero;
 use uom::si::f64::Information;
+use uom::si::information::byte;
 
 #[derive(Debug, Clone)]
 pub enum MainboundMessage {
@@ -10,28 +16,39 @@ pub enum MainboundMessage {
         file_index: usize,
         file_size: Information,
     },
-    EntrySizeDiscovered {
-        file_index: usize,
-        entry_size: Information,
-    },
     Progress {
         file_index: usize,
         processed_size: Information,
     },
     DiscoveredFiles {
         file_index: usize,
-        files: Vec<PathBuf>,
+        files: Vec<DiscoveredFile>,
     },
-    EntryStatus {
+    /// Whether each of the next records parsed, in record order
+    EntryStatuses {
         file_index: usize,
-        is_healthy: bool,
+        is_healthy: Vec<bool>,
+    },
+    /// The file's paths came from a fresh index rather than a parse, which records how many
+    /// records failed to parse but not which
+    LoadedFromIndex {
+        file_index: usize,
+        parse_errors: u64,
+    },
+    /// A delete step finished on its own thread, for the tab at `tab` that started it
+    FileJobDone {
+        tab: usize,
+        result: FileJobResult,
     },
-    Complete {
+    /// The file was replaced on disk and is about to be processed again from scratch
+    Reset {
         file_index: usize,
     },
-    Error {
+    /// Progress or a problem reported while the file is dumped or parsed; `Finished` once it
+    /// is done, whether or not that went well
+    Event {
         file_index: usize,
-        error: Line<'static>,
+        event: ProgressEvent,
     },
 }
 impl MainboundMessage {
@@ -43,12 +60,6 @@ impl MainboundMessage {
             } => {
                 mft_files[file_index].total_size = Some(file_size);
             }
-            MainboundMessage::EntrySizeDiscovered {
-                file_index,
-                entry_size,
-            } => {
-                mft_files[file_index].entry_size = Some(entry_size);
-            }
             MainboundMessage::Progress {
                 file_index,
                 processed_size,
@@ -56,24 +67,142 @@ impl MainboundMessage {
                 let progress = &mut mft_files[file_index];
                 progress.processed_size += processed_size;
             }
-            MainboundMessage::Complete { file_index } => {
-                mft_files[file_index].processing_end = Some(Instant::now());
+            MainboundMessage::Reset { file_index } => {
+                let progress = &mut mft_files[file_index];
+                progress.total_size = None;
+                progress.entry_size = None;
+                progress.processed_size = Information::ZERO;
+                progress.processing_end = None;
+                progress.files_within.clear();
+                progress.path_sample.clear();
+                progress.entry_health_statuses.clear();
+                progress.errors.clear();
+                progress.dumped = None;
+                progress.pause.clear();
+                progress.from_index = None;
             }
-            MainboundMessage::Error { file_index, error } => {
-                mft_files[file_index].errors.push(error);
+            MainboundMessage::LoadedFromIndex {
+                file_index,
+                parse_errors,
+            } => {
+                mft_files[file_index].from_index = Some(parse_errors);
+            }
+            // Handed to the tab by the app, as it's no file's progress
+            MainboundMessage::FileJobDone { .. } => {}
+            MainboundMessage::Event { file_index, event } => {
+                let progress = &mut mft_files[file_index];
+                match event {
+                    ProgressEvent::MftRead { read, total } => {
+                        progress.dumped = Some((
+                            Information::new::<byte>(read as f64),
+                            Information::new::<byte>(total as f64),
+                        ));
+                    }
+                    ProgressEvent::RecordSize { bytes } => {
+                        progress.entry_size = Some(Information::new::<byte>(bytes as f64));
+                    }
+                    ProgressEvent::RecordsParsed { parsed, .. } => {
+                        if let Some(entry_size) = progress.entry_size {
+                            progress.processed_size = entry_size * parsed as f64;
+                        }
+                    }
+                    ProgressEvent::Problem(error) => progress.errors.push(error),
+                    ProgressEvent::Finished => progress.processing_end = Some(Instant::now()),
+                }
             }
             MainboundMessage::DiscoveredFiles { file_index, files } => {
                 let progress = &mut mft_files[file_index];
+                progress.path_sample.extend(&files);
                 progress.files_within.extend(files);
             }
-            MainboundMessage::EntryStatus {
+            MainboundMessage::EntryStatuses {
                 file_index,
                 is_healthy,
             } => {
                 let progress = &mut mft_files[file_index];
-                progress.entry_health_statuses.push(is_healthy);
+                progress.entry_health_statuses.extend(is_healthy);
             }
         }
         Ok(())
     }
 }
+
+/// Records a [`MainboundBatch`] holds at most before sending them on
+const BATCH_RECORDS: usize = 8192;
+/// Longest a [`MainboundBatch`] holds records, so progress keeps moving on slow parses
+const BATCH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Gathers what a worker reports per record into a few messages, sent every [`BATCH_RECORDS`]
+/// records or [`BATCH_INTERVAL`], so the UI loop handles a handful of messages per frame
+/// rather than several per record. Call [`MainboundBatch::flush`] before reporting
+/// `Finished`.
+pub struct MainboundBatch<'a> {
+    tx: &'a Sender<MainboundMessage>,
+    file_index: usize,
+    is_healthy: Vec<bool>,
+    files: Vec<DiscoveredFile>,
+    /// The latest `RecordsParsed` count, as only the last one matters
+    parsed: Option<(u64, u64)>,
+    last_flush: Instant,
+}
+
+impl<'a> MainboundBatch<'a> {
+    pub fn new(tx: &'a Sender<MainboundMessage>, file_index: usize) -> Self {
+        Self {
+            tx,
+            file_index,
+            is_healthy: Vec::new(),
+            files: Vec::new(),
+            parsed: None,
+            last_flush: Instant::now(),
+        }
+    }
+
+    pub fn add_files(&mut self, files: impl IntoIterator<Item = DiscoveredFile>) {
+        self.files.extend(files);
+    }
+
+    /// Send a problem right away; they're few, and the errors tab shouldn't wait on a batch
+    pub fn problem(&self, error: MftError) -> eyre::Result<()> {
+        self.tx.send(MainboundMessage::Event {
+            file_index: self.file_index,
+            event: ProgressEvent::Problem(error),
+        })?;
+        Ok(())
+    }
+
+    /// Note that a record was parsed, or failed to, sending the batch when it's due
+    pub fn record_parsed(&mut self, is_healthy: bool, parsed: u64, total: u64) -> eyre::Result<()> {
+        self.is_healthy.push(is_healthy);
+        self.parsed = Some((parsed, total));
+        if self.is_healthy.len() >= BATCH_RECORDS || self.last_flush.elapsed() >= BATCH_INTERVAL {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Send whatever has been gathered
+    pub fn flush(&mut self) -> eyre::Result<()> {
+        let file_index = self.file_index;
+        if !self.is_healthy.is_empty() {
+            self.tx.send(MainboundMessage::EntryStatuses {
+                file_index,
+                is_healthy: std::mem::take(&mut self.is_healthy),
+            })?;
+        }
+        if !self.files.is_empty() {
+            self.tx.send(MainboundMessage::DiscoveredFiles {
+                file_index,
+                files: std::mem::take(&mut self.files),
+            })?;
+        }
+        if let Some((parsed, total)) = self.parsed.take() {
+            self.tx.send(MainboundMessage::Event {
+                file_index,
+                event: ProgressEvent::RecordsParsed { parsed, total },
+            })?;
+        }
+        self.last_flush = Instant::now();
+        Ok(())
+    }
+}
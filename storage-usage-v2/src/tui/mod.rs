@@ -1,5 +1,12 @@
 pub mod app;
+pub mod entry_statuses;
+pub mod log_buffer;
 pub mod mainbound_message;
+pub mod path_sample;
+pub mod picker;
 pub mod progress;
+pub mod resync;
+pub mod session;
+pub mod theme;
 pub mod widgets;
 pub mod worker;
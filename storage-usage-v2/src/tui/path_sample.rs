@@ -0,0 +1,189 @@
+use crate::file_flags::FileFlag;
+use crate::tui::progress::DiscoveredFile;
+use std::hash::BuildHasher;
+use std::hash::RandomState;
+use std::path::PathBuf;
+
+/// Paths a [`PathSample`] keeps
+pub const SAMPLED_PATHS: usize = 20;
+
+/// What kind of path a discovered file is, for the counts beside the sample
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PathCategory {
+    File,
+    Directory,
+    /// Symlinks, junctions and other reparse points
+    Link,
+    /// NTFS metadata files such as `$MFT` and `$Extend\$UsnJrnl`
+    Metadata,
+    Deleted,
+}
+
+impl PathCategory {
+    pub const ALL: [PathCategory; 5] = [
+        PathCategory::File,
+        PathCategory::Directory,
+        PathCategory::Link,
+        PathCategory::Metadata,
+        PathCategory::Deleted,
+    ];
+
+    pub fn of(file: &DiscoveredFile) -> Self {
+        let is_metadata = file
+            .path
+            .to_string_lossy()
+            .split(['\\', '/'])
+            .any(|name| name.starts_with('$'));
+        if file.flags.contains(FileFlag::Deleted) {
+            PathCategory::Deleted
+        } else if is_metadata {
+            PathCategory::Metadata
+        } else if file.flags.contains(FileFlag::ReparsePoint) {
+            PathCategory::Link
+        } else if file.flags.contains(FileFlag::Directory) {
+            PathCategory::Directory
+        } else {
+            PathCategory::File
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PathCategory::File => "files",
+            PathCategory::Directory => "directories",
+            PathCategory::Link => "links",
+            PathCategory::Metadata => "metadata",
+            PathCategory::Deleted => "deleted",
+        }
+    }
+}
+
+/// A uniform sample of the paths discovered in an MFT, kept by reservoir sampling so it covers
+/// the whole volume rather than the metadata files at its first records, with a count per
+/// [`PathCategory`]. Memory stays at [`SAMPLED_PATHS`] paths however many are seen
+#[derive(Clone, Debug)]
+pub struct PathSample {
+    paths: Vec<PathBuf>,
+    seen: u64,
+    counts: [u64; PathCategory::ALL.len()],
+    /// xorshift64 state picking which seen path replaces a kept one
+    state: u64,
+}
+
+impl Default for PathSample {
+    fn default() -> Self {
+        // Never zero, which xorshift would stay at
+        Self::with_seed(RandomState::new().hash_one(0u64) | 1)
+    }
+}
+
+impl PathSample {
+    fn with_seed(seed: u64) -> Self {
+        Self {
+            paths: Vec::with_capacity(SAMPLED_PATHS),
+            seen: 0,
+            counts: [0; PathCategory::ALL.len()],
+            state: seed,
+        }
+    }
+
+    pub fn add(&mut self, file: &DiscoveredFile) {
+        let category = PathCategory::of(file);
+        self.counts[category as usize] += 1;
+        self.seen += 1;
+        if self.paths.len() < SAMPLED_PATHS {
+            self.paths.push(file.path.clone());
+            return;
+        }
+        // Keep the path with probability SAMPLED_PATHS / seen, in place of a random kept one
+        let slot = self.next_random() % self.seen;
+        if let Some(kept) = self.paths.get_mut(slot as usize) {
+            *kept = file.path.clone();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.paths.clear();
+        self.seen = 0;
+        self.counts = [0; PathCategory::ALL.len()];
+    }
+
+    /// The sampled paths, in no particular order
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Paths seen in total
+    pub fn seen(&self) -> u64 {
+        self.seen
+    }
+
+    pub fn count(&self, category: PathCategory) -> u64 {
+        self.counts[category as usize]
+    }
+
+    fn next_random(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+}
+
+impl<'a> Extend<&'a DiscoveredFile> for PathSample {
+    fn extend<I: IntoIterator<Item = &'a DiscoveredFile>>(&mut self, files: I) {
+        for file in files {
+            self.add(file);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PathCategory;
+    use super::PathSample;
+    use super::SAMPLED_PATHS;
+    use crate::file_flags::FileFlags;
+    use crate::tui::progress::DiscoveredFile;
+    use std::path::PathBuf;
+
+    fn file(path: &str) -> DiscoveredFile {
+        DiscoveredFile {
+            path: PathBuf::from(path),
+            record_number: 0,
+            hard_link_count: 1,
+            flags: FileFlags::default(),
+            size: 0,
+            allocated_size: 0,
+            modified: None,
+            reparse: None,
+        }
+    }
+
+    #[test]
+    fn samples_past_the_metadata_files() {
+        let metadata: Vec<_> = (0..SAMPLED_PATHS)
+            .map(|i| file(&format!("C:\\$Meta{i}")))
+            .collect();
+        let users: Vec<_> = (0..10_000)
+            .map(|i| file(&format!("C:\\Users\\me\\{i}.txt")))
+            .collect();
+        let mut sample = PathSample::with_seed(0x9e37_79b9_7f4a_7c15);
+        sample.extend(metadata.iter().chain(&users));
+
+        assert_eq!(sample.seen(), (SAMPLED_PATHS + 10_000) as u64);
+        assert_eq!(sample.paths().len(), SAMPLED_PATHS);
+        assert_eq!(sample.count(PathCategory::Metadata), SAMPLED_PATHS as u64);
+        assert_eq!(sample.count(PathCategory::File), 10_000);
+        let kept_metadata = sample
+            .paths()
+            .iter()
+            .filter(|path| path.to_string_lossy().contains('$'))
+            .count();
+        assert!(kept_metadata < SAMPLED_PATHS / 2, "{:?}", sample.paths());
+
+        sample.clear();
+        assert_eq!(sample.seen(), 0);
+        assert!(sample.paths().is_empty());
+    }
+}
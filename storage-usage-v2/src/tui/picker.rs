@@ -0,0 +1,210 @@
+use crate::search_filter::nfc;
+use crate::tui::app::frame_interval;
+use crate::tui::theme::theme;
+use nucleo::Nucleo;
+use ratatui::Terminal;
+use ratatui::backend::CrosstermBackend;
+use ratatui::crossterm::event;
+use ratatui::crossterm::event::Event;
+use ratatui::crossterm::event::KeyCode;
+use ratatui::crossterm::event::KeyEventKind;
+use ratatui::crossterm::event::KeyModifiers;
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::EnterAlternateScreen;
+use ratatui::crossterm::terminal::LeaveAlternateScreen;
+use ratatui::crossterm::terminal::disable_raw_mode;
+use ratatui::crossterm::terminal::enable_raw_mode;
+use ratatui::layout::Constraint;
+use ratatui::layout::Layout;
+use ratatui::style::Style;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::List;
+use ratatui::widgets::ListItem;
+use ratatui::widgets::Paragraph;
+use std::collections::BTreeSet;
+use std::io::Stderr;
+use std::sync::Arc;
+
+/// Minimal fzf-like picker.
+///
+/// The UI is drawn on stderr so stdout stays free for the selected paths.
+/// Tab toggles multi-selection, Enter accepts, Esc cancels.
+pub struct Picker {
+    query: String,
+    selected_index: usize,
+    scroll_offset: usize,
+    marked: BTreeSet<u32>,
+    matcher: Nucleo<String>,
+}
+
+impl Picker {
+    pub fn new(items: impl IntoIterator<Item = String>, initial_query: &str) -> Self {
+        let matcher = Nucleo::new(
+            nucleo::Config::DEFAULT.match_paths(),
+            Arc::new(|| {}),
+            None,
+            1,
+        );
+        let injector = matcher.injector();
+        for item in items {
+            injector.push(item, |s, cols| cols[0] = nfc(s).as_ref().into());
+        }
+        let mut picker = Self {
+            query: initial_query.to_string(),
+            selected_index: 0,
+            scroll_offset: 0,
+            marked: BTreeSet::new(),
+            matcher,
+        };
+        picker.update_pattern();
+        picker
+    }
+
+    fn update_pattern(&mut self) {
+        self.matcher.pattern.reparse(
+            0,
+            &nfc(&self.query),
+            nucleo::pattern::CaseMatching::Ignore,
+            nucleo::pattern::Normalization::Smart,
+            false,
+        );
+        self.selected_index = 0;
+        self.scroll_offset = 0;
+        self.marked.clear();
+    }
+
+    /// Run the picker until the user accepts or cancels. Returns the chosen items (empty on cancel).
+    pub fn run(mut self) -> eyre::Result<Vec<String>> {
+        enable_raw_mode()?;
+        let mut stderr = std::io::stderr();
+        execute!(stderr, EnterAlternateScreen)?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(stderr))?;
+        let result = self.event_loop(&mut terminal);
+        disable_raw_mode()?;
+        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+        result
+    }
+
+    fn event_loop(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<Stderr>>,
+    ) -> eyre::Result<Vec<String>> {
+        let mut visible_height = 1usize;
+        loop {
+            self.matcher.tick(10);
+            terminal.draw(|frame| {
+                let [input_area, list_area] =
+                    Layout::vertical([Constraint::Length(1), Constraint::Min(0)])
+                        .areas(frame.area());
+                visible_height = list_area.height.max(1) as usize;
+                let snapshot = self.matcher.snapshot();
+                let matched = snapshot.matched_item_count() as usize;
+                let header = format!(
+                    "> {}  ({matched}/{}, {} marked)",
+                    self.query,
+                    snapshot.item_count(),
+                    self.marked.len()
+                );
+                frame.render_widget(Paragraph::new(header), input_area);
+
+                if self.selected_index >= self.scroll_offset + visible_height {
+                    self.scroll_offset = self.selected_index + 1 - visible_height;
+                }
+                let end = (self.scroll_offset + visible_height).min(matched);
+                let items: Vec<ListItem> = snapshot
+                    .matched_items(self.scroll_offset.min(end) as u32..end as u32)
+                    .enumerate()
+                    .map(|(i, item)| {
+                        let idx = self.scroll_offset + i;
+                        let mark = if self.marked.contains(&(idx as u32)) {
+                            "● "
+                        } else {
+                            "  "
+                        };
+                        let style = if idx == self.selected_index {
+                            theme().selection()
+                        } else {
+                            Style::default()
+                        };
+                        ListItem::new(Line::from(Span::styled(
+                            format!("{mark}{}", item.data),
+                            style,
+                        )))
+                    })
+                    .collect();
+                frame.render_widget(List::new(items), list_area);
+            })?;
+
+            if !event::poll(frame_interval())? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let matched = self.matcher.snapshot().matched_item_count() as usize;
+            match key.code {
+                KeyCode::Esc => return Ok(Vec::new()),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(Vec::new());
+                }
+                KeyCode::Enter => return Ok(self.chosen()),
+                KeyCode::Tab => {
+                    if matched > 0 {
+                        let idx = self.selected_index as u32;
+                        if !self.marked.remove(&idx) {
+                            self.marked.insert(idx);
+                        }
+                        self.selected_index = (self.selected_index + 1).min(matched - 1);
+                    }
+                }
+                KeyCode::Up => {
+                    self.selected_index = self.selected_index.saturating_sub(1);
+                    self.scroll_offset = self.scroll_offset.min(self.selected_index);
+                }
+                KeyCode::Down => {
+                    if matched > 0 {
+                        self.selected_index = (self.selected_index + 1).min(matched - 1);
+                    }
+                }
+                KeyCode::PageUp => {
+                    self.selected_index = self.selected_index.saturating_sub(visible_height);
+                    self.scroll_offset = self.scroll_offset.min(self.selected_index);
+                }
+                KeyCode::PageDown => {
+                    if matched > 0 {
+                        self.selected_index =
+                            (self.selected_index + visible_height).min(matched - 1);
+                    }
+                }
+                KeyCode::Backspace => {
+                    self.query.pop();
+                    self.update_pattern();
+                }
+                KeyCode::Char(c) => {
+                    self.query.push(c);
+                    self.update_pattern();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn chosen(&self) -> Vec<String> {
+        let snapshot = self.matcher.snapshot();
+        if self.marked.is_empty() {
+            return snapshot
+                .get_matched_item(self.selected_index as u32)
+                .map(|item| vec![item.data.clone()])
+                .unwrap_or_default();
+        }
+        self.marked
+            .iter()
+            .filter_map(|idx| snapshot.get_matched_item(*idx))
+            .map(|item| item.data.clone())
+            .collect()
+    }
+}
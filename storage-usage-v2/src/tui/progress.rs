@@ -1,15 +1,50 @@
-use ratatui::text::Line;
+use crate::file_flags::FileFlags;
+use crate::reparse::ReparsePoint;
+use crate::tui::entry_statuses::EntryStatuses;
+use crate::tui::path_sample::PathSample;
+use crate::tui::worker::PauseFlag;
+use chrono::DateTime;
+use chrono::Utc;
 use std::path::PathBuf;
 use std::time::Instant;
 use uom::si::f64::Information;
 
+pub use storage_usage_core::progress::ErrorSeverity;
+pub use storage_usage_core::progress::MftError;
+
+/// A path discovered while processing an MFT, with its attribute flags and size
+#[derive(Debug, Clone)]
+pub struct DiscoveredFile {
+    pub path: PathBuf,
+    pub record_number: u64,
+    /// Number of names the record has; each hard link is discovered as its own path
+    pub hard_link_count: u16,
+    pub flags: FileFlags,
+    /// Logical size of the unnamed `$DATA` stream in bytes
+    pub size: u64,
+    /// Bytes its clusters take on disk, less than `size` for sparse and compressed files
+    pub allocated_size: u64,
+    /// Last modified time from `$STANDARD_INFORMATION`
+    pub modified: Option<DateTime<Utc>>,
+    pub reparse: Option<ReparsePoint>,
+}
+
 pub struct MftFileProgress {
     pub path: PathBuf,
     pub total_size: Option<Information>,
     pub entry_size: Option<Information>,
     pub processed_size: Information,
     pub processing_end: Option<Instant>,
-    pub files_within: Vec<PathBuf>,
-    pub entry_health_statuses: Vec<bool>,
-    pub errors: Vec<Line<'static>>,
+    pub files_within: Vec<DiscoveredFile>,
+    /// Representative paths among `files_within`, with counts by kind
+    pub path_sample: PathSample,
+    pub entry_health_statuses: EntryStatuses,
+    pub errors: Vec<MftError>,
+    /// Bytes read so far by a re-sync's elevated dump, and the total, until the file is reset
+    pub dumped: Option<(Information, Information)>,
+    /// Set by `p` in the overview to hold this file's worker
+    pub pause: PauseFlag,
+    /// Records that failed to parse when the file was loaded from a fresh index instead of
+    /// parsed. Record health and errors aren't collected then, so those views stay empty.
+    pub from_index: Option<u64>,
 }
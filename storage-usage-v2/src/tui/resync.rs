@@ -0,0 +1,118 @@
+use crate::cli::Cli;
+use crate::cli::action::Action;
+use crate::cli::drive_letter_pattern::DriveLetterPattern;
+use crate::cli::global_args::GlobalArgs;
+use crate::cli::mft_action::MftAction;
+use crate::cli::mft_action::MftArgs;
+use crate::cli::mft_dump_action::MftDumpArgs;
+use crate::elevated_report::ElevatedReport;
+use crate::elevated_report::ReportPipe;
+use crate::elevated_report::log_child_event;
+use crate::progress::ProgressEvent;
+use crate::tui::mainbound_message::MainboundMessage;
+use crate::tui::progress::ErrorSeverity;
+use crate::tui::progress::MftError;
+use crate::tui::worker::PauseFlag;
+use crate::tui::worker::process_mft_file;
+use crate::win_elevation::elevation_allowed;
+use crate::win_elevation::is_elevated;
+use crate::win_elevation::run_as_admin;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+
+/// Dump a drive's MFT over its file in the background, then reload it.
+///
+/// The file is reset and reprocessed through the usual worker messages once the dump is
+/// written. Without elevation the dump runs as an elevated `mft dump` child in its own
+/// console, since relaunching this process would restart the TUI; it streams its logs and
+/// read progress back through a pipe while the TUI keeps drawing.
+pub fn start_resync(
+    file_index: usize,
+    mft_file: PathBuf,
+    pause: PauseFlag,
+    tx: Sender<MainboundMessage>,
+) {
+    std::thread::spawn(move || {
+        let result = dump(file_index, &mft_file, &tx).and_then(|()| {
+            // The reloaded file starts unpaused, like one loaded at launch
+            pause.clear();
+            tx.send(MainboundMessage::Reset { file_index })?;
+            process_mft_file(file_index, mft_file.clone(), &pause, tx.clone())
+        });
+        if let Err(e) = result {
+            tracing::warn!("Re-sync of {} failed: {e:#}", mft_file.display());
+            let _ = tx.send(MainboundMessage::Event {
+                file_index,
+                event: ProgressEvent::Problem(MftError {
+                    severity: ErrorSeverity::Error,
+                    record_number: None,
+                    message: format!("Re-sync of {} failed: {e:#}", mft_file.display()),
+                }),
+            });
+            let _ = tx.send(MainboundMessage::Event {
+                file_index,
+                event: ProgressEvent::Finished,
+            });
+        }
+    });
+}
+
+fn dump(file_index: usize, mft_file: &Path, tx: &Sender<MainboundMessage>) -> eyre::Result<()> {
+    // Cached dumps are named after their drive, like C.mft
+    let drive_letter = mft_file
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .and_then(|s| s.chars().next())
+        .filter(|c| c.is_ascii_alphabetic())
+        .map(|c| c.to_ascii_uppercase())
+        .ok_or_else(|| {
+            eyre::eyre!(
+                "Can't tell which drive {} belongs to; expected a name like C.mft",
+                mft_file.display()
+            )
+        })?;
+
+    if is_elevated() {
+        return crate::mft_dump::dump_mft_to_file(mft_file, true, drive_letter);
+    }
+    if !elevation_allowed() {
+        return Err(eyre::eyre!(
+            "Re-dumping {drive_letter}: needs raw volume access, which --no-elevate rules out"
+        ));
+    }
+    let pipe = ReportPipe::create()?;
+    let cli = Cli {
+        global_args: GlobalArgs {
+            report_pipe: Some(pipe.name().to_string()),
+            ..Default::default()
+        },
+        action: Action::Mft(MftArgs {
+            action: MftAction::Dump(MftDumpArgs {
+                drive_letters: DriveLetterPattern(drive_letter.to_string()),
+                output_path: mft_file.to_path_buf(),
+                overwrite_existing: true,
+            }),
+        }),
+    };
+    let child = run_as_admin(&cli)?;
+    let tx = tx.clone();
+    let reader = pipe.spawn_reader(move |report| match report {
+        ElevatedReport::Log { level, message } => log_child_event(&level, &message),
+        ElevatedReport::Progress { read, total } => {
+            let _ = tx.send(MainboundMessage::Event {
+                file_index,
+                event: ProgressEvent::MftRead { read, total },
+            });
+        }
+    });
+    let exit_code = child.wait();
+    reader.finish();
+    let exit_code = exit_code?;
+    if exit_code != 0 {
+        return Err(eyre::eyre!(
+            "Elevated mft dump exited with code {exit_code}"
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use crate::config::session_file_path;
+use eyre::Context;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Where `mft show` left off, saved to `tui-session.json` in the config dir on quit and
+/// restored on the next launch
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    /// Title of the tab that was open
+    pub selected_tab: Option<String>,
+    pub search_query: String,
+    /// Label of the search sort order, like `size`
+    pub search_sort: Option<String>,
+    /// Selected row of each list tab, by tab title
+    pub selected_rows: BTreeMap<String, usize>,
+}
+
+impl Session {
+    /// Load the saved session, or an empty one when there is none or it can't be read
+    pub fn load() -> Self {
+        Self::try_load().unwrap_or_else(|e| {
+            tracing::warn!("Ignoring the saved TUI session: {e:#}");
+            Self::default()
+        })
+    }
+
+    fn try_load() -> eyre::Result<Self> {
+        let path = session_file_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("reading {}", path.display()))?;
+        serde_json::from_str(&contents).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    pub fn save(&self) -> eyre::Result<()> {
+        let path = session_file_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("creating {}", parent.display()))?;
+        }
+        let contents = serde_json::to_string_pretty(self)?;
+        std::fs::write(&path, contents).with_context(|| format!("writing {}", path.display()))
+    }
+}
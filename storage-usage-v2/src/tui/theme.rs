@@ -0,0 +1,204 @@
+use crate::config::theme_file_path;
+use arbitrary::Arbitrary;
+use clap::ValueEnum;
+use eyre::Context;
+use ratatui::style::Color;
+use ratatui::style::Style;
+use serde::Deserialize;
+use serde::Serialize;
+use std::sync::LazyLock;
+use std::sync::RwLock;
+
+static THEME: LazyLock<RwLock<Theme>> = LazyLock::new(|| {
+    let initial = match theme_file_path() {
+        Ok(path) if path.exists() => Theme::load_user().unwrap_or_else(|e| {
+            tracing::warn!("Falling back to the dark theme: {e:#}");
+            Theme::dark()
+        }),
+        _ => Theme::dark(),
+    };
+    RwLock::new(initial)
+});
+
+/// Built-in themes, plus `user` for the one in `theme.json` in the config dir
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum, Arbitrary)]
+pub enum ThemeName {
+    Dark,
+    Light,
+    HighContrast,
+    User,
+}
+
+impl ThemeName {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThemeName::Dark => "dark",
+            ThemeName::Light => "light",
+            ThemeName::HighContrast => "high-contrast",
+            ThemeName::User => "user",
+        }
+    }
+}
+
+/// Colors used by the TUIs, by role rather than by hue.
+///
+/// A user theme in `theme.json` only needs the fields it changes; the rest come from the dark
+/// theme. Colors are names like `"light-blue"`, hex like `"#ff8800"` or 256-color indexes.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Regular text
+    pub text: Color,
+    /// Hints and placeholder messages
+    pub muted: Color,
+    /// Tab titles, keys in the help overlay, labels and popup borders
+    pub accent: Color,
+    /// Border around the tab body
+    pub border: Color,
+    /// Background behind the tab titles and the startup/quit effects
+    pub background: Color,
+    pub selected_tab_fg: Color,
+    pub selected_tab_bg: Color,
+    /// Selected rows in lists
+    pub selection_fg: Color,
+    pub selection_bg: Color,
+    /// Finished work, healthy entries and successful actions
+    pub good: Color,
+    /// Work in progress, headings and warnings
+    pub warn: Color,
+    /// Errors, unhealthy entries and destructive prompts
+    pub bad: Color,
+    /// Rates and other secondary figures
+    pub info: Color,
+    /// Empty or unreadable cells, and alternate shading
+    pub empty: Color,
+    /// Fill colors for treemap rectangles and charts
+    pub palette: [Color; 8],
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            text: Color::White,
+            muted: Color::DarkGray,
+            accent: Color::LightBlue,
+            border: Color::Blue,
+            background: Color::Black,
+            selected_tab_fg: Color::White,
+            selected_tab_bg: Color::Blue,
+            selection_fg: Color::Black,
+            selection_bg: Color::Yellow,
+            good: Color::Green,
+            warn: Color::Yellow,
+            bad: Color::Red,
+            info: Color::Cyan,
+            empty: Color::DarkGray,
+            palette: [
+                Color::Blue,
+                Color::Green,
+                Color::Magenta,
+                Color::Cyan,
+                Color::Yellow,
+                Color::Red,
+                Color::LightBlue,
+                Color::LightGreen,
+            ],
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            text: Color::Black,
+            muted: Color::DarkGray,
+            accent: Color::Blue,
+            border: Color::Blue,
+            background: Color::White,
+            selected_tab_fg: Color::White,
+            selected_tab_bg: Color::Blue,
+            selection_fg: Color::White,
+            selection_bg: Color::Blue,
+            good: Color::Green,
+            warn: Color::Magenta,
+            bad: Color::Red,
+            info: Color::Blue,
+            empty: Color::Gray,
+            palette: [
+                Color::Blue,
+                Color::Green,
+                Color::Magenta,
+                Color::Cyan,
+                Color::Red,
+                Color::Indexed(94),
+                Color::Indexed(25),
+                Color::Indexed(28),
+            ],
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Self {
+            text: Color::White,
+            muted: Color::White,
+            accent: Color::LightYellow,
+            border: Color::White,
+            background: Color::Black,
+            selected_tab_fg: Color::Black,
+            selected_tab_bg: Color::LightYellow,
+            selection_fg: Color::Black,
+            selection_bg: Color::White,
+            good: Color::LightGreen,
+            warn: Color::LightYellow,
+            bad: Color::LightRed,
+            info: Color::LightCyan,
+            empty: Color::Gray,
+            palette: [
+                Color::LightBlue,
+                Color::LightGreen,
+                Color::LightMagenta,
+                Color::LightCyan,
+                Color::LightYellow,
+                Color::LightRed,
+                Color::White,
+                Color::Gray,
+            ],
+        }
+    }
+
+    /// Load the user theme from `theme.json` in the config dir
+    pub fn load_user() -> eyre::Result<Self> {
+        let path = theme_file_path()?;
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("reading {}", path.display()))?;
+        serde_json::from_str(&contents).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    pub fn by_name(name: ThemeName) -> eyre::Result<Self> {
+        Ok(match name {
+            ThemeName::Dark => Self::dark(),
+            ThemeName::Light => Self::light(),
+            ThemeName::HighContrast => Self::high_contrast(),
+            ThemeName::User => Self::load_user()?,
+        })
+    }
+
+    /// Style for the selected row of a list
+    pub fn selection(&self) -> Style {
+        Style::default().fg(self.selection_fg).bg(self.selection_bg)
+    }
+}
+
+/// The theme in use: the user theme when `theme.json` exists, otherwise dark, unless replaced
+/// with [`set_theme`]
+pub fn theme() -> Theme {
+    *THEME.read().unwrap()
+}
+
+pub fn set_theme(theme: Theme) {
+    *THEME.write().unwrap() = theme;
+}
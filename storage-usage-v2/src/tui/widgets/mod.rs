@@ -1 +1,2 @@
+pub mod status_bar;
 pub mod tabs;
@@ -0,0 +1,131 @@
+use crate::tui::progress::MftFileProgress;
+use crate::tui::theme::theme;
+use crate::win_memory::current_process_rss;
+use humansize::DECIMAL;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::Widget;
+use std::time::Duration;
+use std::time::Instant;
+
+/// How often the rate and memory usage are resampled
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Memory usage above which the RSS is highlighted
+const RSS_WARN_BYTES: u64 = 4_000_000_000;
+const RSS_BAD_BYTES: u64 = 8_000_000_000;
+
+/// One-line summary shown below every tab: indexed and matched entries, indexing rate, elapsed
+/// time, the process's resident memory and whether any file was loaded from an index
+pub struct StatusBar {
+    /// When the rate and RSS were last sampled, and the indexed count at that time
+    last_sample: Option<(Instant, usize)>,
+    files_per_second: f64,
+    rss: Option<u64>,
+}
+
+impl Default for StatusBar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StatusBar {
+    pub fn new() -> Self {
+        Self {
+            last_sample: None,
+            files_per_second: 0.0,
+            rss: None,
+        }
+    }
+
+    pub fn render(
+        &mut self,
+        area: Rect,
+        buf: &mut Buffer,
+        mft_files: &[MftFileProgress],
+        processing_begin: Instant,
+        matched: Option<usize>,
+    ) {
+        let indexed: usize = mft_files.iter().map(|mft| mft.files_within.len()).sum();
+        let now = Instant::now();
+        match self.last_sample {
+            Some((at, _)) if now.duration_since(at) < SAMPLE_INTERVAL => {}
+            sample => {
+                if let Some((at, count)) = sample {
+                    let seconds = now.duration_since(at).as_secs_f64();
+                    self.files_per_second = indexed.saturating_sub(count) as f64 / seconds;
+                }
+                self.rss = current_process_rss().ok();
+                self.last_sample = Some((now, indexed));
+            }
+        }
+
+        // Stop the clock once every MFT file is done
+        let finished = mft_files.iter().all(|mft| mft.processing_end.is_some());
+        let elapsed = if finished {
+            mft_files
+                .iter()
+                .filter_map(|mft| mft.processing_end)
+                .max()
+                .map(|end| end.duration_since(processing_begin))
+                .unwrap_or_default()
+        } else {
+            processing_begin.elapsed()
+        };
+
+        let muted = Style::default().fg(theme().muted);
+        let text = Style::default().fg(theme().text);
+        let mut spans = vec![
+            Span::styled("Indexed ", muted),
+            Span::styled(indexed.to_string(), text),
+        ];
+        if let Some(matched) = matched {
+            spans.push(Span::styled("  ·  Matched ", muted));
+            spans.push(Span::styled(matched.to_string(), text));
+        }
+        spans.push(Span::styled("  ·  ", muted));
+        if finished {
+            spans.push(Span::styled("done", Style::default().fg(theme().good)));
+        } else {
+            spans.push(Span::styled(
+                format!("{:.0} files/s", self.files_per_second),
+                text,
+            ));
+        }
+        spans.push(Span::styled("  ·  Elapsed ", muted));
+        spans.push(Span::styled(
+            humantime::format_duration(Duration::from_secs(elapsed.as_secs())).to_string(),
+            text,
+        ));
+        spans.push(Span::styled("  ·  RSS ", muted));
+        spans.push(match self.rss {
+            Some(rss) => {
+                let color = if rss >= RSS_BAD_BYTES {
+                    theme().bad
+                } else if rss >= RSS_WARN_BYTES {
+                    theme().warn
+                } else {
+                    theme().text
+                };
+                Span::styled(
+                    humansize::format_size(rss, DECIMAL),
+                    Style::default().fg(color),
+                )
+            }
+            None => Span::styled("?", muted),
+        });
+        if mft_files.iter().any(|mft| mft.from_index.is_some()) {
+            spans.push(Span::styled("  ·  ", muted));
+            spans.push(Span::styled(
+                "loaded from index: record health and errors not collected",
+                Style::default().fg(theme().warn),
+            ));
+        }
+        Paragraph::new(Line::from(spans)).render(area, buf);
+    }
+}
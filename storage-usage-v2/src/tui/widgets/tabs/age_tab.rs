@@ -0,0 +1,254 @@
+use crate::file_flags::FileFlag;
+use crate::tui::progress::MftFileProgress;
+use crate::tui::theme::theme;
+use crate::tui::widgets::tabs::keyboard_response::KeyboardResponse;
+use chrono::Datelike;
+use chrono::Days;
+use chrono::NaiveDate;
+use chrono::Utc;
+use humansize::DECIMAL;
+use ratatui::buffer::Buffer;
+use ratatui::crossterm::event::KeyCode;
+use ratatui::crossterm::event::KeyEvent;
+use ratatui::layout::Constraint;
+use ratatui::layout::Direction;
+use ratatui::layout::Layout;
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use ratatui::style::Style;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::Bar;
+use ratatui::widgets::BarChart;
+use ratatui::widgets::BarGroup;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::Widget;
+use std::collections::BTreeMap;
+
+/// How wide each age bucket is
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum AgeBucket {
+    Week,
+    Month,
+    Year,
+}
+
+impl AgeBucket {
+    fn next(self) -> Self {
+        match self {
+            AgeBucket::Week => AgeBucket::Month,
+            AgeBucket::Month => AgeBucket::Year,
+            AgeBucket::Year => AgeBucket::Week,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            AgeBucket::Week => "week",
+            AgeBucket::Month => "month",
+            AgeBucket::Year => "year",
+        }
+    }
+
+    /// First day of the bucket holding `date`
+    fn start_of(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            AgeBucket::Week => date - Days::new(date.weekday().num_days_from_monday() as u64),
+            AgeBucket::Month => date.with_day(1).unwrap_or(date),
+            AgeBucket::Year => date.with_ordinal(1).unwrap_or(date),
+        }
+    }
+
+    fn label(self, start: NaiveDate) -> String {
+        match self {
+            AgeBucket::Week => start.format("%Y-%m-%d").to_string(),
+            AgeBucket::Month => start.format("%Y-%m").to_string(),
+            AgeBucket::Year => start.format("%Y").to_string(),
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+struct AgeTotals {
+    count: u64,
+    bytes: u64,
+}
+
+/// Bytes per modified-date bucket, colored by how stale the bucket is
+pub struct AgeTab {
+    /// Totals per modified day; buckets are rolled up from these when rendering
+    days: BTreeMap<NaiveDate, AgeTotals>,
+    /// Files with no modified time
+    unknown: AgeTotals,
+    /// How many of each MFT file's discovered files are already counted
+    consumed: Vec<usize>,
+    bucket: AgeBucket,
+    scroll_offset: usize,
+}
+
+impl Default for AgeTab {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AgeTab {
+    pub const KEYBINDINGS: &[(&str, &str)] = &[
+        ("↑/↓", "Scroll"),
+        ("Home", "Back to the newest bucket"),
+        ("b", "Cycle bucket size: week, month, year"),
+    ];
+
+    pub fn new() -> Self {
+        Self {
+            days: BTreeMap::new(),
+            unknown: AgeTotals::default(),
+            consumed: Vec::new(),
+            bucket: AgeBucket::Month,
+            scroll_offset: 0,
+        }
+    }
+
+    pub fn on_key(&mut self, event: KeyEvent) -> KeyboardResponse {
+        match event.code {
+            KeyCode::Up => {
+                self.scroll_offset = self.scroll_offset.saturating_sub(1);
+                KeyboardResponse::Consume
+            }
+            KeyCode::Down => {
+                self.scroll_offset += 1; // Clamped in render
+                KeyboardResponse::Consume
+            }
+            KeyCode::Home => {
+                self.scroll_offset = 0;
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('b') => {
+                self.bucket = self.bucket.next();
+                self.scroll_offset = 0;
+                KeyboardResponse::Consume
+            }
+            _ => KeyboardResponse::Pass,
+        }
+    }
+
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer, mft_files: &[MftFileProgress]) {
+        self.ingest(mft_files);
+
+        let [header_area, legend_area, chart_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Min(0),
+        ])
+        .areas(area);
+
+        let today = Utc::now().date_naive();
+        let older_than = |days: u64| -> u64 {
+            let cutoff = today - Days::new(days);
+            self.days.range(..cutoff).map(|(_, t)| t.bytes).sum()
+        };
+        let total: u64 = self.days.values().map(|t| t.bytes).sum::<u64>() + self.unknown.bytes;
+        let header = format!(
+            "{} by {} modified  ·  older than 1y: {}  ·  older than 3y: {}  ·  unknown: {}  (b to change bucket)",
+            humansize::format_size(total, DECIMAL),
+            self.bucket.as_str(),
+            humansize::format_size(older_than(365), DECIMAL),
+            humansize::format_size(older_than(3 * 365), DECIMAL),
+            humansize::format_size(self.unknown.bytes, DECIMAL),
+        );
+        Paragraph::new(header)
+            .style(Style::default().fg(theme().text))
+            .render(header_area, buf);
+
+        let legend: Vec<Span> = [
+            (30, "< 30d"),
+            (365, "< 1y"),
+            (3 * 365, "< 3y"),
+            (u64::MAX, "older"),
+        ]
+        .into_iter()
+        .flat_map(|(days, label)| {
+            [
+                Span::styled("█ ", Style::default().fg(age_color(days.saturating_sub(1)))),
+                Span::raw(format!("{label}   ")),
+            ]
+        })
+        .collect();
+        Paragraph::new(Line::from(legend)).render(legend_area, buf);
+
+        if self.days.is_empty() {
+            Paragraph::new("No files with modified times discovered yet.")
+                .style(Style::default().fg(theme().muted))
+                .render(chart_area, buf);
+            return;
+        }
+
+        // Roll the days up into buckets, newest first
+        let mut buckets: BTreeMap<NaiveDate, AgeTotals> = BTreeMap::new();
+        for (day, totals) in &self.days {
+            let bucket = buckets.entry(self.bucket.start_of(*day)).or_default();
+            bucket.count += totals.count;
+            bucket.bytes += totals.bytes;
+        }
+        let visible = chart_area.height as usize;
+        self.scroll_offset = self
+            .scroll_offset
+            .min(buckets.len().saturating_sub(visible));
+        let bars: Vec<Bar> = buckets
+            .iter()
+            .rev()
+            .skip(self.scroll_offset)
+            .take(visible)
+            .map(|(start, totals)| {
+                let age_days = (today - *start).num_days().max(0) as u64;
+                Bar::default()
+                    .label(Line::from(self.bucket.label(*start)))
+                    .value(totals.bytes)
+                    .text_value(format!(
+                        "{} in {} files",
+                        humansize::format_size(totals.bytes, DECIMAL),
+                        totals.count
+                    ))
+                    .style(Style::default().fg(age_color(age_days)))
+            })
+            .collect();
+        BarChart::default()
+            .direction(Direction::Horizontal)
+            .bar_width(1)
+            .bar_gap(0)
+            .value_style(Style::default().fg(theme().text))
+            .data(BarGroup::default().bars(&bars))
+            .render(chart_area, buf);
+    }
+
+    /// Count files discovered since the last frame
+    fn ingest(&mut self, mft_files: &[MftFileProgress]) {
+        self.consumed.resize(mft_files.len(), 0);
+        for (file_index, progress) in mft_files.iter().enumerate() {
+            let start = self.consumed[file_index];
+            for file in &progress.files_within[start..] {
+                if file.flags.contains(FileFlag::Directory) {
+                    continue;
+                }
+                let totals = match file.modified {
+                    Some(modified) => self.days.entry(modified.date_naive()).or_default(),
+                    None => &mut self.unknown,
+                };
+                totals.count += 1;
+                totals.bytes += file.size;
+            }
+            self.consumed[file_index] = progress.files_within.len();
+        }
+    }
+}
+
+/// Heat color for data last modified `days` ago
+fn age_color(days: u64) -> Color {
+    let theme = theme();
+    match days {
+        0..30 => theme.good,
+        30..365 => theme.info,
+        365..1095 => theme.warn,
+        _ => theme.bad,
+    }
+}
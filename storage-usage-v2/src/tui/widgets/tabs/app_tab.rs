@@ -1,11 +1,21 @@
+use crate::tui::log_buffer::warning_count;
 use crate::tui::progress::MftFileProgress;
+use crate::tui::widgets::tabs::age_tab::AgeTab;
+use crate::tui::widgets::tabs::compare_tab::CompareTab;
+use crate::tui::widgets::tabs::extensions_tab::ExtensionsTab;
+use crate::tui::widgets::tabs::file_actions::FileActions;
 use crate::tui::widgets::tabs::keyboard_response::KeyboardResponse;
+use crate::tui::widgets::tabs::largest_directories_tab::LargestDirectoriesTab;
+use crate::tui::widgets::tabs::largest_files_tab::LargestFilesTab;
+use crate::tui::widgets::tabs::log_tab::LogTab;
 use crate::tui::widgets::tabs::overview_tab::OverviewTab;
 use crate::tui::widgets::tabs::search_tab::SearchTab;
+use crate::tui::widgets::tabs::treemap_tab::TreemapTab;
 use crate::tui::widgets::tabs::visualizer_tab::VisualizerTab;
 use crate::tui::widgets::tabs::errors_tab::ErrorsTab;
 use ratatui::buffer::Buffer;
 use ratatui::crossterm::event::KeyEvent;
+use ratatui::crossterm::event::MouseEvent;
 use ratatui::layout::Rect;
 use std::time::Instant;
 
@@ -13,8 +23,15 @@ use std::time::Instant;
 pub enum AppTab {
     Overview(OverviewTab),
     Visualizer(VisualizerTab),
+    Treemap(TreemapTab),
+    Extensions(ExtensionsTab),
+    Age(AgeTab),
+    LargestFiles(LargestFilesTab),
+    LargestDirectories(LargestDirectoriesTab),
     Search(SearchTab),
     Errors(ErrorsTab),
+    Log(LogTab),
+    Compare(CompareTab),
 }
 
 impl AppTab {
@@ -22,8 +39,102 @@ impl AppTab {
         match self {
             AppTab::Overview(_) => "Overview",
             AppTab::Visualizer(_) => "Visualizer",
+            AppTab::Treemap(_) => "Treemap",
+            AppTab::Extensions(_) => "Extensions",
+            AppTab::Age(_) => "Age",
+            AppTab::LargestFiles(_) => "Largest files",
+            AppTab::LargestDirectories(_) => "Largest directories",
             AppTab::Search(_) => "Search",
             AppTab::Errors(_) => "Errors",
+            AppTab::Log(_) => "Log",
+            AppTab::Compare(_) => "Compare",
+        }
+    }
+
+    /// Count shown after the title, like the number of errors collected so far
+    pub fn badge(&self, mft_files: &[MftFileProgress]) -> Option<String> {
+        match self {
+            AppTab::Errors(_) => {
+                let count = ErrorsTab::total_count(mft_files);
+                (count > 0).then(|| count.to_string())
+            }
+            AppTab::Log(_) => {
+                let count = warning_count();
+                (count > 0).then(|| count.to_string())
+            }
+            _ => None,
+        }
+    }
+
+    /// Keys handled by this tab, as (key, description) pairs for the help overlay
+    pub fn keybindings(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            AppTab::Overview(_) => OverviewTab::KEYBINDINGS,
+            AppTab::Visualizer(_) => VisualizerTab::KEYBINDINGS,
+            AppTab::Treemap(_) => TreemapTab::KEYBINDINGS,
+            AppTab::Extensions(_) => ExtensionsTab::KEYBINDINGS,
+            AppTab::Age(_) => AgeTab::KEYBINDINGS,
+            AppTab::LargestFiles(_) => LargestFilesTab::KEYBINDINGS,
+            AppTab::LargestDirectories(_) => LargestDirectoriesTab::KEYBINDINGS,
+            AppTab::Search(_) => SearchTab::KEYBINDINGS,
+            AppTab::Errors(_) => ErrorsTab::KEYBINDINGS,
+            AppTab::Log(_) => LogTab::KEYBINDINGS,
+            AppTab::Compare(_) => CompareTab::KEYBINDINGS,
+        }
+    }
+
+    pub fn on_mouse(&mut self, event: MouseEvent) {
+        match self {
+            AppTab::Treemap(tab) => tab.on_mouse(event),
+            AppTab::Search(tab) => tab.on_mouse(event),
+            AppTab::Errors(tab) => tab.on_mouse(event),
+            AppTab::Overview(_)
+            | AppTab::Visualizer(_)
+            | AppTab::Extensions(_)
+            | AppTab::Age(_)
+            | AppTab::LargestFiles(_)
+            | AppTab::LargestDirectories(_)
+            | AppTab::Log(_)
+            | AppTab::Compare(_) => {}
+        }
+    }
+
+    /// Selected row of the tabs that list rows, for the saved session
+    pub fn selected_row(&self) -> Option<usize> {
+        match self {
+            AppTab::LargestFiles(tab) => Some(tab.selected_row()),
+            AppTab::LargestDirectories(tab) => Some(tab.selected_row()),
+            AppTab::Search(tab) => Some(tab.selected_row()),
+            _ => None,
+        }
+    }
+
+    /// The reveal, copy and delete actions of a tab listing files
+    pub fn file_actions(&mut self) -> Option<&mut FileActions> {
+        match self {
+            AppTab::LargestFiles(tab) => Some(tab.actions_mut()),
+            AppTab::Search(tab) => Some(tab.actions_mut()),
+            _ => None,
+        }
+    }
+
+    pub fn select_row(&mut self, row: usize) {
+        match self {
+            AppTab::LargestFiles(tab) => tab.select_row(row),
+            AppTab::LargestDirectories(tab) => tab.select_row(row),
+            AppTab::Search(tab) => tab.select_row(row),
+            _ => {}
+        }
+    }
+
+    /// Whether the tab has a prompt open that should receive every key
+    pub fn captures_input(&self) -> bool {
+        match self {
+            AppTab::Search(tab) => tab.captures_input(),
+            AppTab::LargestFiles(tab) => tab.captures_input(),
+            AppTab::Errors(tab) => tab.captures_input(),
+            AppTab::Visualizer(tab) => tab.captures_input(),
+            _ => false,
         }
     }
 
@@ -37,8 +148,15 @@ impl AppTab {
         match self {
             AppTab::Overview(tab) => tab.render(area, buf, mft_files, processing_begin),
             AppTab::Visualizer(tab) => tab.render(area, buf, mft_files),
+            AppTab::Treemap(tab) => tab.render(area, buf, mft_files),
+            AppTab::Extensions(tab) => tab.render(area, buf, mft_files),
+            AppTab::Age(tab) => tab.render(area, buf, mft_files),
+            AppTab::LargestFiles(tab) => tab.render(area, buf, mft_files),
+            AppTab::LargestDirectories(tab) => tab.render(area, buf, mft_files),
             AppTab::Search(tab) => tab.render(area, buf, mft_files),
             AppTab::Errors(tab) => tab.render(area, buf, mft_files),
+            AppTab::Log(tab) => tab.render(area, buf),
+            AppTab::Compare(tab) => tab.render(area, buf),
         }
     }
 
@@ -46,8 +164,15 @@ impl AppTab {
         match self {
             AppTab::Overview(tab) => tab.on_key(event),
             AppTab::Visualizer(tab) => tab.on_key(event),
+            AppTab::Treemap(tab) => tab.on_key(event),
+            AppTab::Extensions(tab) => tab.on_key(event),
+            AppTab::Age(tab) => tab.on_key(event),
+            AppTab::LargestFiles(tab) => tab.on_key(event),
+            AppTab::LargestDirectories(tab) => tab.on_key(event),
             AppTab::Search(tab) => tab.on_key(event),
             AppTab::Errors(tab) => tab.on_key(event),
+            AppTab::Log(tab) => tab.on_key(event),
+            AppTab::Compare(tab) => tab.on_key(event),
         }
     }
 }
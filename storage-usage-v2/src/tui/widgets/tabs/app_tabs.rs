@@ -1,24 +1,41 @@
 use crate::tui::progress::MftFileProgress;
+use crate::tui::session::Session;
+use crate::tui::widgets::tabs::age_tab::AgeTab;
+use crate::tui::theme::theme;
+use crate::tui::widgets::status_bar::StatusBar;
 use crate::tui::widgets::tabs::app_tab::AppTab;
+use crate::tui::widgets::tabs::compare_tab::CompareTab;
 use crate::tui::widgets::tabs::errors_tab::ErrorsTab;
+use crate::tui::widgets::tabs::extensions_tab::ExtensionsTab;
+use crate::tui::widgets::tabs::file_actions::FileJob;
+use crate::tui::widgets::tabs::file_actions::FileJobResult;
 use crate::tui::widgets::tabs::keyboard_response::KeyboardResponse;
+use crate::tui::widgets::tabs::largest_directories_tab::LargestDirectoriesTab;
+use crate::tui::widgets::tabs::largest_files_tab::LargestFilesTab;
+use crate::tui::widgets::tabs::log_tab::LogTab;
 use crate::tui::widgets::tabs::overview_tab::OverviewTab;
 use crate::tui::widgets::tabs::search_tab::SearchTab;
+use crate::tui::widgets::tabs::treemap_tab::TreemapTab;
 use crate::tui::widgets::tabs::visualizer_tab::VisualizerTab;
 use ratatui::buffer::Buffer;
 use ratatui::crossterm::event::KeyCode;
 use ratatui::crossterm::event::KeyEvent;
+use ratatui::crossterm::event::MouseButton;
+use ratatui::crossterm::event::MouseEvent;
+use ratatui::crossterm::event::MouseEventKind;
 use ratatui::layout::Constraint;
+use ratatui::layout::Flex;
 use ratatui::layout::Layout;
 use ratatui::layout::Rect;
-use ratatui::style::Color;
 use ratatui::style::Style;
 use ratatui::style::Stylize;
 use ratatui::symbols::border::PROPORTIONAL_TALL;
 use ratatui::text::Line;
 use ratatui::text::Span;
 use ratatui::widgets::Block;
+use ratatui::widgets::Clear;
 use ratatui::widgets::Padding;
+use ratatui::widgets::Paragraph;
 use ratatui::widgets::Tabs;
 use ratatui::widgets::Widget;
 use std::time::Instant;
@@ -26,6 +43,13 @@ use std::time::Instant;
 pub struct AppTabs {
     pub tabs: Vec<AppTab>,
     pub selected: usize,
+    /// Whether the `?` keybinding overlay is open
+    pub show_help: bool,
+    /// Where the tab titles were drawn in the last frame, for mouse clicks
+    tabs_area: Rect,
+    /// Width of each tab's title and badge in the last frame, for mouse clicks
+    title_widths: Vec<u16>,
+    status_bar: StatusBar,
 }
 impl Default for AppTabs {
     fn default() -> Self {
@@ -39,15 +63,43 @@ impl AppTabs {
             tabs: vec![
                 AppTab::Overview(OverviewTab::new()),
                 AppTab::Visualizer(VisualizerTab::new()),
+                AppTab::Treemap(TreemapTab::new()),
+                AppTab::Extensions(ExtensionsTab::new()),
+                AppTab::Age(AgeTab::new()),
+                AppTab::LargestFiles(LargestFilesTab::new()),
+                AppTab::LargestDirectories(LargestDirectoriesTab::new()),
                 AppTab::Search(SearchTab::new()),
                 AppTab::Errors(ErrorsTab::new()),
+                AppTab::Log(LogTab::new()),
             ],
             selected: 0,
+            show_help: false,
+            tabs_area: Rect::default(),
+            title_widths: Vec::new(),
+            status_bar: StatusBar::new(),
         }
     }
 
+    pub const KEYBINDINGS: &[(&str, &str)] = &[
+        ("←/→, click title", "Switch tab"),
+        ("?", "Show this help"),
+        ("q/Esc", "Quit"),
+    ];
+
     pub fn on_key(&mut self, event: KeyEvent) -> KeyboardResponse {
+        if self.show_help {
+            // Any key closes the overlay
+            self.show_help = false;
+            return KeyboardResponse::Consume;
+        }
+        if self.captures_input() {
+            return self.tabs[self.selected].on_key(event);
+        }
         match event.code {
+            KeyCode::Char('?') => {
+                self.show_help = true;
+                KeyboardResponse::Consume
+            }
             KeyCode::Left => {
                 if self.selected > 0 {
                     self.selected -= 1;
@@ -71,18 +123,30 @@ impl AppTabs {
         mft_files: &[MftFileProgress],
         processing_begin: Instant,
     ) {
-        let vertical_layout = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]);
-        let [tabs_area, body_area] = vertical_layout.areas(area);
+        let vertical_layout = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ]);
+        let [tabs_area, body_area, status_area] = vertical_layout.areas(area);
+        self.tabs_area = tabs_area;
 
         // render tabs
-        Tabs::new(self.tabs.iter().map(|t| {
+        self.title_widths.clear();
+        let mut titles = Vec::with_capacity(self.tabs.len());
+        for t in &self.tabs {
             let mut line = Line::default();
             line.push_span(Span::raw(" "));
-            line.push_span(t.title().fg(Color::LightBlue).bg(Color::Black));
+            line.push_span(t.title().fg(theme().accent).bg(theme().background));
+            if let Some(badge) = t.badge(mft_files) {
+                line.push_span(format!(" {badge}").fg(theme().bad).bg(theme().background));
+            }
             line.push_span(Span::raw(" "));
-            line
-        }))
-        .highlight_style(Style::default().fg(Color::White).bg(Color::Blue))
+            self.title_widths.push(line.width() as u16);
+            titles.push(line);
+        }
+        Tabs::new(titles)
+        .highlight_style(Style::default().fg(theme().selected_tab_fg).bg(theme().selected_tab_bg))
         .select(self.selected)
         .padding("", "")
         .divider(" ")
@@ -91,12 +155,208 @@ impl AppTabs {
         // render body border
         let content_block = Block::bordered()
             .border_set(PROPORTIONAL_TALL)
-            .border_style(Color::Blue)
+            .border_style(theme().border)
             .padding(Padding::horizontal(1));
         let content_inner = content_block.inner(body_area);
         content_block.render(body_area, buf);
 
+        // follow a jump from the errors tab to the affected records in the visualizer
+        if let AppTab::Errors(errors) = &mut self.tabs[self.selected]
+            && let Some((file_index, records)) = errors.take_jump_request(mft_files)
+            && let Some(index) = self
+                .tabs
+                .iter()
+                .position(|tab| matches!(tab, AppTab::Visualizer(_)))
+            && let AppTab::Visualizer(visualizer) = &mut self.tabs[index]
+        {
+            visualizer.focus(file_index, records);
+            self.selected = index;
+        }
+
         // render body
         self.tabs[self.selected].render(content_inner, buf, mft_files, processing_begin);
+
+        // render status bar
+        let matched = self.tabs.iter().find_map(|tab| match tab {
+            AppTab::Search(search) => Some(search.get_stats().1),
+            _ => None,
+        });
+        self.status_bar.render(status_area, buf, mft_files, processing_begin, matched);
+
+        if self.show_help {
+            self.render_help(body_area, buf);
+        }
+    }
+
+    /// Click a title to switch tabs; other mouse events go to the current tab
+    pub fn on_mouse(&mut self, event: MouseEvent) {
+        if self.show_help {
+            if let MouseEventKind::Down(_) = event.kind {
+                self.show_help = false;
+            }
+            return;
+        }
+        if let MouseEventKind::Down(MouseButton::Left) = event.kind
+            && event.row == self.tabs_area.y
+            && event.column >= self.tabs_area.x
+        {
+            // Titles are drawn as " title badge " separated by a single space divider
+            let mut x = self.tabs_area.x;
+            for (index, width) in self.title_widths.iter().copied().enumerate() {
+                if event.column < x + width {
+                    self.selected = index;
+                    break;
+                }
+                x += width + 1;
+            }
+            return;
+        }
+        self.tabs[self.selected].on_mouse(event);
+    }
+
+    /// Add the compare tab after the overview and open it
+    pub fn add_compare(&mut self, compare: CompareTab) {
+        self.tabs.insert(1, AppTab::Compare(compare));
+        self.selected = 1;
+    }
+
+    /// A file the overview asked to re-sync
+    pub fn take_resync_request(&mut self) -> Option<usize> {
+        self.tabs.iter_mut().find_map(|tab| match tab {
+            AppTab::Overview(overview) => overview.take_resync_request(),
+            _ => None,
+        })
+    }
+
+    /// A delete step a tab asked to run off the UI thread, with the tab's position
+    pub fn take_file_job(&mut self) -> Option<(usize, FileJob)> {
+        self.tabs
+            .iter_mut()
+            .enumerate()
+            .find_map(|(index, tab)| Some((index, tab.file_actions()?.take_job()?)))
+    }
+
+    /// Hand a finished delete step back to the tab that asked for it
+    pub fn finish_file_job(&mut self, tab: usize, result: FileJobResult) {
+        if let Some(actions) = self.tabs.get_mut(tab).and_then(AppTab::file_actions) {
+            actions.finish_job(result);
+        }
+    }
+
+    /// A file the overview asked to pause or resume
+    pub fn take_pause_request(&mut self) -> Option<usize> {
+        self.tabs.iter_mut().find_map(|tab| match tab {
+            AppTab::Overview(overview) => overview.take_pause_request(),
+            _ => None,
+        })
+    }
+
+    /// Start every tab but the overview, log and compare tabs over, for when a file's entries were cleared to be
+    /// reprocessed
+    pub fn reset(&mut self) {
+        for tab in &mut self.tabs {
+            *tab = match tab {
+                AppTab::Overview(_) | AppTab::Log(_) | AppTab::Compare(_) => continue,
+                AppTab::Visualizer(_) => AppTab::Visualizer(VisualizerTab::new()),
+                AppTab::Treemap(_) => AppTab::Treemap(TreemapTab::new()),
+                AppTab::Extensions(_) => AppTab::Extensions(ExtensionsTab::new()),
+                AppTab::Age(_) => AppTab::Age(AgeTab::new()),
+                AppTab::LargestFiles(_) => AppTab::LargestFiles(LargestFilesTab::new()),
+                AppTab::LargestDirectories(_) => {
+                    AppTab::LargestDirectories(LargestDirectoriesTab::new())
+                }
+                AppTab::Search(_) => AppTab::Search(SearchTab::new()),
+                AppTab::Errors(_) => AppTab::Errors(ErrorsTab::new()),
+            };
+        }
+    }
+
+    /// The open tab, search and selected rows, to be restored on the next launch
+    pub fn session(&self) -> Session {
+        let mut session = Session {
+            selected_tab: Some(self.tabs[self.selected].title().to_string()),
+            ..Session::default()
+        };
+        for tab in &self.tabs {
+            if let AppTab::Search(search) = tab {
+                let (query, sort) = search.session_query();
+                session.search_query = query.to_string();
+                session.search_sort = Some(sort.to_string());
+            }
+            if let Some(row) = tab.selected_row() {
+                session.selected_rows.insert(tab.title().to_string(), row);
+            }
+        }
+        session
+    }
+
+    /// Reopen the saved tab and search. Rows are restored separately with
+    /// [`Self::restore_rows`] once the lists they index into are filled.
+    pub fn restore_session(&mut self, session: &Session) {
+        if let Some(title) = &session.selected_tab
+            && let Some(index) = self.tabs.iter().position(|tab| tab.title() == title)
+        {
+            self.selected = index;
+        }
+        for tab in &mut self.tabs {
+            if let AppTab::Search(search) = tab {
+                search.restore_query(&session.search_query, session.search_sort.as_deref());
+            }
+        }
+    }
+
+    pub fn restore_rows(&mut self, session: &Session) {
+        for tab in &mut self.tabs {
+            if let Some(row) = session.selected_rows.get(tab.title()) {
+                tab.select_row(*row);
+            }
+        }
+    }
+
+    /// Whether the current tab has a prompt open that should receive every key
+    pub fn captures_input(&self) -> bool {
+        self.tabs[self.selected].captures_input()
+    }
+
+    fn render_help(&self, area: Rect, buf: &mut Buffer) {
+        let tab = &self.tabs[self.selected];
+        let key_style = Style::default().fg(theme().accent);
+        let heading_style = Style::default().fg(theme().warn);
+        let key_width = Self::KEYBINDINGS
+            .iter()
+            .chain(tab.keybindings())
+            .map(|(key, _)| key.chars().count())
+            .max()
+            .unwrap_or(0);
+        let binding_line = |(key, description): &(&str, &str)| {
+            Line::from(vec![
+                Span::styled(format!("{key:<key_width$}  "), key_style),
+                Span::raw(description.to_string()),
+            ])
+        };
+
+        let mut lines = vec![Line::from(Span::styled("Global", heading_style))];
+        lines.extend(Self::KEYBINDINGS.iter().map(binding_line));
+        lines.push(Line::default());
+        lines.push(Line::from(Span::styled(tab.title(), heading_style)));
+        if tab.keybindings().is_empty() {
+            lines.push(Line::from("No tab-specific keys"));
+        }
+        lines.extend(tab.keybindings().iter().map(binding_line));
+
+        let [popup] = Layout::vertical([Constraint::Length(lines.len() as u16 + 2)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup] = Layout::horizontal([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .areas(popup);
+        Clear.render(popup, buf);
+        Paragraph::new(lines)
+            .block(
+                Block::bordered()
+                    .title("Keybindings (any key to close)")
+                    .border_style(Style::default().fg(theme().accent)),
+            )
+            .render(popup, buf);
     }
 }
@@ -0,0 +1,422 @@
+use crate::mft_diff::ChangeKind;
+use crate::mft_diff::DiffOptions;
+use crate::mft_diff::DiffSource;
+use crate::mft_diff::DirectoryDelta;
+use crate::mft_diff::EntryChange;
+use crate::mft_diff::MftDiff;
+use crate::mft_diff::format_signed_size;
+use crate::tui::theme::theme;
+use crate::tui::widgets::tabs::keyboard_response::KeyboardResponse;
+use ratatui::buffer::Buffer;
+use ratatui::crossterm::event::KeyCode;
+use ratatui::crossterm::event::KeyEvent;
+use ratatui::layout::Constraint;
+use ratatui::layout::Layout;
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use ratatui::style::Style;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::List;
+use ratatui::widgets::ListItem;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::Widget;
+use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
+use std::time::Instant;
+
+/// Directory depth the rollups start at, as in `mft diff --dir-depth`
+const DEFAULT_DIR_DEPTH: usize = 2;
+
+enum DiffState {
+    Computing(Receiver<eyre::Result<MftDiff>>),
+    Failed(String),
+    Ready(MftDiff),
+}
+
+/// Which changes the file list shows, cycled with `k`
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+enum KindFilter {
+    #[default]
+    All,
+    Only(ChangeKind),
+}
+
+impl KindFilter {
+    fn next(self) -> Self {
+        match self {
+            KindFilter::All => KindFilter::Only(ChangeKind::Added),
+            KindFilter::Only(ChangeKind::Added) => KindFilter::Only(ChangeKind::Removed),
+            KindFilter::Only(ChangeKind::Removed) => KindFilter::Only(ChangeKind::Modified),
+            KindFilter::Only(ChangeKind::Modified) => KindFilter::All,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            KindFilter::All => "all",
+            KindFilter::Only(kind) => kind.as_str(),
+        }
+    }
+}
+
+/// Added, removed and resized files between two dumps given to `mft show --compare`, with
+/// per-directory rollups. The diff is computed on a background thread.
+pub struct CompareTab {
+    old: PathBuf,
+    new: PathBuf,
+    state: DiffState,
+    started: Instant,
+    /// List directory rollups instead of files
+    by_directory: bool,
+    dir_depth: usize,
+    /// Directories rolled up at the current depth, recomputed when it changes
+    directories: Vec<DirectoryDelta>,
+    kind_filter: KindFilter,
+    /// Only list files below this directory, picked from the rollups with Enter
+    under: Option<String>,
+    /// Sort files by path instead of the size of their change
+    by_path: bool,
+    /// Files shown with the current filters, as indexes into the diff's changes
+    shown: Vec<usize>,
+    shown_dirty: bool,
+    selected_index: usize,
+    scroll_offset: usize,
+    visible_height: usize,
+}
+
+impl CompareTab {
+    pub const KEYBINDINGS: &[(&str, &str)] = &[
+        ("↑/↓", "Move selection"),
+        ("PgUp/PgDn", "Move by a page"),
+        ("Home/End", "Jump to first/last"),
+        ("d", "Toggle files or directory rollups"),
+        ("+/-", "Roll directories up deeper or shallower"),
+        ("Enter", "List the files below the selected directory"),
+        ("Backspace", "Back to every directory"),
+        ("k", "Cycle added, removed, modified or all files"),
+        ("s", "Toggle sorting files by size change or path"),
+    ];
+
+    pub fn new(old: PathBuf, new: PathBuf) -> Self {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let (old_source, new_source) =
+            (DiffSource::Dump(old.clone()), DiffSource::Dump(new.clone()));
+        std::thread::spawn(move || {
+            let diff = old_source.load_index().and_then(|old_index| {
+                let new_index = new_source.load_index()?;
+                Ok(MftDiff::between(
+                    &old_index,
+                    old_source.drive_letter(),
+                    &new_index,
+                    new_source.drive_letter(),
+                    &DiffOptions::default(),
+                ))
+            });
+            let _ = tx.send(diff);
+        });
+        Self {
+            old,
+            new,
+            state: DiffState::Computing(rx),
+            started: Instant::now(),
+            by_directory: false,
+            dir_depth: DEFAULT_DIR_DEPTH,
+            directories: Vec::new(),
+            kind_filter: KindFilter::default(),
+            under: None,
+            by_path: false,
+            shown: Vec::new(),
+            shown_dirty: true,
+            selected_index: 0,
+            scroll_offset: 0,
+            visible_height: 20,
+        }
+    }
+
+    pub fn on_key(&mut self, event: KeyEvent) -> KeyboardResponse {
+        match event.code {
+            KeyCode::Up => {
+                self.selected_index = self.selected_index.saturating_sub(1);
+                KeyboardResponse::Consume
+            }
+            KeyCode::Down => {
+                self.selected_index += 1; // Clamped in render
+                KeyboardResponse::Consume
+            }
+            KeyCode::PageUp => {
+                self.selected_index = self.selected_index.saturating_sub(self.visible_height);
+                KeyboardResponse::Consume
+            }
+            KeyCode::PageDown => {
+                self.selected_index += self.visible_height;
+                KeyboardResponse::Consume
+            }
+            KeyCode::Home => {
+                self.selected_index = 0;
+                KeyboardResponse::Consume
+            }
+            KeyCode::End => {
+                self.selected_index = usize::MAX; // Clamped in render
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('d') => {
+                self.by_directory = !self.by_directory;
+                self.select_first();
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('+') if self.by_directory => {
+                self.dir_depth += 1;
+                self.roll_up();
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('-') if self.by_directory => {
+                self.dir_depth = self.dir_depth.saturating_sub(1);
+                self.roll_up();
+                KeyboardResponse::Consume
+            }
+            KeyCode::Enter if self.by_directory => {
+                if let Some(directory) = self.directories.get(self.selected_index) {
+                    self.under = Some(directory.path.clone());
+                    self.by_directory = false;
+                    self.shown_dirty = true;
+                    self.select_first();
+                }
+                KeyboardResponse::Consume
+            }
+            KeyCode::Backspace => {
+                if self.under.take().is_some() {
+                    self.shown_dirty = true;
+                    self.select_first();
+                }
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('k') => {
+                self.kind_filter = self.kind_filter.next();
+                self.shown_dirty = true;
+                self.select_first();
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('s') => {
+                self.by_path = !self.by_path;
+                self.shown_dirty = true;
+                self.select_first();
+                KeyboardResponse::Consume
+            }
+            _ => KeyboardResponse::Pass,
+        }
+    }
+
+    fn select_first(&mut self) {
+        self.selected_index = 0;
+        self.scroll_offset = 0;
+    }
+
+    fn roll_up(&mut self) {
+        if let DiffState::Ready(diff) = &self.state {
+            self.directories = diff.directory_deltas(self.dir_depth);
+        }
+        self.select_first();
+    }
+
+    /// Re-apply the kind and directory filters and the sort to the changes
+    fn refresh_shown(&mut self) {
+        let DiffState::Ready(diff) = &self.state else {
+            return;
+        };
+        let scope = DiffOptions {
+            under: self.under.clone(),
+            ..DiffOptions::default()
+        };
+        let mut shown: Vec<usize> = diff
+            .changes
+            .iter()
+            .enumerate()
+            .filter(|(_, change)| match self.kind_filter {
+                KindFilter::All => true,
+                KindFilter::Only(kind) => change.kind == kind,
+            })
+            .filter(|(_, change)| scope.in_scope(&change.path))
+            .map(|(index, _)| index)
+            .collect();
+        // Changes are already in path order
+        if !self.by_path {
+            shown.sort_by_key(|index| {
+                std::cmp::Reverse(diff.changes[*index].size_delta().unsigned_abs())
+            });
+        }
+        self.shown = shown;
+        self.shown_dirty = false;
+    }
+
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer) {
+        if let DiffState::Computing(rx) = &self.state
+            && let Ok(result) = rx.try_recv()
+        {
+            self.state = match result {
+                Ok(diff) => DiffState::Ready(diff),
+                Err(e) => DiffState::Failed(format!("{e:#}")),
+            };
+            self.roll_up();
+            self.shown_dirty = true;
+        }
+
+        let [header_area, list_area] =
+            Layout::vertical([Constraint::Length(2), Constraint::Min(0)]).areas(area);
+        self.visible_height = list_area.height as usize;
+
+        if self.shown_dirty {
+            self.refresh_shown();
+        }
+        let diff = match &self.state {
+            DiffState::Computing(_) => {
+                Paragraph::new(vec![
+                    Line::from(format!("{} → {}", self.old.display(), self.new.display())),
+                    Line::from(format!("Comparing… {}s", self.started.elapsed().as_secs())),
+                ])
+                .style(Style::default().fg(theme().muted))
+                .render(header_area, buf);
+                return;
+            }
+            DiffState::Failed(message) => {
+                Paragraph::new(format!("Comparison failed: {message}"))
+                    .style(Style::default().fg(theme().bad))
+                    .render(area, buf);
+                return;
+            }
+            DiffState::Ready(diff) => diff,
+        };
+
+        let muted = Style::default().fg(theme().muted);
+        let summary = Line::from(vec![
+            Span::styled(
+                format!("{} → {}  ", self.old.display(), self.new.display()),
+                Style::default().fg(theme().text),
+            ),
+            Span::styled(
+                format!("+{} ", diff.count(ChangeKind::Added)),
+                Style::default().fg(kind_color(ChangeKind::Added)),
+            ),
+            Span::styled(
+                format!("-{} ", diff.count(ChangeKind::Removed)),
+                Style::default().fg(kind_color(ChangeKind::Removed)),
+            ),
+            Span::styled(
+                format!("~{}  ", diff.count(ChangeKind::Modified)),
+                Style::default().fg(kind_color(ChangeKind::Modified)),
+            ),
+            Span::styled("net ", muted),
+            Span::styled(
+                format_signed_size(diff.net_size_change()),
+                Style::default().fg(theme().accent),
+            ),
+        ]);
+        let view = if self.by_directory {
+            format!(
+                "{} directories rolled up {} levels deep  (+/- depth, Enter to list files, d for files)",
+                self.directories.len(),
+                self.dir_depth
+            )
+        } else {
+            format!(
+                "{} {} files{}, by {}  (k kind, s sort, d for directories{})",
+                self.shown.len(),
+                self.kind_filter.label(),
+                self.under
+                    .as_ref()
+                    .map(|under| format!(" below {under}"))
+                    .unwrap_or_default(),
+                if self.by_path { "path" } else { "size change" },
+                if self.under.is_some() {
+                    ", Backspace for every directory"
+                } else {
+                    ""
+                }
+            )
+        };
+        Paragraph::new(vec![summary, Line::styled(view, muted)]).render(header_area, buf);
+
+        let row_count = if self.by_directory {
+            self.directories.len()
+        } else {
+            self.shown.len()
+        };
+        if row_count == 0 {
+            Paragraph::new("No changes.")
+                .style(muted)
+                .render(list_area, buf);
+            return;
+        }
+
+        self.selected_index = self.selected_index.min(row_count - 1);
+        if self.selected_index < self.scroll_offset {
+            self.scroll_offset = self.selected_index;
+        } else if self.selected_index >= self.scroll_offset + self.visible_height {
+            self.scroll_offset = self.selected_index + 1 - self.visible_height;
+        }
+
+        let rows = (self.scroll_offset..row_count).take(self.visible_height);
+        let items: Vec<ListItem> = if self.by_directory {
+            rows.map(|index| {
+                let directory = &self.directories[index];
+                let line = Line::from(vec![
+                    Span::styled(
+                        format!("{:>11}  ", format_signed_size(directory.size_delta)),
+                        Style::default().fg(delta_color(directory.size_delta)),
+                    ),
+                    Span::styled(format!("{:>7}  ", directory.changes), muted),
+                    Span::raw(directory.path.clone()),
+                ]);
+                self.list_item(index, line)
+            })
+            .collect()
+        } else {
+            rows.map(|index| {
+                let change = &diff.changes[self.shown[index]];
+                self.list_item(index, change_line(change))
+            })
+            .collect()
+        };
+        List::new(items).render(list_area, buf);
+    }
+
+    fn list_item<'a>(&self, index: usize, line: Line<'a>) -> ListItem<'a> {
+        if index == self.selected_index {
+            ListItem::new(line).style(theme().selection())
+        } else {
+            ListItem::new(line).style(Style::default().fg(theme().text))
+        }
+    }
+}
+
+fn change_line(change: &EntryChange) -> Line<'static> {
+    let color = kind_color(change.kind);
+    Line::from(vec![
+        Span::styled(
+            format!("{} ", change.kind.marker()),
+            Style::default().fg(color),
+        ),
+        Span::styled(
+            format!("{:>11}  ", format_signed_size(change.size_delta())),
+            Style::default().fg(delta_color(change.size_delta())),
+        ),
+        Span::raw(change.path.clone()),
+    ])
+}
+
+fn kind_color(kind: ChangeKind) -> Color {
+    match kind {
+        ChangeKind::Added => theme().good,
+        ChangeKind::Removed => theme().bad,
+        ChangeKind::Modified => theme().warn,
+    }
+}
+
+/// Growth is highlighted as a warning, shrinking as good
+fn delta_color(delta: i64) -> Color {
+    match delta.cmp(&0) {
+        std::cmp::Ordering::Greater => theme().warn,
+        std::cmp::Ordering::Less => theme().good,
+        std::cmp::Ordering::Equal => theme().muted,
+    }
+}
@@ -0,0 +1,249 @@
+use crate::reparse::ReparsePolicy;
+use crate::tui::progress::DiscoveredFile;
+use crate::tui::progress::MftFileProgress;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+pub struct TreeNode {
+    pub name: String,
+    /// Logical or allocated bytes of every file at or below this node, counting a hard-linked
+    /// file once
+    pub size: u64,
+    /// Part of `size` added by following links, which a link to this node must not follow again
+    followed: u64,
+    pub parent: Option<usize>,
+    pub children: HashMap<String, usize>,
+}
+
+/// Directory sizes rolled up from the discovered files' paths, built up as the workers stream
+/// them in
+pub struct DirectoryTree {
+    /// Arena of directory/file nodes; node 0 is the root holding one child per drive
+    pub nodes: Vec<TreeNode>,
+    /// How many of each MFT file's discovered files are already in the tree
+    consumed: Vec<usize>,
+    /// (MFT file, record, node) of hard-linked files already counted in a node's size
+    linked: HashSet<(usize, u64, usize)>,
+    policy: ReparsePolicy,
+    /// Measure files by the bytes they take on disk rather than their logical size
+    allocated: bool,
+    /// Under [`ReparsePolicy::Follow`], each link's node, its target's full path and the bytes
+    /// of the target already added below the link
+    links: Vec<(usize, String, u64)>,
+}
+
+impl Default for DirectoryTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DirectoryTree {
+    pub const ROOT: usize = 0;
+
+    pub fn new() -> Self {
+        Self::with_policy(ReparsePolicy::default())
+    }
+
+    /// A tree treating symlinks and junctions as `policy` says
+    pub fn with_policy(policy: ReparsePolicy) -> Self {
+        Self {
+            nodes: vec![TreeNode {
+                name: "All drives".to_string(),
+                size: 0,
+                followed: 0,
+                parent: None,
+                children: HashMap::new(),
+            }],
+            consumed: Vec::new(),
+            linked: HashSet::new(),
+            policy,
+            allocated: false,
+            links: Vec::new(),
+        }
+    }
+
+    /// Size nodes by allocated bytes, so a sparse or compressed file counts what it takes on
+    /// disk
+    pub fn measuring_allocated(mut self, allocated: bool) -> Self {
+        self.allocated = allocated;
+        self
+    }
+
+    pub fn is_measuring_allocated(&self) -> bool {
+        self.allocated
+    }
+
+    pub fn policy(&self) -> ReparsePolicy {
+        self.policy
+    }
+
+    /// Add files discovered since the last call; returns whether anything was added
+    pub fn ingest(&mut self, mft_files: &[MftFileProgress]) -> bool {
+        self.consumed.resize(mft_files.len(), 0);
+        let mut changed = false;
+        for (file_index, progress) in mft_files.iter().enumerate() {
+            let start = self.consumed[file_index];
+            for file in &progress.files_within[start..] {
+                let link = file.reparse.as_ref().filter(|reparse| reparse.kind().is_link());
+                if link.is_some() && self.policy == ReparsePolicy::Skip {
+                    continue;
+                }
+                // A link's own record holds no data, whatever its target has
+                let size = match (link, self.allocated) {
+                    (Some(_), _) => 0,
+                    (None, true) => file.allocated_size,
+                    (None, false) => file.size,
+                };
+                let path = file.path.to_string_lossy();
+                let mut node = Self::ROOT;
+                self.add_size(file_index, file, size, node);
+                for component in path.split('\\').filter(|c| !c.is_empty()) {
+                    node = self.child_node(node, component);
+                    self.add_size(file_index, file, size, node);
+                }
+                if self.policy == ReparsePolicy::Follow
+                    && let Some(target) = link.and_then(|link| link.target.as_deref())
+                {
+                    let target = if link.is_some_and(|link| link.is_relative()) {
+                        resolve_relative(&path, target)
+                    } else {
+                        target.to_string()
+                    };
+                    self.links.push((node, target, 0));
+                }
+                changed = true;
+            }
+            self.consumed[file_index] = progress.files_within.len();
+        }
+        if changed {
+            self.follow_links();
+        }
+        changed
+    }
+
+    /// Bring every link's size up to date with its target's, which grows as files stream in.
+    /// Only the target's own files are added, so links to links or to an ancestor of the link
+    /// can't feed back into each other.
+    fn follow_links(&mut self) {
+        for index in 0..self.links.len() {
+            let (node, ref target, applied) = self.links[index];
+            let Some(target) = self.find(target) else {
+                continue;
+            };
+            let own = self.nodes[target].size - self.nodes[target].followed;
+            let Some(delta) = own.checked_sub(applied).filter(|delta| *delta > 0) else {
+                continue;
+            };
+            self.links[index].2 = own;
+            let mut current = Some(node);
+            while let Some(n) = current {
+                self.nodes[n].size += delta;
+                self.nodes[n].followed += delta;
+                current = self.nodes[n].parent;
+            }
+        }
+    }
+
+    /// The node at a full path like `C:\Users`, matching names case-insensitively as NTFS does
+    fn find(&self, path: &str) -> Option<usize> {
+        let mut node = Self::ROOT;
+        for component in path.split('\\').filter(|c| !c.is_empty()) {
+            let children = &self.nodes[node].children;
+            node = match children.get(component) {
+                Some(child) => *child,
+                None => *children
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(component))?
+                    .1,
+            };
+        }
+        Some(node)
+    }
+
+    /// Count a file towards a node unless another of its links already was, so a directory
+    /// holding two links to one file isn't charged twice
+    fn add_size(&mut self, file_index: usize, file: &DiscoveredFile, size: u64, node: usize) {
+        if file.hard_link_count > 1 && !self.linked.insert((file_index, file.record_number, node)) {
+            return;
+        }
+        self.nodes[node].size += size;
+    }
+
+    fn child_node(&mut self, parent: usize, name: &str) -> usize {
+        if let Some(child) = self.nodes[parent].children.get(name) {
+            return *child;
+        }
+        let child = self.nodes.len();
+        self.nodes.push(TreeNode {
+            name: name.to_string(),
+            size: 0,
+            followed: 0,
+            parent: Some(parent),
+            children: HashMap::new(),
+        });
+        self.nodes[parent].children.insert(name.to_string(), child);
+        child
+    }
+
+    pub fn is_directory(&self, node: usize) -> bool {
+        !self.nodes[node].children.is_empty()
+    }
+
+    /// Children of a node, largest first
+    pub fn sorted_children(&self, node: usize) -> Vec<usize> {
+        let mut children: Vec<usize> = self.nodes[node].children.values().copied().collect();
+        children.sort_by(|a, b| {
+            self.nodes[*b]
+                .size
+                .cmp(&self.nodes[*a].size)
+                .then_with(|| self.nodes[*a].name.cmp(&self.nodes[*b].name))
+        });
+        children
+    }
+
+    /// Names from the root down to a node
+    pub fn breadcrumb(&self, node: usize) -> String {
+        let mut names = Vec::new();
+        let mut current = Some(node);
+        while let Some(n) = current {
+            names.push(self.nodes[n].name.as_str());
+            current = self.nodes[n].parent;
+        }
+        names.reverse();
+        names.join(" › ")
+    }
+
+    /// Full Windows path of a node below the root, like `C:\Users\Public`
+    pub fn path(&self, node: usize) -> String {
+        let mut names = Vec::new();
+        let mut current = node;
+        while let Some(parent) = self.nodes[current].parent {
+            names.push(self.nodes[current].name.as_str());
+            current = parent;
+        }
+        names.reverse();
+        match names.as_slice() {
+            [drive] => format!("{drive}\\"),
+            _ => names.join("\\"),
+        }
+    }
+}
+
+/// The full path a relative symlink target names, from the link's own full path
+fn resolve_relative(link: &str, target: &str) -> String {
+    let mut components: Vec<&str> = link.split('\\').filter(|c| !c.is_empty()).collect();
+    components.pop();
+    for component in target.split('\\') {
+        match component {
+            "" | "." => {}
+            // Never climb above the drive
+            ".." if components.len() > 1 => {
+                components.pop();
+            }
+            ".." => {}
+            component => components.push(component),
+        }
+    }
+    components.join("\\")
+}
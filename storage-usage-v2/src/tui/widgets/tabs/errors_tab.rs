@@ -1,112 +1,638 @@
+use crate::error_export::ExportedError;
+use crate::error_export::write_errors;
+use crate::tui::progress::ErrorSeverity;
+use crate::tui::progress::MftError;
 use crate::tui::progress::MftFileProgress;
+use crate::tui::theme::theme;
 use crate::tui::widgets::tabs::keyboard_response::KeyboardResponse;
 use ratatui::buffer::Buffer;
 use ratatui::crossterm::event::KeyCode;
 use ratatui::crossterm::event::KeyEvent;
+use ratatui::crossterm::event::KeyModifiers;
+use ratatui::crossterm::event::MouseButton;
+use ratatui::crossterm::event::MouseEvent;
+use ratatui::crossterm::event::MouseEventKind;
+use ratatui::layout::Constraint;
+use ratatui::layout::Flex;
+use ratatui::layout::Layout;
 use ratatui::layout::Rect;
-use ratatui::style::Color;
 use ratatui::style::Style;
 use ratatui::text::Line;
 use ratatui::text::Span;
+use ratatui::widgets::Block;
+use ratatui::widgets::Clear;
 use ratatui::widgets::List;
 use ratatui::widgets::ListItem;
 use ratatui::widgets::Paragraph;
 use ratatui::widgets::Widget;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// Errors sharing a message
+struct ErrorGroup {
+    message: String,
+    severity: ErrorSeverity,
+    /// Positions in `ErrorsTab::filtered`
+    members: Vec<usize>,
+    /// How many MFT files the members come from
+    file_count: usize,
+}
+
+/// A row of the grouped view: a group, or one of the errors of the expanded group
+enum GroupedRow {
+    /// Position in `ErrorsTab::cached_grouped`
+    Group(usize),
+    /// Position in `ErrorsTab::filtered`
+    Member(usize),
+}
 
 pub struct ErrorsTab {
     scroll_offset: usize,
     selected_index: usize,
     show_grouped: bool,
-    cached_grouped: Vec<(String, usize, Vec<usize>)>, // (message, count, indices)
+    /// Only show errors from this MFT file
+    file_filter: Option<usize>,
+    /// Only show errors of this severity
+    severity_filter: Option<ErrorSeverity>,
+    /// Errors passing the filters as (MFT file index, error index)
+    filtered: Vec<(usize, usize)>,
+    cached_grouped: Vec<ErrorGroup>,
+    /// Message of the group whose errors are listed below it
+    expanded: Option<String>,
+    /// Whether the selected errors should be shown in the visualizer on the next frame
+    jump_requested: bool,
+    /// Destination being typed for an export of every collected error
+    export_prompt: Option<String>,
+    /// Destination to write on the next frame, once the errors are at hand
+    export_request: Option<PathBuf>,
+    /// Outcome of the last export
+    status: Option<Line<'static>>,
+    /// Total error count and filters the caches were built for
+    cache_key: Option<(usize, Option<usize>, Option<ErrorSeverity>)>,
+    list_area: Rect, // where the list was drawn last frame, for mouse clicks
 }
 
-impl Default for ErrorsTab { fn default() -> Self { Self::new() } }
+impl Default for ErrorsTab {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl ErrorsTab {
+    pub const KEYBINDINGS: &[(&str, &str)] = &[
+        ("g", "Toggle grouping by message"),
+        ("Enter", "Expand/collapse the selected group's records"),
+        ("v", "Show the selected records in the visualizer"),
+        ("Ctrl+O", "Export all errors to a text or JSON file"),
+        ("f", "Cycle file filter: all, then each MFT file"),
+        ("s", "Cycle severity filter: all, errors, warnings"),
+        ("↑/↓, wheel", "Move selection"),
+        ("Click", "Select row"),
+        ("PgUp/PgDn", "Move by 10"),
+        ("Home/End", "Jump to first/last"),
+    ];
+
     pub fn new() -> Self {
-        Self { scroll_offset: 0, selected_index: 0, show_grouped: true, cached_grouped: Vec::new() }
+        Self {
+            scroll_offset: 0,
+            selected_index: 0,
+            show_grouped: true,
+            file_filter: None,
+            severity_filter: None,
+            filtered: Vec::new(),
+            cached_grouped: Vec::new(),
+            expanded: None,
+            jump_requested: false,
+            export_prompt: None,
+            export_request: None,
+            status: None,
+            cache_key: None,
+            list_area: Rect::default(),
+        }
+    }
+
+    /// Errors collected across every MFT file, for the tab title badge
+    pub fn total_count(mft_files: &[MftFileProgress]) -> usize {
+        mft_files.iter().map(|file| file.errors.len()).sum()
+    }
+
+    /// Whether the export prompt is open and should receive every key, including q and Esc
+    pub fn captures_input(&self) -> bool {
+        self.export_prompt.is_some()
     }
 
     pub fn on_key(&mut self, event: KeyEvent) -> KeyboardResponse {
+        if let Some(mut destination) = self.export_prompt.take() {
+            match event.code {
+                KeyCode::Enter => self.export_request = Some(PathBuf::from(destination.trim())),
+                KeyCode::Esc => self.status = Some(Line::from("Export cancelled")),
+                KeyCode::Backspace => {
+                    destination.pop();
+                    self.export_prompt = Some(destination);
+                }
+                KeyCode::Char(c) => {
+                    destination.push(c);
+                    self.export_prompt = Some(destination);
+                }
+                _ => self.export_prompt = Some(destination),
+            }
+            return KeyboardResponse::Consume;
+        }
+        self.status = None;
         match event.code {
-            KeyCode::Char('g') => { self.show_grouped = !self.show_grouped; KeyboardResponse::Consume }
-            KeyCode::Up => { if self.selected_index>0 { self.selected_index -=1; if self.selected_index < self.scroll_offset { self.scroll_offset = self.selected_index; }} KeyboardResponse::Consume }
-            KeyCode::Down => { self.selected_index = self.selected_index.saturating_add(1); KeyboardResponse::Consume }
-            KeyCode::PageUp => { self.selected_index = self.selected_index.saturating_sub(10); self.scroll_offset = self.scroll_offset.saturating_sub(10); KeyboardResponse::Consume }
-            KeyCode::PageDown => { self.selected_index = self.selected_index.saturating_add(10); KeyboardResponse::Consume }
-            KeyCode::Home => { self.selected_index = 0; self.scroll_offset = 0; KeyboardResponse::Consume }
-            KeyCode::End => { self.selected_index = usize::MAX; KeyboardResponse::Consume }
+            KeyCode::Char('o') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.export_prompt = Some("mft-errors.txt".to_string());
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('g') => {
+                self.show_grouped = !self.show_grouped;
+                self.reset_selection();
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('f') => {
+                // Wraps back to all files in render once past the last file
+                self.file_filter = Some(self.file_filter.map_or(0, |index| index + 1));
+                self.reset_selection();
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('s') => {
+                self.severity_filter = match self.severity_filter {
+                    None => Some(ErrorSeverity::Error),
+                    Some(ErrorSeverity::Error) => Some(ErrorSeverity::Warning),
+                    Some(ErrorSeverity::Warning) => None,
+                };
+                self.reset_selection();
+                KeyboardResponse::Consume
+            }
+            KeyCode::Enter if self.show_grouped => {
+                let rows = self.grouped_rows();
+                let group = match rows.get(self.selected_index) {
+                    Some(GroupedRow::Group(group)) => Some(*group),
+                    // Collapse from any of the expanded group's rows
+                    Some(GroupedRow::Member(_)) => self
+                        .cached_grouped
+                        .iter()
+                        .position(|g| Some(&g.message) == self.expanded.as_ref()),
+                    None => None,
+                };
+                if let Some(group) = group {
+                    let message = &self.cached_grouped[group].message;
+                    if self.expanded.as_ref() == Some(message) {
+                        self.expanded = None;
+                        self.selected_index = group;
+                    } else {
+                        self.expanded = Some(message.clone());
+                    }
+                }
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('v') => {
+                self.jump_requested = true;
+                KeyboardResponse::Consume
+            }
+            KeyCode::Up => {
+                if self.selected_index > 0 {
+                    self.selected_index -= 1;
+                    if self.selected_index < self.scroll_offset {
+                        self.scroll_offset = self.selected_index;
+                    }
+                }
+                KeyboardResponse::Consume
+            }
+            KeyCode::Down => {
+                self.selected_index = self.selected_index.saturating_add(1);
+                KeyboardResponse::Consume
+            }
+            KeyCode::PageUp => {
+                self.selected_index = self.selected_index.saturating_sub(10);
+                self.scroll_offset = self.scroll_offset.saturating_sub(10);
+                KeyboardResponse::Consume
+            }
+            KeyCode::PageDown => {
+                self.selected_index = self.selected_index.saturating_add(10);
+                KeyboardResponse::Consume
+            }
+            KeyCode::Home => {
+                self.selected_index = 0;
+                self.scroll_offset = 0;
+                KeyboardResponse::Consume
+            }
+            KeyCode::End => {
+                self.selected_index = usize::MAX;
+                KeyboardResponse::Consume
+            }
             _ => KeyboardResponse::Pass,
         }
     }
 
-    pub fn render(&mut self, area: Rect, buf: &mut Buffer, mft_files: &[MftFileProgress]) {
-        // Collect errors from all files
-        let mut all_errors: Vec<(usize, &Line<'static>)> = Vec::new();
-        for (file_idx, file) in mft_files.iter().enumerate() {
-            for error in &file.errors {
-                all_errors.push((file_idx, error));
+    pub fn on_mouse(&mut self, event: MouseEvent) {
+        match event.kind {
+            MouseEventKind::ScrollDown => {
+                self.on_key(KeyEvent::from(KeyCode::Down));
+            }
+            MouseEventKind::ScrollUp => {
+                self.on_key(KeyEvent::from(KeyCode::Up));
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                let area = self.list_area;
+                if event.column >= area.x
+                    && event.column < area.x + area.width
+                    && event.row >= area.y
+                    && event.row < area.y + area.height
+                {
+                    // Clamped in render
+                    self.selected_index = self.scroll_offset + (event.row - area.y) as usize;
+                }
             }
+            _ => {}
         }
+    }
+
+    fn reset_selection(&mut self) {
+        self.selected_index = 0;
+        self.scroll_offset = 0;
+    }
 
-        // Rebuild grouped cache if lengths changed
-        if self.cached_grouped.iter().map(|(_,c,_)| *c).sum::<usize>() != all_errors.len() {
-            let mut map: HashMap<String, (usize, Vec<usize>)> = HashMap::new();
-            for (_i,(file_idx, line)) in all_errors.iter().enumerate() {
-                let mut msg = String::new();
-                for span in &line.spans { msg.push_str(&span.content); }
-                // include file index tag to differentiate same error across files? choose not to for grouping identical text
-                let entry = map.entry(msg).or_insert((0, Vec::new()));
-                entry.0 +=1; entry.1.push(*file_idx);
+    /// Groups in order, with the expanded group's errors following it
+    fn grouped_rows(&self) -> Vec<GroupedRow> {
+        let mut rows = Vec::with_capacity(self.cached_grouped.len());
+        for (index, group) in self.cached_grouped.iter().enumerate() {
+            rows.push(GroupedRow::Group(index));
+            if self.expanded.as_ref() == Some(&group.message) {
+                rows.extend(group.members.iter().map(|m| GroupedRow::Member(*m)));
             }
-            self.cached_grouped = map.into_iter().map(|(msg,(count, indices))| (msg, count, indices)).collect();
-            self.cached_grouped.sort_by(|a,b| b.1.cmp(&a.1));
         }
+        rows
+    }
 
-        let header = if self.show_grouped { "Errors (grouped, press 'g' to toggle)" } else { "Errors (raw, press 'g' to toggle)" };
-        Paragraph::new(header).render(Rect { x: area.x, y: area.y, width: area.width, height: 1 }, buf);
+    /// The MFT file and record numbers of the selected row when `v` was pressed. A group
+    /// selects its records in the same file as its first error.
+    pub fn take_jump_request(
+        &mut self,
+        mft_files: &[MftFileProgress],
+    ) -> Option<(usize, Vec<u64>)> {
+        if !std::mem::take(&mut self.jump_requested) {
+            return None;
+        }
+        let positions = if self.show_grouped {
+            match self.grouped_rows().get(self.selected_index)? {
+                GroupedRow::Group(group) => self.cached_grouped[*group].members.clone(),
+                GroupedRow::Member(position) => vec![*position],
+            }
+        } else {
+            vec![self.selected_index]
+        };
+        let (file_index, _) = *self.filtered.get(*positions.first()?)?;
+        let records: Vec<u64> = positions
+            .iter()
+            .filter_map(|position| self.filtered.get(*position))
+            .filter(|(file, _)| *file == file_index)
+            .filter_map(|(file, error)| mft_files[*file].errors[*error].record_number)
+            .collect();
+        (!records.is_empty()).then_some((file_index, records))
+    }
 
-        let list_area = Rect { x: area.x, y: area.y+1, width: area.width, height: area.height.saturating_sub(1) };
+    /// Rebuild the filtered list and groups when errors arrive or the filters change
+    fn refresh(&mut self, mft_files: &[MftFileProgress]) {
+        if self
+            .file_filter
+            .is_some_and(|index| index >= mft_files.len())
+        {
+            self.file_filter = None;
+        }
+        let key = (
+            Self::total_count(mft_files),
+            self.file_filter,
+            self.severity_filter,
+        );
+        if self.cache_key == Some(key) {
+            return;
+        }
+        self.cache_key = Some(key);
 
-        if self.show_grouped { self.render_grouped(list_area, buf, &mft_files); } else { self.render_raw(list_area, buf, &mft_files); }
+        self.filtered = mft_files
+            .iter()
+            .enumerate()
+            .filter(|(file_index, _)| self.file_filter.is_none_or(|f| f == *file_index))
+            .flat_map(|(file_index, file)| {
+                file.errors
+                    .iter()
+                    .enumerate()
+                    .map(move |(error_index, error)| (file_index, error_index, error))
+            })
+            .filter(|(_, _, error)| self.severity_filter.is_none_or(|s| s == error.severity))
+            .map(|(file_index, error_index, _)| (file_index, error_index))
+            .collect();
+
+        let mut groups: HashMap<&str, ErrorGroup> = HashMap::new();
+        let mut files: HashMap<&str, HashSet<usize>> = HashMap::new();
+        for (position, (file_index, error_index)) in self.filtered.iter().enumerate() {
+            let error = &mft_files[*file_index].errors[*error_index];
+            groups
+                .entry(&error.message)
+                .or_insert_with(|| ErrorGroup {
+                    message: error.message.clone(),
+                    severity: error.severity,
+                    members: Vec::new(),
+                    file_count: 0,
+                })
+                .members
+                .push(position);
+            files.entry(&error.message).or_default().insert(*file_index);
+        }
+        self.cached_grouped = groups
+            .into_iter()
+            .map(|(message, mut group)| {
+                group.file_count = files[message].len();
+                group
+            })
+            .collect();
+        self.cached_grouped.sort_by(|a, b| {
+            b.members
+                .len()
+                .cmp(&a.members.len())
+                .then_with(|| a.message.cmp(&b.message))
+        });
     }
 
-    fn render_grouped(&mut self, area: Rect, buf: &mut Buffer, _mft_files: &[MftFileProgress]) {
-        if self.cached_grouped.is_empty() {
-            Paragraph::new("No errors recorded").style(Style::default().fg(Color::Green)).render(area, buf); return;
+    /// Write every collected error, ignoring the filters, to a file
+    fn export(&self, path: &std::path::Path, mft_files: &[MftFileProgress]) -> eyre::Result<usize> {
+        let errors: Vec<ExportedError> = mft_files
+            .iter()
+            .flat_map(|file| {
+                let name = file.path.to_str().unwrap_or("?");
+                file.errors.iter().map(move |error| ExportedError {
+                    file: name,
+                    record_number: error.record_number,
+                    category: error.severity.as_str(),
+                    message: &error.message,
+                })
+            })
+            .collect();
+        write_errors(path, &errors)?;
+        Ok(errors.len())
+    }
+
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer, mft_files: &[MftFileProgress]) {
+        self.refresh(mft_files);
+        if let Some(path) = self.export_request.take() {
+            let line = match self.export(&path, mft_files) {
+                Ok(count) => Line::from(Span::styled(
+                    format!("Exported {count} errors to {}", path.display()),
+                    Style::default().fg(theme().good),
+                )),
+                Err(e) => Line::from(Span::styled(
+                    format!("{e:#}"),
+                    Style::default().fg(theme().bad),
+                )),
+            };
+            self.status = Some(line);
         }
-        let visible_height = area.height as usize;
-        if visible_height==0 { return; }
-        let len = self.cached_grouped.len();
+
+        let mode = if self.show_grouped { "grouped" } else { "raw" };
+        let file = match self.file_filter {
+            Some(index) => file_name(&mft_files[index]),
+            None => "all",
+        };
+        let severity = self.severity_filter.map_or("all", ErrorSeverity::as_str);
+        let header = format!(
+            "Errors ({mode}, g to toggle)  ·  file: {file} (f)  ·  severity: {severity} (s)  ·  {} shown",
+            self.filtered.len()
+        );
+        Paragraph::new(header).render(
+            Rect {
+                x: area.x,
+                y: area.y,
+                width: area.width,
+                height: 1,
+            },
+            buf,
+        );
+
+        let list_area = Rect {
+            x: area.x,
+            y: area.y + 1,
+            width: area.width,
+            height: area.height.saturating_sub(2),
+        };
+        self.list_area = list_area;
+
+        if self.show_grouped {
+            self.render_grouped(list_area, buf, mft_files);
+        } else {
+            self.render_raw(list_area, buf, mft_files);
+        }
+
+        let status = self.status.clone().unwrap_or_else(|| {
+            Line::from(Span::styled(
+                "Enter expand group, v show in visualizer, Ctrl+O export all errors",
+                Style::default().fg(theme().muted),
+            ))
+        });
+        Paragraph::new(status).render(
+            Rect {
+                x: area.x,
+                y: (area.y + area.height).saturating_sub(1),
+                width: area.width,
+                height: 1,
+            },
+            buf,
+        );
+        self.render_export_prompt(area, buf, mft_files);
+    }
+
+    fn render_export_prompt(&self, area: Rect, buf: &mut Buffer, mft_files: &[MftFileProgress]) {
+        let Some(destination) = &self.export_prompt else {
+            return;
+        };
+        let lines = vec![
+            Line::from(format!(
+                "Export all {} errors (.txt, .json or .jsonl) to:",
+                Self::total_count(mft_files)
+            )),
+            Line::from(Span::styled(
+                format!("{destination}_"),
+                Style::default().fg(theme().text),
+            )),
+            Line::default(),
+            Line::from(Span::styled(
+                "Enter to write, Esc to cancel",
+                Style::default().fg(theme().warn),
+            )),
+        ];
+        let [popup] = Layout::vertical([Constraint::Length(lines.len() as u16 + 2)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup] = Layout::horizontal([Constraint::Percentage(70)])
+            .flex(Flex::Center)
+            .areas(popup);
+        Clear.render(popup, buf);
+        Paragraph::new(lines)
+            .block(
+                Block::bordered()
+                    .title("Export errors")
+                    .border_style(Style::default().fg(theme().accent)),
+            )
+            .render(popup, buf);
+    }
+
+    /// Clamp the selection and scroll so the selected row is visible
+    fn clamp(&mut self, len: usize, visible_height: usize) {
         self.selected_index = self.selected_index.min(len.saturating_sub(1));
         let max_scroll = len.saturating_sub(visible_height);
         self.scroll_offset = self.scroll_offset.min(max_scroll);
-        if self.selected_index >= self.scroll_offset + visible_height { self.scroll_offset = self.selected_index - visible_height +1; }
-
-        let items: Vec<ListItem> = self.cached_grouped.iter().enumerate().skip(self.scroll_offset).take(visible_height).map(|(idx,(msg,count, indices))| {
-            let style = if idx==self.selected_index { Style::default().fg(Color::Black).bg(Color::Yellow) } else { Style::default() };
-            let file_count = indices.len();
-            let display = format!("[{count}x across {file_count} file(s)] {msg}");
-            ListItem::new(Line::from(Span::styled(display, style)))
-        }).collect();
+        if self.selected_index >= self.scroll_offset + visible_height {
+            self.scroll_offset = self.selected_index - visible_height + 1;
+        }
+    }
+
+    fn render_empty(&self, area: Rect, buf: &mut Buffer, mft_files: &[MftFileProgress]) {
+        // Files loaded from an index kept only a count of their errors, so none is listed
+        let from_index: Vec<u64> = mft_files
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| self.file_filter.is_none_or(|filter| filter == *index))
+            .filter_map(|(_, file)| file.from_index)
+            .collect();
+        if !from_index.is_empty() {
+            let message = format!(
+                "Loaded from index: errors not collected ({} records failed to parse). Re-sync to parse the dump",
+                from_index.iter().sum::<u64>()
+            );
+            Paragraph::new(message)
+                .style(Style::default().fg(theme().warn))
+                .render(area, buf);
+            return;
+        }
+        let message = if self.file_filter.is_some() || self.severity_filter.is_some() {
+            "No errors match the filters"
+        } else {
+            "No errors recorded"
+        };
+        Paragraph::new(message)
+            .style(Style::default().fg(theme().good))
+            .render(area, buf);
+    }
+
+    fn render_grouped(&mut self, area: Rect, buf: &mut Buffer, mft_files: &[MftFileProgress]) {
+        if self.cached_grouped.is_empty() {
+            self.render_empty(area, buf, mft_files);
+            return;
+        }
+        let visible_height = area.height as usize;
+        if visible_height == 0 {
+            return;
+        }
+        let rows = self.grouped_rows();
+        self.clamp(rows.len(), visible_height);
+
+        let items: Vec<ListItem> = rows
+            .iter()
+            .enumerate()
+            .skip(self.scroll_offset)
+            .take(visible_height)
+            .map(|(idx, row)| {
+                let style = if idx == self.selected_index {
+                    theme().selection()
+                } else {
+                    Style::default()
+                };
+                match row {
+                    GroupedRow::Group(group) => {
+                        let group = &self.cached_grouped[*group];
+                        let marker = if self.expanded.as_ref() == Some(&group.message) {
+                            '▾'
+                        } else {
+                            '▸'
+                        };
+                        let display = format!(
+                            "{marker} [{}x across {} file(s)] {}",
+                            group.members.len(),
+                            group.file_count,
+                            group.message
+                        );
+                        ListItem::new(Line::from(vec![
+                            severity_span(group.severity),
+                            Span::styled(display, style),
+                        ]))
+                    }
+                    GroupedRow::Member(position) => {
+                        let (file_idx, error_idx) = self.filtered[*position];
+                        let error = &mft_files[file_idx].errors[error_idx];
+                        let record = error
+                            .record_number
+                            .map(|record| format!("record #{record}"))
+                            .unwrap_or_else(|| "record unknown".to_string());
+                        let display = format!("    └ {} {record}", file_name(&mft_files[file_idx]));
+                        ListItem::new(Line::from(Span::styled(display, style)))
+                    }
+                }
+            })
+            .collect();
         List::new(items).render(area, buf);
     }
 
     fn render_raw(&mut self, area: Rect, buf: &mut Buffer, mft_files: &[MftFileProgress]) {
-        let mut raw: Vec<(usize,String)> = Vec::new();
-        for (file_idx, file) in mft_files.iter().enumerate() { for line in &file.errors { let mut msg=String::new(); for span in &line.spans { msg.push_str(&span.content); } raw.push((file_idx, msg)); }}
-        if raw.is_empty() { Paragraph::new("No errors recorded").style(Style::default().fg(Color::Green)).render(area, buf); return; }
-        let visible_height = area.height as usize; if visible_height==0 { return; }
-        let len = raw.len(); self.selected_index = self.selected_index.min(len.saturating_sub(1));
-        let max_scroll = len.saturating_sub(visible_height); self.scroll_offset = self.scroll_offset.min(max_scroll);
-        if self.selected_index >= self.scroll_offset + visible_height { self.scroll_offset = self.selected_index - visible_height +1; }
-        let items: Vec<ListItem> = raw.iter().enumerate().skip(self.scroll_offset).take(visible_height).map(|(idx,(file_idx,msg))| {
-            let style = if idx==self.selected_index { Style::default().fg(Color::Black).bg(Color::Yellow) } else { Style::default() };
-            let file_name = mft_files[*file_idx].path.file_name().and_then(|n| n.to_str()).unwrap_or("?");
-            let display = format!("[{file_name}] {msg}");
-            ListItem::new(Line::from(Span::styled(display, style)))
-        }).collect();
+        if self.filtered.is_empty() {
+            self.render_empty(area, buf, mft_files);
+            return;
+        }
+        let visible_height = area.height as usize;
+        if visible_height == 0 {
+            return;
+        }
+        self.clamp(self.filtered.len(), visible_height);
+
+        let items: Vec<ListItem> = self
+            .filtered
+            .iter()
+            .enumerate()
+            .skip(self.scroll_offset)
+            .take(visible_height)
+            .map(|(idx, (file_idx, error_idx))| {
+                let style = if idx == self.selected_index {
+                    theme().selection()
+                } else {
+                    Style::default()
+                };
+                let error = &mft_files[*file_idx].errors[*error_idx];
+                let display = format!(
+                    "[{}{}] {}",
+                    file_name(&mft_files[*file_idx]),
+                    record_suffix(error),
+                    error.message
+                );
+                ListItem::new(Line::from(vec![
+                    severity_span(error.severity),
+                    Span::styled(display, style),
+                ]))
+            })
+            .collect();
         List::new(items).render(area, buf);
     }
 }
+
+fn file_name(file: &MftFileProgress) -> &str {
+    file.path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("?")
+}
+
+/// ` #123` when the record number is known
+fn record_suffix(error: &MftError) -> String {
+    error
+        .record_number
+        .map(|record| format!(" #{record}"))
+        .unwrap_or_default()
+}
+
+/// Colored marker leading each row
+fn severity_span(severity: ErrorSeverity) -> Span<'static> {
+    let (marker, color) = match severity {
+        ErrorSeverity::Warning => ("W ", theme().warn),
+        ErrorSeverity::Error => ("E ", theme().bad),
+    };
+    Span::styled(marker, Style::default().fg(color))
+}
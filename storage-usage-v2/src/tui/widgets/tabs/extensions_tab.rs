@@ -0,0 +1,185 @@
+use crate::file_flags::FileFlag;
+use crate::tui::progress::MftFileProgress;
+use crate::tui::theme::theme;
+use crate::tui::widgets::tabs::keyboard_response::KeyboardResponse;
+use humansize::DECIMAL;
+use ratatui::buffer::Buffer;
+use ratatui::crossterm::event::KeyCode;
+use ratatui::crossterm::event::KeyEvent;
+use ratatui::layout::Constraint;
+use ratatui::layout::Direction;
+use ratatui::layout::Layout;
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::text::Line;
+use ratatui::widgets::Bar;
+use ratatui::widgets::BarChart;
+use ratatui::widgets::BarGroup;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::Widget;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+/// Bucket for files whose name has no extension
+const NO_EXTENSION: &str = "(none)";
+
+#[derive(Default, Clone, Copy)]
+struct ExtensionTotals {
+    count: u64,
+    bytes: u64,
+}
+
+/// Count and total size of discovered files per extension, as a bar chart
+pub struct ExtensionsTab {
+    totals: HashMap<String, ExtensionTotals>,
+    /// How many of each MFT file's discovered files are already counted
+    consumed: Vec<usize>,
+    /// (MFT file, record) of hard-linked files already counted under their first name
+    linked: HashSet<(usize, u64)>,
+    /// Rank by file count instead of bytes
+    by_count: bool,
+    scroll_offset: usize,
+}
+
+impl Default for ExtensionsTab {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExtensionsTab {
+    pub const KEYBINDINGS: &[(&str, &str)] = &[
+        ("↑/↓", "Scroll"),
+        ("Home", "Back to the top"),
+        ("c", "Toggle ranking by bytes or file count"),
+    ];
+
+    pub fn new() -> Self {
+        Self {
+            totals: HashMap::new(),
+            consumed: Vec::new(),
+            linked: HashSet::new(),
+            by_count: false,
+            scroll_offset: 0,
+        }
+    }
+
+    pub fn on_key(&mut self, event: KeyEvent) -> KeyboardResponse {
+        match event.code {
+            KeyCode::Up => {
+                self.scroll_offset = self.scroll_offset.saturating_sub(1);
+                KeyboardResponse::Consume
+            }
+            KeyCode::Down => {
+                self.scroll_offset += 1; // Clamped in render
+                KeyboardResponse::Consume
+            }
+            KeyCode::Home => {
+                self.scroll_offset = 0;
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('c') => {
+                self.by_count = !self.by_count;
+                self.scroll_offset = 0;
+                KeyboardResponse::Consume
+            }
+            _ => KeyboardResponse::Pass,
+        }
+    }
+
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer, mft_files: &[MftFileProgress]) {
+        self.ingest(mft_files);
+
+        let [header_area, chart_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(area);
+
+        let total_bytes: u64 = self.totals.values().map(|t| t.bytes).sum();
+        let total_count: u64 = self.totals.values().map(|t| t.count).sum();
+        let header = format!(
+            "{} extensions, {} files, {}  (ranked by {}, c to toggle, ↑↓ scroll)",
+            self.totals.len(),
+            total_count,
+            humansize::format_size(total_bytes, DECIMAL),
+            if self.by_count { "file count" } else { "bytes" }
+        );
+        Paragraph::new(header)
+            .style(Style::default().fg(theme().text))
+            .render(header_area, buf);
+
+        if self.totals.is_empty() {
+            Paragraph::new("No files discovered yet.")
+                .style(Style::default().fg(theme().muted))
+                .render(chart_area, buf);
+            return;
+        }
+
+        let mut rows: Vec<(&String, ExtensionTotals)> =
+            self.totals.iter().map(|(ext, t)| (ext, *t)).collect();
+        let key = |t: &ExtensionTotals| if self.by_count { t.count } else { t.bytes };
+        rows.sort_by(|a, b| key(&b.1).cmp(&key(&a.1)).then_with(|| a.0.cmp(b.0)));
+
+        // One bar per row, no gaps
+        let visible = chart_area.height as usize;
+        self.scroll_offset = self.scroll_offset.min(rows.len().saturating_sub(visible));
+        let palette = theme().palette;
+        let bars: Vec<Bar> = rows
+            .iter()
+            .enumerate()
+            .skip(self.scroll_offset)
+            .take(visible)
+            .map(|(rank, (ext, totals))| {
+                let share = if total_bytes > 0 {
+                    totals.bytes as f64 * 100.0 / total_bytes as f64
+                } else {
+                    0.0
+                };
+                let label = if ext.as_str() == NO_EXTENSION {
+                    ext.to_string()
+                } else {
+                    format!(".{ext}")
+                };
+                Bar::default()
+                    .label(Line::from(label))
+                    .value(key(totals))
+                    .text_value(format!(
+                        "{} in {} files ({share:.1}%)",
+                        humansize::format_size(totals.bytes, DECIMAL),
+                        totals.count
+                    ))
+                    .style(Style::default().fg(palette[rank % palette.len()]))
+            })
+            .collect();
+        BarChart::default()
+            .direction(Direction::Horizontal)
+            .bar_width(1)
+            .bar_gap(0)
+            .value_style(Style::default().fg(theme().text))
+            .data(BarGroup::default().bars(&bars))
+            .render(chart_area, buf);
+    }
+
+    /// Count files discovered since the last frame
+    fn ingest(&mut self, mft_files: &[MftFileProgress]) {
+        self.consumed.resize(mft_files.len(), 0);
+        for (file_index, progress) in mft_files.iter().enumerate() {
+            let start = self.consumed[file_index];
+            for file in &progress.files_within[start..] {
+                if file.flags.contains(FileFlag::Directory) {
+                    continue;
+                }
+                if file.hard_link_count > 1 && !self.linked.insert((file_index, file.record_number)) {
+                    continue;
+                }
+                let extension = file
+                    .path
+                    .extension()
+                    .map(|e| e.to_string_lossy().to_ascii_lowercase())
+                    .unwrap_or_else(|| NO_EXTENSION.to_string());
+                let totals = self.totals.entry(extension).or_default();
+                totals.count += 1;
+                totals.bytes += file.size;
+            }
+            self.consumed[file_index] = progress.files_within.len();
+        }
+    }
+}
@@ -0,0 +1,370 @@
+use crate::path_resolver::ORPHANED;
+use crate::tui::theme::theme;
+use crate::tui::widgets::tabs::keyboard_response::KeyboardResponse;
+use crate::win_shell::RecycleOutcome;
+use ratatui::buffer::Buffer;
+use ratatui::crossterm::event::KeyCode;
+use ratatui::crossterm::event::KeyEvent;
+use ratatui::crossterm::event::KeyModifiers;
+use ratatui::layout::Constraint;
+use ratatui::layout::Flex;
+use ratatui::layout::Layout;
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::Block;
+use ratatui::widgets::Clear;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::Widget;
+use ratatui::widgets::Wrap;
+use std::fs::Metadata;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::Instant;
+
+/// The slow steps of a delete, run off the UI thread so it keeps drawing
+#[derive(Debug, Clone)]
+pub enum FileJob {
+    /// Read the selected files' sizes, walking the tree below each directory
+    Measure(Vec<(PathBuf, u64)>),
+    /// Send confirmed files to the Recycle Bin; `changed` more were left because they changed
+    Recycle { paths: Vec<PathBuf>, changed: usize },
+}
+
+/// What a [`FileJob`] found, with errors as text so it can travel as a message
+#[derive(Debug, Clone)]
+pub enum FileJobResult {
+    Measured(Result<Vec<(PathBuf, u64)>, String>),
+    Recycled {
+        outcomes: Result<Vec<RecycleOutcome>, String>,
+        changed: usize,
+    },
+}
+
+impl FileJob {
+    pub fn run(self) -> FileJobResult {
+        match self {
+            FileJob::Measure(selected) => {
+                FileJobResult::Measured(pending_delete(&selected).map_err(|e| format!("{e:#}")))
+            }
+            FileJob::Recycle { paths, changed } => FileJobResult::Recycled {
+                outcomes: crate::win_shell::move_to_recycle_bin(&paths)
+                    .map_err(|e| format!("{e:#}")),
+                changed,
+            },
+        }
+    }
+}
+
+/// Reveal, copy and delete actions for tabs that list files, along with the status line that
+/// reports their outcome and the popup confirming a delete
+#[derive(Default)]
+pub struct FileActions {
+    /// Feedback for the last action, shown until it expires
+    status: Option<(Line<'static>, Instant)>,
+    /// Files waiting for the user to confirm sending them to the Recycle Bin, with their size on
+    /// disk when asked; a directory's is everything below it
+    pending_delete: Option<Vec<(PathBuf, u64)>>,
+    /// A job waiting for the app to start it
+    job: Option<FileJob>,
+    /// What the running job is doing, shown in place of the status until it's done
+    working: Option<&'static str>,
+}
+
+impl FileActions {
+    pub const HINT: &str =
+        "Ctrl+E reveal in Explorer, Ctrl+Y copy path, Ctrl+D send to Recycle Bin";
+
+    /// Whether the delete confirmation is open and should receive every key
+    pub fn captures_input(&self) -> bool {
+        self.pending_delete.is_some()
+    }
+
+    /// Answer the delete confirmation if it's open, otherwise handle Ctrl+E/Y/D for the
+    /// selected files as (path, size) pairs. Other keys are passed back to the tab.
+    pub fn on_key(&mut self, event: KeyEvent, selected: &[(PathBuf, u64)]) -> KeyboardResponse {
+        if let Some(pending) = self.pending_delete.take() {
+            match event.code {
+                KeyCode::Char('y') | KeyCode::Enter => {
+                    // The disk can have changed while the confirmation was open
+                    let (paths, changed): (Vec<_>, Vec<_>) =
+                        pending.into_iter().partition(|(path, size)| {
+                            check_deletable(path)
+                                .is_ok_and(|metadata| metadata.is_dir() || metadata.len() == *size)
+                        });
+                    let paths: Vec<PathBuf> = paths.into_iter().map(|(path, _)| path).collect();
+                    self.start(
+                        FileJob::Recycle {
+                            paths,
+                            changed: changed.len(),
+                        },
+                        "Recycling…",
+                    );
+                }
+                _ => self.set_status(Line::from("Delete cancelled")),
+            }
+            return KeyboardResponse::Consume;
+        }
+        if !event.modifiers.contains(KeyModifiers::CONTROL) {
+            return KeyboardResponse::Pass;
+        }
+        match event.code {
+            KeyCode::Char('d') => {
+                if !selected.is_empty() && self.working.is_none() {
+                    self.start(FileJob::Measure(selected.to_vec()), "Measuring…");
+                }
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('e') => {
+                if let Some((path, _)) = selected.first() {
+                    let result = crate::win_shell::reveal_in_explorer(path);
+                    self.report(result, format!("Revealed {}", path.display()));
+                }
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('y') => {
+                if !selected.is_empty() {
+                    let text = selected
+                        .iter()
+                        .map(|(path, _)| path.to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join("\r\n");
+                    let result = crate::win_shell::copy_to_clipboard(&text);
+                    let message = match selected {
+                        [(path, _)] => format!("Copied {}", path.display()),
+                        _ => format!("Copied {} paths", selected.len()),
+                    };
+                    self.report(result, message);
+                }
+                KeyboardResponse::Consume
+            }
+            _ => KeyboardResponse::Pass,
+        }
+    }
+
+    fn start(&mut self, job: FileJob, working: &'static str) {
+        self.job = Some(job);
+        self.working = Some(working);
+    }
+
+    /// The job the user asked for, for the app to run on another thread
+    pub fn take_job(&mut self) -> Option<FileJob> {
+        self.job.take()
+    }
+
+    /// Take in a job's result: ask to confirm what was measured, or report what was recycled.
+    /// Results for a job this doesn't know of, such as one started before the tab was reset,
+    /// are dropped.
+    pub fn finish_job(&mut self, result: FileJobResult) {
+        if self.working.take().is_none() {
+            return;
+        }
+        match result {
+            FileJobResult::Measured(Ok(pending)) => self.pending_delete = Some(pending),
+            FileJobResult::Recycled {
+                outcomes: Ok(outcomes),
+                changed,
+            } => self.report_recycled(&outcomes, changed),
+            FileJobResult::Measured(Err(e))
+            | FileJobResult::Recycled {
+                outcomes: Err(e), ..
+            } => self.report(Err(eyre::eyre!(e)), String::new()),
+        }
+    }
+
+    pub fn set_status(&mut self, line: Line<'static>) {
+        self.status = Some((line, Instant::now()));
+    }
+
+    /// Show the outcome of an action in the status line
+    pub fn report(&mut self, result: eyre::Result<()>, success: String) {
+        let line = match result {
+            Ok(()) => Line::from(Span::styled(success, Style::default().fg(theme().good))),
+            Err(e) => Line::from(Span::styled(
+                format!("{e:#}"),
+                Style::default().fg(theme().bad),
+            )),
+        };
+        self.set_status(line);
+    }
+
+    /// Show what the shell did with each confirmed item, warning when any weren't recycled
+    fn report_recycled(&mut self, outcomes: &[RecycleOutcome], changed: usize) {
+        let count = |wanted| {
+            outcomes
+                .iter()
+                .filter(|outcome| **outcome == wanted)
+                .count()
+        };
+        let (recycled, permanent, failed) = (
+            count(RecycleOutcome::Recycled),
+            count(RecycleOutcome::DeletedPermanently),
+            count(RecycleOutcome::Failed),
+        );
+        let mut message = format!("Moved {recycled} item(s) to the Recycle Bin");
+        if permanent > 0 {
+            message += &format!("; {permanent} too big for it were deleted permanently");
+        }
+        if failed > 0 {
+            message += &format!("; {failed} couldn't be removed");
+        }
+        if changed > 0 {
+            message += &format!("; left {changed} that changed or are gone");
+        }
+        let color = if permanent > 0 || failed > 0 {
+            theme().warn
+        } else {
+            theme().good
+        };
+        self.set_status(Line::from(Span::styled(
+            message,
+            Style::default().fg(color),
+        )));
+    }
+
+    /// The last action's outcome while it's fresh, otherwise the given hint
+    pub fn render_status(&mut self, area: Rect, buf: &mut Buffer, hint: &str) {
+        if self
+            .status
+            .as_ref()
+            .is_some_and(|(_, shown)| shown.elapsed() > Duration::from_secs(4))
+        {
+            self.status = None;
+        }
+        let line = match (&self.status, self.working) {
+            (_, Some(working)) => Line::from(Span::styled(
+                working.to_string(),
+                Style::default().fg(theme().warn),
+            )),
+            (Some((line, _)), None) => line.clone(),
+            (None, None) => Line::from(Span::styled(
+                hint.to_string(),
+                Style::default().fg(theme().muted),
+            )),
+        };
+        Paragraph::new(line).render(area, buf);
+    }
+
+    pub fn render_delete_confirmation(&self, area: Rect, buf: &mut Buffer) {
+        let Some(pending) = &self.pending_delete else {
+            return;
+        };
+        let total: u64 = pending.iter().map(|(_, size)| size).sum();
+        let mut lines = vec![
+            Line::from(format!(
+                "Send {} item(s) ({}) to the Recycle Bin?",
+                pending.len(),
+                humansize::format_size(total, humansize::DECIMAL)
+            )),
+            Line::default(),
+        ];
+        lines.extend(pending.iter().take(8).map(|(path, size)| {
+            Line::from(format!(
+                "{}  {}",
+                humansize::format_size(*size, humansize::DECIMAL),
+                path.display()
+            ))
+        }));
+        if pending.len() > 8 {
+            lines.push(Line::from(format!("... and {} more", pending.len() - 8)));
+        }
+        lines.push(Line::default());
+        lines.push(Line::from(Span::styled(
+            "y/Enter to confirm, any other key to cancel",
+            Style::default().fg(theme().warn),
+        )));
+
+        let [popup] = Layout::vertical([Constraint::Length(lines.len() as u16 + 2)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup] = Layout::horizontal([Constraint::Percentage(70)])
+            .flex(Flex::Center)
+            .areas(popup);
+        Clear.render(popup, buf);
+        Paragraph::new(lines)
+            .block(
+                Block::bordered()
+                    .title("Confirm delete")
+                    .border_style(Style::default().fg(theme().bad)),
+            )
+            .wrap(Wrap { trim: false })
+            .render(popup, buf);
+    }
+}
+
+/// The selected files as they are on disk now, or why one of them can't be deleted. Sizes are
+/// read again, a directory's being the total of the files below it.
+fn pending_delete(selected: &[(PathBuf, u64)]) -> eyre::Result<Vec<(PathBuf, u64)>> {
+    selected
+        .iter()
+        .map(|(path, _)| {
+            let metadata = check_deletable(path)?;
+            let size = if metadata.is_dir() {
+                directory_size(path)
+            } else {
+                metadata.len()
+            };
+            Ok((path.clone(), size))
+        })
+        .collect()
+}
+
+/// The metadata of a path that's safe to send to the Recycle Bin. Paths from a dump without a
+/// drive letter start at `\`, and those whose parents were lost start at [`ORPHANED`]; neither
+/// says where the file really is, so both are refused.
+fn check_deletable(path: &Path) -> eyre::Result<Metadata> {
+    if let Some(reason) = refusal(path) {
+        return Err(eyre::eyre!("Not deleting {}: {reason}", path.display()));
+    }
+    std::fs::symlink_metadata(path).map_err(|e| eyre::eyre!("Not deleting {}: {e}", path.display()))
+}
+
+fn refusal(path: &Path) -> Option<&'static str> {
+    let text = path.to_string_lossy();
+    let bytes = text.as_bytes();
+    if bytes.len() < 4 || !bytes[0].is_ascii_alphabetic() || &bytes[1..3] != b":\\" {
+        return Some("not a full path below a drive root");
+    }
+    if text.split('\\').any(|component| component == ORPHANED) {
+        return Some("its parent directory is unknown");
+    }
+    None
+}
+
+/// Bytes of the files below a directory on disk, not following links
+fn directory_size(path: &Path) -> u64 {
+    let mut total = 0;
+    let mut pending = vec![path.to_path_buf()];
+    while let Some(directory) = pending.pop() {
+        let Ok(children) = std::fs::read_dir(&directory) else {
+            continue;
+        };
+        for child in children.flatten() {
+            let Ok(file_type) = child.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                pending.push(child.path());
+            } else if file_type.is_file() {
+                total += child.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+            }
+        }
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::refusal;
+    use std::path::Path;
+
+    #[test]
+    fn refuses_paths_without_a_known_place() {
+        assert_eq!(refusal(Path::new(r"C:\Users\me\a.txt")), None);
+        assert!(refusal(Path::new(r"\Users\me\a.txt")).is_some());
+        assert!(refusal(Path::new(r"C:\")).is_some());
+        assert!(refusal(Path::new(r"C:\<orphaned>\lost\a.txt")).is_some());
+    }
+}
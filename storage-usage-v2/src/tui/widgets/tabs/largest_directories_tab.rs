@@ -0,0 +1,250 @@
+use crate::reparse::ReparsePolicy;
+use crate::tui::progress::MftFileProgress;
+use crate::tui::theme::theme;
+use crate::tui::widgets::tabs::directory_tree::DirectoryTree;
+use crate::tui::widgets::tabs::keyboard_response::KeyboardResponse;
+use humansize::DECIMAL;
+use ratatui::buffer::Buffer;
+use ratatui::crossterm::event::KeyCode;
+use ratatui::crossterm::event::KeyEvent;
+use ratatui::layout::Constraint;
+use ratatui::layout::Layout;
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::List;
+use ratatui::widgets::ListItem;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::Widget;
+use std::time::Duration;
+use std::time::Instant;
+
+/// How many of the largest directories are listed
+const TOP_N: usize = 500;
+
+/// Ranking every directory is linear in the tree size, so it's redone at most this often
+const RANK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The directories with the most bytes below them, with drill-down into their children
+pub struct LargestDirectoriesTab {
+    tree: DirectoryTree,
+    /// Largest directories first, as tree nodes
+    ranked: Vec<usize>,
+    ranked_at: Option<Instant>,
+    /// Whether files were added since the last ranking
+    stale: bool,
+    /// Directory whose children are listed, or `None` for the ranking
+    current: Option<usize>,
+    selected_index: usize,
+    scroll_offset: usize,
+    visible_height: usize,
+}
+
+impl Default for LargestDirectoriesTab {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LargestDirectoriesTab {
+    pub const KEYBINDINGS: &[(&str, &str)] = &[
+        ("↑/↓", "Move selection"),
+        ("PgUp/PgDn", "Move by a page"),
+        ("Home/End", "Jump to first/last"),
+        ("Enter", "List the selected directory's children"),
+        ("Backspace", "Go up a level, then back to the ranking"),
+    ];
+
+    pub fn new() -> Self {
+        Self {
+            tree: DirectoryTree::new(),
+            ranked: Vec::new(),
+            ranked_at: None,
+            stale: false,
+            current: None,
+            selected_index: 0,
+            scroll_offset: 0,
+            visible_height: 20,
+        }
+    }
+
+    /// Roll symlinks and junctions up as `policy` says, starting the tree over when it changes
+    pub fn set_reparse_policy(&mut self, policy: ReparsePolicy) {
+        if self.tree.policy() != policy {
+            *self = Self {
+                tree: DirectoryTree::with_policy(policy),
+                ..Self::new()
+            };
+        }
+    }
+
+    /// Selected row of the ranking, or 0 while drilled into a directory
+    pub fn selected_row(&self) -> usize {
+        match self.current {
+            Some(_) => 0,
+            None => self.selected_index,
+        }
+    }
+
+    /// Select a row of the ranking; clamped to the list and scrolled into view in render
+    pub fn select_row(&mut self, row: usize) {
+        self.current = None;
+        self.selected_index = row;
+    }
+
+    pub fn on_key(&mut self, event: KeyEvent) -> KeyboardResponse {
+        match event.code {
+            KeyCode::Up => {
+                self.selected_index = self.selected_index.saturating_sub(1);
+                KeyboardResponse::Consume
+            }
+            KeyCode::Down => {
+                self.selected_index += 1; // Clamped in render
+                KeyboardResponse::Consume
+            }
+            KeyCode::PageUp => {
+                self.selected_index = self.selected_index.saturating_sub(self.visible_height);
+                KeyboardResponse::Consume
+            }
+            KeyCode::PageDown => {
+                self.selected_index += self.visible_height;
+                KeyboardResponse::Consume
+            }
+            KeyCode::Home => {
+                self.selected_index = 0;
+                KeyboardResponse::Consume
+            }
+            KeyCode::End => {
+                self.selected_index = usize::MAX; // Clamped in render
+                KeyboardResponse::Consume
+            }
+            KeyCode::Enter => {
+                if let Some(node) = self.rows().get(self.selected_index).copied()
+                    && self.tree.is_directory(node)
+                {
+                    self.current = Some(node);
+                    self.selected_index = 0;
+                    self.scroll_offset = 0;
+                }
+                KeyboardResponse::Consume
+            }
+            KeyCode::Backspace => {
+                if let Some(current) = self.current {
+                    let parent = self.tree.nodes[current]
+                        .parent
+                        .filter(|parent| *parent != DirectoryTree::ROOT);
+                    self.current = parent;
+                    self.selected_index =
+                        self.rows().iter().position(|n| *n == current).unwrap_or(0);
+                }
+                KeyboardResponse::Consume
+            }
+            _ => KeyboardResponse::Pass,
+        }
+    }
+
+    /// Nodes listed in the current view
+    fn rows(&self) -> Vec<usize> {
+        match self.current {
+            Some(directory) => self.tree.sorted_children(directory),
+            None => self.ranked.clone(),
+        }
+    }
+
+    /// Rank directories by rolled-up size, skipping the root and drives
+    fn rank(&mut self) {
+        let mut directories: Vec<usize> = (0..self.tree.nodes.len())
+            .filter(|node| {
+                self.tree.is_directory(*node)
+                    && self.tree.nodes[*node]
+                        .parent
+                        .is_some_and(|parent| parent != DirectoryTree::ROOT)
+            })
+            .collect();
+        let by_size_desc =
+            |a: &usize, b: &usize| self.tree.nodes[*b].size.cmp(&self.tree.nodes[*a].size);
+        if directories.len() > TOP_N {
+            directories.select_nth_unstable_by(TOP_N, by_size_desc);
+            directories.truncate(TOP_N);
+        }
+        directories.sort_by(by_size_desc);
+        self.ranked = directories;
+        self.ranked_at = Some(Instant::now());
+        self.stale = false;
+    }
+
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer, mft_files: &[MftFileProgress]) {
+        self.stale |= self.tree.ingest(mft_files);
+        if self.stale
+            && self
+                .ranked_at
+                .is_none_or(|at| at.elapsed() >= RANK_INTERVAL)
+        {
+            self.rank();
+        }
+
+        let [header_area, list_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(area);
+        self.visible_height = list_area.height as usize;
+
+        let header = match self.current {
+            Some(directory) => format!(
+                "{}  {}  (Enter open, Backspace up)",
+                self.tree.path(directory),
+                humansize::format_size(self.tree.nodes[directory].size, DECIMAL)
+            ),
+            None => format!(
+                "Largest {} directories by rolled-up size  (Enter to list children)",
+                self.ranked.len()
+            ),
+        };
+        Paragraph::new(header)
+            .style(Style::default().fg(theme().text))
+            .render(header_area, buf);
+
+        let rows = self.rows();
+        if rows.is_empty() {
+            Paragraph::new("No directories discovered yet.")
+                .style(Style::default().fg(theme().muted))
+                .render(list_area, buf);
+            return;
+        }
+
+        self.selected_index = self.selected_index.min(rows.len() - 1);
+        if self.selected_index < self.scroll_offset {
+            self.scroll_offset = self.selected_index;
+        } else if self.selected_index >= self.scroll_offset + self.visible_height {
+            self.scroll_offset = self.selected_index + 1 - self.visible_height;
+        }
+
+        let items: Vec<ListItem> = rows
+            .iter()
+            .enumerate()
+            .skip(self.scroll_offset)
+            .take(self.visible_height)
+            .map(|(index, node)| {
+                let style = if index == self.selected_index {
+                    theme().selection()
+                } else {
+                    Style::default().fg(theme().text)
+                };
+                let name = match self.current {
+                    Some(_) if self.tree.is_directory(*node) => {
+                        format!("{}\\", self.tree.nodes[*node].name)
+                    }
+                    Some(_) => self.tree.nodes[*node].name.clone(),
+                    None => self.tree.path(*node),
+                };
+                ListItem::new(Line::from(Span::styled(
+                    format!(
+                        "{:>10}  {name}",
+                        humansize::format_size(self.tree.nodes[*node].size, DECIMAL)
+                    ),
+                    style,
+                )))
+            })
+            .collect();
+        List::new(items).render(list_area, buf);
+    }
+}
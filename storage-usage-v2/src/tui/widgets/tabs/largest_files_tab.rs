@@ -0,0 +1,227 @@
+use crate::file_flags::FileFlag;
+use crate::tui::progress::MftFileProgress;
+use crate::tui::theme::theme;
+use crate::tui::widgets::tabs::file_actions::FileActions;
+use crate::tui::widgets::tabs::keyboard_response::KeyboardResponse;
+use humansize::DECIMAL;
+use ratatui::buffer::Buffer;
+use ratatui::crossterm::event::KeyCode;
+use ratatui::crossterm::event::KeyEvent;
+use ratatui::layout::Constraint;
+use ratatui::layout::Layout;
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::List;
+use ratatui::widgets::ListItem;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::Widget;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// How many of the largest files are kept
+const TOP_N: usize = 1000;
+
+/// The largest files discovered so far, kept in a bounded min-heap as the workers stream entries
+pub struct LargestFilesTab {
+    /// Smallest of the kept files on top, so it's the one evicted by a larger newcomer
+    heap: BinaryHeap<Reverse<(u64, PathBuf, u16)>>,
+    /// The heap's contents largest first, rebuilt when it changes
+    sorted: Vec<(u64, PathBuf, u16)>,
+    /// How many of each MFT file's discovered files have been considered
+    consumed: Vec<usize>,
+    /// (MFT file, record) of hard-linked files already offered under their first name, so a
+    /// file isn't listed once per link
+    linked: HashSet<(usize, u64)>,
+    selected_index: usize,
+    scroll_offset: usize,
+    visible_height: usize,
+    actions: FileActions,
+}
+
+impl Default for LargestFilesTab {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LargestFilesTab {
+    pub const KEYBINDINGS: &[(&str, &str)] = &[
+        ("↑/↓", "Move selection"),
+        ("PgUp/PgDn", "Move by a page"),
+        ("Home/End", "Jump to first/last"),
+        ("Ctrl+E", "Reveal in Explorer"),
+        ("Ctrl+Y", "Copy path"),
+        ("Ctrl+D", "Send to Recycle Bin"),
+    ];
+
+    pub fn new() -> Self {
+        Self {
+            heap: BinaryHeap::with_capacity(TOP_N + 1),
+            sorted: Vec::new(),
+            consumed: Vec::new(),
+            linked: HashSet::new(),
+            selected_index: 0,
+            scroll_offset: 0,
+            visible_height: 20,
+            actions: FileActions::default(),
+        }
+    }
+
+    /// Whether the delete confirmation is open and should receive every key
+    pub fn captures_input(&self) -> bool {
+        self.actions.captures_input()
+    }
+
+    pub fn selected_row(&self) -> usize {
+        self.selected_index
+    }
+
+    pub fn actions_mut(&mut self) -> &mut FileActions {
+        &mut self.actions
+    }
+
+    /// Select a row; clamped to the list and scrolled into view in render
+    pub fn select_row(&mut self, row: usize) {
+        self.selected_index = row;
+    }
+
+    pub fn on_key(&mut self, event: KeyEvent) -> KeyboardResponse {
+        let selected: Vec<(PathBuf, u64)> = self
+            .sorted
+            .get(self.selected_index)
+            .map(|(size, path, _)| (path.clone(), *size))
+            .into_iter()
+            .collect();
+        if let KeyboardResponse::Consume = self.actions.on_key(event, &selected) {
+            return KeyboardResponse::Consume;
+        }
+        match event.code {
+            KeyCode::Up => {
+                self.selected_index = self.selected_index.saturating_sub(1);
+                KeyboardResponse::Consume
+            }
+            KeyCode::Down => {
+                self.selected_index += 1; // Clamped in render
+                KeyboardResponse::Consume
+            }
+            KeyCode::PageUp => {
+                self.selected_index = self.selected_index.saturating_sub(self.visible_height);
+                KeyboardResponse::Consume
+            }
+            KeyCode::PageDown => {
+                self.selected_index += self.visible_height;
+                KeyboardResponse::Consume
+            }
+            KeyCode::Home => {
+                self.selected_index = 0;
+                KeyboardResponse::Consume
+            }
+            KeyCode::End => {
+                self.selected_index = self.sorted.len().saturating_sub(1);
+                KeyboardResponse::Consume
+            }
+            _ => KeyboardResponse::Pass,
+        }
+    }
+
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer, mft_files: &[MftFileProgress]) {
+        self.ingest(mft_files);
+
+        let [header_area, list_area, status_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+        self.visible_height = list_area.height as usize;
+
+        let total: u64 = self.sorted.iter().map(|(size, _, _)| size).sum();
+        Paragraph::new(format!(
+            "Largest {} files: {}",
+            self.sorted.len(),
+            humansize::format_size(total, DECIMAL)
+        ))
+        .style(Style::default().fg(theme().text))
+        .render(header_area, buf);
+        self.actions.render_status(status_area, buf, FileActions::HINT);
+
+        if self.sorted.is_empty() {
+            Paragraph::new("No files discovered yet.")
+                .style(Style::default().fg(theme().muted))
+                .render(list_area, buf);
+            return;
+        }
+
+        self.selected_index = self.selected_index.min(self.sorted.len() - 1);
+        if self.selected_index < self.scroll_offset {
+            self.scroll_offset = self.selected_index;
+        } else if self.selected_index >= self.scroll_offset + self.visible_height {
+            self.scroll_offset = self.selected_index + 1 - self.visible_height;
+        }
+
+        let items: Vec<ListItem> = self
+            .sorted
+            .iter()
+            .enumerate()
+            .skip(self.scroll_offset)
+            .take(self.visible_height)
+            .map(|(index, (size, path, links))| {
+                let style = if index == self.selected_index {
+                    theme().selection()
+                } else {
+                    Style::default().fg(theme().text)
+                };
+                let links = if *links > 1 {
+                    format!("  ({links} hard links)")
+                } else {
+                    String::new()
+                };
+                ListItem::new(Line::from(Span::styled(
+                    format!(
+                        "{:>10}  {}{links}",
+                        humansize::format_size(*size, DECIMAL),
+                        path.display()
+                    ),
+                    style,
+                )))
+            })
+            .collect();
+        List::new(items).render(list_area, buf);
+
+        self.actions.render_delete_confirmation(area, buf);
+    }
+
+    /// Offer files discovered since the last frame to the heap
+    fn ingest(&mut self, mft_files: &[MftFileProgress]) {
+        self.consumed.resize(mft_files.len(), 0);
+        let mut changed = false;
+        for (file_index, progress) in mft_files.iter().enumerate() {
+            let start = self.consumed[file_index];
+            for file in &progress.files_within[start..] {
+                if file.flags.contains(FileFlag::Directory) {
+                    continue;
+                }
+                if file.hard_link_count > 1 && !self.linked.insert((file_index, file.record_number)) {
+                    continue;
+                }
+                let smallest = self.heap.peek().map(|Reverse((size, _, _))| *size);
+                if self.heap.len() < TOP_N || smallest.is_some_and(|s| file.size > s) {
+                    self.heap.push(Reverse((file.size, file.path.clone(), file.hard_link_count)));
+                    if self.heap.len() > TOP_N {
+                        self.heap.pop();
+                    }
+                    changed = true;
+                }
+            }
+            self.consumed[file_index] = progress.files_within.len();
+        }
+        if changed {
+            self.sorted = self.heap.iter().map(|Reverse(entry)| entry.clone()).collect();
+            self.sorted.sort_by(|a, b| b.cmp(a));
+        }
+    }
+}
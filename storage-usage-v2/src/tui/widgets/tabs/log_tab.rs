@@ -0,0 +1,169 @@
+use crate::tui::log_buffer::LogRecord;
+use crate::tui::log_buffer::clear;
+use crate::tui::log_buffer::with_records;
+use crate::tui::theme::theme;
+use crate::tui::widgets::tabs::keyboard_response::KeyboardResponse;
+use ratatui::buffer::Buffer;
+use ratatui::crossterm::event::KeyCode;
+use ratatui::crossterm::event::KeyEvent;
+use ratatui::layout::Constraint;
+use ratatui::layout::Layout;
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::List;
+use ratatui::widgets::ListItem;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::Widget;
+use tracing::Level;
+
+/// Log lines written while the TUI owns the terminal, newest at the bottom
+pub struct LogTab {
+    /// Least severe level shown
+    min_level: Level,
+    /// Keep the newest line in view as lines arrive
+    follow: bool,
+    scroll_offset: usize,
+    visible_height: usize,
+}
+
+impl Default for LogTab {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LogTab {
+    pub const KEYBINDINGS: &[(&str, &str)] = &[
+        ("↑/↓", "Scroll"),
+        ("PgUp/PgDn", "Scroll by a page"),
+        ("Home", "Jump to the oldest line"),
+        ("End", "Follow the newest line"),
+        ("l", "Cycle the least severe level shown"),
+        ("c", "Clear the log"),
+    ];
+
+    pub fn new() -> Self {
+        Self {
+            min_level: Level::INFO,
+            follow: true,
+            scroll_offset: 0,
+            visible_height: 20,
+        }
+    }
+
+    pub fn on_key(&mut self, event: KeyEvent) -> KeyboardResponse {
+        match event.code {
+            KeyCode::Up => {
+                self.follow = false;
+                self.scroll_offset = self.scroll_offset.saturating_sub(1);
+                KeyboardResponse::Consume
+            }
+            KeyCode::Down => {
+                self.scroll_offset += 1; // Clamped in render
+                KeyboardResponse::Consume
+            }
+            KeyCode::PageUp => {
+                self.follow = false;
+                self.scroll_offset = self.scroll_offset.saturating_sub(self.visible_height);
+                KeyboardResponse::Consume
+            }
+            KeyCode::PageDown => {
+                self.scroll_offset += self.visible_height;
+                KeyboardResponse::Consume
+            }
+            KeyCode::Home => {
+                self.follow = false;
+                self.scroll_offset = 0;
+                KeyboardResponse::Consume
+            }
+            KeyCode::End => {
+                self.follow = true;
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('l') => {
+                self.min_level = match self.min_level {
+                    Level::ERROR => Level::WARN,
+                    Level::WARN => Level::INFO,
+                    Level::INFO => Level::DEBUG,
+                    _ => Level::ERROR,
+                };
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('c') => {
+                clear();
+                self.scroll_offset = 0;
+                self.follow = true;
+                KeyboardResponse::Consume
+            }
+            _ => KeyboardResponse::Pass,
+        }
+    }
+
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer) {
+        let [header_area, list_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(area);
+        self.visible_height = list_area.height as usize;
+
+        let shown: Vec<LogRecord> = with_records(|records| {
+            records
+                .iter()
+                .filter(|record| record.level <= self.min_level)
+                .cloned()
+                .collect()
+        });
+
+        let max_scroll = shown.len().saturating_sub(self.visible_height);
+        if self.scroll_offset >= max_scroll {
+            // Scrolling to the bottom resumes following
+            self.follow = true;
+        }
+        if self.follow {
+            self.scroll_offset = max_scroll;
+        }
+
+        Paragraph::new(format!(
+            "Log: {} lines at {} and above{}  (l level, c clear, End follow)",
+            shown.len(),
+            self.min_level,
+            if self.follow { ", following" } else { "" }
+        ))
+        .style(Style::default().fg(theme().text))
+        .render(header_area, buf);
+
+        if shown.is_empty() {
+            Paragraph::new("Nothing logged yet.")
+                .style(Style::default().fg(theme().muted))
+                .render(list_area, buf);
+            return;
+        }
+
+        let items: Vec<ListItem> = shown
+            .iter()
+            .skip(self.scroll_offset)
+            .take(self.visible_height)
+            .map(|record| {
+                let color = match record.level {
+                    Level::ERROR => theme().bad,
+                    Level::WARN => theme().warn,
+                    Level::INFO => theme().text,
+                    _ => theme().muted,
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{} ", record.time.format("%H:%M:%S")),
+                        Style::default().fg(theme().muted),
+                    ),
+                    Span::styled(format!("{:>5} ", record.level), Style::default().fg(color)),
+                    Span::styled(
+                        format!("{}: ", record.target),
+                        Style::default().fg(theme().muted),
+                    ),
+                    Span::styled(record.message.clone(), Style::default().fg(color)),
+                ]))
+            })
+            .collect();
+        List::new(items).render(list_area, buf);
+    }
+}
@@ -1,7 +1,16 @@
+pub mod age_tab;
 pub mod app_tab;
 pub mod app_tabs;
+pub mod compare_tab;
+pub mod directory_tree;
+pub mod extensions_tab;
+pub mod file_actions;
 pub mod keyboard_response;
+pub mod largest_directories_tab;
+pub mod largest_files_tab;
+pub mod log_tab;
 pub mod overview_tab;
 pub mod search_tab;
+pub mod treemap_tab;
 pub mod visualizer_tab;
 pub mod errors_tab;
@@ -1,20 +1,26 @@
+use crate::tui::path_sample::PathCategory;
+use crate::tui::path_sample::SAMPLED_PATHS;
 use crate::tui::progress::MftFileProgress;
+use crate::tui::theme::theme;
 use crate::tui::widgets::tabs::keyboard_response::KeyboardResponse;
 use humansize::DECIMAL;
 use ratatui::buffer::Buffer;
+use ratatui::crossterm::event::KeyCode;
 use ratatui::crossterm::event::KeyEvent;
 use ratatui::layout::Constraint;
+use ratatui::layout::Layout;
 use ratatui::layout::Rect;
-use ratatui::style::Color;
 use ratatui::style::Stylize;
 use ratatui::text::Line;
 use ratatui::text::Span;
 use ratatui::text::Text;
 use ratatui::widgets::Cell;
+use ratatui::widgets::Paragraph;
 use ratatui::widgets::Row;
 use ratatui::widgets::Table;
 use ratatui::widgets::Widget;
 use std::cmp::min;
+use std::collections::VecDeque;
 use std::time::Duration;
 use std::time::Instant;
 use uom::ConstZero;
@@ -27,7 +33,128 @@ use uom::si::ratio::ratio;
 use uom::si::time::millisecond;
 use uom::si::time::second;
 
-pub struct OverviewTab;
+/// How often each row's throughput is sampled for its sparkline
+const THROUGHPUT_SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Samples kept per row, one character each
+const THROUGHPUT_SAMPLES: usize = 24;
+
+const SPARK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Recent bytes processed per sample interval for one row, oldest first
+#[derive(Default)]
+struct Throughput {
+    /// Processed bytes at the last sample
+    last_processed: f64,
+    samples: VecDeque<f64>,
+}
+
+impl Throughput {
+    fn sample(&mut self, processed: f64) {
+        // A re-synced file starts over from zero
+        if processed < self.last_processed {
+            self.samples.clear();
+            self.last_processed = 0.0;
+        }
+        if self.samples.len() == THROUGHPUT_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(processed - self.last_processed);
+        self.last_processed = processed;
+    }
+
+    /// Samples scaled to the busiest one; intervals with no progress are flagged as stalls
+    fn sparkline(&self) -> Line<'static> {
+        let peak = self.samples.iter().copied().fold(0.0, f64::max);
+        let spans: Vec<Span> = self
+            .samples
+            .iter()
+            .map(|sample| {
+                if *sample <= 0.0 {
+                    return Span::raw("▁").fg(theme().bad);
+                }
+                let level = ((sample / peak) * (SPARK_LEVELS.len() - 1) as f64).round() as usize;
+                Span::raw(SPARK_LEVELS[level].to_string()).fg(theme().info)
+            })
+            .collect();
+        Line::from(spans)
+    }
+}
+
+/// The columns of one overview row, for a single MFT file or all of them combined
+struct RowSource {
+    label: String,
+    total_size: Option<Information>,
+    entry_size: Option<Information>,
+    processed_size: Information,
+    processing_end: Option<Instant>,
+    error_count: usize,
+    /// Held by `p` before finishing
+    paused: bool,
+    /// Progress of a re-sync's dump, before the file is reprocessed
+    dumped: Option<(Information, Information)>,
+}
+
+impl RowSource {
+    fn file(mft: &MftFileProgress) -> Self {
+        Self {
+            label: mft.path.to_string_lossy().to_string(),
+            total_size: mft.total_size,
+            entry_size: mft.entry_size,
+            processed_size: mft.processed_size,
+            processing_end: mft.processing_end,
+            error_count: mft.errors.len(),
+            paused: mft.processing_end.is_none() && mft.pause.is_paused(),
+            dumped: mft.dumped,
+        }
+    }
+
+    /// Totals across every file; sizes are only known once they are known for every file, and
+    /// entries are only counted when every file uses the same entry size
+    fn all_drives(mft_files: &[MftFileProgress]) -> Self {
+        let entry_size = mft_files[0].entry_size.filter(|size| {
+            mft_files
+                .iter()
+                .all(|mft| mft.entry_size.is_some_and(|s| s == *size))
+        });
+        Self {
+            label: format!("All drives ({} files)", mft_files.len()),
+            total_size: mft_files
+                .iter()
+                .map(|mft| mft.total_size)
+                .sum::<Option<Information>>(),
+            entry_size,
+            processed_size: mft_files.iter().map(|mft| mft.processed_size).sum(),
+            processing_end: mft_files
+                .iter()
+                .map(|mft| mft.processing_end)
+                .collect::<Option<Vec<Instant>>>()
+                .and_then(|ends| ends.into_iter().max()),
+            error_count: mft_files.iter().map(|mft| mft.errors.len()).sum(),
+            paused: false,
+            dumped: None,
+        }
+    }
+}
+
+pub struct OverviewTab {
+    /// Row of the MFT file that `r` re-syncs
+    selected_file: usize,
+    /// File to re-sync, picked up by the app after the key is handled
+    resync_request: Option<usize>,
+    /// File to pause or resume, picked up the same way
+    pause_request: Option<usize>,
+    /// Re-synced file to start marking on the next frame
+    sync_started: Option<usize>,
+    /// Files whose re-sync was requested, with their completion time then; the row shows the
+    /// sync until the file is reset for reprocessing or finishes again
+    syncing: Vec<(usize, Option<Instant>)>,
+    /// Per row, including the all-drives row
+    throughput: Vec<Throughput>,
+    last_throughput_sample: Option<Instant>,
+    /// Whether the selected file's path sample is shown below the table
+    show_paths: bool,
+}
 
 impl Default for OverviewTab {
     fn default() -> Self {
@@ -37,7 +164,20 @@ impl Default for OverviewTab {
 
 impl OverviewTab {
     pub fn new() -> Self {
-        Self
+        Self {
+            selected_file: 0,
+            resync_request: None,
+            pause_request: None,
+            sync_started: None,
+            syncing: Vec::new(),
+            throughput: Vec::new(),
+            last_throughput_sample: None,
+            show_paths: false,
+        }
+    }
+
+    pub fn set_show_paths(&mut self, show_paths: bool) {
+        self.show_paths = show_paths;
     }
 
     fn format_number(num: u64) -> String {
@@ -54,8 +194,43 @@ impl OverviewTab {
         result
     }
 
-    pub fn on_key(&mut self, _event: KeyEvent) -> KeyboardResponse {
-        KeyboardResponse::Pass
+    pub const KEYBINDINGS: &[(&str, &str)] = &[
+        ("↑/↓", "Select MFT file"),
+        ("r", "Re-dump the selected drive's MFT and reload it"),
+        ("p", "Pause or resume processing the selected MFT file"),
+    ];
+
+    pub fn on_key(&mut self, event: KeyEvent) -> KeyboardResponse {
+        match event.code {
+            KeyCode::Up => {
+                self.selected_file = self.selected_file.saturating_sub(1);
+                KeyboardResponse::Consume
+            }
+            KeyCode::Down => {
+                self.selected_file += 1; // Clamped in render
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('r') => {
+                self.resync_request = Some(self.selected_file);
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('p') => {
+                self.pause_request = Some(self.selected_file);
+                KeyboardResponse::Consume
+            }
+            _ => KeyboardResponse::Pass,
+        }
+    }
+
+    /// The file `p` was pressed on, once
+    pub fn take_pause_request(&mut self) -> Option<usize> {
+        self.pause_request.take()
+    }
+
+    /// The file `r` was pressed on, once
+    pub fn take_resync_request(&mut self) -> Option<usize> {
+        self.sync_started = self.resync_request.take();
+        self.sync_started
     }
 
     pub fn render(
@@ -65,19 +240,55 @@ impl OverviewTab {
         mft_files: &[MftFileProgress],
         processing_begin: Instant,
     ) {
-        let max_path_width = area.width.saturating_sub(60) as usize; // heuristic to leave room for other columns
-        let rows: Vec<Row> = mft_files
+        let area = if self.show_paths
+            && let Some(mft) = mft_files.get(self.selected_file)
+        {
+            let [table_area, sample_area] = Layout::vertical([
+                Constraint::Min(mft_files.len() as u16 + 2),
+                Constraint::Length(SAMPLED_PATHS as u16 + 2),
+            ])
+            .areas(area);
+            Self::render_path_sample(sample_area, buf, mft);
+            table_area
+        } else {
+            area
+        };
+        let max_path_width = area.width.saturating_sub(60 + THROUGHPUT_SAMPLES as u16) as usize; // heuristic to leave room for other columns
+        self.selected_file = self.selected_file.min(mft_files.len().saturating_sub(1));
+        self.syncing.retain(|(index, end)| {
+            mft_files
+                .get(*index)
+                .is_some_and(|mft| mft.processing_end.is_some() && mft.processing_end == *end)
+        });
+        if let Some(index) = self.sync_started.take()
+            && let Some(mft) = mft_files.get(index)
+            && mft.processing_end.is_some()
+        {
+            self.syncing.push((index, mft.processing_end));
+        }
+        // A combined row follows the files when there is more than one
+        let mut sources: Vec<RowSource> = mft_files.iter().map(RowSource::file).collect();
+        if mft_files.len() > 1 {
+            sources.push(RowSource::all_drives(mft_files));
+        }
+        self.sample_throughput(&sources);
+        let rows: Vec<Row> = sources
             .iter()
-            .map(|mft| {
+            .enumerate()
+            .map(|(index, mft)| {
                 // Status column
-                let status = if mft.processing_end.is_some() {
-                    Text::from("OK").fg(Color::Green)
+                let status = if self.syncing.iter().any(|(i, _)| *i == index) {
+                    Text::from("⟳").fg(theme().info)
+                } else if mft.processing_end.is_some() {
+                    Text::from("OK").fg(theme().good)
+                } else if mft.paused {
+                    Text::from("⏸").fg(theme().muted)
                 } else {
-                    Text::from("...").fg(Color::Yellow)
+                    Text::from("...").fg(theme().warn)
                 };
 
                 // Full path column (with truncation in middle if too long)
-                let full_path = mft.path.to_string_lossy().to_string();
+                let full_path = mft.label.clone();
                 let file_display = if full_path.len() > max_path_width && max_path_width > 10 {
                     let keep_each_side = (max_path_width - 3) / 2;
                     format!(
@@ -92,7 +303,14 @@ impl OverviewTab {
                 // Progress column (with rate and remaining)
                 let elapsed_time =
                     Time::new::<millisecond>(processing_begin.elapsed().as_millis() as f64);
-                let progress_cell = if mft.processing_end.is_some() {
+                let progress_cell = if let Some((read, total)) = mft.dumped {
+                    Cell::from(format!(
+                        "Dumping {}/{}",
+                        humansize::format_size_i(read.get::<byte>(), DECIMAL),
+                        humansize::format_size_i(total.get::<byte>(), DECIMAL)
+                    ))
+                    .fg(theme().info)
+                } else if mft.processing_end.is_some() {
                     // When processing is complete, just show the processed size
                     Cell::from(humansize::format_size_i(
                         mft.processed_size.get::<byte>(),
@@ -117,7 +335,7 @@ impl OverviewTab {
                     );
 
                     let mut spans =
-                        vec![Span::raw(base_text), Span::raw(rate_text).fg(Color::Cyan)];
+                        vec![Span::raw(base_text), Span::raw(rate_text).fg(theme().info)];
 
                     if let Some(total_size) = mft.total_size {
                         let remaining = total_size - mft.processed_size;
@@ -125,7 +343,7 @@ impl OverviewTab {
                             " ({})",
                             humansize::format_size_i(remaining.get::<byte>(), DECIMAL)
                         );
-                        spans.push(Span::raw(remaining_text).fg(Color::Yellow));
+                        spans.push(Span::raw(remaining_text).fg(theme().warn));
                     }
 
                     Cell::from(Text::from(Line::from(spans)))
@@ -179,13 +397,13 @@ impl OverviewTab {
                                 format!(" (+{}/s)", Self::format_number(entries_per_sec as u64));
 
                             let mut spans =
-                                vec![Span::raw(base_text), Span::raw(rate_text).fg(Color::Cyan)];
+                                vec![Span::raw(base_text), Span::raw(rate_text).fg(theme().info)];
 
                             if let Some(total) = total_entries {
                                 let remaining = total - processed_entries;
                                 let remaining_text =
                                     format!(" ({})", Self::format_number(remaining as u64));
-                                spans.push(Span::raw(remaining_text).fg(Color::Yellow));
+                                spans.push(Span::raw(remaining_text).fg(theme().warn));
                             }
 
                             Cell::from(Text::from(Line::from(spans)))
@@ -199,6 +417,11 @@ impl OverviewTab {
                     Cell::from("?/?")
                 };
 
+                let throughput_cell = match self.throughput.get(index) {
+                    Some(throughput) => Cell::from(throughput.sparkline()),
+                    None => Cell::from(""),
+                };
+
                 // Time elapsed column
                 let time_elapsed = humantime::format_duration(Duration::from_millis(
                     mft.processing_end
@@ -235,32 +458,39 @@ impl OverviewTab {
                 };
 
                 // Add error information to ETA column if there are errors
-                let eta_with_errors = if !mft.errors.is_empty() {
-                    format!("{} (Errors: {})", eta, mft.errors.len())
+                let eta_with_errors = if mft.error_count > 0 {
+                    format!("{} (Errors: {})", eta, mft.error_count)
                 } else {
                     eta
                 };
 
-                Row::new(vec![
+                let row = Row::new(vec![
                     Cell::from(status),
                     Cell::from(file_display),
                     progress_cell,
                     entries_cell,
+                    throughput_cell,
                     Cell::from(time_elapsed),
                     Cell::from(eta_with_errors),
-                ])
+                ]);
+                if index == self.selected_file {
+                    row.style(theme().selection())
+                } else {
+                    row
+                }
             })
             .collect();
 
         let table = Table::new(
             rows,
             [
-                Constraint::Length(3), // Status
-                Constraint::Min(30),   // Full path (was file name)
-                Constraint::Fill(3),   // Progress
-                Constraint::Fill(3),   // Entries
-                Constraint::Fill(1),   // Time
-                Constraint::Fill(1),   // ETA
+                Constraint::Length(3),                         // Status
+                Constraint::Min(30),                           // Full path (was file name)
+                Constraint::Fill(3),                           // Progress
+                Constraint::Fill(3),                           // Entries
+                Constraint::Length(THROUGHPUT_SAMPLES as u16), // Throughput
+                Constraint::Fill(1),                           // Time
+                Constraint::Fill(1),                           // ETA
             ],
         )
         .header(Row::new(vec![
@@ -268,10 +498,56 @@ impl OverviewTab {
             Cell::from("Path"),
             Cell::from("Progress"),
             Cell::from("Entries"),
+            Cell::from("Throughput"),
             Cell::from("Time"),
             Cell::from("ETA"),
         ]));
 
         table.render(area, buf);
     }
+
+    /// Counts of each kind of path in the file, then the paths sampled from it
+    fn render_path_sample(area: Rect, buf: &mut Buffer, mft: &MftFileProgress) {
+        let sample = &mft.path_sample;
+        let mut counts = vec![Span::raw(format!(
+            "{} paths: ",
+            Self::format_number(sample.seen())
+        ))];
+        for (index, category) in PathCategory::ALL.iter().enumerate() {
+            if index > 0 {
+                counts.push(Span::raw(" · ").fg(theme().muted));
+            }
+            counts.push(Span::raw(format!(
+                "{} {}",
+                Self::format_number(sample.count(*category)),
+                category.as_str()
+            )));
+        }
+        let mut lines = vec![Line::from(""), Line::from(counts)];
+        lines.extend(
+            sample
+                .paths()
+                .iter()
+                .map(|path| Line::from(path.to_string_lossy().to_string()).fg(theme().muted)),
+        );
+        Paragraph::new(lines).render(area, buf);
+    }
+
+    /// Record each unfinished row's progress since the last sample, at most once per interval
+    fn sample_throughput(&mut self, sources: &[RowSource]) {
+        self.throughput.resize_with(sources.len(), Throughput::default);
+        if self
+            .last_throughput_sample
+            .is_some_and(|at| at.elapsed() < THROUGHPUT_SAMPLE_INTERVAL)
+        {
+            return;
+        }
+        self.last_throughput_sample = Some(Instant::now());
+        for (throughput, source) in self.throughput.iter_mut().zip(sources) {
+            // Finished and paused rows keep their history as it was
+            if source.processing_end.is_none() && !source.paused {
+                throughput.sample(source.processed_size.get::<byte>());
+            }
+        }
+    }
 }
@@ -1,32 +1,111 @@
+use crate::file_flags::FileFlags;
+use crate::mft_record_details::RecordDetails;
+use crate::mft_record_details::describe_record;
+use crate::reparse::ReparsePoint;
+use crate::search_export::DEFAULT_EXPORT_LIMIT;
+use crate::search_export::ExportedResult;
+use crate::search_export::write_search_results;
+use crate::search_filter::SearchQuery;
+use crate::search_filter::nfc;
+use crate::tui::progress::DiscoveredFile;
 use crate::tui::progress::MftFileProgress;
+use crate::tui::theme::theme;
+use crate::tui::widgets::tabs::file_actions::FileActions;
 use crate::tui::widgets::tabs::keyboard_response::KeyboardResponse;
 use nucleo::Nucleo;
 use ratatui::buffer::Buffer;
 use ratatui::crossterm::event::KeyCode;
 use ratatui::crossterm::event::KeyEvent;
+use ratatui::crossterm::event::KeyModifiers;
+use ratatui::crossterm::event::MouseButton;
+use ratatui::crossterm::event::MouseEvent;
+use ratatui::crossterm::event::MouseEventKind;
 use ratatui::layout::Constraint;
 use ratatui::layout::Layout;
 use ratatui::layout::Rect;
-use ratatui::style::Color;
 use ratatui::style::Style;
 use ratatui::text::Line;
 use ratatui::text::Span;
+use ratatui::layout::Flex;
+use ratatui::widgets::Block;
+use ratatui::widgets::Clear;
 use ratatui::widgets::List;
 use ratatui::widgets::ListItem;
 use ratatui::widgets::Paragraph;
+use ratatui::widgets::Wrap;
 use ratatui::widgets::Widget;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::sync::Arc;
 use std::time::Instant;
 use rustc_hash::FxHashSet;
+use chrono::DateTime;
+use chrono::Utc;
 
 #[derive(Clone)]
 struct FileEntry {
     path: PathBuf,
     full_path: String,
+    flags: FileFlags,
+    /// Index of the MFT file this entry came from
+    file_index: usize,
+    record_number: u64,
+    size: u64,
+    modified: Option<DateTime<Utc>>,
+    reparse: Option<ReparsePoint>,
 }
 
+/// Order of the search results, cycled with Ctrl+S
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+enum SearchSort {
+    /// Fuzzy match score, as nucleo returns them
+    #[default]
+    Relevance,
+    /// Largest first
+    Size,
+    /// Most recently modified first
+    Modified,
+    /// By file name
+    Name,
+}
+
+impl SearchSort {
+    fn next(self) -> Self {
+        match self {
+            SearchSort::Relevance => SearchSort::Size,
+            SearchSort::Size => SearchSort::Modified,
+            SearchSort::Modified => SearchSort::Name,
+            SearchSort::Name => SearchSort::Relevance,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SearchSort::Relevance => "relevance",
+            SearchSort::Size => "size",
+            SearchSort::Modified => "date",
+            SearchSort::Name => "name",
+        }
+    }
+
+    fn from_label(label: &str) -> Option<Self> {
+        [
+            SearchSort::Relevance,
+            SearchSort::Size,
+            SearchSort::Modified,
+            SearchSort::Name,
+        ]
+        .into_iter()
+        .find(|sort| sort.label() == label)
+    }
+}
+
+/// Width of the right-aligned size and modified date columns
+const SIZE_COLUMN_WIDTH: usize = 10;
+const DATE_COLUMN_WIDTH: usize = 10;
+
 enum WorkerMessage {
     Batch(Vec<FileEntry>),
     Done,
@@ -34,16 +113,34 @@ enum WorkerMessage {
 
 pub struct SearchTab {
     search_query: String,
+    /// `search_query` split into the fuzzy term and inline filters like `ext:iso size>1gb`
+    parsed_query: SearchQuery,
+    sort: SearchSort,
+    /// Positions of the fuzzy matches to display after inline filters and sorting, or `None`
+    /// to show nucleo's matches as-is
+    view: Option<Vec<u32>>,
+    view_dirty: bool,
     scroll_offset: usize,
     selected_index: usize,
     matcher: Nucleo<FileEntry>,
     last_file_count: usize,
     last_update: Instant,
     visible_height: usize,
-    worker_tx: Sender<Vec<PathBuf>>, // send newly discovered raw paths per MFT file batch
+    worker_tx: Sender<(usize, Vec<DiscoveredFile>)>, // send newly discovered raw paths per MFT file batch
     worker_rx: Receiver<WorkerMessage>,
     pending_batch: Vec<FileEntry>,
     seen: FxHashSet<String>,
+    /// (file index, record number) whose details should be loaded on the next render
+    details_request: Option<(usize, u64)>,
+    details: Option<Result<RecordDetails, String>>,
+    actions: FileActions,
+    /// Results marked with Ctrl+Space for batch actions, by full path
+    marked: BTreeMap<String, FileEntry>,
+    /// Destination being typed for an export of the current results
+    export_prompt: Option<String>,
+    export_limit: usize,
+    /// Where the result list was drawn in the last frame, for mouse clicks
+    results_area: Rect,
 }
 
 impl Default for SearchTab {
@@ -53,6 +150,22 @@ impl Default for SearchTab {
 }
 
 impl SearchTab {
+    pub const KEYBINDINGS: &[(&str, &str)] = &[
+        ("Type", "Fuzzy search; ext:iso size>1gb modified<2023-01-01 filter"),
+        ("↑/↓, wheel", "Move selection"),
+        ("Click", "Select row"),
+        ("PgUp/PgDn", "Move by a page"),
+        ("Home/End", "Jump to first/last"),
+        ("Enter", "Toggle record details"),
+        ("Ctrl+S", "Cycle sort: relevance, size, date, name"),
+        ("Ctrl+Space", "Mark/unmark result for batch actions"),
+        ("Ctrl+A", "Mark every result, or clear the marks"),
+        ("Ctrl+E", "Reveal in Explorer"),
+        ("Ctrl+Y", "Copy path(s) of the marked results or selection"),
+        ("Ctrl+D", "Send marked results or selection to Recycle Bin"),
+        ("Ctrl+O", "Export marked results, or all results, to a file"),
+    ];
+
     pub fn new() -> Self {
         let config = nucleo::Config::DEFAULT;
         let matcher = Nucleo::new(
@@ -62,20 +175,20 @@ impl SearchTab {
             1,
         );
 
-        let (tx_paths, rx_paths) = mpsc::channel::<Vec<PathBuf>>();
+        let (tx_paths, rx_paths) = mpsc::channel::<(usize, Vec<DiscoveredFile>)>();
         let (tx_worker, rx_worker) = mpsc::channel::<WorkerMessage>();
 
         // Spawn background thread for heavy path processing & duplication filtering
         std::thread::spawn(move || {
             let mut local_seen: FxHashSet<String> = FxHashSet::default();
-            while let Ok(batch) = rx_paths.recv() {
+            while let Ok((file_index, batch)) = rx_paths.recv() {
                 if batch.is_empty() { continue; }
                 let mut out = Vec::with_capacity(batch.len());
-                for pb in batch {
-                    let mut s = pb.to_string_lossy().to_string();
+                for file in batch {
+                    let s = file.path.to_string_lossy().to_string();
                     // If root-relative path, leave as-is (already prefixed by workers earlier).
                     if local_seen.insert(s.clone()) {
-                        out.push(FileEntry { path: PathBuf::from(&s), full_path: s.clone() });
+                        out.push(FileEntry { path: file.path, full_path: s, flags: file.flags, file_index, record_number: file.record_number, size: file.size, modified: file.modified, reparse: file.reparse });
                     }
                 }
                 if !out.is_empty() {
@@ -87,6 +200,10 @@ impl SearchTab {
 
         Self {
             search_query: String::new(),
+            parsed_query: SearchQuery::default(),
+            sort: SearchSort::default(),
+            view: None,
+            view_dirty: false,
             scroll_offset: 0,
             selected_index: 0,
             matcher,
@@ -97,11 +214,99 @@ impl SearchTab {
             worker_rx: rx_worker,
             pending_batch: Vec::new(),
             seen: FxHashSet::default(),
+            details_request: None,
+            details: None,
+            actions: FileActions::default(),
+            marked: BTreeMap::new(),
+            export_prompt: None,
+            export_limit: DEFAULT_EXPORT_LIMIT,
+            results_area: Rect::default(),
         }
     }
 
+    /// Whether a prompt is open that should receive every key, including q and Esc
+    pub fn captures_input(&self) -> bool {
+        self.export_prompt.is_some() || self.actions.captures_input()
+    }
+
+    pub fn set_export_limit(&mut self, limit: usize) {
+        self.export_limit = limit;
+    }
+
     pub fn on_key(&mut self, event: KeyEvent) -> KeyboardResponse {
+        if let Some(mut destination) = self.export_prompt.take() {
+            match event.code {
+                KeyCode::Enter => {
+                    let path = PathBuf::from(destination.trim());
+                    let total = self.export_count();
+                    let written = total.min(self.export_limit);
+                    let result = self.export_results(&path);
+                    self.actions.report(
+                        result,
+                        format!("Exported {written} of {total} results to {}", path.display()),
+                    );
+                }
+                KeyCode::Esc => {
+                    self.actions.set_status(Line::from("Export cancelled"));
+                }
+                KeyCode::Backspace => {
+                    destination.pop();
+                    self.export_prompt = Some(destination);
+                }
+                KeyCode::Char(c) => {
+                    destination.push(c);
+                    self.export_prompt = Some(destination);
+                }
+                _ => self.export_prompt = Some(destination),
+            }
+            return KeyboardResponse::Consume;
+        }
+        let selected: Vec<(PathBuf, u64)> = if self.marked.is_empty() {
+            self.result_at(self.selected_index)
+                .map(|entry| (entry.path.clone(), entry.size))
+                .into_iter()
+                .collect()
+        } else {
+            self.marked
+                .values()
+                .map(|entry| (entry.path.clone(), entry.size))
+                .collect()
+        };
+        if let KeyboardResponse::Consume = self.actions.on_key(event, &selected) {
+            return KeyboardResponse::Consume;
+        }
         match event.code {
+            KeyCode::Char(' ') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                if let Some(entry) = self.result_at(self.selected_index).cloned()
+                    && self.marked.remove(&entry.full_path).is_none()
+                {
+                    self.marked.insert(entry.full_path.clone(), entry);
+                }
+                self.on_key(KeyEvent::from(KeyCode::Down));
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('a') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                if self.marked.is_empty() {
+                    self.marked = (0..self.result_count())
+                        .filter_map(|index| self.result_at(index))
+                        .map(|entry| (entry.full_path.clone(), entry.clone()))
+                        .collect();
+                } else {
+                    self.marked.clear();
+                }
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('o') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.export_prompt = Some("search-results.csv".to_string());
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('s') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.sort = self.sort.next();
+                self.view_dirty = true;
+                self.scroll_offset = 0;
+                self.selected_index = 0;
+                KeyboardResponse::Consume
+            }
             KeyCode::Char(c) => {
                 self.search_query.push(c);
                 self.scroll_offset = 0;
@@ -126,8 +331,7 @@ impl SearchTab {
                 KeyboardResponse::Consume
             }
             KeyCode::Down => {
-                let snapshot = self.matcher.snapshot();
-                let matched_count = snapshot.matched_item_count() as usize;
+                let matched_count = self.result_count();
                 if matched_count > 0 && self.selected_index < matched_count - 1 {
                     self.selected_index += 1;
                     if self.selected_index >= self.scroll_offset + self.visible_height {
@@ -143,8 +347,7 @@ impl SearchTab {
                 KeyboardResponse::Consume
             }
             KeyCode::PageDown => {
-                let snapshot = self.matcher.snapshot();
-                let matched_count = snapshot.matched_item_count() as usize;
+                let matched_count = self.result_count();
                 if matched_count > 0 {
                     self.selected_index =
                         (self.selected_index + self.visible_height).min(matched_count - 1);
@@ -155,14 +358,21 @@ impl SearchTab {
                 }
                 KeyboardResponse::Consume
             }
+            KeyCode::Enter => {
+                if self.details.is_some() {
+                    self.details = None;
+                } else if let Some(entry) = self.result_at(self.selected_index) {
+                    self.details_request = Some((entry.file_index, entry.record_number));
+                }
+                KeyboardResponse::Consume
+            }
             KeyCode::Home => {
                 self.selected_index = 0;
                 self.scroll_offset = 0;
                 KeyboardResponse::Consume
             }
             KeyCode::End => {
-                let snapshot = self.matcher.snapshot();
-                let matched_count = snapshot.matched_item_count() as usize;
+                let matched_count = self.result_count();
                 if matched_count > 0 {
                     self.selected_index = matched_count - 1;
                     self.scroll_offset = matched_count.saturating_sub(self.visible_height);
@@ -173,12 +383,124 @@ impl SearchTab {
         }
     }
 
+    /// Wheel moves the selection; clicking a row selects it
+    pub fn on_mouse(&mut self, event: MouseEvent) {
+        if self.captures_input() {
+            return;
+        }
+        match event.kind {
+            MouseEventKind::ScrollDown => {
+                self.on_key(KeyEvent::from(KeyCode::Down));
+            }
+            MouseEventKind::ScrollUp => {
+                self.on_key(KeyEvent::from(KeyCode::Up));
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                let area = self.results_area;
+                if event.column >= area.x
+                    && event.column < area.x + area.width
+                    && event.row >= area.y
+                    && event.row < area.y + area.height
+                {
+                    let index = self.scroll_offset + (event.row - area.y) as usize;
+                    if index < self.result_count() {
+                        self.selected_index = index;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// How many results an export would cover: the marked ones if any, otherwise all of them
+    fn export_count(&self) -> usize {
+        if self.marked.is_empty() {
+            self.result_count()
+        } else {
+            self.marked.len()
+        }
+    }
+
+    /// Write the marked results, or the current results in display order, up to the export
+    /// limit to a file
+    fn export_results(&self, path: &std::path::Path) -> eyre::Result<()> {
+        let count = self.export_count().min(self.export_limit);
+        let entries: Vec<&FileEntry> = if self.marked.is_empty() {
+            (0..count).filter_map(|index| self.result_at(index)).collect()
+        } else {
+            self.marked.values().take(count).collect()
+        };
+        let results: Vec<ExportedResult> = entries
+            .into_iter()
+            .map(|entry| ExportedResult {
+                path: &entry.full_path,
+                record_number: entry.record_number,
+                size: entry.size,
+                modified: entry.modified,
+                flags: entry.flags,
+            })
+            .collect();
+        write_search_results(path, &results)
+    }
+
+    /// Number of results after fuzzy matching and inline filters
+    fn result_count(&self) -> usize {
+        match &self.view {
+            Some(view) => view.len(),
+            None => self.matcher.snapshot().matched_item_count() as usize,
+        }
+    }
+
+    /// The result shown at a position in the list
+    fn result_at(&self, index: usize) -> Option<&FileEntry> {
+        let matched_index = match &self.view {
+            Some(view) => *view.get(index)?,
+            None => index as u32,
+        };
+        self.matcher
+            .snapshot()
+            .get_matched_item(matched_index)
+            .map(|item| item.data)
+    }
+
+    /// Re-run the inline filters and sort over the current fuzzy matches
+    fn refresh_view(&mut self) {
+        if !self.parsed_query.has_filters() && self.sort == SearchSort::Relevance {
+            self.view = None;
+            return;
+        }
+        let snapshot = self.matcher.snapshot();
+        let mut matches: Vec<(u32, &FileEntry)> = snapshot
+            .matched_items(..)
+            .enumerate()
+            .map(|(index, item)| (index as u32, item.data))
+            .filter(|(_, entry)| {
+                self.parsed_query
+                    .matches(&entry.full_path, entry.size, entry.modified)
+            })
+            .collect();
+        match self.sort {
+            SearchSort::Relevance => {}
+            SearchSort::Size => matches.sort_by(|a, b| b.1.size.cmp(&a.1.size)),
+            SearchSort::Modified => matches.sort_by(|a, b| b.1.modified.cmp(&a.1.modified)),
+            SearchSort::Name => matches.sort_by(|a, b| {
+                let a = a.1.path.file_name().unwrap_or_default();
+                let b = b.1.path.file_name().unwrap_or_default();
+                a.cmp(b)
+            }),
+        }
+        let view = matches.into_iter().map(|(index, _)| index).collect();
+        self.view = Some(view);
+    }
+
     fn update_search(&mut self) {
+        self.parsed_query = SearchQuery::parse(&self.search_query);
+        self.view_dirty = true;
         // Update the pattern for fuzzy matching
         self.matcher.pattern.reparse(
             0, // column 0
-            &self.search_query,
-            nucleo::pattern::CaseMatching::Smart,
+            &nfc(&self.parsed_query.fuzzy),
+            nucleo::pattern::CaseMatching::Ignore,
             nucleo::pattern::Normalization::Smart,
             false, // assume new pattern for simplicity
         );
@@ -188,24 +510,144 @@ impl SearchTab {
         let layout = Layout::vertical([
             Constraint::Length(1), // Search input (no border, just text)
             Constraint::Min(0),    // Results
+            Constraint::Length(1), // Status line
         ]);
-        let [search_area, results_area] = layout.areas(area);
+        let [search_area, results_area, status_area] = layout.areas(area);
+        let hint = if self.marked.is_empty() {
+            format!(
+                "Enter details, Ctrl+Space mark, {}, Ctrl+O export",
+                FileActions::HINT
+            )
+        } else {
+            // Two marked links to one file free its bytes only once
+            let marked_size: u64 = self
+                .marked
+                .values()
+                .map(|entry| ((entry.file_index, entry.record_number), entry.size))
+                .collect::<HashMap<_, _>>()
+                .values()
+                .sum();
+            format!(
+                "{} marked ({}): Ctrl+Y copy paths, Ctrl+D delete, Ctrl+O export, Ctrl+A clear",
+                self.marked.len(),
+                humansize::format_size(marked_size, humansize::DECIMAL)
+            )
+        };
+        self.actions.render_status(status_area, buf, &hint);
 
         self.visible_height = results_area.height as usize;
 
         self.render_search_input(search_area, buf);
         self.update_file_entries(mft_files);
-        self.render_search_results(results_area, buf);
+        self.load_requested_details(mft_files);
+        if self.details.is_some() {
+            let [list_area, details_area] =
+                Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)])
+                    .areas(results_area);
+            self.render_search_results(list_area, buf);
+            self.render_details(details_area, buf);
+        } else {
+            self.render_search_results(results_area, buf);
+        }
+        self.actions.render_delete_confirmation(area, buf);
+        self.render_export_prompt(area, buf);
+    }
+
+    fn render_export_prompt(&self, area: Rect, buf: &mut Buffer) {
+        let Some(destination) = &self.export_prompt else {
+            return;
+        };
+        let total = self.export_count();
+        let kind = if self.marked.is_empty() { "" } else { "marked " };
+        let lines = vec![
+            Line::from(format!(
+                "Export {} of {} {kind}results (.csv, .json or .jsonl) to:",
+                total.min(self.export_limit),
+                total
+            )),
+            Line::from(Span::styled(
+                format!("{destination}_"),
+                Style::default().fg(theme().text),
+            )),
+            Line::default(),
+            Line::from(Span::styled(
+                "Enter to write, Esc to cancel",
+                Style::default().fg(theme().warn),
+            )),
+        ];
+        let [popup] = Layout::vertical([Constraint::Length(lines.len() as u16 + 2)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup] = Layout::horizontal([Constraint::Percentage(70)])
+            .flex(Flex::Center)
+            .areas(popup);
+        Clear.render(popup, buf);
+        Paragraph::new(lines)
+            .block(
+                Block::bordered()
+                    .title("Export results")
+                    .border_style(Style::default().fg(theme().accent)),
+            )
+            .wrap(Wrap { trim: false })
+            .render(popup, buf);
+    }
+
+    fn load_requested_details(&mut self, mft_files: &[MftFileProgress]) {
+        let Some((file_index, record_number)) = self.details_request.take() else {
+            return;
+        };
+        let Some(progress) = mft_files.get(file_index) else {
+            return;
+        };
+        let drive_letter = progress
+            .path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.chars().next())
+            .filter(|c| c.is_ascii_alphabetic())
+            .map(|c| c.to_ascii_uppercase())
+            .unwrap_or('?');
+        let details = mft::MftParser::from_path(&progress.path)
+            .map_err(|e| eyre::eyre!("Failed to open {}: {}", progress.path.display(), e))
+            .and_then(|mut parser| describe_record(&mut parser, record_number, drive_letter))
+            .map_err(|e| format!("{e:#}"));
+        self.details = Some(details);
+    }
+
+    fn render_details(&self, area: Rect, buf: &mut Buffer) {
+        let block = Block::bordered().title("Details (Enter to close)");
+        let lines: Vec<Line> = match &self.details {
+            Some(Ok(details)) => details
+                .rows
+                .iter()
+                .map(|(label, value)| {
+                    Line::from(vec![
+                        Span::styled(format!("{label}: "), Style::default().fg(theme().accent)),
+                        Span::raw(value.clone()),
+                    ])
+                })
+                .collect(),
+            Some(Err(error)) => vec![Line::from(Span::styled(
+                error.clone(),
+                Style::default().fg(theme().bad),
+            ))],
+            None => Vec::new(),
+        };
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false })
+            .render(area, buf);
     }
 
     fn render_search_input(&self, area: Rect, buf: &mut Buffer) {
         let search_text = format!(
-            "Search: {} (Type to search, ↑↓ to navigate, PgUp/PgDn to scroll)",
-            self.search_query
+            "Search: {} (Type to search, filters like ext:iso size>1gb modified<2023-01-01, Ctrl+S sort: {})",
+            self.search_query,
+            self.sort.label()
         );
 
         Paragraph::new(search_text)
-            .style(Style::default().fg(Color::White))
+            .style(Style::default().fg(theme().text))
             .render(area, buf);
     }
 
@@ -218,7 +660,7 @@ impl SearchTab {
                     for e in entries {
                         if self.seen.insert(e.full_path.clone()) {
                             injector.push(e.clone(), |entry, columns| {
-                                columns[0] = entry.full_path.clone().into();
+                                columns[0] = nfc(&entry.full_path).as_ref().into();
                             });
                             self.last_file_count += 1;
                         }
@@ -228,19 +670,23 @@ impl SearchTab {
             }
         }
         // Count new raw files; send in chunks to worker
-        for file_progress in mft_files {
+        for (file_index, file_progress) in mft_files.iter().enumerate() {
             if file_progress.files_within.len() > self.last_file_count {
                 // send only new slice; simplistic global counter vs per-file; for precision we'd track per-file
-                let new_paths: Vec<PathBuf> = file_progress.files_within[self.last_file_count.min(file_progress.files_within.len())..].to_vec();
-                if !new_paths.is_empty() { let _ = self.worker_tx.send(new_paths); }
+                let new_paths: Vec<DiscoveredFile> = file_progress.files_within[self.last_file_count.min(file_progress.files_within.len())..].to_vec();
+                if !new_paths.is_empty() { let _ = self.worker_tx.send((file_index, new_paths)); }
             }
         }
-        self.matcher.tick(5);
+        let status = self.matcher.tick(5);
+        if status.changed || self.view_dirty {
+            self.view_dirty = false;
+            self.refresh_view();
+        }
     }
 
     fn render_search_results(&mut self, area: Rect, buf: &mut Buffer) {
-        let snapshot = self.matcher.snapshot();
-        let matched_count = snapshot.matched_item_count() as usize;
+        self.results_area = area;
+        let matched_count = self.result_count();
 
         if matched_count == 0 {
             let message = if self.search_query.is_empty() {
@@ -250,7 +696,7 @@ impl SearchTab {
             };
 
             Paragraph::new(message)
-                .style(Style::default().fg(Color::Gray))
+                .style(Style::default().fg(theme().muted))
                 .render(area, buf);
             return;
         }
@@ -266,30 +712,56 @@ impl SearchTab {
         let start = self.scroll_offset;
         let end = (start + self.visible_height).min(matched_count);
 
-        let items: Vec<ListItem> = snapshot
-            .matched_items(start as u32..end as u32)
-            .enumerate()
-            .map(|(idx, item)| {
-                let global_idx = start + idx;
+        // Mark gutter and path on the left, size and modified date right-aligned
+        let path_width = (area.width as usize)
+            .saturating_sub(SIZE_COLUMN_WIDTH + DATE_COLUMN_WIDTH + 4)
+            .max(1);
+        let items: Vec<ListItem> = (start..end)
+            .filter_map(|global_idx| self.result_at(global_idx).map(|entry| (global_idx, entry)))
+            .map(|(global_idx, entry)| {
                 let is_selected = global_idx == self.selected_index;
 
-                // Show full path
-                let display_path = item.data.full_path.clone();
+                // Show full path, prefixed with attribute tags when any are set
+                let tags = entry.flags.tags();
+                let mut display_path = if tags.is_empty() {
+                    entry.full_path.clone()
+                } else {
+                    format!("[{tags}] {}", entry.full_path)
+                };
+                if let Some(reparse) = &entry.reparse {
+                    display_path = format!("{display_path} ({reparse})");
+                }
+                let size = humansize::format_size(entry.size, humansize::DECIMAL);
+                let modified = entry
+                    .modified
+                    .map(|m| m.format("%Y-%m-%d").to_string())
+                    .unwrap_or_default();
+                let is_marked = self.marked.contains_key(&entry.full_path);
+                let row = format!(
+                    "{} {:<path_width$} {size:>SIZE_COLUMN_WIDTH$} {modified:>DATE_COLUMN_WIDTH$}",
+                    if is_marked { '●' } else { ' ' },
+                    truncate_start(&display_path, path_width)
+                );
 
-                if !self.search_query.is_empty() {
+                if is_marked && !is_selected {
+                    ListItem::new(Line::from(Span::styled(
+                        row,
+                        Style::default().fg(theme().accent),
+                    )))
+                } else if !self.search_query.is_empty() {
                     let style = if is_selected {
-                        Style::default().fg(Color::Black).bg(Color::Yellow)
+                        theme().selection()
                     } else {
-                        Style::default().fg(Color::White)
+                        Style::default().fg(theme().text)
                     };
-                    ListItem::new(Line::from(Span::styled(display_path, style)))
+                    ListItem::new(Line::from(Span::styled(row, style)))
                 } else {
                     let style = if is_selected {
-                        Style::default().fg(Color::Black).bg(Color::Yellow)
+                        theme().selection()
                     } else {
                         Style::default()
                     };
-                    ListItem::new(Line::from(Span::styled(display_path, style)))
+                    ListItem::new(Line::from(Span::styled(row, style)))
                 }
             })
             .collect();
@@ -303,6 +775,9 @@ impl SearchTab {
         let config = nucleo::Config::DEFAULT;
         self.matcher = Nucleo::new(config, Arc::new(|| {}), None, 1);
         self.last_file_count = 0;
+        self.view = None;
+        self.view_dirty = true;
+        self.marked.clear();
         self.scroll_offset = 0;
         self.selected_index = 0;
         self.last_update = Instant::now();
@@ -310,18 +785,55 @@ impl SearchTab {
 
     /// Get search statistics
     pub fn get_stats(&self) -> (usize, usize) {
-        let snapshot = self.matcher.snapshot();
         (
-            snapshot.item_count() as usize,
-            snapshot.matched_item_count() as usize,
+            self.matcher.snapshot().item_count() as usize,
+            self.result_count(),
         )
     }
 
+    /// The query and the label of the sort order, for the saved session
+    pub fn session_query(&self) -> (&str, &'static str) {
+        (&self.search_query, self.sort.label())
+    }
+
+    /// Bring back the query and sort order of a saved session
+    pub fn restore_query(&mut self, query: &str, sort: Option<&str>) {
+        self.search_query = query.to_string();
+        if let Some(sort) = sort.and_then(SearchSort::from_label) {
+            self.sort = sort;
+        }
+        self.update_search();
+    }
+
+    pub fn selected_row(&self) -> usize {
+        self.selected_index
+    }
+
+    pub fn actions_mut(&mut self) -> &mut FileActions {
+        &mut self.actions
+    }
+
+    /// Select a row, scrolling it to the top; clamped to the results in render
+    pub fn select_row(&mut self, row: usize) {
+        self.selected_index = row;
+        self.scroll_offset = row;
+    }
+
     /// Get the currently selected file path, if any
     pub fn get_selected_file(&self) -> Option<PathBuf> {
-        let snapshot = self.matcher.snapshot();
-        snapshot
-            .get_matched_item(self.selected_index as u32)
-            .map(|item| item.data.path.clone())
+        self.result_at(self.selected_index)
+            .map(|entry| entry.path.clone())
+    }
+}
+
+/// Keep the end of a path, which holds the file name, when it doesn't fit
+fn truncate_start(text: &str, width: usize) -> String {
+    let count = text.chars().count();
+    if count <= width {
+        return text.to_string();
     }
+    let keep = width.saturating_sub(1);
+    std::iter::once('…')
+        .chain(text.chars().skip(count - keep))
+        .collect()
 }
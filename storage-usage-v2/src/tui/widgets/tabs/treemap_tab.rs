@@ -0,0 +1,287 @@
+use crate::reparse::ReparsePolicy;
+use crate::tui::progress::MftFileProgress;
+use crate::tui::theme::theme;
+use crate::tui::widgets::tabs::directory_tree::DirectoryTree;
+use crate::tui::widgets::tabs::keyboard_response::KeyboardResponse;
+use humansize::DECIMAL;
+use ratatui::buffer::Buffer;
+use ratatui::crossterm::event::KeyCode;
+use ratatui::crossterm::event::KeyEvent;
+use ratatui::crossterm::event::MouseButton;
+use ratatui::crossterm::event::MouseEvent;
+use ratatui::crossterm::event::MouseEventKind;
+use ratatui::layout::Constraint;
+use ratatui::layout::Layout;
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::widgets::Block;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::Widget;
+
+/// Most children drawn for one directory; the rest are too small to see anyway
+const MAX_VISIBLE_CHILDREN: usize = 200;
+
+/// WizTree-style treemap of directory sizes, built up as the workers discover files
+pub struct TreemapTab {
+    tree: DirectoryTree,
+    current: usize,
+    selected: usize,
+    /// Rectangles drawn for the current node's children in the last frame, for mouse hits
+    hit_areas: Vec<(Rect, usize)>,
+}
+
+impl Default for TreemapTab {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TreemapTab {
+    pub const KEYBINDINGS: &[(&str, &str)] = &[
+        ("↑/↓, k/j", "Select child"),
+        ("Enter", "Open selected directory"),
+        ("Backspace", "Go up a level"),
+        ("a", "Size by allocated or logical bytes"),
+        ("Click/Right-click", "Open directory/go up"),
+    ];
+
+    pub fn new() -> Self {
+        Self {
+            tree: DirectoryTree::new(),
+            current: DirectoryTree::ROOT,
+            selected: 0,
+            hit_areas: Vec::new(),
+        }
+    }
+
+    /// Roll symlinks and junctions up as `policy` says, starting the tree over when it changes
+    pub fn set_reparse_policy(&mut self, policy: ReparsePolicy) {
+        if self.tree.policy() != policy {
+            *self = Self {
+                tree: DirectoryTree::with_policy(policy)
+                    .measuring_allocated(self.tree.is_measuring_allocated()),
+                ..Self::new()
+            };
+        }
+    }
+
+    pub fn on_key(&mut self, event: KeyEvent) -> KeyboardResponse {
+        match event.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected = self.selected.saturating_sub(1);
+                KeyboardResponse::Consume
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected += 1; // Clamped in render
+                KeyboardResponse::Consume
+            }
+            KeyCode::Enter => {
+                if let Some(child) = self.tree.sorted_children(self.current).get(self.selected) {
+                    self.drill_into(*child);
+                }
+                KeyboardResponse::Consume
+            }
+            KeyCode::Backspace => {
+                self.drill_up();
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('a') => {
+                // The tree is rebuilt from every discovered file on the next render
+                let allocated = !self.tree.is_measuring_allocated();
+                *self = Self {
+                    tree: DirectoryTree::with_policy(self.tree.policy())
+                        .measuring_allocated(allocated),
+                    ..Self::new()
+                };
+                KeyboardResponse::Consume
+            }
+            _ => KeyboardResponse::Pass,
+        }
+    }
+
+    pub fn on_mouse(&mut self, event: MouseEvent) {
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => self.on_click(event.column, event.row),
+            MouseEventKind::Down(MouseButton::Right) => self.drill_up(),
+            _ => {}
+        }
+    }
+
+    /// Drill into the child drawn at the given screen position, if any
+    pub fn on_click(&mut self, column: u16, row: u16) {
+        let hit = self
+            .hit_areas
+            .iter()
+            .find(|(rect, _)| {
+                column >= rect.x
+                    && column < rect.x + rect.width
+                    && row >= rect.y
+                    && row < rect.y + rect.height
+            })
+            .map(|(_, node)| *node);
+        if let Some(node) = hit {
+            self.drill_into(node);
+        }
+    }
+
+    fn drill_into(&mut self, node: usize) {
+        if self.tree.is_directory(node) {
+            self.current = node;
+            self.selected = 0;
+        }
+    }
+
+    fn drill_up(&mut self) {
+        if let Some(parent) = self.tree.nodes[self.current].parent {
+            let previous = self.current;
+            self.current = parent;
+            self.selected = self
+                .tree
+                .sorted_children(parent)
+                .iter()
+                .position(|c| *c == previous)
+                .unwrap_or(0);
+        }
+    }
+
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer, mft_files: &[MftFileProgress]) {
+        self.tree.ingest(mft_files);
+
+        let [header_area, map_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(area);
+
+        let children = self.tree.sorted_children(self.current);
+        self.selected = self.selected.min(children.len().saturating_sub(1));
+
+        let header = format!(
+            "{}  {} {}  (↑↓ select, Enter or click open, Backspace or right-click up, a logical/allocated)",
+            self.tree.breadcrumb(self.current),
+            humansize::format_size(self.tree.nodes[self.current].size, DECIMAL),
+            if self.tree.is_measuring_allocated() { "allocated" } else { "logical" }
+        );
+        Paragraph::new(header)
+            .style(Style::default().fg(theme().text))
+            .render(header_area, buf);
+
+        self.hit_areas.clear();
+        let sized: Vec<(usize, u64)> = children
+            .iter()
+            .take(MAX_VISIBLE_CHILDREN)
+            .map(|c| (*c, self.tree.nodes[*c].size))
+            .filter(|(_, size)| *size > 0)
+            .collect();
+        if sized.is_empty() {
+            Paragraph::new("No sized files discovered here yet.")
+                .style(Style::default().fg(theme().muted))
+                .render(map_area, buf);
+            return;
+        }
+
+        let mut rects = Vec::new();
+        split_layout(&sized, map_area, &mut rects);
+        let selected_node = children.get(self.selected).copied();
+        for (i, (node, rect)) in rects.iter().enumerate() {
+            let color = theme().palette[i % theme().palette.len()];
+            self.render_node(*node, *rect, buf, color, Some(*node) == selected_node);
+            self.hit_areas.push((*rect, *node));
+        }
+    }
+
+    fn render_node(&self, node: usize, area: Rect, buf: &mut Buffer, color: Color, selected: bool) {
+        let border_style = if selected {
+            Style::default()
+                .fg(theme().text)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(color)
+        };
+        let title = format!(
+            "{} {}",
+            self.tree.nodes[node].name,
+            humansize::format_size(self.tree.nodes[node].size, DECIMAL)
+        );
+        let block = Block::bordered().border_style(border_style).title(title);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        // One level of nesting inside each rectangle
+        let sized: Vec<(usize, u64)> = self
+            .tree
+            .sorted_children(node)
+            .into_iter()
+            .take(MAX_VISIBLE_CHILDREN)
+            .map(|c| (c, self.tree.nodes[c].size))
+            .filter(|(_, size)| *size > 0)
+            .collect();
+        let mut rects = Vec::new();
+        split_layout(&sized, inner, &mut rects);
+        for (i, (child, rect)) in rects.iter().enumerate() {
+            let shade = if i % 2 == 0 {
+                theme().background
+            } else {
+                theme().empty
+            };
+            buf.set_style(*rect, Style::default().fg(color).bg(shade));
+            let label: String = self.tree.nodes[*child]
+                .name
+                .chars()
+                .take(rect.width as usize)
+                .collect();
+            buf.set_string(rect.x, rect.y, label, Style::default().fg(color).bg(shade));
+        }
+    }
+}
+
+/// Lay out items (already sorted by size) by recursively splitting them into two groups of
+/// roughly equal size and dividing the area along its longer side.
+fn split_layout(items: &[(usize, u64)], area: Rect, out: &mut Vec<(usize, Rect)>) {
+    if items.is_empty() || area.width == 0 || area.height == 0 {
+        return;
+    }
+    if items.len() == 1 {
+        out.push((items[0].0, area));
+        return;
+    }
+    let total: u64 = items.iter().map(|(_, size)| size).sum();
+    let mut running = 0u64;
+    let mut split = items.len() - 1;
+    for (i, (_, size)) in items.iter().enumerate() {
+        running += size;
+        if running * 2 >= total {
+            split = (i + 1).min(items.len() - 1);
+            break;
+        }
+    }
+    let first: u64 = items[..split].iter().map(|(_, size)| size).sum();
+    let ratio = if total == 0 {
+        0.5
+    } else {
+        first as f64 / total as f64
+    };
+    // Terminal cells are about twice as tall as they are wide
+    let (a, b) = if area.width >= area.height * 2 {
+        let width = ((area.width as f64 * ratio).round() as u16).clamp(1, area.width);
+        (
+            Rect { width, ..area },
+            Rect {
+                x: area.x + width,
+                width: area.width - width,
+                ..area
+            },
+        )
+    } else {
+        let height = ((area.height as f64 * ratio).round() as u16).clamp(1, area.height);
+        (
+            Rect { height, ..area },
+            Rect {
+                y: area.y + height,
+                height: area.height - height,
+                ..area
+            },
+        )
+    };
+    split_layout(&items[..split], a, out);
+    split_layout(&items[split..], b, out);
+}
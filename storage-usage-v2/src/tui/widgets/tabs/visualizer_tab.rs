@@ -1,20 +1,146 @@
+use crate::file_flags::FileFlag;
+use crate::file_flags::FileFlags;
+use crate::tui::entry_statuses::EntryStatuses;
 use crate::tui::progress::MftFileProgress;
+use crate::tui::theme::theme;
 use crate::tui::widgets::tabs::keyboard_response::KeyboardResponse;
+use chrono::DateTime;
+use chrono::Utc;
 use ratatui::buffer::Buffer;
+use ratatui::crossterm::event::KeyCode;
 use ratatui::crossterm::event::KeyEvent;
 use ratatui::layout::Constraint;
+use ratatui::layout::Flex;
 use ratatui::layout::Layout;
 use ratatui::layout::Rect;
 use ratatui::style::Color;
 use ratatui::style::Style;
+use ratatui::text::Line;
+use ratatui::text::Span;
 use ratatui::widgets::Block;
 use ratatui::widgets::Borders;
+use ratatui::widgets::Clear;
 use ratatui::widgets::Gauge;
 use ratatui::widgets::Paragraph;
 use ratatui::widgets::Widget;
+use std::collections::HashMap;
+
+/// Records listed per file when inspecting a cell; a cell can hold thousands
+const MAX_INSPECTED_RECORDS: usize = 200;
+
+/// Where the grid was drawn in the last frame, to map the cursor to records
+#[derive(Clone, Copy)]
+struct GridLayout {
+    area: Rect,
+    entries_per_cell: usize,
+    total_count: usize,
+}
+
+impl GridLayout {
+    /// Spread `total_count` entries over `area`, or `None` when it has no room
+    fn new(area: Rect, total_count: usize) -> Option<Self> {
+        let total_cells = area.width as usize * area.height as usize;
+        (total_cells > 0).then(|| Self {
+            area,
+            entries_per_cell: total_count.div_ceil(total_cells),
+            total_count,
+        })
+    }
+
+    fn used_cells(&self) -> usize {
+        self.total_count.div_ceil(self.entries_per_cell)
+    }
+}
+
+/// What the grid's colors show, cycled with `c`
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+enum ColorBy {
+    /// Share of entries that parsed
+    #[default]
+    Health,
+    /// Files, directories, or system and reparse-point entries
+    EntryType,
+    /// Largest file in the cell
+    Size,
+    /// Most recent modification in the cell
+    Age,
+}
+
+impl ColorBy {
+    fn next(self) -> Self {
+        match self {
+            ColorBy::Health => ColorBy::EntryType,
+            ColorBy::EntryType => ColorBy::Size,
+            ColorBy::Size => ColorBy::Age,
+            ColorBy::Age => ColorBy::Health,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ColorBy::Health => "health",
+            ColorBy::EntryType => "entry type",
+            ColorBy::Size => "size",
+            ColorBy::Age => "age",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+enum EntryKind {
+    /// No path was discovered for the record
+    #[default]
+    Unknown,
+    File,
+    Directory,
+    /// System files and reparse points like junctions and symlinks
+    Special,
+}
+
+impl EntryKind {
+    fn of(flags: FileFlags) -> Self {
+        if flags.contains(FileFlag::System) || flags.contains(FileFlag::ReparsePoint) {
+            EntryKind::Special
+        } else if flags.contains(FileFlag::Directory) {
+            EntryKind::Directory
+        } else {
+            EntryKind::File
+        }
+    }
+}
+
+/// What the type, size and age modes know about a record
+#[derive(Copy, Clone, Debug, Default)]
+struct RecordInfo {
+    kind: EntryKind,
+    size: u64,
+    modified: Option<DateTime<Utc>>,
+}
+
+/// What a grid cell holds, listed in a popup
+struct Inspection {
+    title: String,
+    lines: Vec<Line<'static>>,
+    scroll: u16,
+}
 
 pub struct VisualizerTab {
+    /// Index of the MFT file shown; one past the last file shows all drives combined
     selected_file: usize,
+    /// Sorted record numbers of the selected file to mark in the grid, like errors jumped to
+    /// from the errors tab
+    highlighted: Vec<u64>,
+    /// Grid cell under the cursor, once it's been moved with h/j/k/l
+    cursor: Option<usize>,
+    grid: Option<GridLayout>,
+    /// Set by Enter; the cell is inspected on the next render, which has the entries
+    inspect_requested: bool,
+    inspection: Option<Inspection>,
+    color_by: ColorBy,
+    /// Per MFT file, what's known about each record, indexed by record number
+    records: Vec<Vec<RecordInfo>>,
+    /// How many of each MFT file's discovered files are already in `records`
+    consumed: Vec<usize>,
 }
 
 impl Default for VisualizerTab {
@@ -24,22 +150,95 @@ impl Default for VisualizerTab {
 }
 
 impl VisualizerTab {
+    pub const KEYBINDINGS: &[(&str, &str)] = &[
+        ("↑/↓", "Select MFT file, or all drives"),
+        ("h/j/k/l", "Move the cursor over the grid"),
+        ("c", "Color by health, entry type, size or age"),
+        (
+            "Enter",
+            "List the records, errors and paths in the cell under the cursor",
+        ),
+    ];
+
     pub fn new() -> Self {
-        Self { selected_file: 0 }
+        Self {
+            selected_file: 0,
+            highlighted: Vec::new(),
+            cursor: None,
+            grid: None,
+            inspect_requested: false,
+            inspection: None,
+            color_by: ColorBy::default(),
+            records: Vec::new(),
+            consumed: Vec::new(),
+        }
+    }
+
+    /// Whether the inspection popup is open and should receive every key
+    pub fn captures_input(&self) -> bool {
+        self.inspection.is_some()
+    }
+
+    /// Show a file with the given records marked in the grid
+    pub fn focus(&mut self, file_index: usize, mut records: Vec<u64>) {
+        records.sort_unstable();
+        records.dedup();
+        self.selected_file = file_index;
+        self.highlighted = records;
     }
 
     pub fn on_key(&mut self, event: KeyEvent) -> KeyboardResponse {
-        use ratatui::crossterm::event::KeyCode;
+        if let Some(inspection) = &mut self.inspection {
+            match event.code {
+                KeyCode::Up => inspection.scroll = inspection.scroll.saturating_sub(1),
+                KeyCode::Down => inspection.scroll = inspection.scroll.saturating_add(1),
+                KeyCode::PageUp => inspection.scroll = inspection.scroll.saturating_sub(10),
+                KeyCode::PageDown => inspection.scroll = inspection.scroll.saturating_add(10),
+                _ => self.inspection = None,
+            }
+            return KeyboardResponse::Consume;
+        }
 
+        let width = self.grid.map(|grid| grid.area.width as usize).unwrap_or(1);
         match event.code {
             KeyCode::Up => {
                 if self.selected_file > 0 {
                     self.selected_file -= 1;
+                    self.highlighted.clear();
+                    self.cursor = None;
                 }
                 KeyboardResponse::Consume
             }
             KeyCode::Down => {
                 self.selected_file += 1; // Will be clamped in render
+                self.highlighted.clear();
+                self.cursor = None;
+                KeyboardResponse::Consume
+            }
+            // Cursor moves are clamped to the grid in render
+            KeyCode::Char('h') => {
+                self.cursor = Some(self.cursor.unwrap_or(0).saturating_sub(1));
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('l') => {
+                self.cursor = Some(self.cursor.map_or(0, |cursor| cursor + 1));
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('k') => {
+                self.cursor = Some(self.cursor.unwrap_or(0).saturating_sub(width));
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('j') => {
+                self.cursor = Some(self.cursor.map_or(0, |cursor| cursor + width));
+                KeyboardResponse::Consume
+            }
+            KeyCode::Char('c') => {
+                self.color_by = self.color_by.next();
+                KeyboardResponse::Consume
+            }
+            KeyCode::Enter => {
+                self.cursor.get_or_insert(0);
+                self.inspect_requested = true;
                 KeyboardResponse::Consume
             }
             _ => KeyboardResponse::Pass,
@@ -58,8 +257,17 @@ impl VisualizerTab {
             return;
         }
 
-        // Clamp selected_file to valid range
-        self.selected_file = self.selected_file.min(mft_files.len() - 1);
+        // Clamp selected_file to valid range, with an extra all-drives entry for several files
+        let choices = if mft_files.len() > 1 {
+            mft_files.len() + 1
+        } else {
+            1
+        };
+        self.selected_file = self.selected_file.min(choices - 1);
+        // Record details are only collected once a mode needs them
+        if self.color_by != ColorBy::Health {
+            self.ingest(mft_files);
+        }
 
         let layout = Layout::vertical([
             Constraint::Length(3), // File selector
@@ -68,22 +276,219 @@ impl VisualizerTab {
         let [selector_area, viz_area] = layout.areas(area);
 
         self.render_file_selector(selector_area, buf, mft_files);
-        self.render_entry_health_visualization(viz_area, buf, &mft_files[self.selected_file]);
+        let files: Vec<usize> = if self.selected_file < mft_files.len() {
+            vec![self.selected_file]
+        } else {
+            (0..mft_files.len()).collect()
+        };
+        let segments: Vec<&EntryStatuses> = files
+            .iter()
+            .map(|file| &mft_files[*file].entry_health_statuses)
+            .collect();
+        let total_count: usize = segments.iter().map(|segment| segment.len()).sum();
+        if total_count == 0 {
+            self.grid = None;
+            let message = if files
+                .iter()
+                .all(|file| mft_files[*file].from_index.is_some())
+            {
+                "Loaded from index: record health not collected. Re-sync to parse the dump"
+            } else {
+                "No entry health data available yet"
+            };
+            Paragraph::new(message)
+                .style(Style::default().fg(theme().muted))
+                .render(viz_area, buf);
+            return;
+        }
+
+        let layout = Layout::vertical([
+            Constraint::Length(3), // Statistics or legend
+            Constraint::Min(0),    // Visual representation
+        ]);
+        let [stats_area, visual_area] = layout.areas(viz_area);
+        self.grid = GridLayout::new(visual_area, total_count);
+        if let (Some(cursor), Some(grid)) = (&mut self.cursor, self.grid) {
+            *cursor = (*cursor).min(grid.used_cells() - 1);
+        }
+
+        match self.color_by {
+            ColorBy::Health => render_health_stats(stats_area, buf, &segments, total_count),
+            color_by => render_legend(stats_area, buf, color_by),
+        }
+        if let Some(grid) = self.grid {
+            let records: Vec<&[RecordInfo]> = files
+                .iter()
+                .map(|file| {
+                    self.records
+                        .get(*file)
+                        .map(Vec::as_slice)
+                        .unwrap_or_default()
+                })
+                .collect();
+            self.render_grid(buf, grid, &segments, &records);
+        }
+
+        if std::mem::take(&mut self.inspect_requested)
+            && let (Some(cursor), Some(grid)) = (self.cursor, self.grid)
+        {
+            self.inspection = Some(self.inspect(cursor, grid, mft_files));
+        }
+        if let Some(inspection) = &self.inspection {
+            render_inspection(inspection, area, buf);
+        }
+    }
+
+    /// Index the type, size and age of newly discovered files by record number
+    fn ingest(&mut self, mft_files: &[MftFileProgress]) {
+        self.records.resize_with(mft_files.len(), Vec::new);
+        self.consumed.resize(mft_files.len(), 0);
+        for (file_index, progress) in mft_files.iter().enumerate() {
+            let records = &mut self.records[file_index];
+            for discovered in &progress.files_within[self.consumed[file_index]..] {
+                let record = discovered.record_number as usize;
+                if records.len() <= record {
+                    records.resize(record + 1, RecordInfo::default());
+                }
+                // Hard links repeat a record; the first name is enough
+                if records[record].kind == EntryKind::Unknown {
+                    records[record] = RecordInfo {
+                        kind: EntryKind::of(discovered.flags),
+                        size: discovered.size,
+                        modified: discovered.modified,
+                    };
+                }
+            }
+            self.consumed[file_index] = progress.files_within.len();
+        }
+    }
+
+    /// Describe the records in a grid cell: health, errors and resolved paths
+    fn inspect(&self, cell: usize, grid: GridLayout, mft_files: &[MftFileProgress]) -> Inspection {
+        let start = cell * grid.entries_per_cell;
+        let end = (start + grid.entries_per_cell).min(grid.total_count);
+        let files: Vec<usize> = if self.selected_file < mft_files.len() {
+            vec![self.selected_file]
+        } else {
+            (0..mft_files.len()).collect()
+        };
+
+        let muted = Style::default().fg(theme().muted);
+        let mut lines = Vec::new();
+        let mut offset = 0;
+        for file_index in files {
+            let file = &mft_files[file_index];
+            let statuses = &file.entry_health_statuses;
+            // Positions in the health statuses are record numbers
+            let from = start.max(offset) - offset;
+            let to = end.min(offset + statuses.len()).saturating_sub(offset);
+            offset += statuses.len();
+            if from >= to {
+                continue;
+            }
+            let records = from as u64..to as u64;
+
+            let mut paths: HashMap<u64, Vec<String>> = HashMap::new();
+            for discovered in &file.files_within {
+                if records.contains(&discovered.record_number) {
+                    paths
+                        .entry(discovered.record_number)
+                        .or_default()
+                        .push(discovered.path.display().to_string());
+                }
+            }
+            let mut errors: HashMap<u64, Vec<String>> = HashMap::new();
+            for error in &file.errors {
+                if let Some(record) = error.record_number
+                    && records.contains(&record)
+                {
+                    errors.entry(record).or_default().push(format!(
+                        "{}: {}",
+                        error.severity.as_str(),
+                        error.message
+                    ));
+                }
+            }
+
+            lines.push(Line::from(Span::styled(
+                format!(
+                    "{}  records {}–{}",
+                    file.path.display(),
+                    records.start,
+                    records.end - 1
+                ),
+                Style::default().fg(theme().warn),
+            )));
+            for record in records.clone().take(MAX_INSPECTED_RECORDS) {
+                let (status, color) = if statuses.get(record as usize) {
+                    ("ok", theme().good)
+                } else {
+                    ("unreadable", theme().bad)
+                };
+                let record_paths = paths.remove(&record).unwrap_or_default();
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        format!("#{record:<10} "),
+                        Style::default().fg(theme().accent),
+                    ),
+                    Span::styled(format!("{status:<10} "), Style::default().fg(color)),
+                    if record_paths.is_empty() {
+                        Span::styled("(no path)", muted)
+                    } else {
+                        Span::raw(record_paths.join("  ·  "))
+                    },
+                ]));
+                for message in errors.remove(&record).unwrap_or_default() {
+                    lines.push(Line::from(Span::styled(
+                        format!("{:12}{message}", ""),
+                        Style::default().fg(theme().bad),
+                    )));
+                }
+            }
+            if records.end - records.start > MAX_INSPECTED_RECORDS as u64 {
+                lines.push(Line::from(Span::styled(
+                    format!(
+                        "… {} more records",
+                        records.end - records.start - MAX_INSPECTED_RECORDS as u64
+                    ),
+                    muted,
+                )));
+            }
+        }
+        if lines.is_empty() {
+            lines.push(Line::from(Span::styled("No records in this cell", muted)));
+        }
+
+        Inspection {
+            title: format!("Cell {cell}: entries {start}–{}", end.saturating_sub(1)),
+            lines,
+            scroll: 0,
+        }
     }
 
     fn render_file_selector(&self, area: Rect, buf: &mut Buffer, mft_files: &[MftFileProgress]) {
-        let filename = mft_files[self.selected_file]
-            .path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("Unknown");
-
-        let text = format!(
-            "File {}/{}: {} (Use ↑↓ to navigate)",
-            self.selected_file + 1,
-            mft_files.len(),
-            filename
-        );
+        let mut text = match mft_files.get(self.selected_file) {
+            Some(file) => format!(
+                "File {}/{}: {} (Use ↑↓ to navigate)",
+                self.selected_file + 1,
+                mft_files.len(),
+                file.path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("Unknown")
+            ),
+            None => format!(
+                "All drives: {} files end to end (Use ↑↓ to navigate)",
+                mft_files.len()
+            ),
+        };
+        text.push_str(&format!("  ·  Color by {} (c)", self.color_by.label()));
+        if !self.highlighted.is_empty() {
+            text.push_str(&format!(
+                "  ·  ◆ marks {} record(s) from the errors tab",
+                self.highlighted.len()
+            ));
+        }
 
         Paragraph::new(text)
             .block(
@@ -94,107 +499,52 @@ impl VisualizerTab {
             .render(area, buf);
     }
 
-    fn render_entry_health_visualization(
+    /// Draw the entries of the segments laid end to end, colored by the current mode
+    fn render_grid(
         &self,
-        area: Rect,
         buf: &mut Buffer,
-        file: &MftFileProgress,
+        grid: GridLayout,
+        segments: &[&EntryStatuses],
+        records: &[&[RecordInfo]],
     ) {
-        if file.entry_health_statuses.is_empty() {
-            Paragraph::new("No entry health data available yet")
-                .style(Style::default().fg(Color::Gray))
-                .render(area, buf);
-            return;
-        }
-
-        let healthy_count = file.entry_health_statuses.iter().filter(|&&h| h).count();
-        let total_count = file.entry_health_statuses.len();
-        let health_ratio = if total_count > 0 {
-            healthy_count as f64 / total_count as f64
-        } else {
-            0.0
-        };
-
-        let layout = Layout::vertical([
-            Constraint::Length(3), // Health statistics
-            Constraint::Min(0),    // Visual representation
-        ]);
-        let [stats_area, visual_area] = layout.areas(area);
-
-        // Render health statistics
-        let stats_text = format!(
-            "Healthy entries: {}/{} ({:.1}%)",
-            healthy_count,
-            total_count,
-            health_ratio * 100.0
-        );
-
-        Gauge::default()
-            .gauge_style(Style::default().fg(if health_ratio > 0.9 {
-                Color::Green
-            } else if health_ratio > 0.7 {
-                Color::Yellow
-            } else {
-                Color::Red
-            }))
-            .ratio(health_ratio)
-            .label(stats_text)
-            .render(stats_area, buf);
-
-        // Render visual grid of entry health
-        self.render_health_grid(visual_area, buf, &file.entry_health_statuses);
-    }
-
-    fn render_health_grid(&self, area: Rect, buf: &mut Buffer, health_statuses: &[bool]) {
+        let area = grid.area;
         let grid_width = area.width as usize;
         let grid_height = area.height as usize;
-        let total_cells = grid_width * grid_height;
-
-        if total_cells == 0 {
-            return;
-        }
-
-        let entries_per_cell = health_statuses.len().div_ceil(total_cells);
+        let entries_per_cell = grid.entries_per_cell;
+        let total_count = grid.total_count;
+        let now = Utc::now();
 
         for y in 0..grid_height {
             for x in 0..grid_width {
                 let cell_index = y * grid_width + x;
                 let start_entry = cell_index * entries_per_cell;
-                let end_entry = (start_entry + entries_per_cell).min(health_statuses.len());
+                let end_entry = (start_entry + entries_per_cell).min(total_count);
 
-                if start_entry >= health_statuses.len() {
+                if start_entry >= total_count {
                     break;
                 }
 
-                let cell_health = if start_entry < end_entry {
-                    let healthy_in_cell = health_statuses[start_entry..end_entry]
-                        .iter()
-                        .filter(|&&h| h)
-                        .count();
-                    let total_in_cell = end_entry - start_entry;
-                    healthy_in_cell as f64 / total_in_cell as f64
-                } else {
-                    1.0
-                };
-
-                let color = if cell_health > 0.9 {
-                    Color::Green
-                } else if cell_health > 0.7 {
-                    Color::Yellow
-                } else if cell_health > 0.3 {
-                    Color::Red
-                } else {
-                    Color::DarkGray
-                };
+                // Mark cells holding a highlighted record
+                let first = self
+                    .highlighted
+                    .partition_point(|record| *record < start_entry as u64);
+                if self
+                    .highlighted
+                    .get(first)
+                    .is_some_and(|record| *record < end_entry as u64)
+                {
+                    if let Some(cell) = buf.cell_mut((area.x + x as u16, area.y + y as u16)) {
+                        cell.set_symbol("◆");
+                        cell.set_fg(theme().accent);
+                    }
+                    continue;
+                }
 
-                let symbol = if cell_health > 0.9 {
-                    "█"
-                } else if cell_health > 0.7 {
-                    "▓"
-                } else if cell_health > 0.3 {
-                    "▒"
-                } else {
-                    "░"
+                let (symbol, color) = match self.color_by {
+                    ColorBy::Health => health_cell(segments, start_entry, end_entry),
+                    ColorBy::EntryType => type_cell(segments, records, start_entry, end_entry),
+                    ColorBy::Size => size_cell(segments, records, start_entry, end_entry),
+                    ColorBy::Age => age_cell(segments, records, start_entry, end_entry, now),
                 };
 
                 if let Some(cell) = buf.cell_mut((area.x + x as u16, area.y + y as u16)) {
@@ -203,5 +553,289 @@ impl VisualizerTab {
                 }
             }
         }
+
+        if let Some(cursor) = self.cursor {
+            let position = (
+                area.x + (cursor % grid_width) as u16,
+                area.y + (cursor / grid_width) as u16,
+            );
+            if let Some(cell) = buf.cell_mut(position) {
+                cell.set_style(theme().selection());
+            }
+        }
+    }
+}
+
+/// The parts of `start..end` falling in each of the segments laid end to end, as
+/// (segment, range within the segment)
+fn segment_ranges(
+    lengths: impl IntoIterator<Item = usize>,
+    start: usize,
+    end: usize,
+) -> Vec<(usize, std::ops::Range<usize>)> {
+    let mut offset = 0;
+    let mut ranges = Vec::new();
+    for (segment, length) in lengths.into_iter().enumerate() {
+        let from = start.max(offset) - offset;
+        let to = end.min(offset + length).saturating_sub(offset);
+        if from < to {
+            ranges.push((segment, from..to));
+        }
+        offset += length;
+    }
+    ranges
+}
+
+/// Healthy and total entries in `start..end` of the segments laid end to end
+fn count_healthy(segments: &[&EntryStatuses], start: usize, end: usize) -> (usize, usize) {
+    let mut healthy = 0;
+    let mut total = 0;
+    for (segment, range) in segment_ranges(segments.iter().map(|s| s.len()), start, end) {
+        total += range.len();
+        healthy += segments[segment].count_healthy(range);
+    }
+    (healthy, total)
+}
+
+/// Known records in `start..end`, with the entry positions of the health segments.
+/// Records past the last discovered path are unknown and left out.
+fn records_in<'a>(
+    segments: &[&EntryStatuses],
+    records: &[&'a [RecordInfo]],
+    start: usize,
+    end: usize,
+) -> impl Iterator<Item = &'a RecordInfo> {
+    segment_ranges(segments.iter().map(|s| s.len()), start, end)
+        .into_iter()
+        .flat_map(move |(segment, range)| {
+            let known = records[segment];
+            known[range.start.min(known.len())..range.end.min(known.len())].iter()
+        })
+        .filter(|record| record.kind != EntryKind::Unknown)
+}
+
+fn health_cell(segments: &[&EntryStatuses], start: usize, end: usize) -> (&'static str, Color) {
+    let (healthy_in_cell, total_in_cell) = count_healthy(segments, start, end);
+    let cell_health = if total_in_cell > 0 {
+        healthy_in_cell as f64 / total_in_cell as f64
+    } else {
+        1.0
+    };
+    if cell_health > 0.9 {
+        ("█", theme().good)
+    } else if cell_health > 0.7 {
+        ("▓", theme().warn)
+    } else if cell_health > 0.3 {
+        ("▒", theme().bad)
+    } else {
+        ("░", theme().empty)
+    }
+}
+
+/// The most common kind among the cell's known records
+fn type_cell(
+    segments: &[&EntryStatuses],
+    records: &[&[RecordInfo]],
+    start: usize,
+    end: usize,
+) -> (&'static str, Color) {
+    let mut counts = [0usize; 3];
+    for record in records_in(segments, records, start, end) {
+        match record.kind {
+            EntryKind::File => counts[0] += 1,
+            EntryKind::Directory => counts[1] += 1,
+            EntryKind::Special => counts[2] += 1,
+            EntryKind::Unknown => {}
+        }
+    }
+    let most = counts.iter().copied().max().unwrap_or(0);
+    if most == 0 {
+        (" ", theme().empty)
+    } else if counts[0] == most {
+        ("█", kind_color(EntryKind::File))
+    } else if counts[1] == most {
+        ("█", kind_color(EntryKind::Directory))
+    } else {
+        ("█", kind_color(EntryKind::Special))
+    }
+}
+
+fn kind_color(kind: EntryKind) -> Color {
+    match kind {
+        EntryKind::File => theme().info,
+        EntryKind::Directory => theme().accent,
+        EntryKind::Special => theme().warn,
+        EntryKind::Unknown => theme().empty,
     }
 }
+
+/// Size buckets from smallest to largest, as (upper bound in bytes, label)
+const SIZE_BUCKETS: [(u64, &str); 4] = [
+    (1_000_000, "< 1 MB"),
+    (100_000_000, "< 100 MB"),
+    (1_000_000_000, "< 1 GB"),
+    (u64::MAX, "≥ 1 GB"),
+];
+
+fn size_bucket_style(bucket: usize) -> (&'static str, Color) {
+    match bucket {
+        0 => ("░", theme().good),
+        1 => ("▒", theme().info),
+        2 => ("▓", theme().warn),
+        _ => ("█", theme().bad),
+    }
+}
+
+fn size_cell(
+    segments: &[&EntryStatuses],
+    records: &[&[RecordInfo]],
+    start: usize,
+    end: usize,
+) -> (&'static str, Color) {
+    let Some(largest) = records_in(segments, records, start, end)
+        .filter(|record| record.kind != EntryKind::Directory)
+        .map(|record| record.size)
+        .max()
+    else {
+        return (" ", theme().empty);
+    };
+    let bucket = SIZE_BUCKETS
+        .iter()
+        .position(|(bound, _)| largest < *bound)
+        .unwrap_or(SIZE_BUCKETS.len() - 1);
+    size_bucket_style(bucket)
+}
+
+/// Age buckets from newest to oldest, as (upper bound in days, label)
+const AGE_BUCKETS: [(i64, &str); 4] = [
+    (30, "< 30 days"),
+    (365, "< 1 year"),
+    (1095, "< 3 years"),
+    (i64::MAX, "≥ 3 years"),
+];
+
+fn age_bucket_color(bucket: usize) -> Color {
+    match bucket {
+        0 => theme().good,
+        1 => theme().info,
+        2 => theme().warn,
+        _ => theme().bad,
+    }
+}
+
+fn age_cell(
+    segments: &[&EntryStatuses],
+    records: &[&[RecordInfo]],
+    start: usize,
+    end: usize,
+    now: DateTime<Utc>,
+) -> (&'static str, Color) {
+    let Some(newest) = records_in(segments, records, start, end)
+        .filter_map(|record| record.modified)
+        .max()
+    else {
+        return (" ", theme().empty);
+    };
+    let days = (now - newest).num_days();
+    let bucket = AGE_BUCKETS
+        .iter()
+        .position(|(bound, _)| days < *bound)
+        .unwrap_or(AGE_BUCKETS.len() - 1);
+    ("█", age_bucket_color(bucket))
+}
+
+fn render_health_stats(area: Rect, buf: &mut Buffer, segments: &[&EntryStatuses], total_count: usize) {
+    let healthy_count: usize = segments.iter().map(|segment| segment.healthy()).sum();
+    let health_ratio = healthy_count as f64 / total_count as f64;
+    let stats_text = format!(
+        "Healthy entries: {}/{} ({:.1}%)",
+        healthy_count,
+        total_count,
+        health_ratio * 100.0
+    );
+
+    Gauge::default()
+        .gauge_style(Style::default().fg(if health_ratio > 0.9 {
+            theme().good
+        } else if health_ratio > 0.7 {
+            theme().warn
+        } else {
+            theme().bad
+        }))
+        .ratio(health_ratio)
+        .label(stats_text)
+        .render(area, buf);
+}
+
+/// What each color means in the type, size and age modes
+fn render_legend(area: Rect, buf: &mut Buffer, color_by: ColorBy) {
+    let swatch = |symbol: &'static str, color: Color, label: &str| {
+        vec![
+            Span::styled(symbol, Style::default().fg(color)),
+            Span::raw(format!(" {label}   ")),
+        ]
+    };
+    let (spans, note): (Vec<Span>, &str) = match color_by {
+        ColorBy::Health => (Vec::new(), ""),
+        ColorBy::EntryType => (
+            [
+                (EntryKind::File, "file"),
+                (EntryKind::Directory, "directory"),
+                (EntryKind::Special, "system or reparse point"),
+            ]
+            .into_iter()
+            .flat_map(|(kind, label)| swatch("█", kind_color(kind), label))
+            .collect(),
+            "most common kind per cell",
+        ),
+        ColorBy::Size => (
+            SIZE_BUCKETS
+                .iter()
+                .enumerate()
+                .flat_map(|(bucket, (_, label))| {
+                    let (symbol, color) = size_bucket_style(bucket);
+                    swatch(symbol, color, label)
+                })
+                .collect(),
+            "largest file per cell",
+        ),
+        ColorBy::Age => (
+            AGE_BUCKETS
+                .iter()
+                .enumerate()
+                .flat_map(|(bucket, (_, label))| swatch("█", age_bucket_color(bucket), label))
+                .collect(),
+            "most recent modification per cell",
+        ),
+    };
+    Paragraph::new(vec![
+        Line::from(spans),
+        Line::from(Span::styled(
+            format!("{note}; blank cells have no discovered paths yet"),
+            Style::default().fg(theme().muted),
+        )),
+    ])
+    .block(Block::default().borders(Borders::TOP))
+    .render(area, buf);
+}
+
+fn render_inspection(inspection: &Inspection, area: Rect, buf: &mut Buffer) {
+    let [popup] = Layout::vertical([Constraint::Percentage(80)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [popup] = Layout::horizontal([Constraint::Percentage(80)])
+        .flex(Flex::Center)
+        .areas(popup);
+    Clear.render(popup, buf);
+    Paragraph::new(inspection.lines.clone())
+        .block(
+            Block::bordered()
+                .title(format!(
+                    "{} (↑↓ scroll, any key to close)",
+                    inspection.title
+                ))
+                .border_style(Style::default().fg(theme().accent)),
+        )
+        .scroll((inspection.scroll, 0))
+        .render(popup, buf);
+}
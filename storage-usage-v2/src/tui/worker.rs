@@ -1,25 +1,80 @@
+use crate::dir_index::index_allocation_path_for;
+use crate::mft_diff::dump_record_size;
+use crate::mft_index::IndexEntry;
+use crate::mft_index::record_entries;
+use crate::path_resolver::ParentRef;
+use crate::path_resolver::PathResolver;
+use crate::path_resolver::Resolved;
+use crate::progress::ProgressEvent;
+use crate::tui::mainbound_message::MainboundBatch;
 use crate::tui::mainbound_message::MainboundMessage;
+use crate::tui::progress::DiscoveredFile;
+use crate::tui::progress::ErrorSeverity;
+use crate::tui::progress::MftError;
 use mft::MftParser;
-use mft::attribute::MftAttributeContent;
-use ratatui::text::Line;
 use rayon::iter::IndexedParallelIterator;
 use rayon::iter::IntoParallelIterator;
 use rayon::iter::ParallelIterator;
-use std::collections::HashMap;
+use std::io::Cursor;
+use std::io::Read;
+use std::io::Seek;
+use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 use std::sync::mpsc::Receiver;
+use std::sync::mpsc::Sender;
 use std::thread::JoinHandle;
+use std::time::Duration;
 use uom::si::f64::Information;
 use uom::si::information::byte;
 
-// Promote DirectoryEntry so helper can see it
-#[derive(Clone)]
-struct DirectoryEntry { name: String, parent: Option<u64> }
+/// How many entries are parsed between checks for a pause
+const PAUSE_CHECK_INTERVAL: usize = 1024;
 
+/// Records one thread parses at a time when a dump is split across threads
+const RECORDS_PER_CHUNK: u64 = 16 * 1024;
+
+/// Whether a file's processing is held until it is resumed. The app keeps one per file and its
+/// worker shares it, so a file that is reset for reprocessing can start over unpaused.
+#[derive(Clone, Default)]
+pub struct PauseFlag(Arc<AtomicBool>);
+
+impl PauseFlag {
+    /// Pause the file's processing, or resume it if it was paused. Returns whether it is now
+    /// paused.
+    pub fn toggle(&self) -> bool {
+        !self.0.fetch_xor(true, Ordering::Relaxed)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn clear(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+
+    /// Block the calling worker while its file is paused
+    fn wait(&self) {
+        while self.is_paused() {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+}
+
+/// Process every MFT file on a background thread. The returned sender feeds the same receiver,
+/// for work started later like a re-sync.
 pub fn start_workers(
-    mft_files: Vec<PathBuf>,
-) -> eyre::Result<(Receiver<MainboundMessage>, JoinHandle<eyre::Result<()>>)> {
+    mft_files: Vec<(PathBuf, PauseFlag)>,
+) -> eyre::Result<(
+    Sender<MainboundMessage>,
+    Receiver<MainboundMessage>,
+    JoinHandle<eyre::Result<()>>,
+)> {
     let (tx, rx) = std::sync::mpsc::channel::<MainboundMessage>();
+    let app_tx = tx.clone();
     let handle = std::thread::spawn(move || {
         // disabled for now
         const DISABLE_WORKER: bool = false;
@@ -27,16 +82,21 @@ pub fn start_workers(
             mft_files
                 .into_par_iter()
                 .enumerate()
-                .try_for_each(|(index, mft_file)| process_mft_file(index, mft_file, tx.clone()))?;
+                .try_for_each(|(index, (mft_file, pause))| {
+                    let name = mft_file.display().to_string();
+                    process_mft_file(index, mft_file, &pause, tx.clone())
+                        .inspect_err(|e| tracing::warn!("Failed to process {name}: {e:#}"))
+                })?;
         }
         Ok(())
     });
-    Ok((rx, handle))
+    Ok((app_tx, rx, handle))
 }
 
 pub fn process_mft_file(
     index: usize,
     mft_file: PathBuf,
+    pause: &PauseFlag,
     tx: std::sync::mpsc::Sender<MainboundMessage>,
 ) -> eyre::Result<()> {
     // read from os
@@ -57,7 +117,37 @@ pub fn process_mft_file(
         .map(|c| c.to_ascii_uppercase())
         .unwrap_or('?');
 
-    // Memory map the file
+    // A fresh index already has every path; skip the full parse. It only counts the records
+    // that failed to parse, so the app is told which views have nothing to show
+    if let Some(mft_index) = crate::mft_index::load_fresh_index(&mft_file) {
+        if let Some(bytes) = dump_record_size(&mft_file) {
+            tx.send(MainboundMessage::Event {
+                file_index: index,
+                event: ProgressEvent::RecordSize { bytes },
+            })?;
+        }
+        tx.send(MainboundMessage::LoadedFromIndex {
+            file_index: index,
+            parse_errors: mft_index.parse_errors,
+        })?;
+        for chunk in mft_index.resolve_paths(drive_letter).chunks(10_000) {
+            pause.wait();
+            let files = chunk
+                .iter()
+                .map(|(path, entry)| discovered_file(PathBuf::from(path), entry))
+                .collect();
+            tx.send(MainboundMessage::DiscoveredFiles { file_index: index, files })?;
+        }
+        tx.send(MainboundMessage::Progress {
+            file_index: index,
+            processed_size: Information::new::<byte>(file_size_bytes as f64),
+        })?;
+        tx.send(MainboundMessage::Event { file_index: index, event: ProgressEvent::Finished })?;
+        return Ok(());
+    }
+
+    // Memory map the file and parse straight from the mapping, so the dump is never copied onto
+    // the heap; pages are read in as records are parsed and the OS can drop them again
     let file = std::fs::File::open(&mft_file)
         .map_err(|e| eyre::eyre!("Failed to open file {}: {}", mft_file.display(), e))?;
     let mmap = unsafe {
@@ -65,140 +155,149 @@ pub fn process_mft_file(
             .map(&file)
             .map_err(|e| eyre::eyre!("Failed to memory map file {}: {}", mft_file.display(), e))?
     };
-    let mft_bytes = mmap.as_ref().to_vec();
-    drop(mmap);
 
-    process_mft_bytes(index, mft_bytes, drive_letter, tx.clone())?;
+    process_mft_bytes(index, mmap, drive_letter, Some(&index_allocation_path_for(&mft_file)), pause, tx.clone())?;
 
-    tx.send(MainboundMessage::Complete { file_index: index })?;
+    tx.send(MainboundMessage::Event { file_index: index, event: ProgressEvent::Finished })?;
     Ok(())
 }
 
-pub fn process_mft_bytes(
+/// Parse an MFT held in memory, such as a `Vec<u8>` or a memory-mapped dump, without copying it.
+///
+/// Records are fixed-size, so the dump is split into ranges of [`RECORDS_PER_CHUNK`] parsed in
+/// parallel, each with its own parser over the same bytes. A first pass collects the names of
+/// directories, along with the index blocks dumped next to the MFT at `index_allocations`; the
+/// second parses every record a wave of chunks at a time and places its names in record order.
+pub fn process_mft_bytes<T: AsRef<[u8]>>(
     index: usize,
-    mft_bytes: Vec<u8>,
+    mft_bytes: T,
     drive_letter: char,
+    index_allocations: Option<&Path>,
+    pause: &PauseFlag,
     tx: std::sync::mpsc::Sender<MainboundMessage>,
 ) -> eyre::Result<()> {
-    let mut parser = MftParser::from_buffer(mft_bytes)
-        .map_err(|e| eyre::eyre!("Failed to parse MFT bytes: {}", e))?;
-    let entry_size = Information::new::<byte>(parser.entry_size as f64);
-
-    tx.send(MainboundMessage::EntrySizeDiscovered {
+    let bytes = mft_bytes.as_ref();
+    let size = bytes.len() as u64;
+    let open_parser = || {
+        MftParser::from_read_seek(Cursor::new(bytes), Some(size))
+            .map_err(|e| eyre::eyre!("Failed to parse MFT bytes: {}", e))
+    };
+    let parser = open_parser()?;
+    let total = parser.get_entry_count();
+    tx.send(MainboundMessage::Event {
         file_index: index,
-        entry_size,
+        event: ProgressEvent::RecordSize { bytes: parser.entry_size as u64 },
     })?;
 
-    #[derive(Clone)]
-    struct PendingEntry {
-        record_number: u64,
-        filename: String,
-        parent_ref: Option<u64>,
+    // First pass: only directories' names and every record's sequence number, each chunk into
+    // its own resolver, merged in record order
+    let chunk_starts: Vec<u64> = (0..total).step_by(RECORDS_PER_CHUNK as usize).collect();
+    let parts: Vec<PathResolver> = chunk_starts
+        .into_par_iter()
+        .map(|chunk_start| -> eyre::Result<PathResolver> {
+            let mut parser = open_parser()?;
+            let mut part = PathResolver::new(drive_letter);
+            for position in chunk_start..(chunk_start + RECORDS_PER_CHUNK).min(total) {
+                if let Ok(entry) = parser.get_entry(position) {
+                    part.add_entry(&entry);
+                }
+            }
+            Ok(part)
+        })
+        .collect::<eyre::Result<_>>()?;
+    let mut resolver = PathResolver::new(drive_letter);
+    for part in parts {
+        resolver.merge(part);
+    }
+    if let Some(path) = index_allocations && let Err(e) = resolver.add_index_allocations(path) {
+        tracing::debug!("Not using directory index blocks: {e:#}");
     }
 
-    let mut directories: HashMap<u64, DirectoryEntry> = HashMap::new();
-    let mut pending: HashMap<u64, Vec<PendingEntry>> = HashMap::new();
-    let mut resolve_queue: Vec<PendingEntry> = Vec::new();
-
-    for entry in parser.iter_entries() {
-        // progress & health first (assume healthy unless error below)
-        let mut healthy = true;
-        match &entry {
-            Ok(_) => tx.send(MainboundMessage::EntryStatus { file_index: index, is_healthy: true })?,
-            Err(_) => { healthy = false; tx.send(MainboundMessage::EntryStatus { file_index: index, is_healthy: false })?; }
-        }
+    // Second pass: every name, placed as soon as it's parsed and sent on in batches
+    let mut batch = MainboundBatch::new(&tx, index);
+    let wave = RECORDS_PER_CHUNK * rayon::current_num_threads().max(1) as u64;
+    for wave_start in (0..total).step_by(wave as usize) {
+        pause.wait();
+        let wave_end = (wave_start + wave).min(total);
+        let chunk_starts: Vec<u64> = (wave_start..wave_end).step_by(RECORDS_PER_CHUNK as usize).collect();
+        // Collecting an indexed parallel iterator keeps the chunks in record order
+        let chunks: Vec<Vec<ParsedRecord>> = chunk_starts
+            .into_par_iter()
+            .map(|chunk_start| -> eyre::Result<Vec<ParsedRecord>> {
+                let mut parser = open_parser()?;
+                let chunk_end = (chunk_start + RECORDS_PER_CHUNK).min(wave_end);
+                Ok((chunk_start..chunk_end).map(|position| parse_record(&mut parser, position)).collect())
+            })
+            .collect::<eyre::Result<_>>()?;
 
-        let (record_number, attributes) = match entry {
-            Ok(e) => (e.header.record_number, Some(e)),
-            Err(e) => {
-                tx.send(MainboundMessage::Error { file_index: index, error: Line::from(format!("Error processing entry: {e}")) })?;
-                tx.send(MainboundMessage::Progress { file_index: index, processed_size: entry_size })?;
-                continue;
+        for (position, record) in (wave_start..).zip(chunks.into_iter().flatten()) {
+            if position % PAUSE_CHECK_INTERVAL as u64 == 0 {
+                pause.wait();
             }
-        };
-
-        let mut discovered: Vec<PathBuf> = Vec::new();
-
-        // Walk attributes, only use first filename (X30)
-        if let Some(entry_ok) = attributes {
-            for attribute in entry_ok.iter_attributes() {
-                let Ok(attribute) = attribute else { continue; };
-                if let MftAttributeContent::AttrX30(filename_attr) = &attribute.data {
-                    let filename = &filename_attr.name;
-                    if filename.is_empty() || filename.starts_with('$') || filename == "." || filename == ".." { continue; }
-                    let parent_ref = if filename_attr.parent.entry == 0 { None } else { Some(filename_attr.parent.entry) };
-                    // Insert directory (enables traversal); overwrite is fine (latest wins) but we could keep first
-                    directories.insert(record_number, DirectoryEntry { name: filename.clone(), parent: parent_ref });
-                    // Try immediate full path
-                    match try_build_full_path(filename, parent_ref, &directories, drive_letter) {
-                        Ok(full_path) => {
-                            discovered.push(PathBuf::from(full_path));
-                            // New directory may unblock children
-                            if let Some(children) = pending.remove(&record_number) { resolve_queue.extend(children); }
-                        }
-                        Err(missing_parent) => {
-                            pending.entry(missing_parent).or_default().push(PendingEntry { record_number, filename: filename.clone(), parent_ref });
-                        }
-                    }
-                    // Resolve queue breadth-first
-                    while let Some(pend) = resolve_queue.pop() {
-                        match try_build_full_path(&pend.filename, pend.parent_ref, &directories, drive_letter) {
-                            Ok(path) => {
-                                discovered.push(PathBuf::from(path));
-                                if let Some(children) = pending.remove(&pend.record_number) { resolve_queue.extend(children); }
-                            }
-                            Err(missing_parent) => {
-                                pending.entry(missing_parent).or_default().push(pend);
-                            }
-                        }
-                    }
-                    break; // only first X30
+            let is_healthy = match record {
+                ParsedRecord::Failed(message) => {
+                    batch.problem(MftError { severity: ErrorSeverity::Error, record_number: Some(position), message })?;
+                    false
                 }
-            }
-        }
-
-        if !discovered.is_empty() {
-            tx.send(MainboundMessage::DiscoveredFiles { file_index: index, files: discovered })?;
-        }
-        // progress message
-        tx.send(MainboundMessage::Progress { file_index: index, processed_size: entry_size })?;
-        if !healthy { continue; }
-    }
-
-    // Flush unresolved pending entries with minimal fallback path
-    for (_missing, entries) in pending.into_iter() {
-        let mut batch: Vec<PathBuf> = Vec::new();
-        for pend in entries {
-            let partial = if drive_letter != '?' { format!("{drive_letter}:\\{}", pend.filename) } else { pend.filename };
-            batch.push(PathBuf::from(partial));
+                ParsedRecord::Parsed { record_number, names } => {
+                    let files = names.into_iter().map(|ParsedName { name, parent, file }| (resolver.resolve(record_number, &name, parent), file)).collect();
+                    add_resolved(&mut batch, files)?;
+                    true
+                }
+            };
+            batch.record_parsed(is_healthy, position + 1, total)?;
         }
-        if !batch.is_empty() { tx.send(MainboundMessage::DiscoveredFiles { file_index: index, files: batch })?; }
     }
+    batch.flush()?;
 
     Ok(())
 }
 
-fn try_build_full_path(
-    filename: &str,
-    parent_ref: Option<u64>,
-    directories: &HashMap<u64, DirectoryEntry>,
-    drive_letter: char,
-) -> Result<String, u64> {
-    let mut components = vec![filename.to_string()];
-    let mut current = parent_ref;
-    let mut guard = 0usize;
-    while let Some(pid) = current {
-        if guard > 4096 { break; }
-        if pid == 5 { break; } // root sentinel
-        if let Some(dir) = directories.get(&pid) {
-            if dir.name == "." { break; }
-            components.push(dir.name.clone());
-            current = dir.parent;
-        } else {
-            return Err(pid);
+/// What one record adds, parsed apart from the records before it
+enum ParsedRecord {
+    /// The entry didn't parse; holds the error
+    Failed(String),
+    Parsed { record_number: u64, names: Vec<ParsedName> },
+}
+
+/// One name of a record, for the resolver to place
+struct ParsedName {
+    name: String,
+    parent: Option<ParentRef>,
+    /// The path is filled in once the resolver places the name
+    file: DiscoveredFile,
+}
+
+/// Parse the record at `position` into the same entries an index holds. Entries are read by
+/// number rather than through `iter_entries` so the parser stays free to read extension records.
+fn parse_record<R: Read + Seek>(parser: &mut MftParser<R>, position: u64) -> ParsedRecord {
+    let entry = match parser.get_entry(position) {
+        Ok(entry) => entry,
+        Err(e) => return ParsedRecord::Failed(format!("Error processing entry: {e}")),
+    };
+    let record_number = entry.header.record_number;
+    // An extension record's names come with its base record's
+    let names = record_entries(parser, &entry)
+        .into_iter()
+        .map(|(entry, parent)| ParsedName { file: discovered_file(PathBuf::new(), &entry), name: entry.name, parent })
+        .collect();
+    ParsedRecord::Parsed { record_number, names }
+}
+
+/// A file as the TUI lists it, from its entry in an index
+fn discovered_file(path: PathBuf, entry: &IndexEntry) -> DiscoveredFile {
+    DiscoveredFile { path, record_number: entry.record_number, hard_link_count: entry.hard_link_count, flags: entry.flags, size: entry.size, allocated_size: entry.allocated_size, modified: entry.modified, reparse: entry.reparse.clone() }
+}
+
+/// Add placed names to the batch as discovered files, sending a warning for each one that had
+/// to be orphaned
+fn add_resolved(batch: &mut MainboundBatch, resolved: Vec<(Resolved, DiscoveredFile)>) -> eyre::Result<()> {
+    for (Resolved { path, orphan }, mut file) in resolved {
+        if let Some(orphan) = orphan {
+            batch.problem(MftError { severity: ErrorSeverity::Warning, record_number: Some(file.record_number), message: orphan.to_string() })?;
         }
-        guard += 1;
+        file.path = PathBuf::from(path);
+        batch.add_files([file]);
     }
-    components.reverse();
-    if drive_letter == '?' { Ok(format!("\\{}", components.join("\\"))) } else { Ok(format!("{drive_letter}:\\{}", components.join("\\"))) }
+    Ok(())
 }
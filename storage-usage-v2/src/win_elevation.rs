@@ -1,8 +1,14 @@
+use crate::cli::elevation_strategy::ElevationStrategy;
 use crate::to_args::Invocable;
 use crate::win_strings::EasyPCWSTR;
 use eyre::Context;
 use std::ffi::OsString;
 use std::mem::size_of;
+use std::process::Command;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
 use windows::Win32::Foundation::CloseHandle;
 use windows::Win32::Foundation::GetLastError;
 use windows::Win32::Foundation::HANDLE;
@@ -20,6 +26,19 @@ use windows::Win32::UI::Shell::SHELLEXECUTEINFOW;
 use windows::Win32::UI::Shell::ShellExecuteExW;
 use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
 
+/// Whether commands may relaunch elevated; `--no-elevate` turns this off
+static ELEVATION_ALLOWED: AtomicBool = AtomicBool::new(true);
+
+/// Apply the global `--no-elevate` flag
+pub fn configure_elevation(allowed: bool) {
+    ELEVATION_ALLOWED.store(allowed, Ordering::Relaxed);
+}
+
+/// Whether commands may relaunch elevated instead of falling back to what they can do unelevated
+pub fn elevation_allowed() -> bool {
+    ELEVATION_ALLOWED.load(Ordering::Relaxed)
+}
+
 /// Checks if the current process is running with elevated privileges.
 pub fn is_elevated() -> bool {
     unsafe {
@@ -107,7 +126,185 @@ pub fn run_as_admin(invocable: &impl Invocable) -> eyre::Result<AdminChild> {
     }
 }
 
+/// Longest command a scheduled task can run, including the quoted executable
+const MAX_TASK_COMMAND_LEN: usize = 261;
+
+/// `Last Result` of a task that hasn't started yet
+const TASK_NOT_YET_RUN: u32 = 0x41303;
+
+/// `Last Result` of a task that is still running
+const TASK_RUNNING: u32 = 0x41301;
+
+/// Tasks registered by this process, so each gets its own name
+static TASK_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// An elevated instance started by [`run_elevated`]
+pub enum ElevatedChild {
+    Uac(AdminChild),
+    Task(ScheduledTaskChild),
+}
+
+impl ElevatedChild {
+    /// Wait for the instance to exit and return its exit code
+    pub fn wait(self) -> eyre::Result<u32> {
+        match self {
+            ElevatedChild::Uac(child) => child.wait(),
+            ElevatedChild::Task(child) => child.wait(),
+        }
+    }
+}
+
+/// Runs an invocable with administrative privileges using the given strategy.
+pub fn run_elevated(
+    invocable: &impl Invocable,
+    strategy: ElevationStrategy,
+) -> eyre::Result<ElevatedChild> {
+    match strategy {
+        ElevationStrategy::Uac => run_as_admin(invocable).map(ElevatedChild::Uac),
+        ElevationStrategy::TaskScheduler => {
+            run_as_scheduled_task(invocable).map(ElevatedChild::Task)
+        }
+    }
+}
+
+/// A one-shot scheduled task running elevated; the task is deleted when this is dropped
+pub struct ScheduledTaskChild {
+    name: String,
+}
+
+impl ScheduledTaskChild {
+    /// Poll the task until it has run, returning its last result as the exit code
+    pub fn wait(self) -> eyre::Result<u32> {
+        loop {
+            match last_task_result(&self.name)? {
+                TASK_NOT_YET_RUN | TASK_RUNNING => std::thread::sleep(Duration::from_millis(250)),
+                code => return Ok(code),
+            }
+        }
+    }
+}
+
+impl Drop for ScheduledTaskChild {
+    fn drop(&mut self) {
+        let _ = schtasks(&["/Delete", "/TN", &self.name, "/F"]);
+    }
+}
+
+/// Runs an invocable elevated through a one-shot scheduled task with the highest run level.
+///
+/// Registering such a task doesn't prompt, so this works where policy denies UAC prompts, as
+/// long as the current user is an administrator.
+pub fn run_as_scheduled_task(invocable: &impl Invocable) -> eyre::Result<ScheduledTaskChild> {
+    let command = task_command(invocable)?;
+    let name = format!(
+        "storage-usage-{}-{}",
+        std::process::id(),
+        TASK_COUNT.fetch_add(1, Ordering::Relaxed)
+    );
+    // The start time is required but never reached; the task is started explicitly below
+    schtasks(&[
+        "/Create", "/TN", &name, "/TR", &command, "/SC", "ONCE", "/ST", "00:00", "/RL", "HIGHEST",
+        "/IT", "/F",
+    ])
+    .wrap_err("Failed to register the elevated scheduled task")?;
+    let child = ScheduledTaskChild { name };
+    schtasks(&["/Run", "/TN", &child.name])
+        .wrap_err("Failed to start the elevated scheduled task")?;
+    Ok(child)
+}
+
+/// The command line a scheduled task runs for an invocable, as given to `schtasks /TR`
+pub fn task_command(invocable: &impl Invocable) -> eyre::Result<String> {
+    let mut command = format!("\"{}\"", invocable.executable().display());
+    for arg in invocable.args() {
+        command.push(' ');
+        command.push_str(&quote_arg(&arg.to_string_lossy()));
+    }
+    if command.len() > MAX_TASK_COMMAND_LEN {
+        return Err(eyre::eyre!(
+            "Command is too long for a scheduled task ({} > {MAX_TASK_COMMAND_LEN} characters): {command}",
+            command.len()
+        ));
+    }
+    Ok(command)
+}
+
+/// An argument as the MSVC runtime splits it back out of a command line. One with whitespace or
+/// a quote is quoted, escaping its quotes and doubling the backslashes before them and before
+/// the closing quote.
+fn quote_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        return arg.to_string();
+    }
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        if c == '\\' {
+            backslashes += 1;
+            continue;
+        }
+        let escapes = if c == '"' {
+            backslashes * 2 + 1
+        } else {
+            backslashes
+        };
+        quoted.extend(std::iter::repeat_n('\\', escapes));
+        quoted.push(c);
+        backslashes = 0;
+    }
+    quoted.extend(std::iter::repeat_n('\\', backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+/// The task's `Last Result` column from `schtasks /Query`
+fn last_task_result(name: &str) -> eyre::Result<u32> {
+    let output = schtasks(&["/Query", "/TN", name, "/V", "/FO", "CSV", "/NH"])?;
+    // HostName, TaskName, Next Run Time, Status, Logon Mode, Last Run Time, Last Result, ...
+    let last_result = output
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .and_then(|line| line.split("\",\"").nth(6))
+        .ok_or_else(|| eyre::eyre!("Unexpected schtasks output: {output}"))?;
+    last_result
+        .trim_matches('"')
+        .parse::<i64>()
+        .map(|code| code as u32)
+        .map_err(|e| eyre::eyre!("Unexpected last result {last_result:?} for task {name}: {e}"))
+}
+
+/// Run schtasks.exe, returning its stdout
+pub fn schtasks(args: &[&str]) -> eyre::Result<String> {
+    let output = Command::new("schtasks.exe")
+        .args(args)
+        .output()
+        .wrap_err("Failed to run schtasks.exe")?;
+    if !output.status.success() {
+        return Err(eyre::eyre!(
+            "schtasks {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
 /// Relaunches the current executable with administrative privileges using a specific CLI configuration.
 pub fn relaunch_as_admin_with_cli(cli: &crate::cli::Cli) -> eyre::Result<AdminChild> {
     run_as_admin(cli)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::quote_arg;
+
+    #[test]
+    fn quotes_arguments_the_way_msvc_splits_them() {
+        assert_eq!(quote_arg("--drive"), "--drive");
+        assert_eq!(quote_arg(r"C:\dumps\"), r"C:\dumps\");
+        assert_eq!(quote_arg(""), r#""""#);
+        assert_eq!(quote_arg(r"C:\my dumps\"), r#""C:\my dumps\\""#);
+        assert_eq!(quote_arg(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(quote_arg(r#"a\"b c"#), r#""a\\\"b c""#);
+    }
+}
@@ -0,0 +1,68 @@
+use crate::tui::log_buffer::MessageVisitor;
+use crate::win_strings::EasyPCWSTR;
+use std::sync::OnceLock;
+use tracing::Event;
+use tracing::Level;
+use tracing::Subscriber;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::layer::Layer;
+use widestring::U16CString;
+use windows::Win32::Foundation::HANDLE;
+use windows::Win32::System::EventLog::EVENTLOG_ERROR_TYPE;
+use windows::Win32::System::EventLog::EVENTLOG_INFORMATION_TYPE;
+use windows::Win32::System::EventLog::EVENTLOG_WARNING_TYPE;
+use windows::Win32::System::EventLog::RegisterEventSourceW;
+use windows::Win32::System::EventLog::ReportEventW;
+use windows::core::PCWSTR;
+
+/// Event source events are reported under, in the Application log
+pub const EVENT_SOURCE: &str = "storage-usage-v2";
+
+/// The registered event source, once `service run` has started; held as an address since a
+/// `HANDLE` isn't `Send`
+static EVENT_SOURCE_HANDLE: OnceLock<usize> = OnceLock::new();
+
+/// Report events of INFO and above to the Windows Event Log from now on.
+///
+/// Only the service does this; anything run from a terminal has its output to look at.
+pub fn start_event_log() -> eyre::Result<()> {
+    let source = EVENT_SOURCE.easy_pcwstr()?;
+    let handle = unsafe { RegisterEventSourceW(None, source.as_ref())? };
+    let _ = EVENT_SOURCE_HANDLE.set(handle.0 as usize);
+    Ok(())
+}
+
+/// Tracing layer writing to the Windows Event Log once [`start_event_log`] has been called
+pub struct EventLogLayer;
+
+impl<S: Subscriber> Layer<S> for EventLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let Some(&handle) = EVENT_SOURCE_HANDLE.get() else {
+            return;
+        };
+        let event_type = match *event.metadata().level() {
+            Level::ERROR => EVENTLOG_ERROR_TYPE,
+            Level::WARN => EVENTLOG_WARNING_TYPE,
+            Level::INFO => EVENTLOG_INFORMATION_TYPE,
+            _ => return,
+        };
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let message = U16CString::from_str_truncate(&visitor.0);
+        let strings = [PCWSTR(message.as_ptr())];
+        // Without a message file registered for the source, Event Viewer shows the text under
+        // "the following information was included with the event"
+        unsafe {
+            let _ = ReportEventW(
+                HANDLE(handle as *mut _),
+                event_type,
+                0,
+                0,
+                None,
+                0,
+                Some(&strings),
+                None,
+            );
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use eyre::Context;
+use windows::Win32::System::ProcessStatus::GetProcessMemoryInfo;
+use windows::Win32::System::ProcessStatus::PROCESS_MEMORY_COUNTERS;
+use windows::Win32::System::Threading::GetCurrentProcess;
+
+/// Resident set size (working set) of this process in bytes
+pub fn current_process_rss() -> eyre::Result<u64> {
+    let mut counters = PROCESS_MEMORY_COUNTERS {
+        cb: size_of::<PROCESS_MEMORY_COUNTERS>() as u32,
+        ..Default::default()
+    };
+    unsafe {
+        GetProcessMemoryInfo(GetCurrentProcess(), &mut counters, counters.cb)
+            .wrap_err("Failed to query process memory usage")?;
+    }
+    Ok(counters.WorkingSetSize as u64)
+}
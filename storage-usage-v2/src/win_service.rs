@@ -0,0 +1,225 @@
+use crate::cli::drive_letter_pattern::DriveLetterPattern;
+use crate::cli::mft_sync_action::MftSyncArgs;
+use crate::config::Config;
+use crate::config::config_file_path;
+use crate::config::get_cache_dir;
+use crate::win_event_log::start_event_log;
+use crate::win_strings::EasyPCWSTR;
+use chrono::Local;
+use eyre::Context;
+use serde::Serialize;
+use std::ffi::OsString;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::mpsc;
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::Duration;
+use tracing::error;
+use tracing::info;
+use windows::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+use windows_service::define_windows_service;
+use windows_service::service::ServiceAccess;
+use windows_service::service::ServiceControl;
+use windows_service::service::ServiceControlAccept;
+use windows_service::service::ServiceErrorControl;
+use windows_service::service::ServiceExitCode;
+use windows_service::service::ServiceInfo;
+use windows_service::service::ServiceStartType;
+use windows_service::service::ServiceState;
+use windows_service::service::ServiceStatus;
+use windows_service::service::ServiceType;
+use windows_service::service_control_handler;
+use windows_service::service_control_handler::ServiceControlHandlerResult;
+use windows_service::service_dispatcher;
+use windows_service::service_manager::ServiceManager;
+use windows_service::service_manager::ServiceManagerAccess;
+
+/// Name the service is registered under
+pub const SERVICE_NAME: &str = "storage-usage-v2";
+
+/// File in the cache dir that free-space snapshots are appended to, one JSON object per line
+pub const FREE_SPACE_FILE: &str = "free-space.jsonl";
+
+/// Free space of one drive at one point in time
+#[derive(Serialize, Debug)]
+pub struct FreeSpaceSnapshot {
+    pub time: String,
+    pub drive: char,
+    pub total_bytes: u64,
+    pub free_bytes: u64,
+}
+
+/// Register the service to start with Windows and run `service run` with the current user's
+/// config file, so it syncs the same drives into the same cache dir.
+pub fn install_service(manual_start: bool) -> eyre::Result<()> {
+    let manager = ServiceManager::local_computer(
+        None::<&str>,
+        ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+    )
+    .wrap_err("Failed to connect to the service control manager")?;
+    let config_file = config_file_path()?;
+    let info = ServiceInfo {
+        name: SERVICE_NAME.into(),
+        display_name: "Storage Usage MFT Sync".into(),
+        service_type: ServiceType::OWN_PROCESS,
+        start_type: if manual_start {
+            ServiceStartType::OnDemand
+        } else {
+            ServiceStartType::AutoStart
+        },
+        error_control: ServiceErrorControl::Normal,
+        executable_path: std::env::current_exe()?,
+        launch_arguments: vec![
+            "--config-file".into(),
+            config_file.clone().into(),
+            "service".into(),
+            "run".into(),
+        ],
+        dependencies: vec![],
+        account_name: None,
+        account_password: None,
+    };
+    let service = manager
+        .create_service(&info, ServiceAccess::CHANGE_CONFIG)
+        .wrap_err_with(|| format!("Failed to create the {SERVICE_NAME} service"))?;
+    service.set_description(
+        "Periodically syncs MFT dumps into the cache dir and records free space per drive",
+    )?;
+    info!(
+        "Installed the {SERVICE_NAME} service using {}",
+        config_file.display()
+    );
+    Ok(())
+}
+
+/// Stop the service if it is running and remove it
+pub fn uninstall_service() -> eyre::Result<()> {
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)
+        .wrap_err("Failed to connect to the service control manager")?;
+    let service = manager
+        .open_service(
+            SERVICE_NAME,
+            ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE,
+        )
+        .wrap_err_with(|| format!("Failed to open the {SERVICE_NAME} service"))?;
+    if service.query_status()?.current_state != ServiceState::Stopped {
+        info!("Stopping the {SERVICE_NAME} service...");
+        service.stop()?;
+    }
+    service.delete()?;
+    info!("Uninstalled the {SERVICE_NAME} service");
+    Ok(())
+}
+
+define_windows_service!(ffi_service_main, service_main);
+
+/// Hand this process to the service control manager; only works when it started the process
+pub fn run_service_dispatcher() -> eyre::Result<()> {
+    start_event_log().wrap_err("Failed to register the event log source")?;
+    service_dispatcher::start(SERVICE_NAME, ffi_service_main).wrap_err(
+        "Failed to start the service dispatcher; `service run` is started by Windows after `service install`",
+    )?;
+    Ok(())
+}
+
+fn service_main(_arguments: Vec<OsString>) {
+    if let Err(e) = run_service() {
+        error!("The {SERVICE_NAME} service failed: {e:#}");
+    }
+}
+
+fn run_service() -> eyre::Result<()> {
+    let (stop_tx, stop_rx) = mpsc::channel();
+    let status_handle =
+        service_control_handler::register(SERVICE_NAME, move |control| match control {
+            ServiceControl::Stop | ServiceControl::Shutdown => {
+                let _ = stop_tx.send(());
+                ServiceControlHandlerResult::NoError
+            }
+            ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+            _ => ServiceControlHandlerResult::NotImplemented,
+        })?;
+    let status = |state, controls_accepted| ServiceStatus {
+        service_type: ServiceType::OWN_PROCESS,
+        current_state: state,
+        controls_accepted,
+        exit_code: ServiceExitCode::Win32(0),
+        checkpoint: 0,
+        wait_hint: Duration::default(),
+        process_id: None,
+    };
+    status_handle.set_service_status(status(
+        ServiceState::Running,
+        ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN,
+    ))?;
+    info!("The {SERVICE_NAME} service started");
+
+    loop {
+        // A failed cycle is retried after the next interval rather than stopping the service
+        if let Err(e) = run_cycle() {
+            error!("Sync failed: {e:#}");
+        }
+        // Read each time, so a changed interval applies without restarting the service
+        let interval = Config::load()
+            .and_then(|config| config.sync_interval())
+            .unwrap_or(Duration::from_secs(24 * 60 * 60));
+        info!("Next sync in {}", humantime::format_duration(interval));
+        match stop_rx.recv_timeout(interval) {
+            Err(RecvTimeoutError::Timeout) => continue,
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    info!("The {SERVICE_NAME} service stopped");
+    status_handle
+        .set_service_status(status(ServiceState::Stopped, ServiceControlAccept::empty()))?;
+    Ok(())
+}
+
+/// One `mft sync` of the default drives, then a free-space snapshot of each
+fn run_cycle() -> eyre::Result<()> {
+    info!("Syncing...");
+    MftSyncArgs {
+        drive_pattern: DriveLetterPattern::default(),
+        overwrite_existing: true,
+    }
+    .run()?;
+    let drives = Config::load()?
+        .drives_or(DriveLetterPattern::default())?
+        .resolve()?;
+    let path = get_cache_dir()?.join(FREE_SPACE_FILE);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("opening {}", path.display()))?;
+    for drive in drives {
+        let snapshot = free_space_snapshot(drive)?;
+        writeln!(file, "{}", serde_json::to_string(&snapshot)?)
+            .with_context(|| format!("writing {}", path.display()))?;
+    }
+    info!("Synced and recorded free space in {}", path.display());
+    Ok(())
+}
+
+/// Total and free bytes of a drive right now
+pub fn free_space_snapshot(drive: char) -> eyre::Result<FreeSpaceSnapshot> {
+    let root = format!("{drive}:\\").easy_pcwstr()?;
+    let mut total_bytes = 0u64;
+    let mut free_bytes = 0u64;
+    unsafe {
+        GetDiskFreeSpaceExW(
+            root.as_ref(),
+            None,
+            Some(&mut total_bytes),
+            Some(&mut free_bytes),
+        )
+    }
+    .wrap_err_with(|| format!("Failed to read the free space of drive {drive}"))?;
+    Ok(FreeSpaceSnapshot {
+        time: Local::now().to_rfc3339(),
+        drive,
+        total_bytes,
+        free_bytes,
+    })
+}
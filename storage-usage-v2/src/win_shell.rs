@@ -0,0 +1,362 @@
+use crate::win_strings::EasyPCWSTR;
+use eyre::Context;
+use std::cell::RefCell;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+use tracing::debug;
+use windows::Win32::Foundation::HANDLE;
+use windows::Win32::Foundation::S_OK;
+use windows::Win32::System::Com::CLSCTX_ALL;
+use windows::Win32::System::Com::COINIT_APARTMENTTHREADED;
+use windows::Win32::System::Com::COINIT_DISABLE_OLE1DDE;
+use windows::Win32::System::Com::CoCreateInstance;
+use windows::Win32::System::Com::CoInitializeEx;
+use windows::Win32::System::Com::CoUninitialize;
+use windows::Win32::System::DataExchange::CloseClipboard;
+use windows::Win32::System::DataExchange::EmptyClipboard;
+use windows::Win32::System::DataExchange::OpenClipboard;
+use windows::Win32::System::DataExchange::SetClipboardData;
+use windows::Win32::System::Memory::GMEM_MOVEABLE;
+use windows::Win32::System::Memory::GlobalAlloc;
+use windows::Win32::System::Memory::GlobalLock;
+use windows::Win32::System::Memory::GlobalUnlock;
+use windows::Win32::System::Ole::CF_UNICODETEXT;
+use windows::Win32::UI::Shell::FILEOPERATION_FLAGS;
+use windows::Win32::UI::Shell::FOF_ALLOWUNDO;
+use windows::Win32::UI::Shell::FOF_NOCONFIRMATION;
+use windows::Win32::UI::Shell::FOF_NOERRORUI;
+use windows::Win32::UI::Shell::FOF_SILENT;
+use windows::Win32::UI::Shell::FOF_WANTNUKEWARNING;
+use windows::Win32::UI::Shell::FOFX_RECYCLEONDELETE;
+use windows::Win32::UI::Shell::FileOperation;
+use windows::Win32::UI::Shell::IFileOperation;
+use windows::Win32::UI::Shell::IFileOperationProgressSink;
+use windows::Win32::UI::Shell::IFileOperationProgressSink_Impl;
+use windows::Win32::UI::Shell::IShellItem;
+use windows::Win32::UI::Shell::SHCreateItemFromParsingName;
+use windows::Win32::UI::Shell::SHELLEXECUTEINFOW;
+use windows::Win32::UI::Shell::ShellExecuteExW;
+use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+use windows::core::HRESULT;
+use windows::core::PCWSTR;
+use windows::core::Ref;
+use windows::core::implement;
+
+/// Open an Explorer window with the given file selected
+pub fn reveal_in_explorer(path: &Path) -> eyre::Result<()> {
+    let file = "explorer.exe".easy_pcwstr()?;
+    let params = format!("/select,\"{}\"", path.display()).easy_pcwstr()?;
+    unsafe {
+        let mut sei = SHELLEXECUTEINFOW {
+            cbSize: std::mem::size_of::<SHELLEXECUTEINFOW>() as u32,
+            lpFile: file.as_ptr(),
+            lpParameters: params.as_ptr(),
+            nShow: SW_SHOWNORMAL.0,
+            ..Default::default()
+        };
+        ShellExecuteExW(&mut sei).wrap_err("Failed to launch Explorer")?;
+    }
+    Ok(())
+}
+
+/// Replace the clipboard contents with the given text
+pub fn copy_to_clipboard(text: &str) -> eyre::Result<()> {
+    let wide: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+    unsafe {
+        OpenClipboard(None).wrap_err("Failed to open the clipboard")?;
+        let result = (|| -> eyre::Result<()> {
+            EmptyClipboard()?;
+            // Ownership of the allocation passes to the clipboard once SetClipboardData succeeds
+            let memory = GlobalAlloc(GMEM_MOVEABLE, wide.len() * size_of::<u16>())?;
+            let target = GlobalLock(memory) as *mut u16;
+            if target.is_null() {
+                return Err(eyre::eyre!("Failed to lock clipboard memory"));
+            }
+            std::ptr::copy_nonoverlapping(wide.as_ptr(), target, wide.len());
+            let _ = GlobalUnlock(memory);
+            SetClipboardData(CF_UNICODETEXT.0 as u32, Some(HANDLE(memory.0)))?;
+            Ok(())
+        })();
+        let _ = CloseClipboard();
+        result.wrap_err("Failed to set clipboard text")
+    }
+}
+
+/// What became of one path handed to [`move_to_recycle_bin`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RecycleOutcome {
+    Recycled,
+    /// Too big for the Recycle Bin or on a volume without one, and the user agreed to the warning
+    DeletedPermanently,
+    /// Left in place: declined at the warning, in use, denied, or never reached
+    Failed,
+}
+
+/// Recycle, with no dialogs except the shell's warning before an item would be deleted for good
+/// instead; `FOF_WANTNUKEWARNING` overrides `FOF_NOCONFIRMATION` for exactly that case
+const RECYCLE_FLAGS: FILEOPERATION_FLAGS = FILEOPERATION_FLAGS(
+    FOFX_RECYCLEONDELETE.0
+        | FOF_ALLOWUNDO.0
+        | FOF_NOCONFIRMATION.0
+        | FOF_SILENT.0
+        | FOF_NOERRORUI.0
+        | FOF_WANTNUKEWARNING.0,
+);
+
+/// Send files to the Recycle Bin, returning what happened to each path in order; callers
+/// confirm with the user first. Success is judged from the shell's report for each item, as a
+/// file the shell deleted permanently is just as gone as a recycled one.
+pub fn move_to_recycle_bin(paths: &[PathBuf]) -> eyre::Result<Vec<RecycleOutcome>> {
+    if paths.is_empty() {
+        return Ok(Vec::new());
+    }
+    // IFileOperation needs a single-threaded apartment
+    unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED | COINIT_DISABLE_OLE1DDE) }
+        .ok()
+        .wrap_err("Failed to initialise COM")?;
+    let result = recycle_items(paths);
+    unsafe { CoUninitialize() };
+    result
+}
+
+/// Queue and run the deletes; COM must be initialised on this thread
+fn recycle_items(paths: &[PathBuf]) -> eyre::Result<Vec<RecycleOutcome>> {
+    let operation: IFileOperation = unsafe { CoCreateInstance(&FileOperation, None, CLSCTX_ALL) }
+        .wrap_err("Failed to create a file operation")?;
+    unsafe { operation.SetOperationFlags(RECYCLE_FLAGS) }?;
+    let outcomes: Rc<RefCell<Vec<Option<RecycleOutcome>>>> =
+        Rc::new(RefCell::new(vec![None; paths.len()]));
+    for (index, path) in paths.iter().enumerate() {
+        let name = path.clone().easy_pcwstr()?;
+        let item: IShellItem = match unsafe { SHCreateItemFromParsingName(&name, None) } {
+            Ok(item) => item,
+            Err(e) => {
+                debug!("Couldn't open {} for deletion: {e}", path.display());
+                continue;
+            }
+        };
+        let sink: IFileOperationProgressSink = DeleteSink {
+            index,
+            outcomes: outcomes.clone(),
+        }
+        .into();
+        unsafe { operation.DeleteItem(&item, &sink) }
+            .wrap_err_with(|| format!("Failed to queue {} for deletion", path.display()))?;
+    }
+    // A declined warning or a cancel fails the whole operation, yet items before it were handled
+    if let Err(e) = unsafe { operation.PerformOperations() } {
+        debug!("Moving to the Recycle Bin stopped early: {e}");
+    }
+    let outcomes = outcomes
+        .borrow()
+        .iter()
+        .map(|outcome| outcome.unwrap_or(RecycleOutcome::Failed))
+        .collect();
+    Ok(outcomes)
+}
+
+/// What the shell did with one deleted item: anything but `S_OK` means it was skipped, and a
+/// recycled item comes back as the new item in the Recycle Bin
+fn delete_outcome(result: HRESULT, recycled: bool) -> RecycleOutcome {
+    if result != S_OK {
+        RecycleOutcome::Failed
+    } else if recycled {
+        RecycleOutcome::Recycled
+    } else {
+        RecycleOutcome::DeletedPermanently
+    }
+}
+
+/// Collects the outcome of one queued path. A directory deleted permanently reports each item
+/// below it as well, so the worst outcome seen stands for the path.
+#[implement(IFileOperationProgressSink)]
+struct DeleteSink {
+    index: usize,
+    outcomes: Rc<RefCell<Vec<Option<RecycleOutcome>>>>,
+}
+
+impl IFileOperationProgressSink_Impl for DeleteSink_Impl {
+    fn PostDeleteItem(
+        &self,
+        _flags: u32,
+        _item: Ref<'_, IShellItem>,
+        result: HRESULT,
+        newly_created: Ref<'_, IShellItem>,
+    ) -> windows::core::Result<()> {
+        let outcome = delete_outcome(result, !newly_created.is_null());
+        let mut outcomes = self.outcomes.borrow_mut();
+        let slot = &mut outcomes[self.index];
+        *slot = Some(match (*slot, outcome) {
+            (Some(RecycleOutcome::Failed), _) | (_, RecycleOutcome::Failed) => {
+                RecycleOutcome::Failed
+            }
+            (Some(RecycleOutcome::DeletedPermanently), _)
+            | (_, RecycleOutcome::DeletedPermanently) => RecycleOutcome::DeletedPermanently,
+            _ => RecycleOutcome::Recycled,
+        });
+        Ok(())
+    }
+
+    fn StartOperations(&self) -> windows::core::Result<()> {
+        Ok(())
+    }
+
+    fn FinishOperations(&self, _result: HRESULT) -> windows::core::Result<()> {
+        Ok(())
+    }
+
+    fn PreRenameItem(
+        &self,
+        _flags: u32,
+        _item: Ref<'_, IShellItem>,
+        _new_name: &PCWSTR,
+    ) -> windows::core::Result<()> {
+        Ok(())
+    }
+
+    fn PostRenameItem(
+        &self,
+        _flags: u32,
+        _item: Ref<'_, IShellItem>,
+        _new_name: &PCWSTR,
+        _result: HRESULT,
+        _newly_created: Ref<'_, IShellItem>,
+    ) -> windows::core::Result<()> {
+        Ok(())
+    }
+
+    fn PreMoveItem(
+        &self,
+        _flags: u32,
+        _item: Ref<'_, IShellItem>,
+        _destination: Ref<'_, IShellItem>,
+        _new_name: &PCWSTR,
+    ) -> windows::core::Result<()> {
+        Ok(())
+    }
+
+    fn PostMoveItem(
+        &self,
+        _flags: u32,
+        _item: Ref<'_, IShellItem>,
+        _destination: Ref<'_, IShellItem>,
+        _new_name: &PCWSTR,
+        _result: HRESULT,
+        _newly_created: Ref<'_, IShellItem>,
+    ) -> windows::core::Result<()> {
+        Ok(())
+    }
+
+    fn PreCopyItem(
+        &self,
+        _flags: u32,
+        _item: Ref<'_, IShellItem>,
+        _destination: Ref<'_, IShellItem>,
+        _new_name: &PCWSTR,
+    ) -> windows::core::Result<()> {
+        Ok(())
+    }
+
+    fn PostCopyItem(
+        &self,
+        _flags: u32,
+        _item: Ref<'_, IShellItem>,
+        _destination: Ref<'_, IShellItem>,
+        _new_name: &PCWSTR,
+        _result: HRESULT,
+        _newly_created: Ref<'_, IShellItem>,
+    ) -> windows::core::Result<()> {
+        Ok(())
+    }
+
+    fn PreDeleteItem(&self, _flags: u32, _item: Ref<'_, IShellItem>) -> windows::core::Result<()> {
+        Ok(())
+    }
+
+    fn PreNewItem(
+        &self,
+        _flags: u32,
+        _destination: Ref<'_, IShellItem>,
+        _new_name: &PCWSTR,
+    ) -> windows::core::Result<()> {
+        Ok(())
+    }
+
+    fn PostNewItem(
+        &self,
+        _flags: u32,
+        _destination: Ref<'_, IShellItem>,
+        _new_name: &PCWSTR,
+        _template_name: &PCWSTR,
+        _attributes: u32,
+        _result: HRESULT,
+        _new_item: Ref<'_, IShellItem>,
+    ) -> windows::core::Result<()> {
+        Ok(())
+    }
+
+    fn UpdateProgress(&self, _total: u32, _so_far: u32) -> windows::core::Result<()> {
+        Ok(())
+    }
+
+    fn ResetTimer(&self) -> windows::core::Result<()> {
+        Ok(())
+    }
+
+    fn PauseTimer(&self) -> windows::core::Result<()> {
+        Ok(())
+    }
+
+    fn ResumeTimer(&self) -> windows::core::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RECYCLE_FLAGS;
+    use super::RecycleOutcome;
+    use super::delete_outcome;
+    use windows::Win32::Foundation::E_ACCESSDENIED;
+    use windows::Win32::Foundation::S_OK;
+    use windows::Win32::UI::Shell::COPYENGINE_S_USER_IGNORED;
+    use windows::Win32::UI::Shell::FOF_ALLOWUNDO;
+    use windows::Win32::UI::Shell::FOF_NOCONFIRMATION;
+    use windows::Win32::UI::Shell::FOF_WANTNUKEWARNING;
+    use windows::Win32::UI::Shell::FOFX_RECYCLEONDELETE;
+
+    #[test]
+    fn recycles_and_warns_before_deleting_for_good() {
+        for flag in [
+            FOFX_RECYCLEONDELETE,
+            FOF_ALLOWUNDO,
+            FOF_NOCONFIRMATION,
+            FOF_WANTNUKEWARNING,
+        ] {
+            assert_eq!(
+                RECYCLE_FLAGS.0 & flag.0,
+                flag.0,
+                "missing flag 0x{:X}",
+                flag.0
+            );
+        }
+    }
+
+    #[test]
+    fn classifies_deleted_items() {
+        assert_eq!(delete_outcome(S_OK, true), RecycleOutcome::Recycled);
+        assert_eq!(
+            delete_outcome(S_OK, false),
+            RecycleOutcome::DeletedPermanently
+        );
+        assert_eq!(
+            delete_outcome(COPYENGINE_S_USER_IGNORED, false),
+            RecycleOutcome::Failed
+        );
+        assert_eq!(
+            delete_outcome(E_ACCESSDENIED, false),
+            RecycleOutcome::Failed
+        );
+    }
+}